@@ -0,0 +1,5 @@
+//! Structure generator from XSD source files.
+//!
+//! The command line interface lives in the `xsd-tool` crate.
+
+pub use xml_schema_parser::*;
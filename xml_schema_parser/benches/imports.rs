@@ -0,0 +1,98 @@
+//! Measures loading and generating a schema importing many documents, and parsing a single
+//! large document.
+//!
+//! Run with `cargo bench -p xml-schema-parser --bench imports`, and with `--features parallel`
+//! to load the imports and generate the components of each pass on the rayon thread pool.
+
+use std::{fmt::Write, fs, path::Path, time::Instant};
+
+use xml_schema_parser::{GeneratorOptions, Xsd};
+
+const IMPORTS: usize = 12;
+const TYPES: usize = 200;
+
+/// A schema of `types` complex types, each one extending the previous one.
+fn imported_schema(index: usize, types: usize) -> String {
+  let mut schema = format!(
+    "<xs:schema xmlns:xs=\"http://www.w3.org/2001/XMLSchema\" targetNamespace=\"urn:m{index}\" xmlns:m=\"urn:m{index}\">\n"
+  );
+  for ty in 0..types {
+    let content = match ty {
+      0 => "<xs:sequence><xs:element name=\"id\" type=\"xs:string\"/></xs:sequence>".to_string(),
+      _ => format!(
+        "<xs:complexContent><xs:extension base=\"m:t{}\"><xs:sequence><xs:element name=\"f{ty}\" type=\"xs:int\"/></xs:sequence></xs:extension></xs:complexContent>",
+        ty - 1
+      ),
+    };
+    writeln!(
+      schema,
+      "  <xs:complexType name=\"t{ty}\">{content}</xs:complexType>"
+    )
+    .unwrap();
+  }
+  schema.push_str("</xs:schema>\n");
+  schema
+}
+
+fn write_fixture(dir: &Path) -> String {
+  let mut root = String::from("<xs:schema xmlns:xs=\"http://www.w3.org/2001/XMLSchema\"");
+  for index in 0..IMPORTS {
+    write!(root, " xmlns:m{index}=\"urn:m{index}\"").unwrap();
+  }
+  root.push_str(">\n");
+
+  for index in 0..IMPORTS {
+    let path = dir.join(format!("m{index}.xsd"));
+    fs::write(&path, imported_schema(index, TYPES)).unwrap();
+    writeln!(
+      root,
+      "  <xs:import namespace=\"urn:m{index}\" schemaLocation=\"{}\"/>",
+      path.display()
+    )
+    .unwrap();
+  }
+  for index in 0..IMPORTS {
+    writeln!(
+      root,
+      "  <xs:element name=\"e{index}\" type=\"m{index}:t{}\"/>",
+      TYPES - 1
+    )
+    .unwrap();
+  }
+  root.push_str("</xs:schema>\n");
+
+  let path = dir.join("root.xsd");
+  fs::write(&path, root).unwrap();
+  path.display().to_string()
+}
+
+fn main() {
+  let dir = std::env::temp_dir().join(format!("xsd-imports-bench-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  let root = write_fixture(&dir);
+
+  for run in 0..3 {
+    let start = Instant::now();
+    let xsd = Xsd::new_from_file(&root).unwrap();
+    let code = xsd.generate_with(&GeneratorOptions::default()).unwrap();
+    println!(
+      "run {run}: {IMPORTS} imports of {TYPES} types, {} bytes generated in {:?}",
+      code.len(),
+      start.elapsed()
+    );
+  }
+
+  fs::remove_dir_all(&dir).unwrap();
+
+  let large = imported_schema(0, IMPORTS * TYPES);
+  for run in 0..3 {
+    let start = Instant::now();
+    Xsd::new(&large).unwrap();
+    println!(
+      "run {run}: parsed a document of {} types, {} bytes in {:?}",
+      IMPORTS * TYPES,
+      large.len(),
+      start.elapsed()
+    );
+  }
+}
@@ -0,0 +1,56 @@
+//! Report the progress of the parsing of a large document on a progress bar.
+//!
+//! The types generated with `--progress-hooks` get a `gen_with_progress` constructor doing the
+//! same for a whole document.
+
+use std::{
+  io::Write,
+  sync::{Arc, Mutex},
+};
+
+use xml_schema_parser::{
+  GenState, GenType, ProgressEvent, ProgressHook, XMLElement, XsdGen, PROGRESS_INTERVAL,
+};
+
+const NOTES: usize = 50_000;
+const WIDTH: usize = 40;
+
+fn draw(event: &ProgressEvent) {
+  let filled = WIDTH * event.count / NOTES;
+  eprint!(
+    "\r{} [{}{}] {}/{NOTES}",
+    event.path,
+    "#".repeat(filled),
+    " ".repeat(WIDTH - filled),
+    event.count
+  );
+  std::io::stderr().flush().unwrap();
+}
+
+fn main() {
+  let document = format!("<notes>{}</notes>", "<note>c4</note>".repeat(NOTES));
+  let mut element = XMLElement::parse(document.as_bytes()).unwrap();
+
+  let events = Arc::new(Mutex::new(0));
+  let progress: ProgressHook = {
+    let events = events.clone();
+    Arc::new(move |event| {
+      *events.lock().unwrap() += 1;
+      draw(&event);
+    })
+  };
+
+  let state = GenState {
+    is_root: true,
+    state: GenType::Content,
+    progress: Some(progress),
+  };
+  let notes = <Vec<String> as XsdGen>::gen(&mut element, state, Some("note")).unwrap();
+  eprintln!();
+
+  println!(
+    "Parsed {} notes with {} progress events, one every {PROGRESS_INTERVAL} notes.",
+    notes.len(),
+    events.lock().unwrap()
+  );
+}
@@ -1,5 +1,37 @@
 mod xsd;
 
-pub use xsd::{Xsd, XsdError};
-pub use xsd_codegen::{Date, FromXmlString, GenState, GenType, RestrictedVec, XMLElement, XsdGen};
-pub use xsd_types::{XsdGenError, XsdIoError, XsdName, XsdType};
+pub use xsd::{
+  CancellationToken, DriftCause, DriftReport, DriftedItem, FieldOverride, FixedValues,
+  GenerationPlan, GenerationReport, GeneratorOptions, Invalid, ItemChange, ItemKind, LoadOptions,
+  LossyMapping, Naming, PlannedItem, SchemaSource, StringStorage, StubKind, TypeStats, VersionUse,
+  Visibility, Warning, Workspace, Xsd, XsdError, XsdErrorKind, XsdVersion,
+};
+pub use xsd_codegen::{
+  decimal_digits, parse_document, parse_document_ref, parse_document_with, write_document,
+  xml_element, AnyXml, AttributeName, Base64Binary, ContentKind, Date, DateTime, Decimal,
+  DuplicateAttributes, Duration, DynamicFields, DynamicValue, FieldKind, FieldMeta, FixedValue,
+  FromDynamic, FromXmlString, GDay, GMonth, GMonthDay, GYear, GYearMonth, GenState, GenType,
+  HexBinary, KeyIndex, MergeConflict, ParseOptions, Pattern, ProgressEvent, ProgressHook, QName,
+  RawXml, RestrictedVec, Time, ToDynamic, ToXmlString, UnexpectedElements, UnknownContent,
+  VariantMeta, XMLElement, XMLElementRef, XmlDocument, XmlSpace, XmlTypeIndex, XsdGen, XsdGenRef,
+  XsdSer, PROGRESS_INTERVAL,
+};
+#[cfg(feature = "tokio")]
+pub use xsd_codegen::{parse_async, AsyncRead};
+#[allow(deprecated)]
+pub use xsd_types::XsdIoError;
+pub use xsd_types::{
+  DefaultNaming, DocumentError, NamingConvention, SchemaError, XsdGenError, XsdName, XsdType,
+};
+
+/// The supported API: the generator and the types used by the generated code. Everything else
+/// exported by the crate may change between releases.
+pub mod prelude {
+  pub use crate::{
+    FixedValues, GenerationPlan, GenerationReport, GeneratorOptions, Invalid, LoadOptions,
+    SchemaSource, StringStorage, Visibility, Warning, Workspace, Xsd, XsdError, XsdErrorKind,
+    XsdVersion,
+  };
+  pub use xsd_codegen::prelude::*;
+  pub use xsd_types::XsdName;
+}
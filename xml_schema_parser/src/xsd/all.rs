@@ -0,0 +1,103 @@
+use xsd_codegen::XMLElement;
+use xsd_types::{SchemaError, XsdName, XsdParseError, XsdType};
+
+use super::{
+  annotation::Annotation,
+  max_occurences::{self, MaxOccurences},
+  sequence::{Sequence, SequenceOptions},
+  xsd_context::XsdImpl,
+  XsdError,
+};
+use crate::xsd::{element::Element, XsdContext};
+
+/// An `all` compositor: its elements occur at most once each, in any order. It is only found in
+/// a named group, which can only be referenced as the whole content of a complex type, see
+/// `Group::get_implementation`.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct All {
+  pub id: Option<String>,
+  pub min_occurences: u64,
+  pub max_occurences: MaxOccurences,
+  pub annotation: Option<Annotation>,
+  pub children: Vec<Element>,
+}
+
+impl All {
+  pub fn parse(mut element: XMLElement) -> Result<Self, SchemaError> {
+    element.check_name("all")?;
+
+    let annotation = element.try_get_child_with("annotation", Annotation::parse)?;
+
+    let mut children = vec![];
+    for child in element.get_all_children() {
+      children.push(match child.name() {
+        "element" => Element::parse(child, false)?,
+        name => {
+          return Err(SchemaError::Invalid(XsdParseError {
+            node_name: element.node_name(),
+            msg: format!("Unexpected child {name}"),
+          }))
+        }
+      });
+    }
+
+    let (min_occurences, max_occurences) = max_occurences::parse_occurences(&mut element, None)?;
+    if max_occurences != MaxOccurences::default() {
+      return Err(SchemaError::Invalid(XsdParseError {
+        node_name: element.node_name(),
+        msg: "maxOccurs of all must be 1".to_string(),
+      }));
+    }
+
+    // Each element occurs at most once, the fields are not repeated.
+    if let Some(child) = children
+      .iter()
+      .find(|child| !matches!(child.max_occurences, MaxOccurences::Number { value } if value <= 1))
+    {
+      let name = child.name.as_ref().or(child.refers.as_ref());
+      return Err(SchemaError::Invalid(XsdParseError {
+        node_name: element.node_name(),
+        msg: format!(
+          "maxOccurs of the elements of all must be 0 or 1 on `{}`",
+          name.map_or("", |name| name.local_name.as_str())
+        ),
+      }));
+    }
+
+    let output = Self {
+      id: element.try_get_attribute("id")?,
+      min_occurences,
+      max_occurences,
+      annotation,
+      children,
+    };
+
+    element.finalize(false, false)?;
+
+    Ok(output)
+  }
+
+  /// The elements are read like the ones of a sequence, once the struct named as an `all` ordered
+  /// the children of the element, see `XMLElement::order_children`.
+  #[tracing::instrument(skip_all)]
+  pub fn get_implementation(
+    &self,
+    parent_name: Option<XsdName>,
+    context: &XsdContext,
+  ) -> Result<XsdImpl, XsdError> {
+    let sequence = Sequence {
+      id: self.id.clone(),
+      min_occurences: self.min_occurences,
+      max_occurences: self.max_occurences.clone(),
+      annotation: self.annotation.clone(),
+      children: self
+        .children
+        .iter()
+        .cloned()
+        .map(SequenceOptions::Element)
+        .collect(),
+    };
+
+    sequence.implementation(parent_name, context, XsdType::All)
+  }
+}
@@ -1,5 +1,5 @@
 use xsd_codegen::XMLElement;
-use xsd_types::XsdIoError;
+use xsd_types::SchemaError;
 
 #[derive(Clone, Default, Debug, PartialEq)]
 pub struct Annotation {
@@ -8,7 +8,7 @@ pub struct Annotation {
 }
 
 impl Annotation {
-  pub fn parse(mut element: XMLElement) -> Result<Self, XsdIoError> {
+  pub fn parse(mut element: XMLElement) -> Result<Self, SchemaError> {
     element.check_name("annotation")?;
 
     let mut output = Ok(Self {
@@ -19,7 +19,10 @@ impl Annotation {
 
     if let Ok(output) = &mut output {
       for doc in &mut output.documentation {
-        *doc = doc.replace('\t', "  ");
+        *doc = doc
+          .replace('\t', "  ")
+          .replace("\r\n", "\n")
+          .replace('\r', "\n");
       }
     }
 
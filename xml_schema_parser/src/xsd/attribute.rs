@@ -1,11 +1,16 @@
-use xsd_codegen::{FromXmlString, Type, TypeAlias, XMLElement};
-use xsd_types::{XsdIoError, XsdName, XsdParseError, XsdType};
+use xsd_codegen::{
+  escape_str_literal, fromxml_impl, Block, ContentKind, FromXmlString, Function, Impl, Struct,
+  Type, TypeAlias, ValueConstraint, XMLElement,
+};
+use xsd_types::{to_module_name, SchemaError, XsdGenError, XsdName, XsdParseError, XsdType};
 
 use super::{
   annotation::Annotation,
+  fixed_value::marker_impl,
   general_xsdgen,
+  max_occurences::VALUE,
   xsd_context::{XsdImpl, XsdImplType},
-  XsdError,
+  FixedValues, XsdError,
 };
 use crate::xsd::{simple_type::SimpleType, XsdContext};
 
@@ -21,8 +26,9 @@ pub struct Attribute {
   pub simple_type: Option<SimpleType>,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub enum Required {
+  #[default]
   Optional,
   Required,
 }
@@ -40,14 +46,8 @@ impl FromXmlString for Required {
   }
 }
 
-impl Default for Required {
-  fn default() -> Self {
-    Required::Optional
-  }
-}
-
 impl Attribute {
-  pub fn parse(mut element: XMLElement) -> Result<Self, XsdIoError> {
+  pub fn parse(mut element: XMLElement) -> Result<Self, SchemaError> {
     element.check_name("attribute")?;
 
     let name = element
@@ -55,10 +55,10 @@ impl Attribute {
       .map(|v: String| element.new_name(&v, XsdType::Attribute));
     let reference = element
       .try_get_attribute("ref")?
-      .map(|v: String| element.new_name(&v, XsdType::Attribute));
+      .map(|v: String| element.new_qname(&v, XsdType::Attribute));
 
     if name.is_some() && reference.is_some() {
-      return Err(XsdIoError::XsdParseError(XsdParseError {
+      return Err(SchemaError::Invalid(XsdParseError {
         node_name: element.node_name(),
         msg: "name and ref cannot both present".to_string(),
       }));
@@ -66,7 +66,7 @@ impl Attribute {
 
     let r#type = element
       .try_get_attribute("type")?
-      .map(|v: String| XsdName::new(&v, XsdType::SimpleType));
+      .map(|v: String| element.new_qname(&v, XsdType::SimpleType));
 
     let simple_type =
       element.try_get_child_with("simpleType", |child| SimpleType::parse(child, false))?;
@@ -74,14 +74,14 @@ impl Attribute {
     let required = element.get_attribute_default("use")?;
 
     if reference.is_some() && (simple_type.is_some() || r#type.is_some()) {
-      return Err(XsdIoError::XsdParseError(XsdParseError {
+      return Err(SchemaError::Invalid(XsdParseError {
         node_name: element.node_name(),
         msg: "type | simpleType cannot be present when ref is present".to_string(),
       }));
     }
 
     if simple_type.is_some() && r#type.is_some() {
-      return Err(XsdIoError::XsdParseError(XsdParseError {
+      return Err(SchemaError::Invalid(XsdParseError {
         node_name: element.node_name(),
         msg: "simpleType and type cannot both present".to_string(),
       }));
@@ -106,7 +106,7 @@ impl Attribute {
   #[tracing::instrument(skip_all)]
   pub fn get_implementation(
     &self,
-    context: &mut XsdContext,
+    context: &XsdContext,
     parent_is_schema: bool,
   ) -> Result<XsdImpl, XsdError> {
     let mut generated_impl = match (
@@ -121,7 +121,7 @@ impl Attribute {
           } else {
             XsdName {
               namespace: reference.namespace.clone(),
-              local_name: inner.infer_type_name(),
+              local_name: reference.local_name.clone(),
               ty: XsdType::Attribute,
             }
           };
@@ -132,7 +132,9 @@ impl Attribute {
             fieldname_hint: Some(name.to_field_name()),
             inner: vec![],
             implementation: vec![],
-            flatten: self.name.is_none(),
+            flatten: false,
+            content: ContentKind::Simple,
+            attribute_groups: vec![],
           }
         } else {
           return Err(XsdError::XsdImplNotFound(reference.clone()));
@@ -144,7 +146,7 @@ impl Attribute {
             name.clone()
           } else {
             XsdName {
-              namespace: context.xml_schema_prefix.clone(),
+              namespace: context.target_namespace.clone(),
               local_name: inner.name.local_name.clone(),
               ty: XsdType::Attribute,
             }
@@ -153,6 +155,7 @@ impl Attribute {
           let element = if parent_is_schema {
             XsdImplType::TypeAlias(TypeAlias {
               doc: None,
+              vis: Some("pub".to_string()),
               alias: Type::new(Some(name.clone()), &name.to_struct_name()),
               value: inner.element.get_type(),
             })
@@ -167,6 +170,8 @@ impl Attribute {
             inner: vec![],
             implementation: vec![],
             flatten: false,
+            content: ContentKind::Simple,
+            attribute_groups: vec![],
           }
         } else {
           return Err(XsdError::XsdImplNotFound(r#type.clone()));
@@ -179,7 +184,7 @@ impl Attribute {
           name.clone()
         } else {
           XsdName {
-            namespace: context.xml_schema_prefix.clone(),
+            namespace: context.target_namespace.clone(),
             local_name: inner.name.local_name.clone(),
             ty: XsdType::Attribute,
           }
@@ -188,15 +193,19 @@ impl Attribute {
         let element = if parent_is_schema {
           XsdImplType::TypeAlias(TypeAlias {
             doc: None,
+            vis: Some("pub".to_string()),
             alias: Type::new(Some(name.clone()), &name.to_struct_name()),
-            value: inner.element.get_type().path(&name.to_field_name()),
+            value: inner
+              .element
+              .get_type()
+              .path(&to_module_name(&name.to_struct_name())),
           })
         } else {
           XsdImplType::Type(
             inner
               .element
               .get_type()
-              .path(&name.to_field_name())
+              .path(&to_module_name(&name.to_struct_name()))
               .xml_name(Some(name.clone())),
           )
         };
@@ -208,30 +217,62 @@ impl Attribute {
           inner: vec![inner],
           implementation: vec![],
           flatten: false,
+          content: ContentKind::Simple,
+          attribute_groups: vec![],
         }
       }
-      (_, _, _) => panic!("Not implemented Rust type for: {:?}", self),
+      // The other combinations are rejected when parsing.
+      (_, _, _) => {
+        return Err(XsdError::SchemaError(SchemaError::Unsupported(
+          XsdGenError {
+            node_name: self
+              .name
+              .as_ref()
+              .map_or_else(|| "attribute".to_string(), |name| name.to_string()),
+            ty: XsdType::Attribute,
+            msg: "Attribute has neither a type nor a ref.".to_string(),
+          },
+        )))
+      }
     };
 
     if let Some(doc) = &self.annotation {
       generated_impl.element.add_doc(&doc.get_doc().join(""));
     }
 
-    let mut generated_impl = general_xsdgen(generated_impl);
+    let mut generated_impl = general_xsdgen(generated_impl, context);
+
+    if let Some(fixed) = &self.fixed {
+      if context.fixed_values != FixedValues::Owned {
+        return Ok(self.get_fixed_implementation(
+          generated_impl,
+          fixed,
+          context.fixed_values,
+          parent_is_schema,
+        ));
+      }
+    }
+
+    if parent_is_schema {
+      let constant = (self.fixed.as_deref().map(|fixed| ("FIXED", fixed)))
+        .or_else(|| self.default.as_deref().map(|default| ("DEFAULT", default)));
+      if let Some((constant, value)) = constant {
+        return Ok(self.get_constant_implementation(generated_impl, constant, value, context));
+      }
+    }
+
+    // An absent attribute with a default or fixed value has that value, it isn't optional.
+    if let (false, Some(constraint)) = (parent_is_schema, self.value_constraint(context)) {
+      if let XsdImplType::Type(ty) = &mut generated_impl.element {
+        *ty = ty.clone().value_constraint(constraint);
+        return Ok(generated_impl);
+      }
+    }
 
     let generated_impl = if !parent_is_schema {
       if let Required::Optional = self.required {
-        let old_name = generated_impl.name.clone();
         let outer_element = generated_impl.element.get_type().wrap("Option");
-        generated_impl.name.local_name = format!("inner-{}", old_name.local_name);
-        XsdImpl {
-          name: old_name,
-          fieldname_hint: Some(generated_impl.fieldname_hint.clone().unwrap()),
-          element: XsdImplType::Type(outer_element),
-          inner: vec![generated_impl],
-          implementation: vec![],
-          flatten: false,
-        }
+        generated_impl.wrap(outer_element, VALUE, false)
       } else {
         generated_impl
       }
@@ -241,4 +282,174 @@ impl Attribute {
 
     Ok(generated_impl)
   }
+
+  /// The value of the attribute when it is absent: its `fixed` or `default` value, or else the
+  /// constant of the top-level attribute it refers to, see `get_constant_implementation`.
+  fn value_constraint(&self, context: &XsdContext) -> Option<ValueConstraint> {
+    if let Some(fixed) = &self.fixed {
+      return Some(ValueConstraint::Fixed(escape_str_literal(fixed)));
+    }
+    if let Some(default) = &self.default {
+      return Some(ValueConstraint::Default(escape_str_literal(default)));
+    }
+
+    let referred = context.search(self.reference.as_ref()?)?;
+    let ty = referred.element.get_type();
+    referred
+      .implementation
+      .iter()
+      .flat_map(|implementation| &implementation.assoc_consts)
+      .find_map(|(_, constant, _, _)| match constant.as_str() {
+        "FIXED" => Some(ValueConstraint::Fixed(format!("{ty}::FIXED"))),
+        "DEFAULT" => Some(ValueConstraint::Default(format!("{ty}::DEFAULT"))),
+        _ => None,
+      })
+  }
+
+  /// A top-level attribute with a default or fixed value is a newtype of its type holding the
+  /// value as the constant `constant`, since an alias can't carry it to the reference sites.
+  /// Types read from their text are parsed through the newtype, checking the fixed value, and
+  /// enumerations default to the variant of the constant.
+  fn get_constant_implementation(
+    &self,
+    generated_impl: XsdImpl,
+    constant: &str,
+    value: &str,
+    context: &XsdContext,
+  ) -> XsdImpl {
+    let XsdImplType::TypeAlias(alias) = &generated_impl.element else {
+      return generated_impl;
+    };
+    let name = generated_impl.name.clone();
+    let mut inner_type = alias.value.clone();
+    inner_type.xml_name = None;
+    let inner = match &self.r#type {
+      Some(r#type) => context.search(r#type),
+      None => generated_impl.inner.first(),
+    };
+    let from_text = inner.is_some_and(is_read_from_text);
+    let variant = inner.and_then(|inner| match &inner.element {
+      XsdImplType::Enum(r#enum) => r#enum
+        .variants
+        .iter()
+        .find(|variant| variant.xml_value.as_deref() == Some(value))
+        .map(|variant| variant.name.clone()),
+      _ => None,
+    });
+
+    let mut newtype = Struct::new(Some(name.clone()), &name.to_struct_name())
+      .vis("pub")
+      .tuple_field(Some("pub"), inner_type.clone(), false, false)
+      .derives(&["Clone", "Debug", "PartialEq"]);
+    if let Some(doc) = &self.annotation {
+      newtype.doc(&doc.get_doc().join(""));
+    }
+    let ty = newtype.ty().clone();
+
+    let literal = escape_str_literal(value);
+    let mut constant_impl = Impl::new(&ty);
+    constant_impl.associate_const_with_vis("pub", constant, "&'static str", &literal);
+
+    let mut newtype_impl = XsdImpl {
+      fieldname_hint: Some(name.to_field_name()),
+      name,
+      element: XsdImplType::Struct(newtype),
+      inner: generated_impl.inner,
+      implementation: vec![constant_impl],
+      flatten: false,
+      content: ContentKind::Simple,
+      attribute_groups: vec![],
+    };
+    if !from_text {
+      return general_xsdgen(newtype_impl, context);
+    }
+
+    let parse = format!("<{inner_type} as FromXmlString>::from_xml(string).map(Self)");
+    let parse = if constant == "FIXED" {
+      Block::new("match string")
+        .line(format!("{literal} => {parse},"))
+        .line(format!(
+          "value => Err(format!({})),",
+          escape_str_literal(&format!(
+            "Expected the fixed value {} found {{value}}.",
+            value.replace('{', "{{").replace('}', "}}")
+          ))
+        ))
+    } else {
+      Block::new("").line(parse)
+    };
+    newtype_impl
+      .implementation
+      .push(fromxml_impl(ty.clone(), parse));
+    if let Some(variant) = variant {
+      newtype_impl.implementation.push(
+        Impl::new(&ty).impl_trait("Default").push_fn(
+          Function::new("default")
+            .ret("Self")
+            .line(format!("Self({inner_type}::{variant})")),
+        ),
+      );
+    }
+
+    newtype_impl
+  }
+
+  fn get_fixed_implementation(
+    &self,
+    generated_impl: XsdImpl,
+    fixed: &str,
+    fixed_values: FixedValues,
+    parent_is_schema: bool,
+  ) -> XsdImpl {
+    let name = generated_impl.name;
+    let mut marker = marker_impl(name.clone(), &name.to_struct_name(), fixed);
+
+    if let Some(doc) = &self.annotation {
+      marker.element.add_doc(&doc.get_doc().join(""));
+    }
+
+    if parent_is_schema {
+      return marker;
+    }
+
+    let optional = matches!(self.required, Required::Optional);
+    let marker_type = marker.element.get_type();
+
+    let ty = match fixed_values {
+      FixedValues::StaticStr => {
+        // An absent fixed attribute still has the fixed value, so no `Option` is needed.
+        let parse_type = if optional {
+          marker_type.wrap("Option")
+        } else {
+          marker_type
+        };
+        Type::new(Some(name.clone()), "&'static str").parse_as(parse_type)
+      }
+      _ if optional => marker_type.wrap("Option"),
+      _ => marker_type,
+    };
+
+    XsdImpl {
+      name,
+      fieldname_hint: generated_impl.fieldname_hint,
+      element: XsdImplType::Type(ty),
+      inner: vec![marker],
+      implementation: vec![],
+      flatten: false,
+      content: ContentKind::Simple,
+      attribute_groups: vec![],
+    }
+  }
+}
+
+/// Whether the type of `imp` is read from its text by `FromXmlString`, e.g. a builtin type or an
+/// enumeration.
+pub(crate) fn is_read_from_text(imp: &XsdImpl) -> bool {
+  matches!(imp.element, XsdImplType::Type(_))
+    || imp.implementation.iter().any(|implementation| {
+      implementation
+        .impl_trait
+        .as_ref()
+        .is_some_and(|ty| ty.name == "FromXmlString")
+    })
 }
@@ -1,5 +1,5 @@
-use xsd_codegen::{Struct, XMLElement};
-use xsd_types::{XsdIoError, XsdName, XsdParseError, XsdType};
+use xsd_codegen::{ContentKind, Struct, XMLElement};
+use xsd_types::{SchemaError, XsdName, XsdParseError, XsdType};
 
 use crate::xsd::attribute::Attribute;
 
@@ -20,7 +20,7 @@ pub struct AttributeGroup {
 }
 
 impl AttributeGroup {
-  pub fn parse(mut element: XMLElement) -> Result<Self, XsdIoError> {
+  pub fn parse(mut element: XMLElement) -> Result<Self, SchemaError> {
     element.check_name("attributeGroup")?;
 
     let name = element
@@ -28,10 +28,10 @@ impl AttributeGroup {
       .map(|v: String| element.new_name(&v, XsdType::AttributeGroup));
     let reference = element
       .try_get_attribute("ref")?
-      .map(|v: String| element.new_name(&v, XsdType::AttributeGroup));
+      .map(|v: String| element.new_qname(&v, XsdType::AttributeGroup));
 
     if name.is_some() && reference.is_some() {
-      return Err(XsdIoError::XsdParseError(XsdParseError {
+      return Err(SchemaError::Invalid(XsdParseError {
         node_name: element.node_name(),
         msg: "name and ref both present".to_string(),
       }));
@@ -56,7 +56,7 @@ impl AttributeGroup {
   fn create_type(
     &self,
     parent_name: Option<XsdName>,
-    context: &mut XsdContext,
+    context: &XsdContext,
   ) -> Result<XsdImpl, XsdError> {
     // TODO(drosen): We know that both name and reference cannot be some,
     //               but we have no handler for what happens if the parent
@@ -77,15 +77,16 @@ impl AttributeGroup {
           refers.to_field_name()
         };
 
-        let name = if let Some(parent_name) = parent_name {
-          parent_name
-        } else {
-          XsdName {
-            namespace: None,
-            local_name: inner.infer_type_name(),
-            ty: XsdType::AttributeGroup,
-          }
-        };
+        // Named after the referenced group, which the fields are generated from.
+        let name = parent_name.unwrap_or_else(|| XsdName {
+          ty: XsdType::AttributeGroup,
+          ..refers.clone()
+        });
+
+        // The group is read along with the groups it includes, which aren't merged again.
+        let attribute_groups = std::iter::once(refers.clone())
+          .chain(inner.attribute_groups.iter().cloned())
+          .collect();
 
         Ok(XsdImpl {
           name,
@@ -94,6 +95,8 @@ impl AttributeGroup {
           inner: vec![],
           implementation: vec![],
           flatten: true,
+          content: inner.content,
+          attribute_groups,
         })
       }
       (_, None) => {
@@ -113,6 +116,8 @@ impl AttributeGroup {
           inner: vec![],
           implementation: vec![],
           flatten: true,
+          content: ContentKind::Empty,
+          attribute_groups: vec![],
         };
 
         if let Some(reference) = &self.reference {
@@ -125,6 +130,8 @@ impl AttributeGroup {
               inner: vec![],
               implementation: vec![],
               flatten: true,
+              content: imp.content,
+              attribute_groups: vec![],
             };
             generated_struct.merge(value, MergeSettings::default());
           } else {
@@ -142,7 +149,7 @@ impl AttributeGroup {
         for attr in &self.attribute_groups {
           generated_struct.merge(
             attr.get_implementation(parent_name.clone(), context)?,
-            MergeSettings::default(),
+            MergeSettings::ATTRIBUTE,
           );
         }
 
@@ -162,11 +169,11 @@ impl AttributeGroup {
   pub fn get_implementation(
     &self,
     parent_name: Option<XsdName>,
-    context: &mut XsdContext,
+    context: &XsdContext,
   ) -> Result<XsdImpl, XsdError> {
     let generated_impl = self.create_type(parent_name, context)?;
 
-    let mut gen = general_xsdgen(generated_impl);
+    let mut gen = general_xsdgen(generated_impl, context);
 
     gen.name.ty = XsdType::AttributeGroup;
 
@@ -0,0 +1,249 @@
+use std::collections::BTreeMap;
+
+use xsd_codegen::{
+  escape_str_literal, Block, Fields, Function, Impl, Struct, Type, ValueConstraint,
+};
+use xsd_types::to_module_name;
+
+use super::xsd_context::{XsdContext, XsdImpl, XsdImplType};
+
+/// The types read from text without allocating, along with the enums of enumerations.
+const PARSED_TYPES: &[&str] = &[
+  "bool",
+  "i8",
+  "i16",
+  "i32",
+  "i64",
+  "u8",
+  "u16",
+  "u32",
+  "u64",
+  "f32",
+  "f64",
+  "Date",
+  "Decimal",
+  "xml_schema_parser::DateTime",
+  "xml_schema_parser::Time",
+  "xml_schema_parser::Duration",
+  "xml_schema_parser::GYear",
+  "xml_schema_parser::GYearMonth",
+  "xml_schema_parser::GMonthDay",
+  "xml_schema_parser::GDay",
+  "xml_schema_parser::GMonth",
+  "xml_schema_parser::HexBinary",
+  "xml_schema_parser::Base64Binary",
+];
+
+/// How a field of a borrowed type is read.
+enum Read {
+  /// Borrowed from the document, as the given type holding `&'a str`.
+  Borrowed(Box<Type>),
+  /// Converted from the borrowed text, the field keeps its type.
+  Parsed,
+  /// Read by the owned parsing code out of a copy of the element.
+  Owned,
+}
+
+/// The generated types by the name they are referred to with from the top-level scope.
+pub(crate) fn generated_types(context: &XsdContext) -> BTreeMap<String, &XsdImpl> {
+  let mut types = BTreeMap::new();
+  for imp in context.structs.values() {
+    if matches!(imp.element, XsdImplType::Type(_)) {
+      continue;
+    }
+
+    let name = imp.element.get_type().name;
+    let module = to_module_name(&name);
+    for inner in &imp.inner {
+      if let Some(ty) = inner.element.try_get_type() {
+        types.insert(format!("{module}::{}", ty.name), inner);
+      }
+    }
+    types.insert(name, imp);
+  }
+  types
+}
+
+fn classify(ty: &Type, types: &BTreeMap<String, &XsdImpl>, string: &Type) -> Read {
+  if ty.parse_as.is_some() || ty.value_constraint.is_some() {
+    return Read::Owned;
+  }
+  if ty.to_string() == string.to_string() {
+    return Read::Borrowed(Box::new(Type::new(None, "&'a str")));
+  }
+
+  match (ty.name.as_str(), ty.generics.as_slice()) {
+    ("Option" | "Vec" | "RestrictedVec", [inner]) => {
+      return match classify(inner, types, string) {
+        Read::Borrowed(inner) => Read::Borrowed(Box::new(Type {
+          generics: vec![*inner],
+          ..ty.clone()
+        })),
+        read => read,
+      };
+    }
+    (name, []) if PARSED_TYPES.contains(&name) => return Read::Parsed,
+    _ => {}
+  }
+
+  match types.get(&ty.name).map(|imp| &imp.element) {
+    Some(XsdImplType::TypeAlias(alias)) => match classify(&alias.value, types, string) {
+      Read::Borrowed(borrowed) => Read::Borrowed(borrowed),
+      // The alias still names the type.
+      read => read,
+    },
+    Some(XsdImplType::Enum(_))
+      if types[&ty.name].implementation.iter().any(|implementation| {
+        implementation
+          .impl_trait
+          .as_ref()
+          .is_some_and(|tr| tr.name == "FromXmlString")
+      }) =>
+    {
+      Read::Parsed
+    }
+    _ => Read::Owned,
+  }
+}
+
+/// The borrowed counterpart `{Type}Ref<'a>` of the struct of `imp` along with its `XsdGenRef`
+/// impl, see `GeneratorOptions::borrowed_types`. Only generated for the structs with fields of
+/// text that can be borrowed. `types` are the `generated_types` of `context`.
+pub(crate) fn borrowed_type(
+  context: &XsdContext,
+  types: &BTreeMap<String, &XsdImpl>,
+  imp: &XsdImpl,
+) -> Option<(Struct, Impl)> {
+  let XsdImplType::Struct(r#struct) = &imp.element else {
+    return None;
+  };
+  let (Fields::Tuple(fields) | Fields::Named(fields)) = &r#struct.fields else {
+    return None;
+  };
+  // `XMLElementRef` doesn't track what the elements inherit, nor the content left unread.
+  if fields.iter().any(|field| field.value.is_some()) {
+    return None;
+  }
+
+  let string = context.string_storage.to_type();
+  let reads: Vec<Read> = fields
+    .iter()
+    .map(|field| classify(&field.ty, types, &string))
+    .collect();
+  if !reads.iter().any(|read| matches!(read, Read::Borrowed(_))) {
+    return None;
+  }
+
+  let name = &r#struct.ty().name;
+  let ref_name = format!("{name}Ref");
+  if types.contains_key(&ref_name) {
+    tracing::warn!("No borrowed type for {name}: {ref_name} is already generated");
+    return None;
+  }
+
+  let lifetime = Type::new(None, "'a");
+  let mut borrowed = r#struct.clone();
+  borrowed.type_def.ty = Type::new(None, &ref_name).generic(lifetime.clone());
+  borrowed.type_def.docs = None;
+  borrowed.type_def.derive = vec![];
+  borrowed.type_def.macros = vec![];
+  borrowed
+    .doc(&format!(
+      "`{name}` borrowing its text from the document, read with `XsdGenRef`."
+    ))
+    .derive("Clone")
+    .derive("Debug")
+    .derive("PartialEq");
+
+  let mut inner_name_used = false;
+  let mut body = vec![];
+  for (field, read) in borrowed.fields.fields_mut().iter_mut().zip(reads) {
+    field.annotation = vec![];
+
+    let gen_state = if field.attribute {
+      "gen_state.to_attr()"
+    } else {
+      "gen_state.clone()"
+    };
+    let xml_name = if field.flatten {
+      "None".to_string()
+    } else {
+      inner_name_used |= field.xml_name.is_none();
+      field
+        .xml_name
+        .as_ref()
+        .map(|v| format!("Some({})", escape_str_literal(&v.to_string())))
+        .unwrap_or_else(|| "name".to_string())
+    };
+
+    let value = match read {
+      Read::Borrowed(ty) => {
+        field.ty = *ty;
+        format!(
+          "<{} as XsdGenRef<'a>>::gen_ref(element, {gen_state}, {xml_name})",
+          field.ty
+        )
+      }
+      Read::Parsed => format!(
+        "<{} as XsdGenRef<'a>>::gen_ref(element, {gen_state}, {xml_name})",
+        field.ty
+      ),
+      Read::Owned => match (&field.ty.parse_as, &field.ty.value_constraint) {
+        (Some(parse_as), _) => format!(
+          "element.gen_owned::<{parse_as}>({gen_state}, {xml_name}).map(|_| <{parse_as} as FixedValue>::VALUE)"
+        ),
+        (None, Some(ValueConstraint::Default(default))) => format!(
+          "element.gen_owned_attribute_or::<{}>({gen_state}, {xml_name}, {default})",
+          field.ty
+        ),
+        (None, Some(ValueConstraint::Fixed(fixed))) => format!(
+          "element.gen_owned_fixed_attribute::<{}>({gen_state}, {xml_name}, {fixed})",
+          field.ty
+        ),
+        (None, None) => format!("element.gen_owned::<{}>({gen_state}, {xml_name})", field.ty),
+      },
+    };
+    body.push(match &field.name {
+      Some(name) => format!("{name}: {value}?,"),
+      None => format!("{value}?,"),
+    });
+  }
+
+  let closure = if inner_name_used {
+    "let gen_self = |element: &mut XMLElementRef<'a>, name: Option<&str>|"
+  } else {
+    "let gen_self = |element: &mut XMLElementRef<'a>, _name: Option<&str>|"
+  };
+  let self_gen = match &borrowed.fields {
+    Fields::Tuple(_) => body
+      .into_iter()
+      .fold(Block::new(closure).line("Ok(Self ("), Block::line)
+      .line("))"),
+    _ => Block::new(closure).push_block(
+      body
+        .into_iter()
+        .fold(Block::new("Ok(Self"), Block::line)
+        .after(")"),
+    ),
+  }
+  .after(";");
+
+  let gen_ref = Function::new("gen_ref")
+    .arg("element", Type::new(None, "&mut XMLElementRef<'a>"))
+    .arg("gen_state", Type::new(None, "GenState"))
+    .arg("name", Type::new(None, "Option<&str>"))
+    .ret(Type::new(None, "Result<Self, DocumentError>"))
+    .push_block(self_gen)
+    .push_block(
+      Block::new("if let (Some(name), GenType::Content) = (name, gen_state.state)")
+        .line("gen_self(&mut element.get_next_child(name)?, None)"),
+    )
+    .push_block(Block::new("else").line("gen_self(element, name)"));
+
+  let implementation = Impl::new(borrowed.ty())
+    .generic("'a")
+    .impl_trait(Type::new(None, "XsdGenRef").generic(lifetime))
+    .push_fn(gen_ref);
+
+  Some((borrowed, implementation))
+}
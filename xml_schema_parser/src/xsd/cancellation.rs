@@ -0,0 +1,95 @@
+use std::{
+  fmt,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+};
+
+use super::XsdError;
+
+/// A flag stopping a generation from another thread, e.g. when the user presses Ctrl-C. The
+/// clones of a token share its flag.
+#[derive(Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Stop the generations using this token, which return `XsdError::Cancelled`.
+  pub fn cancel(&self) {
+    self.0.store(true, Ordering::Relaxed);
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.0.load(Ordering::Relaxed)
+  }
+
+  /// Fail with `XsdError::Cancelled` once the token is cancelled.
+  pub(crate) fn check(&self) -> Result<(), XsdError> {
+    if self.is_cancelled() {
+      return Err(XsdError::Cancelled);
+    }
+    Ok(())
+  }
+}
+
+/// The state of the token is not shown: the fingerprint of the options is computed from their
+/// debug representation.
+impl fmt::Debug for CancellationToken {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("CancellationToken")
+  }
+}
+
+/// Tokens don't change what is generated, they all compare equal.
+impl PartialEq for CancellationToken {
+  fn eq(&self, _: &Self) -> bool {
+    true
+  }
+}
+
+#[cfg(test)]
+fn large_schema(types: usize) -> String {
+  let mut schema = String::from(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">"#);
+  for index in 0..types {
+    schema.push_str(&format!(
+      r#"<xs:complexType name="part{index}">
+        <xs:sequence>
+          <xs:element name="measure" type="xs:string" maxOccurs="unbounded"/>
+          <xs:element name="name" type="xs:string" minOccurs="0"/>
+        </xs:sequence>
+        <xs:attribute name="id" type="xs:int"/>
+      </xs:complexType>"#
+    ));
+  }
+  schema.push_str("</xs:schema>");
+  schema
+}
+
+#[test]
+fn cancelled_generations_stop() {
+  let xsd = crate::Xsd::new(&large_schema(2000)).unwrap();
+  let options = crate::GeneratorOptions::default();
+  let token = options.cancel_token.clone();
+
+  let start = std::time::Instant::now();
+  let result = std::thread::scope(|scope| {
+    let generation = scope.spawn(|| xsd.generate_with(&options));
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    token.cancel();
+    generation.join().unwrap()
+  });
+
+  assert!(matches!(result, Err(XsdError::Cancelled)), "{result:?}");
+  assert!(start.elapsed() < std::time::Duration::from_secs(2));
+
+  // A token cancelled up front stops the generation before anything is generated.
+  let small = crate::Xsd::new(&large_schema(1)).unwrap();
+  assert!(matches!(
+    small.generate_with(&options),
+    Err(XsdError::Cancelled)
+  ));
+}
@@ -0,0 +1,645 @@
+//! The canonical encoding of the parsed components, hashed by `SchemaOptions::content_hash`, and
+//! of the options, hashed by `GeneratorOptions::fingerprint`.
+//!
+//! Every field is written in the order listed here, strings and lists prefixed with their
+//! length, optional values and enum variants with a tag, so that two values have the same
+//! encoding only when they are equal. The structs are destructured without `..`: a new field
+//! doesn't compile until it is listed.
+
+use std::collections::BTreeMap;
+
+use xsd_types::{XsdName, XsdType};
+
+use super::{
+  all::All,
+  annotation::Annotation,
+  attribute::{Attribute, Required},
+  attribute_group::AttributeGroup,
+  choice::{Choice, ChoiceOptions},
+  complex_content::ComplexContent,
+  complex_type::ComplexType,
+  element::Element,
+  extension::Extension,
+  group::Group,
+  identity_constraint::{ConstraintKind, IdentityConstraint, NameTest, Path, Step},
+  import::Import,
+  list::List,
+  max_occurences::MaxOccurences,
+  options::{
+    FieldOverride, FixedValues, GeneratorOptions, Naming, StringStorage, StubKind, Visibility,
+  },
+  restriction::{Restriction, Whitespace},
+  schema::SchemaOptions,
+  sequence::{Sequence, SequenceOptions},
+  simple_content::SimpleContent,
+  simple_type::SimpleType,
+  union::Union,
+  version::XsdVersion,
+};
+
+pub trait Canonical {
+  /// Append the canonical encoding of `self` to `out`.
+  fn encode(&self, out: &mut Vec<u8>);
+}
+
+/// The tag of the variant of an enum.
+fn tag(out: &mut Vec<u8>, tag: u8) {
+  out.push(tag);
+}
+
+impl Canonical for u64 {
+  fn encode(&self, out: &mut Vec<u8>) {
+    out.extend_from_slice(&self.to_le_bytes());
+  }
+}
+
+impl Canonical for i64 {
+  fn encode(&self, out: &mut Vec<u8>) {
+    out.extend_from_slice(&self.to_le_bytes());
+  }
+}
+
+impl Canonical for bool {
+  fn encode(&self, out: &mut Vec<u8>) {
+    out.push(u8::from(*self));
+  }
+}
+
+impl Canonical for str {
+  fn encode(&self, out: &mut Vec<u8>) {
+    (self.len() as u64).encode(out);
+    out.extend_from_slice(self.as_bytes());
+  }
+}
+
+impl Canonical for String {
+  fn encode(&self, out: &mut Vec<u8>) {
+    self.as_str().encode(out);
+  }
+}
+
+impl<T: Canonical> Canonical for Option<T> {
+  fn encode(&self, out: &mut Vec<u8>) {
+    match self {
+      None => tag(out, 0),
+      Some(value) => {
+        tag(out, 1);
+        value.encode(out);
+      }
+    }
+  }
+}
+
+impl<K: Canonical, V: Canonical> Canonical for BTreeMap<K, V> {
+  fn encode(&self, out: &mut Vec<u8>) {
+    (self.len() as u64).encode(out);
+    for (key, value) in self {
+      key.encode(out);
+      value.encode(out);
+    }
+  }
+}
+
+impl<T: Canonical> Canonical for Vec<T> {
+  fn encode(&self, out: &mut Vec<u8>) {
+    (self.len() as u64).encode(out);
+    for value in self {
+      value.encode(out);
+    }
+  }
+}
+
+/// The kinds are written by name, their order in `XsdType` doesn't matter.
+impl Canonical for XsdType {
+  fn encode(&self, out: &mut Vec<u8>) {
+    let name = match self {
+      XsdType::All => "all",
+      XsdType::Annotation => "annotation",
+      XsdType::AttributeGroup => "attributeGroup",
+      XsdType::Attribute => "attribute",
+      XsdType::Choice => "choice",
+      XsdType::ComplexContent => "complexContent",
+      XsdType::ComplexType => "complexType",
+      XsdType::DerivedTypes => "derivedTypes",
+      XsdType::Element => "element",
+      XsdType::Extension => "extension",
+      XsdType::Group => "group",
+      XsdType::IdentityConstraint => "identityConstraint",
+      XsdType::Import => "import",
+      XsdType::List => "list",
+      XsdType::Restriction => "restriction",
+      XsdType::Sequence => "sequence",
+      XsdType::SimpleContent => "simpleContent",
+      XsdType::SimpleType => "simpleType",
+      XsdType::SubstitutionGroup => "substitutionGroup",
+      XsdType::Union => "union",
+      XsdType::Unknown => "unknown",
+    };
+    name.encode(out);
+  }
+}
+
+/// Implement `Canonical` for a struct, writing the fields in the order listed, which must be all
+/// of them.
+macro_rules! canonical_struct {
+  ($ty:ident { $($field:ident),* $(,)? }) => {
+    impl Canonical for $ty {
+      fn encode(&self, out: &mut Vec<u8>) {
+        let $ty { $($field),* } = self;
+        $($field.encode(out);)*
+      }
+    }
+  };
+}
+
+canonical_struct!(XsdName {
+  namespace,
+  local_name,
+  ty
+});
+canonical_struct!(Import {
+  id,
+  namespace,
+  schema_location
+});
+canonical_struct!(Annotation { id, documentation });
+canonical_struct!(Element {
+  name,
+  kind,
+  refers,
+  min_occurences,
+  r#final,
+  block,
+  substitution_group,
+  r#abstract,
+  max_occurences,
+  complex_type,
+  simple_type,
+  annotation,
+  identity_constraints,
+});
+canonical_struct!(SimpleType {
+  name,
+  annotation,
+  restriction,
+  list,
+  union
+});
+canonical_struct!(ComplexType {
+  name,
+  attributes,
+  attribute_groups,
+  choice,
+  group,
+  sequence,
+  simple_content,
+  complex_content,
+  annotation,
+  r#abstract,
+});
+canonical_struct!(Attribute {
+  annotation,
+  name,
+  r#type,
+  default,
+  fixed,
+  required,
+  reference,
+  simple_type,
+});
+canonical_struct!(AttributeGroup {
+  name,
+  reference,
+  annotation,
+  attributes,
+  attribute_groups,
+});
+canonical_struct!(Group {
+  id,
+  name,
+  refers,
+  min_occurences,
+  max_occurences,
+  annotation,
+  all,
+  sequence,
+  choice,
+});
+canonical_struct!(All {
+  id,
+  min_occurences,
+  max_occurences,
+  annotation,
+  children,
+});
+canonical_struct!(Sequence {
+  id,
+  min_occurences,
+  max_occurences,
+  annotation,
+  children,
+});
+canonical_struct!(Choice {
+  id,
+  min_occurences,
+  max_occurences,
+  children
+});
+canonical_struct!(ComplexContent {
+  extension,
+  restriction
+});
+canonical_struct!(SimpleContent {
+  restriction,
+  extension
+});
+canonical_struct!(Extension {
+  base,
+  attributes,
+  attribute_groups,
+  sequence,
+  group,
+  choice,
+  annotation,
+});
+canonical_struct!(Restriction {
+  base,
+  min_inclusive,
+  max_inclusive,
+  min_exclusive,
+  max_exclusive,
+  total_digits,
+  fraction_digits,
+  enumerations,
+  pattern,
+  whitespace,
+  length,
+  min_length,
+  max_length,
+  annotation,
+  choice,
+  group,
+  sequence,
+  attributes,
+  attribute_groups,
+});
+canonical_struct!(List { item_type });
+canonical_struct!(Union {
+  member_types,
+  simple_types
+});
+canonical_struct!(IdentityConstraint {
+  name,
+  kind,
+  selector,
+  fields,
+  annotation,
+});
+canonical_struct!(Path { descendants, steps });
+canonical_struct!(FieldOverride {
+  owner,
+  xml_name,
+  rename,
+  ty,
+  attribute,
+  flatten,
+  drop,
+});
+
+/// The names the naming conventions are told apart by.
+const NAMING_SAMPLES: &[&str] = &[
+  "note-type",
+  "time_modification",
+  "MIDIDevice",
+  "xml:lang",
+  "a1",
+];
+
+/// A convention is written as the names it gives to the samples, so that the default one reads
+/// the same whether it is set or not.
+impl Canonical for Naming {
+  fn encode(&self, out: &mut Vec<u8>) {
+    let convention = self.convention();
+    for sample in NAMING_SAMPLES {
+      convention.struct_name(sample).encode(out);
+      convention.field_name(sample).encode(out);
+      convention.variant_name(sample).encode(out);
+      convention.module_name(sample).encode(out);
+    }
+  }
+}
+
+/// The cancellation token changes nothing in the generated code, it is left out.
+impl Canonical for GeneratorOptions {
+  fn encode(&self, out: &mut Vec<u8>) {
+    let GeneratorOptions {
+      roots,
+      extra_derives,
+      visibility,
+      namespace_modules,
+      external_namespaces,
+      fixed_values,
+      string_storage,
+      send_sync_assertions,
+      stats_in_docs,
+      verbose_codegen,
+      provenance_comments,
+      content_hashes,
+      field_metadata,
+      flatten_wrappers,
+      progress_hooks,
+      async_entry_points,
+      borrowed_types,
+      inherited_lang,
+      choice_visitors,
+      doc_links,
+      key_resolvers,
+      stubs,
+      field_overrides,
+      conformance,
+      strict_conformance,
+      max_occurs_limit,
+      lenient_enumerations,
+      builtin_types,
+      strict_mappings,
+      serde,
+      dynamic_values,
+      xml_writers,
+      unknown_content,
+      inner_module_lints,
+      type_index,
+      pattern_checks,
+      naming,
+      cancel_token: _,
+    } = self;
+
+    roots.encode(out);
+    extra_derives.encode(out);
+    visibility.encode(out);
+    namespace_modules.encode(out);
+    external_namespaces.encode(out);
+    fixed_values.encode(out);
+    string_storage.encode(out);
+    send_sync_assertions.encode(out);
+    stats_in_docs.encode(out);
+    verbose_codegen.encode(out);
+    provenance_comments.encode(out);
+    content_hashes.encode(out);
+    field_metadata.encode(out);
+    flatten_wrappers.encode(out);
+    progress_hooks.encode(out);
+    async_entry_points.encode(out);
+    borrowed_types.encode(out);
+    inherited_lang.encode(out);
+    choice_visitors.encode(out);
+    doc_links.encode(out);
+    key_resolvers.encode(out);
+    stubs.encode(out);
+    field_overrides.encode(out);
+    conformance.encode(out);
+    strict_conformance.encode(out);
+    max_occurs_limit.encode(out);
+    lenient_enumerations.encode(out);
+    builtin_types.encode(out);
+    strict_mappings.encode(out);
+    serde.encode(out);
+    dynamic_values.encode(out);
+    xml_writers.encode(out);
+    unknown_content.encode(out);
+    inner_module_lints.encode(out);
+    type_index.encode(out);
+    pattern_checks.encode(out);
+    naming.encode(out);
+  }
+}
+
+impl Canonical for Visibility {
+  fn encode(&self, out: &mut Vec<u8>) {
+    match self {
+      Visibility::Pub => tag(out, 0),
+      Visibility::PubCrate => tag(out, 1),
+      Visibility::PubSuper => tag(out, 2),
+    }
+  }
+}
+
+impl Canonical for FixedValues {
+  fn encode(&self, out: &mut Vec<u8>) {
+    match self {
+      FixedValues::Owned => tag(out, 0),
+      FixedValues::Marker => tag(out, 1),
+      FixedValues::StaticStr => tag(out, 2),
+    }
+  }
+}
+
+impl Canonical for StringStorage {
+  fn encode(&self, out: &mut Vec<u8>) {
+    match self {
+      StringStorage::String => tag(out, 0),
+      StringStorage::BoxStr => tag(out, 1),
+      StringStorage::Custom(ty) => {
+        tag(out, 2);
+        ty.encode(out);
+      }
+    }
+  }
+}
+
+impl Canonical for StubKind {
+  fn encode(&self, out: &mut Vec<u8>) {
+    match self {
+      StubKind::Opaque => tag(out, 0),
+      StubKind::Skip => tag(out, 1),
+    }
+  }
+}
+
+impl Canonical for XsdVersion {
+  fn encode(&self, out: &mut Vec<u8>) {
+    match self {
+      XsdVersion::V1_0 => tag(out, 0),
+      XsdVersion::V1_1 => tag(out, 1),
+    }
+  }
+}
+
+impl Canonical for Required {
+  fn encode(&self, out: &mut Vec<u8>) {
+    match self {
+      Required::Optional => tag(out, 0),
+      Required::Required => tag(out, 1),
+    }
+  }
+}
+
+impl Canonical for MaxOccurences {
+  fn encode(&self, out: &mut Vec<u8>) {
+    match self {
+      MaxOccurences::Unbounded => tag(out, 0),
+      MaxOccurences::Number { value } => {
+        tag(out, 1);
+        value.encode(out);
+      }
+    }
+  }
+}
+
+impl Canonical for Whitespace {
+  fn encode(&self, out: &mut Vec<u8>) {
+    match self {
+      Whitespace::Preserve => tag(out, 0),
+      Whitespace::Replace => tag(out, 1),
+      Whitespace::Collapse => tag(out, 2),
+    }
+  }
+}
+
+impl Canonical for ConstraintKind {
+  fn encode(&self, out: &mut Vec<u8>) {
+    match self {
+      ConstraintKind::Unique => tag(out, 0),
+      ConstraintKind::Key => tag(out, 1),
+      ConstraintKind::KeyRef { refer } => {
+        tag(out, 2);
+        refer.encode(out);
+      }
+    }
+  }
+}
+
+impl Canonical for Step {
+  fn encode(&self, out: &mut Vec<u8>) {
+    match self {
+      Step::Current => tag(out, 0),
+      Step::Child(test) => {
+        tag(out, 1);
+        test.encode(out);
+      }
+      Step::Attribute(test) => {
+        tag(out, 2);
+        test.encode(out);
+      }
+    }
+  }
+}
+
+impl Canonical for NameTest {
+  fn encode(&self, out: &mut Vec<u8>) {
+    match self {
+      NameTest::Any => tag(out, 0),
+      NameTest::AnyIn(namespace) => {
+        tag(out, 1);
+        namespace.encode(out);
+      }
+      NameTest::Name(name) => {
+        tag(out, 2);
+        name.encode(out);
+      }
+    }
+  }
+}
+
+impl Canonical for ChoiceOptions {
+  fn encode(&self, out: &mut Vec<u8>) {
+    match self {
+      ChoiceOptions::Element(element) => {
+        tag(out, 0);
+        element.encode(out);
+      }
+      ChoiceOptions::Group(group) => {
+        tag(out, 1);
+        group.encode(out);
+      }
+      ChoiceOptions::Choice(choice) => {
+        tag(out, 2);
+        choice.encode(out);
+      }
+      ChoiceOptions::Sequence(sequence) => {
+        tag(out, 3);
+        sequence.encode(out);
+      }
+    }
+  }
+}
+
+impl Canonical for SequenceOptions {
+  fn encode(&self, out: &mut Vec<u8>) {
+    match self {
+      SequenceOptions::Element(element) => {
+        tag(out, 0);
+        element.encode(out);
+      }
+      SequenceOptions::Group(group) => {
+        tag(out, 1);
+        group.encode(out);
+      }
+      SequenceOptions::Choice(choice) => {
+        tag(out, 2);
+        choice.encode(out);
+      }
+      SequenceOptions::Sequence(sequence) => {
+        tag(out, 3);
+        sequence.encode(out);
+      }
+    }
+  }
+}
+
+impl Canonical for SchemaOptions {
+  fn encode(&self, out: &mut Vec<u8>) {
+    match self {
+      SchemaOptions::Import(import) => {
+        tag(out, 0);
+        import.encode(out);
+      }
+      SchemaOptions::Annotation(annotation) => {
+        tag(out, 1);
+        annotation.encode(out);
+      }
+      SchemaOptions::Element(element) => {
+        tag(out, 2);
+        element.encode(out);
+      }
+      SchemaOptions::SimpleType(simple_type) => {
+        tag(out, 3);
+        simple_type.encode(out);
+      }
+      SchemaOptions::ComplexType(complex_type) => {
+        tag(out, 4);
+        complex_type.encode(out);
+      }
+      SchemaOptions::Attribute(attribute) => {
+        tag(out, 5);
+        attribute.encode(out);
+      }
+      SchemaOptions::AttributeGroup(attribute_group) => {
+        tag(out, 6);
+        attribute_group.encode(out);
+      }
+      SchemaOptions::Group(group) => {
+        tag(out, 7);
+        group.encode(out);
+      }
+    }
+  }
+}
+
+#[test]
+fn components_are_encoded_field_by_field() {
+  let import = Import {
+    id: None,
+    namespace: Some("urn:n".to_string()),
+    schema_location: Some("n.xsd".to_string()),
+  };
+  let mut encoding = vec![];
+  import.encode(&mut encoding);
+
+  let mut expected = vec![0, 1, 5, 0, 0, 0, 0, 0, 0, 0];
+  expected.extend_from_slice(b"urn:n");
+  expected.extend_from_slice(&[1, 5, 0, 0, 0, 0, 0, 0, 0]);
+  expected.extend_from_slice(b"n.xsd");
+  assert_eq!(encoding, expected);
+
+  assert_eq!(SchemaOptions::Import(import).content_hash(), "81a847cd");
+}
@@ -1,13 +1,13 @@
-use xsd_codegen::{Enum, XMLElement};
-use xsd_types::{to_struct_name, XsdIoError, XsdName, XsdType};
+use xsd_codegen::{ContentKind, Enum, XMLElement};
+use xsd_types::{SchemaError, XsdName, XsdParseError, XsdType};
 
 use super::{
   element::Element,
   general_xsdgen,
   group::Group,
-  max_occurences::MaxOccurences,
+  max_occurences::{self, MaxOccurences, ITEM},
   sequence::Sequence,
-  xsd_context::{infer_type_name, MergeSettings, XsdContext, XsdImpl, XsdImplType},
+  xsd_context::{infer_type_name, wrapped_name, MergeSettings, XsdContext, XsdImpl, XsdImplType},
   XsdError,
 };
 
@@ -20,6 +20,7 @@ pub struct Choice {
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 pub enum ChoiceOptions {
   Element(Element),
   Group(Group),
@@ -27,25 +28,44 @@ pub enum ChoiceOptions {
   Sequence(Sequence),
 }
 
+impl ChoiceOptions {
+  fn is_prohibited(&self) -> bool {
+    match self {
+      ChoiceOptions::Element(element) => element.max_occurences.is_prohibited(),
+      ChoiceOptions::Group(group) => group.max_occurences.is_prohibited(),
+      ChoiceOptions::Choice(choice) => choice.max_occurences.is_prohibited(),
+      ChoiceOptions::Sequence(sequence) => sequence.max_occurences.is_prohibited(),
+    }
+  }
+}
+
 impl Choice {
-  pub fn parse(mut element: XMLElement) -> Result<Self, XsdIoError> {
+  pub fn parse(mut element: XMLElement) -> Result<Self, SchemaError> {
     element.check_name("choice")?;
 
     let mut children = vec![];
     for child in element.get_all_children() {
-      children.push(match child.element.name.as_str() {
+      children.push(match child.name() {
         "element" => ChoiceOptions::Element(Element::parse(child, false)?),
         "group" => ChoiceOptions::Group(Group::parse(child)?),
         "choice" => ChoiceOptions::Choice(Choice::parse(child)?),
         "sequence" => ChoiceOptions::Sequence(Sequence::parse(child)?),
-        name => unreachable!("Unexpected child name {name}"),
+        // The documentation of a choice isn't kept.
+        "annotation" => continue,
+        name => {
+          return Err(SchemaError::Invalid(XsdParseError {
+            node_name: element.node_name(),
+            msg: format!("Unexpected child {name}"),
+          }))
+        }
       });
     }
 
+    let (min_occurences, max_occurences) = max_occurences::parse_occurences(&mut element, None)?;
     let output = Self {
       id: element.try_get_attribute("id")?,
-      min_occurences: element.try_get_attribute("minOccurs")?.unwrap_or(1),
-      max_occurences: element.get_attribute_default("maxOccurs")?,
+      min_occurences,
+      max_occurences,
       children,
     };
 
@@ -58,11 +78,12 @@ impl Choice {
   pub fn get_implementation(
     &self,
     parent_name: Option<XsdName>,
-    context: &mut XsdContext,
+    context: &XsdContext,
   ) -> Result<XsdImpl, XsdError> {
     let mut generated_impls = vec![];
 
-    for child in &self.children {
+    // The prohibited particles are left out.
+    for child in self.children.iter().filter(|child| !child.is_prohibited()) {
       match child {
         ChoiceOptions::Element(element) => {
           generated_impls.push(element.get_implementation(context)?)
@@ -91,8 +112,13 @@ impl Choice {
       }
     };
 
-    let struct_name = xml_name.local_name.clone();
-    let struct_name = to_struct_name(&struct_name);
+    // A repeated or optional choice is wrapped, its enum is named after its role in the wrapper.
+    let max_occurences = self.max_occurences.limited(context.max_occurs_limit);
+    let role = max_occurences.role(self.min_occurences);
+    let struct_name = match role {
+      Some(role) => wrapped_name(&xml_name, role).to_struct_name(),
+      None => xml_name.to_struct_name(),
+    };
 
     let mut generated_impl = XsdImpl {
       fieldname_hint: Some(xml_name.to_field_name()),
@@ -105,63 +131,32 @@ impl Choice {
       inner: vec![],
       implementation: vec![],
       flatten: parent_name.is_none(),
+      content: ContentKind::Empty,
+      attribute_groups: vec![],
     };
 
     for imp in generated_impls {
       generated_impl.merge(imp, MergeSettings::default());
     }
 
-    let multiple = match &self.max_occurences {
-      MaxOccurences::Unbounded => true,
-      MaxOccurences::Number { value } => *value > 1,
-    } || self.min_occurences > 1;
+    let generated_impl = general_xsdgen(generated_impl, context);
 
-    let option = match &self.max_occurences {
-      MaxOccurences::Unbounded => false,
-      MaxOccurences::Number { value } => *value == 1 && self.min_occurences == 0,
-    };
-
-    let mut generated_impl = general_xsdgen(generated_impl);
-
-    let mut generated_impl = if multiple {
-      let old_name = generated_impl.name.clone();
-      generated_impl.name.local_name = format!("inner-{}", old_name.local_name);
-
-      XsdImpl {
-        name: old_name,
-        fieldname_hint: Some(generated_impl.fieldname_hint.clone().unwrap()),
-        element: XsdImplType::Type(
-          if self.min_occurences > 0 || self.max_occurences != MaxOccurences::Unbounded {
-            generated_impl
-              .element
-              .get_type()
-              .wrap("RestrictedVec")
-              .generic(self.min_occurences.to_string())
-              .generic(match self.max_occurences {
-                MaxOccurences::Unbounded => "0".to_string(),
-                MaxOccurences::Number { value } => value.to_string(),
-              })
-          } else {
-            generated_impl.element.get_type().wrap("Vec")
-          },
-        ),
-        inner: vec![generated_impl],
-        implementation: vec![],
-        flatten: parent_name.is_none(),
+    // The repetitions are read from the content of the parent, not from a child named after it.
+    let mut generated_impl = match role {
+      Some(ITEM) => {
+        let ty =
+          max_occurences.wrap_repeated(generated_impl.element.get_type(), self.min_occurences);
+        generated_impl.wrap(ty, ITEM, true)
       }
-    } else if option {
-      let old_name = generated_impl.name.clone();
-      generated_impl.name.local_name = format!("inner-{}", old_name.local_name);
-      XsdImpl {
-        name: old_name,
-        fieldname_hint: Some(generated_impl.fieldname_hint.clone().unwrap()),
-        element: XsdImplType::Type(generated_impl.element.get_type().wrap("Option")),
-        inner: vec![generated_impl],
-        implementation: vec![],
-        flatten: parent_name.is_none(),
+      Some(role) => {
+        let ty = generated_impl
+          .element
+          .get_type()
+          .wrap("Option")
+          .occurs(0, Some(1));
+        generated_impl.wrap(ty, role, true)
       }
-    } else {
-      generated_impl
+      None => generated_impl,
     };
 
     generated_impl.name.ty = XsdType::Choice;
@@ -169,3 +164,33 @@ impl Choice {
     Ok(generated_impl)
   }
 }
+
+#[test]
+fn bounded_choice_is_a_restricted_vec() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="measure">
+        <xs:choice maxOccurs="3">
+          <xs:element name="note" type="xs:string"/>
+          <xs:element name="rest" type="xs:string"/>
+        </xs:choice>
+      </xs:complexType>
+      <xs:complexType name="part">
+        <xs:choice minOccurs="0" maxOccurs="unbounded">
+          <xs:element name="note" type="xs:string"/>
+          <xs:element name="rest" type="xs:string"/>
+        </xs:choice>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap();
+
+  assert!(output.contains("pub measure: RestrictedVec<measure::MeasureItem, 1, 3>"));
+  assert!(output.contains("pub part: Vec<part::PartItem>"));
+}
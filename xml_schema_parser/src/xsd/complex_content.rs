@@ -1,5 +1,5 @@
 use xsd_codegen::XMLElement;
-use xsd_types::{XsdIoError, XsdName, XsdType};
+use xsd_types::{SchemaError, XsdName, XsdParseError, XsdType};
 
 use crate::xsd::{extension::Extension, xsd_context::XsdContext};
 
@@ -16,7 +16,7 @@ pub struct ComplexContent {
 }
 
 impl ComplexContent {
-  pub fn parse(mut element: XMLElement) -> Result<Self, XsdIoError> {
+  pub fn parse(mut element: XMLElement) -> Result<Self, SchemaError> {
     element.check_name("complexContent")?;
 
     let output = Self {
@@ -26,6 +26,16 @@ impl ComplexContent {
       })?,
     };
 
+    if output.extension.is_some() == output.restriction.is_some() {
+      return Err(
+        XsdParseError {
+          node_name: element.node_name(),
+          msg: "One of extension and restriction is required, they cannot both present".to_string(),
+        }
+        .into(),
+      );
+    }
+
     element.finalize(false, false)?;
 
     Ok(output)
@@ -35,15 +45,16 @@ impl ComplexContent {
   pub fn get_implementation(
     &self,
     parent_name: XsdName,
-    context: &mut XsdContext,
+    context: &XsdContext,
   ) -> Result<XsdImpl, XsdError> {
     let mut gen = match (&self.extension, &self.restriction) {
       (None, Some(restriction)) => {
         restriction.get_implementation(parent_name, RestrictionParentType::ComplexContent, context)
       }
       (Some(extension), None) => extension.get_implementation(parent_name, context),
+      // Exactly one of them is present, checked when parsing.
       _ => {
-        unimplemented!("The source xsd is invalid.")
+        unreachable!("The source xsd is invalid.")
       }
     }?;
 
@@ -1,5 +1,5 @@
-use xsd_codegen::{Struct, XMLElement};
-use xsd_types::{XsdIoError, XsdName, XsdParseError, XsdType};
+use xsd_codegen::{ContentKind, Struct, XMLElement};
+use xsd_types::{SchemaError, XsdName, XsdParseError, XsdType};
 
 use super::{
   annotation::Annotation,
@@ -26,10 +26,13 @@ pub struct ComplexType {
   pub simple_content: Option<SimpleContent>,
   pub complex_content: Option<ComplexContent>,
   pub annotation: Option<Annotation>,
+  /// Whether the type is abstract, the elements of this type are then of one of its derived
+  /// types, named by their `xsi:type`.
+  pub r#abstract: bool,
 }
 
 impl ComplexType {
-  pub fn parse(mut element: XMLElement) -> Result<Self, XsdIoError> {
+  pub fn parse(mut element: XMLElement) -> Result<Self, SchemaError> {
     element.check_name("complexType")?;
 
     // (annotation?,(simpleContent|complexContent|((group|all|choice|sequence)?,((attribute|attributeGroup)*,anyAttribute?))))
@@ -45,7 +48,7 @@ impl ComplexType {
     let attribute_groups = element.get_children_with("attributeGroup", AttributeGroup::parse)?;
 
     if simple_content.is_some() && complex_content.is_some() {
-      return Err(XsdIoError::XsdParseError(XsdParseError {
+      return Err(SchemaError::Invalid(XsdParseError {
         node_name: element.node_name(),
         msg: "simpleContent | complexContent cannot both present".to_string(),
       }));
@@ -58,11 +61,11 @@ impl ComplexType {
         || choice.is_some()
         || sequence.is_some())
     {
-      return Err(XsdIoError::XsdParseError(XsdParseError {node_name: element.node_name(), msg: "(simpleContent | complexContent) and (group | choice | sequence | attribute | attributeGroup) cannot both present".to_string()}));
+      return Err(SchemaError::Invalid(XsdParseError {node_name: element.node_name(), msg: "(simpleContent | complexContent) and (group | choice | sequence | attribute | attributeGroup) cannot both present".to_string()}));
     }
 
     if group.is_some() as u8 + choice.is_some() as u8 + sequence.is_some() as u8 > 1 {
-      return Err(XsdIoError::XsdParseError(XsdParseError {
+      return Err(SchemaError::Invalid(XsdParseError {
         node_name: element.node_name(),
         msg: "group | choice | sequence cannot all be present".to_string(),
       }));
@@ -80,6 +83,9 @@ impl ComplexType {
       attribute_groups,
       attributes,
       annotation: element.try_get_child_with("annotation", Annotation::parse)?,
+      r#abstract: element
+        .try_get_attribute::<String>("abstract")?
+        .is_some_and(|v| matches!(v.trim(), "true" | "1")),
     };
 
     element.finalize(false, false)?;
@@ -90,9 +96,9 @@ impl ComplexType {
   #[tracing::instrument(skip_all)]
   pub fn get_implementation(
     &self,
-    parent_is_schema: bool,
+    _parent_is_schema: bool,
     parent_name: Option<XsdName>,
-    context: &mut XsdContext,
+    context: &XsdContext,
   ) -> Result<XsdImpl, XsdError> {
     let struct_id = self
       .name
@@ -117,6 +123,8 @@ impl ComplexType {
       implementation: vec![],
       inner: vec![],
       flatten: false,
+      content: ContentKind::Empty,
+      attribute_groups: vec![],
     };
 
     let inner_impl = match (
@@ -138,6 +146,7 @@ impl ComplexType {
       }
       (None, None, None, None, Some(choice)) => Some(choice.get_implementation(xml_name, context)?),
       (None, None, None, None, None) => None,
+      // At most one of them is present, checked when parsing.
       _ => unreachable!("Xsd is invalid."),
     };
 
@@ -156,13 +165,7 @@ impl ComplexType {
     }
 
     for i in generated_impls {
-      generated_impl.merge(
-        i,
-        MergeSettings {
-          conflict_prefix: Some("attr_"),
-          merge_type: super::xsd_context::MergeType::Field,
-        },
-      );
+      generated_impl.merge(i, MergeSettings::ATTRIBUTE);
     }
 
     if let Some(docs) = &self.annotation {
@@ -171,6 +174,37 @@ impl ComplexType {
 
     generated_impl.name.ty = XsdType::ComplexType;
 
-    Ok(general_xsdgen(generated_impl))
+    Ok(general_xsdgen(generated_impl, context))
   }
 }
+
+#[test]
+fn empty_types_never_require_content() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="note">
+        <xs:sequence>
+          <xs:element name="chord" minOccurs="0"><xs:complexType/></xs:element>
+          <xs:element name="rest"><xs:complexType><xs:sequence/></xs:complexType></xs:element>
+        </xs:sequence>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap();
+
+  assert!(output.contains("pub struct Chord;"));
+  assert!(output.contains("pub struct Rest;"));
+  assert_eq!(
+    output
+      .matches("element.read_next_child(name, |_| Ok::<_, DocumentError>(()))?;")
+      .count(),
+    2
+  );
+  assert!(!output.contains("get_attribute::<String>(name)"));
+}
@@ -0,0 +1,159 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use xsd_codegen::{Block, Field, Fields, Function, Impl, Type};
+use xsd_types::{to_module_name, XsdName, XsdType};
+
+use super::xsd_context::{XsdContext, XsdImpl, XsdImplType};
+
+/// Add `From` conversions between the types of the top-level elements of `selected` when they
+/// are different structs holding the same fields, e.g. the partwise and timewise forms of a
+/// score. Elements sharing a named type already share their Rust type and get nothing.
+pub(crate) fn add_conversions(context: &mut XsdContext, selected: &BTreeSet<XsdName>) {
+  // The emitted structs, by the name of their Rust type.
+  let structs: BTreeMap<String, &XsdName> = context
+    .structs
+    .iter()
+    .filter(|(name, _)| selected.contains(name))
+    .filter_map(|(name, value)| match &value.element {
+      XsdImplType::Struct(ty) => Some((ty.ty().name.clone(), name)),
+      _ => None,
+    })
+    .collect();
+
+  // The structs the top-level elements are read into, a type reference is followed to the
+  // struct of the referenced type.
+  let targets: BTreeSet<&XsdName> = context
+    .structs
+    .iter()
+    .filter(|(name, _)| name.ty == XsdType::Element && selected.contains(name))
+    .filter_map(|(_, value)| value.element.try_get_type())
+    .filter_map(|ty| structs.get(&ty.name).copied())
+    .collect();
+
+  let fields = |name: &XsdName| match &context.structs[name].element {
+    XsdImplType::Struct(ty) => match &ty.fields {
+      Fields::Named(fields) if !fields.is_empty() => Some((ty.ty().clone(), fields.clone())),
+      _ => None,
+    },
+    _ => None,
+  };
+  let targets: Vec<_> = targets
+    .into_iter()
+    .filter_map(|name| Some((name.clone(), fields(name)?)))
+    .collect();
+
+  let mut components = BTreeMap::new();
+  for imp in context.structs.values() {
+    index_components(imp, "", &mut components);
+  }
+
+  for (name, (ty, fields)) in &targets {
+    for (other_name, (other_ty, other_fields)) in &targets {
+      if name == other_name || !same_fields(fields, other_fields, &components) {
+        continue;
+      }
+
+      tracing::debug!("Convert {} into {}", other_ty.name, ty.name);
+      let conversion = from_impl(ty, other_ty, fields);
+      context
+        .structs
+        .get_mut(name)
+        .unwrap()
+        .implementation
+        .push(conversion);
+    }
+  }
+}
+
+/// Index the component each type defined by `imp` and its inner types is generated from, by the
+/// path of the type from the top of the generated code.
+fn index_components(imp: &XsdImpl, prefix: &str, components: &mut BTreeMap<String, XsdName>) {
+  let ty = imp.element.get_type();
+  // A type reference defines nothing.
+  if !matches!(imp.element, XsdImplType::Type(_)) {
+    components.insert(format!("{prefix}{}", ty.name), imp.name.clone());
+  }
+
+  let prefix = format!("{prefix}{}::", to_module_name(&ty.name));
+  for inner in &imp.inner {
+    index_components(inner, &prefix, components);
+  }
+}
+
+/// Whether the fields have the same names and hold the same types: the types generated from the
+/// same component, the others, e.g. the builtins, named the same.
+fn same_fields(fields: &[Field], other: &[Field], components: &BTreeMap<String, XsdName>) -> bool {
+  fields.len() == other.len()
+    && fields
+      .iter()
+      .zip(other)
+      .all(|(field, other)| field.name == other.name && same_type(&field.ty, &other.ty, components))
+}
+
+fn same_type(ty: &Type, other: &Type, components: &BTreeMap<String, XsdName>) -> bool {
+  let same_component = match (components.get(&ty.name), components.get(&other.name)) {
+    (Some(component), Some(other)) => component == other,
+    (None, None) => ty.name == other.name,
+    _ => false,
+  };
+
+  same_component
+    && ty.const_generics == other.const_generics
+    && ty.generics.len() == other.generics.len()
+    && ty
+      .generics
+      .iter()
+      .zip(&other.generics)
+      .all(|(ty, other)| same_type(ty, other, components))
+}
+
+fn from_impl(ty: &Type, from: &Type, fields: &[Field]) -> Impl {
+  let mut body = Block::new("Self");
+  for field in fields {
+    body = body.line(format!("{0}: value.{0},", field.name()));
+  }
+
+  Impl::new(ty).impl_trait(format!("From<{from}>")).push_fn(
+    Function::new("from")
+      .arg("value", from)
+      .ret("Self")
+      .push_block(body),
+  )
+}
+
+#[test]
+fn fields_of_the_same_components_convert() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="title"><xs:attribute name="lang" type="xs:string"/></xs:complexType>
+      <xs:complexType name="heading"><xs:attribute name="lang" type="xs:string"/></xs:complexType>
+      <xs:element name="opus">
+        <xs:complexType>
+          <xs:sequence><xs:element name="title" type="title"/></xs:sequence>
+        </xs:complexType>
+      </xs:element>
+      <xs:element name="score">
+        <xs:complexType>
+          <xs:sequence><xs:element name="title" type="title"/></xs:sequence>
+        </xs:complexType>
+      </xs:element>
+      <xs:element name="movement">
+        <xs:complexType>
+          <xs:sequence><xs:element name="title" type="heading"/></xs:sequence>
+        </xs:complexType>
+      </xs:element>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap();
+
+  assert!(output.contains("impl From<Opus> for Score {"));
+  assert!(output.contains("impl From<Score> for Opus {"));
+  assert!(!output.contains("impl From<Movement>"));
+  assert!(!output.contains("> for Movement {"));
+}
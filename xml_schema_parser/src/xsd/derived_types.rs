@@ -0,0 +1,147 @@
+use xsd_codegen::{
+  escape_str_literal, xsdgen_impl, Block, ContentKind, Enum, Impl, Provenance, Variant,
+};
+use xsd_types::{to_struct_name, to_variant_name, XsdName, XsdType};
+
+use super::{
+  xsd_context::{XsdContext, XsdImpl, XsdImplType},
+  XsdError,
+};
+
+/// The name of the enum of the types derived from `base`.
+pub(crate) fn enum_name(base: &XsdName) -> XsdName {
+  XsdName {
+    ty: XsdType::DerivedTypes,
+    ..base.clone()
+  }
+}
+
+/// The enum read in place of the complex type `base` when other types derive from it, with a
+/// variant per type picked from the `xsi:type` of the element. `INSTANCE_TYPES` lists the
+/// `xsi:type` it reads.
+pub(crate) fn get_implementation(
+  base: &XsdName,
+  context: &XsdContext,
+) -> Result<XsdImpl, XsdError> {
+  let name = enum_name(base);
+  let types = context.derived_types.substitutes(base);
+
+  let mut r#enum = Enum::new(None, &to_struct_name(&format!("any-{}", base.local_name)))
+    .derives(&["Clone", "Debug", "PartialEq"])
+    .vis("pub");
+  r#enum.doc(&format!(
+    "`{}` or a type derived from it, named by the `xsi:type` of the element.",
+    base.local_name
+  ));
+
+  let mut dispatch = Block::new("match element.instance_type(name)");
+  for ty in &types {
+    let imp = context
+      .search(ty)
+      .ok_or_else(|| XsdError::XsdImplNotFound(ty.clone()))?;
+    let rust_type = imp.element.get_type();
+    let variant = to_variant_name(&ty.local_name);
+
+    let pattern = if ty == base {
+      format!("None | Some({})", escape_str_literal(&ty.local_name))
+    } else {
+      format!("Some({})", escape_str_literal(&ty.local_name))
+    };
+    dispatch = dispatch.line(format!(
+      "{pattern} => Ok(Self::{variant}(<{rust_type} as XsdGen>::gen(element, gen_state, name)?)),"
+    ));
+
+    r#enum = r#enum.push_variant(
+      Variant::new(None, &variant)
+        .tuple(None, rust_type, false, false)
+        .provenance(Provenance::new(ty.clone())),
+    );
+  }
+
+  // Without an `xsi:type` the element is of its declared type, unless that type is abstract.
+  if !types.contains(base) {
+    dispatch = dispatch.line(format!(
+      "None => Err(XsdGenError {{ ty: XsdType::Unknown, node_name: element.name().to_string(), msg: \"The type {} is abstract, expected an xsi:type.\".to_string() }}.into()),",
+      base.local_name.escape_default()
+    ));
+  }
+  dispatch = dispatch.line(
+    "Some(ty) => Err(XsdGenError { ty: XsdType::Unknown, node_name: element.name().to_string(), msg: format!(\"Expected an xsi:type of {}, found {ty}.\", Self::INSTANCE_TYPES.join(\", \")) }.into()),",
+  );
+
+  let ty = r#enum.ty().clone();
+  let instance_types = types
+    .iter()
+    .map(|ty| escape_str_literal(&ty.local_name))
+    .collect::<Vec<_>>();
+  let mut registry = Impl::new(ty.clone());
+  registry.associate_const_with_vis(
+    "pub",
+    "INSTANCE_TYPES",
+    "&'static [&'static str]",
+    &format!("&[{}]", instance_types.join(", ")),
+  );
+
+  Ok(XsdImpl {
+    fieldname_hint: Some(base.to_field_name()),
+    name,
+    element: XsdImplType::Enum(r#enum),
+    inner: vec![],
+    implementation: vec![
+      registry,
+      xsdgen_impl(ty, Block::new("").push_block(dispatch), false, true),
+    ],
+    flatten: false,
+    content: ContentKind::Complex,
+    attribute_groups: vec![],
+  })
+}
+
+#[test]
+fn derived_types_dispatch_on_the_instance_type() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="shape" abstract="true">
+        <xs:attribute name="color" type="xs:string" />
+      </xs:complexType>
+      <xs:complexType name="circle">
+        <xs:complexContent>
+          <xs:extension base="shape">
+            <xs:attribute name="radius" type="xs:double" />
+          </xs:extension>
+        </xs:complexContent>
+      </xs:complexType>
+      <xs:complexType name="dot">
+        <xs:complexContent>
+          <xs:restriction base="circle" />
+        </xs:complexContent>
+      </xs:complexType>
+      <xs:complexType name="drawing">
+        <xs:sequence>
+          <xs:element name="shape" type="shape" maxOccurs="unbounded" />
+          <xs:element name="circle" type="circle" />
+        </xs:sequence>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap();
+
+  // The abstract type isn't a variant, the types derived from a derived type are.
+  assert!(output.contains("pub enum AnyShape {\n    Circle(Circle),\n    Dot(Dot),\n}"));
+  assert!(
+    output.contains("pub const INSTANCE_TYPES: &'static [&'static str] = &[\"circle\", \"dot\"];")
+  );
+  assert!(output.contains("None => Err(XsdGenError {"));
+  assert!(output.contains("The type shape is abstract, expected an xsi:type."));
+  assert!(output.contains("None | Some(\"circle\") => Ok(Self::Circle("));
+
+  // The elements of a type with derived types read any of them.
+  assert!(output.contains("pub shape: RestrictedVec<AnyShape, 1, 0>,"));
+  assert!(output.contains("pub circle: AnyCircle,"));
+}
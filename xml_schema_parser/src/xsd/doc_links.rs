@@ -0,0 +1,210 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use xsd_codegen::{Fields, Item, Scope, Type};
+
+/// The path of an item from the top-level scope, e.g. `["xlink", "Href"]`.
+pub(super) type Path = Vec<String>;
+
+/// The generated types of a scope, and the modules each module imports with a glob.
+#[derive(Default)]
+pub(super) struct Types {
+  pub(super) paths: BTreeSet<Path>,
+  /// The paths of the types by their name.
+  by_name: BTreeMap<String, Vec<Path>>,
+  globs: BTreeMap<Path, Vec<Path>>,
+}
+
+impl Types {
+  pub(super) fn collect(&mut self, scope: &Scope, module: &[String]) {
+    // `use super::*;` and the `pub use {namespace}::*;` of the namespace modules.
+    let mut globs: Vec<Path> = scope
+      .imports
+      .iter()
+      .filter(|(_, imports)| imports.contains_key("*"))
+      .filter_map(|(path, _)| relative(module, path))
+      .collect();
+
+    for item in &scope.items {
+      let name = match item {
+        Item::Struct(r#struct) => r#struct.ty().name.clone(),
+        Item::Enum(r#enum) => r#enum.ty().name.clone(),
+        Item::Trait(r#trait) => r#trait.ty().name.clone(),
+        Item::TypeAlias(alias) => alias.alias.name.clone(),
+        Item::Module(inner) => {
+          let mut path = module.to_vec();
+          path.push(inner.name.clone());
+          self.collect(&inner.scope, &path);
+          continue;
+        }
+        Item::Raw(raw) => {
+          let reexported = raw
+            .strip_prefix("pub use ")
+            .and_then(|raw| raw.strip_suffix("::*;"));
+          globs.extend(reexported.and_then(|name| relative(module, name)));
+          continue;
+        }
+        Item::Function(_) | Item::Impl(_) => continue,
+      };
+
+      let mut path = module.to_vec();
+      path.push(name.clone());
+      self.by_name.entry(name).or_default().push(path.clone());
+      self.paths.insert(path);
+    }
+    self.globs.insert(module.to_vec(), globs);
+  }
+
+  /// The generated type `name` refers to in `module`, looked up like the generated code does.
+  pub(super) fn resolve(&self, module: &[String], name: &str) -> Option<Path> {
+    self.resolve_in(module, name, &mut BTreeSet::new())
+  }
+
+  fn resolve_in(
+    &self,
+    module: &[String],
+    name: &str,
+    visited: &mut BTreeSet<Path>,
+  ) -> Option<Path> {
+    if !visited.insert(module.to_vec()) {
+      return None;
+    }
+
+    let path = relative(module, name)?;
+    if self.paths.contains(&path) {
+      return Some(path);
+    }
+    self
+      .globs
+      .get(module)?
+      .iter()
+      .find_map(|glob| self.resolve_in(glob, name, visited))
+  }
+}
+
+/// The path of `name` written in `module`, its leading `super` segments going up.
+fn relative(module: &[String], name: &str) -> Option<Path> {
+  let mut path = module.to_vec();
+  for segment in name.split("::") {
+    match segment {
+      "super" => {
+        path.pop()?;
+      }
+      "self" => {}
+      segment => path.push(segment.to_string()),
+    }
+  }
+  Some(path)
+}
+
+/// The path naming `target` in `module`, going up with `super` as far as needed.
+pub(super) fn link_path(module: &[String], target: &[String]) -> String {
+  let common = module
+    .iter()
+    .zip(target)
+    .take_while(|(left, right)| left == right)
+    .count()
+    .min(target.len() - 1);
+  std::iter::repeat_n("super", module.len() - common)
+    .chain(target[common..].iter().map(String::as_str))
+    .collect::<Vec<_>>()
+    .join("::")
+}
+
+/// The type held by a field, through its `Option`, `Vec` and the like.
+fn held_type(ty: &Type) -> &Type {
+  match (ty.name.as_str(), ty.generics.as_slice()) {
+    ("Option" | "Vec" | "RestrictedVec" | "Box", [inner]) => held_type(inner),
+    _ => ty,
+  }
+}
+
+/// `text` of the schema annotations, in `module`, with its brackets escaped and the names of the
+/// generated types it mentions linked. The names in code spans are left as is.
+fn link_mentions(text: &str, module: &[String], types: &Types) -> String {
+  let link = |word: &str| match types.by_name.get(word).map(Vec::as_slice) {
+    // Only the names of a single type are linked, by their path when the name doesn't resolve.
+    Some([target]) => match types.resolve(module, word) {
+      Some(resolved) if resolved == *target => format!("[{word}]"),
+      _ => format!("[{word}]({})", link_path(module, target)),
+    },
+    _ => word.to_string(),
+  };
+
+  let mut linked = String::with_capacity(text.len());
+  let mut word = String::new();
+  let mut in_code = false;
+  for c in text.chars() {
+    if c.is_alphanumeric() || c == '_' {
+      word.push(c);
+      continue;
+    }
+
+    if in_code {
+      linked.push_str(&word);
+    } else {
+      linked.push_str(&link(&word));
+    }
+    word.clear();
+    match c {
+      '`' => in_code = !in_code,
+      '[' | ']' if !in_code => linked.push('\\'),
+      _ => {}
+    }
+    linked.push(c);
+  }
+  if in_code {
+    linked.push_str(&word);
+  } else {
+    linked.push_str(&link(&word));
+  }
+
+  linked
+}
+
+fn add_links(scope: &mut Scope, module: &[String], types: &Types) {
+  let link = |text: &mut String| *text = link_mentions(text, module, types);
+
+  for item in &mut scope.items {
+    match item {
+      Item::Struct(r#struct) => {
+        if let Some(docs) = &mut r#struct.type_def.docs {
+          link(&mut docs.docs);
+        }
+        let Fields::Named(fields) = &mut r#struct.fields else {
+          continue;
+        };
+        for field in fields {
+          field.documentation.iter_mut().for_each(link);
+          if let Some(target) = types.resolve(module, &held_type(&field.ty).name) {
+            field
+              .documentation
+              .push(format!("Type: [`{}`]", link_path(module, &target)));
+          }
+        }
+      }
+      Item::Enum(r#enum) => {
+        if let Some(docs) = &mut r#enum.type_def.docs {
+          link(&mut docs.docs);
+        }
+        for variant in &mut r#enum.variants {
+          variant.doc.iter_mut().for_each(link);
+        }
+      }
+      Item::TypeAlias(alias) => alias.doc.iter_mut().for_each(link),
+      Item::Module(inner) => {
+        let mut path = module.to_vec();
+        path.push(inner.name.clone());
+        add_links(&mut inner.scope, &path, types);
+      }
+      _ => {}
+    }
+  }
+}
+
+/// Link the generated types from the docs of `scope`, see `GeneratorOptions::doc_links`. Each
+/// field holding a generated type documents it with a `Type:` line.
+pub(crate) fn add_doc_links(scope: &mut Scope) {
+  let mut types = Types::default();
+  types.collect(scope, &[]);
+  add_links(scope, &[], &types);
+}
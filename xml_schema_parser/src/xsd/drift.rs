@@ -0,0 +1,247 @@
+use std::{
+  collections::BTreeMap,
+  fmt, fs,
+  path::{Path, PathBuf},
+};
+
+use super::{GeneratorOptions, XsdError};
+
+const HEADER_PREFIX: &str = "// @generated by xml-schema-parser ";
+
+/// The first line of the generated code, naming the version of the generator and the
+/// fingerprint of its options. It holds no timestamp, generating twice gives the same code.
+pub(crate) fn header(options: &GeneratorOptions) -> String {
+  format!(
+    "{HEADER_PREFIX}{} with options {}, do not edit.",
+    env!("CARGO_PKG_VERSION"),
+    options.fingerprint()
+  )
+}
+
+/// The version of the generator and the fingerprint of the options of a `header` line.
+fn parse_header(line: &str) -> Option<(&str, &str)> {
+  line
+    .strip_prefix(HEADER_PREFIX)?
+    .strip_suffix(", do not edit.")?
+    .split_once(" with options ")
+}
+
+/// The top-level items of `code`, by the first line of each that is neither an attribute nor a
+/// comment, e.g. `impl XsdGen for Note {`. The blank lines between the items are left out.
+fn items(code: &str) -> Vec<(String, String)> {
+  let mut items = vec![];
+  let mut text = String::new();
+  let mut name = None;
+  for line in code.lines() {
+    if text.is_empty() && line.trim().is_empty() {
+      continue;
+    }
+    text.push_str(line);
+    text.push('\n');
+
+    let top_level = !line.starts_with(char::is_whitespace);
+    let annotation = line.starts_with('#') || line.starts_with("//");
+    if !top_level || annotation {
+      continue;
+    }
+    if name.is_none() {
+      name = Some(line.to_string());
+    }
+    if line.ends_with('}') || line.ends_with(';') {
+      items.push((name.take().unwrap_or_default(), std::mem::take(&mut text)));
+    }
+  }
+  if !text.is_empty() {
+    items.push((name.unwrap_or_default(), text));
+  }
+  items
+}
+
+/// How a top-level item of the generated code differs from the file on disk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemChange {
+  /// Only generated now.
+  Added,
+  /// Only in the file on disk.
+  Removed,
+  Changed,
+}
+
+impl ItemChange {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ItemChange::Added => "added",
+      ItemChange::Removed => "removed",
+      ItemChange::Changed => "changed",
+    }
+  }
+}
+
+/// A top-level item of the generated code differing from the file on disk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DriftedItem {
+  /// The first line of the item that is neither an attribute nor a comment, e.g.
+  /// `pub struct Note {`.
+  pub item: String,
+  pub change: ItemChange,
+}
+
+/// Why the file on disk differs from the generated code, as told by its header.
+#[derive(Debug)]
+pub enum DriftCause {
+  /// The file could not be read.
+  Unreadable(std::io::Error),
+  /// The schema could not be generated.
+  Generation(Box<XsdError>),
+  /// The file was generated by another version of the generator, `None` when it has no header.
+  Generator { version: Option<String> },
+  /// The file was generated with other options, whose fingerprint is given.
+  Options { fingerprint: String },
+  /// The file was generated by the same generator with the same options, the schema changed.
+  Schema,
+}
+
+/// The differences between a file of generated code and the code generated now, see
+/// `Xsd::check_generated`.
+#[derive(Debug)]
+pub struct DriftReport {
+  pub path: PathBuf,
+  pub cause: DriftCause,
+  /// The items differing, in the order of the generated code then of the file. Empty when the
+  /// file couldn't be compared.
+  pub items: Vec<DriftedItem>,
+}
+
+impl fmt::Display for DriftReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let path = self.path.display();
+    match &self.cause {
+      DriftCause::Unreadable(error) => return write!(f, "{path} could not be read: {error}"),
+      DriftCause::Generation(error) => {
+        return write!(f, "{path} could not be regenerated: {error}")
+      }
+      DriftCause::Generator {
+        version: Some(version),
+      } => write!(
+        f,
+        "{path} was generated by xml-schema-parser {version}, not {}",
+        env!("CARGO_PKG_VERSION")
+      )?,
+      DriftCause::Generator { version: None } => {
+        write!(f, "{path} has no header naming its generator")?
+      }
+      DriftCause::Options { fingerprint } => write!(
+        f,
+        "{path} was generated with other options (fingerprint {fingerprint})"
+      )?,
+      DriftCause::Schema => write!(f, "{path} is out of date with the schema")?,
+    }
+    for item in &self.items {
+      write!(f, "\n  {}: {}", item.change.as_str(), item.item)?;
+    }
+    Ok(())
+  }
+}
+
+impl std::error::Error for DriftReport {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match &self.cause {
+      DriftCause::Unreadable(error) => Some(error),
+      DriftCause::Generation(error) => Some(error.as_ref()),
+      _ => None,
+    }
+  }
+}
+
+/// Compare the code at `path` with `generated`, generated with `options`. The header lines are
+/// only compared to tell the cause of the differences.
+pub(crate) fn check(
+  path: &Path,
+  generated: Result<String, XsdError>,
+  options: &GeneratorOptions,
+) -> Result<(), DriftReport> {
+  let report = |cause, items| DriftReport {
+    path: path.to_path_buf(),
+    cause,
+    items,
+  };
+  let generated =
+    generated.map_err(|error| report(DriftCause::Generation(Box::new(error)), vec![]))?;
+  let on_disk =
+    fs::read_to_string(path).map_err(|error| report(DriftCause::Unreadable(error), vec![]))?;
+
+  let (header, on_disk) = split_header(&on_disk);
+  let (_, generated) = split_header(&generated);
+  let items = diff(&items(on_disk), &items(generated));
+  if items.is_empty() {
+    return Ok(());
+  }
+
+  let cause = match header {
+    None => DriftCause::Generator { version: None },
+    Some((version, _)) if version != env!("CARGO_PKG_VERSION") => DriftCause::Generator {
+      version: Some(version.to_string()),
+    },
+    Some((_, fingerprint)) if fingerprint != options.fingerprint() => DriftCause::Options {
+      fingerprint: fingerprint.to_string(),
+    },
+    Some(_) => DriftCause::Schema,
+  };
+  Err(report(cause, items))
+}
+
+/// The version and the options fingerprint of the header of `code`, and the code after it.
+fn split_header(code: &str) -> (Option<(&str, &str)>, &str) {
+  let (first, rest) = code.split_once('\n').unwrap_or((code, ""));
+  // The file may have been checked out with CRLF line endings.
+  match parse_header(first.trim_end_matches('\r')) {
+    Some(header) => (Some(header), rest),
+    None => (None, code),
+  }
+}
+
+/// The items of `generated` that differ from the ones of `on_disk`, matched by name and then
+/// by their order among the items of the same name.
+fn diff(on_disk: &[(String, String)], generated: &[(String, String)]) -> Vec<DriftedItem> {
+  fn by_name(items: &[(String, String)]) -> BTreeMap<&str, Vec<&str>> {
+    let mut by_name: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (name, text) in items {
+      by_name.entry(name).or_default().push(text);
+    }
+    by_name
+  }
+  let on_disk_by_name = by_name(on_disk);
+  let generated_by_name = by_name(generated);
+
+  let mut drifted = vec![];
+  let mut seen: BTreeMap<&str, usize> = BTreeMap::new();
+  for (name, text) in generated {
+    let index = seen.entry(name).or_default();
+    let change = match on_disk_by_name
+      .get(name.as_str())
+      .and_then(|texts| texts.get(*index))
+    {
+      None => Some(ItemChange::Added),
+      Some(on_disk) if on_disk != text => Some(ItemChange::Changed),
+      Some(_) => None,
+    };
+    *index += 1;
+    drifted.extend(change.map(|change| DriftedItem {
+      item: name.clone(),
+      change,
+    }));
+  }
+
+  let mut seen: BTreeMap<&str, usize> = BTreeMap::new();
+  for (name, _) in on_disk {
+    let index = seen.entry(name).or_default();
+    if generated_by_name.get(name.as_str()).map_or(0, Vec::len) <= *index {
+      drifted.push(DriftedItem {
+        item: name.clone(),
+        change: ItemChange::Removed,
+      });
+    }
+    *index += 1;
+  }
+  drifted
+}
@@ -0,0 +1,274 @@
+use xsd_codegen::{escape_str_literal, Block, ContentKind, Field, Fields, Function, Impl, Type};
+
+use super::{
+  serde_attributes::xml_name,
+  xsd_context::{XsdImpl, XsdImplType},
+};
+
+/// Convert `imp` and its inner types to and from `DynamicValue`: `From<&T> for DynamicValue`
+/// and `ToDynamic` build the value, `TryFrom<&DynamicValue>` and `FromDynamic` read it back.
+/// Structs are maps of their fields by XML name, choices maps of their single alternative, and
+/// enumerations and fixed values their text.
+pub(crate) fn add_dynamic_conversions(imp: &mut XsdImpl) {
+  for inner in &mut imp.inner {
+    add_dynamic_conversions(inner);
+  }
+
+  let ty = imp.element.get_type();
+  let name = escape_str_literal(&ty.name);
+  let mut to = Function::new("from").ret("Self");
+  let mut from = Function::new("try_from")
+    .arg("value", Type::new(None, "&DynamicValue"))
+    .ret("Result<Self, DocumentError>");
+
+  match &imp.element {
+    // A marker of a fixed value, see `fixed_value::marker_impl`.
+    XsdImplType::Struct(r#struct)
+      if r#struct.fields == Fields::Empty && imp.content == ContentKind::Simple =>
+    {
+      to = to
+        .arg("_value", Type::new(None, &format!("&{ty}")))
+        .line(format!(
+          "DynamicValue::Text(<{ty} as FixedValue>::VALUE.to_string())"
+        ));
+      from = from.line(format!("value.parse_text({name})"));
+    }
+    XsdImplType::Struct(r#struct) => {
+      let fields = r#struct.fields.fields();
+      let value = if fields.is_empty() { "_value" } else { "value" };
+      to = to.arg(value, Type::new(None, &format!("&{ty}")));
+      to = match &r#struct.fields {
+        Fields::Tuple(fields) if fields.len() == 1 => to.line("value.0.to_dynamic()"),
+        _ => entries(&r#struct.fields, "value.")
+          .into_iter()
+          .fold(to.line("DynamicValue::map(["), |to, entry| {
+            to.line(format!("    {entry},"))
+          })
+          .line("])"),
+      };
+
+      from = match &r#struct.fields {
+        Fields::Tuple(fields) if fields.len() == 1 => {
+          from.line(format!("Ok(Self({}?))", getter(&fields[0], None)))
+        }
+        _ if fields.is_empty() => from
+          .line(format!("value.fields({name})?;"))
+          .line("Ok(Self {})"),
+        fields => from
+          .line(format!("let fields = value.fields({name})?;"))
+          .push_block(read_fields(fields, "Self")),
+      };
+    }
+    XsdImplType::Enum(r#enum) if r#enum.variants.iter().all(|v| v.xml_value.is_some()) => {
+      let text = r#enum
+        .variants
+        .iter()
+        .fold(Block::new("let text = match value"), |text, variant| {
+          text.line(format!(
+            "{ty}::{} => {},",
+            variant.name,
+            escape_str_literal(variant.xml_value.as_deref().unwrap_or_default())
+          ))
+        })
+        .after(";");
+      to = to
+        .arg("value", Type::new(None, &format!("&{ty}")))
+        .push_block(text)
+        .line("DynamicValue::Text(text.to_string())");
+      from = from.line(format!("value.parse_text({name})"));
+    }
+    XsdImplType::Enum(r#enum) => {
+      let keys = keys(
+        r#enum.variants.iter().map(|variant| {
+          variant
+            .xml_name
+            .as_ref()
+            .map(|name| name.local_name.clone())
+            .or_else(|| variant.xml_value.clone())
+        }),
+        r#enum.variants.iter().map(|variant| variant.name.clone()),
+      );
+
+      let mut build = Block::new("match value");
+      let mut read = Block::new("match name");
+      for (variant, key) in r#enum.variants.iter().zip(keys) {
+        let key = escape_str_literal(&key);
+        let (pattern, payload) = match &variant.fields {
+          Fields::Empty => (String::new(), "DynamicValue::Null".to_string()),
+          Fields::Tuple(fields) if fields.len() == 1 => {
+            ("(value)".to_string(), "value.to_dynamic()".to_string())
+          }
+          Fields::Tuple(fields) => (
+            format!(
+              "({})",
+              (0..fields.len())
+                .map(|index| format!("value{index}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+            ),
+            format!(
+              "DynamicValue::map([{}])",
+              entries(&variant.fields, "value").join(", ")
+            ),
+          ),
+          Fields::Named(fields) => (
+            format!(
+              " {{ {} }}",
+              fields
+                .iter()
+                .map(Field::name)
+                .collect::<Vec<_>>()
+                .join(", ")
+            ),
+            format!(
+              "DynamicValue::map([{}])",
+              entries(&variant.fields, "").join(", ")
+            ),
+          ),
+        };
+        build = build.line(format!(
+          "{ty}::{}{pattern} => DynamicValue::map([({key}, {payload})]),",
+          variant.name
+        ));
+
+        let path = format!("Self::{}", variant.name);
+        read = match &variant.fields {
+          Fields::Empty => read.line(format!("{key} => Ok({path}),")),
+          Fields::Tuple(fields) if fields.len() == 1 => read.line(format!(
+            "{key} => Ok({path}({}?)),",
+            getter(&fields[0], Some("name"))
+          )),
+          fields => read.push_block(
+            Block::new(&format!("{key} =>"))
+              .line("let fields = fields.fields(name)?;")
+              .push_block(read_fields(fields, &path)),
+          ),
+        };
+      }
+      read = read.line("name => Err(fields.unknown_variant(name)),");
+
+      to = to
+        .arg("value", Type::new(None, &format!("&{ty}")))
+        .push_block(build);
+      from = from
+        .line(format!("let (name, fields) = value.variant({name})?;"))
+        .push_block(read);
+    }
+    XsdImplType::Type(_) | XsdImplType::TypeAlias(_) => return,
+  }
+
+  imp.implementation.extend([
+    Impl::new(Type::new(None, "DynamicValue"))
+      .impl_trait(format!("From<&{ty}>"))
+      .push_fn(to),
+    Impl::new(&ty).impl_trait("ToDynamic").push_fn(
+      Function::new("to_dynamic")
+        .arg_ref_self()
+        .ret("DynamicValue")
+        .line("DynamicValue::from(self)"),
+    ),
+    {
+      let mut try_from = Impl::new(&ty)
+        .impl_trait("TryFrom<&DynamicValue>")
+        .push_fn(from);
+      try_from.associate_type(None, "Error", "DocumentError", false, false);
+      try_from
+    },
+    Impl::new(&ty).impl_trait("FromDynamic").push_fn(
+      Function::new("from_dynamic")
+        .arg("value", Type::new(None, "&DynamicValue"))
+        .ret("Result<Self, DocumentError>")
+        .line("Self::try_from(value)"),
+    ),
+  ]);
+}
+
+/// The entries of the map holding `fields`, e.g. `("part-name", value.part_name.to_dynamic())`.
+/// The fields are read from `{prefix}{name}`, or from `{prefix}{index}` for tuple fields.
+fn entries(fields: &Fields, prefix: &str) -> Vec<String> {
+  let keys = field_keys(fields);
+  fields
+    .fields()
+    .iter()
+    .enumerate()
+    .zip(keys)
+    .map(|((index, field), key)| {
+      let name = field.name.clone().unwrap_or_else(|| index.to_string());
+      format!(
+        "({}, {prefix}{name}.to_dynamic())",
+        escape_str_literal(&key)
+      )
+    })
+    .collect()
+}
+
+/// Build `path` out of the entries of `fields`, e.g. `Ok(Self { part_name: ... })`.
+fn read_fields(fields: &Fields, path: &str) -> Block {
+  let values = fields
+    .fields()
+    .iter()
+    .zip(field_keys(fields))
+    .map(|(field, key)| {
+      let value = getter(field, Some(&escape_str_literal(&key)));
+      match &field.name {
+        Some(name) => format!("{name}: {value}?,"),
+        None => format!("{value}?,"),
+      }
+    });
+
+  match fields {
+    Fields::Tuple(_) => values
+      .fold(Block::new(&format!("Ok({path}(")), Block::line)
+      .after("))"),
+    _ => values
+      .fold(Block::new(&format!("Ok({path}")), Block::line)
+      .after(")"),
+  }
+}
+
+/// The keys of the entries holding `fields`: the XML names of the fields, or their Rust names
+/// or indexes when they have none or share it with another field, e.g. an attribute and an
+/// element.
+fn field_keys(fields: &Fields) -> Vec<String> {
+  keys(
+    fields.fields().iter().map(xml_name),
+    fields
+      .fields()
+      .iter()
+      .enumerate()
+      .map(|(index, field)| match &field.name {
+        Some(name) => name.trim_start_matches("r#").to_string(),
+        None => index.to_string(),
+      }),
+  )
+}
+
+/// The expression reading `field` out of the entry `key` of `fields`, or out of `value` itself.
+/// Fixed values stored as `&'static str` are read as their marker.
+fn getter(field: &Field, key: Option<&str>) -> String {
+  match (&field.ty.parse_as, key) {
+    (Some(parse_as), Some(key)) => {
+      format!("fields.get::<{parse_as}>({key}).map(|_| <{parse_as} as FixedValue>::VALUE)")
+    }
+    (Some(parse_as), None) => format!(
+      "<{parse_as} as FromDynamic>::from_dynamic(value).map(|_| <{parse_as} as FixedValue>::VALUE)"
+    ),
+    (None, Some(key)) => format!("fields.get({key})"),
+    (None, None) => "FromDynamic::from_dynamic(value)".to_string(),
+  }
+}
+
+/// The keys of the entries: their preferred key, e.g. the XML name of a field, unless it is
+/// missing or shared with another entry, in which case the fallback, e.g. the Rust name.
+fn keys(
+  preferred: impl Iterator<Item = Option<String>>,
+  fallback: impl Iterator<Item = String>,
+) -> Vec<String> {
+  let preferred: Vec<Option<String>> = preferred.collect();
+  let unique = |key: &String| preferred.iter().flatten().filter(|k| *k == key).count() == 1;
+  preferred
+    .iter()
+    .zip(fallback)
+    .map(|(key, fallback)| key.clone().filter(unique).unwrap_or(fallback))
+    .collect()
+}
@@ -1,11 +1,14 @@
-use xsd_codegen::{Struct, XMLElement};
-use xsd_types::{XsdGenError, XsdIoError, XsdName, XsdParseError, XsdType};
+use xsd_codegen::{ContentKind, Struct, XMLElement};
+use xsd_types::{SchemaError, XsdGenError, XsdName, XsdParseError, XsdType};
 
 use crate::xsd::{
   annotation::Annotation,
   complex_type::ComplexType,
-  max_occurences::MaxOccurences,
+  derived_types,
+  identity_constraint::IdentityConstraint,
+  max_occurences::{self, MaxOccurences, ITEM, VALUE},
   simple_type::SimpleType,
+  substitution_group,
   xsd_context::{XsdImpl, XsdImplType},
   XsdContext, XsdError,
 };
@@ -18,18 +21,19 @@ pub struct Element {
   pub min_occurences: u64,
   pub r#final: Option<String>,
   pub block: Option<String>,
+  /// The head of the substitution group of a top-level element, see `Substitutions`.
+  pub substitution_group: Option<XsdName>,
+  pub r#abstract: bool,
 
   pub max_occurences: MaxOccurences,
   pub complex_type: Option<ComplexType>,
   pub simple_type: Option<SimpleType>,
   pub annotation: Option<Annotation>,
-  // pub uniques: Vec<String>,
-  // pub keys: Vec<String>,
-  // pub keyrefs: Vec<String>,
+  pub identity_constraints: Vec<IdentityConstraint>,
 }
 
 impl Element {
-  pub fn parse(mut element: XMLElement, parent_is_schema: bool) -> Result<Self, XsdIoError> {
+  pub fn parse(mut element: XMLElement, parent_is_schema: bool) -> Result<Self, SchemaError> {
     element.check_name("element")?;
 
     let name = element
@@ -37,20 +41,24 @@ impl Element {
       .map(|v: String| element.new_name(&v, XsdType::Element));
     let refers = element
       .try_get_attribute("ref")?
-      .map(|v: String| XsdName::new(&v, XsdType::Element));
+      .map(|v: String| element.new_qname(&v, XsdType::Element));
+
+    if name.is_some() && refers.is_some() {
+      return Err(SchemaError::Invalid(XsdParseError {
+        node_name: element.node_name(),
+        msg: "name and ref cannot both present".to_string(),
+      }));
+    }
 
     if parent_is_schema && name.is_none() {
-      return Err(XsdIoError::XsdParseError(XsdParseError {
+      return Err(SchemaError::Invalid(XsdParseError {
         node_name: element.node_name(),
         msg: "name attribute cannot be absent when parent is the schema tag.".to_string(),
       }));
-    } else if parent_is_schema && refers.is_some() {
-      return Err(XsdIoError::XsdParseError(XsdParseError {
+    } else if let Some(refers) = refers.as_ref().filter(|_| parent_is_schema) {
+      return Err(SchemaError::Invalid(XsdParseError {
         node_name: element.node_name(),
-        msg: format!(
-          "ref attribute ({}) cannot be present when parent is the schema tag.",
-          refers.unwrap()
-        ),
+        msg: format!("ref attribute ({refers}) cannot be present when parent is the schema tag."),
       }));
     }
 
@@ -59,7 +67,7 @@ impl Element {
       element.try_get_child_with("simpleType", |child| SimpleType::parse(child, false))?;
 
     if simple_type.is_some() && complex_type.is_some() {
-      return Err(XsdIoError::XsdParseError(XsdParseError {
+      return Err(SchemaError::Invalid(XsdParseError {
         node_name: element.node_name(),
         msg: "simpleType | complexType cannot both present".to_string(),
       }));
@@ -67,19 +75,34 @@ impl Element {
 
     let annotation = element.try_get_child_with("annotation", Annotation::parse)?;
 
+    let mut identity_constraints = vec![];
+    for kind in ["unique", "key", "keyref"] {
+      identity_constraints.extend(element.get_children_with(kind, IdentityConstraint::parse)?);
+    }
+
+    let (min_occurences, max_occurences) =
+      max_occurences::parse_occurences(&mut element, name.as_ref().or(refers.as_ref()))?;
+
     let output = Ok(Self {
       name,
       kind: element
         .try_get_attribute("type")?
-        .map(|v: String| XsdName::new(&v, XsdType::SimpleType)),
+        .map(|v: String| element.new_qname(&v, XsdType::SimpleType)),
       refers,
       r#final: element.try_get_attribute("final")?,
       block: element.try_get_attribute("block")?,
-      min_occurences: element.try_get_attribute("minOccurs")?.unwrap_or(1),
-      max_occurences: element.get_attribute_default("maxOccurs")?,
+      substitution_group: element
+        .try_get_attribute("substitutionGroup")?
+        .map(|v: String| element.new_qname(&v, XsdType::Element)),
+      r#abstract: element
+        .try_get_attribute::<String>("abstract")?
+        .is_some_and(|v| matches!(v.trim(), "true" | "1")),
+      min_occurences,
+      max_occurences,
       complex_type,
       simple_type,
       annotation,
+      identity_constraints,
     });
 
     element.finalize(false, false)?;
@@ -87,23 +110,62 @@ impl Element {
     output
   }
 
-  fn is_multiple(&self) -> bool {
-    (match &self.max_occurences {
-      MaxOccurences::Unbounded => true,
-      MaxOccurences::Number { value } => *value > 1,
-    }) || self.min_occurences > 1
-  }
+  #[tracing::instrument(skip_all)]
+  pub fn get_implementation(&self, context: &XsdContext) -> Result<XsdImpl, XsdError> {
+    // A reference reuses the type of the top-level element, but keeps the occurrence bounds
+    // of the reference site. The reference to the head of a substitution group reads any element
+    // of the group, its name is picked by the enum of the group.
+    let mut dispatch = false;
+    let (xml_name, referenced) = match (&self.name, &self.refers) {
+      // A member of a substitution group without a type has the type of its head.
+      (Some(name), _) => match &self.substitution_group {
+        Some(head)
+          if self.kind.is_none() && self.complex_type.is_none() && self.simple_type.is_none() =>
+        {
+          let head = context
+            .search(head)
+            .ok_or_else(|| XsdError::XsdImplNotFound(head.clone()))?;
+          (name.clone(), Some((head.element.get_type(), head.content)))
+        }
+        _ => (name.clone(), None),
+      },
+      (None, Some(refers)) if context.substitution_groups.is_substituted(refers) => {
+        let group = substitution_group::group_name(refers);
+        let group = context
+          .search(&group)
+          .ok_or(XsdError::XsdImplNotFound(group))?;
 
-  fn could_be_none(&self) -> bool {
-    (match &self.max_occurences {
-      MaxOccurences::Unbounded => false,
-      MaxOccurences::Number { value } => *value == 1,
-    }) && self.min_occurences == 0
-  }
+        dispatch = true;
+        let name = XsdName {
+          ty: XsdType::Element,
+          ..refers.clone()
+        };
+        (name, Some((group.element.get_type(), group.content)))
+      }
+      (None, Some(refers)) => {
+        let referenced = context
+          .search(refers)
+          .ok_or_else(|| XsdError::XsdImplNotFound(refers.clone()))?;
 
-  #[tracing::instrument(skip_all)]
-  pub fn get_implementation(&self, context: &mut XsdContext) -> Result<XsdImpl, XsdError> {
-    let xml_name = self.name.clone().unwrap();
+        let name = XsdName {
+          ty: XsdType::Element,
+          ..referenced.name.clone()
+        };
+        (
+          name,
+          Some((referenced.element.get_type(), referenced.content)),
+        )
+      }
+      (None, None) => {
+        return Err(XsdError::SchemaError(SchemaError::Unsupported(
+          XsdGenError {
+            node_name: "element".to_string(),
+            ty: XsdType::Element,
+            msg: "Element has neither a name nor a ref.".to_string(),
+          },
+        )))
+      }
+    };
 
     let mut generated_struct = match (&self.simple_type, &self.complex_type, &self.kind) {
       (None, Some(complex_type), None) => {
@@ -120,6 +182,20 @@ impl Element {
         );
         match imp {
           super::xsd_context::SearchResult::SingleMatch(imp) => {
+            // An element of a type others derive from is of any of them, see `derived_types`.
+            let base = XsdName {
+              ty: XsdType::ComplexType,
+              ..kind.clone()
+            };
+            let imp = if context.derived_types.is_substituted(&base) {
+              let name = derived_types::enum_name(&base);
+              context
+                .search(&name)
+                .ok_or(XsdError::XsdImplNotFound(name))?
+            } else {
+              imp
+            };
+
             let mut ty = imp.element.get_type();
             ty.xml_name = Some(xml_name.clone());
             XsdImpl {
@@ -129,41 +205,56 @@ impl Element {
               inner: vec![],
               implementation: vec![],
               flatten: false,
+              content: imp.content,
+              attribute_groups: vec![],
             }
           }
-          super::xsd_context::SearchResult::MultipleMatches => {
-            return Err(XsdError::XsdIoError(XsdIoError::XsdGenError(XsdGenError {
-              node_name: xml_name.to_string(),
-              ty: XsdType::Element,
-              msg: format!(
-                "Found both a simple and complex type named {}",
-                self.kind.as_ref().unwrap()
-              ),
-            })));
+          super::xsd_context::SearchResult::MultipleMatches(candidates) => {
+            return Err(XsdError::ContextSearchError {
+              name: kind.clone(),
+              candidates,
+              msg: format!("found both a simple and complex type for element {xml_name}"),
+            });
           }
           super::xsd_context::SearchResult::NoMatches => {
             return Err(XsdError::XsdImplNotFound(xml_name));
           }
         }
       }
-      (None, None, None) => {
-        return Ok(XsdImpl {
+      (None, None, None) => match referenced {
+        Some((ty, content)) => XsdImpl {
           name: xml_name.clone(),
           fieldname_hint: Some(xml_name.to_field_name()),
-          element: XsdImplType::Struct(
-            Struct::new(Some(xml_name.clone()), &xml_name.to_struct_name()).vis("pub"),
-          ),
+          element: XsdImplType::Type(ty.xml_name((!dispatch).then(|| xml_name.clone()))),
           inner: vec![],
           implementation: vec![],
-          flatten: false,
-        });
-      }
+          flatten: dispatch,
+          content,
+          attribute_groups: vec![],
+        },
+        None => {
+          return Ok(XsdImpl {
+            name: xml_name.clone(),
+            fieldname_hint: Some(xml_name.to_field_name()),
+            element: XsdImplType::Struct(
+              Struct::new(Some(xml_name.clone()), &xml_name.to_struct_name()).vis("pub"),
+            ),
+            inner: vec![],
+            implementation: vec![],
+            flatten: false,
+            content: ContentKind::Empty,
+            attribute_groups: vec![],
+          });
+        }
+      },
       _ => {
-        return Err(XsdError::XsdIoError(XsdIoError::XsdGenError(XsdGenError {
-          node_name: xml_name.to_string(),
-          ty: XsdType::Element,
-          msg: "Found both simple and complex type in element.".to_string(),
-        })))
+        return Err(XsdError::SchemaError(SchemaError::Unsupported(
+          XsdGenError {
+            node_name: xml_name.to_string(),
+            ty: XsdType::Element,
+            msg: "Found both simple and complex type in element.".to_string(),
+          },
+        )))
       }
     };
 
@@ -173,28 +264,19 @@ impl Element {
         .add_doc(&annotation.get_doc().join("\n"));
     }
 
-    let mut generated_struct = if self.is_multiple() || self.could_be_none() {
+    let max_occurences = self.max_occurences.limited(context.max_occurs_limit);
+    let role = max_occurences.role(self.min_occurences);
+    let mut generated_struct = if role.is_some() {
       let field_name = xml_name.to_field_name();
       let field_type = generated_struct.element.get_type();
 
-      let field_type = if self.is_multiple() {
-        if self.min_occurences > 0 || self.max_occurences != MaxOccurences::Unbounded {
-          field_type
-            .wrap("RestrictedVec")
-            .generic(self.min_occurences.to_string())
-            .generic(match self.max_occurences {
-              MaxOccurences::Unbounded => "0".to_string(),
-              MaxOccurences::Number { value } => value.to_string(),
-            })
-        } else {
-          field_type.wrap("Vec")
-        }
-      } else if self.could_be_none() {
-        field_type.wrap("Option")
-      } else {
-        field_type
+      let field_type = match role {
+        Some(ITEM) => max_occurences.wrap_repeated(field_type, self.min_occurences),
+        Some(VALUE) => field_type.wrap("Option").occurs(0, Some(1)),
+        _ => field_type,
       };
 
+      let content = generated_struct.content;
       let inner = if let XsdImplType::Struct(_) | XsdImplType::Enum(_) = generated_struct.element {
         vec![generated_struct]
       } else {
@@ -207,7 +289,9 @@ impl Element {
         element: XsdImplType::Type(field_type),
         inner,
         implementation: vec![],
-        flatten: false,
+        flatten: dispatch,
+        content,
+        attribute_groups: vec![],
       }
     } else {
       generated_struct
@@ -218,3 +302,33 @@ impl Element {
     Ok(generated_struct)
   }
 }
+
+#[test]
+fn reference_keeps_occurrence_bounds() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:element name="title" type="xs:string" />
+      <xs:complexType name="work">
+        <xs:sequence>
+          <xs:element ref="title" maxOccurs="unbounded" />
+        </xs:sequence>
+      </xs:complexType>
+      <xs:complexType name="movement">
+        <xs:sequence>
+          <xs:element ref="title" minOccurs="0" />
+        </xs:sequence>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap();
+
+  assert!(output.contains("pub title: RestrictedVec<String, 1, 0>"));
+  assert!(output.contains("pub title: Option<String>"));
+  assert!(output.contains("Some(\"title\")"));
+}
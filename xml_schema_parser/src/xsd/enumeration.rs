@@ -1,6 +1,7 @@
 use xsd_codegen::XMLElement;
+use xsd_types::{XsdName, XsdType};
 
-use super::XsdError;
+use super::{xsd_context::XsdContext, Warning, XsdError};
 
 #[derive(Clone, Default, Debug, PartialEq)]
 pub struct Enumeration {
@@ -20,3 +21,164 @@ impl Enumeration {
     Ok(output)
   }
 }
+
+const XML_SCHEMA: &str = "http://www.w3.org/2001/XMLSchema";
+
+/// The enumeration values of a restriction, along with the base they restrict.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnumerationUse {
+  /// Location of the restriction, e.g. `/schema/simpleType[@name="yes-no"]/restriction`.
+  pub path: String,
+  /// The base as written in the schema, e.g. `xs:integer`.
+  pub qname: String,
+  pub base: XsdName,
+  pub values: Vec<String>,
+}
+
+/// Collect the restrictions of a schema document enumerating values of their base.
+pub(crate) fn scan(
+  schema: &xmltree::Element,
+  target_namespace: Option<&str>,
+) -> Vec<EnumerationUse> {
+  let mut uses = vec![];
+  scan_element(schema, "", target_namespace, &mut uses);
+  uses
+}
+
+fn scan_element(
+  element: &xmltree::Element,
+  parent: &str,
+  target_namespace: Option<&str>,
+  uses: &mut Vec<EnumerationUse>,
+) {
+  if element.namespace.as_deref() != Some(XML_SCHEMA) || element.name == "annotation" {
+    return;
+  }
+
+  let path = match element.attributes.get("name") {
+    Some(name) => format!("{parent}/{}[@name=\"{name}\"]", element.name),
+    None => format!("{parent}/{}", element.name),
+  };
+
+  let children = element
+    .children
+    .iter()
+    .filter_map(|child| child.as_element())
+    .filter(|child| child.namespace.as_deref() == Some(XML_SCHEMA));
+
+  if let ("restriction", Some(qname)) = (element.name.as_str(), element.attributes.get("base")) {
+    let values = children
+      .clone()
+      .filter(|child| child.name == "enumeration")
+      .filter_map(|child| child.attributes.get("value").cloned())
+      .collect::<Vec<_>>();
+    if !values.is_empty() {
+      uses.push(EnumerationUse {
+        path: path.clone(),
+        qname: qname.clone(),
+        base: resolve(element, qname, target_namespace),
+        values,
+      });
+    }
+  }
+
+  for child in children {
+    scan_element(child, &path, target_namespace, uses);
+  }
+}
+
+/// Resolve a type name the way the parser does: through the prefixes in scope, the names without
+/// one being in the default namespace, or else the target namespace.
+fn resolve(element: &xmltree::Element, qname: &str, target_namespace: Option<&str>) -> XsdName {
+  let in_scope = |prefix: &str| {
+    element
+      .namespaces
+      .as_ref()
+      .and_then(|namespaces| namespaces.get(prefix))
+      .filter(|uri| !uri.is_empty())
+  };
+
+  let (namespace, local_name) = match qname.split_once(':') {
+    Some((prefix, local_name)) => (Some(in_scope(prefix).unwrap_or(prefix)), local_name),
+    None => (in_scope("").or(target_namespace), qname),
+  };
+
+  XsdName {
+    namespace: namespace.map(str::to_string),
+    local_name: local_name.to_string(),
+    ty: XsdType::SimpleType,
+  }
+}
+
+/// Report the enumeration values that are not values of the base of their restriction.
+pub(crate) fn check(uses: &[EnumerationUse], context: &XsdContext) -> Vec<Warning> {
+  uses
+    .iter()
+    .flat_map(|used| {
+      used.values.iter().filter_map(|value| {
+        let reason = context.check_lexical(&used.base, value).err()?;
+        Some(Warning {
+          path: used.path.clone(),
+          msg: format!(
+            "enumeration value `{value}` is not a value of the base {}: {reason}",
+            used.qname
+          ),
+        })
+      })
+    })
+    .collect()
+}
+
+#[test]
+fn enumerations_outside_their_base_are_reported() {
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:simpleType name="yes-no">
+        <xs:restriction base="xs:string">
+          <xs:enumeration value="yes"/>
+          <xs:enumeration value="no"/>
+        </xs:restriction>
+      </xs:simpleType>
+      <xs:simpleType name="yes">
+        <xs:restriction base="yes-no">
+          <xs:enumeration value="yes"/>
+          <xs:enumeration value="yess"/>
+        </xs:restriction>
+      </xs:simpleType>
+      <xs:simpleType name="staff-count">
+        <xs:restriction base="xs:integer">
+          <xs:enumeration value=" 1 "/>
+          <xs:enumeration value="1.5"/>
+        </xs:restriction>
+      </xs:simpleType>
+    </xs:schema>
+  "#;
+
+  let error = crate::Xsd::new(schema)
+    .unwrap()
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap_err();
+  assert_eq!(error.error_code(), "invalid-enumeration");
+
+  let lenient = crate::GeneratorOptions {
+    lenient_enumerations: true,
+    ..Default::default()
+  };
+  let (_, report) = crate::Xsd::new(schema)
+    .unwrap()
+    .generate_with_report(&lenient)
+    .unwrap();
+  assert_eq!(
+    report.warnings,
+    [
+      Warning {
+        path: r#"/schema/simpleType[@name="yes"]/restriction"#.to_string(),
+        msg: "enumeration value `yess` is not a value of the base yes-no: `yess` is not one of yes, no".to_string(),
+      },
+      Warning {
+        path: r#"/schema/simpleType[@name="staff-count"]/restriction"#.to_string(),
+        msg: "enumeration value `1.5` is not a value of the base xs:integer: `1.5` is not a valid integer".to_string(),
+      },
+    ]
+  );
+}
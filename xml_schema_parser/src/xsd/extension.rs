@@ -1,5 +1,5 @@
-use xsd_codegen::{Struct, XMLElement};
-use xsd_types::{XsdIoError, XsdName, XsdParseError, XsdType};
+use xsd_codegen::{ContentKind, Struct, XMLElement};
+use xsd_types::{SchemaError, XsdName, XsdParseError, XsdType};
 
 use crate::xsd::{attribute::Attribute, sequence::Sequence, XsdContext};
 
@@ -24,7 +24,7 @@ pub struct Extension {
 }
 
 impl Extension {
-  pub fn parse(mut element: XMLElement) -> Result<Self, XsdIoError> {
+  pub fn parse(mut element: XMLElement) -> Result<Self, SchemaError> {
     element.check_name("extension")?;
 
     let attributes = element.get_children_with("attribute", Attribute::parse)?;
@@ -39,7 +39,7 @@ impl Extension {
     if (!attributes.is_empty() || !attribute_groups.is_empty())
       && (group.is_some() || choice.is_some() || sequence.is_some())
     {
-      return Err(XsdIoError::XsdParseError(XsdParseError {
+      return Err(SchemaError::Invalid(XsdParseError {
         node_name: element.node_name(),
         msg: "(group | choice | sequence) and (attribute | attributeGroup) cannot both present"
           .to_string(),
@@ -47,18 +47,16 @@ impl Extension {
     }
 
     if group.is_some() as u8 + choice.is_some() as u8 + sequence.is_some() as u8 > 1 {
-      return Err(XsdIoError::XsdParseError(XsdParseError {
+      return Err(SchemaError::Invalid(XsdParseError {
         node_name: element.node_name(),
         msg: "group | choice | sequence cannot all be present".to_string(),
       }));
     }
 
+    let base: String = element.get_attribute("base")?;
     let output = Self {
-      base: XsdName::new(
-        &element.get_attribute::<String>("base")?,
-        XsdType::SimpleType,
-      ),
-      sequence: element.try_get_child_with("sequence", Sequence::parse)?,
+      base: element.new_qname(&base, XsdType::SimpleType),
+      sequence,
       group,
       choice,
       attributes,
@@ -75,7 +73,7 @@ impl Extension {
   pub fn get_implementation(
     &self,
     parent_name: XsdName,
-    context: &mut XsdContext,
+    context: &XsdContext,
   ) -> Result<XsdImpl, XsdError> {
     let generated_impl = context.multi_search(
       self.base.namespace.clone(),
@@ -84,9 +82,10 @@ impl Extension {
     );
     let base_impl = match generated_impl {
       super::xsd_context::SearchResult::SingleMatch(imp) => imp,
-      super::xsd_context::SearchResult::MultipleMatches => {
+      super::xsd_context::SearchResult::MultipleMatches(candidates) => {
         return Err(XsdError::ContextSearchError {
           name: self.base.clone(),
+          candidates,
           msg: "found both a simple and complex type".to_string(),
         });
       }
@@ -102,6 +101,8 @@ impl Extension {
       inner: vec![],
       implementation: vec![],
       flatten: false,
+      content: ContentKind::Empty,
+      attribute_groups: vec![],
     };
 
     let mut base_impl = base_impl.to_type();
@@ -115,6 +116,7 @@ impl Extension {
       (None, Some(sequence), None) => Some(sequence.get_implementation(Some(parent_name), context)),
       (Some(group), None, None) => Some(group.get_implementation(Some(parent_name), context)),
       (None, None, None) => None,
+      // At most one of them is present, checked when parsing.
       _ => unreachable!("Error parsing {}, Invalid XSD!", &parent_name.local_name),
     };
 
@@ -132,7 +134,7 @@ impl Extension {
     for attribute in &self.attribute_groups {
       generated_impl.merge(
         attribute.get_implementation(None, context)?,
-        MergeSettings::default(),
+        MergeSettings::ATTRIBUTE,
       );
     }
 
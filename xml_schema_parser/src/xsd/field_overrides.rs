@@ -0,0 +1,228 @@
+use xsd_codegen::{Field, Fields, Type};
+
+use super::{
+  regenerate_xsdgen,
+  stub::tag,
+  xsd_context::{XsdContext, XsdImpl, XsdImplType},
+  FieldOverride, Warning,
+};
+
+/// Apply `overrides` to the fields of the structs of `context`, and generate the parsing code of
+/// the structs they changed again. Returns a warning for every override whose component or field
+/// was not found.
+pub(crate) fn apply_field_overrides(
+  context: &mut XsdContext,
+  overrides: &[FieldOverride],
+) -> Vec<Warning> {
+  let mut warnings = vec![];
+  let mut structs = std::mem::take(&mut context.structs);
+  for field_override in overrides {
+    let mut owners = vec![];
+    let mut applied = false;
+    for imp in structs.values_mut() {
+      apply(imp, field_override, context, &mut owners, &mut applied);
+    }
+
+    let path = format!(
+      "/schema/{}[@name=\"{}\"]",
+      tag(field_override.owner.ty),
+      field_override.owner.local_name
+    );
+    if owners.is_empty() {
+      let names = structs
+        .values()
+        .flat_map(XsdImpl::iter_recursive)
+        .map(|imp| imp.name.local_name.clone())
+        .collect();
+      warnings.push(Warning {
+        path,
+        msg: format!(
+          "no struct to override `{}` in{}",
+          field_override.xml_name,
+          near(&field_override.owner.local_name, names)
+        ),
+      });
+    } else if !applied {
+      warnings.push(Warning {
+        path,
+        msg: format!(
+          "no field `{}` to override{}",
+          field_override.xml_name,
+          near(&field_override.xml_name, owners)
+        ),
+      });
+    }
+  }
+  context.structs = structs;
+
+  warnings
+}
+
+/// Apply `field_override` to `imp` and its inner types. The XML names of the fields of the
+/// structs it designates are added to `owners`, and `applied` is set once a field was changed.
+fn apply(
+  imp: &mut XsdImpl,
+  field_override: &FieldOverride,
+  context: &XsdContext,
+  owners: &mut Vec<String>,
+  applied: &mut bool,
+) {
+  for inner in &mut imp.inner {
+    apply(inner, field_override, context, owners, applied);
+  }
+
+  if !context.matches(&field_override.owner, &imp.name) {
+    return;
+  }
+  let XsdImplType::Struct(r#struct) = &mut imp.element else {
+    return;
+  };
+  let Fields::Named(fields) = &mut r#struct.fields else {
+    return;
+  };
+
+  owners.extend(fields.iter().filter_map(xml_name).cloned());
+  let designated = |field: &Field| xml_name(field) == Some(&field_override.xml_name);
+  if !fields.iter().any(designated) {
+    return;
+  }
+
+  if field_override.drop {
+    fields.retain(|field| !designated(field));
+  }
+  for field in fields.iter_mut().filter(|field| designated(field)) {
+    if let Some(name) = &field_override.rename {
+      field.name = Some(name.clone());
+    }
+    if let Some(ty) = &field_override.ty {
+      field.ty = Type::new(field.ty.xml_name.clone(), ty);
+    }
+    if let Some(attribute) = field_override.attribute {
+      field.attribute = attribute;
+    }
+    if let Some(flatten) = field_override.flatten {
+      field.flatten = flatten;
+    }
+  }
+
+  *applied = true;
+  *imp = regenerate_xsdgen(imp.clone(), context);
+}
+
+fn xml_name(field: &Field) -> Option<&String> {
+  field.xml_name.as_ref().map(|name| &name.local_name)
+}
+
+/// The names of `candidates` close to `name`, e.g. ` (near: `note`, `notes`)`, or nothing when
+/// none is.
+fn near(name: &str, candidates: Vec<String>) -> String {
+  let mut close = candidates
+    .into_iter()
+    .map(|candidate| (distance(name, &candidate), candidate))
+    .filter(|(distance, candidate)| *distance <= 2.max(candidate.chars().count() / 3))
+    .collect::<Vec<_>>();
+  close.sort();
+  close.dedup();
+
+  if close.is_empty() {
+    return String::new();
+  }
+  let names = close
+    .iter()
+    .take(3)
+    .map(|(_, candidate)| format!("`{candidate}`"))
+    .collect::<Vec<_>>();
+  format!(" (near: {})", names.join(", "))
+}
+
+/// The number of characters to insert, remove or replace to turn `a` into `b`.
+fn distance(a: &str, b: &str) -> usize {
+  let b = b.chars().collect::<Vec<_>>();
+  let mut previous = (0..=b.len()).collect::<Vec<_>>();
+  for (i, ca) in a.chars().enumerate() {
+    let mut current = vec![i + 1];
+    for (j, cb) in b.iter().enumerate() {
+      let replace = previous[j] + usize::from(ca != *cb);
+      current.push(replace.min(previous[j + 1] + 1).min(current[j] + 1));
+    }
+    previous = current;
+  }
+  previous[b.len()]
+}
+
+#[test]
+fn near_matches_are_the_closest_names() {
+  let candidates = ["pitch", "pitches", "duration", "dot"].map(str::to_string);
+  assert_eq!(near("pich", candidates.to_vec()), " (near: `pitch`)");
+  assert_eq!(near("stem", candidates.to_vec()), "");
+  assert_eq!(distance("note", "notes"), 1);
+  assert_eq!(distance("", "dot"), 3);
+}
+
+#[test]
+fn overridden_fields_are_still_read_from_their_xml_name() {
+  use xsd_types::{XsdName, XsdType};
+
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="note">
+        <xs:sequence>
+          <xs:element name="pitch" type="xs:string" />
+          <xs:element name="duration" type="xs:int" />
+          <xs:element name="editorial-junk" type="xs:string" minOccurs="0" />
+        </xs:sequence>
+        <xs:attribute name="print-object" type="xs:boolean" />
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let note = XsdName::new("note", XsdType::ComplexType);
+  let options = crate::GeneratorOptions {
+    field_overrides: vec![
+      FieldOverride {
+        rename: Some("visible".to_string()),
+        ..FieldOverride::new(note.clone(), "print-object")
+      },
+      FieldOverride {
+        ty: Some("u16".to_string()),
+        ..FieldOverride::new(note.clone(), "duration")
+      },
+      FieldOverride {
+        drop: true,
+        ..FieldOverride::new(note.clone(), "editorial-junk")
+      },
+      FieldOverride {
+        drop: true,
+        ..FieldOverride::new(note, "pich")
+      },
+      FieldOverride::new(XsdName::new("notes", XsdType::ComplexType), "pitch"),
+    ],
+    ..Default::default()
+  };
+  let (output, report) = xsd.generate_with_report(&options).unwrap();
+
+  assert!(output.contains("pub duration: u16,\n    pub visible: Option<bool>,\n}"));
+  assert!(output.contains(
+    r#"visible: <Option<bool> as XsdGen>::gen(element, gen_state.to_attr(), Some("print-object"))?,"#
+  ));
+  assert!(output
+    .contains(r#"duration: <u16 as XsdGen>::gen(element, gen_state.clone(), Some("duration"))?,"#));
+  assert!(!output.contains("junk"));
+
+  assert_eq!(
+    report.warnings,
+    [
+      Warning {
+        path: r#"/schema/complexType[@name="note"]"#.to_string(),
+        msg: "no field `pich` to override (near: `pitch`)".to_string(),
+      },
+      Warning {
+        path: r#"/schema/complexType[@name="notes"]"#.to_string(),
+        msg: "no struct to override `pitch` in (near: `note`)".to_string(),
+      },
+    ]
+  );
+}
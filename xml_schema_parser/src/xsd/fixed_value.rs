@@ -0,0 +1,95 @@
+use xsd_codegen::{escape_str_literal, fromxml_impl, Block, ContentKind, Function, Impl, Struct};
+use xsd_types::XsdName;
+
+use super::xsd_context::{XsdImpl, XsdImplType};
+
+/// Generate a zero-sized marker type standing in for the constant `value`.
+///
+/// Parsing still checks that the document holds `value`.
+pub fn marker_impl(name: XsdName, typename: &str, value: &str) -> XsdImpl {
+  let marker = Struct::new(Some(name.clone()), typename)
+    .vis("pub")
+    .derives(&["Clone", "Copy", "Debug", "Default", "PartialEq"]);
+  let ty = marker.ty().clone();
+  let literal = escape_str_literal(value);
+
+  let mut fixed_impl = Impl::new(&ty).impl_trait("FixedValue");
+  fixed_impl.associate_const("VALUE", "&'static str", &literal);
+
+  let parse = Block::new("match string")
+    .line(format!("{literal} => Ok(Self),"))
+    .line(format!(
+      "value => Err(format!({})),",
+      escape_str_literal(&format!(
+        "Expected the fixed value {} found {{value}}.",
+        value.replace('{', "{{").replace('}', "}}")
+      ))
+    ));
+
+  let implementation = vec![
+    Impl::new(&ty).push_fn(
+      Function::new("as_str")
+        .vis("pub")
+        .arg_ref_self()
+        .ret("&'static str")
+        .line(&literal),
+    ),
+    fixed_impl,
+    fromxml_impl(ty.clone(), parse),
+    Impl::new(&ty).impl_trait("std::fmt::Display").push_fn(
+      Function::new("fmt")
+        .arg_ref_self()
+        .arg("f", "&mut std::fmt::Formatter<'_>")
+        .ret("std::fmt::Result")
+        .line(format!("f.write_str({literal})")),
+    ),
+  ];
+
+  XsdImpl {
+    fieldname_hint: Some(name.to_field_name()),
+    name,
+    element: XsdImplType::Struct(marker),
+    inner: vec![],
+    implementation,
+    flatten: false,
+    content: ContentKind::Simple,
+    attribute_groups: vec![],
+  }
+}
+
+#[test]
+fn fixed_values_representation() {
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="note">
+        <xs:attribute name="version" type="xs:string" fixed="3.1" />
+      </xs:complexType>
+    </xs:schema>
+  "#;
+
+  let generate = |fixed_values| {
+    crate::Xsd::new(schema)
+      .unwrap()
+      .generate_with(&crate::GeneratorOptions {
+        fixed_values,
+        ..Default::default()
+      })
+      .unwrap()
+  };
+
+  // An absent fixed attribute has the fixed value, which is checked when present.
+  let owned = generate(crate::FixedValues::Owned);
+  assert!(owned.contains("pub version: String"));
+  assert!(owned.contains(
+    "element.gen_fixed_attribute::<String>(gen_state.to_attr(), Some(\"version\"), \"3.1\")?"
+  ));
+
+  let marker = generate(crate::FixedValues::Marker);
+  assert!(marker.contains("pub version: Option<note::Version>"));
+  assert!(marker.contains("pub struct Version;"));
+  assert!(marker.contains("const VALUE: &'static str = \"3.1\";"));
+
+  let static_str = generate(crate::FixedValues::StaticStr);
+  assert!(static_str.contains("pub version: &'static str"));
+  assert!(static_str.contains("<Option<note::Version> as FixedValue>::VALUE"));
+}
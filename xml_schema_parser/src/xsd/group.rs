@@ -1,11 +1,12 @@
 use xsd_codegen::XMLElement;
-use xsd_types::{to_field_name, XsdIoError, XsdName, XsdParseError, XsdType};
+use xsd_types::{to_field_name, SchemaError, XsdName, XsdParseError, XsdType};
 
 use super::{
+  all::All,
   annotation::Annotation,
   choice::Choice,
   general_xsdgen,
-  max_occurences::MaxOccurences,
+  max_occurences::{self, MaxOccurences, ITEM},
   sequence::Sequence,
   xsd_context::{XsdContext, XsdImpl, XsdImplType},
   XsdError,
@@ -19,12 +20,13 @@ pub struct Group {
   pub min_occurences: u64,
   pub max_occurences: MaxOccurences,
   pub annotation: Option<Annotation>,
+  pub all: Option<All>,
   pub sequence: Option<Sequence>,
   pub choice: Option<Choice>,
 }
 
 impl Group {
-  pub fn parse(mut element: XMLElement) -> Result<Self, XsdIoError> {
+  pub fn parse(mut element: XMLElement) -> Result<Self, SchemaError> {
     element.check_name("group")?;
 
     let name = element
@@ -32,8 +34,9 @@ impl Group {
       .map(|v: String| element.new_name(&v, XsdType::Group));
     let refers = element
       .try_get_attribute("ref")?
-      .map(|v: String| XsdName::new(&v, XsdType::Group));
+      .map(|v: String| element.new_qname(&v, XsdType::Group));
 
+    let all = element.try_get_child_with("all", All::parse)?;
     let sequence = element.try_get_child_with("sequence", Sequence::parse)?;
     let choice = element.try_get_child_with("choice", Choice::parse)?;
 
@@ -47,25 +50,51 @@ impl Group {
       );
     }
 
-    if sequence.is_some() && choice.is_some() {
+    if name.is_none() && refers.is_none() {
       return Err(
         XsdParseError {
           node_name: element.node_name(),
-          msg: "sequence and choice cannot both present in".to_string(),
+          msg: "name or ref is required".to_string(),
         }
         .into(),
       );
     }
 
+    let compositors = [all.is_some(), sequence.is_some(), choice.is_some()]
+      .into_iter()
+      .filter(|present| *present)
+      .count();
+
+    if name.is_some() && compositors == 0 {
+      return Err(
+        XsdParseError {
+          node_name: element.node_name(),
+          msg: "One of all, sequence and choice is required".to_string(),
+        }
+        .into(),
+      );
+    }
+
+    if compositors > 1 {
+      return Err(
+        XsdParseError {
+          node_name: element.node_name(),
+          msg: "Only one of all, sequence and choice can be present".to_string(),
+        }
+        .into(),
+      );
+    }
+
+    let (min_occurences, max_occurences) =
+      max_occurences::parse_occurences(&mut element, name.as_ref().or(refers.as_ref()))?;
     let output = Self {
       id: element.try_get_attribute("id")?,
       name,
       refers,
-      min_occurences: element.try_get_attribute("minOccurs")?.unwrap_or(1),
-      max_occurences: element
-        .try_get_attribute("maxOccurs")?
-        .unwrap_or(MaxOccurences::Number { value: 1 }),
+      min_occurences,
+      max_occurences,
       annotation: element.try_get_child_with("annotation", Annotation::parse)?,
+      all,
       sequence,
       choice,
     };
@@ -79,12 +108,14 @@ impl Group {
   pub fn get_implementation(
     &self,
     parent_name: Option<XsdName>,
-    context: &mut XsdContext,
+    context: &XsdContext,
   ) -> Result<XsdImpl, XsdError> {
     let gen = match (&self.name, &parent_name, &self.refers) {
-      (Some(name), _, None) => match (&self.choice, &self.sequence) {
-        (None, Some(sequence)) => sequence.get_implementation(Some(name.clone()), context)?,
-        (Some(choice), None) => choice.get_implementation(Some(name.clone()), context)?,
+      (Some(name), _, None) => match (&self.all, &self.choice, &self.sequence) {
+        (Some(all), None, None) => all.get_implementation(Some(name.clone()), context)?,
+        (None, None, Some(sequence)) => sequence.get_implementation(Some(name.clone()), context)?,
+        (None, Some(choice), None) => choice.get_implementation(Some(name.clone()), context)?,
+        // Exactly one of them is present, checked when parsing.
         _ => unreachable!("The Xsd is invalid!"),
       },
       (None, _, Some(refers)) => {
@@ -94,6 +125,21 @@ impl Group {
           return Err(XsdError::XsdImplNotFound(refers.clone()));
         };
 
+        // The children of an `all` are only ordered as the whole content of the element.
+        let once = self.min_occurences == 1 && self.max_occurences == MaxOccurences::default();
+        if holds_all(inner) && (parent_name.is_none() || !once) {
+          return Err(
+            SchemaError::Invalid(XsdParseError {
+              node_name: "group".to_string(),
+              msg: format!(
+                "`{}` holds an all, it can only be referenced once as the content of a complex type",
+                refers.local_name
+              ),
+            })
+            .into(),
+          );
+        }
+
         let field_name = if let Some(parent_name) = &parent_name {
           parent_name.to_field_name()
         } else if let Some(field_hint) = &inner.fieldname_hint {
@@ -106,7 +152,7 @@ impl Group {
           parent_name
         } else {
           XsdName {
-            namespace: None,
+            namespace: refers.namespace.clone(),
             local_name: inner.infer_type_name(),
             ty: XsdType::Group,
           }
@@ -121,63 +167,28 @@ impl Group {
           inner: vec![],
           implementation: vec![],
           flatten: true,
+          content: inner.content,
+          attribute_groups: vec![],
         }
       }
+      // Either the name or the reference is present, checked when parsing.
       _ => unreachable!("The Xsd is invalid!"),
     };
 
-    let multiple = match &self.max_occurences {
-      MaxOccurences::Unbounded => true,
-      MaxOccurences::Number { value } => *value > 1,
-    } || self.min_occurences > 1;
-
-    let option = match &self.max_occurences {
-      MaxOccurences::Unbounded => false,
-      MaxOccurences::Number { value } => *value == 1 && self.min_occurences == 0,
-    };
-
-    let mut gen = if multiple {
-      let mut gen = general_xsdgen(gen);
-
-      let old_name = gen.name.clone();
-      gen.name.local_name = format!("inner-{}", old_name.local_name);
-      XsdImpl {
-        name: old_name,
-        fieldname_hint: Some(gen.fieldname_hint.clone().unwrap()),
-        element: XsdImplType::Type(
-          if self.min_occurences > 0 || self.max_occurences != MaxOccurences::Unbounded {
-            gen
-              .element
-              .get_type()
-              .wrap("RestrictedVec")
-              .generic(self.min_occurences.to_string())
-              .generic(match self.max_occurences {
-                MaxOccurences::Unbounded => "0".to_string(),
-                MaxOccurences::Number { value } => value.to_string(),
-              })
-          } else {
-            gen.element.get_type().wrap("Vec")
-          },
-        ),
-        inner: vec![gen],
-        implementation: vec![],
-        flatten: parent_name.is_none() && self.name.is_none(),
+    let flatten = parent_name.is_none() && self.name.is_none();
+    let max_occurences = self.max_occurences.limited(context.max_occurs_limit);
+    let mut gen = match max_occurences.role(self.min_occurences) {
+      Some(ITEM) => {
+        let gen = general_xsdgen(gen, context);
+        let ty = max_occurences.wrap_repeated(gen.element.get_type(), self.min_occurences);
+        gen.wrap(ty, ITEM, flatten)
       }
-    } else if option {
-      let mut gen = general_xsdgen(gen);
-
-      let old_name = gen.name.clone();
-      gen.name.local_name = format!("inner-{}", old_name.local_name);
-      XsdImpl {
-        name: old_name,
-        fieldname_hint: Some(gen.fieldname_hint.clone().unwrap()),
-        element: XsdImplType::Type(gen.element.get_type().wrap("Option")),
-        inner: vec![gen],
-        implementation: vec![],
-        flatten: parent_name.is_none() && self.name.is_none(),
+      Some(role) => {
+        let gen = general_xsdgen(gen, context);
+        let ty = gen.element.get_type().wrap("Option").occurs(0, Some(1));
+        gen.wrap(ty, role, flatten)
       }
-    } else {
-      gen
+      None => gen,
     };
 
     if let Some(annotation) = &self.annotation {
@@ -189,3 +200,17 @@ impl Group {
     Ok(gen)
   }
 }
+
+/// Whether the group generated as `imp` holds an `all`, its struct being named as one, see
+/// `All::get_implementation`. An optional `all` is wrapped.
+fn holds_all(imp: &XsdImpl) -> bool {
+  let imp = match &imp.element {
+    XsdImplType::Type(_) => imp.inner.first(),
+    _ => Some(imp),
+  };
+  matches!(
+    imp.map(|imp| &imp.element),
+    Some(XsdImplType::Struct(ty))
+      if ty.type_def.ty.xml_name.as_ref().map(|name| name.ty) == Some(XsdType::All)
+  )
+}
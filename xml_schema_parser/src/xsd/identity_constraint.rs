@@ -0,0 +1,343 @@
+use xsd_codegen::XMLElement;
+use xsd_types::{SchemaError, XsdName, XsdParseError, XsdType};
+
+use super::annotation::Annotation;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConstraintKind {
+  Unique,
+  Key,
+  /// A reference to the values of a `key` or `unique` constraint.
+  KeyRef {
+    refer: XsdName,
+  },
+}
+
+/// An `xs:unique`, `xs:key` or `xs:keyref` declared on an element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IdentityConstraint {
+  pub name: XsdName,
+  pub kind: ConstraintKind,
+  /// Alternatives of the `selector` XPath, selecting the constrained elements.
+  pub selector: Vec<Path>,
+  /// Alternatives of the XPath of each `field`, selecting the values of a selected element.
+  pub fields: Vec<Vec<Path>>,
+  pub annotation: Option<Annotation>,
+}
+
+/// A path of the XPath subset allowed in identity constraints, e.g. `.//m:part/@id`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Path {
+  /// Whether the path starts with `.//`, matching at any depth.
+  pub descendants: bool,
+  pub steps: Vec<Step>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Step {
+  /// `.`
+  Current,
+  Child(NameTest),
+  /// `@name`, only allowed as the last step of a field.
+  Attribute(NameTest),
+}
+
+/// A name test, with its prefix resolved to a namespace URI.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NameTest {
+  /// `*`
+  Any,
+  /// `prefix:*`
+  AnyIn(String),
+  Name(XsdName),
+}
+
+impl IdentityConstraint {
+  pub fn parse(mut element: XMLElement) -> Result<Self, SchemaError> {
+    let kind = match element.name() {
+      "unique" => ConstraintKind::Unique,
+      "key" => ConstraintKind::Key,
+      "keyref" => {
+        let refer: String = element.get_attribute("refer")?;
+        ConstraintKind::KeyRef {
+          refer: element.new_qname(&refer, XsdType::IdentityConstraint),
+        }
+      }
+      name => {
+        return Err(SchemaError::Invalid(XsdParseError {
+          node_name: element.node_name(),
+          msg: format!("Unexpected element name {name} expected unique, key or keyref"),
+        }))
+      }
+    };
+
+    let name: String = element.get_attribute("name")?;
+    let selector = element.get_child_with("selector", |child| parse_xpath(child, false))?;
+    let fields = element.get_children_with("field", |child| parse_xpath(child, true))?;
+    if fields.is_empty() {
+      return Err(SchemaError::Invalid(XsdParseError {
+        node_name: element.node_name(),
+        msg: "At least one field is required".to_string(),
+      }));
+    }
+
+    let output = Self {
+      name: element.new_name(&name, XsdType::IdentityConstraint),
+      kind,
+      selector,
+      fields,
+      annotation: element.try_get_child_with("annotation", Annotation::parse)?,
+    };
+
+    element.finalize(false, false)?;
+
+    Ok(output)
+  }
+}
+
+/// Parse the `xpath` of a `selector`, or of a `field` when `field` is set.
+fn parse_xpath(mut element: XMLElement, field: bool) -> Result<Vec<Path>, SchemaError> {
+  let xpath: String = element.get_attribute("xpath")?;
+  element.try_get_child_with("annotation", Annotation::parse)?;
+
+  let error = |offset: usize, msg: String| {
+    SchemaError::Invalid(XsdParseError {
+      node_name: element.node_name(),
+      msg: format!("{msg} at offset {offset} of `{xpath}`"),
+    })
+  };
+
+  let mut paths = vec![];
+  let mut start = 0;
+  for alternative in xpath.split('|') {
+    let offset = start + alternative.len() - alternative.trim_start().len();
+    start += alternative.len() + 1;
+
+    let mut path = alternative.trim();
+    let mut offset = offset;
+    let descendants = path.starts_with(".//");
+    if descendants {
+      path = &path[3..];
+      offset += 3;
+    }
+
+    let mut steps = vec![];
+    let step_count = path.split('/').count();
+    for (index, step) in path.split('/').enumerate() {
+      let step_offset = offset + step.len() - step.trim_start().len();
+      offset += step.len() + 1;
+      let step = step.trim();
+
+      let (attribute, name_test, name_offset) = if let Some(name) = step.strip_prefix('@') {
+        (
+          true,
+          name.trim_start(),
+          step_offset + step.len() - name.trim_start().len(),
+        )
+      } else if let Some(name) = step.strip_prefix("attribute::") {
+        (true, name, step_offset + 11)
+      } else if let Some(name) = step.strip_prefix("child::") {
+        (false, name, step_offset + 7)
+      } else if step == "." {
+        steps.push(Step::Current);
+        continue;
+      } else {
+        (false, step, step_offset)
+      };
+
+      if attribute && (!field || index != step_count - 1) {
+        return Err(error(
+          step_offset,
+          "Attribute steps are only allowed at the end of a field".to_string(),
+        ));
+      }
+
+      let name_test = parse_name_test(&element, name_test, attribute)
+        .map_err(|(position, msg)| error(name_offset + position, msg))?;
+      steps.push(if attribute {
+        Step::Attribute(name_test)
+      } else {
+        Step::Child(name_test)
+      });
+    }
+
+    paths.push(Path { descendants, steps });
+  }
+
+  element.finalize(false, false)?;
+
+  Ok(paths)
+}
+
+/// Parse a name test, returning the position in `name` and the reason it is rejected otherwise.
+fn parse_name_test(
+  element: &XMLElement,
+  name: &str,
+  attribute: bool,
+) -> Result<NameTest, (usize, String)> {
+  if name == "*" {
+    return Ok(NameTest::Any);
+  }
+
+  let (prefix, local_name) = match name.split_once(':') {
+    Some((prefix, local_name)) => (Some(prefix), local_name),
+    None => (None, name),
+  };
+  let local_offset = prefix.map(|prefix| prefix.len() + 1).unwrap_or(0);
+
+  if let Some(prefix) = prefix {
+    check_ncname(prefix, 0)?;
+  }
+  if local_name != "*" || prefix.is_none() {
+    check_ncname(local_name, local_offset)?;
+  }
+
+  let ty = if attribute {
+    XsdType::Attribute
+  } else {
+    XsdType::Element
+  };
+
+  let Some(prefix) = prefix else {
+    // Unprefixed names are in no namespace, the default namespace doesn't apply to XPath.
+    return Ok(NameTest::Name(XsdName::new(name, ty)));
+  };
+
+  let namespace = element
+    .element()
+    .namespaces
+    .as_ref()
+    .and_then(|namespaces| namespaces.get(prefix))
+    .ok_or_else(|| (0, format!("Unknown namespace prefix `{prefix}`")))?;
+
+  if local_name == "*" {
+    Ok(NameTest::AnyIn(namespace.to_string()))
+  } else {
+    Ok(NameTest::Name(element.new_qname(name, ty)))
+  }
+}
+
+fn check_ncname(name: &str, offset: usize) -> Result<(), (usize, String)> {
+  let invalid = name.char_indices().find(|(index, c)| {
+    !(c.is_alphabetic() || *c == '_' || (*index > 0 && (c.is_numeric() || *c == '-' || *c == '.')))
+  });
+
+  match invalid {
+    Some((index, c)) => Err((offset + index, format!("Unsupported XPath token `{c}`"))),
+    None if name.is_empty() => Err((offset, "Missing name".to_string())),
+    None => Ok(()),
+  }
+}
+
+#[test]
+fn xpath_steps_are_resolved() {
+  let parse = |constraint: &str| {
+    IdentityConstraint::parse(XMLElement::from_element(
+      xmltree::Element::parse(
+        format!(
+          r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:m="urn:music" xmlns="urn:default">{constraint}</xs:schema>"#
+        )
+        .as_bytes(),
+      )
+      .unwrap()
+      .take_child("key")
+      .unwrap(),
+      None,
+    ))
+  };
+
+  let key = parse(
+    r#"<xs:key name="part"><xs:selector xpath=".//m:part | score"/><xs:field xpath="m:id/@m:n"/><xs:field xpath="@id"/></xs:key>"#,
+  )
+  .unwrap();
+
+  let name = |namespace: Option<&str>, local_name: &str, ty| {
+    NameTest::Name(XsdName {
+      namespace: namespace.map(str::to_string),
+      local_name: local_name.to_string(),
+      ty,
+    })
+  };
+  assert_eq!(
+    key.selector,
+    [
+      Path {
+        descendants: true,
+        steps: vec![Step::Child(name(
+          Some("urn:music"),
+          "part",
+          XsdType::Element
+        ))],
+      },
+      // The default namespace doesn't apply to unprefixed steps.
+      Path {
+        descendants: false,
+        steps: vec![Step::Child(name(None, "score", XsdType::Element))],
+      },
+    ]
+  );
+  assert_eq!(
+    key.fields,
+    [
+      vec![Path {
+        descendants: false,
+        steps: vec![
+          Step::Child(name(Some("urn:music"), "id", XsdType::Element)),
+          Step::Attribute(name(Some("urn:music"), "n", XsdType::Attribute)),
+        ],
+      }],
+      vec![Path {
+        descendants: false,
+        steps: vec![Step::Attribute(name(None, "id", XsdType::Attribute))],
+      }],
+    ]
+  );
+
+  let error = parse(
+    r#"<xs:key name="part"><xs:selector xpath="m:part[@id]"/><xs:field xpath="@id"/></xs:key>"#,
+  )
+  .unwrap_err();
+  assert_eq!(
+    error.to_string(),
+    "Error parsing xml node[selector]: Unsupported XPath token `[` at offset 6 of `m:part[@id]`"
+  );
+
+  let error =
+    parse(r#"<xs:key name="part"><xs:selector xpath="x:part"/><xs:field xpath="@id"/></xs:key>"#)
+      .unwrap_err();
+  assert_eq!(
+    error.to_string(),
+    "Error parsing xml node[selector]: Unknown namespace prefix `x` at offset 0 of `x:part`"
+  );
+}
+
+#[test]
+fn elements_with_identity_constraints_generate() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:element name="score">
+        <xs:complexType>
+          <xs:sequence>
+            <xs:element name="part" maxOccurs="unbounded">
+              <xs:complexType>
+                <xs:attribute name="id" type="xs:ID"/>
+              </xs:complexType>
+            </xs:element>
+          </xs:sequence>
+        </xs:complexType>
+        <xs:key name="part">
+          <xs:selector xpath="part"/>
+          <xs:field xpath="@id"/>
+        </xs:key>
+      </xs:element>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  assert!(xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap()
+    .contains("pub struct Score"));
+}
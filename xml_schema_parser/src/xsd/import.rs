@@ -1,9 +1,62 @@
+use std::{
+  collections::BTreeMap,
+  sync::{Arc, Mutex},
+};
+
 use xsd_codegen::XMLElement;
-use xsd_types::XsdIoError;
+use xsd_types::{SchemaError, XsdName};
 
 use crate::Xsd;
 
-use super::{xsd_context::XsdContext, XsdError};
+use super::{
+  substitutions::Substitutions,
+  xsd_context::{XsdContext, XsdImpl},
+  LossyMapping, VersionUse, Warning, XsdError,
+};
+
+/// What an imported schema adds to the context of the importing one.
+#[derive(Clone, Debug, Default)]
+pub struct Imported {
+  /// The implementations of its top-level components.
+  pub impls: Vec<XsdImpl>,
+  /// The constructs of the loaded documents requiring a newer version of XSD.
+  pub versions: Vec<VersionUse>,
+  /// The references of the loaded documents to builtin types with a lossy mapping.
+  pub lossy_mappings: Vec<LossyMapping>,
+  /// The enumeration values of the loaded documents that are not values of their base.
+  pub invalid_enumerations: Vec<Warning>,
+  /// The `maxOccurs` of the loaded documents read as unbounded.
+  pub unbounded_max_occurs: Vec<Warning>,
+  /// Hash of the definition of its top-level components.
+  pub content_hashes: BTreeMap<XsdName, String>,
+  /// The substitution groups of the loaded documents.
+  pub substitution_groups: Substitutions,
+  /// The complex types derived from another one in the loaded documents.
+  pub derived_types: Substitutions,
+}
+
+/// What the imported documents add to a context, by the document and the namespace they are
+/// imported with. It is shared by the contexts of the schemas of a `Workspace`, which are filled
+/// with the same options, so that a document they all import is only loaded once.
+#[derive(Clone, Debug, Default)]
+pub struct ImportCache(Arc<Mutex<BTreeMap<ImportKey, Imported>>>);
+
+/// The imported document, see `LoadOptions::document_key`, and the namespace it is imported with.
+type ImportKey = (String, Option<String>);
+
+impl ImportCache {
+  fn get(&self, location: &str, namespace: &Option<String>) -> Option<Imported> {
+    let cache = self.0.lock().unwrap();
+    cache
+      .get(&(location.to_string(), namespace.clone()))
+      .cloned()
+  }
+
+  fn insert(&self, location: &str, namespace: &Option<String>, imported: &Imported) {
+    let mut cache = self.0.lock().unwrap();
+    cache.insert((location.to_string(), namespace.clone()), imported.clone());
+  }
+}
 
 #[derive(Clone, Default, Debug, PartialEq)]
 pub struct Import {
@@ -13,25 +66,426 @@ pub struct Import {
 }
 
 impl Import {
-  pub fn parse(mut element: XMLElement) -> Result<Self, XsdIoError> {
+  pub fn parse(mut element: XMLElement) -> Result<Self, SchemaError> {
     Ok(Self {
       id: element.try_get_attribute("id")?,
       namespace: element.try_get_attribute("namespace")?,
-      schema_location: element.try_get_attribute("schemaLocation")?,
+      schema_location: element
+        .try_get_attribute::<String>("schemaLocation")?
+        .map(|location| normalize_location(&location)),
     })
   }
 
-  pub fn get_implementation(&self, context: &mut XsdContext) -> Result<(), XsdError> {
-    let mut xsd = Xsd::new_from_file(self.schema_location.as_ref().unwrap())?;
+  /// Load the imported schema, returning what it adds to `context`.
+  pub fn get_implementation(&self, context: &XsdContext) -> Result<Imported, XsdError> {
+    let Some(schema_location) = &self.schema_location else {
+      // The namespace can be provided by another schema embedded in the same document, e.g.
+      // the same WSDL file, and added to this one.
+      if context.embedded {
+        tracing::debug!("No schema location to import {:?} from", self.namespace);
+        return Ok(Imported::default());
+      }
+
+      return Err(XsdError::ImportWithoutLocation {
+        namespace: self.namespace.clone(),
+      });
+    };
+
+    let document = context.load_options.document_key(schema_location);
+    let key = (document, self.namespace.clone());
+    if let Some(first) = context
+      .import_chain
+      .iter()
+      .position(|loading| *loading == key)
+    {
+      return Err(XsdError::CircularImport {
+        cycle: context.import_chain[first..]
+          .iter()
+          .chain([&key])
+          .map(|(document, _)| document.clone())
+          .collect(),
+      });
+    }
+
+    if let Some(imported) = context
+      .import_cache
+      .as_ref()
+      .and_then(|cache| cache.get(&key.0, &key.1))
+    {
+      tracing::debug!("Reuse the components imported from {schema_location}");
+      return Ok(imported);
+    }
+
+    context.cancel_token.check()?;
+    let mut xsd = Xsd::new_from_file_with(schema_location, &context.load_options)?;
+    xsd.context.set_options(&context.options);
+    xsd.context.import_cache = context.import_cache.clone();
+    xsd.context.import_chain = [context.import_chain.clone(), vec![key.clone()]].concat();
     let top_level_names = xsd
       .schema
       .fill_context(&mut xsd.context, self.namespace.as_deref())?;
 
-    for name in top_level_names {
-      let gen = xsd.context.remove_impl(&name).unwrap();
-      context.insert_impl(name, gen);
+    // Uses of the imported document itself are located relative to its file.
+    let versions = xsd
+      .context
+      .versions
+      .drain(..)
+      .map(|mut used| {
+        if used.path.starts_with('/') {
+          used.path = format!("{schema_location}:{}", used.path);
+        }
+        used
+      })
+      .collect();
+    let located = |warnings: &mut Vec<Warning>| {
+      warnings
+        .drain(..)
+        .map(|mut warning| {
+          if warning.path.starts_with('/') {
+            warning.path = format!("{schema_location}:{}", warning.path);
+          }
+          warning
+        })
+        .collect()
+    };
+    let lossy_mappings = xsd
+      .context
+      .lossy_mappings
+      .drain(..)
+      .map(|mut used| {
+        if used.path.starts_with('/') {
+          used.path = format!("{schema_location}:{}", used.path);
+        }
+        used
+      })
+      .collect();
+    let invalid_enumerations = located(&mut xsd.context.invalid_enumerations);
+    let unbounded_max_occurs = located(&mut xsd.context.unbounded_max_occurs);
+
+    let imported = Imported {
+      impls: top_level_names
+        .iter()
+        .map(|name| xsd.context.remove_impl(name).unwrap())
+        .collect(),
+      versions,
+      lossy_mappings,
+      invalid_enumerations,
+      unbounded_max_occurs,
+      content_hashes: std::mem::take(&mut xsd.context.content_hashes),
+      substitution_groups: std::mem::take(&mut xsd.context.substitution_groups),
+      derived_types: std::mem::take(&mut xsd.context.derived_types),
+    };
+    if let Some(cache) = &context.import_cache {
+      cache.insert(&key.0, &key.1, &imported);
     }
 
-    Ok(())
+    Ok(imported)
   }
 }
+
+/// Use forward slashes in a location, so that a relative path written on Windows is loaded, and
+/// reported, the same way on every platform.
+fn normalize_location(location: &str) -> String {
+  location.replace('\\', "/")
+}
+
+#[test]
+fn schemas_embedded_in_wsdl() {
+  let wsdl = r#"
+    <wsdl:definitions xmlns:wsdl="http://schemas.xmlsoap.org/wsdl/"
+      xmlns:xs="http://www.w3.org/2001/XMLSchema"
+      xmlns:a="urn:a" xmlns:b="urn:b">
+      <wsdl:types>
+        <xs:schema targetNamespace="urn:a">
+          <xs:import namespace="urn:b"/>
+          <xs:complexType name="note">
+            <xs:sequence>
+              <xs:element name="pitch" type="b:pitch"/>
+            </xs:sequence>
+          </xs:complexType>
+        </xs:schema>
+        <xs:schema targetNamespace="urn:b">
+          <xs:import namespace="urn:a"/>
+          <xs:simpleType name="pitch">
+            <xs:restriction base="xs:string"/>
+          </xs:simpleType>
+          <xs:complexType name="chord">
+            <xs:sequence>
+              <xs:element name="note" type="a:note" maxOccurs="unbounded"/>
+            </xs:sequence>
+          </xs:complexType>
+        </xs:schema>
+      </wsdl:types>
+    </wsdl:definitions>
+  "#;
+
+  let second = Xsd::from_embedded(wsdl, 1).unwrap();
+  assert_eq!(second.target_namespace(), Some("urn:b"));
+  assert!(Xsd::from_embedded(wsdl, 2).is_err());
+
+  let mut xsd = Xsd::from_embedded(wsdl, 0).unwrap();
+  xsd.add_embedded(wsdl, 1).unwrap();
+
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap();
+  assert!(output.contains("pub struct Note"));
+  assert!(output.contains("pub struct Chord"));
+  assert!(output.contains("pub note: RestrictedVec<Note, 1, 0>"));
+
+  // Outside of a wrapper document no other schema can provide the imported namespace.
+  let alone = Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:a">
+      <xs:import namespace="urn:b"/>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+  let error = alone
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap_err();
+  assert_eq!(error.error_code(), "import-without-location");
+  assert_eq!(error.to_string(), "Import of urn:b has no schemaLocation");
+}
+
+/// Schema documents by location.
+#[cfg(test)]
+struct Memory(std::collections::BTreeMap<&'static str, String>);
+
+#[cfg(test)]
+impl crate::SchemaSource for Memory {
+  fn fetch(&self, location: &str) -> Result<Vec<u8>, XsdError> {
+    self
+      .0
+      .get(location)
+      .map(|doc| doc.as_bytes().to_vec())
+      .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, location.to_string()).into())
+  }
+}
+
+/// Offline options loading `documents` only.
+#[cfg(test)]
+fn memory_options(documents: [(&'static str, String); 2]) -> crate::LoadOptions {
+  crate::LoadOptions {
+    offline: true,
+    source: Some(std::sync::Arc::new(Memory(documents.into()))),
+    ..Default::default()
+  }
+}
+
+#[test]
+fn imports_are_fetched_from_the_source() {
+  let options = memory_options([
+    (
+      "score.xsd",
+      r#"
+      <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:l="urn:link">
+        <xs:import namespace="urn:link" schemaLocation="https://example.com/link.xsd"/>
+        <xs:complexType name="credit">
+          <xs:attribute ref="l:href"/>
+        </xs:complexType>
+      </xs:schema>
+    "#
+      .to_string(),
+    ),
+    (
+      "https://example.com/link.xsd",
+      r#"
+      <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:link">
+        <xs:attribute name="href" type="xs:anyURI"/>
+      </xs:schema>
+    "#
+      .to_string(),
+    ),
+  ]);
+
+  // Offline, only the source can provide the imported document.
+  let xsd = Xsd::new_from_file_with("score.xsd", &options).unwrap();
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap();
+  assert!(output.contains("pub href: Option<Href>"));
+  assert!(output.contains("pub type Href = String;"));
+
+  let error = Xsd::new_from_file_with("missing.xsd", &options).unwrap_err();
+  assert_eq!(error.error_code(), "io");
+}
+
+#[test]
+fn import_cycles_are_errors() {
+  let options = memory_options([
+    (
+      "score.xsd",
+      r#"
+      <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:score" xmlns:p="urn:part">
+        <xs:import namespace="urn:part" schemaLocation="part.xsd"/>
+        <xs:element name="score" type="p:part"/>
+      </xs:schema>
+    "#
+      .to_string(),
+    ),
+    (
+      "part.xsd",
+      r#"
+      <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:part">
+        <xs:import namespace="urn:score" schemaLocation="score.xsd"/>
+        <xs:complexType name="part"/>
+      </xs:schema>
+    "#
+      .to_string(),
+    ),
+  ]);
+
+  let xsd = Xsd::new_from_file_with("score.xsd", &options).unwrap();
+  let error = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap_err();
+  assert_eq!(error.error_code(), "circular-import");
+  assert_eq!(
+    error.to_string(),
+    "Circular import: score.xsd -> part.xsd -> score.xsd"
+  );
+}
+
+#[test]
+fn output_does_not_depend_on_the_platform() {
+  let score = r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:l="urn:link">
+  <xs:import namespace="urn:link" schemaLocation="common/link.xsd"/>
+  <xs:complexType name="credit">
+    <xs:annotation>
+      <xs:documentation>A credit
+spanning lines.</xs:documentation>
+    </xs:annotation>
+    <xs:attribute ref="l:href"/>
+  </xs:complexType>
+</xs:schema>
+"#;
+  let link = r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:link">
+  <xs:attribute name="href" type="xs:anyURI">
+    <xs:annotation>
+      <xs:documentation>The target
+of the link.</xs:documentation>
+    </xs:annotation>
+  </xs:attribute>
+</xs:schema>
+"#;
+
+  let unix = memory_options([
+    ("score.xsd", score.to_string()),
+    ("common/link.xsd", link.to_string()),
+  ]);
+  let windows = memory_options([
+    (
+      "score.xsd",
+      score
+        .replace("common/link.xsd", r"common\link.xsd")
+        .replace('\n', "\r\n"),
+    ),
+    ("common/link.xsd", link.replace('\n', "\r\n")),
+  ]);
+  let generate = |options| {
+    let xsd = Xsd::new_from_file_with("score.xsd", &options).unwrap();
+    xsd
+      .generate_with(&crate::GeneratorOptions::default())
+      .unwrap()
+  };
+
+  let output = generate(unix);
+  assert!(output.contains("/// A credit\n/// spanning lines.\n"));
+  assert!(output.contains("/// The target\n/// of the link.\n"));
+  assert_eq!(generate(windows), output);
+}
+
+#[test]
+fn relative_locations_are_read_from_the_base_dir() {
+  let write_schemas = |attribute: &str| {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+      dir.path().join("score.xsd"),
+      r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:l="urn:link">
+  <xs:import namespace="urn:link" schemaLocation="link.xsd"/>
+  <xs:complexType name="credit">
+    <xs:attribute ref="l:link"/>
+  </xs:complexType>
+</xs:schema>"#,
+    )
+    .unwrap();
+    std::fs::write(
+      dir.path().join("link.xsd"),
+      format!(
+        r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:link">
+  <xs:attribute name="link" type="xs:{attribute}"/>
+</xs:schema>"#
+      ),
+    )
+    .unwrap();
+    dir
+  };
+  let generate = |dir: &tempfile::TempDir| {
+    let options = crate::LoadOptions {
+      offline: true,
+      base_dir: Some(dir.path().to_path_buf()),
+      ..Default::default()
+    };
+    let xsd = Xsd::new_from_file_with("score.xsd", &options).unwrap();
+    xsd
+      .generate_with(&crate::GeneratorOptions::default())
+      .unwrap()
+  };
+
+  // The schemas of both directories have the same relative locations.
+  let uris = write_schemas("anyURI");
+  let numbers = write_schemas("int");
+  let (uri, number) = std::thread::scope(|scope| {
+    let uri = scope.spawn(|| generate(&uris));
+    let number = scope.spawn(|| generate(&numbers));
+    (uri.join().unwrap(), number.join().unwrap())
+  });
+  assert!(uri.contains("pub type Link = String;"), "{uri}");
+  assert!(number.contains("pub type Link = i32;"), "{number}");
+}
+
+#[test]
+fn imports_are_read_from_the_directory_of_the_importing_document() {
+  let dir = tempfile::tempdir().unwrap();
+  let write = |path: &str, content: &str| {
+    let path = dir.path().join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+  };
+  write(
+    "score/score.xsd",
+    r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:l="urn:link">
+  <xs:import namespace="urn:link" schemaLocation="../common/link.xsd"/>
+  <xs:complexType name="credit">
+    <xs:attribute ref="l:link"/>
+  </xs:complexType>
+</xs:schema>"#,
+  );
+  write(
+    "common/link.xsd",
+    r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:link" xmlns:u="urn:units">
+  <xs:import namespace="urn:units" schemaLocation="units.xsd"/>
+  <xs:attribute name="link" type="u:target"/>
+</xs:schema>"#,
+  );
+  write(
+    "common/units.xsd",
+    r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:units">
+  <xs:simpleType name="target">
+    <xs:restriction base="xs:anyURI"/>
+  </xs:simpleType>
+</xs:schema>"#,
+  );
+  // Same relative location as the import of `link.xsd`, from the directory of `score.xsd`.
+  write("score/units.xsd", "not a schema");
+
+  let score = dir.path().join("score/score.xsd");
+  let xsd =
+    Xsd::new_from_file_with(score.to_str().unwrap(), &crate::LoadOptions::default()).unwrap();
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap();
+  assert!(output.contains("pub type Link = Target;"), "{output}");
+}
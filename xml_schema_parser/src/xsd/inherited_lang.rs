@@ -0,0 +1,86 @@
+use xsd_codegen::{Field, Fields, Type};
+use xsd_types::XsdType;
+
+use super::{
+  regenerate_xsdgen,
+  xsd_context::{XsdContext, XsdImpl, XsdImplType},
+};
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Name of the field holding the inherited language.
+const FIELD_NAME: &str = "xml_lang";
+
+/// Whether `fields` read the `xml:lang` attribute, themselves or through an attribute group.
+fn references_lang(fields: &Fields, context: &XsdContext) -> bool {
+  fields.fields().iter().any(|field| {
+    let is_lang = field.xml_name.as_ref().is_some_and(|name| {
+      name.namespace.as_deref() == Some(XML_NAMESPACE) && name.local_name == "lang"
+    });
+    let group = field
+      .provenance
+      .as_ref()
+      .filter(|provenance| provenance.name.ty == XsdType::AttributeGroup)
+      .and_then(|provenance| context.structs.get(&provenance.name));
+
+    is_lang
+      || group.is_some_and(|group| match &group.element {
+        XsdImplType::Struct(r#struct) => references_lang(&r#struct.fields, context),
+        _ => false,
+      })
+  })
+}
+
+/// Add the `xml_lang` field to the struct of `imp` and of its inner types when they read
+/// `xml:lang`. Returns whether one was added.
+fn add_field(imp: &mut XsdImpl, context: &XsdContext) -> bool {
+  let mut added = false;
+  for inner in &mut imp.inner {
+    added |= add_field(inner, context);
+  }
+
+  let XsdImplType::Struct(r#struct) = &mut imp.element else {
+    return added;
+  };
+  if !matches!(r#struct.fields, Fields::Named(_))
+    || r#struct
+      .fields
+      .fields()
+      .iter()
+      .any(|field| field.name() == FIELD_NAME)
+    || !references_lang(&r#struct.fields, context)
+  {
+    return added;
+  }
+
+  let mut field = Field::new(
+    None,
+    FIELD_NAME,
+    Type::new(None, "Option<String>"),
+    true,
+    false,
+  )
+  .vis("pub")
+  .value("element.effective_lang().map(str::to_string)");
+  field.doc(vec![
+    "The `xml:lang` in scope, set on the element or inherited from its ancestors.",
+  ]);
+  r#struct.push_field(field);
+  *imp = regenerate_xsdgen(imp.clone(), context);
+  true
+}
+
+/// Add an `xml_lang` field holding the language in scope to the structs reading `xml:lang`, see
+/// `GeneratorOptions::inherited_lang`. The attribute groups are left as is.
+pub(crate) fn add_inherited_lang(context: &mut XsdContext) {
+  let updated: Vec<_> = context
+    .structs
+    .iter()
+    .filter(|(name, _)| name.ty != XsdType::AttributeGroup)
+    .filter_map(|(name, imp)| {
+      let mut imp = imp.clone();
+      add_field(&mut imp, context).then(|| (name.clone(), imp))
+    })
+    .collect();
+  context.structs.extend(updated);
+}
@@ -0,0 +1,616 @@
+use xsd_codegen::{Fields, Function, Impl, Item, Scope, Struct, Type};
+use xsd_types::{to_field_name, to_struct_name, XsdName};
+
+use super::{
+  doc_links::{link_path, Path, Types},
+  identity_constraint::{self, ConstraintKind, IdentityConstraint, NameTest, Step},
+  Warning,
+};
+
+/// The identity constraints of a top-level element, and the path of the type of its documents.
+pub(crate) struct DocumentKeys {
+  pub element: XsdName,
+  pub root: Path,
+  pub constraints: Vec<IdentityConstraint>,
+}
+
+/// A field read from an element or an attribute, reached through the flattened fields holding
+/// it.
+struct Hop {
+  /// The path of the struct holding the field.
+  at: Path,
+  field: String,
+  ty: Type,
+}
+
+/// A key or a keyref mapped onto the generated types.
+struct Mapped {
+  /// The path of the type of the selected values.
+  target: Path,
+  /// The selected values of the document, e.g. `self.part.iter().flat_map(|v| v.note.iter())`.
+  selected: String,
+  /// The value of each field of a selected value, e.g. `v.staff?`, and its type.
+  fields: Vec<(String, Type)>,
+}
+
+impl Mapped {
+  /// The expression building the key `key_type` of a selected value.
+  fn key(&self, key_type: &str) -> String {
+    let fields: Vec<&str> = self
+      .fields
+      .iter()
+      .map(|(value, _)| value.as_str())
+      .collect();
+    format!("{key_type}({})", fields.join(", "))
+  }
+
+  fn has_optional_fields(&self) -> bool {
+    self.fields.iter().any(|(value, _)| value.contains('?'))
+  }
+}
+
+/// The name of the methods of the constraint `name`, e.g. `note_staff`.
+fn method_name(name: &XsdName) -> String {
+  to_field_name(&name.local_name)
+    .trim_start_matches("r#")
+    .to_string()
+}
+
+/// The types the values of the fields of a key can have: the ones that are `Ord` and `Hash`.
+fn is_key_component(ty: &Type) -> Option<bool> {
+  let copy = match (ty.name.as_str(), ty.generics.as_slice()) {
+    ("String", []) => false,
+    ("Box", [inner]) if inner.name == "str" => false,
+    (
+      "bool" | "char" | "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32"
+      | "u64" | "u128" | "usize",
+      [],
+    ) => true,
+    _ => return None,
+  };
+  Some(copy)
+}
+
+/// Whether the name of the XML field `xml_name` passes `test`. The namespaces are only compared
+/// when both are known.
+fn name_matches(xml_name: &XsdName, test: &XsdName) -> bool {
+  xml_name.local_name == test.local_name
+    && match (&xml_name.namespace, &test.namespace) {
+      (Some(namespace), Some(expected)) => namespace == expected,
+      _ => true,
+    }
+}
+
+struct Mapper<'a> {
+  scope: &'a Scope,
+  types: Types,
+}
+
+impl Mapper<'_> {
+  /// The struct at `path`.
+  fn find_struct(&self, path: &[String]) -> Option<&Struct> {
+    let (name, modules) = path.split_last()?;
+    let mut scope = self.scope;
+    for module in modules {
+      scope = &scope.get_module(module.as_str())?.scope;
+    }
+    scope.items.iter().find_map(|item| match item {
+      Item::Struct(r#struct) if &r#struct.ty().name == name => Some(r#struct),
+      _ => None,
+    })
+  }
+
+  /// The path of the generated type `ty` holds through its `Option`, `Vec` and the like, as
+  /// written in the module of the struct at `at`.
+  fn resolve(&self, at: &[String], ty: &Type) -> Option<Path> {
+    self.types.resolve(&at[..at.len() - 1], &peel(ty).0.name)
+  }
+
+  /// The fields of the struct at `at` reading the element or the attribute `name`, looked up
+  /// through its flattened fields.
+  fn hops(&self, at: &[String], name: &XsdName, attribute: bool) -> Result<Vec<Hop>, String> {
+    let Some(r#struct) = self.find_struct(at) else {
+      return Err(format!("`{}` is not a struct", at.join("::")));
+    };
+    self
+      .find_hops(at, r#struct, name, attribute)
+      .ok_or_else(|| {
+        let kind = if attribute { "attribute" } else { "element" };
+        format!(
+          "`{}` has no field read from the {kind} `{}`",
+          r#struct.ty().name,
+          name.local_name
+        )
+      })
+  }
+
+  fn find_hops(
+    &self,
+    at: &[String],
+    r#struct: &Struct,
+    name: &XsdName,
+    attribute: bool,
+  ) -> Option<Vec<Hop>> {
+    let Fields::Named(fields) = &r#struct.fields else {
+      return None;
+    };
+    let hop = |field: &xsd_codegen::Field| Hop {
+      at: at.to_vec(),
+      field: field.name.clone().unwrap_or_default(),
+      ty: field.ty.clone(),
+    };
+
+    let named = fields.iter().find(|field| {
+      !field.flatten
+        && field.attribute == attribute
+        && field
+          .xml_name
+          .as_ref()
+          .is_some_and(|xml_name| name_matches(xml_name, name))
+    });
+    if let Some(field) = named {
+      return Some(vec![hop(field)]);
+    }
+
+    fields
+      .iter()
+      .filter(|field| field.flatten)
+      .find_map(|field| {
+        let (_, wrappers) = peel(&field.ty);
+        if wrappers.iter().any(|wrapper| *wrapper != "Box") {
+          return None;
+        }
+        let path = self.resolve(at, &field.ty)?;
+        let inner = self.find_struct(&path)?;
+        let mut hops = vec![hop(field)];
+        hops.extend(self.find_hops(&path, inner, name, attribute)?);
+        Some(hops)
+      })
+  }
+
+  /// The iterator over the values selected by `selector` from the document at `root`.
+  fn select(
+    &self,
+    root: &[String],
+    selector: &identity_constraint::Path,
+  ) -> Result<Mapped, String> {
+    let mut at = root.to_vec();
+    let mut selected: Option<String> = None;
+    for step in &selector.steps {
+      let name = match step {
+        Step::Current => continue,
+        Step::Child(NameTest::Name(name)) => name,
+        Step::Child(_) | Step::Attribute(_) => {
+          return Err("the selector has a wildcard or an attribute".to_string())
+        }
+      };
+
+      for Hop {
+        at: holder,
+        field,
+        ty,
+      } in self.hops(&at, name, false)?
+      {
+        let (_, wrappers) = peel(&ty);
+        let mut wrappers = wrappers.into_iter();
+        let code = match (&selected, wrappers.next()) {
+          (None, Some("Box")) => format!("std::iter::once(&*self.{field})"),
+          (None, Some(_)) => format!("self.{field}.iter()"),
+          (None, None) => format!("std::iter::once(&self.{field})"),
+          (Some(code), Some("Box")) => format!("{code}.map(|v| &*v.{field})"),
+          (Some(code), Some(_)) => format!("{code}.flat_map(|v| v.{field}.iter())"),
+          (Some(code), None) => format!("{code}.map(|v| &v.{field})"),
+        };
+        selected = Some(wrappers.fold(code, |code, wrapper| match wrapper {
+          "Box" => format!("{code}.map(|v| &**v)"),
+          _ => format!("{code}.flat_map(|v| v.iter())"),
+        }));
+        at = self.resolve(&holder, &ty).ok_or_else(|| {
+          format!(
+            "the element `{}` is not read into a struct",
+            name.local_name
+          )
+        })?;
+      }
+    }
+
+    Ok(Mapped {
+      selected: selected.unwrap_or_else(|| "std::iter::once(self)".to_string()),
+      target: at,
+      fields: vec![],
+    })
+  }
+
+  /// The value of `field` read from `value`, a value of the struct at `at`, and its type.
+  fn field(
+    &self,
+    at: &[String],
+    value: &str,
+    field: &identity_constraint::Path,
+  ) -> Result<(String, Type), String> {
+    let mut at = at.to_vec();
+    let mut hops = vec![];
+    for step in &field.steps {
+      let (name, attribute) = match step {
+        Step::Current => continue,
+        Step::Child(NameTest::Name(name)) => (name, false),
+        Step::Attribute(NameTest::Name(name)) => (name, true),
+        Step::Child(_) | Step::Attribute(_) => return Err("a field has a wildcard".to_string()),
+      };
+      if let Some(Hop {
+        at: holder,
+        field,
+        ty,
+      }) = hops.last()
+      {
+        at = self
+          .resolve(holder, ty)
+          .ok_or_else(|| format!("the field `{field}` doesn't hold a struct"))?;
+      }
+      hops.extend(self.hops(&at, name, attribute)?);
+    }
+
+    let Some(last) = hops.last() else {
+      return Err("a field selects the element itself".to_string());
+    };
+
+    // `optional` when `code` is an `Option`, of a reference unless it's the last field.
+    let mut code = value.to_string();
+    let mut optional = false;
+    for (index, Hop { field, ty, .. }) in hops.iter().enumerate() {
+      if optional {
+        code.push('?');
+      }
+      code.push('.');
+      code.push_str(field);
+      optional = match peel(ty).1.as_slice() {
+        [] | ["Box"] => false,
+        ["Option"] | ["Option", "Box"] => true,
+        _ => return Err(format!("the field `{field}` holds several values")),
+      };
+      if optional && index + 1 < hops.len() {
+        code.push_str(".as_ref()");
+      }
+    }
+
+    // The values of the generated simple types are read from their tuple field.
+    let not_a_key = || format!("the type of the field `{}` can't be a key", last.field);
+    let mut ty = peel(&last.ty).0.clone();
+    let mut at = last.at.clone();
+    let mut unwraps = 0;
+    let copy = loop {
+      if let Some(copy) = is_key_component(&ty) {
+        break copy;
+      }
+      let path = self.resolve(&at, &ty).ok_or_else(not_a_key)?;
+      let Some(Struct {
+        fields: Fields::Tuple(fields),
+        ..
+      }) = self.find_struct(&path)
+      else {
+        return Err(not_a_key());
+      };
+      let [inner] = fields.as_slice() else {
+        return Err(not_a_key());
+      };
+      ty = inner.ty.clone();
+      at = path;
+      unwraps += 1;
+    };
+    if unwraps == 0 && peel(&last.ty).1.contains(&"Box") {
+      return Err(not_a_key());
+    }
+
+    if unwraps > 0 {
+      if optional {
+        code.push_str(".as_ref()?");
+        optional = false;
+      }
+      code.push_str(&".0".repeat(unwraps));
+    }
+    if !copy {
+      code.push_str(".clone()");
+    }
+    if optional {
+      code.push('?');
+    }
+    Ok((code, ty))
+  }
+
+  /// Map `constraint` onto the types of the document at `root`.
+  fn map(
+    &self,
+    root: &[String],
+    constraint: &IdentityConstraint,
+    value: &str,
+  ) -> Result<Mapped, String> {
+    let [selector] = constraint.selector.as_slice() else {
+      return Err("the selector has alternatives".to_string());
+    };
+    if selector.descendants
+      || constraint
+        .fields
+        .iter()
+        .flatten()
+        .any(|path| path.descendants)
+    {
+      return Err("a path starts with `.//`".to_string());
+    }
+
+    let mut mapped = self.select(root, selector)?;
+    for field in &constraint.fields {
+      let [field] = field.as_slice() else {
+        return Err("a field has alternatives".to_string());
+      };
+      let field = self.field(&mapped.target, value, field)?;
+      mapped.fields.push(field);
+    }
+    Ok(mapped)
+  }
+}
+
+/// `ty` without its `Option`, `Vec`, `RestrictedVec` and `Box`, along with their names from the
+/// outermost. `Box<str>` is kept.
+fn peel(ty: &Type) -> (&Type, Vec<&'static str>) {
+  let wrapper = match (ty.name.as_str(), ty.generics.as_slice()) {
+    ("Box", [inner]) if inner.name == "str" => None,
+    ("Option", [inner]) => Some(("Option", inner)),
+    ("Vec", [inner]) => Some(("Vec", inner)),
+    ("RestrictedVec", [inner]) => Some(("RestrictedVec", inner)),
+    ("Box", [inner]) => Some(("Box", inner)),
+    _ => None,
+  };
+  match wrapper {
+    Some((name, inner)) => {
+      let (held, mut wrappers) = peel(inner);
+      wrappers.insert(0, name);
+      (held, wrappers)
+    }
+    None => (ty, vec![]),
+  }
+}
+
+/// The location of `constraint` of the top-level element `element` in the schema.
+fn location(element: &XsdName, constraint: &IdentityConstraint) -> String {
+  let tag = match constraint.kind {
+    ConstraintKind::Unique => "unique",
+    ConstraintKind::Key => "key",
+    ConstraintKind::KeyRef { .. } => "keyref",
+  };
+  format!(
+    "/schema/element[@name=\"{}\"]/{tag}[@name=\"{}\"]",
+    element.local_name, constraint.name.local_name
+  )
+}
+
+/// The items resolving the keys of `document` its keyrefs refer to: the methods of the document
+/// type, then each key type with its constructors from the values of the keyrefs.
+fn resolvers(mapper: &Mapper, document: &DocumentKeys, warnings: &mut Vec<Warning>) -> Vec<Item> {
+  let module = &document.root[..document.root.len() - 1];
+  let root_name = &document.root[document.root.len() - 1];
+  let mut warn = |constraint: &IdentityConstraint, reason: String| {
+    warnings.push(Warning {
+      path: location(&document.element, constraint),
+      msg: format!("no resolver for `{}`: {reason}", constraint.name.local_name),
+    })
+  };
+
+  let is_keyref =
+    |constraint: &&IdentityConstraint| matches!(constraint.kind, ConstraintKind::KeyRef { .. });
+  let refers = |keyref: &IdentityConstraint, key: &IdentityConstraint| matches!(&keyref.kind, ConstraintKind::KeyRef { refer } if *refer == key.name);
+  let (keyrefs, keys): (Vec<&IdentityConstraint>, Vec<&IdentityConstraint>) =
+    document.constraints.iter().partition(is_keyref);
+  for keyref in &keyrefs {
+    if !keys.iter().any(|key| refers(keyref, key)) {
+      warn(
+        keyref,
+        format!(
+          "the key it refers to is not declared on `{}`",
+          document.element.local_name
+        ),
+      );
+    }
+  }
+
+  let mut root_impl = Impl::new(Type::new(None, root_name));
+  let mut items = vec![];
+  for key in keys {
+    let keyrefs: Vec<&IdentityConstraint> = keyrefs
+      .iter()
+      .copied()
+      .filter(|keyref| refers(keyref, key))
+      .collect();
+    if keyrefs.is_empty() {
+      continue;
+    }
+
+    let key_type = format!("{}Key", to_struct_name(&key.name.local_name));
+    let mut key_path = module.to_vec();
+    key_path.push(key_type.clone());
+    if mapper.types.paths.contains(&key_path) {
+      warn(key, format!("`{key_type}` is already generated"));
+      continue;
+    }
+    let mapped = match mapper.map(&document.root, key, "v") {
+      Ok(mapped) => mapped,
+      Err(reason) => {
+        warn(key, reason);
+        continue;
+      }
+    };
+
+    let name = method_name(&key.name);
+    let target = link_path(module, &mapped.target);
+    let entries = if mapped.has_optional_fields() {
+      format!(
+        "{}.filter_map(|v| Some(({}, v)))",
+        mapped.selected,
+        mapped.key(&key_type)
+      )
+    } else {
+      format!(
+        "{}.map(|v| ({}, v))",
+        mapped.selected,
+        mapped.key(&key_type)
+      )
+    };
+    root_impl = root_impl
+      .push_fn(
+        Function::new(&format!("{name}_entries"))
+          .arg_ref_self()
+          .ret(format!("impl Iterator<Item = ({key_type}, &{target})> + '_").as_str())
+          .line(entries),
+      )
+      .push_fn(
+        Function::new(&format!("{name}_index"))
+          .doc(&format!(
+            "The `{target}` of the document by their `{}` key, indexed on the first lookup.",
+            key.name.local_name
+          ))
+          .vis("pub")
+          .arg_ref_self()
+          .ret(format!("KeyIndex<'_, {key_type}, {target}>").as_str())
+          .line(format!("KeyIndex::new(self.{name}_entries())")),
+      )
+      .push_fn(
+        Function::new(&format!("resolve_{name}"))
+          .doc(&format!(
+            "The `{target}` with the `{}` key `key`, the first one when several have it. Looking many keys up is faster with `{name}_index`.",
+            key.name.local_name
+          ))
+          .vis("pub")
+          .arg_ref_self()
+          .arg("key", Type::new(None, &format!("&{key_type}")))
+          .ret(format!("Option<&{target}>").as_str())
+          .line(format!(
+            "self.{name}_entries().find(|(entry, _)| entry == key).map(|(_, value)| value)"
+          )),
+      );
+
+    let mut key_struct = Struct::new(None, &key_type).vis("pub").derives(&[
+      "Clone",
+      "Debug",
+      "PartialEq",
+      "Eq",
+      "PartialOrd",
+      "Ord",
+      "Hash",
+    ]);
+    key_struct.doc(&format!(
+      "The value of the `{}` key of `{}`, identifying a `{target}`.",
+      key.name.local_name, document.element.local_name
+    ));
+    for (_, ty) in &mapped.fields {
+      key_struct = key_struct.tuple_field(Some("pub"), ty.clone(), false, false);
+    }
+
+    let mut key_impl = Impl::new(Type::new(None, &key_type));
+    for keyref in keyrefs {
+      let referring = match mapper.map(&document.root, keyref, "value") {
+        Ok(referring) => referring,
+        Err(reason) => {
+          warn(keyref, reason);
+          continue;
+        }
+      };
+      let types = |mapped: &Mapped| {
+        mapped
+          .fields
+          .iter()
+          .map(|(_, ty)| ty.to_string())
+          .collect::<Vec<_>>()
+      };
+      if types(&referring) != types(&mapped) {
+        warn(
+          keyref,
+          format!(
+            "its fields don't have the types of the fields of `{}`",
+            key.name.local_name
+          ),
+        );
+        continue;
+      }
+
+      let keyref_name = method_name(&keyref.name);
+      let source = link_path(module, &referring.target);
+      key_impl = key_impl.push_fn(
+        Function::new(&format!("from_{keyref_name}"))
+          .doc(&format!(
+            "The key `value` refers to through the `{}` keyref, `None` when it lacks one of its fields.",
+            keyref.name.local_name
+          ))
+          .vis("pub")
+          .arg("value", Type::new(None, &format!("&{source}")))
+          .ret("Option<Self>")
+          .line(format!("Some({})", referring.key("Self"))),
+      );
+      root_impl = root_impl.push_fn(
+        Function::new(&format!("resolve_{keyref_name}"))
+          .doc(&format!(
+            "The `{target}` `value` refers to through the `{}` keyref.",
+            keyref.name.local_name
+          ))
+          .vis("pub")
+          .arg_ref_self()
+          .arg("value", Type::new(None, &format!("&{source}")))
+          .ret(format!("Option<&{target}>").as_str())
+          .line(format!(
+            "self.resolve_{name}(&{key_type}::from_{keyref_name}(value)?)"
+          )),
+      );
+    }
+
+    items.push(Item::Struct(key_struct));
+    items.push(Item::Impl(key_impl));
+  }
+
+  if !items.is_empty() {
+    items.insert(0, Item::Impl(root_impl));
+  }
+  items
+}
+
+/// Follow the type of each document of `documents` and its impls with the resolvers of the keys
+/// its keyrefs refer to, see `GeneratorOptions::key_resolvers`. The keys and keyrefs whose paths
+/// can't be mapped onto the generated fields are reported.
+pub(crate) fn add_key_resolvers(scope: &mut Scope, documents: &[DocumentKeys]) -> Vec<Warning> {
+  let mut types = Types::default();
+  types.collect(scope, &[]);
+  let mapper = Mapper { scope, types };
+
+  let mut warnings = vec![];
+  let resolved: Vec<(&Path, Vec<Item>)> = documents
+    .iter()
+    .map(|document| (&document.root, resolvers(&mapper, document, &mut warnings)))
+    .filter(|(_, items)| !items.is_empty())
+    .collect();
+
+  for (root, resolvers) in resolved {
+    let (name, modules) = root.split_last().unwrap();
+    let mut target = &mut *scope;
+    for module in modules {
+      target = target.get_module_mut(module.as_str()).unwrap().scope();
+    }
+    let Some(position) = target
+      .items
+      .iter()
+      .position(|item| matches!(item, Item::Struct(r#struct) if &r#struct.ty().name == name))
+    else {
+      continue;
+    };
+    let end = position
+      + 1
+      + target.items[position + 1..]
+        .iter()
+        .take_while(
+          |item| matches!(item, Item::Impl(implementation) if &implementation.target.name == name),
+        )
+        .count();
+    target.items.splice(end..end, resolvers);
+  }
+
+  for warning in &warnings {
+    tracing::warn!("{}: {}", warning.path, warning.msg);
+  }
+  warnings
+}
@@ -1,5 +1,5 @@
-use xsd_codegen::{fromxml_impl, Block, Struct, Type, XMLElement};
-use xsd_types::{XsdIoError, XsdName, XsdType};
+use xsd_codegen::{fromxml_impl, Block, ContentKind, Struct, Type, XMLElement};
+use xsd_types::{SchemaError, XsdName, XsdType};
 
 use crate::xsd::XsdContext;
 
@@ -14,13 +14,13 @@ pub struct List {
 }
 
 impl List {
-  pub fn parse(mut element: XMLElement) -> Result<Self, XsdIoError> {
+  pub fn parse(mut element: XMLElement) -> Result<Self, SchemaError> {
     element.check_name("list")?;
 
     let item_type: String = element.get_attribute("itemType")?;
 
     let output = Self {
-      item_type: element.new_name(&item_type, XsdType::SimpleType),
+      item_type: element.new_qname(&item_type, XsdType::SimpleType),
     };
 
     element.finalize(false, false)?;
@@ -32,7 +32,7 @@ impl List {
   pub fn get_implementation(
     &self,
     name: XsdName,
-    context: &mut XsdContext,
+    context: &XsdContext,
   ) -> Result<XsdImpl, XsdError> {
     let struct_name = name.to_struct_name();
     let inner = if let Some(imp) = context.search(&self.item_type) {
@@ -70,6 +70,8 @@ impl List {
       inner: vec![],
       implementation: vec![from_xml],
       flatten: false,
+      content: ContentKind::Simple,
+      attribute_groups: vec![],
     })
   }
 }
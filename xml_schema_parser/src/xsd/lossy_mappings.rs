@@ -0,0 +1,215 @@
+use xsd_types::{XsdName, XsdType};
+
+use super::xsd_context::{XsdContext, XsdImplType};
+
+const XML_SCHEMA: &str = "http://www.w3.org/2001/XMLSchema";
+
+/// Attributes holding (whitespace separated) type names.
+const TYPE_REFERENCES: &[&str] = &["type", "base", "itemType", "memberTypes"];
+
+/// Builtin types whose default mapping can't hold all their values, along with that mapping and
+/// what it loses.
+const LOSSY: &[(&str, &str, &str)] = &[
+  (
+    "integer",
+    "i32",
+    "values outside of the i32 range are rejected",
+  ),
+  (
+    "nonNegativeInteger",
+    "u64",
+    "values outside of the u64 range are rejected",
+  ),
+  (
+    "positiveInteger",
+    "u64",
+    "values outside of the u64 range are rejected",
+  ),
+  (
+    "decimal",
+    "Decimal",
+    "values with more than 38 digits are rejected",
+  ),
+  ("byte", "u8", "negative values are rejected"),
+  (
+    "dateTime",
+    "xml_schema_parser::DateTime",
+    "the digits of the seconds past the nanoseconds are dropped",
+  ),
+  (
+    "dateTimeStamp",
+    "xml_schema_parser::DateTime",
+    "the digits of the seconds past the nanoseconds are dropped",
+  ),
+  (
+    "time",
+    "xml_schema_parser::Time",
+    "the digits of the seconds past the nanoseconds are dropped",
+  ),
+];
+
+/// A reference to a builtin type read into a Rust type that can't hold all its values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LossyMapping {
+  /// Location of the reference, e.g. `/schema/complexType[@name="note"]/attribute[@name="x"]`.
+  pub path: String,
+  /// The local name of the builtin type, e.g. `integer`.
+  pub builtin: String,
+  /// The Rust type it is read into, e.g. `i32`.
+  pub ty: String,
+  /// What the mapping loses.
+  pub loss: String,
+}
+
+/// Collect the references of a schema document to the builtin types with a lossy mapping.
+pub(crate) fn scan(schema: &xmltree::Element) -> Vec<LossyMapping> {
+  let mut uses = vec![];
+  scan_element(schema, "", &mut uses);
+  uses
+}
+
+fn scan_element(element: &xmltree::Element, parent: &str, uses: &mut Vec<LossyMapping>) {
+  if element.namespace.as_deref() != Some(XML_SCHEMA) || element.name == "annotation" {
+    return;
+  }
+
+  let path = match element
+    .attributes
+    .get("name")
+    .map(|name| ("name", name))
+    .or_else(|| element.attributes.get("ref").map(|name| ("ref", name)))
+  {
+    Some((attribute, name)) => format!("{parent}/{}[@{attribute}=\"{name}\"]", element.name),
+    None => format!("{parent}/{}", element.name),
+  };
+
+  for attribute in TYPE_REFERENCES {
+    let names = element.attributes.get(*attribute).into_iter();
+    for name in names.flat_map(|names| names.split_whitespace()) {
+      let (prefix, local_name) = name.split_once(':').unwrap_or(("", name));
+      let namespace = element
+        .namespaces
+        .as_ref()
+        .and_then(|namespaces| namespaces.get(prefix));
+      if namespace != Some(XML_SCHEMA) {
+        continue;
+      }
+      if let Some((builtin, ty, loss)) = LOSSY.iter().find(|(builtin, ..)| *builtin == local_name) {
+        uses.push(LossyMapping {
+          path: path.clone(),
+          builtin: builtin.to_string(),
+          ty: ty.to_string(),
+          loss: loss.to_string(),
+        });
+      }
+    }
+  }
+
+  for child in element
+    .children
+    .iter()
+    .filter_map(|child| child.as_element())
+  {
+    scan_element(child, &path, uses);
+  }
+}
+
+/// The uses whose builtin type is still read into its lossy mapping, i.e. is not overridden by
+/// `GeneratorOptions::builtin_types`.
+pub(crate) fn check(uses: &[LossyMapping], context: &XsdContext) -> Vec<LossyMapping> {
+  uses
+    .iter()
+    .filter(|used| {
+      let name = XsdName {
+        namespace: Some(XML_SCHEMA.to_string()),
+        local_name: used.builtin.clone(),
+        ty: XsdType::SimpleType,
+      };
+      matches!(
+        context.structs.get(&name).map(|imp| &imp.element),
+        Some(XsdImplType::Type(ty)) if ty.to_string() == used.ty
+      )
+    })
+    .cloned()
+    .collect()
+}
+
+#[cfg(test)]
+const SCORE: &str = r#"
+  <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+    <xs:simpleType name="tempo">
+      <xs:restriction base="xs:decimal"/>
+    </xs:simpleType>
+    <xs:element name="sound">
+      <xs:complexType>
+        <xs:attribute name="tempo" type="tempo"/>
+        <xs:attribute name="dynamics" type="xs:integer"/>
+        <xs:attribute name="pan" type="xs:int"/>
+      </xs:complexType>
+    </xs:element>
+  </xs:schema>
+"#;
+
+#[test]
+fn lossy_mappings_are_reported() {
+  let xsd = crate::Xsd::new(SCORE).unwrap();
+  let (_, report) = xsd.generate_with_report(&Default::default()).unwrap();
+
+  assert_eq!(
+    report.lossy_mappings,
+    [
+      LossyMapping {
+        path: r#"/schema/simpleType[@name="tempo"]/restriction"#.to_string(),
+        builtin: "decimal".to_string(),
+        ty: "Decimal".to_string(),
+        loss: "values with more than 38 digits are rejected".to_string(),
+      },
+      LossyMapping {
+        path: r#"/schema/element[@name="sound"]/complexType/attribute[@name="dynamics"]"#
+          .to_string(),
+        builtin: "integer".to_string(),
+        ty: "i32".to_string(),
+        loss: "values outside of the i32 range are rejected".to_string(),
+      },
+    ]
+  );
+
+  let strict = crate::GeneratorOptions {
+    strict_mappings: true,
+    ..Default::default()
+  };
+  let Err(super::XsdError::LossyMapping { uses }) = xsd.generate_with(&strict) else {
+    panic!("lossy mappings are errors in strict mode");
+  };
+  assert_eq!(uses, report.lossy_mappings);
+}
+
+#[test]
+fn builtin_types_override_the_lossy_mappings() {
+  let xsd = crate::Xsd::new(SCORE).unwrap();
+  let options = crate::GeneratorOptions {
+    builtin_types: [("integer".to_string(), "i64".to_string())].into(),
+    ..Default::default()
+  };
+  let (output, report) = xsd.generate_with_report(&options).unwrap();
+
+  assert!(output.contains("pub dynamics: Option<i64>,"), "{output}");
+  assert_eq!(
+    report
+      .lossy_mappings
+      .iter()
+      .map(|used| used.builtin.as_str())
+      .collect::<Vec<_>>(),
+    ["decimal"]
+  );
+
+  let unknown = crate::GeneratorOptions {
+    builtin_types: [("integr".to_string(), "i64".to_string())].into(),
+    ..Default::default()
+  };
+  let (_, report) = xsd.generate_with_report(&unknown).unwrap();
+  assert!(report
+    .warnings
+    .iter()
+    .any(|warning| warning.msg == "integr is not a builtin type, its override is ignored"));
+}
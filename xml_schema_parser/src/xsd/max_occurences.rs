@@ -1,9 +1,17 @@
-use xsd_codegen::FromXmlString;
+use xsd_codegen::{FromXmlString, Type, XMLElement};
+use xsd_types::{SchemaError, XsdName, XsdParseError};
+
+use super::Warning;
+
+const XML_SCHEMA: &str = "http://www.w3.org/2001/XMLSchema";
+
+/// The largest `maxOccurs` read as a bound when `GeneratorOptions::max_occurs_limit` is `None`.
+pub const DEFAULT_MAX_OCCURS_LIMIT: u64 = u32::MAX as u64;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum MaxOccurences {
   Unbounded,
-  Number { value: u32 },
+  Number { value: u64 },
 }
 
 impl Default for MaxOccurences {
@@ -12,13 +20,271 @@ impl Default for MaxOccurences {
   }
 }
 
+/// Role of a particle held in a vector, see `XsdImpl::wrap`.
+pub const ITEM: &str = "item";
+/// Role of an optional particle, see `XsdImpl::wrap`.
+pub const VALUE: &str = "value";
+
+impl MaxOccurences {
+  /// The bound the particle is generated with: unbounded above `limit`, see
+  /// `GeneratorOptions::max_occurs_limit`.
+  pub fn limited(&self, limit: u64) -> Self {
+    match self {
+      MaxOccurences::Number { value } if *value > limit => MaxOccurences::Unbounded,
+      max => max.clone(),
+    }
+  }
+
+  /// Whether the particle is prohibited, i.e. `maxOccurs` is 0. Nothing is generated for it.
+  pub fn is_prohibited(&self) -> bool {
+    *self == MaxOccurences::Number { value: 0 }
+  }
+
+  /// The role of a particle occurring between `min_occurences` and `self` times, `None` when it
+  /// occurs exactly once and is not wrapped.
+  pub fn role(&self, min_occurences: u64) -> Option<&'static str> {
+    match self {
+      MaxOccurences::Unbounded => Some(ITEM),
+      MaxOccurences::Number { value } if *value > 1 || min_occurences > 1 => Some(ITEM),
+      MaxOccurences::Number { value: 1 } if min_occurences == 0 => Some(VALUE),
+      MaxOccurences::Number { .. } => None,
+    }
+  }
+
+  /// The maximum number of values, `None` when unbounded.
+  pub fn bound(&self) -> Option<usize> {
+    match self {
+      MaxOccurences::Unbounded => None,
+      MaxOccurences::Number { value } => Some(usize::try_from(*value).unwrap_or(usize::MAX)),
+    }
+  }
+
+  /// The type holding between `min_occurences` and `self` values of `ty`. A `RestrictedVec` is
+  /// used when either bound restricts the number of values, with 0 standing for unbounded.
+  pub fn wrap_repeated(&self, ty: Type, min_occurences: u64) -> Type {
+    let min = min_occurences as usize;
+    let ty = match self.bound() {
+      None if min == 0 => ty.wrap("Vec"),
+      max => ty
+        .wrap("RestrictedVec")
+        .const_generic(min)
+        .const_generic(max.unwrap_or(0)),
+    };
+
+    ty.occurs(min, self.bound())
+  }
+}
+
 impl FromXmlString for MaxOccurences {
+  /// Values too large for a `u64` are read as `u64::MAX`, which is above any limit.
   fn from_xml(s: &str) -> Result<Self, String> {
+    let s = s.trim();
     if s == "unbounded" {
       Ok(MaxOccurences::Unbounded)
+    } else if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) {
+      Ok(MaxOccurences::Number {
+        value: s.parse().unwrap_or(u64::MAX),
+      })
     } else {
-      let number = s.parse::<u32>().map_err(|e| e.to_string())?;
-      Ok(MaxOccurences::Number { value: number })
+      Err(format!("`{s}` is not a non-negative integer or unbounded"))
+    }
+  }
+}
+
+/// Read the `minOccurs` and `maxOccurs` of the particle `element`, both 1 when absent. `owner`
+/// names the element or the group the particle is, if any. A minimum above the maximum is an
+/// error.
+pub fn parse_occurences(
+  element: &mut XMLElement,
+  owner: Option<&XsdName>,
+) -> Result<(u64, MaxOccurences), SchemaError> {
+  let node_name = element.node_name();
+  let error = |msg: String| {
+    let msg = match owner {
+      Some(owner) => format!("{msg} on `{}`", owner.local_name),
+      None => msg,
+    };
+    SchemaError::Invalid(XsdParseError {
+      node_name: node_name.clone(),
+      msg,
+    })
+  };
+
+  let min_occurences = match element.try_get_attribute::<String>("minOccurs")? {
+    Some(min) => min
+      .trim()
+      .parse()
+      .map_err(|_| error(format!("minOccurs `{min}` is not a non-negative integer")))?,
+    None => 1,
+  };
+  let max_occurences = match element.try_get_attribute::<String>("maxOccurs")? {
+    Some(max) => MaxOccurences::from_xml(&max).map_err(|e| error(format!("maxOccurs {e}")))?,
+    None => MaxOccurences::default(),
+  };
+
+  if let MaxOccurences::Number { value } = max_occurences {
+    if min_occurences > value {
+      return Err(error(format!(
+        "minOccurs {min_occurences} is greater than maxOccurs {value}"
+      )));
     }
   }
+  Ok((min_occurences, max_occurences))
+}
+
+/// A numeric `maxOccurs` of a schema document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaxOccursUse {
+  /// Location of the particle, e.g. `/schema/complexType[@name="part"]/sequence/element[@ref="note"]`.
+  pub path: String,
+  pub value: u64,
+}
+
+/// Collect the numeric `maxOccurs` of a schema document.
+pub(crate) fn scan(schema: &xmltree::Element) -> Vec<MaxOccursUse> {
+  let mut uses = vec![];
+  scan_element(schema, "", &mut uses);
+  uses
+}
+
+fn scan_element(element: &xmltree::Element, parent: &str, uses: &mut Vec<MaxOccursUse>) {
+  if element.namespace.as_deref() != Some(XML_SCHEMA) || element.name == "annotation" {
+    return;
+  }
+
+  let path = match element
+    .attributes
+    .get("name")
+    .map(|name| ("name", name))
+    .or_else(|| element.attributes.get("ref").map(|name| ("ref", name)))
+  {
+    Some((attribute, name)) => format!("{parent}/{}[@{attribute}=\"{name}\"]", element.name),
+    None => format!("{parent}/{}", element.name),
+  };
+
+  let max = element.attributes.get("maxOccurs");
+  if let Some(Ok(MaxOccurences::Number { value })) = max.map(|max| MaxOccurences::from_xml(max)) {
+    uses.push(MaxOccursUse {
+      path: path.clone(),
+      value,
+    });
+  }
+
+  for child in element
+    .children
+    .iter()
+    .filter_map(|child| child.as_element())
+  {
+    scan_element(child, &path, uses);
+  }
+}
+
+/// Report the `maxOccurs` above `limit`, which are read as unbounded.
+pub(crate) fn check(uses: &[MaxOccursUse], limit: u64) -> Vec<Warning> {
+  uses
+    .iter()
+    .filter(|used| used.value > limit)
+    .map(|used| Warning {
+      path: used.path.clone(),
+      msg: format!(
+        "maxOccurs {} is above {limit}, it is read as unbounded",
+        used.value
+      ),
+    })
+    .collect()
+}
+
+#[test]
+fn oversized_max_occurs_are_unbounded() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="part">
+        <xs:sequence>
+          <xs:element name="measure" type="xs:string" maxOccurs="99999999999"/>
+          <xs:element name="note" type="xs:string" maxOccurs="99999999999999999999999"/>
+        </xs:sequence>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let (output, report) = xsd
+    .generate_with_report(&crate::GeneratorOptions::default())
+    .unwrap();
+  assert!(output.contains("pub measure: RestrictedVec<String, 1, 0>,"));
+  assert!(output.contains("pub note: RestrictedVec<String, 1, 0>,"));
+  assert_eq!(
+    report.warnings,
+    vec![
+      Warning {
+        path: r#"/schema/complexType[@name="part"]/sequence/element[@name="measure"]"#.to_string(),
+        msg: "maxOccurs 99999999999 is above 4294967295, it is read as unbounded".to_string(),
+      },
+      Warning {
+        path: r#"/schema/complexType[@name="part"]/sequence/element[@name="note"]"#.to_string(),
+        msg: "maxOccurs 18446744073709551615 is above 4294967295, it is read as unbounded"
+          .to_string(),
+      },
+    ]
+  );
+
+  // The bounds have to fit the `usize` of the 32-bit targets.
+  let error = xsd
+    .generate_with_report(&crate::GeneratorOptions {
+      max_occurs_limit: Some(99999999999),
+      ..Default::default()
+    })
+    .unwrap_err();
+  assert_eq!(
+    error.to_string(),
+    "Invalid option max_occurs_limit: 99999999999 is above 4294967295"
+  );
+}
+
+#[test]
+fn min_occurs_above_max_occurs_is_an_error() {
+  let error = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="chord">
+        <xs:sequence>
+          <xs:element name="note" type="xs:string" minOccurs="3" maxOccurs="2"/>
+        </xs:sequence>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap_err();
+
+  assert!(
+    error
+      .to_string()
+      .contains("minOccurs 3 is greater than maxOccurs 2 on `note`"),
+    "{error}"
+  );
+}
+
+#[test]
+fn prohibited_particles_are_not_generated() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="note">
+        <xs:sequence>
+          <xs:element name="pitch" type="xs:string"/>
+          <xs:element name="unpitched" type="xs:string" minOccurs="0" maxOccurs="0"/>
+        </xs:sequence>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap();
+  assert!(output.contains("pub pitch: String,"));
+  assert!(!output.contains("unpitched"));
 }
@@ -1,56 +1,315 @@
+mod all;
 mod annotation;
 mod attribute;
 mod attribute_group;
+mod borrowed;
+mod cancellation;
+mod canonical;
 mod choice;
 mod complex_content;
 mod complex_type;
+mod conversion;
+mod derived_types;
+mod doc_links;
+mod drift;
+mod dynamic_values;
 mod element;
+#[allow(dead_code)]
 mod enumeration;
 mod extension;
+mod field_overrides;
+mod fixed_value;
 mod group;
+mod identity_constraint;
 mod import;
+mod inherited_lang;
+mod key_resolvers;
 mod list;
+mod lossy_mappings;
 mod max_occurences;
+mod options;
+mod progress;
 mod qualification;
+mod report;
 mod restriction;
 mod schema;
 mod sequence;
+mod serde_attributes;
 mod simple_content;
 mod simple_type;
+mod sniff;
+mod stub;
+mod substitution_group;
+mod substitutions;
 mod union;
+mod unknown_content;
+mod validate;
+mod version;
+mod visibility;
+mod visitor;
+mod workspace;
+mod xml_writers;
 mod xsd_context;
 
-use std::fs;
+use std::path::Path;
+
 use thiserror::Error;
 use xml::namespace::{NS_XML_PREFIX, NS_XML_URI};
-use xsd_codegen::{xsdgen_impl, Block, Field, TupleField, XMLElement};
+use xsd_codegen::{
+  escape_str_literal, xsdgen_impl, Block, Enum, Field, MergeConflict, Occurrence, Scope, Type,
+  ValueConstraint, Variant, XMLElement,
+};
 use xsd_context::XsdContext;
-use xsd_types::{XsdIoError, XsdName};
+use xsd_types::{with_naming_convention, DocumentError, SchemaError, XsdName, XsdType};
 
 use self::xsd_context::XsdImpl;
+pub use cancellation::CancellationToken;
+pub use drift::{DriftCause, DriftReport, DriftedItem, ItemChange};
+pub use lossy_mappings::LossyMapping;
+pub use options::{
+  FieldOverride, FixedValues, GeneratorOptions, LoadOptions, Naming, SchemaSource, StringStorage,
+  StubKind, Visibility,
+};
+pub use report::{GenerationPlan, GenerationReport, ItemKind, PlannedItem, TypeStats, Warning};
+pub use validate::Invalid;
+pub use version::{VersionUse, XsdVersion};
+pub use workspace::Workspace;
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum XsdError {
   #[error("{0} not found")]
   XsdImplNotFound(XsdName),
   #[error(transparent)]
-  XsdIoError(#[from] XsdIoError),
-  #[error(transparent)]
+  SchemaError(#[from] SchemaError),
+  #[error("Failed to parse the xml document")]
   XmlParseError(#[from] xmltree::ParseError),
-  #[error("{0}")]
-  XsdMissing(String),
-  #[error("When searching for {name}: {msg}")]
-  ContextSearchError { name: XsdName, msg: String },
-  #[error(transparent)]
+  #[error("Could not find:{}", format_missing(.missing))]
+  XsdMissing { missing: Vec<(XsdName, XsdError)> },
+  #[error("Circular definition: {}", format_cycle(.cycle))]
+  CircularDefinition { cycle: Vec<XsdName> },
+  #[error("When searching for {name}: {msg} (candidates: {})", format_candidates(.candidates))]
+  ContextSearchError {
+    name: XsdName,
+    candidates: Vec<XsdName>,
+    msg: String,
+  },
+  #[error("Failed to read the schema")]
   Io(#[from] std::io::Error),
+  #[error("Failed to read the schema {}", .path.display())]
+  ReadSchema {
+    path: std::path::PathBuf,
+    #[source]
+    source: std::io::Error,
+  },
   #[error("Unknown Xsd error")]
   Unknown,
-  #[error(transparent)]
+  #[error("Failed to fetch the schema")]
   NetworkError(#[from] reqwest::Error),
+  #[error("Refusing to fetch {location} while offline")]
+  Offline { location: String },
+  #[error("Invalid catalog entry in {}: {line}", .path.display())]
+  InvalidCatalog {
+    path: std::path::PathBuf,
+    line: String,
+  },
+  #[error("External namespace {namespace}: {msg}")]
+  ExternalNamespace { namespace: String, msg: String },
+  #[error("Failed to generate [{:?}] {name} (needed by {})", .name.ty, format_candidates(.dependents))]
+  ComponentError {
+    name: XsdName,
+    dependents: Vec<XsdName>,
+    #[source]
+    source: Box<XsdError>,
+  },
+  #[error("The schema does not conform to XSD {version}:{}", format_version_uses(.uses))]
+  Conformance {
+    version: XsdVersion,
+    uses: Vec<VersionUse>,
+  },
+  #[error("Enumeration values are not values of their base:{}", format_warnings(.invalid))]
+  InvalidEnumeration { invalid: Vec<Warning> },
+  #[error("Builtin types are read into types that can't hold all their values:{}", format_lossy_mappings(.uses))]
+  LossyMapping { uses: Vec<LossyMapping> },
+  #[error(transparent)]
+  MergeConflict(#[from] MergeConflict),
+  #[error("Import of {} has no schemaLocation", .namespace.as_deref().unwrap_or("the absent namespace"))]
+  ImportWithoutLocation { namespace: Option<String> },
+  #[error("Circular import: {}", .cycle.join(" -> "))]
+  CircularImport {
+    /// The documents importing each other, from the first one imported again to itself.
+    cycle: Vec<String>,
+  },
+  #[error("{} is not an XML Schema: {msg}", .location.as_deref().unwrap_or("The document"))]
+  NotASchema {
+    /// Where the document was loaded from, if known.
+    location: Option<String>,
+    msg: String,
+  },
+  #[error("The generation was cancelled")]
+  Cancelled,
+  #[error("Invalid option {option}: {msg}")]
+  InvalidOption { option: &'static str, msg: String },
   #[error(transparent)]
   Infalible(#[from] std::convert::Infallible),
 }
 
+/// What an `XsdError` is a failure of, e.g. to pick the exit status of a command line tool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XsdErrorKind {
+  /// A document couldn't be read or fetched.
+  Io,
+  /// The schema is invalid or incomplete, or uses constructs the options reject.
+  Schema,
+  /// The code couldn't be generated from the schema with the options.
+  Generation,
+  /// The generation was cancelled.
+  Cancelled,
+}
+
+/// What can't be read in a schema document is a problem of the schema.
+impl From<DocumentError> for XsdError {
+  fn from(error: DocumentError) -> Self {
+    XsdError::SchemaError(error.into())
+  }
+}
+
+fn format_missing(missing: &[(XsdName, XsdError)]) -> String {
+  missing
+    .iter()
+    .map(|(name, source)| format!("\n[{:?}] {name}: {source}", name.ty))
+    .collect()
+}
+
+fn format_version_uses(uses: &[VersionUse]) -> String {
+  uses
+    .iter()
+    .map(|used| format!("\n{}: {} (XSD {})", used.path, used.construct, used.version))
+    .collect()
+}
+
+fn format_lossy_mappings(uses: &[LossyMapping]) -> String {
+  uses
+    .iter()
+    .map(|used| {
+      format!(
+        "\n{}: xs:{} as {}, {}",
+        used.path, used.builtin, used.ty, used.loss
+      )
+    })
+    .collect()
+}
+
+fn format_warnings(warnings: &[Warning]) -> String {
+  warnings
+    .iter()
+    .map(|warning| format!("\n{}: {}", warning.path, warning.msg))
+    .collect()
+}
+
+fn format_cycle(cycle: &[XsdName]) -> String {
+  cycle
+    .iter()
+    .chain(cycle.first())
+    .map(|name| format!("[{:?}] {name}", name.ty))
+    .collect::<Vec<_>>()
+    .join(" -> ")
+}
+
+fn format_candidates(candidates: &[XsdName]) -> String {
+  candidates
+    .iter()
+    .map(|name| format!("[{:?}] {name}", name.ty))
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+impl XsdError {
+  /// A stable, machine readable identifier for the kind of error.
+  ///
+  /// Unlike the `Display` output these values will not change between releases
+  /// and are intended to be matched on by tooling (e.g. to pick an exit status).
+  pub fn error_code(&self) -> &'static str {
+    match self {
+      XsdError::XsdImplNotFound(_) => "xsd-impl-not-found",
+      XsdError::SchemaError(SchemaError::Invalid(_)) => "xsd-parse",
+      XsdError::SchemaError(SchemaError::Unsupported(_)) => "xsd-gen",
+      XsdError::XmlParseError(_) => "xml-parse",
+      XsdError::XsdMissing { .. } => "xsd-missing",
+      XsdError::CircularDefinition { .. } => "circular-definition",
+      XsdError::ContextSearchError { .. } => "context-search",
+      XsdError::Io(_) | XsdError::ReadSchema { .. } => "io",
+      XsdError::Unknown => "unknown",
+      XsdError::NetworkError(_) => "network",
+      XsdError::Offline { .. } => "offline",
+      XsdError::InvalidCatalog { .. } => "invalid-catalog",
+      XsdError::ExternalNamespace { .. } => "external-namespace",
+      XsdError::ComponentError { source, .. } => source.error_code(),
+      XsdError::Conformance { .. } => "conformance",
+      XsdError::InvalidEnumeration { .. } => "invalid-enumeration",
+      XsdError::LossyMapping { .. } => "lossy-mapping",
+      XsdError::MergeConflict(_) => "merge-conflict",
+      XsdError::ImportWithoutLocation { .. } => "import-without-location",
+      XsdError::CircularImport { .. } => "circular-import",
+      XsdError::NotASchema { .. } => "not-a-schema",
+      XsdError::Cancelled => "cancelled",
+      XsdError::InvalidOption { .. } => "invalid-option",
+      XsdError::Infalible(_) => "infallible",
+    }
+  }
+
+  /// What the error is a failure of, the one of its source for a component that failed to
+  /// generate.
+  pub fn kind(&self) -> XsdErrorKind {
+    match self {
+      XsdError::Io(_)
+      | XsdError::ReadSchema { .. }
+      | XsdError::NetworkError(_)
+      | XsdError::Offline { .. }
+      | XsdError::InvalidCatalog { .. } => XsdErrorKind::Io,
+      XsdError::XsdImplNotFound(_)
+      | XsdError::SchemaError(SchemaError::Invalid(_))
+      | XsdError::XmlParseError(_)
+      | XsdError::XsdMissing { .. }
+      | XsdError::CircularDefinition { .. }
+      | XsdError::ContextSearchError { .. }
+      | XsdError::Conformance { .. }
+      | XsdError::InvalidEnumeration { .. }
+      | XsdError::LossyMapping { .. }
+      | XsdError::ImportWithoutLocation { .. }
+      | XsdError::CircularImport { .. }
+      | XsdError::NotASchema { .. } => XsdErrorKind::Schema,
+      // The external namespaces are options, the merge conflicts are between generated items.
+      XsdError::SchemaError(SchemaError::Unsupported(_))
+      | XsdError::Unknown
+      | XsdError::ExternalNamespace { .. }
+      | XsdError::InvalidOption { .. }
+      | XsdError::MergeConflict(_)
+      | XsdError::Infalible(_) => XsdErrorKind::Generation,
+      XsdError::ComponentError { source, .. } => source.kind(),
+      XsdError::Cancelled => XsdErrorKind::Cancelled,
+    }
+  }
+}
+
+/// Find the `remaining`th schema element of the document, in document order.
+fn nth_schema(element: xmltree::Element, remaining: &mut usize) -> Option<xmltree::Element> {
+  if element.namespace.as_deref() == Some("http://www.w3.org/2001/XMLSchema")
+    && element.name == "schema"
+  {
+    if *remaining == 0 {
+      return Some(element);
+    }
+    *remaining -= 1;
+    return None;
+  }
+
+  element.children.into_iter().find_map(|child| match child {
+    xmltree::XMLNode::Element(child) => nth_schema(child, remaining),
+    _ => None,
+  })
+}
+
 #[derive(Clone, Debug)]
 pub struct Xsd {
   context: XsdContext,
@@ -59,11 +318,72 @@ pub struct Xsd {
 
 impl Xsd {
   pub fn new(content: &str) -> Result<Self, XsdError> {
-    let mut context = XsdContext::new(content)?;
-    let schema = schema::Schema::parse(XMLElement {
-      element: xmltree::Element::parse(content.as_bytes())?,
-      default_namespace: None,
+    Self::new_from_bytes(content.as_bytes())
+  }
+
+  /// Load a schema from the bytes of the document. A byte order mark is skipped, the encoding
+  /// is the one of the byte order mark or of the XML declaration, UTF-8 by default.
+  pub fn new_from_bytes(content: &[u8]) -> Result<Self, XsdError> {
+    Self::load(content, None)
+  }
+
+  /// Load a schema from the bytes of the document read from `location`, checking that the
+  /// document is a schema before parsing it.
+  fn load(content: &[u8], location: Option<&str>) -> Result<Self, XsdError> {
+    let content = content.strip_prefix(&[0xef, 0xbb, 0xbf]).unwrap_or(content);
+    sniff::check_schema_root(content).map_err(|msg| XsdError::NotASchema {
+      location: location.map(str::to_string),
+      msg,
     })?;
+    let element = xmltree::Element::parse(content)?;
+    let mut context = XsdContext::new(&element)?;
+    context.location = location.map(str::to_string);
+
+    Self::from_parts(context, element)
+  }
+
+  /// Load the `schema_index`th `schema` element embedded in a wrapper document, e.g. the
+  /// `types` section of a WSDL file. Namespaces declared on the ancestors of the schema are
+  /// kept.
+  pub fn from_embedded(content: &str, schema_index: usize) -> Result<Self, XsdError> {
+    let root = xmltree::Element::parse(content.as_bytes())?;
+
+    let mut remaining = schema_index;
+    let element = nth_schema(root, &mut remaining).ok_or_else(|| {
+      SchemaError::Invalid(xsd_types::XsdParseError {
+        node_name: "schema".to_string(),
+        msg: format!("Bad XML Schema, unable to find schema element {schema_index}."),
+      })
+    })?;
+
+    let mut context = XsdContext::for_schema(&element);
+    context.embedded = true;
+
+    Self::from_parts(context, element)
+  }
+
+  /// Add the `schema_index`th schema embedded in `content` to this one. The components of
+  /// both schemas share a context and so can reference each other.
+  pub fn add_embedded(&mut self, content: &str, schema_index: usize) -> Result<(), XsdError> {
+    let embedded = Self::from_embedded(content, schema_index)?;
+    self.context.embedded = true;
+
+    for (prefix, uri) in &embedded.context.namespace {
+      self.context.namespace.put(prefix, uri);
+    }
+    self.schema.children.extend(embedded.schema.children);
+    self.schema.versions.extend(embedded.schema.versions);
+    self.schema.max_occurs.extend(embedded.schema.max_occurs);
+    self
+      .schema
+      .lossy_mappings
+      .extend(embedded.schema.lossy_mappings);
+
+    Ok(())
+  }
+
+  fn from_parts(mut context: XsdContext, element: xmltree::Element) -> Result<Self, XsdError> {
+    let schema = schema::Schema::parse(XMLElement::from_element(element, None))?;
 
     context.namespace.put(NS_XML_PREFIX, NS_XML_URI);
 
@@ -79,32 +399,448 @@ impl Xsd {
   }
 
   pub fn new_from_file(source: &str) -> Result<Self, XsdError> {
-    let content = if source.starts_with("http://") || source.starts_with("https://") {
-      tracing::info!("Load HTTP schema {}", source);
-      reqwest::blocking::get(source)?.text()?
-    } else {
-      let path = std::env::current_dir().unwrap();
-      tracing::info!("The current directory is {}", path.display());
+    Self::new_from_file_with(source, &LoadOptions::default())
+  }
+
+  /// Load the schema at `source` with the source of `options`, which also provides the
+  /// documents it imports. The relative locations of its imports are read from its directory.
+  pub fn new_from_file_with(source: &str, options: &LoadOptions) -> Result<Self, XsdError> {
+    let content = options.fetch(source)?;
+
+    let mut xsd = Xsd::load(&content, Some(source))?;
+    xsd.context.load_options = options.for_document(source);
+    xsd.context.import_chain = vec![(
+      options.document_key(source),
+      xsd.schema.target_namespace.clone(),
+    )];
+
+    Ok(xsd)
+  }
+
+  /// The names of the components declared at the top level of the schema.
+  pub fn top_level_components(&self) -> Vec<XsdName> {
+    self.schema.top_level_names()
+  }
 
-      fs::read_to_string(source)?
-    };
+  pub fn target_namespace(&self) -> Option<&str> {
+    self.schema.target_namespace.as_deref()
+  }
+
+  /// Check an instance document against the schema and the schemas it imports.
+  ///
+  /// The names of the children and attributes of each element, the presence of the required
+  /// ones and the simple values are checked, the order and number of the children are not.
+  pub fn validate(&self, instance: &xmltree::Element) -> Result<Vec<Invalid>, XsdError> {
+    let validator = validate::Validator::new(&self.schema, &self.context.load_options)?;
+    Ok(validator.validate(instance))
+  }
+
+  /// Generate the code with the default options. The target prefix is not used.
+  #[deprecated(
+    since = "0.0.8",
+    note = "use `Xsd::generate_with`, which takes the `GeneratorOptions`"
+  )]
+  pub fn generate(&self, _target_prefix: &Option<String>) -> Result<String, XsdError> {
+    self.generate_with(&GeneratorOptions::default())
+  }
+
+  /// Generate the code with `options`.
+  pub fn generate_with(&self, options: &GeneratorOptions) -> Result<String, XsdError> {
+    Ok(self.generate_with_report(options)?.0)
+  }
+
+  /// Generate the code along with statistics about the generated types.
+  ///
+  /// The components are generated into a copy of the context, generating the same `Xsd` again,
+  /// with the same or other options, gives the same output as a freshly loaded one.
+  ///
+  /// The output is deterministic: the components, attributes and namespaces are emitted in an
+  /// order derived from their names, never from hashing or thread scheduling, so generating
+  /// again, in this process or another one, gives the same bytes.
+  pub fn generate_with_report(
+    &self,
+    options: &GeneratorOptions,
+  ) -> Result<(String, GenerationReport), XsdError> {
+    with_naming_convention(options.naming.convention(), || {
+      self.schema.generate(&mut self.context.clone(), options)
+    })
+  }
 
-    // skip BOM header, can be present on some files
-    let content = if content.as_bytes()[0..3] == [0xef, 0xbb, 0xbf] {
-      content[3..].to_owned()
-    } else {
-      content
-    };
+  /// Decide what `generate_with_report` would emit with `options`, without formatting the code.
+  /// Generation errors and warnings are the same.
+  pub fn plan(&self, options: &GeneratorOptions) -> Result<GenerationPlan, XsdError> {
+    with_naming_convention(options.naming.convention(), || {
+      self.schema.plan(&mut self.context.clone(), options)
+    })
+  }
+
+  /// Check the generated code at `path` is up to date, i.e. that its items are the ones
+  /// `generate` emits now. Its header line, naming the version of the generator and the
+  /// fingerprint of the options, tells the cause of the differences in the report.
+  pub fn check_generated(&self, path: &Path) -> Result<(), DriftReport> {
+    self.check_generated_with(path, &GeneratorOptions::default())
+  }
 
-    Xsd::new(&content)
+  /// Check the code at `path` is what `generate_with` emits with `options`, see
+  /// `check_generated`.
+  pub fn check_generated_with(
+    &self,
+    path: &Path,
+    options: &GeneratorOptions,
+  ) -> Result<(), DriftReport> {
+    drift::check(path, self.generate_with(options), options)
   }
 
-  pub fn generate(&mut self, _target_prefix: &Option<String>) -> Result<String, XsdError> {
-    self.schema.generate(&mut self.context)
+  /// Generate several schemas into a single module, along with the report of all of them.
+  /// Types generated by more than one schema, like the ones of a common import, are only emitted
+  /// once and must be identical.
+  pub fn generate_merged(
+    xsds: &[Xsd],
+    options: &GeneratorOptions,
+  ) -> Result<(String, GenerationReport), XsdError> {
+    with_naming_convention(options.naming.convention(), || {
+      Self::generate_merged_with_naming(xsds, options)
+    })
+  }
+
+  fn generate_merged_with_naming(
+    xsds: &[Xsd],
+    options: &GeneratorOptions,
+  ) -> Result<(String, GenerationReport), XsdError> {
+    let mut merged = Scope::new();
+    let mut merged_report = GenerationReport::default();
+    let mut send_sync_paths = vec![];
+    let mut type_index = vec![];
+    for xsd in xsds {
+      let mut context = xsd.context.clone();
+      let (scope, report) = xsd.schema.generate_scope(&mut context, options)?;
+      merged = merged.merge(scope)?;
+      merged_report.merge(report);
+
+      if options.send_sync_assertions {
+        for path in schema::Schema::type_paths(&context, options)? {
+          if !send_sync_paths.contains(&path) {
+            send_sync_paths.push(path);
+          }
+        }
+      }
+      if options.type_index {
+        type_index.extend(schema::Schema::type_index(&context, options)?);
+      }
+    }
+
+    Ok((
+      schema::render(&merged, &send_sync_paths, type_index, options),
+      merged_report,
+    ))
   }
 }
 
-fn general_xsdgen(mut generated_impl: XsdImpl) -> XsdImpl {
+/// The expression parsing a value of type `ty`.
+fn gen_field(ty: &Type, element: &str, gen_state: &str, xml_name: &str) -> String {
+  match (&ty.parse_as, &ty.value_constraint) {
+    (Some(parse_as), _) => format!(
+      "<{parse_as} as XsdGen>::gen({element}, {gen_state}, {xml_name}).map(|_| <{parse_as} as FixedValue>::VALUE)"
+    ),
+    (None, Some(ValueConstraint::Default(default))) => {
+      format!("{element}.gen_attribute_or::<{ty}>({gen_state}, {xml_name}, {default})")
+    }
+    (None, Some(ValueConstraint::Fixed(fixed))) => {
+      format!("{element}.gen_fixed_attribute::<{ty}>({gen_state}, {xml_name}, {fixed})")
+    }
+    (None, None) => format!("<{ty} as XsdGen>::gen({element}, {gen_state}, {xml_name})"),
+  }
+}
+
+/// Describe the schema construct a generated field comes from, e.g.
+/// `field: pitch (element, minOccurs=1)`.
+fn field_origin(
+  name: &str,
+  occurrence: Occurrence,
+  xml_name: Option<&XsdName>,
+  attribute: bool,
+  flatten: bool,
+) -> String {
+  let name = xml_name.map_or(name, |xml_name| &xml_name.local_name);
+  let occurences = match occurrence {
+    (0, _) if attribute => "use=optional".to_string(),
+    _ if attribute => "use=required".to_string(),
+    (min, Some(1)) => format!("minOccurs={min}"),
+    (min, max) => format!(
+      "minOccurs={min}, maxOccurs={}",
+      max.map_or("unbounded".to_string(), |max| max.to_string())
+    ),
+  };
+  let kind = if attribute {
+    "attribute"
+  } else if flatten {
+    "flattened"
+  } else {
+    "element"
+  };
+
+  format!("field: {name} ({kind}, {occurences})")
+}
+
+/// The bounds of the particle read into `field`, as recorded where it was wrapped or else as
+/// restricted by its type.
+fn field_occurrence(field: &Field) -> Occurrence {
+  field
+    .occurrence
+    .unwrap_or_else(|| occurrence_bounds(&field.ty))
+}
+
+/// The minimum and maximum (`None` when unbounded) number of values held by a field of type `ty`.
+fn occurrence_bounds(ty: &Type) -> Occurrence {
+  let ty = ty.parse_as.as_deref().unwrap_or(ty);
+  match (ty.name.as_str(), ty.generics.as_slice()) {
+    ("Option", _) => (0, Some(1)),
+    ("Vec", _) => (0, None),
+    ("RestrictedVec", _) => match ty.const_generics.as_slice() {
+      [min, max] => (*min, Some(*max).filter(|max| *max != 0)),
+      _ => (0, None),
+    },
+    _ => (1, Some(1)),
+  }
+}
+
+fn option_literal(value: Option<&str>) -> String {
+  value.map_or("None".to_string(), |value| {
+    format!("Some({})", escape_str_literal(value))
+  })
+}
+
+/// The `FieldMeta` expression describing a generated field.
+fn field_meta(
+  name: &str,
+  (min, max): Occurrence,
+  xml_name: Option<&XsdName>,
+  attribute: bool,
+  flatten: bool,
+) -> String {
+  let kind = if attribute {
+    "Attribute"
+  } else if flatten {
+    "Flattened"
+  } else {
+    "Element"
+  };
+
+  format!(
+    "FieldMeta {{ name: {}, xml_name: {}, namespace: {}, kind: FieldKind::{kind}, min: {min}, max: {}, required: {} }}",
+    escape_str_literal(name),
+    option_literal(xml_name.map(|name| name.local_name.as_str())),
+    option_literal(xml_name.and_then(|name| name.namespace.as_deref())),
+    max.map_or("None".to_string(), |max| format!("Some({max})")),
+    min > 0
+  )
+}
+
+fn fields_meta(fields: &xsd_codegen::Fields) -> Vec<String> {
+  fields
+    .fields()
+    .iter()
+    .enumerate()
+    .map(|(index, field)| {
+      field_meta(
+        field.name.as_deref().unwrap_or(&index.to_string()),
+        field_occurrence(field),
+        field.xml_name.as_ref(),
+        field.attribute,
+        field.flatten,
+      )
+    })
+    .collect()
+}
+
+/// A slice expression with one entry per line, or on a single line when `inline` is set.
+fn slice_literal(entries: Vec<String>, inline: bool) -> String {
+  if entries.is_empty() {
+    return "&[]".to_string();
+  }
+
+  if inline {
+    return format!("&[{}]", entries.join(", "));
+  }
+
+  let entries: String = entries
+    .iter()
+    .map(|entry| format!("    {entry},\n"))
+    .collect();
+  format!("&[\n{entries}]")
+}
+
+/// The impl holding the `CONTENT` constant and the `FIELDS` or `VARIANTS` constant of a
+/// generated struct or enum.
+fn metadata_impl(imp: &XsdImpl) -> Option<xsd_codegen::Impl> {
+  let element = &imp.element;
+  let (name, ty, entries) = match element {
+    xsd_context::XsdImplType::Struct(r#struct) => {
+      ("FIELDS", "FieldMeta", fields_meta(&r#struct.fields))
+    }
+    xsd_context::XsdImplType::Enum(r#enum) => (
+      "VARIANTS",
+      "VariantMeta",
+      r#enum
+        .variants
+        .iter()
+        .map(|variant| {
+          format!(
+            "VariantMeta {{ name: {}, xml_name: {}, namespace: {}, fields: {} }}",
+            escape_str_literal(&variant.name),
+            option_literal(
+              variant
+                .xml_name
+                .as_ref()
+                .map(|name| name.local_name.as_str())
+            ),
+            option_literal(
+              variant
+                .xml_name
+                .as_ref()
+                .and_then(|name| name.namespace.as_deref())
+            ),
+            slice_literal(fields_meta(&variant.fields), true),
+          )
+        })
+        .collect(),
+    ),
+    xsd_context::XsdImplType::Type(_) | xsd_context::XsdImplType::TypeAlias(_) => return None,
+  };
+
+  let mut metadata = xsd_codegen::Impl::new(element.get_type());
+  metadata.associate_const_with_vis(
+    "pub",
+    "CONTENT",
+    "ContentKind",
+    &format!("ContentKind::{:?}", imp.content),
+  );
+  metadata.associate_const_with_vis(
+    "pub",
+    name,
+    format!("&'static [{ty}]").as_str(),
+    &slice_literal(entries, false),
+  );
+  Some(metadata)
+}
+
+/// Number of variants from which a choice is large, see `is_large_choice`.
+const LARGE_CHOICE_VARIANTS: usize = 64;
+
+/// Estimated number of lines of parsing code from which a choice is large.
+const LARGE_CHOICE_LINES: usize = 2000;
+
+/// Whether the parsing code of `r#enum` would be too large to check the alternatives in turn,
+/// e.g. MusicXML's `music-data`. Its element alternatives are dispatched on the name of the next
+/// child instead.
+pub(crate) fn is_large_choice(r#enum: &Enum) -> bool {
+  let lines: usize = r#enum
+    .variants
+    .iter()
+    .map(|variant| match &variant.fields {
+      _ if element_alternative(variant).is_some() => 3,
+      xsd_codegen::Fields::Empty => 12,
+      xsd_codegen::Fields::Tuple(fields) | xsd_codegen::Fields::Named(fields) => {
+        4 + 2 * fields.len()
+      }
+    })
+    .sum();
+  r#enum.variants.len() >= LARGE_CHOICE_VARIANTS || lines >= LARGE_CHOICE_LINES
+}
+
+/// The field of `variant` when it holds a single element picked from its name.
+fn element_alternative(variant: &Variant) -> Option<&Field> {
+  match &variant.fields {
+    xsd_codegen::Fields::Tuple(fields) if variant.xml_name.is_some() => match fields.as_slice() {
+      [field @ Field {
+        ty,
+        attribute: false,
+        flatten: false,
+        ..
+      }] if !matches!(ty.name.as_str(), "Option" | "Vec") => Some(field),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+/// The expression parsing the element alternative `variant` into `Self`.
+fn element_alternative_value(variant: &Variant, last: bool) -> String {
+  let ty = &element_alternative(variant).unwrap().ty;
+  let xml_name = escape_str_literal(&variant.xml_name.as_ref().unwrap().to_string());
+  let gen_state = if last {
+    "gen_state"
+  } else {
+    "gen_state.clone()"
+  };
+  format!(
+    "Ok(Self::{}({}?))",
+    variant.name,
+    gen_field(ty, "element", gen_state, &format!("Some({xml_name})"))
+  )
+}
+
+/// Parse the element alternative `variant` when the next child has its name.
+fn element_alternative_check(variant: &Variant, last: bool) -> Block {
+  let xml_name = escape_str_literal(&variant.xml_name.as_ref().unwrap().to_string());
+  Block::new(&format!("if element.next_child_is({xml_name})")).line(format!(
+    "return {};",
+    element_alternative_value(variant, last)
+  ))
+}
+
+fn element_alternative_origin(variant: &Variant) -> String {
+  let field = element_alternative(variant).unwrap();
+  field_origin(
+    "0",
+    field_occurrence(field),
+    variant.xml_name.as_ref(),
+    false,
+    false,
+  )
+}
+
+/// Push the parsing of consecutive element alternatives of a large choice, matching the name of
+/// the next child against theirs. `table` holds the alternatives along with whether they are the
+/// last variant.
+fn push_dispatch_table(mut block: Block, table: Vec<(&Variant, bool)>, verbose: bool) -> Block {
+  if let [(variant, last)] = table[..] {
+    if verbose {
+      block = block
+        .comment(&format!("variant: {}", variant.name))
+        .comment(&element_alternative_origin(variant));
+    }
+    return block.push_block(element_alternative_check(variant, last));
+  }
+  if table.is_empty() {
+    return block;
+  }
+
+  let mut dispatch = Block::new("match next_child.as_deref()");
+  for (variant, last) in table {
+    if verbose {
+      dispatch = dispatch
+        .comment(&format!("variant: {}", variant.name))
+        .comment(&element_alternative_origin(variant));
+    }
+
+    // The children are named by their local name only.
+    let local_name = escape_str_literal(&variant.xml_name.as_ref().unwrap().local_name);
+    dispatch = dispatch.line(format!(
+      "Some({local_name}) => return {},",
+      element_alternative_value(variant, last)
+    ));
+  }
+
+  block.push_block(
+    Block::new("")
+      .line("let next_child = element.next_child_name().map(str::to_string);")
+      .push_block(dispatch.line("_ => {}")),
+  )
+}
+
+fn general_xsdgen(mut generated_impl: XsdImpl, context: &XsdContext) -> XsdImpl {
+  let verbose = context.verbose_codegen;
   let mut block = Block::new("");
   let mut generated_new_impl = true;
 
@@ -113,40 +849,35 @@ fn general_xsdgen(mut generated_impl: XsdImpl) -> XsdImpl {
     xsd_context::XsdImplType::Struct(ty) => {
       name_used = true;
       block = match &ty.fields {
+        // An empty type has nothing to read: only consume the element when it is named, its
+        // children and attributes are never required.
         xsd_codegen::Fields::Empty => block
           .push_block(
-            Block::new("if let Some(name) = name").push_block(
-              Block::new("match gen_state.state")
-                .push_block(
-                  Block::new("GenType::Attribute =>")
-                    .line("element.get_attribute::<String>(name)?;".to_string()),
-                )
-                .push_block(
-                  Block::new("GenType::Content =>").line("element.get_child(name)?;".to_string()),
-                ),
-            ),
+            Block::new("if let (Some(name), GenType::Content) = (name, gen_state.state)")
+              .line("element.read_next_child(name, |_| Ok::<_, DocumentError>(()))?;"),
           )
           .line("Ok(Self)"),
-        xsd_codegen::Fields::Tuple(fields) => {
-          name_used = true;
+        xsd_codegen::Fields::Tuple(fields) | xsd_codegen::Fields::Named(fields) => {
           let mut inner_name_used = false;
-          let mut self_gen =
-            Block::new("let gen_self = |element: &mut XMLElement, name: Option<&str>|");
-          self_gen = self_gen.line("Ok(Self (");
-          for TupleField {
-            ty: field,
-            attribute,
-            flatten,
-            ..
-          } in fields
-          {
-            let new_gen_state = if *attribute {
+          let mut body = vec![];
+          for (field_index, field) in fields.iter().enumerate() {
+            let new_gen_state = if field.attribute {
               "gen_state.to_attr()"
             } else {
               "gen_state.clone()"
             };
 
-            let next_xml_name = if *flatten {
+            let comment = verbose.then(|| {
+              field_origin(
+                field.name.as_deref().unwrap_or(&field_index.to_string()),
+                field_occurrence(field),
+                field.xml_name.as_ref(),
+                field.attribute,
+                field.flatten,
+              )
+            });
+
+            let next_xml_name = if field.flatten || field.value.is_some() {
               "None".to_string()
             } else {
               if field.xml_name.is_none() {
@@ -155,64 +886,58 @@ fn general_xsdgen(mut generated_impl: XsdImpl) -> XsdImpl {
               field
                 .xml_name
                 .as_ref()
-                .map(|v| format!("Some(\"{}\")", v))
+                .map(|v| format!("Some({})", escape_str_literal(&v.to_string())))
                 .unwrap_or_else(|| "name".to_string())
             };
 
-            self_gen = self_gen.line(format!(
-              "<{} as XsdGen>::gen(element, {new_gen_state}, {next_xml_name})?,",
-              field.to_string(),
-            ));
-          }
-          let mut self_gen = self_gen.line("))").after(";");
-
-          if !inner_name_used {
-            self_gen.before =
-              Some("let gen_self = |element: &mut XMLElement, _name: Option<&str>|".to_string())
-          }
-
-          block
-            .push_block(self_gen)
-            .push_block(
-              Block::new("if let (Some(name), GenType::Content) = (name, gen_state.state)").line(
-                "element.get_next_child_with(name, |mut element| gen_self(&mut element, None))",
+            let value = match &field.value {
+              Some(value) => value.clone(),
+              None => format!(
+                "{}?",
+                gen_field(&field.ty, "element", new_gen_state, &next_xml_name)
               ),
-            )
-            .push_block(Block::new("else").line("gen_self(element, name)"))
-        }
-        xsd_codegen::Fields::Named(fields) => {
-          name_used = true;
-          let mut inner_name_used = false;
-          let self_gen =
-            Block::new("let gen_self = |element: &mut XMLElement, name: Option<&str>|");
-          let mut inner_block = Block::new("Ok(Self");
-          for field in fields {
-            let new_gen_state = if field.attribute {
-              "gen_state.to_attr()"
-            } else {
-              "gen_state.clone()"
             };
+            body.push((
+              comment,
+              match &field.name {
+                Some(name) => format!("{name}: {value},"),
+                None => format!("{value},"),
+              },
+            ));
+          }
 
-            let next_xml_name = if field.flatten {
-              "None".to_string()
-            } else {
-              if field.xml_name.is_none() {
-                inner_name_used = true;
+          let push_body = |mut block: Block| {
+            for (comment, line) in body {
+              if let Some(comment) = comment {
+                block = block.comment(&comment);
               }
-              field
-                .xml_name
-                .as_ref()
-                .map(|v| format!("Some(\"{}\")", v))
-                .unwrap_or_else(|| "name".to_string())
-            };
+              block = block.line(line);
+            }
+            block
+          };
 
-            inner_block = inner_block.line(format!(
-              "{}: <{} as XsdGen>::gen(element, {new_gen_state}, {next_xml_name})?,",
-              field.name,
-              field.ty.to_string()
-            ));
+          // Tuple fields are read in the parentheses of the constructor, named fields in its
+          // braces.
+          let mut self_gen =
+            Block::new("let gen_self = |element: &mut XMLElement, name: Option<&str>|");
+          // Claimed before the fields are read, their types read the same element.
+          if fields.iter().any(unknown_content::is_unknown_content) {
+            self_gen = self_gen.line("let keep_unknown = element.claim_unknown();");
+          }
+          // The children of an `all` come in any order, the fields are read in the declared one.
+          if ty.type_def.ty.xml_name.as_ref().map(|name| name.ty) == Some(XsdType::All) {
+            let names: Vec<_> = fields
+              .iter()
+              .filter_map(|field| field.xml_name.as_ref())
+              .map(|name| escape_str_literal(&name.local_name))
+              .collect();
+            self_gen = self_gen.line(format!("element.order_children(&[{}]);", names.join(", ")));
           }
-          let mut self_gen = self_gen.push_block(inner_block.after(")")).after(";");
+          let mut self_gen = match &ty.fields {
+            xsd_codegen::Fields::Tuple(_) => push_body(self_gen.line("Ok(Self (")).line("))"),
+            _ => self_gen.push_block(push_body(Block::new("Ok(Self")).after(")")),
+          }
+          .after(";");
 
           if !inner_name_used {
             self_gen.before =
@@ -222,46 +947,78 @@ fn general_xsdgen(mut generated_impl: XsdImpl) -> XsdImpl {
           block
             .push_block(self_gen)
             .push_block(
-              Block::new("if let (Some(name), GenType::Content) = (name, gen_state.state)").line(
-                "element.get_next_child_with(name, |mut element| gen_self(&mut element, None))",
-              ),
+              Block::new("if let (Some(name), GenType::Content) = (name, gen_state.state)")
+                .line("element.read_next_child(name, |element| gen_self(element, None))"),
             )
             .push_block(Block::new("else").line("gen_self(element, name)"))
         }
       }
     }
     xsd_context::XsdImplType::Enum(r#enum) => {
+      let dispatch = is_large_choice(r#enum);
+      let mut table = vec![];
       for (variant_index, variant) in r#enum.variants.iter().enumerate() {
+        let last = variant_index == r#enum.variants.len() - 1;
+        if dispatch && element_alternative(variant).is_some() {
+          table.push((variant, last));
+          continue;
+        }
+        block = push_dispatch_table(block, std::mem::take(&mut table), verbose);
+
+        if verbose {
+          if variant_index != 0 {
+            block = block.blank_line();
+          }
+          block = block.comment(&format!("variant: {}", variant.name));
+        }
+
         block = match &variant.fields {
-          xsd_codegen::Fields::Empty => block
-            .push_block(
+          // A unit alternative is picked when its attribute or element is present, the next
+          // alternatives are attempted otherwise.
+          xsd_codegen::Fields::Empty => {
+            let xml_name = escape_str_literal(&variant.xml_name.as_ref().unwrap().to_string());
+            let picked = format!("return Ok(Self::{});", variant.name);
+            block.push_block(
               Block::new("match gen_state.state")
-                .push_block(Block::new("GenType::Attribute").line(format!(
-                  "assert!(element.element.attributes.remove(\"{}\").is_some());",
-                  variant.xml_name.clone().unwrap()
-                )))
-                .push_block(Block::new("GenType::Content").line(format!(
-                  "assert!(element.try_get_child(\"{}\")?.is_some());",
-                  variant.xml_name.clone().unwrap()
-                ))),
+                .push_block(
+                  Block::new("GenType::Attribute").push_block(
+                    Block::new(&format!(
+                      "if element.try_get_attribute::<String>({xml_name})?.is_some()"
+                    ))
+                    .line(&picked),
+                  ),
+                )
+                .push_block(
+                  Block::new("GenType::Content").push_block(
+                    Block::new(&format!("if element.try_get_child({xml_name})?.is_some()"))
+                      .line(&picked),
+                  ),
+                ),
             )
-            .line(format!("Ok(Self::{})", &variant.name)),
-          xsd_codegen::Fields::Tuple(fields) => {
+          }
+          // An element alternative is picked from the name of the next child, repeated
+          // alternatives then keep the order of the document.
+          _ if element_alternative(variant).is_some() => {
+            if verbose {
+              block = block.comment(&element_alternative_origin(variant));
+            }
+
+            block.push_block(element_alternative_check(variant, last))
+          }
+          // Other alternatives are attempted in turn.
+          xsd_codegen::Fields::Tuple(fields) | xsd_codegen::Fields::Named(fields) => {
             let mut current_block =
               Block::new("").line("let mut variant_element = element.clone();");
 
             let mut field_blocks = vec![];
-            for (
-              field_index,
-              TupleField {
-                ty: field,
-                attribute,
-                flatten,
-                ..
-              },
-            ) in fields.iter().enumerate()
-            {
-              let new_gen_state = if *attribute {
+            let mut attempts = vec![];
+            for (field_index, field) in fields.iter().enumerate() {
+              let label = field
+                .name
+                .clone()
+                .unwrap_or_else(|| field_index.to_string());
+
+              let new_gen_state = if field.attribute {
                 "gen_state.to_attr()"
               } else if (field_index == (fields.len() - 1))
                 && (variant_index == (r#enum.variants.len() - 1))
@@ -271,7 +1028,7 @@ fn general_xsdgen(mut generated_impl: XsdImpl) -> XsdImpl {
                 "gen_state.clone()"
               };
 
-              let next_xml_name = if *flatten {
+              let next_xml_name = if field.flatten {
                 "None".to_string()
               } else {
                 if field.xml_name.is_none() {
@@ -280,102 +1037,57 @@ fn general_xsdgen(mut generated_impl: XsdImpl) -> XsdImpl {
                 field
                   .xml_name
                   .as_ref()
-                  .map(|v| format!("Some(\"{}\")", v))
+                  .map(|v| format!("Some({})", escape_str_literal(&v.to_string())))
                   .unwrap_or_else(|| "name".to_string())
               };
 
-              current_block = current_block.line(format!(
-                "let attempt_{field_index} = <{} as XsdGen>::gen(&mut variant_element, {new_gen_state}, {next_xml_name});",
-                field.to_string(),
-              ));
-
-              field_blocks.push(current_block);
-
-              current_block = Block::new(&format!(
-                "if let Ok(attempt_{field_index}) = attempt_{field_index}"
-              ));
-            }
-
-            let all_fields = (0..fields.len())
-              .map(|v| format!("attempt_{v}"))
-              .collect::<Vec<_>>()
-              .join(", ");
-            field_blocks.push(
-              current_block
-                .line("*element = variant_element;")
-                .line(&format!("return Ok(Self::{}({all_fields}));", variant.name)),
-            );
-
-            block.push_block(
-              field_blocks
-                .into_iter()
-                .reduce(|current, v| current.push_block(v))
-                .unwrap(),
-            )
-          }
-          xsd_codegen::Fields::Named(fields) => {
-            let mut current_block =
-              Block::new("").line("let mut variant_element = element.clone();");
-
-            let mut field_blocks = vec![];
-            for (
-              field_index,
-              Field {
-                name,
-                ty,
-                xml_name,
-                attribute,
-                flatten,
-                ..
-              },
-            ) in fields.iter().enumerate()
-            {
-              let new_gen_state = if *attribute {
-                "gen_state.to_attr()"
-              } else if (field_index == (fields.len() - 1))
-                && (variant_index == (r#enum.variants.len() - 1))
-              {
-                "gen_state"
-              } else {
-                "gen_state.clone()"
-              };
-
-              let next_xml_name = if *flatten {
-                "None".to_string()
-              } else {
-                if xml_name.is_none() {
-                  name_used = true;
-                }
-                xml_name
-                  .as_ref()
-                  .map(|v| format!("Some(\"{}\")", v))
-                  .unwrap_or_else(|| "name".to_string())
-              };
+              if verbose {
+                current_block = current_block.comment(&field_origin(
+                  &label,
+                  field_occurrence(field),
+                  field.xml_name.as_ref(),
+                  field.attribute,
+                  field.flatten,
+                ));
+              }
 
               current_block = current_block.line(format!(
-                "let attempt_{name} = <{} as XsdGen>::gen(&mut variant_element, {new_gen_state}, {next_xml_name});",
-                ty.to_string(),
+                "let attempt_{label} = {};",
+                gen_field(
+                  &field.ty,
+                  "&mut variant_element",
+                  new_gen_state,
+                  &next_xml_name
+                )
               ));
 
-              field_blocks.push((name, current_block));
+              field_blocks.push(current_block);
 
-              current_block = Block::new(&format!("if let Ok(attempt_{name}) = attempt_{name}"));
+              current_block = Block::new(&format!("if let Ok(attempt_{label}) = attempt_{label}"));
+              attempts.push(label);
             }
 
-            let all_fields = field_blocks
-              .iter()
-              .map(|v| format!("{0}: attempt_{0}", v.0))
-              .fold(
-                Block::new(&format!("return Ok(Self::{}", variant.name)),
-                |current, v| current.line(format!("{v},")),
-              )
-              .after(");");
-            let mut field_blocks = field_blocks.into_iter().map(|v| v.1).collect::<Vec<_>>();
-            field_blocks.push(
-              current_block
-                .line("*element = variant_element;")
-                .push_block(all_fields),
-            );
+            current_block = current_block.line("*element = variant_element;");
+            field_blocks.push(match &variant.fields {
+              xsd_codegen::Fields::Tuple(_) => current_block.line(format!(
+                "return Ok(Self::{}({}));",
+                variant.name,
+                attempts
+                  .iter()
+                  .map(|label| format!("attempt_{label}"))
+                  .collect::<Vec<_>>()
+                  .join(", ")
+              )),
+              _ => current_block.push_block(
+                attempts
+                  .iter()
+                  .fold(
+                    Block::new(&format!("return Ok(Self::{}", variant.name)),
+                    |current, label| current.line(format!("{label}: attempt_{label},")),
+                  )
+                  .after(");"),
+              ),
+            });
 
             block.push_block(
               field_blocks
@@ -386,6 +1098,7 @@ fn general_xsdgen(mut generated_impl: XsdImpl) -> XsdImpl {
           }
         }
       }
+      block = push_dispatch_table(block, table, verbose);
       block = block.line("Err(XsdGenError { ty: XsdType::Unknown, node_name: element.name().to_string(), msg: \"No valid values could be parsed.\".to_string() }.into())")
     }
     _ => {
@@ -400,7 +1113,894 @@ fn general_xsdgen(mut generated_impl: XsdImpl) -> XsdImpl {
       false,
       name_used,
     ));
+
+    if context.field_metadata {
+      generated_impl
+        .implementation
+        .extend(metadata_impl(&generated_impl));
+    }
   }
 
   generated_impl
 }
+
+/// Generate the parsing code and the metadata of `imp` again, once its fields changed.
+fn regenerate_xsdgen(mut imp: XsdImpl, context: &XsdContext) -> XsdImpl {
+  let ty = imp.element.get_type();
+  // The parsing code and the metadata are the ones `general_xsdgen` generated.
+  imp.implementation.retain(|implementation| {
+    implementation.target != ty
+      || !(implementation
+        .impl_trait
+        .as_ref()
+        .is_some_and(|tr| tr.name == "XsdGen")
+        || implementation
+          .assoc_consts
+          .iter()
+          .any(|(_, name, _, _)| name == "CONTENT"))
+  });
+  general_xsdgen(imp, context)
+}
+
+#[test]
+fn field_metadata_matches_the_schema() {
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="note">
+        <xs:sequence>
+          <xs:element name="pitch" type="xs:string" />
+          <xs:element name="dot" type="xs:string" minOccurs="0" maxOccurs="3" />
+          <xs:choice>
+            <xs:element name="duration" type="xs:int" />
+            <xs:element name="grace" type="xs:string" />
+          </xs:choice>
+        </xs:sequence>
+        <xs:attribute name="id" type="xs:string" use="required" />
+        <xs:attribute name="color" type="xs:string" />
+      </xs:complexType>
+    </xs:schema>
+  "#;
+
+  let generate = |field_metadata| {
+    Xsd::new(schema)
+      .unwrap()
+      .generate_with(&GeneratorOptions {
+        field_metadata,
+        ..Default::default()
+      })
+      .unwrap()
+  };
+
+  assert!(!generate(false).contains("FieldMeta"));
+
+  let output = generate(true);
+  assert!(output.contains("pub const FIELDS: &'static [FieldMeta] = &["));
+  assert!(output.contains("pub const CONTENT: ContentKind = ContentKind::Complex;"));
+  for field in [
+    r#"FieldMeta { name: "pitch", xml_name: Some("pitch"), namespace: None, kind: FieldKind::Element, min: 1, max: Some(1), required: true }"#,
+    r#"FieldMeta { name: "dot", xml_name: Some("dot"), namespace: None, kind: FieldKind::Element, min: 0, max: Some(3), required: false }"#,
+    r#"FieldMeta { name: "id", xml_name: Some("id"), namespace: None, kind: FieldKind::Attribute, min: 1, max: Some(1), required: true }"#,
+    r#"FieldMeta { name: "color", xml_name: Some("color"), namespace: None, kind: FieldKind::Attribute, min: 0, max: Some(1), required: false }"#,
+  ] {
+    assert!(output.contains(field), "{field}");
+  }
+
+  assert!(output.contains("pub const VARIANTS: &'static [VariantMeta] = &["));
+  assert!(output.contains(r#"VariantMeta { name: "Duration", xml_name: Some("duration"), namespace: None, fields: &[FieldMeta { name: "0", xml_name: Some("duration"), namespace: None, kind: FieldKind::Element, min: 1, max: Some(1), required: true }] }"#));
+}
+
+#[test]
+fn field_docs_give_the_occurrence_bounds() {
+  let xsd = Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="measure">
+        <xs:sequence>
+          <xs:element name="number" type="xs:string" />
+          <xs:element name="note" type="xs:string" minOccurs="0" maxOccurs="unbounded" />
+          <xs:element name="barline" type="xs:string" minOccurs="0" />
+          <xs:element name="direction" type="xs:string" minOccurs="2" maxOccurs="4" />
+          <xs:choice minOccurs="0">
+            <xs:element name="print" type="xs:string" />
+            <xs:element name="sound" type="xs:string" />
+          </xs:choice>
+        </xs:sequence>
+        <xs:attribute name="width" type="xs:string" />
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let output = xsd
+    .generate_with(&GeneratorOptions {
+      field_metadata: true,
+      ..Default::default()
+    })
+    .unwrap();
+  for field in [
+    "    pub number: String,",
+    "    /// Occurs: 0..∞\n    pub note: Vec<String>,",
+    "    /// Occurs: 0..1\n    pub barline: Option<String>,",
+    "    /// Occurs: 2..4\n    pub direction: RestrictedVec<String, 2, 4>,",
+    "    /// Occurs: 0..1\n    pub printsound: Option<measure::PrintsoundValue>,",
+    "    pub width: Option<String>,",
+  ] {
+    assert!(output.contains(field), "{field} in {output}");
+  }
+  // Only the particles read more or less than once are annotated.
+  assert_eq!(output.matches("/// Occurs:").count(), 4);
+
+  for field in [
+    r#"FieldMeta { name: "note", xml_name: Some("note"), namespace: None, kind: FieldKind::Element, min: 0, max: None, required: false }"#,
+    r#"FieldMeta { name: "direction", xml_name: Some("direction"), namespace: None, kind: FieldKind::Element, min: 2, max: Some(4), required: true }"#,
+    r#"FieldMeta { name: "printsound", xml_name: Some("printsound"), namespace: None, kind: FieldKind::Flattened, min: 0, max: Some(1), required: false }"#,
+  ] {
+    assert!(output.contains(field), "{field}");
+  }
+}
+
+#[test]
+fn verbose_codegen_only_adds_comments() {
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="note">
+        <xs:sequence>
+          <xs:element name="pitch" type="xs:string"/>
+          <xs:element name="tie" type="xs:string" maxOccurs="unbounded"/>
+        </xs:sequence>
+        <xs:attribute name="id" type="xs:string"/>
+      </xs:complexType>
+    </xs:schema>
+  "#;
+
+  let generate = |verbose_codegen| {
+    Xsd::new(schema)
+      .unwrap()
+      .generate_with(&GeneratorOptions {
+        verbose_codegen,
+        ..Default::default()
+      })
+      .unwrap()
+  };
+
+  let quiet = generate(false);
+  let verbose = generate(true);
+
+  assert!(verbose.contains("// field: pitch (element, minOccurs=1)"));
+  assert!(verbose.contains("// field: tie (element, minOccurs=1, maxOccurs=unbounded)"));
+  assert!(verbose.contains("// field: id (attribute, use=optional)"));
+
+  // The headers differ by the fingerprint of the options.
+  let stripped: Vec<_> = verbose
+    .lines()
+    .filter(|line| !line.trim_start().starts_with("// "))
+    .collect();
+  assert_eq!(stripped, quiet.lines().skip(1).collect::<Vec<_>>());
+}
+
+#[test]
+fn provenance_comments_only_add_comments() {
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:m="urn:music"
+      targetNamespace="urn:music">
+      <xs:complexType name="note">
+        <xs:sequence>
+          <xs:element name="pitch" type="xs:string"/>
+        </xs:sequence>
+        <xs:attribute name="id" type="xs:string"/>
+      </xs:complexType>
+      <xs:complexType name="grace-note">
+        <xs:complexContent>
+          <xs:extension base="m:note">
+            <xs:attribute name="slash" type="xs:boolean"/>
+          </xs:extension>
+        </xs:complexContent>
+      </xs:complexType>
+    </xs:schema>
+  "#;
+
+  let generate = |provenance_comments| {
+    Xsd::new(schema)
+      .unwrap()
+      .generate_with(&GeneratorOptions {
+        provenance_comments,
+        ..Default::default()
+      })
+      .unwrap()
+  };
+
+  let quiet = generate(false);
+  let commented = generate(true);
+
+  assert!(commented.contains("pub pitch: String, // from: m:pitch (Element)\n"));
+  assert!(commented.contains("pub slash: Option<bool>, // from: m:slash (Attribute)\n"));
+  assert!(commented.contains("// from: m:grace-note (ComplexType)\nimpl XsdGen for GraceNote {"));
+  assert!(commented.contains("pub grace_note: Note, // from: m:note (ComplexType)\n"));
+
+  // The headers differ by the fingerprint of the options.
+  let stripped: Vec<_> = commented
+    .lines()
+    .skip(1)
+    .filter(|line| !line.trim_start().starts_with("// from: "))
+    .map(|line| line.split(" // from: ").next().unwrap())
+    .collect();
+  assert_eq!(stripped, quiet.lines().skip(1).collect::<Vec<_>>());
+}
+
+#[test]
+fn content_hashes_change_with_the_definition() {
+  let generate = |schema: &str, content_hashes| {
+    Xsd::new(schema)
+      .unwrap()
+      .generate_with(&GeneratorOptions {
+        content_hashes,
+        ..Default::default()
+      })
+      .unwrap()
+  };
+  let hash_of = |generated: &str, item: &str| {
+    let lines: Vec<_> = generated.lines().collect();
+    let index = lines.iter().position(|line| *line == item).unwrap();
+    lines[..index]
+      .iter()
+      .rev()
+      .find_map(|line| line.strip_prefix("// xsd-hash: "))
+      .unwrap()
+      .to_string()
+  };
+
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="note">
+        <xs:attribute name="id" type="xs:string"/>
+      </xs:complexType>
+      <xs:complexType name="rest">
+        <xs:attribute name="measure" type="xs:boolean"/>
+      </xs:complexType>
+    </xs:schema>
+  "#;
+  // The components swapped and formatted differently, with the definition of `rest` changed.
+  let updated = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="rest">
+        <xs:attribute name="measure" type="xs:string"/>
+      </xs:complexType>
+      <xs:complexType name="note"><xs:attribute name="id" type="xs:string"/></xs:complexType>
+    </xs:schema>
+  "#;
+
+  let hashed = generate(schema, true);
+  assert_eq!(hashed, generate(schema, true));
+  let hash = hash_of(&hashed, "pub struct Note {");
+  assert_eq!(hash.len(), 8);
+  assert_ne!(hash, hash_of(&hashed, "pub struct Rest {"));
+
+  let updated = generate(updated, true);
+  assert_eq!(hash, hash_of(&updated, "pub struct Note {"));
+  assert_ne!(
+    hash_of(&hashed, "pub struct Rest {"),
+    hash_of(&updated, "pub struct Rest {")
+  );
+
+  // The headers differ by the fingerprint of the options.
+  let stripped: Vec<_> = hashed
+    .lines()
+    .skip(1)
+    .filter(|line| !line.starts_with("// xsd-hash: "))
+    .collect();
+  assert_eq!(
+    stripped,
+    generate(schema, false).lines().skip(1).collect::<Vec<_>>()
+  );
+}
+
+#[test]
+fn schema_errors_are_told_apart_from_document_errors() {
+  let error =
+    Xsd::new(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"><xs:element/></xs:schema>"#)
+      .unwrap_err();
+  assert!(
+    matches!(error, XsdError::SchemaError(SchemaError::Invalid(_))),
+    "{error:?}"
+  );
+
+  // A schema is read as a document, what can't be read is a problem of the schema.
+  let mut note = XMLElement::parse(b"<note/>").unwrap();
+  let error = note.get_attribute::<String>("id").unwrap_err();
+  assert!(matches!(error, DocumentError::XsdParseError(_)));
+  assert!(matches!(SchemaError::from(error), SchemaError::Invalid(_)));
+}
+
+#[test]
+fn groups_holding_all_are_referenced_once() {
+  let generate = |occurrences: &str| {
+    Xsd::new(&format!(
+      r#"
+      <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+        <xs:group name="pitch">
+          <xs:all><xs:element name="step" type="xs:string"/></xs:all>
+        </xs:group>
+        <xs:complexType name="pitch">
+          <xs:group ref="pitch"{occurrences}/>
+        </xs:complexType>
+      </xs:schema>
+    "#
+    ))
+    .unwrap()
+    .generate_with(&GeneratorOptions::default())
+  };
+
+  let output = generate("").unwrap();
+  assert!(output.contains("element.order_children(&[\"step\"]);"));
+
+  for occurrences in [r#" minOccurs="0""#, r#" maxOccurs="2""#] {
+    let error = generate(occurrences).unwrap_err();
+    assert!(
+      format!("{error:?}").contains("`pitch` holds an all"),
+      "{error:?}"
+    );
+  }
+
+  let error = Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:group name="pitch">
+        <xs:all><xs:element name="step" type="xs:string" maxOccurs="2"/></xs:all>
+      </xs:group>
+    </xs:schema>
+  "#,
+  )
+  .unwrap_err();
+  assert!(
+    error
+      .to_string()
+      .contains("maxOccurs of the elements of all must be 0 or 1 on `step`"),
+    "{error}"
+  );
+}
+
+#[test]
+fn generating_again_gives_the_same_output() {
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:element name="chord" type="chord"/>
+      <xs:complexType name="chord">
+        <xs:sequence>
+          <xs:element name="note" type="note" maxOccurs="unbounded"/>
+        </xs:sequence>
+      </xs:complexType>
+      <xs:complexType name="note">
+        <xs:attribute name="version" type="xs:string" fixed="3.1"/>
+      </xs:complexType>
+      <xs:complexType name="rest"/>
+    </xs:schema>
+  "#;
+  let pruned = GeneratorOptions {
+    roots: vec!["chord".to_string()],
+    fixed_values: FixedValues::Marker,
+    ..Default::default()
+  };
+  let fresh = |options| Xsd::new(schema).unwrap().generate_with(options).unwrap();
+
+  let xsd = Xsd::new(schema).unwrap();
+  let first = xsd.generate_with(&pruned).unwrap();
+  assert!(!first.contains("pub struct Rest"));
+  assert_eq!(first, fresh(&pruned));
+
+  let all = xsd.generate_with(&GeneratorOptions::default()).unwrap();
+  assert!(all.contains("pub struct Rest"));
+  assert_eq!(all, fresh(&GeneratorOptions::default()));
+
+  assert_eq!(xsd.generate_with(&pruned).unwrap(), first);
+}
+
+#[test]
+fn schemas_are_loaded_from_bytes() {
+  let schema = r#"<?xml version="1.0" encoding="UTF-16"?>
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:element name="work-title" type="xs:string"/>
+    </xs:schema>"#;
+
+  let mut utf8 = vec![0xef, 0xbb, 0xbf];
+  utf8.extend(schema.replace("UTF-16", "UTF-8").bytes());
+  let mut utf16 = vec![0xff, 0xfe];
+  utf16.extend(schema.encode_utf16().flat_map(u16::to_le_bytes));
+
+  for content in [utf8, utf16] {
+    let xsd = Xsd::new_from_bytes(&content).unwrap();
+    assert_eq!(
+      xsd.top_level_components(),
+      [XsdName::new("work-title", xsd_types::XsdType::Element)]
+    );
+  }
+}
+
+#[test]
+fn large_choices_keep_the_order_of_their_alternatives() {
+  let elements = |range: std::ops::Range<usize>| {
+    range
+      .map(|i| format!(r#"<xs:element name="mark-{i}" type="xs:string" />"#))
+      .collect::<String>()
+  };
+  let schema = format!(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="data">
+        <xs:choice>
+          {}
+          <xs:sequence>
+            <xs:element name="mark-0" type="xs:int" />
+            <xs:element name="mark-1" type="xs:int" />
+          </xs:sequence>
+          {}
+        </xs:choice>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+    elements(0..40),
+    elements(40..80)
+  );
+  let xsd = Xsd::new(&schema).unwrap();
+
+  // The sequence is still attempted between the two runs of element alternatives.
+  let (output, report) = xsd
+    .generate_with_report(&GeneratorOptions::default())
+    .unwrap();
+  let table = output.find("Some(\"mark-39\") => return").unwrap();
+  let sequence = output
+    .find("let mut variant_element = element.clone();")
+    .unwrap();
+  let second_table = output.rfind("match next_child.as_deref()").unwrap();
+  assert!(table < sequence && sequence < second_table);
+  assert_eq!(output.matches("match next_child.as_deref()").count(), 2);
+  assert_eq!(report.dispatch_tables, ["data::Data"]);
+
+  let small = Xsd::new(&schema.replace(&elements(40..80), ""))
+    .unwrap()
+    .generate_with_report(&GeneratorOptions::default())
+    .unwrap();
+  assert!(!small.0.contains("next_child_name"));
+  assert!(small.1.dispatch_tables.is_empty());
+}
+
+#[test]
+fn choice_visitors_are_only_emitted_for_choices() {
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:simpleType name="step">
+        <xs:restriction base="xs:string">
+          <xs:enumeration value="A"/>
+          <xs:enumeration value="B"/>
+        </xs:restriction>
+      </xs:simpleType>
+      <xs:simpleType name="size">
+        <xs:union memberTypes="xs:int step"/>
+      </xs:simpleType>
+      <xs:complexType name="mark">
+        <xs:choice>
+          <xs:element name="type" type="step"/>
+          <xs:element name="size" type="size"/>
+        </xs:choice>
+      </xs:complexType>
+    </xs:schema>
+  "#;
+  let generate = |choice_visitors| {
+    Xsd::new(schema)
+      .unwrap()
+      .generate_with(&GeneratorOptions {
+        choice_visitors,
+        ..Default::default()
+      })
+      .unwrap()
+  };
+
+  let output = generate(true);
+  assert_eq!(output.matches("pub trait ").count(), 1, "{output}");
+  assert!(output.contains("fn visit_r_type(&mut self, _value: &Step) {}"));
+  assert!(output.contains("Self::Size(value) => visitor.visit_size(value),"));
+  assert!(output.contains("pub fn accept(&self, visitor: &mut impl MarkVisitor) {"));
+  assert!(!generate(false).contains("Visitor"));
+}
+
+#[test]
+fn extra_derives_reach_every_generated_type() {
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="mark">
+        <xs:choice>
+          <xs:element name="size" type="xs:int"/>
+          <xs:element name="step">
+            <xs:simpleType>
+              <xs:restriction base="xs:string">
+                <xs:enumeration value="A"/>
+                <xs:enumeration value="B"/>
+              </xs:restriction>
+            </xs:simpleType>
+          </xs:element>
+        </xs:choice>
+        <xs:attribute name="id" type="xs:string"/>
+      </xs:complexType>
+    </xs:schema>
+  "#;
+  let generate = |serde| {
+    Xsd::new(schema)
+      .unwrap()
+      .generate_with(&GeneratorOptions {
+        extra_derives: vec!["serde::Serialize".to_string(), "Eq".to_string()],
+        serde,
+        ..Default::default()
+      })
+      .unwrap()
+  };
+
+  // The inner types too, in their module.
+  let output = generate(false);
+  let derives: Vec<&str> = output
+    .lines()
+    .map(str::trim)
+    .filter(|line| line.starts_with("#[derive("))
+    .collect();
+  assert_eq!(derives.len(), 3, "{output}");
+  assert!(
+    derives
+      .iter()
+      .all(|derive| derive.ends_with(", serde::Serialize, Eq)]")),
+    "{output}"
+  );
+
+  // Along with `serde`, the derive is not repeated.
+  let output = generate(true);
+  assert!(
+    !output.contains("serde::Serialize, Eq, serde::Serialize"),
+    "{output}"
+  );
+  assert!(output.contains("serde::Serialize, Eq, serde::Deserialize)]\npub struct Mark {"));
+}
+
+#[test]
+fn doc_links_name_the_types_from_their_module() {
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:simpleType name="pitch">
+        <xs:annotation><xs:documentation>The pitch of a Note, e.g. [C4].</xs:documentation></xs:annotation>
+        <xs:restriction base="xs:string"><xs:maxLength value="3"/></xs:restriction>
+      </xs:simpleType>
+      <xs:complexType name="measure">
+        <xs:sequence>
+          <xs:element name="note" maxOccurs="unbounded">
+            <xs:complexType>
+              <xs:annotation><xs:documentation>A Note of a Measure, at a `Pitch`.</xs:documentation></xs:annotation>
+              <xs:attribute name="pitch" type="pitch"/>
+            </xs:complexType>
+          </xs:element>
+        </xs:sequence>
+      </xs:complexType>
+    </xs:schema>
+  "#;
+  let output = Xsd::new(schema)
+    .unwrap()
+    .generate_with(&GeneratorOptions {
+      doc_links: true,
+      ..Default::default()
+    })
+    .unwrap();
+
+  assert!(output.contains("/// Type: [`measure::Note`]\n    /// Occurs: 1..∞\n"));
+  assert!(output.contains("/// Type: [`super::Pitch`]\n        pub pitch: Option<Pitch>,"));
+  assert!(output.contains("/// A [Note] of a [Measure], at a `Pitch`."));
+  assert!(output.contains("/// The pitch of a [Note](measure::Note), e.g. \\[C4\\]."));
+}
+
+#[test]
+fn documented_attributes_keep_their_doc_on_the_alias() {
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:attribute name="color" type="xs:token">
+        <xs:annotation><xs:documentation>The color of an element, as #RRGGBB.</xs:documentation></xs:annotation>
+      </xs:attribute>
+      <xs:attribute name="placement">
+        <xs:annotation><xs:documentation>Above or below.</xs:documentation></xs:annotation>
+        <xs:simpleType>
+          <xs:restriction base="xs:string">
+            <xs:enumeration value="above"/>
+            <xs:enumeration value="below"/>
+          </xs:restriction>
+        </xs:simpleType>
+      </xs:attribute>
+    </xs:schema>
+  "#;
+  let output = Xsd::new(schema)
+    .unwrap()
+    .generate_with(&GeneratorOptions::default())
+    .unwrap();
+
+  assert!(output.contains("/// The color of an element, as #RRGGBB.\npub type Color = String;"));
+  assert!(output.contains("/// Above or below.\npub type Placement = placement::Placement;"));
+}
+
+#[test]
+fn attributes_with_a_constant_are_newtypes() {
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="http://www.w3.org/XML/1998/namespace">
+      <xs:attribute name="space" default="preserve">
+        <xs:simpleType>
+          <xs:restriction base="xs:NCName">
+            <xs:enumeration value="default"/>
+            <xs:enumeration value="preserve"/>
+          </xs:restriction>
+        </xs:simpleType>
+      </xs:attribute>
+      <xs:attribute name="lang" type="xs:language" fixed="en"/>
+      <xs:complexType name="code">
+        <xs:attribute ref="xml:space"/>
+        <xs:attribute ref="xml:lang"/>
+      </xs:complexType>
+    </xs:schema>
+  "#;
+  let output = Xsd::new(schema)
+    .unwrap()
+    .generate_with(&GeneratorOptions::default())
+    .unwrap();
+
+  assert!(output.contains("pub struct Space(pub space::Space);"));
+  assert!(output.contains("pub const DEFAULT: &'static str = \"preserve\";"));
+  assert!(output.contains("<space::Space as FromXmlString>::from_xml(string).map(Self)"));
+  assert!(output.contains("pub const FIXED: &'static str = \"en\";"));
+  assert!(output.contains("value => Err(format!(\"Expected the fixed value en found {value}.\")),"));
+  assert!(output.contains("Self(space::Space::Preserve)"));
+  // The references use the newtypes, and their constant when the attribute is absent.
+  assert!(output.contains("pub space: Space,"));
+  assert!(output.contains("pub lang: Lang,"));
+  assert!(output.contains(
+    "space: element.gen_attribute_or::<Space>(gen_state.to_attr(), Some(\"http://www.w3.org/XML/1998/namespace:space\"), Space::DEFAULT)?,"
+  ));
+  assert!(output.contains("Some(\"http://www.w3.org/XML/1998/namespace:lang\"), Lang::FIXED)?,"));
+}
+
+#[test]
+fn keys_without_generated_fields_are_warnings() {
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:element name="score">
+        <xs:complexType>
+          <xs:sequence>
+            <xs:element name="part" maxOccurs="unbounded">
+              <xs:complexType>
+                <xs:attribute name="id" type="xs:ID" use="required"/>
+              </xs:complexType>
+            </xs:element>
+            <xs:element name="part-ref" minOccurs="0">
+              <xs:complexType>
+                <xs:attribute name="part" type="xs:IDREF"/>
+              </xs:complexType>
+            </xs:element>
+          </xs:sequence>
+        </xs:complexType>
+        <xs:key name="part">
+          <xs:selector xpath=".//part"/>
+          <xs:field xpath="@id"/>
+        </xs:key>
+        <xs:keyref name="part-ref" refer="part">
+          <xs:selector xpath="part-ref"/>
+          <xs:field xpath="@part"/>
+        </xs:keyref>
+        <xs:keyref name="staff-ref" refer="staff">
+          <xs:selector xpath="part-ref"/>
+          <xs:field xpath="@part"/>
+        </xs:keyref>
+      </xs:element>
+    </xs:schema>
+  "#;
+  let xsd = Xsd::new(schema).unwrap();
+  let options = GeneratorOptions {
+    key_resolvers: true,
+    ..Default::default()
+  };
+  let (output, report) = xsd.generate_with_report(&options).unwrap();
+
+  assert!(!output.contains("PartKey"));
+  assert_eq!(
+    report.warnings,
+    vec![
+      Warning {
+        path: r#"/schema/element[@name="score"]/keyref[@name="staff-ref"]"#.to_string(),
+        msg: "no resolver for `staff-ref`: the key it refers to is not declared on `score`"
+          .to_string(),
+      },
+      Warning {
+        path: r#"/schema/element[@name="score"]/key[@name="part"]"#.to_string(),
+        msg: "no resolver for `part`: a path starts with `.//`".to_string(),
+      },
+    ]
+  );
+  assert_eq!(xsd.plan(&options).unwrap().warnings, report.warnings);
+}
+
+#[test]
+fn naming_conventions_case_the_generated_names() {
+  use xsd_types::{DefaultNaming, NamingConvention};
+
+  /// Keeps the camelCase of the schema for the fields.
+  struct PreserveCase;
+
+  impl NamingConvention for PreserveCase {
+    fn struct_name(&self, name: &str) -> String {
+      DefaultNaming.struct_name(name)
+    }
+
+    fn field_name(&self, name: &str) -> String {
+      name.replace('-', "_")
+    }
+
+    fn module_name(&self, name: &str) -> String {
+      DefaultNaming.field_name(name)
+    }
+  }
+
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:simpleType name="stepName">
+        <xs:restriction base="xs:string">
+          <xs:enumeration value="C-sharp"/>
+          <xs:enumeration value="D"/>
+        </xs:restriction>
+      </xs:simpleType>
+      <xs:complexType name="pitchInfo">
+        <xs:sequence>
+          <xs:element name="stepName" type="stepName"/>
+          <xs:element name="octaveNumber" type="xs:int"/>
+        </xs:sequence>
+        <xs:attribute name="type" type="xs:string"/>
+      </xs:complexType>
+    </xs:schema>
+  "#;
+  let xsd = Xsd::new(schema).unwrap();
+  let output = xsd
+    .generate_with(&GeneratorOptions {
+      naming: Naming::new(PreserveCase),
+      ..Default::default()
+    })
+    .unwrap();
+
+  // The keyword is still escaped.
+  assert!(output.contains(
+    "pub struct PitchInfo {\n    pub stepName: StepName,\n    pub octaveNumber: i32,\n    pub r#type: Option<String>,\n}"
+  ));
+  assert!(output.contains(
+    "stepName: <StepName as XsdGen>::gen(element, gen_state.clone(), Some(\"stepName\"))?,"
+  ));
+  assert!(output.contains("\"C-sharp\" => Ok(Self::CSharp),"));
+
+  // The default convention is back once generated.
+  let output = xsd.generate_with(&GeneratorOptions::default()).unwrap();
+  assert!(output.contains("pub step_name: StepName,"));
+
+  // The fingerprint of the options tells the conventions apart, the default one is the same
+  // whether it is set or not.
+  let fingerprint = |naming| {
+    GeneratorOptions {
+      naming,
+      ..Default::default()
+    }
+    .fingerprint()
+  };
+  assert_ne!(
+    fingerprint(Naming::new(PreserveCase)),
+    fingerprint(Naming::default())
+  );
+  assert_eq!(
+    fingerprint(Naming::new(DefaultNaming)),
+    fingerprint(Naming::default())
+  );
+}
+
+#[test]
+fn check_generated_tells_the_cause_of_the_drift() {
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="note"><xs:attribute name="id" type="xs:string"/></xs:complexType>
+      <xs:complexType name="rest"/>
+    </xs:schema>
+  "#;
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("note.rs");
+  let xsd = Xsd::new(schema).unwrap();
+  std::fs::write(
+    &path,
+    xsd.generate_with(&GeneratorOptions::default()).unwrap(),
+  )
+  .unwrap();
+
+  let header = format!(
+    "// @generated by xml-schema-parser {} with options {}, do not edit.",
+    env!("CARGO_PKG_VERSION"),
+    GeneratorOptions::default().fingerprint()
+  );
+  assert!(std::fs::read_to_string(&path)
+    .unwrap()
+    .starts_with(&format!("{header}\n")));
+  xsd.check_generated(&path).unwrap();
+
+  // Only the header names the options, the items are the same.
+  let serde = GeneratorOptions {
+    serde: true,
+    ..Default::default()
+  };
+  assert_ne!(
+    serde.fingerprint(),
+    GeneratorOptions::default().fingerprint()
+  );
+  let report = xsd.check_generated_with(&path, &serde).unwrap_err();
+  assert!(
+    matches!(&report.cause, DriftCause::Options { fingerprint } if *fingerprint == GeneratorOptions::default().fingerprint()),
+    "{report}"
+  );
+  assert!(report
+    .items
+    .iter()
+    .all(|item| item.change == ItemChange::Changed));
+  assert!(report
+    .items
+    .iter()
+    .any(|item| item.item == "pub struct Note {"));
+
+  let updated = Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="note"><xs:attribute name="id" type="xs:int"/></xs:complexType>
+      <xs:complexType name="chord"/>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+  let report = updated.check_generated(&path).unwrap_err();
+  assert!(matches!(report.cause, DriftCause::Schema), "{report}");
+  let items: Vec<_> = report
+    .items
+    .iter()
+    .map(|item| (item.change, item.item.as_str()))
+    .collect();
+  assert_eq!(
+    items,
+    [
+      (ItemChange::Added, "pub struct Chord;"),
+      (ItemChange::Added, "impl XsdGen for Chord {"),
+      (ItemChange::Changed, "pub struct Note {"),
+      (ItemChange::Changed, "impl XsdGen for Note {"),
+      (ItemChange::Removed, "pub struct Rest;"),
+      (ItemChange::Removed, "impl XsdGen for Rest {"),
+    ]
+  );
+
+  // Another version of the generator is told apart from the schema.
+  let older =
+    std::fs::read_to_string(&path)
+      .unwrap()
+      .replacen(env!("CARGO_PKG_VERSION"), "0.0.1", 1);
+  std::fs::write(&path, older).unwrap();
+  xsd.check_generated(&path).unwrap();
+  let report = updated.check_generated(&path).unwrap_err();
+  assert!(
+    matches!(&report.cause, DriftCause::Generator { version: Some(version) } if version == "0.0.1"),
+    "{report}"
+  );
+}
+
+#[test]
+fn component_errors_have_the_kind_of_their_source() {
+  let error = XsdError::ComponentError {
+    name: XsdName::new("note", XsdType::ComplexType),
+    dependents: vec![],
+    source: Box::new(XsdError::Offline {
+      location: "http://www.musicxml.org/xsd/xlink.xsd".to_string(),
+    }),
+  };
+  assert_eq!(error.kind(), XsdErrorKind::Io);
+
+  let error = XsdError::ExternalNamespace {
+    namespace: "urn:missing".to_string(),
+    msg: "no component of the schema is in this namespace".to_string(),
+  };
+  assert_eq!(error.kind(), XsdErrorKind::Generation);
+}
@@ -0,0 +1,556 @@
+use std::{
+  collections::BTreeMap,
+  fmt, fs,
+  path::{Path, PathBuf},
+  str::FromStr,
+  sync::Arc,
+};
+
+use xsd_codegen::Type;
+use xsd_types::{DefaultNaming, NamingConvention, XsdName};
+
+use super::{
+  canonical::Canonical, max_occurences::DEFAULT_MAX_OCCURS_LIMIT, CancellationToken, XsdError,
+  XsdVersion,
+};
+
+/// Provides the content of schema documents by location, e.g. out of a zip archive or a map of
+/// embedded assets.
+pub trait SchemaSource: Send + Sync {
+  /// The bytes of the document at `location`, either the schema loaded with
+  /// `Xsd::new_from_file_with` or the `schemaLocation` of an import.
+  fn fetch(&self, location: &str) -> Result<Vec<u8>, XsdError>;
+}
+
+/// Controls how schema documents (and the documents they import) are located.
+#[derive(Clone, Default)]
+pub struct LoadOptions {
+  /// Never fetch schemas over the network, only local files and catalog entries are used.
+  pub offline: bool,
+  /// Maps schema locations (usually URLs) to local files.
+  pub catalog: BTreeMap<String, PathBuf>,
+  /// Fetches every document instead of the catalog, the local files and the network.
+  pub source: Option<Arc<dyn SchemaSource>>,
+  /// The directory the relative location of a loaded schema is read from, it is read as given
+  /// when unset. The relative locations of the imports are read from the directory of the
+  /// document importing them.
+  pub base_dir: Option<PathBuf>,
+}
+
+impl fmt::Debug for LoadOptions {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("LoadOptions")
+      .field("offline", &self.offline)
+      .field("catalog", &self.catalog)
+      .field("source", &self.source.as_ref().map(|_| "SchemaSource"))
+      .field("base_dir", &self.base_dir)
+      .finish()
+  }
+}
+
+/// Options are equal when they share the same source.
+impl PartialEq for LoadOptions {
+  fn eq(&self, other: &Self) -> bool {
+    let source = match (&self.source, &other.source) {
+      (Some(source), Some(other)) => Arc::ptr_eq(source, other),
+      (source, other) => source.is_none() && other.is_none(),
+    };
+    source
+      && self.offline == other.offline
+      && self.catalog == other.catalog
+      && self.base_dir == other.base_dir
+  }
+}
+
+/// The default source: the `source` of the options when set, otherwise the catalog entry of the
+/// location, the network for HTTP(S) locations and the local file.
+impl SchemaSource for LoadOptions {
+  fn fetch(&self, location: &str) -> Result<Vec<u8>, XsdError> {
+    if let Some(source) = &self.source {
+      return source.fetch(location);
+    }
+
+    if let Some(local) = self.resolve(location) {
+      tracing::info!("Load {} from catalog entry {}", location, local.display());
+      read_schema(local)
+    } else if is_remote(location) {
+      if self.offline {
+        return Err(XsdError::Offline {
+          location: location.to_string(),
+        });
+      }
+
+      tracing::info!("Load HTTP schema {}", location);
+      Ok(reqwest::blocking::get(location)?.bytes()?.to_vec())
+    } else {
+      let path = resolve_location(self.base_dir.as_deref(), location);
+      tracing::info!("Load {} from {}", location, path.display());
+
+      read_schema(&path)
+    }
+  }
+}
+
+fn read_schema(path: &Path) -> Result<Vec<u8>, XsdError> {
+  fs::read(path).map_err(|source| XsdError::ReadSchema {
+    path: path.to_path_buf(),
+    source,
+  })
+}
+
+fn is_remote(location: &str) -> bool {
+  location.starts_with("http://") || location.starts_with("https://")
+}
+
+impl LoadOptions {
+  /// Read catalog entries from a file.
+  ///
+  /// Every non-empty line that does not start with `#` is a schema location followed by
+  /// whitespace and the path of the local copy. Relative paths are resolved against the
+  /// directory containing the catalog file.
+  pub fn with_catalog_file(mut self, path: impl AsRef<Path>) -> Result<Self, XsdError> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)?;
+    let base = path.parent();
+
+    for line in content.lines() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+
+      if let Some((location, local)) = line.split_once(char::is_whitespace) {
+        self
+          .catalog
+          .insert(location.to_string(), resolve_location(base, local.trim()));
+      } else {
+        return Err(XsdError::InvalidCatalog {
+          path: path.to_path_buf(),
+          line: line.to_string(),
+        });
+      }
+    }
+
+    Ok(self)
+  }
+
+  /// Resolve a schema location through the catalog.
+  pub fn resolve(&self, location: &str) -> Option<&Path> {
+    self.catalog.get(location).map(|v| v.as_path())
+  }
+
+  /// The local file the document at `location` is read from, `None` when it is fetched from the
+  /// `source` or the network.
+  pub(crate) fn local_path(&self, location: &str) -> Option<PathBuf> {
+    if self.source.is_some() {
+      None
+    } else if let Some(local) = self.resolve(location) {
+      Some(local.to_path_buf())
+    } else if is_remote(location) {
+      None
+    } else {
+      Some(resolve_location(self.base_dir.as_deref(), location))
+    }
+  }
+
+  /// What identifies the document at `location`: the local file it is read from, otherwise the
+  /// location itself. The same relative location read from two directories names two documents.
+  pub(crate) fn document_key(&self, location: &str) -> String {
+    match self.local_path(location) {
+      Some(path) => path.display().to_string(),
+      None => location.to_string(),
+    }
+  }
+
+  /// The options loading the documents imported by the one at `location`, whose relative
+  /// locations are read from its directory.
+  pub(crate) fn for_document(&self, location: &str) -> Self {
+    let mut options = self.clone();
+    if let Some(dir) = self.local_path(location).as_deref().and_then(Path::parent) {
+      options.base_dir = Some(dir.to_path_buf());
+    }
+    options
+  }
+}
+
+/// The path of the local `location`, resolved against `base` when it is relative. Without a
+/// base, only the location of a schema loaded by the caller, it is read as given.
+pub(crate) fn resolve_location(base: Option<&Path>, location: &str) -> PathBuf {
+  match base {
+    Some(base) => base.join(location),
+    None => PathBuf::from(location),
+  }
+}
+
+/// How values that can only ever hold a single string are represented.
+///
+/// This covers attributes with a `fixed` value and restrictions with a single enumeration.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FixedValues {
+  /// Use the regular type of the value (usually an owned `String`).
+  #[default]
+  Owned,
+  /// Use a zero-sized marker type, with `as_str()` returning the constant.
+  Marker,
+  /// Use `&'static str` fields holding the constant. Named types (top-level attributes and
+  /// single enumerations) still use the marker type.
+  StaticStr,
+}
+
+impl FromStr for FixedValues {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "owned" => Ok(Self::Owned),
+      "marker" => Ok(Self::Marker),
+      "static-str" => Ok(Self::StaticStr),
+      s => Err(format!(
+        "{s} is not a recognized representation; expected (owned|marker|static-str)."
+      )),
+    }
+  }
+}
+
+/// The visibility of the generated items, see `GeneratorOptions::visibility`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Visibility {
+  #[default]
+  Pub,
+  /// `pub(crate)`, keeping the items out of the API of the crate holding them.
+  PubCrate,
+  /// `pub(super)`, only visible to the module including the generated code.
+  PubSuper,
+}
+
+impl Visibility {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Self::Pub => "pub",
+      Self::PubCrate => "pub(crate)",
+      Self::PubSuper => "pub(super)",
+    }
+  }
+}
+
+impl FromStr for Visibility {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "pub" => Ok(Self::Pub),
+      "pub(crate)" => Ok(Self::PubCrate),
+      "pub(super)" => Ok(Self::PubSuper),
+      s => Err(format!(
+        "{s} is not a recognized visibility; expected (pub|pub(crate)|pub(super))."
+      )),
+    }
+  }
+}
+
+/// Rust type used for the builtin types holding text (`xs:string`, `xs:anyURI`, ...).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum StringStorage {
+  #[default]
+  String,
+  /// `Box<str>`, a word smaller than `String` and never holding spare capacity.
+  BoxStr,
+  /// Path of a type implementing `FromXmlString`, along with the traits derived by the
+  /// generated types.
+  Custom(String),
+}
+
+impl StringStorage {
+  pub fn to_type(&self) -> Type {
+    match self {
+      Self::String => Type::new(None, "String"),
+      Self::BoxStr => {
+        let mut ty = Type::new(None, "Box");
+        ty.generics = vec![Type::new(None, "str")];
+        ty
+      }
+      Self::Custom(path) => Type::new(None, path),
+    }
+  }
+}
+
+impl FromStr for StringStorage {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "string" => Ok(Self::String),
+      "box-str" => Ok(Self::BoxStr),
+      "" => Err("expected (string|box-str) or the path of a type.".to_string()),
+      path => Ok(Self::Custom(path.to_string())),
+    }
+  }
+}
+
+/// What is generated for a component stubbed out with `GeneratorOptions::stubs`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StubKind {
+  /// Keep the content of the component unparsed: elements and types become an alias of
+  /// `RawXml`, attributes of the type holding text.
+  Opaque,
+  /// Generate nothing for the component, and remove the fields and variants referencing it.
+  Skip,
+}
+
+impl FromStr for StubKind {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "opaque" => Ok(Self::Opaque),
+      "skip" => Ok(Self::Skip),
+      s => Err(format!(
+        "{s} is not a recognized stub; expected (opaque|skip)."
+      )),
+    }
+  }
+}
+
+/// A change to a field of a generated struct, see `GeneratorOptions::field_overrides`. The field
+/// is still read from the same element or attribute unless it is dropped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldOverride {
+  /// The component the struct is generated from, e.g. the complex type `note`. As for the stubs,
+  /// the namespace can be a prefix declared by the schema, and a name without namespace matches
+  /// the component in any namespace.
+  pub owner: XsdName,
+  /// The local name of the element or attribute the field is read from.
+  pub xml_name: String,
+  /// The Rust name of the field.
+  pub rename: Option<String>,
+  /// The whole Rust type of the field, e.g. `Option<u32>`, which must implement `XsdGen`.
+  pub ty: Option<String>,
+  /// Whether the field is read from an attribute.
+  pub attribute: Option<bool>,
+  /// Whether the field is read from the current element instead of a child.
+  pub flatten: Option<bool>,
+  /// Remove the field, its element or attribute is no longer read.
+  pub drop: bool,
+}
+
+impl FieldOverride {
+  /// An override of the field of `owner` read from `xml_name`, changing nothing yet.
+  pub fn new(owner: XsdName, xml_name: &str) -> Self {
+    Self {
+      owner,
+      xml_name: xml_name.to_string(),
+      rename: None,
+      ty: None,
+      attribute: None,
+      flatten: None,
+      drop: false,
+    }
+  }
+}
+
+/// The `NamingConvention` of the generated names, `DefaultNaming` when `None`.
+#[derive(Clone, Default)]
+pub struct Naming(pub Option<Arc<dyn NamingConvention>>);
+
+impl Naming {
+  pub fn new(convention: impl NamingConvention + 'static) -> Self {
+    Self(Some(Arc::new(convention)))
+  }
+
+  pub fn convention(&self) -> Arc<dyn NamingConvention> {
+    self.0.clone().unwrap_or_else(|| Arc::new(DefaultNaming))
+  }
+}
+
+impl fmt::Debug for Naming {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_tuple("Naming")
+      .field(&self.0.as_ref().map(|_| "NamingConvention"))
+      .finish()
+  }
+}
+
+/// Namings are equal when they share the same convention.
+impl PartialEq for Naming {
+  fn eq(&self, other: &Self) -> bool {
+    match (&self.0, &other.0) {
+      (Some(convention), Some(other)) => Arc::ptr_eq(convention, other),
+      (convention, other) => convention.is_none() && other.is_none(),
+    }
+  }
+}
+
+/// Controls what `Xsd::generate_with` emits.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GeneratorOptions {
+  /// Only emit these top-level components (by local name) and the types they depend on.
+  /// Everything is emitted when empty.
+  pub roots: Vec<String>,
+  /// Derives added to every generated struct and enum, the inner types included, e.g. `Eq` or
+  /// `serde::Serialize`. See `serde` to derive both serde traits with the fields renamed after
+  /// their XML names.
+  pub extra_derives: Vec<String>,
+  /// The visibility of the generated items, e.g. `pub(crate)` to keep them out of the API of the
+  /// crate holding them. Their fields and methods stay `pub`. The types the crate doesn't use are
+  /// then reported by the `dead_code` lint.
+  pub visibility: Visibility,
+  /// Emit the components of each namespace into their own module.
+  pub namespace_modules: bool,
+  /// Namespaces whose types are provided by another crate, mapped to the Rust path they are
+  /// imported from (e.g. `xlink_types`). The types of these namespaces are not emitted.
+  pub external_namespaces: BTreeMap<String, String>,
+  /// Representation of fixed values.
+  pub fixed_values: FixedValues,
+  /// Type of the fields holding text.
+  pub string_storage: StringStorage,
+  /// Emit a `#[cfg(test)]` module asserting every generated type is `Send + Sync + 'static`.
+  pub send_sync_assertions: bool,
+  /// Add the statistics of the generation report to the doc comment of each type.
+  pub stats_in_docs: bool,
+  /// Comment the generated parsing code with the schema construct behind each field and variant.
+  pub verbose_codegen: bool,
+  /// Follow each generated field and variant, and precede each impl, with a `// from:` comment
+  /// naming the schema component it was generated from, its kind and its document, e.g.
+  /// `// from: mxl:note-type (ComplexType, musicxml.xsd)`.
+  pub provenance_comments: bool,
+  /// Precede each top-level generated item with a `// xsd-hash: 1a2b3c4d` comment holding a hash
+  /// of the parsed definition of its schema component, which depends neither on the formatting
+  /// of the document nor on the names of the generated types. A diff of the generated code after
+  /// updating the schema then tells the types whose definition changed from those that moved.
+  pub content_hashes: bool,
+  /// Emit a `FIELDS` constant describing the fields of each generated struct, and a `VARIANTS`
+  /// constant for the enums.
+  pub field_metadata: bool,
+  /// Emit a `pub type` alias instead of a one-field tuple struct for the simple types restricting
+  /// another type without any facet or documentation.
+  pub flatten_wrappers: bool,
+  /// Emit a `gen_with_progress` constructor on the types of the top-level elements, parsing the
+  /// document with a progress hook.
+  pub progress_hooks: bool,
+  /// Emit an `async fn parse_async` constructor on the types of the top-level elements, reading
+  /// the document from a tokio reader and parsing it on the blocking pool. The constructors are
+  /// gated on the `tokio` feature of the crate holding the generated code, which has to enable
+  /// the `tokio` feature of this crate.
+  pub async_entry_points: bool,
+  /// Experimental: also emit a borrowed counterpart `{Type}Ref<'a>` of the structs holding text,
+  /// with `&'a str` instead of the owned strings, read with `XsdGenRef` out of a document parsed
+  /// by `xmltree`. Only the text of attributes and simple content is borrowed, the fields holding
+  /// other structs are read by their owned parsing code.
+  pub borrowed_types: bool,
+  /// Add an `xml_lang: Option<String>` field to the types reading the `xml:lang` attribute,
+  /// directly or through an attribute group such as `xml:specialAttrs`. It holds the language in
+  /// scope, i.e. the `xml:lang` of the element or else the one inherited from its ancestors.
+  pub inherited_lang: bool,
+  /// Follow the enum of each choice with a `{Enum}Visitor` trait, with a `visit_` method doing
+  /// nothing for each variant, and an `accept` method on the enum calling the method of the
+  /// variant it holds.
+  pub choice_visitors: bool,
+  /// Add intra-doc links to the docs for rustdoc: each field holding a generated type gets a
+  /// `Type:` line linking it by its path, e.g. `xlink::Href`, and the names of the generated
+  /// types mentioned by the annotations are linked. The brackets of the annotations are escaped.
+  pub doc_links: bool,
+  /// Emit resolvers for the `xs:key` constraints of the top-level elements that an `xs:keyref`
+  /// refers to: a `{Key}Key` type holding the values of its fields, and `{key}_index` and
+  /// `resolve_{key}` methods on the type of the document, along with a `resolve_{keyref}`
+  /// method for each keyref. Keys whose paths don't map to generated fields are reported as
+  /// warnings and get no resolver.
+  pub key_resolvers: bool,
+  /// Components generated as a stub instead of from their definition, which is not resolved. The
+  /// namespace can be a prefix declared by the schema, a name without namespace matches the
+  /// component in any namespace.
+  pub stubs: BTreeMap<XsdName, StubKind>,
+  /// Changes to single fields of the generated structs, applied once the types are merged and
+  /// before their parsing code is generated. Overrides of unknown components or fields are
+  /// reported as warnings naming the close matches.
+  pub field_overrides: Vec<FieldOverride>,
+  /// The newest version of XSD the schema may use, newer constructs are reported as warnings.
+  pub conformance: XsdVersion,
+  /// Fail instead of warning when the schema uses constructs newer than `conformance`.
+  pub strict_conformance: bool,
+  /// The largest `maxOccurs` read as a bound, 4294967295 when `None`. Larger values, such as
+  /// the `99999999999` some generators write for unbounded, are read as unbounded with a
+  /// warning. A limit above 4294967295 is an error, the bounds wouldn't fit the `usize` of the
+  /// 32-bit targets.
+  pub max_occurs_limit: Option<u64>,
+  /// Warn instead of failing when an enumeration value is not a value of the base of its
+  /// restriction, e.g. `1.5` restricting `xs:integer`.
+  pub lenient_enumerations: bool,
+  /// The Rust types the builtin types of XSD are read into, by the local name of the builtin,
+  /// e.g. `integer` to `i64` or `i128` instead of the default `i32`. The types have to implement
+  /// `FromXmlString`. Unknown builtins are reported as warnings.
+  pub builtin_types: BTreeMap<String, String>,
+  /// Fail instead of reporting the builtin types read into a Rust type that can't hold all their
+  /// values, e.g. `xs:integer` read into an `i32`, see `GenerationReport::lossy_mappings`.
+  pub strict_mappings: bool,
+  /// Derive `serde::Serialize` and `serde::Deserialize` on the generated types, with the fields
+  /// and variants renamed after the XML names. The generated code needs the `serde` feature of
+  /// this crate. Fixed values stored as `&'static str` and opaque stubs can't be deserialized.
+  pub serde: bool,
+  /// Convert the generated types to and from the runtime's `DynamicValue`, a tree of maps, lists
+  /// and scalars, e.g. to hand documents to a templating engine: `From<&T> for DynamicValue`,
+  /// `TryFrom<&DynamicValue>` and the `ToDynamic` and `FromDynamic` traits they nest through.
+  /// Structs are maps of their fields by XML name, and choices maps of their single alternative.
+  pub dynamic_values: bool,
+  /// Write the generated types back to XML, e.g. to round-trip documents: the types read from
+  /// their text implement `ToXmlString`, the others `XsdSer`, and the types of the top-level
+  /// elements get `XmlDocument::to_xml_string`. Elements and attributes are written by local
+  /// name, as they are read.
+  pub xml_writers: bool,
+  /// Keep the attributes and child elements that the types reading an element, the anonymous ones
+  /// of the nested elements included, don't read in an `unknown_content` field instead of
+  /// dropping or rejecting them, e.g. the extensions of a vendor, along with the positions of the
+  /// children. The writers of `xml_writers` write them back where they were, so that a document
+  /// can be modified and written again without losing them. The attributes keep their prefix
+  /// and namespace, see `UnknownContent`. Only the children reading skips are kept, i.e.
+  /// trailing or among the children read by name: the choices and the required complex children
+  /// are read from the next child element, which still has to be the expected one.
+  pub unknown_content: bool,
+  /// Lints allowed in the modules holding the inner types, which may not all be used once the
+  /// types are pruned and merged. `dead_code` and `unused_imports` when `None`.
+  pub inner_module_lints: Option<Vec<String>>,
+  /// Emit an `XML_TYPE_INDEX` static mapping the names of the schema types to the generated
+  /// types, which `QName::resolve` looks the values of `xs:QName` attributes up in.
+  pub type_index: bool,
+  /// Check the `pattern` facets of the simple types when reading their values, with the XSD
+  /// regular expressions translated to the syntax of the `regex` crate, see `Pattern`. The
+  /// patterns it can't express, e.g. with the block escapes like `\p{IsBasicLatin}`, are
+  /// logged and not checked.
+  pub pattern_checks: bool,
+  /// How the names of the schema are cased into the names of the generated types, fields,
+  /// variants and modules.
+  pub naming: Naming,
+  /// Stops the generation once cancelled, e.g. from another thread: it is checked while the
+  /// components are generated, before each import is loaded and while the code is emitted. The
+  /// generation then fails with `XsdError::Cancelled`.
+  pub cancel_token: CancellationToken,
+}
+
+impl GeneratorOptions {
+  /// Hash of the options, as 8 hex digits, recorded in the header of the generated code. The
+  /// canonical encoding of the options is hashed, see `canonical`, the hash is stable across runs
+  /// and versions of Rust. The naming convention counts by the names it gives, the cancellation
+  /// token doesn't count.
+  pub fn fingerprint(&self) -> String {
+    let mut encoding = vec![];
+    self.encode(&mut encoding);
+    // 32-bit FNV-1a, like the content hashes of the components.
+    let hash = encoding.iter().fold(0x811c9dc5_u32, |hash, byte| {
+      (hash ^ u32::from(*byte)).wrapping_mul(0x01000193)
+    });
+    format!("{hash:08x}")
+  }
+
+  /// Fail on the options the generated code can't be built with.
+  pub(crate) fn check(&self) -> Result<(), XsdError> {
+    // The bounds are emitted as `usize` const generics, only 32 bits wide on some targets.
+    if let Some(limit) = self.max_occurs_limit {
+      if limit > DEFAULT_MAX_OCCURS_LIMIT {
+        return Err(XsdError::InvalidOption {
+          option: "max_occurs_limit",
+          msg: format!("{limit} is above {DEFAULT_MAX_OCCURS_LIMIT}"),
+        });
+      }
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,128 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use xsd_codegen::{escape_str_literal, Function, Impl, Type};
+use xsd_types::{XsdName, XsdType};
+
+use super::xsd_context::{XsdContext, XsdImplType};
+
+/// Add a `gen_with_progress` constructor to the types the top-level elements of `selected` are
+/// read into, installing a progress hook for the parsing of the whole document.
+pub(crate) fn add_progress_helpers(context: &mut XsdContext, selected: &BTreeSet<XsdName>) {
+  for name in document_types(context, selected) {
+    let value = context.structs.get_mut(&name).unwrap();
+    let helper = progress_impl(&value.element.get_type());
+    value.implementation.push(helper);
+  }
+}
+
+/// The emitted structs and enums the top-level elements of `selected` are read into, i.e. the
+/// types of whole documents.
+pub(crate) fn document_types(
+  context: &XsdContext,
+  selected: &BTreeSet<XsdName>,
+) -> BTreeSet<XsdName> {
+  document_roots(context, selected).into_keys().collect()
+}
+
+/// The types of the documents, along with the top-level elements read into them.
+fn document_roots(
+  context: &XsdContext,
+  selected: &BTreeSet<XsdName>,
+) -> BTreeMap<XsdName, Vec<XsdName>> {
+  // The emitted structs and enums, by the name of their Rust type.
+  let types: BTreeMap<String, &XsdName> = context
+    .structs
+    .iter()
+    .filter(|(name, _)| selected.contains(name))
+    .filter_map(|(name, value)| match &value.element {
+      XsdImplType::Struct(ty) => Some((ty.ty().name.clone(), name)),
+      XsdImplType::Enum(ty) => Some((ty.ty().name.clone(), name)),
+      _ => None,
+    })
+    .collect();
+
+  let mut roots: BTreeMap<XsdName, Vec<XsdName>> = BTreeMap::new();
+  for (element, value) in &context.structs {
+    if element.ty != XsdType::Element || !selected.contains(element) {
+      continue;
+    }
+    let Some(name) = value
+      .element
+      .try_get_type()
+      .and_then(|ty| types.get(&ty.name))
+    else {
+      continue;
+    };
+    roots
+      .entry((*name).clone())
+      .or_default()
+      .push(element.clone());
+  }
+  roots
+}
+
+/// Implement `XmlDocument` for the types of the documents. A type read from several top-level
+/// elements has no single root name and is left out.
+pub(crate) fn add_document_impls(context: &mut XsdContext, selected: &BTreeSet<XsdName>) {
+  for (name, elements) in document_roots(context, selected) {
+    let [element] = &elements[..] else {
+      continue;
+    };
+    let value = context.structs.get_mut(&name).unwrap();
+    let document = document_impl(&value.element.get_type(), &element.local_name);
+    value.implementation.push(document);
+  }
+}
+
+fn document_impl(ty: &Type, root_name: &str) -> Impl {
+  let mut document = Impl::new(ty)
+    .impl_trait("xml_schema_parser::XmlDocument")
+    .push_fn(
+      Function::new("parse_str")
+        .arg("s", Type::new(None, "&str"))
+        .ret("Result<Self, DocumentError>")
+        .line("xml_schema_parser::parse_document(s, Self::ROOT_NAME)"),
+    );
+  document.associate_const("ROOT_NAME", "&'static str", &escape_str_literal(root_name));
+  document
+}
+
+/// Add an `async fn parse_async` constructor to the types of the documents, see
+/// `GeneratorOptions::async_entry_points`.
+pub(crate) fn add_async_entry_points(context: &mut XsdContext, selected: &BTreeSet<XsdName>) {
+  for name in document_types(context, selected) {
+    let value = context.structs.get_mut(&name).unwrap();
+    let entry_point = async_impl(&value.element.get_type());
+    value.implementation.push(entry_point);
+  }
+}
+
+fn async_impl(ty: &Type) -> Impl {
+  let mut entry_point = Impl::new(ty).push_fn(
+    Function::new("parse_async")
+      .doc("Read the document from `reader` and parse it on tokio's blocking pool.")
+      .vis("pub")
+      .set_async(true)
+      .arg(
+        "reader",
+        Type::new(None, "impl xml_schema_parser::AsyncRead + Unpin"),
+      )
+      .ret("Result<Self, DocumentError>")
+      .line("xml_schema_parser::parse_async(reader).await"),
+  );
+  entry_point.r#macro("#[cfg(feature = \"tokio\")]");
+  entry_point
+}
+
+fn progress_impl(ty: &Type) -> Impl {
+  Impl::new(ty).push_fn(
+    Function::new("gen_with_progress")
+      .doc("Parse the document rooted at `element`, reporting the progress of its repeated elements to `progress`.")
+      .vis("pub")
+      .arg("element", Type::new(None, "&mut XMLElement"))
+      .arg("progress", Type::new(None, "ProgressHook"))
+      .ret("Result<Self, DocumentError>")
+      .line("let gen_state = GenState { is_root: true, state: GenType::Content, progress: Some(progress) };")
+      .line("<Self as XsdGen>::gen(element, gen_state, None)"),
+  )
+}
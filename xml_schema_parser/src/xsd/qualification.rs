@@ -1,17 +1,12 @@
 use xsd_codegen::FromXmlString;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub enum Qualification {
   Qualidified,
+  #[default]
   Unqualified,
 }
 
-impl Default for Qualification {
-  fn default() -> Self {
-    Qualification::Unqualified
-  }
-}
-
 impl FromXmlString for Qualification {
   fn from_xml(s: &str) -> Result<Self, String> {
     match s {
@@ -0,0 +1,543 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use xsd_codegen::{ContentKind, Fields};
+use xsd_types::{is_reserved, to_module_name, to_struct_name, XsdName, XsdType};
+
+use super::{
+  is_large_choice,
+  lossy_mappings::LossyMapping,
+  xsd_context::{XsdImpl, XsdImplType},
+};
+
+/// Statistics about a single generated type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeStats {
+  /// Path of the type in the generated code (e.g. `note::Version`).
+  pub path: String,
+  /// The schema component the type was generated for.
+  pub component: XsdName,
+  /// Number of fields (or variants for enums).
+  pub fields: usize,
+  /// Number of fields of this type and of every generated type reachable from it.
+  pub transitive_size: usize,
+  /// Number of other generated types referencing this one.
+  pub referenced_by: usize,
+  /// What the element of the type holds.
+  pub content: ContentKind,
+}
+
+/// A problem found while generating that did not prevent the generation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Warning {
+  /// Location of the problem in the schema, e.g. `/schema/complexType[@name="note"]`.
+  pub path: String,
+  pub msg: String,
+}
+
+/// Summary of a generation run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GenerationReport {
+  pub types: Vec<TypeStats>,
+  pub warnings: Vec<Warning>,
+  /// Paths of the simple types emitted as an alias of their base instead of a wrapper struct.
+  pub collapsed_wrappers: Vec<String>,
+  /// Types named after a reserved name, e.g. `Date`, with the path they were emitted at instead.
+  pub reserved_renames: Vec<(String, String)>,
+  /// Paths of the large choices whose element alternatives are dispatched on the name of the
+  /// next child instead of being checked in turn.
+  pub dispatch_tables: Vec<String>,
+  /// The references to builtin types read into a Rust type that can't hold all their values,
+  /// see `GeneratorOptions::builtin_types`.
+  pub lossy_mappings: Vec<LossyMapping>,
+}
+
+/// The kind of a generated Rust item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemKind {
+  Struct,
+  Enum,
+  TypeAlias,
+}
+
+impl ItemKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ItemKind::Struct => "struct",
+      ItemKind::Enum => "enum",
+      ItemKind::TypeAlias => "type",
+    }
+  }
+}
+
+/// A Rust item a generation run would emit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlannedItem {
+  /// Path of the item in the generated code (e.g. `note::Version`).
+  pub path: String,
+  pub kind: ItemKind,
+  /// The schema component the item is generated for.
+  pub origin: XsdName,
+  /// Number of fields (or variants for enums).
+  pub fields: usize,
+}
+
+/// What a generation run would emit, in the order it would emit it, without the code.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GenerationPlan {
+  pub items: Vec<PlannedItem>,
+  pub warnings: Vec<Warning>,
+  /// See `GenerationReport::dispatch_tables`.
+  pub dispatch_tables: Vec<String>,
+}
+
+impl GenerationReport {
+  pub fn type_stats(&self, path: &str) -> Option<&TypeStats> {
+    self.types.iter().find(|stats| stats.path == path)
+  }
+
+  /// Generated types that no other generated type references.
+  pub fn unreferenced(&self) -> impl Iterator<Item = &TypeStats> {
+    self.types.iter().filter(|stats| stats.referenced_by == 0)
+  }
+
+  /// Add the report of another schema generated in the same module. The types both schemas
+  /// generate, like the ones of a common import, are only counted once.
+  pub(crate) fn merge(&mut self, other: GenerationReport) {
+    for stats in other.types {
+      if self.type_stats(&stats.path).is_none() {
+        self.types.push(stats);
+      }
+    }
+    for warning in other.warnings {
+      if !self.warnings.contains(&warning) {
+        self.warnings.push(warning);
+      }
+    }
+    for path in other.collapsed_wrappers {
+      if !self.collapsed_wrappers.contains(&path) {
+        self.collapsed_wrappers.push(path);
+      }
+    }
+    for rename in other.reserved_renames {
+      if !self.reserved_renames.contains(&rename) {
+        self.reserved_renames.push(rename);
+      }
+    }
+    for path in other.dispatch_tables {
+      if !self.dispatch_tables.contains(&path) {
+        self.dispatch_tables.push(path);
+      }
+    }
+    for used in other.lossy_mappings {
+      if !self.lossy_mappings.contains(&used) {
+        self.lossy_mappings.push(used);
+      }
+    }
+  }
+}
+
+fn field_count(imp: &XsdImpl) -> usize {
+  let count = |fields: &Fields| fields.fields().len();
+
+  match &imp.element {
+    XsdImplType::Struct(r#struct) => count(&r#struct.fields),
+    XsdImplType::Enum(r#enum) => r#enum.variants.len(),
+    XsdImplType::Type(_) | XsdImplType::TypeAlias(_) => 0,
+  }
+}
+
+/// The items emitted for the given components.
+pub fn planned_items<'a>(
+  impls: impl IntoIterator<Item = (&'a XsdName, &'a XsdImpl)>,
+) -> Vec<PlannedItem> {
+  let mut items = vec![];
+  for (_, imp) in impls {
+    imp.visit_types(&mut |path, imp| {
+      let kind = match imp.element {
+        XsdImplType::Struct(_) => ItemKind::Struct,
+        XsdImplType::Enum(_) => ItemKind::Enum,
+        XsdImplType::TypeAlias(_) => ItemKind::TypeAlias,
+        XsdImplType::Type(_) => return,
+      };
+      items.push(PlannedItem {
+        path,
+        kind,
+        origin: imp.name.clone(),
+        fields: field_count(imp),
+      });
+    });
+  }
+  items
+}
+
+/// Paths of the simple types emitted as a type alias.
+pub fn collapsed_wrappers<'a>(
+  impls: impl IntoIterator<Item = (&'a XsdName, &'a XsdImpl)>,
+) -> Vec<String> {
+  let mut paths = vec![];
+  for (_, imp) in impls {
+    imp.visit_types(&mut |path, imp| {
+      if imp.name.ty == XsdType::SimpleType && matches!(imp.element, XsdImplType::TypeAlias(_)) {
+        paths.push(path);
+      }
+    });
+  }
+  paths
+}
+
+/// Paths of the large choices, see `is_large_choice`.
+pub fn dispatch_tables<'a>(
+  impls: impl IntoIterator<Item = (&'a XsdName, &'a XsdImpl)>,
+) -> Vec<String> {
+  let mut paths = vec![];
+  for (_, imp) in impls {
+    imp.visit_types(&mut |path, imp| {
+      if matches!(&imp.element, XsdImplType::Enum(r#enum) if is_large_choice(r#enum)) {
+        paths.push(path);
+      }
+    });
+  }
+  paths
+}
+
+/// The reserved names of the schema components, with the path of the type emitted for them.
+pub fn reserved_renames<'a>(
+  impls: impl IntoIterator<Item = (&'a XsdName, &'a XsdImpl)>,
+) -> Vec<(String, String)> {
+  let mut renames = vec![];
+  for (_, imp) in impls {
+    imp.visit_types(&mut |path, imp| {
+      let name = to_struct_name(&imp.name.local_name);
+      if is_reserved(&name) {
+        renames.push((name, path));
+      }
+    });
+  }
+  renames
+}
+
+/// Compute per-type statistics for the generated implementations of the given components.
+pub fn type_statistics<'a>(
+  impls: impl IntoIterator<Item = (&'a XsdName, &'a XsdImpl)>,
+) -> Vec<TypeStats> {
+  struct Node<'a> {
+    component: &'a XsdName,
+    path: String,
+    imp: &'a XsdImpl,
+  }
+
+  let mut nodes = vec![];
+  for (component, imp) in impls {
+    imp.visit_types(&mut |path, imp| {
+      nodes.push(Node {
+        component,
+        path,
+        imp,
+      })
+    });
+  }
+
+  // Types are found by their component and their path in it. Names are relative to the module
+  // of the referencing type or to the root, and refer to an inner type of the same component or
+  // else to a top-level type.
+  let index: BTreeMap<(&XsdName, &str), usize> = nodes
+    .iter()
+    .enumerate()
+    .map(|(i, node)| ((node.component, node.path.as_str()), i))
+    .collect();
+  let top_level: BTreeMap<&str, &XsdName> = nodes
+    .iter()
+    .filter(|node| !node.path.contains("::"))
+    .map(|node| (node.path.as_str(), node.component))
+    .collect();
+  let resolve = |node: &Node<'a>, name: &str| {
+    let qualified = match node.path.rsplit_once("::") {
+      Some((module, _)) => format!("{module}::{name}"),
+      None => name.to_string(),
+    };
+    index
+      .get(&(node.component, qualified.as_str()))
+      .or_else(|| index.get(&(node.component, name)))
+      .or_else(|| index.get(&(*top_level.get(name)?, name)))
+      .copied()
+  };
+
+  let edges: Vec<BTreeSet<usize>> = nodes
+    .iter()
+    .enumerate()
+    .map(|(i, node)| {
+      let mut references = BTreeSet::new();
+      node.imp.direct_references(&mut references);
+      references
+        .iter()
+        .filter_map(|name| resolve(node, name))
+        .filter(|target| *target != i)
+        .collect()
+    })
+    .collect();
+
+  let mut referenced_by = vec![0; nodes.len()];
+  for targets in &edges {
+    for target in targets {
+      referenced_by[*target] += 1;
+    }
+  }
+
+  let fields: Vec<usize> = nodes.iter().map(|node| field_count(node.imp)).collect();
+
+  nodes
+    .iter()
+    .enumerate()
+    .map(|(i, node)| {
+      let mut seen = BTreeSet::from([i]);
+      let mut queue = vec![i];
+      while let Some(current) = queue.pop() {
+        for target in &edges[current] {
+          if seen.insert(*target) {
+            queue.push(*target);
+          }
+        }
+      }
+
+      TypeStats {
+        path: node.path.clone(),
+        component: node.imp.name.clone(),
+        fields: fields[i],
+        transitive_size: seen.iter().map(|v| fields[*v]).sum(),
+        referenced_by: referenced_by[i],
+        content: node.imp.content,
+      }
+    })
+    .collect()
+}
+
+/// Append the statistics to the doc comment of every generated type of `imp`.
+pub fn annotate(imp: &mut XsdImpl, module: &str, stats: &BTreeMap<String, &TypeStats>) {
+  let type_name = imp.element.get_type().name;
+
+  let type_stats = match imp.element {
+    XsdImplType::Type(_) => None,
+    _ => stats.get(&format!("{module}{type_name}")),
+  };
+
+  if let Some(stats) = type_stats {
+    imp.element.append_doc(&format!(
+      "Fields: {}, transitive size: {}, referenced by: {}.\nContent: {}.",
+      stats.fields,
+      stats.transitive_size,
+      stats.referenced_by,
+      stats.content.as_str()
+    ));
+  }
+
+  if !imp.inner.is_empty() {
+    let inner_module = format!("{module}{}::", to_module_name(&type_name));
+    for inner in &mut imp.inner {
+      annotate(inner, &inner_module, stats);
+    }
+  }
+}
+
+#[test]
+fn generation_report_counts_fields_and_references() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:simpleType name="color">
+        <xs:restriction base="xs:string">
+          <xs:enumeration value="red"/>
+          <xs:enumeration value="green"/>
+        </xs:restriction>
+      </xs:simpleType>
+      <xs:complexType name="note">
+        <xs:sequence>
+          <xs:element name="pitch" type="xs:string"/>
+          <xs:element name="tie" type="color"/>
+        </xs:sequence>
+        <xs:attribute name="version" type="xs:string" fixed="3.1" />
+      </xs:complexType>
+      <xs:complexType name="chord">
+        <xs:sequence>
+          <xs:element name="note" type="note" maxOccurs="unbounded"/>
+        </xs:sequence>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let (output, report) = xsd
+    .generate_with_report(&crate::GeneratorOptions {
+      fixed_values: crate::FixedValues::Marker,
+      stats_in_docs: true,
+      ..Default::default()
+    })
+    .unwrap();
+
+  let stats = |path| {
+    let stats = report.type_stats(path).unwrap();
+    (stats.fields, stats.transitive_size, stats.referenced_by)
+  };
+
+  assert_eq!(stats("Color"), (2, 2, 1));
+  assert_eq!(stats("note::Version"), (0, 0, 1));
+  assert_eq!(stats("Note"), (3, 5, 1));
+  assert_eq!(stats("Chord"), (1, 6, 0));
+
+  let unreferenced: Vec<_> = report.unreferenced().map(|stats| &stats.path).collect();
+  assert_eq!(unreferenced, ["Chord"]);
+
+  assert!(output.contains("/// Fields: 3, transitive size: 5, referenced by: 1."));
+}
+
+#[test]
+fn generation_report_records_content_kinds() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:simpleType name="color">
+        <xs:restriction base="xs:string">
+          <xs:enumeration value="red"/>
+        </xs:restriction>
+      </xs:simpleType>
+      <xs:complexType name="rest"/>
+      <xs:complexType name="tie">
+        <xs:attribute name="type" type="xs:string"/>
+      </xs:complexType>
+      <xs:complexType name="note">
+        <xs:sequence>
+          <xs:element name="pitch" type="xs:string"/>
+        </xs:sequence>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let (output, report) = xsd
+    .generate_with_report(&crate::GeneratorOptions {
+      stats_in_docs: true,
+      ..Default::default()
+    })
+    .unwrap();
+
+  let content = |path| report.type_stats(path).unwrap().content;
+  assert_eq!(content("Color"), crate::ContentKind::Simple);
+  assert_eq!(content("Rest"), crate::ContentKind::Empty);
+  assert_eq!(content("Tie"), crate::ContentKind::Attributes);
+  assert_eq!(content("Note"), crate::ContentKind::Complex);
+
+  assert!(output.contains("/// Content: attributes."));
+}
+
+#[test]
+fn generation_report_records_reserved_renames() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:simpleType name="option">
+        <xs:restriction base="xs:string">
+          <xs:enumeration value="on"/>
+        </xs:restriction>
+      </xs:simpleType>
+      <xs:complexType name="date">
+        <xs:sequence>
+          <xs:element name="day" type="xs:date"/>
+          <xs:element name="setting" type="option"/>
+        </xs:sequence>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let (output, report) = xsd
+    .generate_with_report(&crate::GeneratorOptions::default())
+    .unwrap();
+
+  assert!(output.contains("pub struct DateType {"));
+  assert!(output.contains("pub day: Date,"));
+  assert!(output.contains("pub setting: OptionType,"));
+  assert_eq!(
+    report.reserved_renames,
+    [
+      ("Date".to_string(), "DateType".to_string()),
+      ("Option".to_string(), "OptionType".to_string())
+    ]
+  );
+}
+
+#[test]
+fn plan_lists_the_generated_items() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:simpleType name="color">
+        <xs:restriction base="xs:string">
+          <xs:enumeration value="red"/>
+          <xs:enumeration value="green"/>
+        </xs:restriction>
+      </xs:simpleType>
+      <xs:simpleType name="label">
+        <xs:restriction base="xs:string"/>
+      </xs:simpleType>
+      <xs:complexType name="note">
+        <xs:sequence>
+          <xs:element name="pitch" type="xs:string"/>
+          <xs:element name="tie" type="color"/>
+        </xs:sequence>
+        <xs:attribute name="version" type="xs:string" fixed="3.1" />
+      </xs:complexType>
+      <xs:complexType name="rest"/>
+      <xs:element name="chord">
+        <xs:complexType>
+          <xs:sequence>
+            <xs:element name="note" type="note" maxOccurs="unbounded"/>
+            <xs:element name="rest" type="rest" minOccurs="0"/>
+          </xs:sequence>
+        </xs:complexType>
+      </xs:element>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let options = crate::GeneratorOptions {
+    fixed_values: crate::FixedValues::Marker,
+    flatten_wrappers: true,
+    stubs: [(
+      XsdName::new("rest", XsdType::ComplexType),
+      crate::StubKind::Skip,
+    )]
+    .into(),
+    ..Default::default()
+  };
+  let plan = xsd.plan(&options).unwrap();
+
+  let items: Vec<_> = plan
+    .items
+    .iter()
+    .map(|item| (item.path.as_str(), item.kind, item.origin.ty, item.fields))
+    .collect();
+  assert_eq!(
+    items,
+    [
+      ("Chord", ItemKind::Struct, XsdType::Element, 1),
+      ("Color", ItemKind::Enum, XsdType::SimpleType, 2),
+      ("Label", ItemKind::TypeAlias, XsdType::SimpleType, 0),
+      ("Note", ItemKind::Struct, XsdType::ComplexType, 3),
+      ("note::Version", ItemKind::Struct, XsdType::Attribute, 0),
+    ]
+  );
+  assert_eq!(plan.warnings.len(), 1);
+
+  let (output, report) = xsd.generate_with_report(&options).unwrap();
+  assert_eq!(plan.warnings, report.warnings);
+  for item in &plan.items {
+    let name = item.path.rsplit("::").next().unwrap();
+    assert!(
+      output.contains(&format!("pub {} {name}", item.kind.as_str())),
+      "{name} in {output}"
+    );
+  }
+}
@@ -1,22 +1,28 @@
-use xsd_codegen::{fromxml_impl, Block, Enum, FromXmlString, Struct, Variant, XMLElement};
-use xsd_types::{to_struct_name, XsdIoError, XsdName, XsdParseError, XsdType};
+use xsd_codegen::{
+  escape_str_literal, fromxml_impl, xsdgen_impl, Block, ContentKind, Decimal, Enum, Fields,
+  FromXmlString, Impl, Pattern, Struct, Type, TypeAlias, Variant, XMLElement,
+};
+use xsd_types::{to_variant_name, SchemaError, XsdName, XsdParseError, XsdType};
 
 use super::{
   annotation::Annotation,
-  attribute::Attribute,
+  attribute::{is_read_from_text, Attribute},
   attribute_group::AttributeGroup,
   choice::Choice,
+  fixed_value::marker_impl,
   general_xsdgen,
   group::Group,
   sequence::Sequence,
-  xsd_context::{MergeSettings, XsdImpl, XsdImplType},
-  XsdError,
+  serde_attributes::rename_attribute,
+  xsd_context::{MergeSettings, SearchResult, XsdImpl, XsdImplType},
+  FixedValues, XsdError,
 };
 use crate::xsd::XsdContext;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub enum Whitespace {
   // No normalization is done, the value is not changed (this is the behavior required by [XML 1.0 (Second Edition)] for element content)
+  #[default]
   Preserve,
   // All occurrences of #x9 (tab), #xA (line feed) and #xD (carriage return) are replaced with #x20 (space)
   Replace,
@@ -24,12 +30,6 @@ pub enum Whitespace {
   Collapse,
 }
 
-impl Default for Whitespace {
-  fn default() -> Self {
-    Self::Preserve
-  }
-}
-
 impl FromXmlString for Whitespace {
   fn from_xml(s: &str) -> Result<Self, String> {
     match s {
@@ -43,15 +43,14 @@ impl FromXmlString for Whitespace {
   }
 }
 
-// TODO(drosen): Actually implement these checks on the input
-
+/// The facets are checked when reading the values of the simple types, see `facet_checks`.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Restriction {
   pub base: XsdName,
-  pub min_inclusive: Option<i64>,
-  pub max_inclusive: Option<i64>,
-  pub min_exclusive: Option<i64>,
-  pub max_exclusive: Option<i64>,
+  pub min_inclusive: Option<String>,
+  pub max_inclusive: Option<String>,
+  pub min_exclusive: Option<String>,
+  pub max_exclusive: Option<String>,
   pub total_digits: Option<i64>,
   pub fraction_digits: Option<i64>,
 
@@ -80,11 +79,117 @@ pub enum RestrictionParentType {
   SimpleContent,
 }
 
+/// A condition failing the value read, along with the `String` expression of its message.
+struct FacetCheck {
+  condition: String,
+  message: String,
+}
+
+/// The checks of the facets of a restriction, see `Restriction::facet_checks`.
+#[derive(Default)]
+struct FacetChecks {
+  /// The statements run before the checks, binding what they compare.
+  lines: Vec<String>,
+  checks: Vec<FacetCheck>,
+}
+
+/// The builtin value wrapped by the generated type `imp`, through the newtypes and aliases of
+/// the simple types it restricts, as its type and its expression from `access`, e.g. `value.0`.
+fn wrapped_value(imp: &XsdImpl, context: &XsdContext, access: String) -> (Type, String) {
+  let wrapped = match &imp.element {
+    XsdImplType::TypeAlias(alias) => Some((alias.value.clone(), access.clone())),
+    XsdImplType::Struct(r#struct) => match r#struct.fields.fields() {
+      [field] if field.name.is_none() => Some((field.ty.clone(), format!("{access}.0"))),
+      _ => None,
+    },
+    _ => None,
+  };
+  let Some((ty, access)) = wrapped else {
+    return (imp.element.get_type(), access);
+  };
+
+  let generated = context.structs.values().find(|imp| {
+    !matches!(imp.element, XsdImplType::Type(_)) && imp.element.get_type().name == ty.name
+  });
+  match generated {
+    Some(imp) => wrapped_value(imp, context, access),
+    None => (ty, access),
+  }
+}
+
+/// The range of the integer types, the bounds outside of it can't be compared with.
+fn integer_range(ty: &str) -> Option<(i128, i128)> {
+  Some(match ty {
+    "i8" => (i8::MIN.into(), i8::MAX.into()),
+    "i16" => (i16::MIN.into(), i16::MAX.into()),
+    "i32" => (i32::MIN.into(), i32::MAX.into()),
+    "i64" | "isize" => (i64::MIN.into(), i64::MAX.into()),
+    "u8" => (0, u8::MAX.into()),
+    "u16" => (0, u16::MAX.into()),
+    "u32" => (0, u32::MAX.into()),
+    "u64" | "usize" => (0, u64::MAX.into()),
+    _ => return None,
+  })
+}
+
+/// The condition failing the values of the builtin type `ty` at `access` when compared with
+/// `bound` by `operator`, or `None` when all the values of the type satisfy the bound.
+fn bound_condition(
+  ty: &str,
+  bound: &str,
+  operator: &str,
+  access: &str,
+) -> Result<Option<String>, String> {
+  if let Some((min, max)) = integer_range(ty) {
+    let bound = bound
+      .parse::<i128>()
+      .ok()
+      .or_else(|| {
+        bound
+          .parse::<f64>()
+          .ok()
+          .filter(|bound| bound.is_finite() && bound.fract() == 0.0)
+          .map(|bound| bound as i128)
+      })
+      .ok_or_else(|| format!("{bound} isn't an integer"))?;
+    if !(min..=max).contains(&bound) {
+      return Err(format!("{bound} is out of the range of {ty}"));
+    }
+    // Comparing with the extremes of the type is either always true or an equality.
+    return Ok(match operator {
+      "<" if bound == min => None,
+      ">" if bound == max => None,
+      "<=" if bound == min => Some(format!("{access} == {bound}")),
+      ">=" if bound == max => Some(format!("{access} == {bound}")),
+      _ => Some(format!("{access} {operator} {bound}")),
+    });
+  }
+
+  let literal = match ty {
+    "f32" => bound
+      .parse::<f32>()
+      .ok()
+      .filter(|bound| bound.is_finite())
+      .map(|bound| format!("{bound:?}")),
+    "f64" => bound
+      .parse::<f64>()
+      .ok()
+      .filter(|bound| bound.is_finite())
+      .map(|bound| format!("{bound:?}")),
+    "Decimal" => Decimal::from_xml(bound)
+      .ok()
+      .map(|bound| format!("Decimal::new({}, {})", bound.digits(), bound.scale())),
+    _ => return Err(format!("the values of {ty} aren't compared")),
+  };
+  let literal = literal.ok_or_else(|| format!("{bound} isn't a finite number"))?;
+  Ok(Some(format!("{access} {operator} {literal}")))
+}
+
 impl Restriction {
   pub fn parse(
     parent_type: RestrictionParentType,
     mut element: XMLElement,
-  ) -> Result<Self, XsdIoError> {
+  ) -> Result<Self, SchemaError> {
     element.check_name("restriction")?;
 
     let annotation = element.try_get_child_with("annotation", Annotation::parse)?;
@@ -127,7 +232,7 @@ impl Restriction {
 
     let base: String = element.get_attribute("base")?;
     let output = Self {
-      base: element.new_name(&base, XsdType::SimpleType),
+      base: element.new_qname(&base, XsdType::SimpleType),
       annotation,
       min_inclusive: element
         .try_get_child_with("minInclusive", |mut child| child.get_attribute("value"))?,
@@ -165,23 +270,319 @@ impl Restriction {
     Ok(output)
   }
 
+  /// Whether the restriction only renames its base: no facet, enumeration or attribute.
+  pub fn is_plain(&self) -> bool {
+    self.min_inclusive.is_none()
+      && self.max_inclusive.is_none()
+      && self.min_exclusive.is_none()
+      && self.max_exclusive.is_none()
+      && self.total_digits.is_none()
+      && self.fraction_digits.is_none()
+      && self.enumerations.is_empty()
+      && self.pattern.is_none()
+      && self.whitespace.is_none()
+      && self.length.is_none()
+      && self.min_length.is_none()
+      && self.max_length.is_none()
+      && self.choice.is_none()
+      && self.group.is_none()
+      && self.sequence.is_none()
+      && self.attributes.is_empty()
+      && self.attribute_groups.is_empty()
+  }
+
+  /// The checks of the facets on the value read from `value`, whose builtin value is `wrapped`,
+  /// see `wrapped_value`. The lexical facets, `pattern`, `totalDigits` and `fractionDigits`, are
+  /// checked on the text of the values read from it, bound to `string`, the patterns also on the
+  /// strings read through other types. The patterns are only checked with
+  /// `GeneratorOptions::pattern_checks`. The facets that can't be checked are warned about.
+  fn facet_checks(
+    &self,
+    name: &XsdName,
+    (ty, access): &(Type, String),
+    lexical: bool,
+    context: &XsdContext,
+  ) -> FacetChecks {
+    let unchecked = |facet: &str, reason: &str| {
+      tracing::warn!(
+        "The {facet} facet of {} isn't checked, {reason}.",
+        name.local_name
+      );
+    };
+    let message = |failure: String, args: &str| {
+      let failure = format!("{failure} of {}.", name.local_name);
+      format!("format!({}, {args})", escape_str_literal(&failure))
+    };
+    let mut output = FacetChecks::default();
+
+    let string = context.string_storage.to_type().to_string();
+    let is_string = matches!(ty.to_string(), ty if ty == "String" || ty == string);
+
+    if let Some(pattern) = self.pattern.as_ref().filter(|_| context.pattern_checks) {
+      match Pattern::compile(pattern) {
+        Err(e) => unchecked("pattern", &e),
+        _ if !lexical && !is_string => unchecked("pattern", "its base isn't read from its text"),
+        Ok(_) => {
+          output.lines.push(format!(
+            "static PATTERN: xml_schema_parser::Pattern = xml_schema_parser::Pattern::new({});",
+            escape_str_literal(pattern)
+          ));
+          if lexical {
+            output.lines.push("PATTERN.check(string)?;".to_string());
+          } else {
+            output.checks.push(FacetCheck {
+              condition: format!("let Err(error) = PATTERN.check(&{access})"),
+              message: "error".to_string(),
+            });
+          }
+        }
+      }
+    }
+
+    let digits = [
+      ("totalDigits", self.total_digits, "total_digits", "digits"),
+      (
+        "fractionDigits",
+        self.fraction_digits,
+        "fraction_digits",
+        "fraction digits",
+      ),
+    ];
+    // The digits of a `Decimal` are counted on the value, of the other types on their text.
+    let is_decimal = ty.name == "Decimal";
+    let mut bindings = vec![];
+    for (facet, digits, binding, kind) in digits {
+      let Some(digits) = digits else {
+        bindings.push("_");
+        continue;
+      };
+      match usize::try_from(digits) {
+        Err(_) => unchecked(facet, &format!("{digits} isn't a number of digits")),
+        Ok(digits) if is_decimal => output.checks.push(FacetCheck {
+          condition: format!("{access}.{binding}() > {digits}"),
+          message: message(
+            format!("{{}} has more {kind} than the {facet} {digits}"),
+            access,
+          ),
+        }),
+        _ if !lexical => unchecked(facet, "its base isn't read from its text"),
+        Ok(digits) => {
+          bindings.push(binding);
+          output.checks.push(FacetCheck {
+            condition: format!("{binding} > {digits}"),
+            message: message(
+              format!("{{}} has more {kind} than the {facet} {digits}"),
+              "string",
+            ),
+          });
+        }
+      }
+    }
+    if bindings.iter().any(|binding| *binding != "_") {
+      output.lines.push(format!(
+        "let ({}) = xml_schema_parser::decimal_digits(string);",
+        bindings.join(", ")
+      ));
+    }
+
+    let bounds = [
+      ("minInclusive", &self.min_inclusive, "<", "is less than"),
+      ("maxInclusive", &self.max_inclusive, ">", "is greater than"),
+      (
+        "minExclusive",
+        &self.min_exclusive,
+        "<=",
+        "is not greater than",
+      ),
+      (
+        "maxExclusive",
+        &self.max_exclusive,
+        ">=",
+        "is not less than",
+      ),
+    ];
+    for (facet, bound, operator, failure) in bounds {
+      let Some(bound) = bound else {
+        continue;
+      };
+      let bound = bound.trim();
+      match bound_condition(&ty.to_string(), bound, operator, access) {
+        Ok(Some(condition)) => output.checks.push(FacetCheck {
+          condition,
+          message: message(format!("{{}} {failure} the {facet} {bound}"), access),
+        }),
+        Ok(None) => {}
+        Err(reason) => unchecked(facet, &reason),
+      }
+    }
+
+    let length = match ty.name.as_str() {
+      "Vec" => Some("len()"),
+      // The binaries are measured in octets.
+      "xml_schema_parser::HexBinary" | "xml_schema_parser::Base64Binary" => Some("0.len()"),
+      _ if is_string => Some("chars().count()"),
+      _ => None,
+    };
+    let lengths = [
+      ("length", self.length, "!=", "is not"),
+      ("minLength", self.min_length, "<", "is less than"),
+      ("maxLength", self.max_length, ">", "is greater than"),
+    ];
+    let mut measured = false;
+    for (facet, expected, operator, failure) in lengths {
+      let Some(expected) = expected else {
+        continue;
+      };
+      match (length, usize::try_from(expected)) {
+        (None, _) => unchecked(facet, &format!("the length of {ty} isn't measured")),
+        (_, Err(_)) => unchecked(facet, &format!("{expected} isn't a length")),
+        // All the lengths are at least 0.
+        (Some(_), Ok(0)) if operator == "<" => {}
+        (Some(_), Ok(expected)) => {
+          measured = true;
+          output.checks.push(FacetCheck {
+            condition: format!("length {operator} {expected}"),
+            message: message(
+              format!("The length {{}} {failure} the {facet} {expected}"),
+              "length",
+            ),
+          });
+        }
+      }
+    }
+    if let (true, Some(length)) = (measured, length) {
+      output
+        .lines
+        .push(format!("let length = {access}.{length};"));
+    }
+
+    output
+  }
+
+  /// The impl reading the newtype `ty` of the restriction of the simple type `base`, checking
+  /// the facets of its value: `FromXmlString` when the base is read from its text, or else an
+  /// `XsdGen` impl reading the base. `None` when no facet is checked.
+  fn checked_implementation(
+    &self,
+    name: &XsdName,
+    ty: &Type,
+    base: &XsdImpl,
+    context: &XsdContext,
+  ) -> Option<Impl> {
+    // The unions aren't written back to their text, see `xml_writers::to_xml_string`.
+    let lexical = is_read_from_text(base)
+      && !matches!(&base.element, XsdImplType::Enum(r#enum)
+        if r#enum.variants.iter().any(|variant| variant.xml_value.is_none()));
+    let wrapped = wrapped_value(base, context, "value".to_string());
+    let FacetChecks { lines, checks } = self.facet_checks(name, &wrapped, lexical, context);
+    if lines.is_empty() && checks.is_empty() {
+      return None;
+    }
+
+    let mut base_type = base.element.get_type();
+    base_type.xml_name = None;
+    let (read, error) = if lexical {
+      (
+        format!("let value = <{base_type} as FromXmlString>::from_xml(string)?;"),
+        "{}".to_string(),
+      )
+    } else {
+      (
+        format!("let value = <{base_type} as XsdGen>::gen(element, gen_state, name)?;"),
+        "XsdGenError { ty: XsdType::Unknown, node_name: element.name().to_string(), msg: {} }.into()".to_string(),
+      )
+    };
+
+    let mut body = lines
+      .into_iter()
+      .fold(Block::new("").line(read), Block::line);
+    for check in checks {
+      body = body.push_block(Block::new(&format!("if {}", check.condition)).line(format!(
+        "return Err({});",
+        error.replace("{}", &check.message)
+      )));
+    }
+    let body = body.line("Ok(Self(value))");
+
+    Some(if lexical {
+      fromxml_impl(ty.clone(), body)
+    } else {
+      xsdgen_impl(ty.clone(), body, false, true)
+    })
+  }
+
+  /// A plain restriction of a simple type as an alias of its base, instead of a tuple struct
+  /// wrapping it.
+  pub fn get_alias_implementation(
+    &self,
+    parent_name: XsdName,
+    context: &XsdContext,
+  ) -> Result<XsdImpl, XsdError> {
+    let base_type = self.search_base(context)?;
+
+    let mut value = base_type.element.get_type();
+    value.xml_name = None;
+
+    Ok(XsdImpl {
+      name: parent_name.clone(),
+      fieldname_hint: Some(parent_name.to_field_name()),
+      element: XsdImplType::TypeAlias(TypeAlias {
+        doc: None,
+        vis: Some("pub".to_string()),
+        alias: Type::new(Some(parent_name.clone()), &parent_name.to_struct_name()),
+        value,
+      }),
+      inner: vec![],
+      implementation: vec![],
+      flatten: false,
+      content: base_type.content,
+      attribute_groups: vec![],
+    })
+  }
+
+  /// The base can be a simple type, or a complex type when restricting simple or complex content.
+  fn search_base<'a>(&self, context: &'a XsdContext) -> Result<&'a XsdImpl, XsdError> {
+    match context.multi_search(
+      self.base.namespace.clone(),
+      self.base.local_name.clone(),
+      &[XsdType::SimpleType, XsdType::ComplexType],
+    ) {
+      SearchResult::SingleMatch(imp) => Ok(imp),
+      SearchResult::MultipleMatches(candidates) => Err(XsdError::ContextSearchError {
+        name: self.base.clone(),
+        candidates,
+        msg: "found both a simple and complex type".to_string(),
+      }),
+      SearchResult::NoMatches => Err(XsdError::XsdImplNotFound(self.base.clone())),
+    }
+  }
+
   fn get_simple_implementation(
     &self,
     parent_name: XsdName,
-    context: &mut XsdContext,
+    context: &XsdContext,
     allow_attributes: bool,
   ) -> Result<XsdImpl, XsdError> {
-    let base_type = context.search(&self.base);
+    let base_type = self.search_base(context)?;
 
     let mut generate_xsdgen = true;
 
-    if base_type.is_none() {
-      return Err(XsdError::XsdImplNotFound(self.base.clone()));
-    }
+    let has_attributes =
+      allow_attributes && !(self.attributes.is_empty() && self.attribute_groups.is_empty());
 
-    let base_type = base_type.unwrap();
+    let single_value = self.enumerations.len() == 1
+      && self.attributes.is_empty()
+      && self.attribute_groups.is_empty()
+      && context.fixed_values != FixedValues::Owned;
 
-    let mut generated_impl = if !self.enumerations.is_empty() {
+    let mut generated_impl = if single_value {
+      generate_xsdgen = false;
+      marker_impl(
+        parent_name.clone(),
+        &parent_name.to_struct_name(),
+        &self.enumerations[0],
+      )
+    } else if !self.enumerations.is_empty() {
       let typename = parent_name.to_struct_name();
       let mut generated_enum = Enum::new(Some(parent_name.clone()), &typename)
         .vis("pub")
@@ -192,11 +593,19 @@ impl Restriction {
         let enum_name = if enumeration.is_empty() {
           "Empty".to_string()
         } else {
-          to_struct_name(enumeration)
+          to_variant_name(enumeration)
         };
-        generated_enum = generated_enum.push_variant(Variant::new(None, &enum_name));
+        let mut variant = Variant::new(None, &enum_name).xml_value(enumeration);
+        if context.serde {
+          variant = variant.attribute(&rename_attribute(enumeration));
+        }
+        generated_enum = generated_enum.push_variant(variant);
 
-        parse_match = parse_match.line(format!("\"{}\" => Ok(Self::{}),", enumeration, enum_name));
+        parse_match = parse_match.line(format!(
+          "{} => Ok(Self::{}),",
+          escape_str_literal(enumeration),
+          enum_name
+        ));
       }
       parse_match = parse_match
         .line("value => Err(format!(\"Invalid xml node found unexpected content {value}.\")),");
@@ -212,25 +621,55 @@ impl Restriction {
         inner: Vec::new(),
         implementation: vec![enum_impl],
         flatten: false,
+        content: ContentKind::Simple,
+        attribute_groups: vec![],
       }
-    } else {
-      let mut ty = base_type.element.get_type();
-      ty.xml_name = None;
-      XsdImpl {
+    } else if has_attributes {
+      // Keep the content next to the attributes, as extensions do.
+      let mut generated_impl = XsdImpl {
         name: parent_name.clone(),
         fieldname_hint: Some(parent_name.to_field_name()),
         element: XsdImplType::Struct(
           Struct::new(Some(parent_name.clone()), &parent_name.to_struct_name())
-            .tuple_field(Some("pub"), ty, false, false)
+            .vis("pub")
             .derives(&["Clone", "Debug", "PartialEq"]),
         ),
         inner: vec![],
         implementation: vec![],
         flatten: false,
+        content: ContentKind::Empty,
+        attribute_groups: vec![],
+      };
+
+      let mut base_impl = base_type.to_type();
+      base_impl.fieldname_hint = Some(parent_name.to_field_name());
+      base_impl.flatten = true;
+      generated_impl.merge(base_impl, MergeSettings::default());
+
+      generated_impl
+    } else {
+      let mut ty = base_type.element.get_type();
+      ty.xml_name = None;
+      let newtype = Struct::new(Some(parent_name.clone()), &parent_name.to_struct_name())
+        .tuple_field(Some("pub"), ty, false, false)
+        .derives(&["Clone", "Debug", "PartialEq"]);
+
+      let checked = self.checked_implementation(&parent_name, newtype.ty(), base_type, context);
+      generate_xsdgen = checked.is_none();
+
+      XsdImpl {
+        name: parent_name.clone(),
+        fieldname_hint: Some(parent_name.to_field_name()),
+        element: XsdImplType::Struct(newtype),
+        inner: vec![],
+        implementation: checked.into_iter().collect(),
+        flatten: false,
+        content: base_type.content,
+        attribute_groups: vec![],
       }
     };
 
-    if allow_attributes {
+    if has_attributes {
       for attribute in &self.attributes {
         generated_impl.merge(
           attribute.get_implementation(context, false)?,
@@ -240,14 +679,14 @@ impl Restriction {
 
       for group in &self.attribute_groups {
         generated_impl.merge(
-          group.get_implementation(Some(parent_name.clone()), context)?,
-          MergeSettings::default(),
+          group.get_implementation(None, context)?,
+          MergeSettings::ATTRIBUTE,
         );
       }
     }
 
     let generated_impl = if generate_xsdgen {
-      general_xsdgen(generated_impl)
+      general_xsdgen(generated_impl, context)
     } else {
       generated_impl
     };
@@ -255,43 +694,85 @@ impl Restriction {
     Ok(generated_impl)
   }
 
+  /// Complex content restrictions restate the content of their base, only the attributes that
+  /// are not restated are inherited.
   fn get_complex_implementation(
     &self,
     parent_name: XsdName,
-    context: &mut XsdContext,
+    context: &XsdContext,
   ) -> Result<XsdImpl, XsdError> {
-    let base_type = context.search(&self.base);
-
-    if base_type.is_none() {
-      return Err(XsdError::XsdImplNotFound(self.base.clone()));
-    }
-
-    let mut base_type = base_type.unwrap().clone();
-    base_type.name = parent_name.clone();
+    let base_type = self.search_base(context)?;
+
+    let mut generated_impl = XsdImpl {
+      name: parent_name.clone(),
+      fieldname_hint: Some(parent_name.to_field_name()),
+      element: XsdImplType::Struct(
+        Struct::new(Some(parent_name.clone()), &parent_name.to_struct_name())
+          .vis("pub")
+          .derives(&["Clone", "Debug", "PartialEq"]),
+      ),
+      inner: vec![],
+      implementation: vec![],
+      flatten: false,
+      content: ContentKind::Empty,
+      attribute_groups: vec![],
+    };
 
-    match (&self.group, &self.choice, &self.sequence) {
+    let content = match (&self.group, &self.sequence, &self.choice) {
       (Some(group), None, None) => {
-        base_type.merge(
-          group.get_implementation(Some(parent_name), context)?,
-          MergeSettings::default(),
-        );
+        Some(group.get_implementation(Some(parent_name.clone()), context)?)
       }
-      (None, Some(choice), None) => {
-        base_type.merge(
-          choice.get_implementation(Some(parent_name), context)?,
-          MergeSettings::default(),
-        );
+      (None, Some(sequence), None) => {
+        Some(sequence.get_implementation(Some(parent_name.clone()), context)?)
       }
-      (None, None, Some(sequence)) => {
-        base_type.merge(
-          sequence.get_implementation(Some(parent_name), context)?,
-          MergeSettings::default(),
-        );
+      (None, None, Some(choice)) => {
+        Some(choice.get_implementation(Some(parent_name.clone()), context)?)
       }
-      _ => unreachable!("Should have already validated the input schema."),
+      (None, None, None) => None,
+      // At most one of them is present, checked when parsing.
+      _ => unreachable!("Error parsing {}, Invalid XSD!", &parent_name.local_name),
+    };
+    if let Some(content) = content {
+      generated_impl.merge(content, MergeSettings::default());
+    }
+
+    let restated: Vec<_> = self
+      .attributes
+      .iter()
+      .filter_map(|attribute| attribute.name.as_ref().or(attribute.reference.as_ref()))
+      .map(|name| name.local_name.as_str())
+      .collect();
+    if let (XsdImplType::Struct(base), XsdImplType::Struct(r#struct)) =
+      (&base_type.element, &mut generated_impl.element)
+    {
+      if let Fields::Named(fields) = &base.fields {
+        for field in fields {
+          let is_restated = field
+            .xml_name
+            .as_ref()
+            .is_some_and(|name| restated.contains(&name.local_name.as_str()));
+          if field.attribute && !is_restated {
+            r#struct.push_field(field.clone());
+          }
+        }
+      }
+    }
+
+    for attribute in &self.attributes {
+      generated_impl.merge(
+        attribute.get_implementation(context, false)?,
+        MergeSettings::ATTRIBUTE,
+      );
     }
 
-    Ok(general_xsdgen(base_type))
+    for group in &self.attribute_groups {
+      generated_impl.merge(
+        group.get_implementation(None, context)?,
+        MergeSettings::ATTRIBUTE,
+      );
+    }
+
+    Ok(general_xsdgen(generated_impl, context))
   }
 
   #[tracing::instrument(skip_all)]
@@ -299,16 +780,18 @@ impl Restriction {
     &self,
     parent_name: XsdName,
     parent_type: RestrictionParentType,
-    context: &mut XsdContext,
+    context: &XsdContext,
   ) -> Result<XsdImpl, XsdError> {
     let mut gen = match parent_type {
       RestrictionParentType::SimpleType => {
         self.get_simple_implementation(parent_name, context, false)
       }
-      RestrictionParentType::ComplexContent => {
+      RestrictionParentType::SimpleContent => {
         self.get_simple_implementation(parent_name, context, true)
       }
-      RestrictionParentType::SimpleContent => self.get_complex_implementation(parent_name, context),
+      RestrictionParentType::ComplexContent => {
+        self.get_complex_implementation(parent_name, context)
+      }
     }?;
 
     gen.name.ty = XsdType::Restriction;
@@ -316,3 +799,182 @@ impl Restriction {
     Ok(gen)
   }
 }
+
+#[test]
+fn attribute_groups_of_simple_content_restrictions_are_attributes() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:attributeGroup name="position">
+        <xs:attribute name="x" type="xs:int" />
+        <xs:attribute name="y" type="xs:int" />
+      </xs:attributeGroup>
+      <xs:complexType name="text">
+        <xs:simpleContent>
+          <xs:extension base="xs:string">
+            <xs:attribute name="lang" type="xs:string" />
+          </xs:extension>
+        </xs:simpleContent>
+      </xs:complexType>
+      <xs:complexType name="words">
+        <xs:simpleContent>
+          <xs:restriction base="text">
+            <xs:attributeGroup ref="position" />
+          </xs:restriction>
+        </xs:simpleContent>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap();
+
+  assert!(output.contains("pub position: Position"));
+  assert!(output.contains("<Position as XsdGen>::gen(element, gen_state.to_attr(), None)"));
+}
+
+#[test]
+fn plain_restrictions_are_flattened_into_aliases() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:simpleType name="tenths">
+        <xs:restriction base="xs:decimal"/>
+      </xs:simpleType>
+      <xs:simpleType name="divisions">
+        <xs:restriction base="tenths"/>
+      </xs:simpleType>
+      <xs:simpleType name="octave">
+        <xs:restriction base="xs:integer">
+          <xs:maxInclusive value="9"/>
+        </xs:restriction>
+      </xs:simpleType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let (output, report) = xsd
+    .generate_with_report(&crate::GeneratorOptions::default())
+    .unwrap();
+  assert!(output.contains("pub struct Divisions(pub Tenths);"));
+  assert!(report.collapsed_wrappers.is_empty());
+
+  let (output, report) = xsd
+    .generate_with_report(&crate::GeneratorOptions {
+      flatten_wrappers: true,
+      ..Default::default()
+    })
+    .unwrap();
+  assert!(output.contains("pub type Divisions = Tenths;"));
+  assert!(output.contains("pub struct Octave(pub i32);"));
+  assert_eq!(report.collapsed_wrappers, ["Divisions", "Tenths"]);
+}
+
+#[test]
+fn facets_are_checked_on_the_values_read() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:simpleType name="staff">
+        <xs:restriction base="xs:nonNegativeInteger">
+          <xs:minInclusive value="0"/>
+          <xs:maxExclusive value="100"/>
+        </xs:restriction>
+      </xs:simpleType>
+      <xs:simpleType name="voice">
+        <xs:restriction base="xs:unsignedByte">
+          <xs:minExclusive value="0"/>
+          <xs:maxInclusive value="1.5"/>
+        </xs:restriction>
+      </xs:simpleType>
+      <xs:simpleType name="tenths">
+        <xs:restriction base="xs:decimal"/>
+      </xs:simpleType>
+      <xs:simpleType name="width">
+        <xs:restriction base="tenths">
+          <xs:maxInclusive value="1000.5"/>
+          <xs:fractionDigits value="2"/>
+          <xs:pattern value="\d+"/>
+        </xs:restriction>
+      </xs:simpleType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap();
+
+  // The bounds every value of the type satisfies aren't checked, the ones at its extremes are
+  // equalities.
+  assert!(output.contains("impl FromXmlString for Staff {"));
+  assert!(!output.contains("value < 0"));
+  assert!(output.contains("if value >= 100 {"));
+  assert!(output.contains("if value == 0 {"));
+  // A decimal bound of an integer isn't checked.
+  assert!(!output.contains("1.5"));
+
+  // The restrictions of the types not read from their text check the value they read, the
+  // digits of a decimal are counted on it.
+  assert!(output.contains("impl XsdGen for Width {"));
+  assert!(output.contains("if value.0 > Decimal::new(10005, 1) {"));
+  assert!(output.contains("if value.0.fraction_digits() > 2 {"));
+  assert!(!output.contains("PATTERN"));
+}
+
+#[test]
+fn patterns_are_checked_with_pattern_checks() {
+  let xsd = crate::Xsd::new(
+    r##"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:simpleType name="color">
+        <xs:restriction base="xs:token">
+          <xs:pattern value="#[\dA-F]{6}"/>
+        </xs:restriction>
+      </xs:simpleType>
+      <xs:simpleType name="glyph-name">
+        <xs:restriction base="xs:NMTOKEN"/>
+      </xs:simpleType>
+      <xs:simpleType name="coda-glyph-name">
+        <xs:restriction base="glyph-name">
+          <xs:pattern value="coda\c*"/>
+        </xs:restriction>
+      </xs:simpleType>
+      <xs:simpleType name="letter">
+        <xs:restriction base="xs:string">
+          <xs:pattern value="\p{IsBasicLatin}"/>
+        </xs:restriction>
+      </xs:simpleType>
+    </xs:schema>
+  "##,
+  )
+  .unwrap();
+
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap();
+  assert!(!output.contains("PATTERN"));
+  assert!(output.contains("pub struct Color(pub String);"));
+
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions {
+      pattern_checks: true,
+      ..Default::default()
+    })
+    .unwrap();
+  assert!(output.contains("impl FromXmlString for Color {"));
+  assert!(output.contains("PATTERN.check(string)?;"));
+
+  // The strings read through another type are checked once read.
+  assert!(output.contains("impl XsdGen for CodaGlyphName {"));
+  assert!(output.contains("if let Err(error) = PATTERN.check(&value.0) {"));
+  assert!(output.contains("msg: error }.into()"));
+
+  // The patterns `regex` can't express aren't checked.
+  assert!(!output.contains("IsBasicLatin"));
+}
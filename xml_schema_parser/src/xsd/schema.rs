@@ -1,16 +1,33 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
 
-use xsd_codegen::{Formatter, XMLElement};
-use xsd_types::{XsdIoError, XsdName, XsdType};
+use xsd_codegen::{escape_str_literal, Formatter, Module, Scope, XMLElement};
+use xsd_types::{generated_prelude, to_module_name, SchemaError, XsdName, XsdParseError, XsdType};
 
 use crate::xsd::{
-  attribute, attribute_group, complex_type, element, group, import, qualification, simple_type,
-  XsdContext,
+  attribute, attribute_group, complex_type, element, group, import,
+  max_occurences::{self, MaxOccursUse},
+  qualification, simple_type, XsdContext,
 };
 
-use super::{annotation, XsdError};
+use super::{
+  annotation, borrowed,
+  canonical::Canonical,
+  conversion, derived_types, doc_links, drift, dynamic_values,
+  enumeration::{self, EnumerationUse},
+  field_overrides, inherited_lang,
+  key_resolvers::{self, DocumentKeys},
+  lossy_mappings::{self, LossyMapping},
+  progress, report, serde_attributes, stub, substitution_group,
+  substitutions::Substitutions,
+  unknown_content, version, visibility, visitor, xml_writers,
+  xsd_context::{XsdImpl, XsdImplType, DEFAULT_INNER_MODULE_LINTS},
+  GenerationPlan, GenerationReport, GeneratorOptions, StubKind, VersionUse, Visibility, Warning,
+  XsdError,
+};
 
 #[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 pub enum SchemaOptions {
   Import(import::Import),
   Annotation(annotation::Annotation),
@@ -22,6 +39,52 @@ pub enum SchemaOptions {
   Group(group::Group),
 }
 
+impl SchemaOptions {
+  /// Name of the top-level component, `None` for the imports and annotations.
+  pub fn name(&self) -> Option<&XsdName> {
+    match self {
+      SchemaOptions::Import(_) | SchemaOptions::Annotation(_) => None,
+      SchemaOptions::Element(ty) => ty.name.as_ref(),
+      SchemaOptions::SimpleType(ty) => ty.name.as_ref(),
+      SchemaOptions::ComplexType(ty) => ty.name.as_ref(),
+      SchemaOptions::Attribute(ty) => ty.name.as_ref(),
+      SchemaOptions::AttributeGroup(ty) => ty.name.as_ref(),
+      SchemaOptions::Group(ty) => ty.name.as_ref(),
+    }
+  }
+
+  /// Hash of the definition of the component, as 8 hex digits.
+  ///
+  /// The canonical encoding of the parsed component is hashed rather than its text, so the hash
+  /// doesn't depend on the formatting of the document nor on where the component is, and it is
+  /// computed before the names of the generated types are decided. The encoding lists every
+  /// field in a fixed order, see `canonical`, the hash is stable across runs and versions of
+  /// Rust.
+  pub fn content_hash(&self) -> String {
+    let mut encoding = vec![];
+    self.encode(&mut encoding);
+    // 32-bit FNV-1a.
+    let hash = encoding.iter().fold(0x811c9dc5_u32, |hash, byte| {
+      (hash ^ u32::from(*byte)).wrapping_mul(0x01000193)
+    });
+    format!("{hash:08x}")
+  }
+}
+
+/// Apply `f` to every item, on the rayon thread pool with the `parallel` feature.
+#[cfg(feature = "parallel")]
+fn par_map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync + Send) -> Vec<R> {
+  use rayon::prelude::*;
+
+  items.par_iter().map(f).collect()
+}
+
+/// Apply `f` to every item, on the rayon thread pool with the `parallel` feature.
+#[cfg(not(feature = "parallel"))]
+fn par_map<T, R>(items: &[T], f: impl Fn(&T) -> R) -> Vec<R> {
+  items.iter().map(f).collect()
+}
+
 #[derive(Clone, Default, Debug, PartialEq)]
 pub struct Schema {
   pub target_namespace: Option<String>,
@@ -29,19 +92,33 @@ pub struct Schema {
   pub attribute_form_default: qualification::Qualification,
   pub children: Vec<SchemaOptions>,
   pub extra: Vec<(String, String)>,
+  /// Constructs of the document requiring a version of XSD newer than 1.0.
+  pub versions: Vec<VersionUse>,
+  /// Restrictions of the document enumerating values of their base.
+  pub enumerations: Vec<EnumerationUse>,
+  /// The numeric `maxOccurs` of the document.
+  pub max_occurs: Vec<MaxOccursUse>,
+  /// References of the document to builtin types with a lossy mapping.
+  pub lossy_mappings: Vec<LossyMapping>,
 }
 
 impl Schema {
-  pub fn parse(mut element: XMLElement) -> Result<Self, XsdIoError> {
+  pub fn parse(mut element: XMLElement) -> Result<Self, SchemaError> {
     element.check_name("schema")?;
 
+    let versions = version::scan(element.element());
+    let max_occurs = max_occurences::scan(element.element());
+    let lossy_mappings = lossy_mappings::scan(element.element());
+
     let target_namespace: Option<String> = element.try_get_attribute("targetNamespace")?;
 
-    element.default_namespace = target_namespace.clone();
+    element.set_default_namespace(target_namespace.clone());
+
+    let enumerations = enumeration::scan(element.element(), target_namespace.as_deref());
 
     let mut children = vec![];
     for child in element.get_all_children() {
-      children.push(match child.element.name.as_str() {
+      children.push(match child.name() {
         "annotation" => SchemaOptions::Annotation(annotation::Annotation::parse(child)?),
         "import" => SchemaOptions::Import(import::Import::parse(child)?),
         "element" => SchemaOptions::Element(element::Element::parse(child, true)?),
@@ -52,16 +129,43 @@ impl Schema {
           SchemaOptions::AttributeGroup(attribute_group::AttributeGroup::parse(child)?)
         }
         "group" => SchemaOptions::Group(group::Group::parse(child)?),
-        name => unreachable!("Unexpected child name {name}"),
+        name => {
+          return Err(SchemaError::Invalid(XsdParseError {
+            node_name: element.node_name(),
+            msg: format!("Unexpected child {name}"),
+          }))
+        }
       });
     }
 
+    for child in &children {
+      let unnamed = match child {
+        SchemaOptions::Import(_) | SchemaOptions::Annotation(_) => None,
+        SchemaOptions::Element(ty) => ty.name.is_none().then_some("element"),
+        SchemaOptions::SimpleType(ty) => ty.name.is_none().then_some("simpleType"),
+        SchemaOptions::ComplexType(ty) => ty.name.is_none().then_some("complexType"),
+        SchemaOptions::Attribute(ty) => ty.name.is_none().then_some("attribute"),
+        SchemaOptions::AttributeGroup(ty) => ty.name.is_none().then_some("attributeGroup"),
+        SchemaOptions::Group(ty) => ty.name.is_none().then_some("group"),
+      };
+      if let Some(node_name) = unnamed {
+        return Err(SchemaError::Invalid(XsdParseError {
+          node_name: node_name.to_string(),
+          msg: "The name attribute is required if the parent node is a schema.".to_string(),
+        }));
+      }
+    }
+
     let output = Self {
       target_namespace,
       element_form_default: element.get_attribute_default("elementFormDefault")?,
       attribute_form_default: element.get_attribute_default("attributeFormDefault")?,
       children,
       extra: element.get_remaining_attributes(),
+      versions,
+      enumerations,
+      max_occurs,
+      lossy_mappings,
     };
 
     element.finalize(false, false)?;
@@ -69,14 +173,133 @@ impl Schema {
     Ok(output)
   }
 
+  pub fn top_level_names(&self) -> Vec<XsdName> {
+    self
+      .children
+      .iter()
+      .filter_map(|child| match child {
+        SchemaOptions::Import(_) | SchemaOptions::Annotation(_) => None,
+        SchemaOptions::Element(ty) => ty.name.clone(),
+        SchemaOptions::SimpleType(ty) => ty.name.clone(),
+        SchemaOptions::ComplexType(ty) => ty.name.clone(),
+        SchemaOptions::Attribute(ty) => ty.name.clone(),
+        SchemaOptions::AttributeGroup(ty) => ty.name.clone(),
+        SchemaOptions::Group(ty) => ty.name.clone(),
+      })
+      .collect()
+  }
+
+  /// The substitution groups of the top-level elements of the schema.
+  pub fn substitution_groups(&self) -> Substitutions {
+    let mut groups = Substitutions::default();
+    for child in &self.children {
+      let SchemaOptions::Element(element) = child else {
+        continue;
+      };
+      let Some(name) = &element.name else {
+        continue;
+      };
+      if let Some(head) = &element.substitution_group {
+        groups.add_substitute(head.clone(), name.clone());
+      }
+      if element.r#abstract {
+        groups.add_abstract(name.clone());
+      }
+    }
+    groups
+  }
+
+  /// The top-level complex types of the schema derived from another complex type, by extension
+  /// or restriction of its content. Only the bases declared as complex types by the schema, and
+  /// not as simple types as well, are read from `xsi:type`.
+  pub fn derived_types(&self) -> Substitutions {
+    let declared = |base: &XsdName, ty: XsdType| {
+      self.children.iter().any(|child| {
+        child.name().is_some_and(|name| {
+          name.ty == ty && name.local_name == base.local_name && name.namespace == base.namespace
+        })
+      })
+    };
+
+    let mut derived_types = Substitutions::default();
+    for child in &self.children {
+      let SchemaOptions::ComplexType(complex_type) = child else {
+        continue;
+      };
+      let Some(name) = &complex_type.name else {
+        continue;
+      };
+      let base = complex_type.complex_content.as_ref().and_then(|content| {
+        match (&content.extension, &content.restriction) {
+          (Some(extension), _) => Some(&extension.base),
+          (None, Some(restriction)) => Some(&restriction.base),
+          (None, None) => None,
+        }
+      });
+      let base = base.filter(|base| {
+        declared(base, XsdType::ComplexType) && !declared(base, XsdType::SimpleType)
+      });
+      if let Some(base) = base {
+        let base = XsdName {
+          ty: XsdType::ComplexType,
+          ..base.clone()
+        };
+        derived_types.add_substitute(base, name.clone());
+      }
+      if complex_type.r#abstract {
+        derived_types.add_abstract(name.clone());
+      }
+    }
+    derived_types
+  }
+
+  /// The enums generated for the substitutions the schema declares: one per substitution group
+  /// of its elements, then one per complex type its types derive from. Their indexes follow the
+  /// children of the schema, see `generate_component`.
+  fn substitution_enums(&self) -> Vec<XsdName> {
+    let groups = self.substitution_groups();
+    let derived_types = self.derived_types();
+    groups
+      .substituted()
+      .map(substitution_group::group_name)
+      .chain(derived_types.substituted().map(derived_types::enum_name))
+      .collect()
+  }
+
+  /// The `import` elements of the schema, in document order.
+  pub fn imports(&self) -> impl Iterator<Item = &import::Import> {
+    self.children.iter().filter_map(|child| match child {
+      SchemaOptions::Import(import) => Some(import),
+      _ => None,
+    })
+  }
+
   pub fn fill_context(
     &self,
     context: &mut XsdContext,
     namespace_filter: Option<&str>,
+  ) -> Result<Vec<XsdName>, XsdError> {
+    self.fill_context_with(context, namespace_filter, cfg!(feature = "parallel"))
+  }
+
+  /// Fill the context, generating the components of each pass up front when `speculate` is set.
+  fn fill_context_with(
+    &self,
+    context: &mut XsdContext,
+    namespace_filter: Option<&str>,
+    speculate: bool,
   ) -> Result<Vec<XsdName>, XsdError> {
     // let namespace_definition = generate_namespace_definition(target_prefix, &self.target_namespace);
 
-    context.xml_schema_prefix = self.target_namespace.clone();
+    context.target_namespace = self.target_namespace.clone();
+    context.versions.extend(self.versions.iter().cloned());
+    context
+      .lossy_mappings
+      .extend(self.lossy_mappings.iter().cloned());
+    context.unbounded_max_occurs.extend(max_occurences::check(
+      &self.max_occurs,
+      context.max_occurs_limit,
+    ));
 
     let mut top_level_names = vec![];
 
@@ -84,20 +307,7 @@ impl Schema {
 
     for (index, child) in self.children.iter().enumerate() {
       match child {
-        SchemaOptions::Import(ty) => {
-          to_run.insert(
-            XsdName {
-              namespace: None,
-              local_name: ty
-                .schema_location
-                .as_ref()
-                .unwrap_or_else(|| ty.namespace.as_ref().unwrap())
-                .clone(),
-              ty: XsdType::Import,
-            },
-            (Some(index), 0),
-          );
-        }
+        SchemaOptions::Import(_) => {}
         SchemaOptions::Annotation(_) => {
           to_run.insert(
             XsdName {
@@ -129,43 +339,133 @@ impl Schema {
       }
     }
 
+    // The enum of a substitution group is generated with the schema declaring its members, the
+    // enum of the types derived from a type with the schema declaring them.
+    for (index, name) in self.substitution_enums().into_iter().enumerate() {
+      to_run.insert(name, (Some(self.children.len() + index), 0));
+    }
+    context
+      .substitution_groups
+      .extend(self.substitution_groups());
+    context.derived_types.extend(self.derived_types());
+
+    context.content_hashes.extend(
+      self
+        .children
+        .iter()
+        .filter_map(|child| Some((child.name()?.clone(), child.content_hash()))),
+    );
+
+    context.type_names.extend(
+      to_run
+        .keys()
+        .filter(|name| matches!(name.ty, XsdType::ComplexType | XsdType::SimpleType))
+        .map(|name| name.to_struct_name()),
+    );
+
+    // Stubbed components are not generated from their definition, the stub is in the context
+    // before anything refers to it.
+    let stubbed = to_run
+      .keys()
+      .filter_map(|name| Some((name.clone(), context.stub(name)?)))
+      .collect::<Vec<_>>();
+    for (name, kind) in stubbed {
+      to_run.remove(&name);
+
+      if namespace_filter.is_none() || name.namespace.as_deref() == namespace_filter {
+        top_level_names.push(name.clone());
+      }
+      let imp = stub::stub_impl(&name, kind, context)?;
+      context.insert_impl(name, imp);
+    }
+
     let mut next_to_run = BTreeMap::new();
 
+    // The reference each pending component is waiting on. A reference to another pending
+    // component is a forward reference and is retried once that component is generated.
+    let mut waiting_on = BTreeMap::new();
+
+    // Components not generated yet, and those that failed for another reason than a missing
+    // reference. Failures are reported once the pass has recorded what waits on them.
+    let mut pending: BTreeSet<XsdName> = to_run.keys().cloned().collect();
+    let mut failed = BTreeMap::new();
+
+    // Imported schemas don't depend on the components of this one, they are loaded first.
+    let imports = self.imports().collect::<Vec<_>>();
+    let shared: &XsdContext = context;
+    for imported in par_map(&imports, |import| import.get_implementation(shared)) {
+      let imported = imported?;
+      for imp in imported.impls {
+        context.insert_impl(imp.name.clone(), imp);
+      }
+      context.versions.extend(imported.versions);
+      context.lossy_mappings.extend(imported.lossy_mappings);
+      context
+        .unbounded_max_occurs
+        .extend(imported.unbounded_max_occurs);
+      context
+        .invalid_enumerations
+        .extend(imported.invalid_enumerations);
+      context.content_hashes.extend(imported.content_hashes);
+      context
+        .substitution_groups
+        .extend(imported.substitution_groups);
+      context.derived_types.extend(imported.derived_types);
+    }
+
     let mut changed = true;
     while changed {
       changed = false;
 
-      for (type_to_run, (index, _error)) in &to_run {
+      // With the `parallel` feature, the components that are not waiting on another one are
+      // generated up front against the context of the start of the pass. Their results are then
+      // used in order as if generated one after the other, until an earlier component of the pass
+      // is inserted: the context changed, so the following ones are generated again.
+      let mut speculative = BTreeMap::new();
+      if speculate {
+        let ready = to_run
+          .iter()
+          .filter(|(name, _)| {
+            !failed.contains_key(*name) && !Self::is_blocked(name, &waiting_on, &pending)
+          })
+          .filter_map(|(name, (index, _))| Some((name, (*index)?)))
+          .collect::<Vec<_>>();
+
+        let shared: &XsdContext = context;
+        let results = par_map(&ready, |(_, index)| self.generate_component(*index, shared));
+        speculative = ready
+          .into_iter()
+          .map(|(name, _)| name)
+          .zip(results)
+          .collect();
+      }
+      let mut inserted = false;
+
+      for (type_to_run, (index, error)) in &to_run {
+        context.cancel_token.check()?;
+        if failed.contains_key(type_to_run) {
+          continue;
+        }
+
+        // Retrying before the blocker is generated would fail the same way again.
+        if Self::is_blocked(type_to_run, &waiting_on, &pending) {
+          next_to_run
+            .entry(type_to_run.clone())
+            .or_insert((*index, *error));
+          continue;
+        }
+
         if let Some(index) = index {
-          let result = match &self.children[*index] {
-            SchemaOptions::Import(import) => {
-              import.get_implementation(context)?;
-              None
-            }
-            SchemaOptions::Annotation(annotation) => {
-              annotation.get_doc();
-              None
-            }
-            SchemaOptions::Element(element) => Some(element.get_implementation(context)),
-            SchemaOptions::SimpleType(simple_type) => {
-              Some(simple_type.get_implementation(None, context))
-            }
-            SchemaOptions::ComplexType(complex_type) => {
-              Some(complex_type.get_implementation(true, None, context))
-            }
-            SchemaOptions::Attribute(attribute) => {
-              Some(attribute.get_implementation(context, true))
-            }
-            SchemaOptions::AttributeGroup(attribute_group) => {
-              Some(attribute_group.get_implementation(None, context))
-            }
-            SchemaOptions::Group(group) => Some(group.get_implementation(None, context)),
+          let result = match speculative.remove(type_to_run) {
+            Some(result) if !inserted => result,
+            _ => self.generate_component(*index, context),
           };
 
           if let Some(result) = result {
             match result {
               Ok(temp) => {
                 changed = true;
+                inserted = true;
                 let mut include_type = false;
                 if let Some(filter) = namespace_filter {
                   if let Some(namespace) = &temp.name.namespace {
@@ -183,11 +483,15 @@ impl Schema {
                 // It's possible that a type was missed earlier in the loop and
                 // added to the need to run queue. If we found it now, we can just remove it.
                 next_to_run.remove(&temp.name);
+                waiting_on.remove(type_to_run);
+                pending.remove(type_to_run);
 
                 context.insert_impl(temp.name.clone(), temp);
               }
               Err(ty) => match ty {
                 XsdError::XsdImplNotFound(name) => {
+                  waiting_on.insert(type_to_run.clone(), name.clone());
+
                   let curr = to_run
                     .get(&name)
                     .map(|v| (v.0, v.1 + 1))
@@ -200,52 +504,1364 @@ impl Schema {
                     .unwrap_or_else(|| (None, 0));
                   next_to_run.insert(type_to_run.clone(), curr);
                 }
-                _ => return Err(ty),
+                error => {
+                  next_to_run.remove(type_to_run);
+                  failed.insert(type_to_run.clone(), error);
+                }
               },
             }
           }
         }
       }
 
+      if let Some((name, source)) = failed.pop_first() {
+        let dependents = Self::dependents(&waiting_on, &name);
+        if dependents.is_empty() {
+          return Err(source);
+        }
+
+        return Err(XsdError::ComponentError {
+          name,
+          dependents,
+          source: Box::new(source),
+        });
+      }
+
       std::mem::swap(&mut to_run, &mut next_to_run);
       next_to_run.clear();
     }
 
-    let mut error_msg = String::new();
-    for (name, (index, error)) in to_run {
-      error_msg.push_str(&format!(
-        "\n[{:?}] {}{name} [{error}]",
-        name.ty,
-        if index.is_some() { "*" } else { "" }
-      ));
-    }
+    if !to_run.is_empty() {
+      if let Some(cycle) = Self::find_group_cycle(&waiting_on) {
+        return Err(XsdError::CircularDefinition { cycle });
+      }
+
+      for (name, (index, error)) in &to_run {
+        tracing::debug!(
+          "Unresolved {:?} {name} (declared in schema: {}, attempts: {error})",
+          name.ty,
+          index.is_some()
+        );
+      }
 
-    if !error_msg.is_empty() {
-      return Err(XsdError::XsdMissing(format!(
-        "COULD NOT FIND:{}",
-        error_msg
-      )));
+      // A declared component is missing the reference it last waited on, the others are only
+      // referenced.
+      let missing = to_run
+        .into_keys()
+        .map(|name| {
+          let source = XsdError::XsdImplNotFound(
+            waiting_on
+              .get(&name)
+              .cloned()
+              .unwrap_or_else(|| name.clone()),
+          );
+          (name, source)
+        })
+        .collect();
+      return Err(XsdError::XsdMissing { missing });
     }
 
+    context
+      .invalid_enumerations
+      .extend(enumeration::check(&self.enumerations, context));
+
     Ok(top_level_names)
   }
 
-  pub fn generate(&self, context: &mut XsdContext) -> Result<String, XsdError> {
-    let top_level_names = self.fill_context(context, None)?;
+  /// Generate the `index`th child of the schema, `None` for children without an implementation.
+  /// The indexes past the children are the enums of the substitutions of the schema.
+  fn generate_component(
+    &self,
+    index: usize,
+    context: &XsdContext,
+  ) -> Option<Result<XsdImpl, XsdError>> {
+    let Some(child) = self.children.get(index) else {
+      let name = self
+        .substitution_enums()
+        .into_iter()
+        .nth(index - self.children.len())?;
+      return Some(match name.ty {
+        XsdType::SubstitutionGroup => {
+          let head = XsdName {
+            ty: XsdType::Element,
+            ..name
+          };
+          substitution_group::get_implementation(&head, context)
+        }
+        _ => {
+          let base = XsdName {
+            ty: XsdType::ComplexType,
+            ..name
+          };
+          derived_types::get_implementation(&base, context)
+        }
+      });
+    };
+
+    match child {
+      SchemaOptions::Import(_) => None,
+      SchemaOptions::Annotation(annotation) => {
+        annotation.get_doc();
+        None
+      }
+      SchemaOptions::Element(element) => Some(element.get_implementation(context)),
+      SchemaOptions::SimpleType(simple_type) => Some(simple_type.get_implementation(None, context)),
+      SchemaOptions::ComplexType(complex_type) => {
+        Some(complex_type.get_implementation(true, None, context))
+      }
+      SchemaOptions::Attribute(attribute) => Some(attribute.get_implementation(context, true)),
+      SchemaOptions::AttributeGroup(attribute_group) => {
+        Some(attribute_group.get_implementation(None, context))
+      }
+      SchemaOptions::Group(group) => Some(group.get_implementation(None, context)),
+    }
+  }
+
+  /// Whether `name` waits on another component that is still pending. Some components report
+  /// themselves as missing, those are never blocked.
+  fn is_blocked(
+    name: &XsdName,
+    waiting_on: &BTreeMap<XsdName, XsdName>,
+    pending: &BTreeSet<XsdName>,
+  ) -> bool {
+    waiting_on.get(name).is_some_and(|blocker| {
+      pending
+        .iter()
+        .any(|other| other != name && Self::refers_to(blocker, other))
+    })
+  }
 
-    let mut dst = String::new();
-    dst.push_str(
-      "use xml_schema_parser::{XsdIoError, XsdGenError, XMLElement, XsdType, XsdGen, GenState, GenType, Date, FromXmlString, RestrictedVec};\n\n",
-    );
-    let mut formatter = Formatter::new(&mut dst);
-    // for name in top_level_names {
-    //   context.search(&name).unwrap().fmt(&mut formatter).unwrap();
-    // }
+  /// Whether the missing `reference` names the component `name`. A reference to a type may
+  /// be either a simple or a complex type (e.g. the base of an extension).
+  fn refers_to(reference: &XsdName, name: &XsdName) -> bool {
+    let is_type = |ty: XsdType| matches!(ty, XsdType::SimpleType | XsdType::ComplexType);
+
+    reference.namespace == name.namespace
+      && reference.local_name == name.local_name
+      && (reference.ty == name.ty || (is_type(reference.ty) && is_type(name.ty)))
+  }
+
+  /// The components waiting, directly or through other components, on `name`.
+  fn dependents(waiting_on: &BTreeMap<XsdName, XsdName>, name: &XsdName) -> Vec<XsdName> {
+    let mut dependents: Vec<XsdName> = vec![];
+    let mut queue = vec![name];
+
+    while let Some(blocker) = queue.pop() {
+      let waiting = waiting_on
+        .iter()
+        .filter(|(_, on)| Self::refers_to(on, blocker));
+      for (dependent, _) in waiting {
+        if dependent != name && !dependents.contains(dependent) {
+          dependents.push(dependent.clone());
+          queue.push(dependent);
+        }
+      }
+    }
+
+    dependents
+  }
+
+  /// Find a cycle of groups or attribute groups that all wait on the next one.
+  fn find_group_cycle(waiting_on: &BTreeMap<XsdName, XsdName>) -> Option<Vec<XsdName>> {
+    let is_group = |name: &XsdName| matches!(name.ty, XsdType::Group | XsdType::AttributeGroup);
+
+    for start in waiting_on.keys().filter(|name| is_group(name)) {
+      let mut cycle = vec![start.clone()];
+      let mut current = waiting_on.get(start);
+
+      while let Some(name) = current {
+        if name == start {
+          return Some(cycle);
+        }
+        if !is_group(name) || cycle.contains(name) {
+          break;
+        }
+        cycle.push(name.clone());
+        current = waiting_on.get(name);
+      }
+    }
+
+    None
+  }
+
+  /// Find the components reachable from the requested roots.
+  fn select_roots(context: &XsdContext, roots: &[String]) -> Result<BTreeSet<XsdName>, XsdError> {
+    let mut by_type: BTreeMap<String, Vec<&XsdName>> = BTreeMap::new();
+    for (name, value) in &context.structs {
+      if let Some(ty) = value.element.try_get_type() {
+        by_type.entry(ty.name.clone()).or_default().push(name);
+      }
+    }
 
-    for value in context.structs.values() {
-      value.fmt(&mut formatter).unwrap()
+    let mut selected = BTreeSet::new();
+    let mut queue = vec![];
+    for root in roots {
+      let found = context
+        .structs
+        .keys()
+        .filter(|name| &name.local_name == root)
+        .collect::<Vec<_>>();
+
+      if found.is_empty() {
+        return Err(XsdError::XsdImplNotFound(XsdName::new(
+          root,
+          XsdType::Unknown,
+        )));
+      }
+
+      queue.extend(found);
+    }
+
+    while let Some(name) = queue.pop() {
+      if !selected.insert(name.clone()) {
+        continue;
+      }
+
+      let mut referenced = BTreeSet::new();
+      context.structs[name].referenced_types(&mut referenced);
+      for ty in referenced {
+        if let Some(names) = by_type.get(&ty) {
+          queue.extend(names.iter().copied());
+        }
+      }
+    }
+
+    Ok(selected)
+  }
+
+  fn is_external(name: &XsdName, options: &GeneratorOptions) -> bool {
+    name
+      .namespace
+      .as_ref()
+      .is_some_and(|namespace| options.external_namespaces.contains_key(namespace))
+  }
+
+  /// Find the names the emitted types use from external namespaces, grouped by the path they
+  /// are imported from.
+  fn external_imports(
+    context: &XsdContext,
+    selected: &BTreeSet<XsdName>,
+    options: &GeneratorOptions,
+  ) -> Result<BTreeMap<String, BTreeSet<String>>, XsdError> {
+    let mut provided: BTreeMap<String, &String> = BTreeMap::new();
+    let mut emitted = BTreeSet::new();
+    for (name, value) in &context.structs {
+      let Some(ty) = value.element.try_get_type() else {
+        continue;
+      };
+
+      let namespace = name
+        .namespace
+        .as_ref()
+        .and_then(|namespace| options.external_namespaces.get_key_value(namespace));
+      match namespace {
+        Some((namespace, _)) => {
+          provided.insert(ty.name, namespace);
+        }
+        None if selected.contains(name) => {
+          emitted.insert(ty.name);
+        }
+        None => {}
+      }
+    }
+
+    for namespace in options.external_namespaces.keys() {
+      if !provided.values().any(|v| *v == namespace) {
+        return Err(XsdError::ExternalNamespace {
+          namespace: namespace.clone(),
+          msg: "no component of the schema is in this namespace".to_string(),
+        });
+      }
     }
 
-    Ok(dst)
+    let mut referenced = BTreeSet::new();
+    for name in selected {
+      context.structs[name].referenced_types(&mut referenced);
+    }
+
+    let mut imports: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for name in referenced {
+      let Some(namespace) = provided.get(&name) else {
+        continue;
+      };
+
+      if emitted.contains(&name) {
+        return Err(XsdError::ExternalNamespace {
+          namespace: namespace.to_string(),
+          msg: format!("`{name}` is also the name of a generated type"),
+        });
+      }
+
+      imports
+        .entry(options.external_namespaces[*namespace].clone())
+        .or_default()
+        .insert(name);
+    }
+
+    Ok(imports)
+  }
+
+  /// Pick a module name for a namespace, preferring the prefix the schema declared for it.
+  pub(super) fn namespace_module_name(context: &XsdContext, namespace: &str) -> String {
+    let is_prefix = |v: &str| !v.is_empty() && !v.contains(':') && !v.contains('/');
+
+    for (key, value) in &context.namespace.0 {
+      if value == namespace && is_prefix(key) && key != "xmlns" {
+        return to_module_name(key);
+      }
+      if key == namespace && is_prefix(value) {
+        return to_module_name(value);
+      }
+    }
+
+    if is_prefix(namespace) {
+      return to_module_name(namespace);
+    }
+
+    to_module_name(
+      namespace
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or(namespace),
+    )
+  }
+
+  pub fn generate(
+    &self,
+    context: &mut XsdContext,
+    options: &GeneratorOptions,
+  ) -> Result<(String, GenerationReport), XsdError> {
+    let (scope, report) = self.generate_scope(context, options)?;
+    let send_sync_paths = if options.send_sync_assertions {
+      Self::type_paths(context, options)?
+    } else {
+      vec![]
+    };
+    let type_index = if options.type_index {
+      Self::type_index(context, options)?
+    } else {
+      vec![]
+    };
+
+    Ok((
+      render(&scope, &send_sync_paths, type_index, options),
+      report,
+    ))
+  }
+
+  /// The components to generate, once the context is filled.
+  fn selected(
+    context: &XsdContext,
+    options: &GeneratorOptions,
+  ) -> Result<BTreeSet<XsdName>, XsdError> {
+    let mut selected = if options.roots.is_empty() {
+      context.structs.keys().cloned().collect()
+    } else {
+      Self::select_roots(context, &options.roots)?
+    };
+
+    selected.retain(|name| !Self::is_external(name, options));
+    Ok(selected)
+  }
+
+  /// The paths of the generated types, e.g. `Note` and `note::Version`.
+  pub(crate) fn type_paths(
+    context: &XsdContext,
+    options: &GeneratorOptions,
+  ) -> Result<Vec<String>, XsdError> {
+    let selected = Self::selected(context, options)?;
+    let mut paths = vec![];
+    for (name, value) in &context.structs {
+      if selected.contains(name) {
+        value.type_paths(&mut paths);
+      }
+    }
+    Ok(paths)
+  }
+
+  /// The entries of the `XML_TYPE_INDEX` static, see `GeneratorOptions::type_index`: the
+  /// namespace and the name of the emitted schema types along with their Rust type.
+  pub(crate) fn type_index(
+    context: &XsdContext,
+    options: &GeneratorOptions,
+  ) -> Result<Vec<IndexEntry>, XsdError> {
+    let selected = Self::selected(context, options)?;
+    Ok(
+      context
+        .structs
+        .iter()
+        .filter(|(name, _)| selected.contains(name))
+        .filter(|(name, _)| matches!(name.ty, XsdType::ComplexType | XsdType::SimpleType))
+        .filter(|(_, value)| !matches!(value.element, XsdImplType::Type(_)))
+        .map(|(name, value)| {
+          (
+            name.namespace.clone(),
+            name.local_name.clone(),
+            value.element.get_type().name,
+          )
+        })
+        .collect(),
+    )
+  }
+
+  /// Generate the types of the schema into a scope, without the `use` prelude rendered by
+  /// `generate`.
+  pub(crate) fn generate_scope(
+    &self,
+    context: &mut XsdContext,
+    options: &GeneratorOptions,
+  ) -> Result<(Scope, GenerationReport), XsdError> {
+    let decisions = self.decide(context, options)?;
+    let mut scope = Self::emit(context, &decisions, options)?;
+    let mut report = decisions.report;
+    if options.key_resolvers {
+      let documents = self.document_keys(context, &decisions.selected, options);
+      report
+        .warnings
+        .extend(key_resolvers::add_key_resolvers(&mut scope, &documents));
+    }
+    Ok((scope, report))
+  }
+
+  /// The items the generation would emit, without formatting them.
+  pub(crate) fn plan(
+    &self,
+    context: &mut XsdContext,
+    options: &GeneratorOptions,
+  ) -> Result<GenerationPlan, XsdError> {
+    let decisions = self.decide(context, options)?;
+    let items = report::planned_items(Self::emitted(context, &decisions.selected));
+    let mut warnings = decisions.report.warnings.clone();
+    if options.key_resolvers {
+      // The paths of the keys are mapped onto the emitted types.
+      let documents = self.document_keys(context, &decisions.selected, options);
+      let mut scope = Self::emit(context, &decisions, options)?;
+      warnings.extend(key_resolvers::add_key_resolvers(&mut scope, &documents));
+    }
+    Ok(GenerationPlan {
+      items,
+      warnings,
+      dispatch_tables: decisions.report.dispatch_tables,
+    })
+  }
+
+  /// The identity constraints of the selected top-level elements, along with the path of the
+  /// type of their documents in the generated scope.
+  fn document_keys(
+    &self,
+    context: &XsdContext,
+    selected: &BTreeSet<XsdName>,
+    options: &GeneratorOptions,
+  ) -> Vec<DocumentKeys> {
+    let mut documents = vec![];
+    for child in &self.children {
+      let SchemaOptions::Element(element) = child else {
+        continue;
+      };
+      let Some(name) = &element.name else {
+        continue;
+      };
+      if element.identity_constraints.is_empty() || !selected.contains(name) {
+        continue;
+      }
+      let Some(ty) = context
+        .structs
+        .get(name)
+        .and_then(|value| value.element.try_get_type())
+      else {
+        continue;
+      };
+
+      let mut root = match (&name.namespace, options.namespace_modules) {
+        (Some(namespace), true) => vec![Self::namespace_module_name(context, namespace)],
+        _ => vec![],
+      };
+      root.push(ty.name);
+      documents.push(DocumentKeys {
+        element: name.clone(),
+        root,
+        constraints: element.identity_constraints.clone(),
+      });
+    }
+    documents
+  }
+
+  /// Fill the context and decide what to emit: the components, the names imported from
+  /// external namespaces and the report.
+  fn decide(
+    &self,
+    context: &mut XsdContext,
+    options: &GeneratorOptions,
+  ) -> Result<Decisions, XsdError> {
+    options.check()?;
+    let unknown_builtins = context.set_options(options);
+
+    context.versions.clear();
+    context.lossy_mappings.clear();
+    context.invalid_enumerations.clear();
+    context.unbounded_max_occurs.clear();
+    let _top_level_names = self.fill_context(context, None)?;
+
+    let newer = context
+      .versions
+      .iter()
+      .filter(|used| used.version > options.conformance)
+      .cloned()
+      .collect::<Vec<_>>();
+    if options.strict_conformance && !newer.is_empty() {
+      return Err(XsdError::Conformance {
+        version: options.conformance,
+        uses: newer,
+      });
+    }
+
+    let mut warnings = newer
+      .into_iter()
+      .map(|used| Warning {
+        path: used.path,
+        msg: format!("{} requires XSD {}", used.construct, used.version),
+      })
+      .collect::<Vec<_>>();
+
+    let lossy = lossy_mappings::check(&context.lossy_mappings, context);
+    if options.strict_mappings && !lossy.is_empty() {
+      return Err(XsdError::LossyMapping { uses: lossy });
+    }
+    warnings.extend(unknown_builtins.into_iter().map(|builtin| Warning {
+      path: "/schema".to_string(),
+      msg: format!("{builtin} is not a builtin type, its override is ignored"),
+    }));
+
+    let invalid = std::mem::take(&mut context.invalid_enumerations);
+    if !options.lenient_enumerations && !invalid.is_empty() {
+      return Err(XsdError::InvalidEnumeration { invalid });
+    }
+    warnings.extend(invalid);
+    warnings.append(&mut context.unbounded_max_occurs);
+    warnings.extend(stub::remove_skipped(context));
+    warnings.extend(field_overrides::apply_field_overrides(
+      context,
+      &options.field_overrides,
+    ));
+    if options.inherited_lang {
+      inherited_lang::add_inherited_lang(context);
+    }
+    if options.unknown_content {
+      unknown_content::add_unknown_content(context);
+    }
+    for warning in &warnings {
+      tracing::warn!("{}: {}", warning.path, warning.msg);
+    }
+
+    let selected = Self::selected(context, options)?;
+    let imports = Self::external_imports(context, &selected, options)?;
+
+    let report = GenerationReport {
+      types: report::type_statistics(
+        context
+          .structs
+          .iter()
+          .filter(|(name, _)| selected.contains(name)),
+      ),
+      warnings,
+      collapsed_wrappers: report::collapsed_wrappers(
+        context
+          .structs
+          .iter()
+          .filter(|(name, _)| selected.contains(name)),
+      ),
+      reserved_renames: report::reserved_renames(
+        context
+          .structs
+          .iter()
+          .filter(|(name, _)| selected.contains(name)),
+      ),
+      dispatch_tables: report::dispatch_tables(
+        context
+          .structs
+          .iter()
+          .filter(|(name, _)| selected.contains(name)),
+      ),
+      lossy_mappings: lossy,
+    };
+
+    Ok(Decisions {
+      selected,
+      imports,
+      report,
+    })
+  }
+
+  /// The emitted components, in the order they are emitted. Top-level types are ordered by
+  /// namespace and then by their Rust name.
+  fn emitted<'a>(
+    context: &'a XsdContext,
+    selected: &BTreeSet<XsdName>,
+  ) -> Vec<(&'a XsdName, &'a XsdImpl)> {
+    let mut values: Vec<_> = context
+      .structs
+      .iter()
+      .filter(|(name, _)| selected.contains(name))
+      .collect();
+    values.sort_by_cached_key(|(name, value)| {
+      (
+        name.namespace.clone(),
+        value.element.try_get_type().map(|ty| ty.name),
+        (*name).clone(),
+      )
+    });
+    values
+  }
+
+  /// Format the decided components into a scope, along with the impls added by the options.
+  fn emit(
+    context: &mut XsdContext,
+    decisions: &Decisions,
+    options: &GeneratorOptions,
+  ) -> Result<Scope, XsdError> {
+    let selected = &decisions.selected;
+
+    if !options.extra_derives.is_empty() {
+      for (name, value) in context.structs.iter_mut() {
+        if selected.contains(name) {
+          value.add_derives(&options.extra_derives);
+        }
+      }
+    }
+
+    if options.serde {
+      for (name, value) in context.structs.iter_mut() {
+        if selected.contains(name) {
+          serde_attributes::add_serde_attributes(value);
+        }
+      }
+    }
+
+    if options.dynamic_values {
+      for (name, value) in context.structs.iter_mut() {
+        if selected.contains(name) {
+          dynamic_values::add_dynamic_conversions(value);
+        }
+      }
+    }
+
+    if options.xml_writers {
+      for (name, value) in context.structs.iter_mut() {
+        if selected.contains(name) {
+          xml_writers::add_xml_writers(value);
+        }
+      }
+    }
+
+    conversion::add_conversions(context, selected);
+    progress::add_document_impls(context, selected);
+    if options.progress_hooks {
+      progress::add_progress_helpers(context, selected);
+    }
+    if options.async_entry_points {
+      progress::add_async_entry_points(context, selected);
+    }
+
+    if options.stats_in_docs {
+      let stats = decisions
+        .report
+        .types
+        .iter()
+        .map(|stats| (stats.path.clone(), stats))
+        .collect();
+      for (name, value) in context.structs.iter_mut() {
+        if selected.contains(name) {
+          report::annotate(value, "", &stats);
+        }
+      }
+    }
+
+    let mut scope = Scope::new();
+    for (path, names) in &decisions.imports {
+      for name in names {
+        scope.import(path, name);
+      }
+    }
+
+    let allow: Vec<&str> = match &options.inner_module_lints {
+      Some(lints) => lints.iter().map(String::as_str).collect(),
+      None => DEFAULT_INNER_MODULE_LINTS.to_vec(),
+    };
+
+    // Each type is followed by its impls and the module holding its inner types. Namespace
+    // modules, in name order, come after the types that are not in one.
+    let mut modules: BTreeMap<String, Module> = BTreeMap::new();
+    let generated_types = borrowed::generated_types(context);
+    for (name, value) in Self::emitted(context, selected) {
+      options.cancel_token.check()?;
+      let target = match (&name.namespace, options.namespace_modules) {
+        (Some(namespace), true) => {
+          let module_name = Self::namespace_module_name(context, namespace);
+          let module = modules.entry(module_name.clone()).or_insert_with(|| {
+            let mut module = Module::new(&module_name).vis("pub");
+            module.import("super", "*");
+            module
+          });
+
+          module.scope()
+        }
+        _ => &mut scope,
+      };
+
+      if options.content_hashes {
+        if let Some(hash) = context.content_hashes.get(name) {
+          target.raw(&format!("// xsd-hash: {hash}"));
+        }
+      }
+      value.push_to_scope(target, &allow);
+      if options.borrowed_types {
+        if let Some((borrowed, implementation)) =
+          borrowed::borrowed_type(context, &generated_types, value)
+        {
+          target.push_struct(borrowed).push_impl(implementation);
+        }
+      }
+    }
+
+    for (name, module) in modules {
+      if module.scope.items.is_empty() {
+        continue;
+      }
+
+      scope.push_module(module);
+      scope.raw(&format!("pub use {name}::*;"));
+    }
+
+    if options.choice_visitors {
+      visitor::add_choice_visitors(&mut scope);
+    }
+    if options.doc_links {
+      doc_links::add_doc_links(&mut scope);
+    }
+    // Last, the visitors and the links are added as public items.
+    if options.visibility != Visibility::Pub {
+      visibility::restrict_visibility(&mut scope, options.visibility.as_str());
+    }
+
+    Ok(scope)
+  }
+}
+
+/// What a generation run emits, decided once the context is filled and before anything is
+/// formatted.
+struct Decisions {
+  selected: BTreeSet<XsdName>,
+  /// The names used from external namespaces, by the path they are imported from.
+  imports: BTreeMap<String, BTreeSet<String>>,
+  report: GenerationReport,
+}
+
+/// An entry of the `XML_TYPE_INDEX` static: the namespace and the name of a schema type, and
+/// the generated type.
+pub(crate) type IndexEntry = (Option<String>, String, String);
+
+/// Render a generated scope along with the imports of the generated code and, when
+/// `send_sync_paths` is not empty, a test module asserting the types are `Send + Sync`. The
+/// entries of `type_index` are rendered when `GeneratorOptions::type_index` is set.
+pub(crate) fn render(
+  scope: &Scope,
+  send_sync_paths: &[String],
+  mut type_index: Vec<IndexEntry>,
+  options: &GeneratorOptions,
+) -> String {
+  // The generated types are never named after these names, see `is_reserved`.
+  let mut imports = vec![generated_prelude::BASE];
+  if options.field_metadata {
+    imports.push(generated_prelude::FIELD_METADATA);
+  }
+  if options.progress_hooks {
+    imports.push(generated_prelude::PROGRESS_HOOKS);
+  }
+  if options.stubs.values().any(|kind| *kind == StubKind::Opaque) {
+    imports.push(generated_prelude::OPAQUE_STUBS);
+  }
+  if options.type_index {
+    imports.push(generated_prelude::TYPE_INDEX);
+  }
+  if options.borrowed_types {
+    imports.push(generated_prelude::BORROWED_TYPES);
+  }
+  if options.key_resolvers {
+    imports.push(generated_prelude::KEY_RESOLVERS);
+  }
+  if options.dynamic_values {
+    imports.push(generated_prelude::DYNAMIC_VALUES);
+  }
+  if options.xml_writers {
+    imports.push(generated_prelude::XML_WRITERS);
+  }
+  if options.unknown_content {
+    imports.push(generated_prelude::UNKNOWN_CONTENT);
+  }
+
+  // The prelude is not trimmed to the names the generated code uses.
+  let mut dst = drift::header(options);
+  dst.push('\n');
+  for names in imports {
+    dst.push_str("#[allow(unused_imports)]\n");
+    match names {
+      [name] => dst.push_str(&format!("use xml_schema_parser::{name};\n")),
+      names => dst.push_str(&format!(
+        "use xml_schema_parser::{{{}}};\n",
+        names.join(", ")
+      )),
+    }
+  }
+  dst.push('\n');
+
+  let mut formatter = Formatter::new(&mut dst).provenance_comments(options.provenance_comments);
+  scope.fmt(&mut formatter).unwrap();
+
+  if options.type_index {
+    // Sorted for `XmlTypeIndex::get`.
+    type_index.sort();
+    type_index.dedup();
+    writeln!(
+      formatter,
+      "\n/// The generated types by the namespace and the name of their schema type."
+    )
+    .unwrap();
+    writeln!(
+      formatter,
+      "{} static XML_TYPE_INDEX: XmlTypeIndex = XmlTypeIndex::new(&[",
+      options.visibility.as_str()
+    )
+    .unwrap();
+    for (namespace, name, path) in &type_index {
+      let namespace = match namespace {
+        Some(namespace) => format!("Some({})", escape_str_literal(namespace)),
+        None => "None".to_string(),
+      };
+      writeln!(
+        formatter,
+        "    ({namespace}, {}, {}),",
+        escape_str_literal(name),
+        escape_str_literal(path)
+      )
+      .unwrap();
+    }
+    writeln!(formatter, "]);").unwrap();
+  }
+
+  if !send_sync_paths.is_empty() {
+    writeln!(formatter, "\n#[cfg(test)]\nmod send_sync_assertions {{").unwrap();
+    writeln!(formatter, "    use super::*;\n").unwrap();
+    // The traits are named by path, a generated type may be named `Send` or `Sync`.
+    writeln!(
+      formatter,
+      "    fn assert_send_sync<T: ::core::marker::Send + ::core::marker::Sync + 'static>() {{}}\n"
+    )
+    .unwrap();
+    writeln!(
+      formatter,
+      "    #[test]\n    fn generated_types_are_send_sync() {{"
+    )
+    .unwrap();
+    for path in send_sync_paths {
+      writeln!(formatter, "        assert_send_sync::<{path}>();").unwrap();
+    }
+    writeln!(formatter, "    }}\n}}").unwrap();
+  }
+
+  dst
+}
+
+#[test]
+fn missing_names_are_structured() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="thing">
+        <xs:complexContent>
+          <xs:extension base="missing" />
+        </xs:complexContent>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let error = xsd.generate_with(&GeneratorOptions::default()).unwrap_err();
+  assert_eq!(error.error_code(), "xsd-missing");
+
+  match error {
+    XsdError::XsdMissing { missing } => {
+      let missing_base = XsdName::new("missing", xsd_types::XsdType::SimpleType);
+      let reasons = missing
+        .iter()
+        .map(|(name, source)| (name.local_name.as_str(), source.to_string()))
+        .collect::<Vec<_>>();
+      assert_eq!(
+        reasons,
+        [
+          ("missing", format!("{missing_base} not found")),
+          ("thing", format!("{missing_base} not found")),
+        ]
+      );
+    }
+    error => panic!("unexpected error {error:?}"),
+  }
+}
+
+#[test]
+fn send_sync_assertions_cover_nested_types() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="note">
+        <xs:attribute name="version" type="xs:string" fixed="3.1" />
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let output = xsd
+    .generate_with(&GeneratorOptions {
+      fixed_values: crate::FixedValues::Marker,
+      send_sync_assertions: true,
+      ..Default::default()
+    })
+    .unwrap();
+
+  assert!(output.contains("#[cfg(test)]\nmod send_sync_assertions"));
+  assert!(output.contains("assert_send_sync::<Note>();"));
+  assert!(output.contains("assert_send_sync::<note::Version>();"));
+}
+
+#[test]
+fn types_are_not_named_after_the_prelude() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="sync">
+        <xs:attribute name="key-index" type="xs:string" />
+      </xs:complexType>
+      <xs:complexType name="key-index">
+        <xs:sequence>
+          <xs:element name="sync" type="sync" />
+        </xs:sequence>
+      </xs:complexType>
+      <xs:complexType name="xsd-ser" />
+      <xs:complexType name="unknown-content" />
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let output = xsd
+    .generate_with(&GeneratorOptions {
+      field_metadata: true,
+      progress_hooks: true,
+      type_index: true,
+      borrowed_types: true,
+      key_resolvers: true,
+      dynamic_values: true,
+      xml_writers: true,
+      unknown_content: true,
+      ..Default::default()
+    })
+    .unwrap();
+
+  let imported: Vec<_> = output
+    .lines()
+    .filter_map(|line| line.strip_prefix("use xml_schema_parser::"))
+    .flat_map(|names| names.trim_matches(|c| "{};".contains(c)).split(", "))
+    .collect();
+  assert!(imported.contains(&"UnknownContent"));
+  for name in imported {
+    assert!(xsd_types::is_reserved(name), "{name}");
+  }
+  for name in [
+    "SyncType",
+    "KeyIndexType",
+    "XsdSerType",
+    "UnknownContentType",
+  ] {
+    assert!(output.contains(&format!("pub struct {name}")), "{name}");
+  }
+}
+
+#[test]
+fn type_index_lists_the_schema_types() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:shapes">
+      <xs:simpleType name="radius">
+        <xs:restriction base="xs:double" />
+      </xs:simpleType>
+      <xs:complexType name="circle">
+        <xs:attribute name="radius" type="xs:double" />
+      </xs:complexType>
+      <xs:element name="circle" type="circle" />
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let output = xsd.generate_with(&GeneratorOptions::default()).unwrap();
+  assert!(!output.contains("XML_TYPE_INDEX"));
+
+  let output = xsd
+    .generate_with(&GeneratorOptions {
+      type_index: true,
+      ..Default::default()
+    })
+    .unwrap();
+  assert!(output.contains("use xml_schema_parser::XmlTypeIndex;"));
+  assert!(output.contains(
+    r#"pub static XML_TYPE_INDEX: XmlTypeIndex = XmlTypeIndex::new(&[
+    (Some("urn:shapes"), "circle", "Circle"),
+    (Some("urn:shapes"), "radius", "Radius"),
+]);"#
+  ));
+}
+
+#[test]
+fn group_cycles_are_reported() {
+  let cycle = |schema: &str| match crate::Xsd::new(schema)
+    .unwrap()
+    .generate_with(&GeneratorOptions::default())
+    .unwrap_err()
+  {
+    XsdError::CircularDefinition { cycle } => cycle
+      .into_iter()
+      .map(|name| name.local_name)
+      .collect::<Vec<_>>(),
+    error => panic!("unexpected error {error:?}"),
+  };
+
+  let self_reference = cycle(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:attributeGroup name="common">
+        <xs:attribute name="id" type="xs:string"/>
+        <xs:attributeGroup ref="common"/>
+      </xs:attributeGroup>
+    </xs:schema>
+  "#,
+  );
+  assert_eq!(self_reference, ["common"]);
+
+  let two_groups = cycle(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:group name="a">
+        <xs:sequence>
+          <xs:element name="x" type="xs:string"/>
+          <xs:group ref="b"/>
+        </xs:sequence>
+      </xs:group>
+      <xs:group name="b">
+        <xs:sequence>
+          <xs:group ref="a"/>
+        </xs:sequence>
+      </xs:group>
+    </xs:schema>
+  "#,
+  );
+  assert_eq!(two_groups, ["a", "b"]);
+
+  // Forward references between groups are not cycles.
+  crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:group name="a">
+        <xs:sequence>
+          <xs:group ref="b"/>
+        </xs:sequence>
+      </xs:group>
+      <xs:group name="b">
+        <xs:sequence>
+          <xs:element name="x" type="xs:string"/>
+        </xs:sequence>
+      </xs:group>
+    </xs:schema>
+  "#,
+  )
+  .unwrap()
+  .generate_with(&GeneratorOptions::default())
+  .unwrap();
+}
+
+#[test]
+fn types_are_emitted_with_their_impls_and_inner_modules() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:simpleType name="zone">
+        <xs:restriction base="xs:string">
+          <xs:enumeration value="low"/>
+        </xs:restriction>
+      </xs:simpleType>
+      <xs:complexType name="note">
+        <xs:sequence>
+          <xs:element name="pitch" type="xs:string"/>
+          <xs:choice>
+            <xs:element name="tie" type="xs:string"/>
+            <xs:sequence>
+              <xs:element name="slur" type="xs:int"/>
+              <xs:choice>
+                <xs:element name="up" type="xs:string"/>
+                <xs:element name="down" type="xs:string"/>
+              </xs:choice>
+            </xs:sequence>
+          </xs:choice>
+        </xs:sequence>
+      </xs:complexType>
+      <xs:simpleType name="beam">
+        <xs:restriction base="xs:string">
+          <xs:enumeration value="begin"/>
+          <xs:enumeration value="end"/>
+        </xs:restriction>
+      </xs:simpleType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let output = xsd.generate_with(&GeneratorOptions::default()).unwrap();
+  let layout: Vec<_> = output
+    .lines()
+    .filter(|line| {
+      let line = line.trim_start();
+      ["pub struct", "pub enum", "impl", "pub mod"]
+        .iter()
+        .any(|item| line.starts_with(item))
+    })
+    .map(|line| line.trim_end_matches(" {"))
+    .collect();
+
+  assert_eq!(
+    layout,
+    [
+      "pub enum Beam",
+      "impl FromXmlString for Beam",
+      "pub struct Note",
+      "impl XsdGen for Note",
+      "pub mod note",
+      "    pub enum Tieslurupdown",
+      "    impl XsdGen for Tieslurupdown",
+      "    pub mod tieslurupdown",
+      "        pub struct Slurupdown",
+      "        impl XsdGen for Slurupdown",
+      "        pub mod slurupdown",
+      "            pub enum Updown",
+      "            impl XsdGen for Updown",
+      "pub enum Zone",
+      "impl FromXmlString for Zone",
+    ]
+  );
+}
+
+#[test]
+fn merged_schemas_share_their_common_imports() {
+  let dir = tempfile::tempdir().unwrap();
+  let common = dir.path().join("common.xsd");
+  std::fs::write(
+    &common,
+    r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:common">
+  <xs:complexType name="pitch">
+    <xs:sequence>
+      <xs:element name="step" type="xs:string"/>
+    </xs:sequence>
+  </xs:complexType>
+</xs:schema>"#,
+  )
+  .unwrap();
+
+  let schema = |root: &str| {
+    format!(
+      r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:c="urn:common">
+  <xs:import namespace="urn:common" schemaLocation="{}"/>
+  <xs:complexType name="{root}">
+    <xs:sequence>
+      <xs:element name="pitch" type="c:pitch"/>
+    </xs:sequence>
+  </xs:complexType>
+</xs:schema>"#,
+      common.display()
+    )
+  };
+
+  let xsds = [
+    crate::Xsd::new(&schema("note")).unwrap(),
+    crate::Xsd::new(&schema("chord")).unwrap(),
+  ];
+  let (output, report) = crate::Xsd::generate_merged(&xsds, &GeneratorOptions::default()).unwrap();
+
+  assert_eq!(output.matches("pub struct Pitch {").count(), 1);
+  assert_eq!(output.matches("impl XsdGen for Pitch {").count(), 1);
+  assert!(output.contains("pub struct Note {"));
+  assert!(output.contains("pub struct Chord {"));
+
+  let mut paths: Vec<_> = report
+    .types
+    .iter()
+    .map(|stats| stats.path.as_str())
+    .collect();
+  paths.sort();
+  assert_eq!(paths, ["Chord", "Note", "Pitch"]);
+
+  let note = |ty: &str| {
+    crate::Xsd::new(&format!(
+      r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:complexType name="note">
+    <xs:attribute name="step" type="{ty}"/>
+  </xs:complexType>
+</xs:schema>"#
+    ))
+    .unwrap()
+  };
+  let error = crate::Xsd::generate_merged(
+    &[note("xs:string"), note("xs:integer")],
+    &GeneratorOptions::default(),
+  )
+  .unwrap_err();
+  assert_eq!(error.error_code(), "merge-conflict");
+  let message = error.to_string();
+  assert!(message.starts_with("Conflicting definitions of `Note`:"));
+  assert!(message.contains("pub step: Option<String>"));
+  assert!(message.contains("pub step: Option<i32>"));
+}
+
+#[test]
+fn speculation_does_not_change_the_context() {
+  let fill = |schema: &str, speculate| {
+    let mut xsd = crate::Xsd::new(schema).unwrap();
+    let names = xsd
+      .schema
+      .fill_context_with(&mut xsd.context, None, speculate)
+      .map_err(|error| error.to_string());
+    (names, xsd.context.structs)
+  };
+
+  // Forward references, generated over several passes.
+  let forward = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="chord">
+        <xs:sequence>
+          <xs:element name="note" type="note" maxOccurs="unbounded"/>
+        </xs:sequence>
+      </xs:complexType>
+      <xs:complexType name="note">
+        <xs:complexContent>
+          <xs:extension base="pitched">
+            <xs:attribute name="color" type="color"/>
+          </xs:extension>
+        </xs:complexContent>
+      </xs:complexType>
+      <xs:complexType name="pitched">
+        <xs:sequence>
+          <xs:element name="step" type="step"/>
+        </xs:sequence>
+      </xs:complexType>
+      <xs:simpleType name="step">
+        <xs:restriction base="xs:string"/>
+      </xs:simpleType>
+      <xs:simpleType name="color">
+        <xs:restriction base="xs:token"/>
+      </xs:simpleType>
+    </xs:schema>
+  "#;
+  let sequential = fill(forward, false);
+  assert!(sequential.0.is_ok());
+  assert_eq!(fill(forward, true), sequential);
+
+  // `thing` can be generated at the start of the second pass, as only the complex `dup` exists
+  // then. The simple `dup` is generated earlier in that pass and makes its base ambiguous.
+  let ambiguous = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="thing">
+        <xs:complexContent>
+          <xs:extension base="dup">
+            <xs:sequence>
+              <xs:element name="z" type="zz"/>
+            </xs:sequence>
+          </xs:extension>
+        </xs:complexContent>
+      </xs:complexType>
+      <xs:complexType name="dup">
+        <xs:sequence>
+          <xs:element name="a" type="xs:string"/>
+        </xs:sequence>
+      </xs:complexType>
+      <xs:simpleType name="dup">
+        <xs:restriction base="zeta"/>
+      </xs:simpleType>
+      <xs:simpleType name="zeta">
+        <xs:restriction base="xs:string"/>
+      </xs:simpleType>
+      <xs:simpleType name="zz">
+        <xs:restriction base="xs:string"/>
+      </xs:simpleType>
+    </xs:schema>
+  "#;
+  let sequential = fill(ambiguous, false);
+  assert!(sequential.0.is_err());
+  assert_eq!(fill(ambiguous, true), sequential);
+}
+
+#[test]
+fn schemas_with_a_doctype_are_read() {
+  let xsd = crate::Xsd::new(
+    r#"<?xml version="1.0"?>
+<!DOCTYPE xs:schema PUBLIC "-//W3C//DTD XMLSCHEMA 200102//EN" "XMLSchema.dtd" [
+  <!ENTITY tempo "tempo">
+]>
+<?xml-stylesheet type="text/xsl" href="schema.xsl"?>
+<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:element name="&tempo;">
+    <xs:complexType>
+      <xs:attribute name="bpm" type="xs:int"/>
+    </xs:complexType>
+  </xs:element>
+</xs:schema>
+"#,
+  )
+  .unwrap();
+
+  assert!(xsd
+    .generate_with(&GeneratorOptions::default())
+    .unwrap()
+    .contains("pub struct Tempo"));
+}
+
+#[test]
+fn malformed_schemas_are_errors() {
+  for (components, code) in [
+    (r#"<xs:include schemaLocation="other.xsd" />"#, "xsd-parse"),
+    (
+      r#"<xs:complexType><xs:sequence /></xs:complexType>"#,
+      "xsd-parse",
+    ),
+    (r#"<xs:attributeGroup ref="common" />"#, "xsd-parse"),
+    (r#"<xs:simpleType name="empty" />"#, "xsd-parse"),
+    (r#"<xs:group name="empty" />"#, "xsd-parse"),
+    (
+      r#"<xs:complexType name="note"><xs:complexContent /></xs:complexType>"#,
+      "xsd-parse",
+    ),
+    (
+      r#"<xs:complexType name="note"><xs:sequence><xs:any /></xs:sequence></xs:complexType>"#,
+      "xsd-parse",
+    ),
+    (
+      r#"<xs:attribute name="id" /><xs:complexType name="note"><xs:attribute ref="id" /></xs:complexType>"#,
+      "xsd-gen",
+    ),
+  ] {
+    let schema =
+      format!(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">{components}</xs:schema>"#);
+    let error = crate::Xsd::new(&schema)
+      .and_then(|xsd| xsd.generate_with(&GeneratorOptions::default()))
+      .unwrap_err();
+    assert_eq!(error.error_code(), code, "{components}: {error}");
   }
 }
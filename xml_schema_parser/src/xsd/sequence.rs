@@ -1,18 +1,19 @@
-use xsd_codegen::{Struct, XMLElement};
-use xsd_types::{XsdIoError, XsdName, XsdType};
+use xsd_codegen::{ContentKind, Struct, XMLElement};
+use xsd_types::{SchemaError, XsdName, XsdParseError, XsdType};
 
 use super::{
   annotation::Annotation,
   choice::Choice,
   general_xsdgen,
   group::Group,
-  max_occurences::MaxOccurences,
-  xsd_context::{infer_type_name, MergeSettings, XsdImpl, XsdImplType},
+  max_occurences::{self, MaxOccurences, ITEM},
+  xsd_context::{infer_type_name, wrapped_name, MergeSettings, XsdImpl, XsdImplType},
   XsdError,
 };
 use crate::xsd::{element::Element, XsdContext};
 
 #[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 pub enum SequenceOptions {
   Element(Element),
   Group(Group),
@@ -20,6 +21,17 @@ pub enum SequenceOptions {
   Sequence(Sequence),
 }
 
+impl SequenceOptions {
+  fn is_prohibited(&self) -> bool {
+    match self {
+      SequenceOptions::Element(element) => element.max_occurences.is_prohibited(),
+      SequenceOptions::Group(group) => group.max_occurences.is_prohibited(),
+      SequenceOptions::Choice(choice) => choice.max_occurences.is_prohibited(),
+      SequenceOptions::Sequence(sequence) => sequence.max_occurences.is_prohibited(),
+    }
+  }
+}
+
 #[derive(Clone, Default, Debug, PartialEq)]
 pub struct Sequence {
   pub id: Option<String>,
@@ -30,27 +42,33 @@ pub struct Sequence {
 }
 
 impl Sequence {
-  pub fn parse(mut element: XMLElement) -> Result<Self, XsdIoError> {
+  pub fn parse(mut element: XMLElement) -> Result<Self, SchemaError> {
     element.check_name("sequence")?;
 
+    let annotation = element.try_get_child_with("annotation", Annotation::parse)?;
+
     let mut children = vec![];
     for child in element.get_all_children() {
-      children.push(match child.element.name.as_str() {
+      children.push(match child.name() {
         "element" => SequenceOptions::Element(Element::parse(child, false)?),
         "group" => SequenceOptions::Group(Group::parse(child)?),
         "choice" => SequenceOptions::Choice(Choice::parse(child)?),
         "sequence" => SequenceOptions::Sequence(Sequence::parse(child)?),
-        name => unreachable!("Unexpected child name {name}"),
+        name => {
+          return Err(SchemaError::Invalid(XsdParseError {
+            node_name: element.node_name(),
+            msg: format!("Unexpected child {name}"),
+          }))
+        }
       });
     }
 
+    let (min_occurences, max_occurences) = max_occurences::parse_occurences(&mut element, None)?;
     let output = Self {
       id: element.try_get_attribute("id")?,
-      min_occurences: element.try_get_attribute("minOccurs")?.unwrap_or(1),
-      max_occurences: element
-        .try_get_attribute("maxOccurs")?
-        .unwrap_or(MaxOccurences::Number { value: 1 }),
-      annotation: element.try_get_child_with("annotation", Annotation::parse)?,
+      min_occurences,
+      max_occurences,
+      annotation,
       children,
     };
 
@@ -63,11 +81,39 @@ impl Sequence {
   pub fn get_implementation(
     &self,
     parent_name: Option<XsdName>,
-    context: &mut XsdContext,
+    context: &XsdContext,
+  ) -> Result<XsdImpl, XsdError> {
+    self.implementation(parent_name, context, XsdType::Sequence)
+  }
+
+  /// The implementation of the particles, its struct named as a `compositor`: an `all` is read
+  /// like a sequence once its children are ordered, see `All`.
+  pub(crate) fn implementation(
+    &self,
+    parent_name: Option<XsdName>,
+    context: &XsdContext,
+    compositor: XsdType,
   ) -> Result<XsdImpl, XsdError> {
+    let max_occurences = self.max_occurences.limited(context.max_occurs_limit);
+    let role = max_occurences.role(self.min_occurences);
+    let multiple = role == Some(ITEM);
+
+    // When the only content of a repeated sequence is an optional choice, an absent choice
+    // just ends the repetitions. Parsing the choice as required avoids `Vec<Option<_>>`.
+    let required_choice = multiple
+      && matches!(
+        self.children.as_slice(),
+        [SequenceOptions::Choice(Choice {
+          min_occurences: 0,
+          max_occurences: MaxOccurences::Number { value: 1 },
+          ..
+        })]
+      );
+
     let mut generated_impls = vec![];
 
-    for child in &self.children {
+    // The prohibited particles are left out.
+    for child in self.children.iter().filter(|child| !child.is_prohibited()) {
       match child {
         SequenceOptions::Element(element) => {
           generated_impls.push(element.get_implementation(context)?)
@@ -75,6 +121,13 @@ impl Sequence {
         SequenceOptions::Group(group) => {
           generated_impls.push(group.get_implementation(None, context)?)
         }
+        SequenceOptions::Choice(choice) if required_choice => {
+          let choice = Choice {
+            min_occurences: 1,
+            ..choice.clone()
+          };
+          generated_impls.push(choice.get_implementation(None, context)?)
+        }
         SequenceOptions::Choice(choice) => {
           generated_impls.push(choice.get_implementation(None, context)?)
         }
@@ -91,64 +144,61 @@ impl Sequence {
       XsdName {
         namespace: None,
         local_name: inferred_name,
-        ty: XsdType::Sequence,
+        ty: compositor,
       }
     };
-    xml_name.ty = XsdType::Sequence;
+    xml_name.ty = compositor;
+
+    // A repeated or optional sequence is wrapped, its struct is named after its role in the wrapper.
+    let struct_name = match role {
+      Some(role) => wrapped_name(&xml_name, role).to_struct_name(),
+      None => xml_name.to_struct_name(),
+    };
 
     let mut generated_impl = XsdImpl {
       name: xml_name.clone(),
       fieldname_hint: Some(xml_name.to_field_name()),
       element: XsdImplType::Struct(
-        Struct::new(Some(xml_name.clone()), &xml_name.to_struct_name())
+        Struct::new(Some(xml_name.clone()), &struct_name)
           .vis("pub")
           .derives(&["Clone", "Debug", "PartialEq"]),
       ),
       inner: vec![],
       implementation: vec![],
       flatten: parent_name.is_none(),
+      content: ContentKind::Empty,
+      attribute_groups: vec![],
     };
 
     for imp in generated_impls {
       generated_impl.merge(imp, MergeSettings::default());
     }
 
-    let multiple = match &self.max_occurences {
-      MaxOccurences::Unbounded => true,
-      MaxOccurences::Number { value } => *value > 1,
-    } || self.min_occurences > 1;
-
-    let option = match &self.max_occurences {
-      MaxOccurences::Unbounded => false,
-      MaxOccurences::Number { value } => *value == 1 && self.min_occurences == 0,
-    };
-
-    let mut generated_impl = general_xsdgen(generated_impl);
-
-    let mut generated_impl = if multiple {
-      let old_name = generated_impl.name.clone();
-      generated_impl.name.local_name = format!("inner-{}", old_name.local_name);
-      XsdImpl {
-        name: old_name,
-        fieldname_hint: Some(generated_impl.fieldname_hint.clone().unwrap()),
-        element: XsdImplType::Type(generated_impl.element.get_type().wrap("Vec")),
-        flatten: generated_impl.flatten,
-        inner: vec![generated_impl],
-        implementation: vec![],
+    let generated_impl = general_xsdgen(generated_impl, context);
+
+    // The repetitions are read from the content of the parent, not from a child named after it.
+    let mut generated_impl = match role {
+      Some(ITEM) => {
+        // Every repetition may hold no choice at all.
+        let ty = max_occurences.wrap_repeated(
+          generated_impl.element.get_type(),
+          if required_choice {
+            0
+          } else {
+            self.min_occurences
+          },
+        );
+        generated_impl.wrap(ty, ITEM, true)
       }
-    } else if option {
-      let old_name = generated_impl.name.clone();
-      generated_impl.name.local_name = format!("inner-{}", old_name.local_name);
-      XsdImpl {
-        name: old_name,
-        fieldname_hint: Some(generated_impl.fieldname_hint.clone().unwrap()),
-        element: XsdImplType::Type(generated_impl.element.get_type().wrap("Option")),
-        flatten: generated_impl.flatten,
-        inner: vec![generated_impl],
-        implementation: vec![],
+      Some(role) => {
+        let ty = generated_impl
+          .element
+          .get_type()
+          .wrap("Option")
+          .occurs(0, Some(1));
+        generated_impl.wrap(ty, role, true)
       }
-    } else {
-      generated_impl
+      None => generated_impl,
     };
 
     generated_impl.name.ty = XsdType::Sequence;
@@ -156,3 +206,41 @@ impl Sequence {
     Ok(generated_impl)
   }
 }
+
+#[test]
+fn optional_choice_in_repeated_sequence_is_not_an_option() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="measure">
+        <xs:sequence>
+          <xs:element name="number" type="xs:int"/>
+          <xs:sequence maxOccurs="unbounded">
+            <xs:choice minOccurs="0">
+              <xs:element name="note" type="xs:string"/>
+              <xs:element name="rest" type="xs:string"/>
+            </xs:choice>
+          </xs:sequence>
+        </xs:sequence>
+      </xs:complexType>
+      <xs:complexType name="part">
+        <xs:sequence>
+          <xs:choice minOccurs="0">
+            <xs:element name="note" type="xs:string"/>
+            <xs:element name="rest" type="xs:string"/>
+          </xs:choice>
+        </xs:sequence>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap();
+
+  assert!(output.contains("pub noterest: Vec<measure::NoterestItem>"));
+  assert!(output.contains("pub noterest: noterest_item::Noterest,"));
+  assert!(output.contains("pub noterest: Option<part::NoterestValue>"));
+}
@@ -0,0 +1,132 @@
+use xsd_codegen::{escape_str_literal, Field, Fields};
+
+use super::xsd_context::{XsdImpl, XsdImplType};
+
+/// Derive `serde::Serialize` and `serde::Deserialize` on `imp` and its inner types, renaming the
+/// fields and variants after their XML names.
+pub(crate) fn add_serde_attributes(imp: &mut XsdImpl) {
+  imp.add_derives(&[
+    "serde::Serialize".to_string(),
+    "serde::Deserialize".to_string(),
+  ]);
+  rename(imp);
+}
+
+/// The attribute renaming a field or variant to `xml_name`.
+pub(crate) fn rename_attribute(xml_name: &str) -> String {
+  format!("#[serde(rename = {})]", escape_str_literal(xml_name))
+}
+
+fn rename(imp: &mut XsdImpl) {
+  match &mut imp.element {
+    XsdImplType::Struct(r#struct) => annotate_fields(&mut r#struct.fields),
+    XsdImplType::Enum(r#enum) => {
+      for variant in &mut r#enum.variants {
+        if let Some(xml_name) = variant.xml_name.as_ref().map(|name| &name.local_name) {
+          if *xml_name != variant.name {
+            push_unique(&mut variant.attributes, rename_attribute(xml_name));
+          }
+        }
+        annotate_fields(&mut variant.fields);
+      }
+    }
+    XsdImplType::Type(_) | XsdImplType::TypeAlias(_) => {}
+  }
+
+  for inner in &mut imp.inner {
+    rename(inner);
+  }
+}
+
+/// Rename the named fields read from an element or attribute after it, unless another field has the
+/// same XML name (e.g. an attribute and an element). Optional fields are not serialized when empty and
+/// repeated ones default to no values.
+fn annotate_fields(fields: &mut Fields) {
+  let Fields::Named(fields) = fields else {
+    return;
+  };
+
+  let xml_names: Vec<Option<String>> = fields.iter().map(xml_name).collect();
+  for (field, name) in fields.iter_mut().zip(&xml_names) {
+    let unique = |name: &String| xml_names.iter().flatten().filter(|n| *n == name).count() == 1;
+    if let Some(name) = name
+      .as_ref()
+      .filter(|name| unique(name) && *name != field.name())
+    {
+      push_unique(&mut field.annotation, rename_attribute(name));
+    }
+
+    match field.ty.name.as_str() {
+      "Option" => push_unique(
+        &mut field.annotation,
+        "#[serde(skip_serializing_if = \"Option::is_none\")]".to_string(),
+      ),
+      "Vec" => push_unique(&mut field.annotation, "#[serde(default)]".to_string()),
+      "RestrictedVec" if field.ty.const_generics.first() == Some(&0) => {
+        push_unique(&mut field.annotation, "#[serde(default)]".to_string())
+      }
+      _ => {}
+    }
+  }
+}
+
+/// The XML name a field is renamed to, flattened fields keep holding their content.
+pub(super) fn xml_name(field: &Field) -> Option<String> {
+  field
+    .xml_name
+    .as_ref()
+    .filter(|_| !field.flatten)
+    .map(|name| name.local_name.clone())
+}
+
+fn push_unique(attributes: &mut Vec<String>, attribute: String) {
+  if !attributes.contains(&attribute) {
+    attributes.push(attribute);
+  }
+}
+
+#[test]
+fn fields_are_renamed_after_unique_xml_names() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:simpleType name="line-type">
+        <xs:restriction base="xs:string">
+          <xs:enumeration value="solid" />
+          <xs:enumeration value="dashed &quot;long&quot;" />
+        </xs:restriction>
+      </xs:simpleType>
+      <xs:complexType name="bracket">
+        <xs:sequence>
+          <xs:element name="type" type="xs:string" />
+          <xs:element name="end-length" type="xs:int" minOccurs="0" maxOccurs="3" />
+        </xs:sequence>
+        <xs:attribute name="type" type="xs:string" />
+        <xs:attribute name="line-type" type="line-type" />
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap();
+  assert!(!output.contains("serde"));
+
+  let options = crate::GeneratorOptions {
+    serde: true,
+    ..Default::default()
+  };
+  let output = xsd.generate_with(&options).unwrap();
+  assert!(output.contains("serde::Serialize, serde::Deserialize)]\npub struct Bracket {"));
+  // The element and the attribute keep their Rust names.
+  assert_eq!(output.matches(r#"#[serde(rename = "type")]"#).count(), 0);
+  assert!(output.contains(
+    "    #[serde(rename = \"end-length\")]\n    #[serde(default)]\n    pub end_length: RestrictedVec<i32, 0, 3>,"
+  ));
+  assert!(output.contains(
+    "    #[serde(rename = \"line-type\")]\n    #[serde(skip_serializing_if = \"Option::is_none\")]\n"
+  ));
+  assert!(output.contains("    #[serde(rename = \"dashed \\\"long\\\"\")]\n    DashedLong,"));
+}
@@ -1,5 +1,5 @@
 use xsd_codegen::XMLElement;
-use xsd_types::{XsdIoError, XsdName, XsdParseError, XsdType};
+use xsd_types::{SchemaError, XsdName, XsdParseError, XsdType};
 
 use crate::xsd::{extension::Extension, XsdContext};
 
@@ -16,7 +16,7 @@ pub struct SimpleContent {
 }
 
 impl SimpleContent {
-  pub fn parse(mut element: XMLElement) -> Result<Self, XsdIoError> {
+  pub fn parse(mut element: XMLElement) -> Result<Self, SchemaError> {
     element.check_name("simpleContent")?;
 
     let restriction = element.try_get_child_with("restriction", |child| {
@@ -24,11 +24,11 @@ impl SimpleContent {
     })?;
     let extension = element.try_get_child_with("extension", Extension::parse)?;
 
-    if restriction.is_some() && extension.is_some() {
+    if restriction.is_some() == extension.is_some() {
       return Err(
         XsdParseError {
           node_name: element.node_name(),
-          msg: "extension and restriction cannot both present".to_string(),
+          msg: "One of extension and restriction is required, they cannot both present".to_string(),
         }
         .into(),
       );
@@ -48,13 +48,14 @@ impl SimpleContent {
   pub fn get_implementation(
     &self,
     parent_name: XsdName,
-    context: &mut XsdContext,
+    context: &XsdContext,
   ) -> Result<XsdImpl, XsdError> {
     let mut gen = match (&self.restriction, &self.extension) {
       (None, Some(extension)) => extension.get_implementation(parent_name, context),
       (Some(restriction), None) => {
         restriction.get_implementation(parent_name, RestrictionParentType::SimpleContent, context)
       }
+      // Exactly one of them is present, checked when parsing.
       _ => unreachable!("Xsd is invalid!"),
     }?;
 
@@ -1,5 +1,5 @@
 use xsd_codegen::XMLElement;
-use xsd_types::{XsdIoError, XsdName, XsdParseError, XsdType};
+use xsd_types::{SchemaError, XsdName, XsdParseError, XsdType};
 
 use crate::xsd::{list::List, restriction::Restriction, union::Union, XsdContext};
 
@@ -17,7 +17,7 @@ pub struct SimpleType {
 }
 
 impl SimpleType {
-  pub fn parse(mut element: XMLElement, parent_is_schema: bool) -> Result<Self, XsdIoError> {
+  pub fn parse(mut element: XMLElement, parent_is_schema: bool) -> Result<Self, SchemaError> {
     element.check_name("simpleType")?;
 
     let restriction = element.try_get_child_with("restriction", |child| {
@@ -26,14 +26,26 @@ impl SimpleType {
     let list = element.try_get_child_with("list", List::parse)?;
     let union = element.try_get_child_with("union", Union::parse)?;
 
-    if restriction.is_some() as u8 + list.is_some() as u8 + union.is_some() as u8 > 1 {
-      return Err(
-        XsdParseError {
-          node_name: element.node_name(),
-          msg: "Two of (extension | restriction | union) cannot be present".to_string(),
-        }
-        .into(),
-      );
+    match restriction.is_some() as u8 + list.is_some() as u8 + union.is_some() as u8 {
+      0 => {
+        return Err(
+          XsdParseError {
+            node_name: element.node_name(),
+            msg: "One of (restriction | list | union) is required".to_string(),
+          }
+          .into(),
+        )
+      }
+      1 => {}
+      _ => {
+        return Err(
+          XsdParseError {
+            node_name: element.node_name(),
+            msg: "Two of (extension | restriction | union) cannot be present".to_string(),
+          }
+          .into(),
+        )
+      }
     }
 
     let name = element
@@ -76,7 +88,7 @@ impl SimpleType {
   pub fn get_implementation(
     &self,
     parent_name: Option<XsdName>,
-    context: &mut XsdContext,
+    context: &XsdContext,
   ) -> Result<XsdImpl, XsdError> {
     let name = self.name.clone().unwrap_or_else(|| {
       let mut parent = parent_name.unwrap();
@@ -84,12 +96,23 @@ impl SimpleType {
       parent
     });
 
+    let documented = self
+      .annotation
+      .as_ref()
+      .is_some_and(|annotation| !annotation.get_doc().is_empty());
+
     let mut generated_impl = match (&self.list, &self.union, &self.restriction) {
+      (None, None, Some(restriction))
+        if context.flatten_wrappers && !documented && restriction.is_plain() =>
+      {
+        restriction.get_alias_implementation(name.clone(), context)
+      }
       (None, None, Some(restriction)) => {
         restriction.get_implementation(name.clone(), RestrictionParentType::SimpleType, context)
       }
       (None, Some(union), None) => union.get_implementation(name.clone(), context),
       (Some(list), None, None) => list.get_implementation(name.clone(), context),
+      // Exactly one of them is present, checked when parsing.
       _ => unreachable!("Invalid Xsd!"),
     }?;
 
@@ -0,0 +1,99 @@
+use xml::reader::{EventReader, XmlEvent};
+
+const XML_SCHEMA: &str = "http://www.w3.org/2001/XMLSchema";
+/// Number of characters of an HTML page quoted in the error.
+const HTML_EXCERPT: usize = 200;
+
+/// Check that the root element of `content` is a `schema` element, reading the document only up
+/// to it. Returns what the document is instead. Documents that are not well formed before their
+/// root element are left to the parser to report, unless they look like an HTML page.
+pub(crate) fn check_schema_root(content: &[u8]) -> Result<(), String> {
+  if content.iter().all(u8::is_ascii_whitespace) {
+    return Err("the document is empty".to_string());
+  }
+
+  for event in EventReader::new(content) {
+    match event {
+      Ok(XmlEvent::StartElement { name, .. }) => {
+        return if name.local_name.eq_ignore_ascii_case("html") {
+          Err(html_page(content))
+        } else if name.namespace.as_deref() == Some(XML_SCHEMA) && name.local_name == "schema" {
+          Ok(())
+        } else {
+          let namespace = name
+            .namespace
+            .map_or("no namespace".to_string(), |namespace| {
+              format!("the namespace {namespace}")
+            });
+          Err(format!(
+            "the root element is {} in {namespace}, expected schema in the namespace {XML_SCHEMA}",
+            name.local_name
+          ))
+        };
+      }
+      Ok(_) => {}
+      Err(_) if looks_like_html(content) => return Err(html_page(content)),
+      Err(_) => return Ok(()),
+    }
+  }
+
+  Ok(())
+}
+
+fn looks_like_html(content: &[u8]) -> bool {
+  let start = String::from_utf8_lossy(&content[..content.len().min(1024)]).to_lowercase();
+  start.contains("<!doctype html") || start.contains("<html")
+}
+
+fn html_page(content: &[u8]) -> String {
+  let text = String::from_utf8_lossy(content);
+  let excerpt: String = text
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ")
+    .chars()
+    .take(HTML_EXCERPT)
+    .collect();
+  format!(
+    "the document is an HTML page, the server probably returned an error page instead of the schema: {excerpt}"
+  )
+}
+
+#[test]
+fn documents_other_than_schemas_are_described() {
+  assert_eq!(
+    check_schema_root(
+      br#"<?xml version="1.0"?><xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"/>"#
+    ),
+    Ok(())
+  );
+  // Left to the parser.
+  assert_eq!(check_schema_root(b"<schema"), Ok(()));
+
+  assert_eq!(
+    check_schema_root(b" \n").unwrap_err(),
+    "the document is empty"
+  );
+  assert_eq!(
+    check_schema_root(br#"<score xmlns="urn:scores"><title/></score>"#).unwrap_err(),
+    "the root element is score in the namespace urn:scores, expected schema in the namespace http://www.w3.org/2001/XMLSchema"
+  );
+  assert_eq!(
+    check_schema_root(b"<schema/>").unwrap_err(),
+    "the root element is schema in no namespace, expected schema in the namespace http://www.w3.org/2001/XMLSchema"
+  );
+
+  let page = "<!DOCTYPE html>\n<HTML><head><title>404 Not Found</title><meta charset=utf-8></head>\n<body><h1>Not Found</h1>".to_string()
+    + &"<p>The requested URL was not found on this server.</p>".repeat(10)
+    + "</body></HTML>";
+  let error = check_schema_root(page.as_bytes()).unwrap_err();
+  assert!(error.starts_with("the document is an HTML page"), "{error}");
+  assert!(error.contains(": <!DOCTYPE html> <HTML><head><title>404 Not Found</title>"));
+  assert!(error.len() < 400, "{error}");
+  // Not well formed before the root element.
+  let error = check_schema_root(b"<!doctype html><p>Bad Gateway").unwrap_err();
+  assert!(
+    error.ends_with("schema: <!doctype html><p>Bad Gateway"),
+    "{error}"
+  );
+}
@@ -0,0 +1,302 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use xsd_codegen::{ContentKind, Fields, Type, TypeAlias};
+use xsd_types::{to_module_name, SchemaError, XsdGenError, XsdName, XsdType};
+
+use super::xsd_context::{XsdContext, XsdImpl, XsdImplType};
+use super::{regenerate_xsdgen, StubKind, Warning, XsdError};
+
+/// Prefix of the placeholder types standing for the skipped components until they are removed.
+/// Generated names never start with an underscore.
+const SKIPPED_PREFIX: &str = "__Skipped";
+
+/// The implementation generated for the stubbed component `name` instead of its definition.
+pub(crate) fn stub_impl(
+  name: &XsdName,
+  kind: StubKind,
+  context: &XsdContext,
+) -> Result<XsdImpl, XsdError> {
+  let (element, content) = match (kind, name.ty) {
+    (StubKind::Skip, _) => (
+      XsdImplType::Type(Type::new(
+        Some(name.clone()),
+        &format!("{SKIPPED_PREFIX}{}", name.to_struct_name()),
+      )),
+      ContentKind::Empty,
+    ),
+    (StubKind::Opaque, XsdType::Group | XsdType::AttributeGroup) => {
+      return Err(XsdError::SchemaError(SchemaError::Unsupported(
+        XsdGenError {
+          node_name: name.to_string(),
+          ty: name.ty,
+          msg: "Groups are merged into other types and can't be opaque, skip them instead."
+            .to_string(),
+        },
+      )))
+    }
+    (StubKind::Opaque, ty) => {
+      let (value, content) = if ty == XsdType::Attribute {
+        (context.string_storage.to_type(), ContentKind::Simple)
+      } else {
+        (Type::new(None, "RawXml"), ContentKind::Complex)
+      };
+
+      (
+        XsdImplType::TypeAlias(TypeAlias {
+          doc: None,
+          vis: Some("pub".to_string()),
+          alias: Type::new(Some(name.clone()), &name.to_struct_name()),
+          value,
+        }),
+        content,
+      )
+    }
+  };
+
+  Ok(XsdImpl {
+    name: name.clone(),
+    fieldname_hint: Some(name.to_field_name()),
+    element,
+    inner: vec![],
+    implementation: vec![],
+    flatten: false,
+    content,
+    attribute_groups: vec![],
+  })
+}
+
+/// Remove the skipped components from `context`, along with the components standing for them
+/// (e.g. an element of a skipped type) and the fields and variants referencing them. Returns a
+/// warning for every skipped component listing what was removed.
+pub(crate) fn remove_skipped(context: &mut XsdContext) -> Vec<Warning> {
+  // The types standing for a skipped component, by the component.
+  let mut origins = BTreeMap::new();
+  let mut removed: BTreeMap<XsdName, Vec<String>> = BTreeMap::new();
+  for (name, imp) in &context.structs {
+    if context.stub(name) == Some(StubKind::Skip) {
+      origins.insert(imp.element.get_type().name, name.clone());
+      removed.insert(name.clone(), vec![]);
+    }
+  }
+
+  loop {
+    let standing_for = context
+      .structs
+      .iter()
+      .filter(|(_, imp)| is_wrapper(imp))
+      .filter_map(|(name, imp)| Some((name.clone(), origin(imp, &origins)?)))
+      .collect::<Vec<_>>();
+    if standing_for.is_empty() {
+      break;
+    }
+
+    for (name, origin) in standing_for {
+      let imp = context.structs.remove(&name).unwrap();
+      if context.stub(&name) != Some(StubKind::Skip) {
+        removed
+          .get_mut(&origin)
+          .unwrap()
+          .push(format!("[{:?}] {name}", name.ty));
+      }
+      origins.entry(imp.element.get_type().name).or_insert(origin);
+    }
+  }
+
+  let mut structs = std::mem::take(&mut context.structs);
+  for imp in structs.values_mut() {
+    remove_references(imp, "", &origins, &mut removed, context);
+  }
+  context.structs = structs;
+
+  removed
+    .into_iter()
+    .map(|(name, removed)| Warning {
+      path: format!("/schema/{}[@name=\"{}\"]", tag(name.ty), name.local_name),
+      msg: if removed.is_empty() {
+        "skipped".to_string()
+      } else {
+        format!("skipped, removing {}", removed.join(", "))
+      },
+    })
+    .collect()
+}
+
+/// Whether `imp` is only another name for the type it holds, and goes away with it.
+fn is_wrapper(imp: &XsdImpl) -> bool {
+  match &imp.element {
+    XsdImplType::Type(_) | XsdImplType::TypeAlias(_) => true,
+    XsdImplType::Struct(r#struct) => matches!(r#struct.fields, Fields::Tuple(_)),
+    XsdImplType::Enum(_) => false,
+  }
+}
+
+/// The skipped component `imp` refers to, if any.
+fn origin(imp: &XsdImpl, origins: &BTreeMap<String, XsdName>) -> Option<XsdName> {
+  let mut references = BTreeSet::new();
+  imp.direct_references(&mut references);
+  references.iter().find_map(|name| lookup(name, origins))
+}
+
+fn lookup(name: &str, origins: &BTreeMap<String, XsdName>) -> Option<XsdName> {
+  origins
+    .get(name.rsplit("::").next().unwrap_or(name))
+    .cloned()
+}
+
+fn type_origin(ty: &Type, origins: &BTreeMap<String, XsdName>) -> Option<XsdName> {
+  lookup(&ty.name, origins)
+    .or_else(|| ty.generics.iter().find_map(|ty| type_origin(ty, origins)))
+    .or_else(|| ty.parse_as.as_ref().and_then(|ty| type_origin(ty, origins)))
+}
+
+fn fields_origin(fields: &Fields, origins: &BTreeMap<String, XsdName>) -> Option<XsdName> {
+  fields
+    .fields()
+    .iter()
+    .find_map(|f| type_origin(&f.ty, origins))
+}
+
+/// Remove the fields, variants and inner types of `imp` referencing a skipped component, and
+/// generate the parsing code of `imp` again when it lost some. `prefix` is the module path of
+/// `imp`, as in `XsdImpl::visit_types`.
+fn remove_references(
+  imp: &mut XsdImpl,
+  prefix: &str,
+  origins: &BTreeMap<String, XsdName>,
+  removed: &mut BTreeMap<XsdName, Vec<String>>,
+  context: &XsdContext,
+) {
+  let ty = imp.element.get_type();
+  let path = format!("{prefix}{}", ty.name);
+
+  let mut changed = false;
+  let mut remove = |origin: Option<XsdName>, item: &str| match origin {
+    Some(origin) => {
+      removed
+        .get_mut(&origin)
+        .unwrap()
+        .push(format!("{path}::{item}"));
+      changed = true;
+      false
+    }
+    None => true,
+  };
+
+  match &mut imp.element {
+    XsdImplType::Struct(r#struct) => {
+      if let Fields::Named(fields) = &mut r#struct.fields {
+        fields.retain(|field| remove(type_origin(&field.ty, origins), field.name()));
+      }
+    }
+    XsdImplType::Enum(r#enum) => {
+      r#enum
+        .variants
+        .retain(|variant| remove(fields_origin(&variant.fields, origins), &variant.name));
+    }
+    XsdImplType::Type(_) | XsdImplType::TypeAlias(_) => {}
+  }
+
+  if changed {
+    *imp = regenerate_xsdgen(imp.clone(), context);
+  }
+
+  imp
+    .inner
+    .retain(|inner| !is_wrapper(inner) || origin(inner, origins).is_none());
+
+  let module = format!("{prefix}{}::", to_module_name(&ty.name));
+  for inner in &mut imp.inner {
+    remove_references(inner, &module, origins, removed, context);
+  }
+}
+
+/// The name of the schema element declaring a component of type `ty`, e.g. `complexType`.
+pub(super) fn tag(ty: XsdType) -> String {
+  let name = format!("{ty:?}");
+  let mut chars = name.chars();
+  match chars.next() {
+    Some(first) => first.to_lowercase().chain(chars).collect(),
+    None => name,
+  }
+}
+
+#[test]
+fn stubbed_components_are_not_generated() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="identification">
+        <xs:sequence>
+          <xs:element name="creator" type="creator" />
+          <xs:element name="encoding">
+            <xs:complexType>
+              <xs:sequence>
+                <xs:element name="software" type="xs:string" />
+              </xs:sequence>
+            </xs:complexType>
+          </xs:element>
+          <xs:element name="rights" type="undefined" />
+        </xs:sequence>
+      </xs:complexType>
+      <xs:complexType name="creator">
+        <xs:attribute name="type" type="xs:string" />
+      </xs:complexType>
+      <xs:complexType name="figured-bass">
+        <xs:sequence>
+          <xs:element name="figure" type="xs:string" />
+        </xs:sequence>
+      </xs:complexType>
+      <xs:element name="identification" type="identification" />
+      <xs:element name="score">
+        <xs:complexType>
+          <xs:sequence>
+            <xs:element name="title" type="xs:string" />
+            <xs:element ref="identification" minOccurs="0" />
+            <xs:element name="figured-bass" type="figured-bass" maxOccurs="unbounded" />
+          </xs:sequence>
+        </xs:complexType>
+      </xs:element>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let options = crate::GeneratorOptions {
+    roots: vec!["score".to_string()],
+    stubs: [
+      (
+        XsdName::new("identification", XsdType::ComplexType),
+        StubKind::Skip,
+      ),
+      (
+        XsdName::new("figured-bass", XsdType::ComplexType),
+        StubKind::Opaque,
+      ),
+    ]
+    .into(),
+    ..Default::default()
+  };
+  let (output, report) = xsd.generate_with_report(&options).unwrap();
+
+  // The undefined type is only used by the skipped component, it is never resolved.
+  for name in [
+    "Identification",
+    "Creator",
+    "Encoding",
+    "software",
+    "rights",
+  ] {
+    assert!(!output.contains(name), "{name} in {output}");
+  }
+  assert!(output.contains("pub type FiguredBass = RawXml;"));
+  assert!(output.contains("pub figured_bass: RestrictedVec<FiguredBass, 1, 0>"));
+  assert!(output.contains("use xml_schema_parser::RawXml;"));
+
+  assert_eq!(
+    report.warnings,
+    [crate::Warning {
+      path: r#"/schema/complexType[@name="identification"]"#.to_string(),
+      msg: "skipped, removing [Element] identification, Score::identification".to_string(),
+    }]
+  );
+}
@@ -0,0 +1,102 @@
+use xsd_codegen::{ContentKind, Enum};
+use xsd_types::{to_struct_name, XsdName, XsdType};
+
+use super::{
+  general_xsdgen,
+  xsd_context::{MergeSettings, XsdContext, XsdImpl, XsdImplType},
+  XsdError,
+};
+
+/// The name of the enum of the substitution group of `head`.
+pub(crate) fn group_name(head: &XsdName) -> XsdName {
+  XsdName {
+    ty: XsdType::SubstitutionGroup,
+    ..head.clone()
+  }
+}
+
+/// The enum of the substitution group of `head`, with a variant per element substitutable for
+/// it, which is picked from the name of the next child.
+pub(crate) fn get_implementation(
+  head: &XsdName,
+  context: &XsdContext,
+) -> Result<XsdImpl, XsdError> {
+  let name = group_name(head);
+  let mut generated_impl = XsdImpl {
+    fieldname_hint: Some(head.to_field_name()),
+    name: name.clone(),
+    element: XsdImplType::Enum(
+      Enum::new(None, &to_struct_name(&format!("{}-group", head.local_name)))
+        .derives(&["Clone", "Debug", "PartialEq"])
+        .vis("pub"),
+    ),
+    inner: vec![],
+    implementation: vec![],
+    flatten: false,
+    content: ContentKind::Empty,
+    attribute_groups: vec![],
+  };
+
+  for element in context.substitution_groups.substitutes(head) {
+    let imp = context
+      .search(&element)
+      .ok_or_else(|| XsdError::XsdImplNotFound(element.clone()))?;
+
+    let variant = XsdImpl {
+      name: element.clone(),
+      fieldname_hint: Some(element.to_field_name()),
+      element: XsdImplType::Type(imp.element.get_type().xml_name(Some(element.clone()))),
+      inner: vec![],
+      implementation: vec![],
+      flatten: false,
+      content: imp.content,
+      attribute_groups: vec![],
+    };
+    generated_impl.merge(variant, MergeSettings::default());
+  }
+
+  Ok(general_xsdgen(generated_impl, context))
+}
+
+#[test]
+fn substitution_groups_dispatch_on_the_element_name() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:element name="instrument" type="xs:string" abstract="true" />
+      <xs:element name="piano" type="xs:string" substitutionGroup="instrument" />
+      <xs:element name="keyboard" substitutionGroup="instrument" />
+      <xs:element name="organ" substitutionGroup="keyboard">
+        <xs:complexType>
+          <xs:attribute name="stops" type="xs:int" />
+        </xs:complexType>
+      </xs:element>
+      <xs:complexType name="part">
+        <xs:sequence>
+          <xs:element ref="instrument" maxOccurs="unbounded" />
+          <xs:element ref="keyboard" minOccurs="0" />
+        </xs:sequence>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap();
+
+  // The abstract head isn't a variant, the members of a member are.
+  assert!(output.contains(
+    "pub enum InstrumentGroup {\n    Piano(String),\n    Keyboard(String),\n    Organ(Organ),\n}"
+  ));
+  assert!(output.contains("pub enum KeyboardGroup {\n    Keyboard(String),\n    Organ(Organ),\n}"));
+  assert!(output.contains("if element.next_child_is(\"organ\") {"));
+
+  // The references to a head read any element of its group.
+  assert!(output.contains("pub instrument: RestrictedVec<InstrumentGroup, 1, 0>,"));
+  assert!(output.contains("pub keyboard: Option<KeyboardGroup>,"));
+  assert!(output.contains(
+    "keyboard: <Option<KeyboardGroup> as XsdGen>::gen(element, gen_state.clone(), None)?,"
+  ));
+}
@@ -0,0 +1,74 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use xsd_types::XsdName;
+
+/// The components a document can use in place of another: the members of a substitution group
+/// in place of its head element, or the types derived from a complex type, named by the
+/// `xsi:type` of the element.
+#[derive(Clone, Debug, Default)]
+pub struct Substitutions {
+  /// The components declared as a substitute of each component, in document order.
+  substitutes: BTreeMap<XsdName, Vec<XsdName>>,
+  /// The abstract components, which only appear in documents through their substitutes.
+  abstract_components: BTreeSet<XsdName>,
+}
+
+impl Substitutions {
+  pub fn add_substitute(&mut self, substituted: XsdName, substitute: XsdName) {
+    let substitutes = self.substitutes.entry(substituted).or_default();
+    if !substitutes.contains(&substitute) {
+      substitutes.push(substitute);
+    }
+  }
+
+  pub fn add_abstract(&mut self, component: XsdName) {
+    self.abstract_components.insert(component);
+  }
+
+  pub fn extend(&mut self, other: Substitutions) {
+    for (substituted, substitutes) in other.substitutes {
+      for substitute in substitutes {
+        self.add_substitute(substituted.clone(), substitute);
+      }
+    }
+    self.abstract_components.extend(other.abstract_components);
+  }
+
+  /// Whether `component` has substitutes.
+  pub fn is_substituted(&self, component: &XsdName) -> bool {
+    self.substitutes.contains_key(component)
+  }
+
+  /// The components with substitutes.
+  pub fn substituted(&self) -> impl Iterator<Item = &XsdName> {
+    self.substitutes.keys()
+  }
+
+  /// The components a document can use in place of `component`: the component itself unless it
+  /// is abstract, then each of its substitutes followed by their own substitutes.
+  pub fn substitutes(&self, component: &XsdName) -> Vec<XsdName> {
+    let mut substitutes = vec![];
+    let mut visited = BTreeSet::new();
+    self.push_substitutes(component, &mut substitutes, &mut visited);
+    substitutes
+  }
+
+  fn push_substitutes(
+    &self,
+    component: &XsdName,
+    substitutes: &mut Vec<XsdName>,
+    visited: &mut BTreeSet<XsdName>,
+  ) {
+    // The substitutions of an invalid schema can be circular.
+    if !visited.insert(component.clone()) {
+      return;
+    }
+
+    if !self.abstract_components.contains(component) {
+      substitutes.push(component.clone());
+    }
+    for substitute in self.substitutes.get(component).into_iter().flatten() {
+      self.push_substitutes(substitute, substitutes, visited);
+    }
+  }
+}
@@ -1,5 +1,5 @@
-use xsd_codegen::{Enum, XMLElement};
-use xsd_types::{XsdIoError, XsdName, XsdType};
+use xsd_codegen::{ContentKind, Enum, XMLElement};
+use xsd_types::{SchemaError, XsdName, XsdType};
 
 use super::{
   general_xsdgen,
@@ -15,7 +15,7 @@ pub struct Union {
 }
 
 impl Union {
-  pub fn parse(mut element: XMLElement) -> Result<Self, XsdIoError> {
+  pub fn parse(mut element: XMLElement) -> Result<Self, SchemaError> {
     element.check_name("union")?;
 
     let member_types: Option<String> = element.try_get_attribute("memberTypes")?;
@@ -23,7 +23,7 @@ impl Union {
 
     if let Some(member_types) = member_types {
       for member in member_types.split_whitespace() {
-        members.push(element.new_name(member, XsdType::SimpleType));
+        members.push(element.new_qname(member, XsdType::SimpleType));
       }
     }
 
@@ -42,7 +42,7 @@ impl Union {
   pub fn get_implementation(
     &self,
     parent_name: XsdName,
-    context: &mut XsdContext,
+    context: &XsdContext,
   ) -> Result<XsdImpl, XsdError> {
     let mut xml_name = parent_name.clone();
     xml_name.ty = XsdType::Union;
@@ -58,6 +58,8 @@ impl Union {
       implementation: vec![],
       inner: vec![],
       flatten: false,
+      content: ContentKind::Simple,
+      attribute_groups: vec![],
     };
 
     for member in &self.member_types {
@@ -75,7 +77,7 @@ impl Union {
       );
     }
 
-    Ok(general_xsdgen(generated_impl))
+    Ok(general_xsdgen(generated_impl, context))
   }
 }
 
@@ -83,7 +85,7 @@ impl Union {
 //   pub fn get_implementation(
 //     &self,
 //     mut parent_name: XsdName,
-//     context: &mut XsdContext,
+//     context: &XsdContext,
 //   ) -> Result<XsdImpl, XsdError> {
 //     let mut generated_enum = Enum::new(&parent_name.to_struct_name())
 //       .vis("pub")
@@ -0,0 +1,73 @@
+use xsd_codegen::{Field, Fields, Type};
+use xsd_types::XsdType;
+
+use super::{
+  regenerate_xsdgen,
+  xsd_context::{XsdContext, XsdImpl, XsdImplType},
+};
+
+/// Name of the field holding the unknown content.
+const FIELD_NAME: &str = "unknown_content";
+
+/// Whether `field` is the one added by `add_unknown_content`, the type holding it claims the
+/// element it reads first, see `XMLElement::claim_unknown`.
+pub(crate) fn is_unknown_content(field: &Field) -> bool {
+  field.name() == FIELD_NAME && field.ty.name == "UnknownContent" && field.value.is_some()
+}
+
+/// Add the `unknown_content` field to the struct of `imp` and of its inner types. Returns whether
+/// one was added.
+fn add_field(imp: &mut XsdImpl, context: &XsdContext) -> bool {
+  let mut added = false;
+  for inner in &mut imp.inner {
+    added |= add_field(inner, context);
+  }
+
+  let XsdImplType::Struct(r#struct) = &mut imp.element else {
+    return added;
+  };
+  if !matches!(r#struct.fields, Fields::Named(_))
+    || r#struct
+      .fields
+      .fields()
+      .iter()
+      .any(|field| field.name() == FIELD_NAME)
+  {
+    return added;
+  }
+
+  // Read once the other fields are, by the type which claimed the element.
+  let mut field = Field::new(
+    None,
+    FIELD_NAME,
+    Type::new(None, "UnknownContent"),
+    false,
+    false,
+  )
+  .vis("pub")
+  .value("if keep_unknown { element.take_unknown() } else { UnknownContent::default() }");
+  field.doc(vec![
+    "The attributes and child elements the type doesn't know, written back where they were.",
+  ]);
+  r#struct.push_field(field);
+  *imp = regenerate_xsdgen(imp.clone(), context);
+  true
+}
+
+/// Add an `unknown_content` field keeping what the document holds that the type doesn't read to
+/// the structs reading an element, inner types included, see `GeneratorOptions::unknown_content`.
+/// The struct claiming the element keeps its unknown content, the ones read along with it in the
+/// same element, e.g. a sequence or the base of an extension, hold none. The attribute groups are
+/// left as is, they never read an element of their own.
+pub(crate) fn add_unknown_content(context: &mut XsdContext) {
+  let updated: Vec<_> = context
+    .structs
+    .iter()
+    .filter(|(name, _)| name.ty != XsdType::AttributeGroup)
+    .filter_map(|(name, imp)| {
+      let mut imp = imp.clone();
+      add_field(&mut imp, context).then(|| (name.clone(), imp))
+    })
+    .collect();
+  context.structs.extend(updated);
+}
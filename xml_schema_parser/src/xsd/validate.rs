@@ -0,0 +1,756 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use xsd_types::XsdName;
+
+use super::{
+  attribute::{Attribute, Required},
+  attribute_group::AttributeGroup,
+  choice::{Choice, ChoiceOptions},
+  complex_type::ComplexType,
+  element::Element,
+  group::Group,
+  schema::{Schema, SchemaOptions},
+  sequence::{Sequence, SequenceOptions},
+  simple_type::SimpleType,
+  LoadOptions, Xsd, XsdError,
+};
+
+const XML_SCHEMA: &str = "http://www.w3.org/2001/XMLSchema";
+
+/// Attributes of the `xsi` namespace, accepted on every element. The reader drops the prefix of
+/// these attributes, so they are only recognized by their local name when not declared.
+const INSTANCE_ATTRIBUTES: &[&str] =
+  &["type", "nil", "schemaLocation", "noNamespaceSchemaLocation"];
+
+/// The local name of the attribute `name` of an instance document, which keeps the prefix of the
+/// attributes of a namespace other than the XML and XSI ones.
+fn local_name(name: &str) -> &str {
+  name
+    .rsplit_once(':')
+    .map_or(name, |(_, local_name)| local_name)
+}
+
+/// A problem found in an instance document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Invalid {
+  /// Location of the problem, e.g. `/score/note[2]/@id`.
+  pub path: String,
+  pub msg: String,
+}
+
+/// The type of a text value, an attribute or an element with simple content.
+#[derive(Clone, Copy)]
+enum SimpleRef<'a> {
+  Named(&'a XsdName),
+  Inline(&'a SimpleType),
+}
+
+/// What an element with a complex type may hold, ignoring the order and number of children.
+#[derive(Default)]
+struct Content<'a> {
+  children: BTreeMap<&'a str, &'a Element>,
+  required_children: BTreeSet<&'a str>,
+  attributes: BTreeMap<&'a str, (bool, Option<SimpleRef<'a>>)>,
+  text: Option<SimpleRef<'a>>,
+}
+
+/// Checks instance documents against a schema and the schemas it imports.
+///
+/// Only the names of the children and attributes, the presence of the required ones and the
+/// values of simple types (enumerations and numeric builtins) are checked, not the order or
+/// number of occurrences of the children.
+pub(crate) struct Validator {
+  schemas: Vec<Schema>,
+}
+
+impl Validator {
+  pub(crate) fn new(schema: &Schema, options: &LoadOptions) -> Result<Self, XsdError> {
+    let mut validator = Self {
+      schemas: vec![schema.clone()],
+    };
+    // The options loading the imports of each schema, which are read from its directory.
+    let mut load_options = vec![options.clone()];
+
+    let mut loaded = BTreeSet::new();
+    let mut index = 0;
+    while index < validator.schemas.len() {
+      let locations = validator.schemas[index]
+        .children
+        .iter()
+        .filter_map(|child| match child {
+          SchemaOptions::Import(import) => import.schema_location.clone(),
+          _ => None,
+        })
+        .collect::<Vec<_>>();
+
+      for location in locations {
+        let options = &load_options[index];
+        if loaded.insert(options.document_key(&location)) {
+          let xsd = Xsd::new_from_file_with(&location, options)?;
+          load_options.push(xsd.context.load_options);
+          validator.schemas.push(xsd.schema);
+        }
+      }
+      index += 1;
+    }
+
+    Ok(validator)
+  }
+
+  pub(crate) fn validate(&self, instance: &xmltree::Element) -> Vec<Invalid> {
+    let mut invalid = vec![];
+    let path = format!("/{}", instance.name);
+
+    let root = self.components().find_map(|child| match child {
+      SchemaOptions::Element(element)
+        if element.name.as_ref().map(|name| name.local_name.as_str())
+          == Some(instance.name.as_str()) =>
+      {
+        Some(element)
+      }
+      _ => None,
+    });
+
+    match root {
+      Some(root) => self.check_element(root, instance, &path, &mut invalid),
+      None => invalid.push(Invalid {
+        path,
+        msg: format!(
+          "root element `{}` is not declared at the top level of the schema",
+          instance.name
+        ),
+      }),
+    }
+
+    invalid
+  }
+
+  fn components(&self) -> impl Iterator<Item = &SchemaOptions> {
+    self.schemas.iter().flat_map(|schema| &schema.children)
+  }
+
+  fn find<T>(&self, f: impl Fn(&SchemaOptions) -> Option<&T>) -> Option<&T> {
+    self.components().find_map(f)
+  }
+
+  fn matches(declared: &Option<XsdName>, name: &XsdName) -> bool {
+    declared.as_ref().is_some_and(|declared| {
+      declared.namespace == name.namespace && declared.local_name == name.local_name
+    })
+  }
+
+  fn element(&self, name: &XsdName) -> Option<&Element> {
+    self.find(|child| match child {
+      SchemaOptions::Element(element) if Self::matches(&element.name, name) => Some(element),
+      _ => None,
+    })
+  }
+
+  fn complex_type(&self, name: &XsdName) -> Option<&ComplexType> {
+    self.find(|child| match child {
+      SchemaOptions::ComplexType(ty) if Self::matches(&ty.name, name) => Some(ty),
+      _ => None,
+    })
+  }
+
+  fn simple_type(&self, name: &XsdName) -> Option<&SimpleType> {
+    self.find(|child| match child {
+      SchemaOptions::SimpleType(ty) if Self::matches(&ty.name, name) => Some(ty),
+      _ => None,
+    })
+  }
+
+  fn group(&self, name: &XsdName) -> Option<&Group> {
+    self.find(|child| match child {
+      SchemaOptions::Group(group) if Self::matches(&group.name, name) => Some(group),
+      _ => None,
+    })
+  }
+
+  fn attribute_group(&self, name: &XsdName) -> Option<&AttributeGroup> {
+    self.find(|child| match child {
+      SchemaOptions::AttributeGroup(group) if Self::matches(&group.name, name) => Some(group),
+      _ => None,
+    })
+  }
+
+  fn attribute(&self, name: &XsdName) -> Option<&Attribute> {
+    self.find(|child| match child {
+      SchemaOptions::Attribute(attribute) if Self::matches(&attribute.name, name) => {
+        Some(attribute)
+      }
+      _ => None,
+    })
+  }
+
+  fn check_element(
+    &self,
+    declaration: &Element,
+    node: &xmltree::Element,
+    path: &str,
+    invalid: &mut Vec<Invalid>,
+  ) {
+    let declaration = match &declaration.refers {
+      Some(refers) => match self.element(refers) {
+        Some(element) => element,
+        None => return,
+      },
+      None => declaration,
+    };
+
+    let mut report = |path: String, msg: String| invalid.push(Invalid { path, msg });
+
+    let complex_type = match (&declaration.complex_type, &declaration.kind) {
+      (Some(complex_type), _) => Some(complex_type),
+      (None, Some(kind)) => self.complex_type(kind),
+      (None, None) => None,
+    };
+    let simple_type = match (&declaration.simple_type, &declaration.kind) {
+      (Some(simple_type), _) => Some(SimpleRef::Inline(simple_type)),
+      (None, Some(kind)) if complex_type.is_none() => Some(SimpleRef::Named(kind)),
+      _ => None,
+    };
+
+    let content = match (complex_type, simple_type) {
+      (Some(complex_type), _) => {
+        let mut content = Content::default();
+        self.complex_content(complex_type, true, &mut content, &mut BTreeSet::new());
+        content
+      }
+      (None, Some(text)) => Content {
+        text: Some(text),
+        ..Default::default()
+      },
+      // Without a type, the element can hold anything.
+      (None, None) => return,
+    };
+
+    let mut attributes = node.attributes.iter().collect::<Vec<_>>();
+    attributes.sort();
+    for (name, value) in attributes {
+      match content.attributes.get(local_name(name)) {
+        Some((_, Some(ty))) => {
+          if let Err(msg) = self.check_value(*ty, value) {
+            report(format!("{path}/@{name}"), msg);
+          }
+        }
+        Some((_, None)) => {}
+        None if INSTANCE_ATTRIBUTES.contains(&name.as_str()) => {}
+        None => report(path.to_string(), format!("unexpected attribute `{name}`")),
+      }
+    }
+    for (name, (required, _)) in &content.attributes {
+      if *required && !node.attributes.keys().any(|key| local_name(key) == *name) {
+        report(
+          path.to_string(),
+          format!("missing required attribute `{name}`"),
+        );
+      }
+    }
+
+    let children = node
+      .children
+      .iter()
+      .filter_map(|child| child.as_element())
+      .collect::<Vec<_>>();
+    let mut seen = BTreeMap::new();
+    for child in &children {
+      let index = seen.entry(child.name.as_str()).or_insert(0);
+      *index += 1;
+      let child_path = if children.iter().filter(|c| c.name == child.name).count() > 1 {
+        format!("{path}/{}[{index}]", child.name)
+      } else {
+        format!("{path}/{}", child.name)
+      };
+
+      match content.children.get(child.name.as_str()) {
+        Some(declaration) => self.check_element(declaration, child, &child_path, invalid),
+        None => match self.substitute(&content, &child.name) {
+          Some((head, member)) => {
+            seen.entry(head).or_insert(0);
+            self.check_element(member, child, &child_path, invalid);
+          }
+          None => invalid.push(Invalid {
+            path: path.to_string(),
+            msg: format!("unexpected element `{}`", child.name),
+          }),
+        },
+      }
+    }
+    for name in &content.required_children {
+      if !seen.contains_key(name) {
+        invalid.push(Invalid {
+          path: path.to_string(),
+          msg: format!("missing required element `{name}`"),
+        });
+      }
+    }
+
+    if let Some(text) = content.text {
+      let value = node.get_text().unwrap_or_default();
+      if let Err(msg) = self.check_value(text, &value) {
+        invalid.push(Invalid {
+          path: path.to_string(),
+          msg,
+        });
+      }
+    }
+  }
+
+  /// The top-level element `name` when it is in the substitution group of a child of `content`,
+  /// directly or through the group of another member, along with the name of that child.
+  fn substitute<'a>(&'a self, content: &Content<'a>, name: &str) -> Option<(&'a str, &'a Element)> {
+    let member = self.find(|child| match child {
+      SchemaOptions::Element(element)
+        if element.name.as_ref().map(|name| name.local_name.as_str()) == Some(name) =>
+      {
+        Some(element)
+      }
+      _ => None,
+    })?;
+
+    let mut visited = BTreeSet::new();
+    let mut element = member;
+    while let Some(head) = &element.substitution_group {
+      if let Some((name, _)) = content.children.get_key_value(head.local_name.as_str()) {
+        return Some((name, member));
+      }
+      if !visited.insert(head) {
+        return None;
+      }
+      element = self.element(head)?;
+    }
+    None
+  }
+
+  /// Collect what an element of type `complex_type` may hold. `visited` guards against types
+  /// and groups (indirectly) referencing themselves.
+  fn complex_content<'a>(
+    &'a self,
+    complex_type: &'a ComplexType,
+    required: bool,
+    content: &mut Content<'a>,
+    visited: &mut BTreeSet<&'a XsdName>,
+  ) {
+    for attribute in &complex_type.attributes {
+      self.add_attribute(attribute, content);
+    }
+    for group in &complex_type.attribute_groups {
+      self.add_attribute_group(group, content, visited);
+    }
+    if let Some(sequence) = &complex_type.sequence {
+      self.add_sequence(sequence, required, content, visited);
+    }
+    if let Some(choice) = &complex_type.choice {
+      self.add_choice(choice, required, content, visited);
+    }
+    if let Some(group) = &complex_type.group {
+      self.add_group(group, required, content, visited);
+    }
+
+    if let Some(simple_content) = &complex_type.simple_content {
+      if let Some(extension) = &simple_content.extension {
+        self.add_base(&extension.base, content, visited);
+        for attribute in &extension.attributes {
+          self.add_attribute(attribute, content);
+        }
+        for group in &extension.attribute_groups {
+          self.add_attribute_group(group, content, visited);
+        }
+      }
+      if let Some(restriction) = &simple_content.restriction {
+        self.add_base(&restriction.base, content, visited);
+        for attribute in &restriction.attributes {
+          self.add_attribute(attribute, content);
+        }
+        for group in &restriction.attribute_groups {
+          self.add_attribute_group(group, content, visited);
+        }
+      }
+    }
+
+    if let Some(complex_content) = &complex_type.complex_content {
+      if let Some(extension) = &complex_content.extension {
+        self.add_base(&extension.base, content, visited);
+        for attribute in &extension.attributes {
+          self.add_attribute(attribute, content);
+        }
+        for group in &extension.attribute_groups {
+          self.add_attribute_group(group, content, visited);
+        }
+        if let Some(sequence) = &extension.sequence {
+          self.add_sequence(sequence, required, content, visited);
+        }
+        if let Some(choice) = &extension.choice {
+          self.add_choice(choice, required, content, visited);
+        }
+        if let Some(group) = &extension.group {
+          self.add_group(group, required, content, visited);
+        }
+      }
+      if let Some(restriction) = &complex_content.restriction {
+        // A restriction replaces the particles of its base and inherits its attributes.
+        let mut base = Content::default();
+        self.add_base(&restriction.base, &mut base, visited);
+        content.attributes.extend(base.attributes);
+
+        for attribute in &restriction.attributes {
+          self.add_attribute(attribute, content);
+        }
+        for group in &restriction.attribute_groups {
+          self.add_attribute_group(group, content, visited);
+        }
+        if let Some(sequence) = &restriction.sequence {
+          self.add_sequence(sequence, required, content, visited);
+        }
+        if let Some(choice) = &restriction.choice {
+          self.add_choice(choice, required, content, visited);
+        }
+        if let Some(group) = &restriction.group {
+          self.add_group(group, required, content, visited);
+        }
+      }
+    }
+  }
+
+  /// Add the content of the base of an extension or restriction.
+  fn add_base<'a>(
+    &'a self,
+    base: &'a XsdName,
+    content: &mut Content<'a>,
+    visited: &mut BTreeSet<&'a XsdName>,
+  ) {
+    match self.complex_type(base) {
+      Some(complex_type) => {
+        if visited.insert(base) {
+          self.complex_content(complex_type, true, content, visited);
+        }
+      }
+      None if base.namespace.as_deref() == Some(XML_SCHEMA) && base.local_name == "anyType" => {}
+      None => content.text = Some(SimpleRef::Named(base)),
+    }
+  }
+
+  fn add_sequence<'a>(
+    &'a self,
+    sequence: &'a Sequence,
+    required: bool,
+    content: &mut Content<'a>,
+    visited: &mut BTreeSet<&'a XsdName>,
+  ) {
+    let required = required && sequence.min_occurences > 0;
+    for child in &sequence.children {
+      match child {
+        SequenceOptions::Element(element) => Self::add_element(element, required, content),
+        SequenceOptions::Group(group) => self.add_group(group, required, content, visited),
+        SequenceOptions::Choice(choice) => self.add_choice(choice, required, content, visited),
+        SequenceOptions::Sequence(sequence) => {
+          self.add_sequence(sequence, required, content, visited)
+        }
+      }
+    }
+  }
+
+  fn add_choice<'a>(
+    &'a self,
+    choice: &'a Choice,
+    required: bool,
+    content: &mut Content<'a>,
+    visited: &mut BTreeSet<&'a XsdName>,
+  ) {
+    // Only the single alternative of a required choice has to be present.
+    let required = required && choice.min_occurences > 0 && choice.children.len() == 1;
+    for child in &choice.children {
+      match child {
+        ChoiceOptions::Element(element) => Self::add_element(element, required, content),
+        ChoiceOptions::Group(group) => self.add_group(group, required, content, visited),
+        ChoiceOptions::Choice(choice) => self.add_choice(choice, required, content, visited),
+        ChoiceOptions::Sequence(sequence) => {
+          self.add_sequence(sequence, required, content, visited)
+        }
+      }
+    }
+  }
+
+  fn add_group<'a>(
+    &'a self,
+    group: &'a Group,
+    required: bool,
+    content: &mut Content<'a>,
+    visited: &mut BTreeSet<&'a XsdName>,
+  ) {
+    let required = required && group.min_occurences > 0;
+    let definition = match &group.refers {
+      Some(refers) if visited.insert(refers) => self.group(refers),
+      Some(_) => None,
+      None => Some(group),
+    };
+
+    if let Some(definition) = definition {
+      if let Some(sequence) = &definition.sequence {
+        self.add_sequence(sequence, required, content, visited);
+      }
+      if let Some(choice) = &definition.choice {
+        self.add_choice(choice, required, content, visited);
+      }
+    }
+
+    if let Some(refers) = &group.refers {
+      visited.remove(refers);
+    }
+  }
+
+  fn add_element<'a>(element: &'a Element, required: bool, content: &mut Content<'a>) {
+    let Some(name) = element.name.as_ref().or(element.refers.as_ref()) else {
+      return;
+    };
+
+    content.children.insert(&name.local_name, element);
+    if required && element.min_occurences > 0 {
+      content.required_children.insert(&name.local_name);
+    }
+  }
+
+  fn add_attribute<'a>(&'a self, attribute: &'a Attribute, content: &mut Content<'a>) {
+    let required = attribute.required == Required::Required;
+    let (name, declaration) = match (&attribute.name, &attribute.reference) {
+      (Some(name), _) => (name, Some(attribute)),
+      (None, Some(reference)) => (reference, self.attribute(reference)),
+      (None, None) => return,
+    };
+
+    let ty = declaration.and_then(|declaration| match &declaration.simple_type {
+      Some(simple_type) => Some(SimpleRef::Inline(simple_type)),
+      None => declaration.r#type.as_ref().map(SimpleRef::Named),
+    });
+    content.attributes.insert(&name.local_name, (required, ty));
+  }
+
+  fn add_attribute_group<'a>(
+    &'a self,
+    group: &'a AttributeGroup,
+    content: &mut Content<'a>,
+    visited: &mut BTreeSet<&'a XsdName>,
+  ) {
+    let definition = match &group.reference {
+      Some(reference) if visited.insert(reference) => self.attribute_group(reference),
+      Some(_) => None,
+      None => Some(group),
+    };
+
+    if let Some(definition) = definition {
+      for attribute in &definition.attributes {
+        self.add_attribute(attribute, content);
+      }
+      for nested in &definition.attribute_groups {
+        self.add_attribute_group(nested, content, visited);
+      }
+    }
+
+    if let Some(reference) = &group.reference {
+      visited.remove(reference);
+    }
+  }
+
+  fn check_value(&self, ty: SimpleRef, value: &str) -> Result<(), String> {
+    let simple_type = match ty {
+      SimpleRef::Inline(simple_type) => simple_type,
+      SimpleRef::Named(name) if name.namespace.as_deref() == Some(XML_SCHEMA) => {
+        return check_builtin(&name.local_name, value.trim());
+      }
+      SimpleRef::Named(name) => match self.simple_type(name) {
+        Some(simple_type) => simple_type,
+        None => return Ok(()),
+      },
+    };
+
+    if let Some(restriction) = &simple_type.restriction {
+      self.check_value(SimpleRef::Named(&restriction.base), value)?;
+
+      if !restriction.enumerations.is_empty()
+        && !restriction.enumerations.iter().any(|v| v == value.trim())
+      {
+        return Err(format!(
+          "`{}` is not one of {}",
+          value.trim(),
+          restriction.enumerations.join(", ")
+        ));
+      }
+    }
+
+    if let Some(list) = &simple_type.list {
+      for item in value.split_whitespace() {
+        self.check_value(SimpleRef::Named(&list.item_type), item)?;
+      }
+    }
+
+    if let Some(union) = &simple_type.union {
+      let mut members = union
+        .member_types
+        .iter()
+        .map(SimpleRef::Named)
+        .chain(union.simple_types.iter().map(SimpleRef::Inline));
+      if !members.any(|member| self.check_value(member, value).is_ok()) {
+        return Err(format!("`{}` matches no member of the union", value.trim()));
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// Check the values of the numeric and boolean builtin types, other builtins accept any text.
+fn check_builtin(ty: &str, value: &str) -> Result<(), String> {
+  let range = match ty {
+    "byte" => Some((i8::MIN as i128, i8::MAX as i128)),
+    "unsignedByte" => Some((0, u8::MAX as i128)),
+    "short" => Some((i16::MIN as i128, i16::MAX as i128)),
+    "unsignedShort" => Some((0, u16::MAX as i128)),
+    "int" => Some((i32::MIN as i128, i32::MAX as i128)),
+    "unsignedInt" => Some((0, u32::MAX as i128)),
+    "long" => Some((i64::MIN as i128, i64::MAX as i128)),
+    "unsignedLong" | "nonNegativeInteger" => Some((0, i128::MAX)),
+    "positiveInteger" => Some((1, i128::MAX)),
+    "negativeInteger" => Some((i128::MIN, -1)),
+    "nonPositiveInteger" => Some((i128::MIN, 0)),
+    "integer" => Some((i128::MIN, i128::MAX)),
+    _ => None,
+  };
+
+  let valid = match (ty, range) {
+    (_, Some((min, max))) => value
+      .parse::<i128>()
+      .is_ok_and(|value| (min..=max).contains(&value)),
+    ("boolean", _) => matches!(value, "true" | "false" | "1" | "0"),
+    ("decimal" | "double" | "float", _) => value.parse::<f64>().is_ok(),
+    _ => true,
+  };
+
+  if valid {
+    Ok(())
+  } else {
+    Err(format!("`{value}` is not a valid {ty}"))
+  }
+}
+
+#[test]
+fn content_is_checked_through_references() {
+  let xsd = Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:attributeGroup name="ids">
+        <xs:attribute name="id" type="xs:string" use="required"/>
+      </xs:attributeGroup>
+      <xs:group name="body">
+        <xs:sequence>
+          <xs:element name="pitch" type="xs:string"/>
+          <xs:choice>
+            <xs:element name="duration" type="xs:positiveInteger"/>
+            <xs:element name="grace" type="xs:string"/>
+          </xs:choice>
+        </xs:sequence>
+      </xs:group>
+      <xs:complexType name="base">
+        <xs:group ref="body"/>
+        <xs:attributeGroup ref="ids"/>
+      </xs:complexType>
+      <xs:complexType name="note">
+        <xs:complexContent>
+          <xs:extension base="base">
+            <xs:sequence>
+              <xs:element ref="stem" minOccurs="0"/>
+            </xs:sequence>
+          </xs:extension>
+        </xs:complexContent>
+      </xs:complexType>
+      <xs:element name="stem">
+        <xs:simpleType>
+          <xs:restriction base="xs:string">
+            <xs:enumeration value="up"/>
+            <xs:enumeration value="down"/>
+          </xs:restriction>
+        </xs:simpleType>
+      </xs:element>
+      <xs:element name="note" type="note"/>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let check = |document: &str| {
+    let instance = xmltree::Element::parse(document.as_bytes()).unwrap();
+    xsd
+      .validate(&instance)
+      .unwrap()
+      .into_iter()
+      .map(|invalid| format!("{}: {}", invalid.path, invalid.msg))
+      .collect::<Vec<_>>()
+  };
+
+  assert_eq!(
+    check(r#"<note id="n1"><pitch>C</pitch><duration>2</duration><stem>up</stem></note>"#),
+    Vec::<String>::new()
+  );
+  assert_eq!(
+    check(r#"<note color="red"><duration>0</duration><stem>sideways</stem><beam/></note>"#),
+    [
+      "/note: unexpected attribute `color`",
+      "/note: missing required attribute `id`",
+      "/note/duration: `0` is not a valid positiveInteger",
+      "/note/stem: `sideways` is not one of up, down",
+      "/note: unexpected element `beam`",
+      "/note: missing required element `pitch`",
+    ]
+  );
+}
+
+#[test]
+fn substitution_group_members_replace_their_head() {
+  let xsd = Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:element name="instrument" type="xs:string" abstract="true"/>
+      <xs:element name="keyboard" type="xs:string" substitutionGroup="instrument"/>
+      <xs:element name="organ" substitutionGroup="keyboard">
+        <xs:complexType>
+          <xs:attribute name="stops" type="xs:int"/>
+        </xs:complexType>
+      </xs:element>
+      <xs:element name="part">
+        <xs:complexType>
+          <xs:sequence>
+            <xs:element ref="instrument"/>
+          </xs:sequence>
+        </xs:complexType>
+      </xs:element>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let check = |document: &str| {
+    let instance = xmltree::Element::parse(document.as_bytes()).unwrap();
+    xsd
+      .validate(&instance)
+      .unwrap()
+      .into_iter()
+      .map(|invalid| format!("{}: {}", invalid.path, invalid.msg))
+      .collect::<Vec<_>>()
+  };
+
+  assert_eq!(
+    check("<part><keyboard>Grand</keyboard></part>"),
+    Vec::<String>::new()
+  );
+  assert_eq!(
+    check(r#"<part><organ stops="many"/><flute/></part>"#),
+    [
+      "/part/organ/@stops: `many` is not a valid int",
+      "/part: unexpected element `flute`",
+    ]
+  );
+  assert_eq!(
+    check("<part/>"),
+    ["/part: missing required element `instrument`"]
+  );
+}
@@ -0,0 +1,305 @@
+use std::{fmt, str::FromStr};
+
+const XML_SCHEMA: &str = "http://www.w3.org/2001/XMLSchema";
+
+/// Elements only defined by XSD 1.1.
+const ELEMENTS_1_1: &[&str] = &[
+  "alternative",
+  "assert",
+  "assertion",
+  "defaultOpenContent",
+  "explicitTimezone",
+  "openContent",
+  "override",
+];
+
+/// Attributes only defined by XSD 1.1, along with the element they are used on.
+const ATTRIBUTES_1_1: &[(&str, &str)] = &[
+  ("schema", "defaultAttributes"),
+  ("schema", "xpathDefaultNamespace"),
+  ("complexType", "defaultAttributesApply"),
+  ("attribute", "inheritable"),
+  ("attribute", "targetNamespace"),
+  ("element", "targetNamespace"),
+  ("any", "notNamespace"),
+  ("any", "notQName"),
+  ("anyAttribute", "notNamespace"),
+  ("anyAttribute", "notQName"),
+];
+
+/// Builtin types only defined by XSD 1.1.
+const TYPES_1_1: &[&str] = &[
+  "anyAtomicType",
+  "dateTimeStamp",
+  "dayTimeDuration",
+  "error",
+  "yearMonthDuration",
+];
+
+/// Attributes holding (whitespace separated) type names.
+const TYPE_REFERENCES: &[&str] = &["type", "base", "itemType", "memberTypes"];
+
+/// A version of the XML Schema definition language.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum XsdVersion {
+  V1_0,
+  #[default]
+  V1_1,
+}
+
+impl fmt::Display for XsdVersion {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::V1_0 => write!(f, "1.0"),
+      Self::V1_1 => write!(f, "1.1"),
+    }
+  }
+}
+
+impl FromStr for XsdVersion {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "1.0" => Ok(Self::V1_0),
+      "1.1" => Ok(Self::V1_1),
+      s => Err(format!(
+        "{s} is not a recognized XSD version; expected (1.0|1.1)."
+      )),
+    }
+  }
+}
+
+/// A construct of a schema that requires a version of XSD newer than 1.0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionUse {
+  /// Location of the construct, e.g. `/schema/complexType[@name="note"]/assert`.
+  pub path: String,
+  /// The construct, e.g. `element assert` or `type dateTimeStamp`.
+  pub construct: String,
+  /// The first version defining the construct.
+  pub version: XsdVersion,
+}
+
+/// Collect the constructs of a schema document that are not part of XSD 1.0.
+pub(crate) fn scan(schema: &xmltree::Element) -> Vec<VersionUse> {
+  let mut uses = vec![];
+  scan_element(schema, "", "", &mut uses);
+  uses
+}
+
+fn scan_element(
+  element: &xmltree::Element,
+  parent: &str,
+  parent_name: &str,
+  uses: &mut Vec<VersionUse>,
+) {
+  if element.namespace.as_deref() != Some(XML_SCHEMA) || element.name == "annotation" {
+    return;
+  }
+
+  let path = match element
+    .attributes
+    .get("name")
+    .map(|name| ("name", name))
+    .or_else(|| element.attributes.get("ref").map(|name| ("ref", name)))
+  {
+    Some((attribute, name)) => format!("{parent}/{}[@{attribute}=\"{name}\"]", element.name),
+    None => format!("{parent}/{}", element.name),
+  };
+
+  let mut require = |construct: String| {
+    uses.push(VersionUse {
+      path: path.clone(),
+      construct,
+      version: XsdVersion::V1_1,
+    })
+  };
+
+  if ELEMENTS_1_1.contains(&element.name.as_str()) {
+    require(format!("element {}", element.name));
+  }
+
+  for (owner, attribute) in ATTRIBUTES_1_1 {
+    if *owner == element.name && element.attributes.contains_key(*attribute) {
+      require(format!("attribute {attribute}"));
+    }
+  }
+
+  // XSD 1.0 only allows `all` groups of elements occurring at most once, that are not extended.
+  if parent_name == "all" {
+    let max_occurs = element.attributes.get("maxOccurs").map(String::as_str);
+    if max_occurs == Some("unbounded")
+      || max_occurs.and_then(|max| max.parse::<u64>().ok()) > Some(1)
+    {
+      require("maxOccurs above 1 in all".to_string());
+    }
+  }
+  if element.name == "all" && parent_name == "extension" {
+    require("all in an extension".to_string());
+  }
+
+  for attribute in TYPE_REFERENCES {
+    let names = element.attributes.get(*attribute).into_iter();
+    for name in names.flat_map(|names| names.split_whitespace()) {
+      let (prefix, local_name) = name.split_once(':').unwrap_or(("", name));
+      let namespace = element
+        .namespaces
+        .as_ref()
+        .and_then(|namespaces| namespaces.get(prefix));
+      if namespace == Some(XML_SCHEMA) && TYPES_1_1.contains(&local_name) {
+        require(format!("type {local_name}"));
+      }
+    }
+  }
+
+  for child in element
+    .children
+    .iter()
+    .filter_map(|child| child.as_element())
+  {
+    scan_element(child, &path, &element.name, uses);
+  }
+}
+
+#[test]
+fn constructs_newer_than_the_conformance_are_reported() {
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="note">
+        <xs:sequence>
+          <xs:element name="stamp" type="xs:dateTimeStamp"/>
+        </xs:sequence>
+      </xs:complexType>
+    </xs:schema>
+  "#;
+
+  let lenient = crate::GeneratorOptions {
+    conformance: XsdVersion::V1_0,
+    ..Default::default()
+  };
+  let (output, report) = crate::Xsd::new(schema)
+    .unwrap()
+    .generate_with_report(&lenient)
+    .unwrap();
+  assert!(output.contains("pub stamp: xml_schema_parser::DateTime"));
+  assert_eq!(
+    report.warnings,
+    [crate::Warning {
+      path: r#"/schema/complexType[@name="note"]/sequence/element[@name="stamp"]"#.to_string(),
+      msg: "type dateTimeStamp requires XSD 1.1".to_string(),
+    }]
+  );
+
+  let (_, report) = crate::Xsd::new(schema)
+    .unwrap()
+    .generate_with_report(&crate::GeneratorOptions::default())
+    .unwrap();
+  assert!(report.warnings.is_empty());
+
+  let strict = crate::GeneratorOptions {
+    strict_conformance: true,
+    ..lenient
+  };
+  let error = crate::Xsd::new(schema)
+    .unwrap()
+    .generate_with(&strict)
+    .unwrap_err();
+  assert_eq!(error.error_code(), "conformance");
+}
+
+#[test]
+fn constructs_of_imported_schemas_are_reported() {
+  let dir = tempfile::tempdir().unwrap();
+  let common = dir.path().join("common.xsd");
+  std::fs::write(
+    &common,
+    r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:common">
+  <xs:simpleType name="stamp">
+    <xs:restriction base="xs:dateTimeStamp"/>
+  </xs:simpleType>
+</xs:schema>"#,
+  )
+  .unwrap();
+
+  let schema = format!(
+    r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:c="urn:common">
+  <xs:import namespace="urn:common" schemaLocation="{}"/>
+  <xs:complexType name="note">
+    <xs:attribute name="stamp" type="c:stamp"/>
+  </xs:complexType>
+</xs:schema>"#,
+    common.display()
+  );
+
+  let options = crate::GeneratorOptions {
+    conformance: XsdVersion::V1_0,
+    ..Default::default()
+  };
+  let xsd = crate::Xsd::new(&schema).unwrap();
+  let (_, report) = xsd.generate_with_report(&options).unwrap();
+  assert_eq!(
+    report.warnings,
+    [crate::Warning {
+      path: format!(
+        r#"{}:/schema/simpleType[@name="stamp"]/restriction"#,
+        common.display()
+      ),
+      msg: "type dateTimeStamp requires XSD 1.1".to_string(),
+    }]
+  );
+
+  // Generating again doesn't report the uses twice.
+  let (_, again) = xsd.generate_with_report(&options).unwrap();
+  assert_eq!(again.warnings, report.warnings);
+}
+
+#[test]
+fn all_groups_beyond_xsd_1_0_are_reported() {
+  let schema = xmltree::Element::parse(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="note">
+        <xs:all>
+          <xs:element name="pitch" type="xs:string"/>
+          <xs:element name="dot" type="xs:string" maxOccurs="2"/>
+          <xs:element name="tie" type="xs:string" maxOccurs="unbounded"/>
+        </xs:all>
+      </xs:complexType>
+      <xs:complexType name="chord">
+        <xs:complexContent>
+          <xs:extension base="note">
+            <xs:all>
+              <xs:element name="root" type="xs:string" maxOccurs="1"/>
+            </xs:all>
+          </xs:extension>
+        </xs:complexContent>
+      </xs:complexType>
+    </xs:schema>
+  "#
+    .as_bytes(),
+  )
+  .unwrap();
+
+  let uses: Vec<_> = scan(&schema)
+    .into_iter()
+    .map(|used| (used.path, used.construct))
+    .collect();
+  assert_eq!(
+    uses,
+    [
+      (
+        r#"/schema/complexType[@name="note"]/all/element[@name="dot"]"#.to_string(),
+        "maxOccurs above 1 in all".to_string()
+      ),
+      (
+        r#"/schema/complexType[@name="note"]/all/element[@name="tie"]"#.to_string(),
+        "maxOccurs above 1 in all".to_string()
+      ),
+      (
+        r#"/schema/complexType[@name="chord"]/complexContent/extension/all"#.to_string(),
+        "all in an extension".to_string()
+      ),
+    ]
+  );
+}
@@ -0,0 +1,85 @@
+use xsd_codegen::{Item, Scope};
+
+/// Give the public items of `scope` and of its modules the visibility `vis`, see
+/// `GeneratorOptions::visibility`. The fields and the methods are left public, they are only
+/// reachable through their type.
+pub(super) fn restrict_visibility(scope: &mut Scope, vis: &str) {
+  let restrict = |current: &mut Option<String>| {
+    if current.as_deref() == Some("pub") {
+      *current = Some(vis.to_string());
+    }
+  };
+
+  for item in &mut scope.items {
+    match item {
+      Item::Module(module) => {
+        restrict(&mut module.vis);
+        restrict_visibility(&mut module.scope, vis);
+      }
+      Item::Struct(r#struct) => restrict(&mut r#struct.type_def.vis),
+      Item::Enum(r#enum) => restrict(&mut r#enum.type_def.vis),
+      Item::Trait(r#trait) => restrict(&mut r#trait.type_def.vis),
+      Item::TypeAlias(alias) => restrict(&mut alias.vis),
+      // The re-exports of the namespace modules, which are reported as unused imports once
+      // they aren't public, when the types are named by their module.
+      Item::Raw(raw) => {
+        if let Some(reexport) = raw.strip_prefix("pub use ") {
+          *raw = format!("#[allow(unused_imports)]\n{vis} use {reexport}");
+        }
+      }
+      Item::Function(_) | Item::Impl(_) => {}
+    }
+  }
+}
+
+#[test]
+fn public_items_get_the_visibility() {
+  use crate::{GeneratorOptions, Visibility, Xsd};
+
+  let xsd = Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:music" xmlns="urn:music">
+      <xs:attribute name="title" type="xs:string"/>
+      <xs:complexType name="note">
+        <xs:choice>
+          <xs:element name="pitch" type="xs:string"/>
+          <xs:element name="rest">
+            <xs:complexType/>
+          </xs:element>
+        </xs:choice>
+        <xs:attribute ref="title"/>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+  let generate = |visibility| {
+    xsd
+      .generate_with(&GeneratorOptions {
+        visibility,
+        namespace_modules: true,
+        choice_visitors: true,
+        ..Default::default()
+      })
+      .unwrap()
+  };
+
+  let output = generate(Visibility::PubCrate);
+  for item in [
+    "pub(crate) mod music {",
+    "pub(crate) use music::*;",
+    "pub(crate) type Title = String;",
+    "pub(crate) struct Note {",
+    "pub(crate) enum Note {",
+    "pub(crate) trait NoteVisitor {",
+    "pub(crate) mod note {",
+    "pub(crate) struct Rest;",
+  ] {
+    assert!(output.contains(item), "{item} in {output}");
+  }
+  assert!(output.contains("pub fn accept(&self, visitor: &mut impl NoteVisitor) {"));
+  assert!(!output.contains("pub mod") && !output.contains("pub struct"));
+
+  let output = generate(Visibility::Pub);
+  assert!(output.contains("pub mod music {") && output.contains("pub struct Note {"));
+}
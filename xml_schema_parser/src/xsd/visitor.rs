@@ -0,0 +1,119 @@
+use xsd_codegen::{Block, Enum, Fields, Function, Impl, Item, Scope, Trait, Type, Variant};
+use xsd_types::{to_field_name, XsdType};
+
+/// Whether `r#enum` is the enum of a choice, each variant holding the value of one of its
+/// particles. The enums of unions and enumerations are left out.
+fn is_choice(r#enum: &Enum) -> bool {
+  r#enum
+    .ty()
+    .xml_name
+    .as_ref()
+    .is_some_and(|name| name.ty != XsdType::Union)
+    && !r#enum.variants.is_empty()
+    && r#enum
+      .variants
+      .iter()
+      .all(|variant| matches!(&variant.fields, Fields::Tuple(fields) if fields.len() == 1))
+}
+
+/// The method visiting `variant`, e.g. `visit_note`.
+fn method_name(variant: &Variant) -> String {
+  format!(
+    "visit_{}",
+    to_field_name(&variant.name).trim_start_matches("r#")
+  )
+}
+
+/// The `{Enum}Visitor` trait named `name`, with a method doing nothing for each variant of
+/// `r#enum`, and the impl of `accept` dispatching to them.
+fn visitor(r#enum: &Enum, name: &str) -> (Trait, Impl) {
+  let enum_name = &r#enum.ty().name;
+  let mut visitor = Trait::new(name, None).vis("pub");
+  visitor.doc(&format!(
+    "Visits the variants of `{enum_name}`, see `{enum_name}::accept`. The methods do nothing unless overridden."
+  ));
+
+  let mut arms = Block::new("match self");
+  let mut ptr_arg = false;
+  for variant in &r#enum.variants {
+    let method = method_name(variant);
+    let ty = &variant.fields.fields()[0].ty;
+    ptr_arg |= matches!(ty.name.as_str(), "String" | "Vec");
+    let mut visit = Function::new(&method);
+    visit.arg_mut_self();
+    visitor.push_fn(visit.arg("_value", Type::new(None, &format!("&{ty}"))));
+    arms = arms.line(format!(
+      "Self::{}(value) => visitor.{method}(value),",
+      variant.name
+    ));
+  }
+  // The methods take the values as they are stored, e.g. `&String`.
+  if ptr_arg {
+    visitor.allow("clippy::ptr_arg");
+  }
+
+  let accept = Function::new("accept")
+    .doc(&format!(
+      "Call the method of `visitor` for the variant of this `{enum_name}`."
+    ))
+    .vis("pub")
+    .arg_ref_self()
+    .arg("visitor", Type::new(None, &format!("&mut impl {name}")))
+    .push_block(arms);
+
+  (visitor, Impl::new(r#enum.ty()).push_fn(accept))
+}
+
+/// Follow each choice enum of `scope` and of its modules with its `{Enum}Visitor` trait and an
+/// `accept` method, see `GeneratorOptions::choice_visitors`. The trait comes after the impls of
+/// the enum.
+pub(crate) fn add_choice_visitors(scope: &mut Scope) {
+  let names: Vec<String> = scope
+    .items
+    .iter()
+    .filter_map(|item| match item {
+      Item::Struct(r#struct) => Some(r#struct.ty().name.clone()),
+      Item::Enum(r#enum) => Some(r#enum.ty().name.clone()),
+      Item::Trait(r#trait) => Some(r#trait.ty().name.clone()),
+      Item::TypeAlias(alias) => Some(alias.alias.name.clone()),
+      _ => None,
+    })
+    .collect();
+
+  let mut items = Vec::with_capacity(scope.items.len());
+  let mut pending: Option<(String, Trait, Impl)> = None;
+  for mut item in std::mem::take(&mut scope.items) {
+    let follows_enum = matches!(
+      (&item, &pending),
+      (Item::Impl(implementation), Some((enum_name, ..))) if &implementation.target.name == enum_name
+    );
+    if !follows_enum {
+      if let Some((_, visitor, implementation)) = pending.take() {
+        items.push(Item::Trait(visitor));
+        items.push(Item::Impl(implementation));
+      }
+    }
+
+    match &mut item {
+      Item::Module(module) => add_choice_visitors(module.scope()),
+      Item::Enum(r#enum) if is_choice(r#enum) => {
+        let enum_name = r#enum.ty().name.clone();
+        let name = format!("{enum_name}Visitor");
+        if names.contains(&name) {
+          tracing::warn!("No visitor for {enum_name}: {name} is already generated");
+        } else {
+          let (visitor, implementation) = visitor(r#enum, &name);
+          pending = Some((enum_name, visitor, implementation));
+        }
+      }
+      _ => {}
+    }
+    items.push(item);
+  }
+  if let Some((_, visitor, implementation)) = pending {
+    items.push(Item::Trait(visitor));
+    items.push(Item::Impl(implementation));
+  }
+
+  scope.items = items;
+}
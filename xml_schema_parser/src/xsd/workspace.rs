@@ -0,0 +1,169 @@
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use super::{
+  drift, import::ImportCache, schema::Schema, GeneratorOptions, LoadOptions, Xsd, XsdError,
+};
+
+/// Generates related schemas into the modules of one directory.
+///
+/// The documents the schemas import are loaded once for all of them, and each imported
+/// namespace is emitted once, into a module of its own named after the prefix the schemas
+/// declare for it. The modules of the schemas import the types of the namespaces from these
+/// modules, or from the module of the schema whose target namespace it is. Imports without a
+/// namespace are still generated into each schema importing them.
+#[derive(Clone, Debug, Default)]
+pub struct Workspace {
+  load_options: LoadOptions,
+  /// The location of each schema, by the module it is generated into.
+  schemas: BTreeMap<String, String>,
+}
+
+/// A schema of the workspace, or a document imported by them, and the module it is generated
+/// into.
+struct Unit {
+  module: String,
+  xsd: Xsd,
+  /// Whether the schema was added to the workspace, the other units are imported documents.
+  root: bool,
+}
+
+impl Workspace {
+  /// A workspace loading the schemas, and the documents they import, with `load_options`.
+  pub fn new(load_options: &LoadOptions) -> Self {
+    Self {
+      load_options: load_options.clone(),
+      schemas: BTreeMap::new(),
+    }
+  }
+
+  /// Add the schema at `location`, generated into the module `module`.
+  pub fn add_schema(&mut self, module: &str, location: &str) -> &mut Self {
+    self
+      .schemas
+      .insert(module.to_string(), location.to_string());
+    self
+  }
+
+  /// Generate the schemas, and the namespaces they import, with `options`. The code of each
+  /// module is returned by the name of the module. The types of the `roots` of the options are
+  /// looked up in every schema, the imported namespaces are generated whole.
+  pub fn generate_with(
+    &self,
+    options: &GeneratorOptions,
+  ) -> Result<BTreeMap<String, String>, XsdError> {
+    let cache = ImportCache::default();
+    let mut units = vec![];
+    for (module, location) in &self.schemas {
+      let mut xsd = Xsd::new_from_file_with(location, &self.load_options)?;
+      xsd.context.import_cache = Some(cache.clone());
+      units.push(Unit {
+        module: module.clone(),
+        xsd,
+        root: true,
+      });
+    }
+
+    // The module of each namespace: the first schema targeting it, else the imported document.
+    let mut modules = BTreeMap::new();
+    for unit in &units {
+      if let Some(namespace) = unit.xsd.target_namespace() {
+        modules
+          .entry(namespace.to_string())
+          .or_insert_with(|| unit.module.clone());
+      }
+    }
+
+    let mut next = 0;
+    while next < units.len() {
+      let mut imported = vec![];
+      for import in units[next].xsd.schema.imports() {
+        let (Some(namespace), Some(location)) = (&import.namespace, &import.schema_location) else {
+          continue;
+        };
+        if modules.contains_key(namespace) || options.external_namespaces.contains_key(namespace) {
+          continue;
+        }
+
+        let module = Schema::namespace_module_name(&units[next].xsd.context, namespace);
+        if units
+          .iter()
+          .chain(&imported)
+          .any(|unit| unit.module == module)
+        {
+          return Err(XsdError::ExternalNamespace {
+            namespace: namespace.clone(),
+            msg: format!("its module `{module}` is also the module of another schema"),
+          });
+        }
+
+        let mut xsd = Xsd::new_from_file_with(location, &self.load_options)?;
+        xsd.context.import_cache = Some(cache.clone());
+        modules.insert(namespace.clone(), module.clone());
+        imported.push(Unit {
+          module,
+          xsd,
+          root: false,
+        });
+      }
+      units.extend(imported);
+      next += 1;
+    }
+
+    let mut code = BTreeMap::new();
+    for unit in &units {
+      let mut options = options.clone();
+      if !unit.root {
+        options.roots.clear();
+      }
+      for import in unit.xsd.schema.imports() {
+        let Some(namespace) = &import.namespace else {
+          continue;
+        };
+        if let Some(module) = modules.get(namespace) {
+          if *module != unit.module {
+            options
+              .external_namespaces
+              .entry(namespace.clone())
+              .or_insert_with(|| format!("super::{module}"));
+          }
+        }
+      }
+
+      tracing::info!("Generate the module {}", unit.module);
+      code.insert(unit.module.clone(), unit.xsd.generate_with(&options)?);
+    }
+
+    Ok(code)
+  }
+
+  /// Generate the modules with `options` into `dir`, one `{module}.rs` file each, along with a
+  /// `mod.rs` declaring them. Returns the paths of the written files.
+  pub fn write_with(
+    &self,
+    dir: &Path,
+    options: &GeneratorOptions,
+  ) -> Result<Vec<PathBuf>, XsdError> {
+    let modules = self.generate_with(options)?;
+
+    fs::create_dir_all(dir)?;
+    let mut declarations = drift::header(options);
+    declarations.push_str("\n\n");
+    let mut written = vec![];
+    for (module, code) in &modules {
+      let path = dir.join(format!("{module}.rs"));
+      fs::write(&path, code)?;
+      written.push(path);
+      declarations.push_str(&format!("pub mod {module};\n"));
+    }
+
+    let path = dir.join("mod.rs");
+    fs::write(&path, declarations)?;
+    written.push(path);
+
+    Ok(written)
+  }
+}
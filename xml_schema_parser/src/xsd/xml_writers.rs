@@ -0,0 +1,347 @@
+use xsd_codegen::{escape_str_literal, Block, Enum, Field, Fields, Function, Impl};
+use xsd_types::{XsdName, XsdType};
+
+use super::{
+  element_alternative, unknown_content,
+  xsd_context::{XsdImpl, XsdImplType},
+};
+
+/// Write `imp` and its inner types back to XML: the types read from their text implement
+/// `ToXmlString`, the others `XsdSer`, writing each field the way the code of `general_xsdgen`
+/// reads it.
+pub(crate) fn add_xml_writers(imp: &mut XsdImpl) {
+  for inner in &mut imp.inner {
+    add_xml_writers(inner);
+  }
+
+  let implements = |name: &str| {
+    imp.implementation.iter().any(|implementation| {
+      implementation
+        .impl_trait
+        .as_ref()
+        .is_some_and(|tr| tr.name == name)
+    })
+  };
+
+  let ty = imp.element.get_type();
+  let writer = if implements("FromXmlString") {
+    to_xml_string(&imp.element)
+      .map(|function| Impl::new(&ty).impl_trait("ToXmlString").push_fn(function))
+  } else if implements("XsdGen") {
+    let function = match (&imp.element, imp.name.ty) {
+      (XsdImplType::Enum(r#enum), XsdType::DerivedTypes) => {
+        let base = XsdName {
+          ty: XsdType::ComplexType,
+          ..imp.name.clone()
+        };
+        Some(instance_type_ser(r#enum, &base))
+      }
+      (element, _) => xsd_ser(element),
+    };
+    function.map(|function| Impl::new(&ty).impl_trait("XsdSer").push_fn(function))
+  } else {
+    None
+  };
+  imp.implementation.extend(writer);
+}
+
+/// The lexical form of the types read with `FromXmlString`.
+fn to_xml_string(element: &XsdImplType) -> Option<Function> {
+  let function = Function::new("to_xml").arg_ref_self().ret("String");
+  match element {
+    // A marker of a fixed value, see `fixed_value::marker_impl`.
+    XsdImplType::Struct(r#struct) if r#struct.fields == Fields::Empty => {
+      Some(function.line("<Self as FixedValue>::VALUE.to_string()"))
+    }
+    XsdImplType::Struct(r#struct) => match r#struct.fields.fields() {
+      // A list, its items are separated by spaces.
+      [field] if field.ty.name == "Vec" => Some(
+        function.line("self.0.iter().map(ToXmlString::to_xml).collect::<Vec<_>>().join(\" \")"),
+      ),
+      // A simple type with a default or fixed value.
+      [_] => Some(function.line("ToXmlString::to_xml(&self.0)")),
+      _ => None,
+    },
+    XsdImplType::Enum(r#enum) if r#enum.variants.iter().all(|v| v.xml_value.is_some()) => {
+      let text = r#enum
+        .variants
+        .iter()
+        .fold(Block::new("let text = match self"), |text, variant| {
+          text.line(format!(
+            "Self::{} => {},",
+            variant.name,
+            escape_str_literal(variant.xml_value.as_deref().unwrap_or_default())
+          ))
+        })
+        .after(";");
+      Some(function.push_block(text).line("text.to_string()"))
+    }
+    _ => None,
+  }
+}
+
+/// Used arguments of the generated `ser`, the others are prefixed with an underscore.
+#[derive(Default)]
+struct Used {
+  element: bool,
+  state: bool,
+  name: bool,
+}
+
+/// Write the types read with the `XsdGen` impl of `general_xsdgen`.
+fn xsd_ser(element: &XsdImplType) -> Option<Function> {
+  let mut used = Used::default();
+  let body = match element {
+    // An empty type only writes its element.
+    XsdImplType::Struct(r#struct) if r#struct.fields == Fields::Empty => {
+      used = Used {
+        element: true,
+        state: true,
+        name: true,
+      };
+      Block::new("").push_block(
+        Block::new("if let (Some(name), GenType::Content) = (name, state)")
+          .line("element.write_child(name, |_| {});"),
+      )
+    }
+    XsdImplType::Struct(r#struct) => {
+      used = Used {
+        element: true,
+        state: true,
+        name: true,
+      };
+      let mut inner_name_used = false;
+      let mut ser_self = vec![];
+      for (index, field) in r#struct.fields.fields().iter().enumerate() {
+        let value = match &field.name {
+          Some(name) => format!("&self.{name}"),
+          None => format!("&self.{index}"),
+        };
+        if field.value.is_some() {
+          // Written back once the known content is, it is the last field.
+          if unknown_content::is_unknown_content(field) {
+            ser_self.push(format!("element.write_unknown({value});"));
+          }
+          continue;
+        }
+        let xml_name = match (&field.xml_name, field.flatten) {
+          (_, true) => "None".to_string(),
+          (Some(xml_name), false) => {
+            format!("Some({})", escape_str_literal(&xml_name.to_string()))
+          }
+          (None, false) => {
+            inner_name_used = true;
+            "name".to_string()
+          }
+        };
+        ser_self.extend(ser_field(field, &value, &xml_name, &mut Used::default()));
+      }
+
+      let name = if inner_name_used { "name" } else { "_name" };
+      let ser_self = ser_self
+        .into_iter()
+        .fold(
+          Block::new(&format!(
+            "let ser_self = |element: &mut XMLElement, {name}: Option<&str>|"
+          )),
+          Block::line,
+        )
+        .after(";");
+
+      Block::new("")
+        .push_block(ser_self)
+        .push_block(
+          Block::new("if let (Some(name), GenType::Content) = (name, state)")
+            .line("element.write_child(name, |element| ser_self(element, None));"),
+        )
+        .push_block(Block::new("else").line("ser_self(element, name);"))
+    }
+    XsdImplType::Enum(r#enum) if r#enum.variants.is_empty() => {
+      Block::new("").line("match *self {}")
+    }
+    XsdImplType::Enum(r#enum) => {
+      let mut arms = Block::new("match self");
+      for variant in &r#enum.variants {
+        let path = format!("Self::{}", variant.name);
+        let fields = variant.fields.fields();
+        let alternative = element_alternative(variant).is_some();
+
+        if fields.is_empty() {
+          arms = match &variant.xml_name {
+            // A unit alternative is picked from the presence of its attribute or element.
+            Some(xml_name) => {
+              used.element = true;
+              used.state = true;
+              let xml_name = escape_str_literal(&xml_name.to_string());
+              arms.push_block(
+                Block::new(&format!("{path} => match state"))
+                  .line(format!(
+                    "GenType::Attribute => element.write_attribute({xml_name}, String::new()),"
+                  ))
+                  .line(format!(
+                    "GenType::Content => element.write_child({xml_name}, |_| {{}}),"
+                  ))
+                  .after(","),
+              )
+            }
+            None => arms.line(format!("{path} => {{}}")),
+          };
+          continue;
+        }
+
+        let mut bindings = vec![];
+        let mut lines = vec![];
+        for (index, field) in fields.iter().enumerate() {
+          let written = field.value.is_none() && field.ty.parse_as.is_none();
+          let binding = match (&field.name, written) {
+            (Some(name), true) => name.clone(),
+            (Some(name), false) => format!("{name}: _"),
+            (None, true) => format!("value{index}"),
+            (None, false) => "_".to_string(),
+          };
+          let value = field.name.clone().unwrap_or(format!("value{index}"));
+          bindings.push(binding);
+          if field.value.is_some() {
+            continue;
+          }
+
+          let xml_name = match (alternative, &field.xml_name, field.flatten) {
+            (true, ..) => variant.xml_name.as_ref(),
+            (false, xml_name, false) => xml_name.as_ref(),
+            (false, _, true) => None,
+          };
+          let xml_name = match xml_name {
+            Some(xml_name) => format!("Some({})", escape_str_literal(&xml_name.to_string())),
+            None if field.flatten && !alternative => "None".to_string(),
+            None => {
+              used.name = true;
+              "name".to_string()
+            }
+          };
+          lines.extend(ser_field(field, &value, &xml_name, &mut used));
+        }
+
+        let pattern = match &variant.fields {
+          Fields::Named(_) => format!("{path} {{ {} }}", bindings.join(", ")),
+          _ => format!("{path}({})", bindings.join(", ")),
+        };
+        arms = arms.push_block(
+          lines
+            .into_iter()
+            .fold(Block::new(&format!("{pattern} =>")), Block::line),
+        );
+      }
+      Block::new("").push_block(arms)
+    }
+    XsdImplType::Type(_) | XsdImplType::TypeAlias(_) => return None,
+  };
+
+  let arg = |used: bool, name: &str| match used {
+    true => name.to_string(),
+    false => format!("_{name}"),
+  };
+  let mut function = Function::new("ser")
+    .arg_ref_self()
+    .arg(&arg(used.element, "element"), "&mut XMLElement")
+    .arg(&arg(used.state, "state"), "GenType")
+    .arg(&arg(used.name, "name"), "Option<&str>");
+  function.body = Some(body.body);
+  Some(function)
+}
+
+/// Write the enum of the types derived from `base`, see `derived_types::get_implementation`: the
+/// value is written with the `xsi:type` of its variant, the ones of `base` without it.
+fn instance_type_ser(r#enum: &Enum, base: &XsdName) -> Function {
+  let mut arms = Block::new("match self");
+  for variant in &r#enum.variants {
+    let mut arm = Block::new(&format!("Self::{}(value) =>", variant.name))
+      .line("XsdSer::ser(value, element, state, name);");
+    if let Some(provenance) = variant.provenance.as_ref().filter(|p| &p.name != base) {
+      arm = arm.line(format!(
+        "element.write_instance_type(name, {});",
+        escape_str_literal(&provenance.name.local_name)
+      ));
+    }
+    arms = arms.push_block(arm);
+  }
+
+  let mut function = Function::new("ser")
+    .arg_ref_self()
+    .arg("element", "&mut XMLElement")
+    .arg("state", "GenType")
+    .arg("name", "Option<&str>");
+  function.body = Some(Block::new("").push_block(arms).body);
+  function
+}
+
+/// The statement writing `field`, held by `value`, as `xml_name`. The fields whose value isn't
+/// read from the document aren't written, and the fixed values stored as `&'static str` are
+/// written through their marker.
+fn ser_field(field: &Field, value: &str, xml_name: &str, used: &mut Used) -> Option<String> {
+  if field.value.is_some() {
+    return None;
+  }
+
+  used.element = true;
+  let state = if field.attribute {
+    "GenType::Attribute"
+  } else {
+    used.state = true;
+    "state"
+  };
+  Some(match &field.ty.parse_as {
+    Some(parse_as) => {
+      format!("XsdSer::ser(&<{parse_as} as Default>::default(), element, {state}, {xml_name});")
+    }
+    None => format!("XsdSer::ser({value}, element, {state}, {xml_name});"),
+  })
+}
+
+#[test]
+fn writers_mirror_the_readers() {
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="note">
+        <xs:sequence>
+          <xs:choice>
+            <xs:element name="rest"><xs:complexType /></xs:element>
+            <xs:sequence>
+              <xs:element name="step" type="xs:string" />
+              <xs:element name="octave" type="xs:int" />
+            </xs:sequence>
+          </xs:choice>
+          <xs:element name="dot" minOccurs="0" maxOccurs="unbounded"><xs:complexType /></xs:element>
+        </xs:sequence>
+        <xs:attribute name="dynamics" type="xs:decimal" />
+      </xs:complexType>
+      <xs:simpleType name="yes-no">
+        <xs:restriction base="xs:token">
+          <xs:enumeration value="yes" />
+          <xs:enumeration value="no" />
+        </xs:restriction>
+      </xs:simpleType>
+    </xs:schema>
+  "#;
+
+  let generate = |xml_writers| {
+    crate::Xsd::new(schema)
+      .unwrap()
+      .generate_with(&crate::GeneratorOptions {
+        xml_writers,
+        ..Default::default()
+      })
+      .unwrap()
+  };
+
+  assert!(!generate(false).contains("XsdSer"));
+
+  let generated = generate(true);
+  assert!(generated.contains("impl XsdSer for Note {"));
+  assert!(generated.contains("XsdSer::ser(&self.reststepoctave, element, state, None);"));
+  assert!(generated.contains("XsdSer::ser(&self.dot, element, state, Some(\"dot\"));"));
+  assert!(generated
+    .contains("XsdSer::ser(&self.dynamics, element, GenType::Attribute, Some(\"dynamics\"));"));
+  assert!(generated.contains("XsdSer::ser(value0, element, state, Some(\"rest\"));"));
+  assert!(generated.contains("impl ToXmlString for YesNo {"));
+  assert!(generated.contains("Self::Yes => \"yes\","));
+}
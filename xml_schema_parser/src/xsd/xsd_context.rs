@@ -1,16 +1,23 @@
 use xsd_codegen::{
-  Enum, Field, Fields, Formatter, Impl, Item, Module, Struct, Type, TypeAlias, Variant,
+  Base64Binary, ContentKind, Date, DateTime, Decimal, Duration, Enum, Field, Fields, Formatter,
+  FromXmlString, GDay, GMonth, GMonthDay, GYear, GYearMonth, HexBinary, Impl, Module, Provenance,
+  Scope, Struct, Time, Type, TypeAlias, Variant,
+};
+use xsd_types::{
+  to_field_name, to_module_name, to_struct_name, to_variant_name, SchemaError, XsdName,
+  XsdParseError, XsdType,
 };
-use xsd_types::{to_field_name, to_struct_name, XsdIoError, XsdName, XsdParseError, XsdType};
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Debug;
-use std::io::Cursor;
 use std::iter::FromIterator;
-use xml::namespace::Namespace;
-use xml::reader::{EventReader, XmlEvent};
+use xml::namespace::{Namespace, NamespaceStack};
 
-use super::XsdError;
+use super::{
+  cancellation::CancellationToken, import::ImportCache, max_occurences::DEFAULT_MAX_OCCURS_LIMIT,
+  regenerate_xsdgen, substitutions::Substitutions, FixedValues, GeneratorOptions, LoadOptions,
+  LossyMapping, StringStorage, StubKind, VersionUse, Warning, XsdError,
+};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum XsdImplType {
@@ -21,15 +28,6 @@ pub enum XsdImplType {
 }
 
 impl XsdImplType {
-  pub fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
-    match &self {
-      XsdImplType::Struct(r#struct) => r#struct.fmt(f),
-      XsdImplType::Enum(r#enum) => r#enum.fmt(f),
-      XsdImplType::TypeAlias(ty) => ty.fmt(f),
-      _ => Ok(()),
-    }
-  }
-
   pub fn get_type(&self) -> Type {
     self.try_get_type().unwrap()
   }
@@ -47,12 +45,52 @@ impl XsdImplType {
   pub fn set_type(&mut self, name: impl Into<Type>) {
     match self {
       XsdImplType::Struct(r#struct) => {
+        let xml_name = r#struct.type_def.ty.xml_name.take();
         r#struct.type_def.ty = name.into();
+        r#struct.type_def.ty.xml_name = xml_name;
       }
       XsdImplType::Enum(r#enum) => {
+        let xml_name = r#enum.type_def.ty.xml_name.take();
         r#enum.type_def.ty = name.into();
+        r#enum.type_def.ty.xml_name = xml_name;
       }
-      _ => {}
+      XsdImplType::TypeAlias(alias) => {
+        let xml_name = alias.alias.xml_name.take();
+        alias.alias = name.into();
+        alias.alias.xml_name = xml_name;
+      }
+      XsdImplType::Type(_) => {}
+    }
+  }
+
+  pub fn add_derive(&mut self, derive: &str) {
+    match self {
+      XsdImplType::Struct(r#struct) => {
+        if !r#struct.type_def.derive.iter().any(|d| d == derive) {
+          r#struct.derive(derive);
+        }
+      }
+      XsdImplType::Enum(r#enum) => {
+        if !r#enum.type_def.derive.iter().any(|d| d == derive) {
+          r#enum.derive(derive);
+        }
+      }
+      XsdImplType::Type(_) | XsdImplType::TypeAlias(_) => {}
+    }
+  }
+
+  /// Add a paragraph after the existing documentation.
+  pub fn append_doc(&mut self, doc: &str) {
+    let existing = match self {
+      XsdImplType::Struct(r#struct) => r#struct.type_def.docs.as_ref().map(|d| d.docs.clone()),
+      XsdImplType::Enum(r#enum) => r#enum.type_def.docs.as_ref().map(|d| d.docs.clone()),
+      XsdImplType::Type(ty) => ty.docs.as_ref().map(|d| d.docs.clone()),
+      XsdImplType::TypeAlias(ty) => ty.doc.clone(),
+    };
+
+    match existing {
+      Some(existing) if !existing.is_empty() => self.add_doc(&format!("{existing}\n\n{doc}")),
+      _ => self.add_doc(doc),
     }
   }
 
@@ -78,6 +116,11 @@ pub struct XsdImpl {
   pub inner: Vec<XsdImpl>,
   pub implementation: Vec<Impl>,
   pub flatten: bool,
+  /// What the element of the type holds.
+  pub content: ContentKind,
+  /// The attribute groups read from the element of the type, directly or through other groups.
+  /// Each of them is merged once, see `merge`.
+  pub attribute_groups: Vec<XsdName>,
 }
 
 pub enum MergeType {
@@ -106,6 +149,161 @@ impl<'a> Default for MergeSettings<'a> {
   }
 }
 
+/// Add `field` to `fields`, without its name when they are tuple fields. A name already taken is
+/// prefixed with the conflict prefix of `settings`. Content fields are kept in the order they are
+/// added, before the attribute fields.
+fn add_field(fields: &mut Fields, mut field: Field, settings: &MergeSettings) {
+  if let Fields::Tuple(_) = fields {
+    field.name = None;
+  }
+
+  if let (Some(prefix), Some(name)) = (settings.conflict_prefix, &field.name) {
+    if fields
+      .fields()
+      .iter()
+      .any(|f| f.name.as_ref() == Some(name))
+    {
+      field.name = Some(format!("{prefix}{name}"));
+    }
+  }
+
+  match fields {
+    Fields::Empty if field.name.is_some() => *fields = Fields::Named(vec![field]),
+    Fields::Empty => *fields = Fields::Tuple(vec![field]),
+    Fields::Tuple(fields) | Fields::Named(fields) => {
+      let index = if field.attribute {
+        fields.len()
+      } else {
+        fields
+          .iter()
+          .position(|field| field.attribute)
+          .unwrap_or(fields.len())
+      };
+      fields.insert(index, field);
+    }
+  }
+}
+
+/// The lints allowed in the modules of the inner types unless
+/// `GeneratorOptions::inner_module_lints` says otherwise.
+pub(crate) const DEFAULT_INNER_MODULE_LINTS: &[&str] = &["dead_code", "unused_imports"];
+
+/// The types the fields of the generated code may use without importing them.
+const PRELUDE_TYPES: &[&str] = &[
+  "bool", "char", "str", "String", "Box", "Option", "Vec", "i8", "i16", "i32", "i64", "i128",
+  "isize", "u8", "u16", "u32", "u64", "u128", "usize", "f32", "f64",
+];
+
+/// The clippy lints tripped by the names and the layout the schema gives to `r#enum`.
+fn enum_lints(r#enum: &Enum) -> Vec<&'static str> {
+  fn words(name: &str) -> Vec<&str> {
+    let starts = name
+      .match_indices(|c: char| c.is_ascii_uppercase())
+      .map(|(start, _)| start)
+      .filter(|start| *start != 0);
+    std::iter::once(0)
+      .chain(starts)
+      .chain([name.len()])
+      .collect::<Vec<_>>()
+      .windows(2)
+      .map(|bounds| &name[bounds[0]..bounds[1]])
+      .collect()
+  }
+
+  let name = words(&r#enum.ty().name);
+  let variants: Vec<_> = r#enum.variants.iter().map(|v| words(&v.name)).collect();
+  // Clippy only compares the words of three variants or more.
+  let shared = variants.len() >= 3
+    && (variants.iter().all(|v| v.first() == variants[0].first())
+      || variants.iter().all(|v| v.last() == variants[0].last()));
+
+  let mut lints = vec![];
+  if shared
+    || variants
+      .iter()
+      .any(|v| v.starts_with(&name) || v.ends_with(&name))
+  {
+    lints.push("clippy::enum_variant_names");
+  }
+  // The alternatives of a choice are held inline, whatever their size.
+  if r#enum
+    .variants
+    .iter()
+    .any(|v| !matches!(v.fields, Fields::Empty))
+  {
+    lints.push("clippy::large_enum_variant");
+  }
+  lints
+}
+
+/// The name of an impl wrapped as `role` in the impl named `name`, e.g. `foo-item`.
+pub fn wrapped_name(name: &XsdName, role: &str) -> XsdName {
+  XsdName {
+    local_name: format!("{}-{role}", name.local_name),
+    ..name.clone()
+  }
+}
+
+/// The name of the element `ty` is read from, naming the variant of a choice it is merged in.
+fn element_name(ty: &Type) -> Option<XsdName> {
+  ty.xml_name
+    .clone()
+    .filter(|name| name.ty == XsdType::Element)
+}
+
+/// Qualify `ty` (or one of its generics) with `path` when it refers to `inner`.
+fn qualify_type(mut ty: Type, inner: &Type, path: &str) -> Type {
+  if &ty == inner {
+    ty = ty.path(path);
+  }
+
+  ty.generics = ty
+    .generics
+    .into_iter()
+    .map(|generic| {
+      if &generic == inner {
+        generic.path(path)
+      } else {
+        generic
+      }
+    })
+    .collect();
+
+  ty
+}
+
+/// Whether `value` reads into the Rust type a builtin is generated as. The types read as strings
+/// accept any value.
+fn is_lexical(rust_type: &str, value: &str) -> bool {
+  let value = value.trim();
+  match rust_type {
+    "bool" => matches!(value, "true" | "false" | "1" | "0"),
+    "u8" => value.parse::<u8>().is_ok(),
+    "u16" => value.parse::<u16>().is_ok(),
+    "u32" => value.parse::<u32>().is_ok(),
+    "u64" => value.parse::<u64>().is_ok(),
+    "i8" => value.parse::<i8>().is_ok(),
+    "i16" => value.parse::<i16>().is_ok(),
+    "i32" => value.parse::<i32>().is_ok(),
+    "i64" => value.parse::<i64>().is_ok(),
+    "f32" => value.parse::<f32>().is_ok(),
+    "f64" => value.parse::<f64>().is_ok(),
+    "Date" => Date::from_xml(value).is_ok(),
+    "Decimal" => Decimal::from_xml(value).is_ok(),
+    "xml_schema_parser::DateTime" => DateTime::from_xml(value).is_ok(),
+    "xml_schema_parser::Time" => Time::from_xml(value).is_ok(),
+    "xml_schema_parser::Duration" => Duration::from_xml(value).is_ok(),
+    "xml_schema_parser::GYear" => GYear::from_xml(value).is_ok(),
+    "xml_schema_parser::GYearMonth" => GYearMonth::from_xml(value).is_ok(),
+    "xml_schema_parser::GMonthDay" => GMonthDay::from_xml(value).is_ok(),
+    "xml_schema_parser::GDay" => GDay::from_xml(value).is_ok(),
+    "xml_schema_parser::GMonth" => GMonth::from_xml(value).is_ok(),
+    "xml_schema_parser::HexBinary" => HexBinary::from_xml(value).is_ok(),
+    "xml_schema_parser::Base64Binary" => Base64Binary::from_xml(value).is_ok(),
+    _ => true,
+  }
+}
+
 pub fn infer_type_name(this: &[XsdImpl]) -> String {
   let mut output = String::new();
 
@@ -131,95 +329,358 @@ impl XsdImpl {
         inner: vec![],
         implementation: vec![],
         flatten: self.flatten,
+        content: self.content,
+        attribute_groups: self.attribute_groups.clone(),
       },
     }
   }
 
-  fn wrap_inner_mod(&self, existing_module: &mut Module, level: usize) -> bool {
+  /// Wrap this impl in the type of the field holding it, e.g. a `Vec` of it. The wrapper takes
+  /// the name of this impl, which is renamed after its `role` in the wrapper (`foo-item` for the
+  /// items of a vector, `foo-value` for an optional value). Types emitted for the wrapped impl
+  /// are named the same way, see `wrapped_name`.
+  pub fn wrap(mut self, ty: Type, role: &str, flatten: bool) -> XsdImpl {
+    let name = self.name.clone();
+    let content = self.content;
+    self.name = wrapped_name(&name, role);
+
+    XsdImpl {
+      name,
+      fieldname_hint: self.fieldname_hint.clone(),
+      element: XsdImplType::Type(ty),
+      inner: vec![self],
+      implementation: vec![],
+      flatten,
+      content,
+      attribute_groups: vec![],
+    }
+  }
+
+  /// Push the definition of this type, its impls and then the module holding its inner types,
+  /// so that everything generated for the type is emitted contiguously.
+  fn push_items(&self, scope: &mut Scope, level: usize, allow: &[&str]) -> bool {
+    let mut pushed_something = true;
+
+    match &self.element {
+      XsdImplType::Struct(a) => {
+        scope.push_struct(a.clone());
+      }
+      XsdImplType::Enum(a) => {
+        let mut a = a.clone();
+        for lint in enum_lints(&a) {
+          a.allow(lint);
+        }
+        scope.push_enum(a);
+      }
+      XsdImplType::TypeAlias(alias) => scope.push_type_alias(alias.clone()),
+      XsdImplType::Type(_) => pushed_something = false,
+    }
+
+    for i in &self.implementation {
+      pushed_something = true;
+      scope.push_impl(i.clone());
+    }
+
+    if let Some(module) = self.wrap_inner(level, allow) {
+      pushed_something = true;
+      scope.push_module(module);
+    }
+
+    pushed_something
+  }
+
+  /// The module holding the inner types, with the lints of `allow` allowed in it. The types an
+  /// inner type is pruned or merged into may not use all of them.
+  fn wrap_inner(&self, level: usize, allow: &[&str]) -> Option<Module> {
     if self.inner.is_empty() {
-      return false;
+      return None;
     }
 
-    let mod_name = to_field_name(&self.element.get_type().name);
+    let mod_name = to_module_name(&self.element.get_type().name);
     let mut module = Module::new(&mod_name).vis("pub");
+    // A type standing for its inner type, e.g. the wrapper of a choice, holds it in a module of
+    // the same name.
+    if self.inner.iter().any(|inner| {
+      !inner.inner.is_empty() && to_module_name(&inner.element.get_type().name) == mod_name
+    }) {
+      module.allow("clippy::module_inception");
+    }
+    for lint in allow {
+      module.allow(lint);
+    }
 
-    module.import(
-      &(0..level).map(|_| "super").collect::<Vec<_>>().join("::"),
-      "*",
-    );
+    if self.inner_uses_parent_items() {
+      module.import(
+        &(0..level).map(|_| "super").collect::<Vec<_>>().join("::"),
+        "*",
+      );
+    }
 
     let mut pushed_something = false;
+    for inner in &self.inner {
+      pushed_something |= inner.push_items(module.scope(), level + 1, allow);
+    }
+
+    pushed_something.then_some(module)
+  }
+
+  /// Name the type of this impl `name`. The module of its inner types is renamed with it and
+  /// the parsing code referring to them is generated again.
+  fn rename(&mut self, name: String, context: &XsdContext) {
+    let old_type = self.element.get_type();
+    self.element.set_type(name);
+    for implementation in &mut self.implementation {
+      if implementation.target == old_type {
+        implementation.target = self.element.get_type();
+      }
+    }
+
+    let old_module = to_module_name(&old_type.name);
+    let new_module = to_module_name(&self.element.get_type().name);
+    if self.inner.is_empty() || old_module == new_module {
+      return;
+    }
+
+    let old_prefix = format!("{old_module}::");
+    self.map_types(&mut |ty| {
+      if let Some(name) = ty.name.strip_prefix(&old_prefix) {
+        ty.name = format!("{new_module}::{name}");
+      }
+    });
+
+    *self = regenerate_xsdgen(self.clone(), context);
+  }
 
+  /// Whether the module of the inner types uses an item of the enclosing scopes: their parsing
+  /// code uses the runtime imported at the top, and their fields may hold the outer types. The
+  /// modules of their own inner types import what they use themselves.
+  fn inner_uses_parent_items(&self) -> bool {
+    let mut local: BTreeSet<String> = PRELUDE_TYPES.iter().map(|ty| ty.to_string()).collect();
+    let mut references = BTreeSet::new();
     for inner in &self.inner {
-      match &inner.element {
-        XsdImplType::Struct(a) => {
-          pushed_something = true;
-          module.push_struct(a.clone());
-        }
-        XsdImplType::Enum(a) => {
-          pushed_something = true;
-          module.push_enum(a.clone());
+      if !inner.implementation.is_empty() {
+        return true;
+      }
+
+      let ty = inner.element.get_type();
+      if !inner.inner.is_empty() {
+        local.insert(to_module_name(&ty.name));
+      }
+      local.insert(ty.name);
+      inner.direct_references(&mut references);
+    }
+
+    references.iter().any(|name| {
+      let name = name.trim_start_matches("&'static ");
+      !local.contains(name.split("::").next().unwrap_or(name))
+    })
+  }
+
+  /// Add everything generated for this type to `scope`, with the lints of `allow` allowed in the
+  /// modules of the inner types.
+  pub fn push_to_scope(&self, scope: &mut Scope, allow: &[&str]) {
+    self.push_items(scope, 1, allow);
+  }
+
+  pub fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+    let mut scope = Scope::new();
+    self.push_to_scope(&mut scope, DEFAULT_INNER_MODULE_LINTS);
+    scope.fmt(f)
+  }
+
+  /// Record that the fields, variants and impls without a provenance were generated from the
+  /// component of this implementation, and locate the provenances in the document of `context`.
+  fn locate_provenance(&mut self, context: &XsdContext) {
+    let own = Provenance::new(self.name.clone());
+    let locate = |provenance: &mut Option<Provenance>| {
+      let provenance = provenance.get_or_insert_with(|| own.clone());
+      if provenance.prefixed_name.is_none() {
+        provenance.prefixed_name = Some(context.prefixed_name(&provenance.name));
+        provenance.document = context.location.clone();
+      }
+    };
+
+    match &mut self.element {
+      XsdImplType::Struct(r#struct) => {
+        for field in r#struct.fields.fields_mut() {
+          locate(&mut field.provenance);
         }
-        XsdImplType::Type(_) => {}
-        XsdImplType::TypeAlias(alias) => {
-          pushed_something = true;
-          module.push_type_alias(alias.clone());
+      }
+      XsdImplType::Enum(r#enum) => {
+        for variant in &mut r#enum.variants {
+          locate(&mut variant.provenance);
         }
       }
+      XsdImplType::Type(_) | XsdImplType::TypeAlias(_) => {}
+    }
+    for implementation in &mut self.implementation {
+      locate(&mut implementation.provenance);
+    }
 
-      for i in &inner.implementation {
-        pushed_something = true;
-        module.push_impl(i.clone());
-      }
+    for inner in &mut self.inner {
+      inner.locate_provenance(context);
+    }
+  }
 
-      pushed_something |= inner.wrap_inner_mod(&mut module, level + 1);
+  pub fn add_derives(&mut self, derives: &[String]) {
+    for derive in derives {
+      self.element.add_derive(derive);
     }
 
-    if pushed_something {
-      existing_module.push_module(module);
+    for inner in &mut self.inner {
+      inner.add_derives(derives);
     }
+  }
 
-    pushed_something
+  /// Collect the (unqualified) names of every type used by this implementation.
+  pub fn referenced_types(&self, output: &mut BTreeSet<String>) {
+    let mut direct = BTreeSet::new();
+    for imp in self.iter_recursive() {
+      imp.direct_references(&mut direct);
+    }
+    output.extend(
+      direct
+        .iter()
+        .map(|name| name.rsplit("::").next().unwrap_or(name).to_string()),
+    );
   }
 
-  pub fn wrap_inner(&self) -> Option<Module> {
-    if self.inner.is_empty() {
-      return None;
+  /// Collect the names of the types used by this implementation, as written in the generated
+  /// code, without the ones only used by its inner types.
+  pub fn direct_references(&self, output: &mut BTreeSet<String>) {
+    fn visit(ty: &Type, output: &mut BTreeSet<String>) {
+      output.insert(ty.name.clone());
+      for generic in &ty.generics {
+        visit(generic, output);
+      }
+      if let Some(parse_as) = &ty.parse_as {
+        visit(parse_as, output);
+      }
     }
 
-    let mut top_level = Module::new("-temp");
-    self.wrap_inner_mod(&mut top_level, 1);
+    fn visit_fields(fields: &Fields, output: &mut BTreeSet<String>) {
+      fields.fields().iter().for_each(|f| visit(&f.ty, output));
+    }
 
-    for i in top_level.scope.items {
-      if let Item::Module(m) = i {
-        return Some(m);
-      };
+    match &self.element {
+      XsdImplType::Struct(r#struct) => visit_fields(&r#struct.fields, output),
+      XsdImplType::Enum(r#enum) => {
+        for variant in &r#enum.variants {
+          visit_fields(&variant.fields, output);
+        }
+      }
+      XsdImplType::Type(ty) => visit(ty, output),
+      XsdImplType::TypeAlias(alias) => visit(&alias.value, output),
     }
+  }
 
-    None
+  /// Collect the paths of the types emitted for this implementation, following the module
+  /// layout used by `wrap_inner`.
+  pub fn type_paths(&self, output: &mut Vec<String>) {
+    self.visit_types(&mut |path, _| output.push(path));
   }
 
-  pub fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
-    self.element.fmt(f)?;
-    for r#impl in &self.implementation {
-      r#impl.fmt(f)?;
+  /// Call `visitor` with the path and the implementation of every type emitted for this
+  /// implementation, outer types first.
+  pub fn visit_types<'a>(&'a self, visitor: &mut impl FnMut(String, &'a XsdImpl)) {
+    fn visit<'a>(imp: &'a XsdImpl, prefix: &str, visitor: &mut impl FnMut(String, &'a XsdImpl)) {
+      if imp.inner.is_empty() {
+        return;
+      }
+
+      let module = format!("{prefix}{}::", to_module_name(&imp.element.get_type().name));
+      for inner in &imp.inner {
+        if !matches!(inner.element, XsdImplType::Type(_)) {
+          visitor(format!("{module}{}", inner.element.get_type().name), inner);
+        }
+        visit(inner, &module, visitor);
+      }
     }
 
-    if let Some(module) = self.wrap_inner() {
-      module.fmt(f)?;
+    if !matches!(self.element, XsdImplType::Type(_)) {
+      visitor(self.element.get_type().name, self);
     }
+    visit(self, "", visitor);
+  }
 
-    Ok(())
+  /// This implementation and its inner types, depth first, each type before its inner types.
+  pub fn iter_recursive(&self) -> impl Iterator<Item = &XsdImpl> {
+    let mut stack = vec![self];
+    std::iter::from_fn(move || {
+      let imp = stack.pop()?;
+      stack.extend(imp.inner.iter().rev());
+      Some(imp)
+    })
+  }
+
+  /// The first implementation of `iter_recursive` whose type is named `name`, as written in the
+  /// module it is emitted in, e.g. `Tempo` rather than `sound::Tempo`.
+  #[allow(dead_code)]
+  pub fn find_by_type_name(&self, name: &str) -> Option<&XsdImpl> {
+    self
+      .iter_recursive()
+      .find(|imp| imp.element.try_get_type().is_some_and(|ty| ty.name == name))
+  }
+
+  /// Call `f` once with every type written by this implementation: the type it defines, the
+  /// types of its fields and variants, the targets, traits and associated items of its impls,
+  /// and the generic arguments and `parse_as` of each of them. The types of the inner
+  /// implementations are not visited: their paths are relative to their own module.
+  pub fn map_types(&mut self, f: &mut impl FnMut(&mut Type)) {
+    fn visit(ty: &mut Type, f: &mut impl FnMut(&mut Type)) {
+      f(ty);
+      for generic in &mut ty.generics {
+        visit(generic, f);
+      }
+      if let Some(parse_as) = &mut ty.parse_as {
+        visit(parse_as, f);
+      }
+    }
+
+    match &mut self.element {
+      XsdImplType::Struct(r#struct) => {
+        visit(&mut r#struct.type_def.ty, f);
+        for field in r#struct.fields.fields_mut() {
+          visit(&mut field.ty, f);
+        }
+      }
+      XsdImplType::Enum(r#enum) => {
+        visit(&mut r#enum.type_def.ty, f);
+        for variant in &mut r#enum.variants {
+          for field in variant.fields.fields_mut() {
+            visit(&mut field.ty, f);
+          }
+        }
+      }
+      XsdImplType::Type(ty) => visit(ty, f),
+      XsdImplType::TypeAlias(alias) => {
+        visit(&mut alias.alias, f);
+        visit(&mut alias.value, f);
+      }
+    }
+
+    for implementation in &mut self.implementation {
+      visit(&mut implementation.target, f);
+      if let Some(impl_trait) = &mut implementation.impl_trait {
+        visit(impl_trait, f);
+      }
+      for assoc_ty in &mut implementation.assoc_tys {
+        visit(&mut assoc_ty.ty, f);
+      }
+      for (_, _, ty, _) in &mut implementation.assoc_consts {
+        visit(ty, f);
+      }
+    }
   }
 
   pub fn infer_type_name(&self) -> String {
     match &self.element {
       XsdImplType::Struct(a) => match &a.fields {
-        Fields::Empty => unimplemented!(),
+        Fields::Empty => a.ty().name.clone(),
         Fields::Tuple(tup) => tup.iter().map(|v| v.ty.name.as_str()).collect::<String>(),
         Fields::Named(names) => names
           .iter()
-          .map(|f| to_struct_name(&f.name))
+          .map(|f| to_struct_name(f.name()))
           .collect::<String>(),
       },
       XsdImplType::Enum(a) => a.variants.iter().map(|v| v.name.as_str()).collect(),
@@ -240,6 +701,17 @@ impl XsdImpl {
 
   fn merge_inner(&mut self, others: Vec<XsdImpl>) {
     'outer: for mut other in others {
+      if let Some(kept) = self.inner.iter().find(|i| i.is_copy_of(&other)) {
+        tracing::debug!(
+          "{} is the same type as {}",
+          other.element.get_type(),
+          kept.element.get_type()
+        );
+        let (from, to) = (other.element.get_type().name, kept.element.get_type().name);
+        self.retype_inner_fields(&from, &to);
+        continue;
+      }
+
       for i in &self.inner {
         if other.element.get_type().to_string() == i.element.get_type().to_string() {
           if i == &other {
@@ -248,7 +720,7 @@ impl XsdImpl {
           let old_type = other.element.get_type();
           other.element.set_type(format!(
             "{}{}",
-            other.element.get_type().to_string(),
+            other.element.get_type(),
             to_struct_name(&format!("{:?}", other.name.ty))
           ));
           for implementation in &mut other.implementation {
@@ -263,81 +735,165 @@ impl XsdImpl {
     }
   }
 
+  /// Whether `other` is an anonymous simple type generated the same way as this one under another
+  /// name, e.g. the yes/no enumerations of sibling elements.
+  fn is_copy_of(&self, other: &XsdImpl) -> bool {
+    let (ty, other_ty) = (self.element.get_type(), other.element.get_type());
+    if self.content != ContentKind::Simple
+      || other.content != ContentKind::Simple
+      || !self.inner.is_empty()
+      || !other.inner.is_empty()
+      || ty.name == other_ty.name
+      || matches!(
+        self.element,
+        XsdImplType::Type(_) | XsdImplType::TypeAlias(_)
+      )
+    {
+      return false;
+    }
+
+    let mut renamed = other.clone();
+    renamed.element.set_type(ty.name.as_str());
+    for implementation in &mut renamed.implementation {
+      if implementation.target == other_ty {
+        implementation.target = renamed.element.get_type();
+      }
+    }
+    matches!((self.to_string(), renamed.to_string()), (Ok(a), Ok(b)) if a == b)
+  }
+
+  /// Point the fields of this impl holding its inner type `from` to its inner type `to`.
+  fn retype_inner_fields(&mut self, from: &str, to: &str) {
+    let module = to_module_name(&self.element.get_type().name);
+    let (from, to) = (format!("{module}::{from}"), format!("{module}::{to}"));
+    self.map_types(&mut |ty| {
+      if ty.name == from {
+        ty.name = to.clone();
+      }
+    });
+  }
+
+  /// Add `other` to this impl, as its fields, as a field or as a variant. The content fields of a
+  /// struct are kept in the order they are merged, which has to be the order of the particles in
+  /// the schema since they are read in that order, and the attribute fields come after them
+  /// whatever the order they are merged in.
+  ///
+  /// An attribute group reaching this impl a second time, e.g. directly and through another
+  /// group, isn't merged again: only its first field would read the attributes. A field read
+  /// through a group `other` includes is replaced by `other`.
   pub fn merge(&mut self, mut other: XsdImpl, settings: MergeSettings) {
-    let children_are_attributes =
-      matches!(other.name.ty, XsdType::Attribute | XsdType::AttributeGroup);
+    if other.name.ty == XsdType::AttributeGroup && !other.attribute_groups.is_empty() {
+      if other
+        .attribute_groups
+        .iter()
+        .all(|group| self.attribute_groups.contains(group))
+      {
+        tracing::debug!("{} is already merged into {}", other.name, self.name);
+        return;
+      }
+
+      if let XsdImplType::Struct(r#struct) = &mut self.element {
+        if let Fields::Tuple(fields) | Fields::Named(fields) = &mut r#struct.fields {
+          fields.retain(|field| {
+            !field.flatten
+              || !field
+                .provenance
+                .as_ref()
+                .is_some_and(|provenance| other.attribute_groups.contains(&provenance.name))
+          });
+        }
+      }
+    }
+
+    // Attribute groups can reach here under the name of the component they are merged into,
+    // the merge settings of the call site are authoritative.
+    let children_are_attributes = matches!(settings.merge_type, MergeType::Attribute)
+      || matches!(other.name.ty, XsdType::Attribute | XsdType::AttributeGroup);
 
     let flatten_children =
       matches!(other.name.ty, XsdType::Group | XsdType::AttributeGroup) || other.flatten;
 
+    // The fields of a struct are copied into a struct of the same shape, `other` is a field of
+    // this type otherwise.
+    let copies_fields = match (&self.element, &other.element) {
+      (XsdImplType::Struct(a), XsdImplType::Struct(b)) => matches!(
+        (&a.fields, &b.fields),
+        (Fields::Empty, _)
+          | (Fields::Tuple(_), Fields::Tuple(_))
+          | (Fields::Named(_), Fields::Named(_))
+      ),
+      _ => false,
+    };
+    let content = if children_are_attributes {
+      ContentKind::Attributes
+    } else if !copies_fields
+      && !flatten_children
+      && other
+        .element
+        .try_get_type()
+        .and_then(|ty| element_name(&ty))
+        .is_some()
+    {
+      // Read from a child element.
+      ContentKind::Complex
+    } else {
+      other.content
+    };
+    self.content = self.content.and(content);
+
+    // The attribute groups of a child element are read from the child.
+    if copies_fields || flatten_children {
+      for group in &other.attribute_groups {
+        if !self.attribute_groups.contains(group) {
+          self.attribute_groups.push(group.clone());
+        }
+      }
+    }
+
     match &mut self.element {
       XsdImplType::Struct(a) => match &other.element {
-        XsdImplType::Struct(b) => match (&mut a.fields, &b.fields) {
-          (Fields::Empty, b_fields) => {
-            a.fields = b_fields.clone();
+        XsdImplType::Struct(b) if copies_fields => {
+          if let Fields::Empty = a.fields {
+            a.fields = b.fields.clone();
             self.inner.extend(other.inner);
-          }
-          (Fields::Tuple(a_fields), Fields::Tuple(b_fields)) => {
-            for field in b_fields {
+          } else {
+            for field in b.fields.fields() {
               let mut field = field.clone();
+              field
+                .provenance
+                .get_or_insert_with(|| Provenance::new(other.name.clone()));
               field.attribute = children_are_attributes;
               field.flatten = flatten_children;
-              a_fields.push(field);
+              add_field(&mut a.fields, field, &settings);
             }
             self.merge_inner(other.inner);
           }
-          (Fields::Named(a_fields), Fields::Named(b_fields)) => {
-            for field in b_fields {
-              let mut conflict = false;
-              for name in &*a_fields {
-                if name.name == field.name {
-                  conflict = true;
-                  break;
-                }
-              }
+        }
+        XsdImplType::Struct(b) => {
+          let field_name = to_field_name(
+            other
+              .fieldname_hint
+              .as_ref()
+              .unwrap_or_else(|| &b.ty().name),
+          );
+          let ty = b.ty().clone();
 
-              if settings.conflict_prefix.is_none() {
-                conflict = false;
-              }
+          other.fieldname_hint = Some(field_name.clone());
+          let ty = ty.path(&to_module_name(&a.ty().name));
 
-              let mut field = field.clone();
-              field.attribute = children_are_attributes;
-              field.flatten = flatten_children;
+          let field = Field::new(
+            ty.xml_name.clone(),
+            &field_name,
+            ty,
+            children_are_attributes,
+            flatten_children,
+          )
+          .vis("pub")
+          .provenance(Provenance::new(other.name.clone()));
+          add_field(&mut a.fields, field, &settings);
 
-              if conflict {
-                field.name = format!("{}{}", settings.conflict_prefix.unwrap(), field.name);
-                a_fields.push(field);
-              } else {
-                a_fields.push(field);
-              }
-            }
-            self.merge_inner(other.inner);
-          }
-          _ => {
-            let field_name = to_field_name(
-              other
-                .fieldname_hint
-                .as_ref()
-                .unwrap_or_else(|| &b.ty().name),
-            );
-            let ty = b.ty().clone();
-
-            other.fieldname_hint = Some(field_name.clone());
-            let ty = ty.path(&to_field_name(&a.ty().name));
-
-            let field = Field::new(
-              ty.xml_name.clone(),
-              &field_name,
-              ty,
-              children_are_attributes,
-              flatten_children,
-            )
-            .vis("pub");
-            a.push_field(field);
-
-            self.merge_inner(vec![other]);
-          }
-        },
+          self.merge_inner(vec![other]);
+        }
         XsdImplType::Enum(b) => {
           let field_name = to_field_name(
             other
@@ -349,7 +905,7 @@ impl XsdImpl {
 
           other.fieldname_hint = Some(field_name.clone());
 
-          let ty = ty.path(&to_field_name(&a.ty().name));
+          let ty = ty.path(&to_module_name(&a.ty().name));
 
           let field = Field::new(
             ty.xml_name.clone(),
@@ -358,8 +914,9 @@ impl XsdImpl {
             children_are_attributes,
             flatten_children,
           )
-          .vis("pub");
-          a.push_field(field);
+          .vis("pub")
+          .provenance(Provenance::new(other.name.clone()));
+          add_field(&mut a.fields, field, &settings);
 
           self.merge_inner(vec![other]);
         }
@@ -367,145 +924,132 @@ impl XsdImpl {
           let field_name = to_field_name(other.fieldname_hint.as_ref().unwrap_or(&b.name));
 
           let mut b = b.clone();
+          let module = to_module_name(&a.ty().to_string());
           for i in &mut other.inner {
             if let XsdImplType::Type(_) | XsdImplType::TypeAlias(..) = i.element {
               continue;
             }
 
             if i.element.get_type() == b {
-              b = b.path(&to_field_name(&a.ty().to_string()));
+              b = b.path(&module);
             }
 
             let mut new_generics = vec![];
             for generic in b.generics {
               if i.element.get_type() == generic {
-                new_generics.push(generic.path(&to_field_name(&a.ty().to_string())));
+                new_generics.push(generic.path(&module));
               } else {
                 new_generics.push(generic);
               }
             }
             b.generics = new_generics;
+
+            if let Some(parse_as) = b.parse_as.take() {
+              b.parse_as = Some(Box::new(qualify_type(
+                *parse_as,
+                &i.element.get_type(),
+                &module,
+              )));
+            }
           }
 
-          let mut field = Field::new(
+          let field = Field::new(
             b.xml_name.clone(),
             &field_name,
             b,
             children_are_attributes,
             flatten_children,
           )
-          .vis("pub");
-
-          let mut name_conflict = match &a.fields {
-            Fields::Empty => false,
-            Fields::Tuple(_) => false,
-            Fields::Named(a_fields) => {
-              let mut conflict = false;
-              for a_field in a_fields {
-                if a_field.name == field.name {
-                  conflict = true;
-                  break;
-                }
-              }
-
-              conflict
-            }
-          };
-
-          if settings.conflict_prefix.is_none() {
-            name_conflict = false;
-          }
-
-          if name_conflict {
-            field.name = format!("{}{}", settings.conflict_prefix.unwrap(), field.name);
-          }
-
-          a.push_field(field);
+          .vis("pub")
+          .provenance(Provenance::new(other.name.clone()));
+          add_field(&mut a.fields, field, &settings);
 
           self.merge_inner(other.inner);
         }
       },
-      XsdImplType::Enum(a) => {
-        match &other.element {
-          XsdImplType::Struct(b) => {
-            let field_name = to_field_name(
-              other
-                .fieldname_hint
-                .as_ref()
-                .unwrap_or_else(|| &b.ty().name),
-            );
-            let ty = b.ty().clone();
+      XsdImplType::Enum(a) => match &other.element {
+        XsdImplType::Struct(b) => {
+          let field_name = to_field_name(
+            other
+              .fieldname_hint
+              .as_ref()
+              .unwrap_or_else(|| &b.ty().name),
+          );
+          let ty = b.ty().clone();
 
-            other.fieldname_hint = Some(field_name.clone());
+          other.fieldname_hint = Some(field_name.clone());
 
-            let ty = ty.path(&to_field_name(&a.ty().name));
+          let ty = ty.path(&to_module_name(&a.ty().name));
 
-            let variant = Variant::new(b.ty().xml_name.clone(), &to_struct_name(&field_name))
-              .tuple(None, ty, children_are_attributes, flatten_children);
-            a.variants.push(variant);
+          let variant = Variant::new(element_name(b.ty()), &to_variant_name(&field_name))
+            .tuple(None, ty, children_are_attributes, flatten_children)
+            .provenance(Provenance::new(other.name.clone()));
+          a.variants.push(variant);
 
-            self.merge_inner(vec![other]);
-          }
-          XsdImplType::Enum(b) => {
-            let field_name = to_field_name(
-              other
-                .fieldname_hint
-                .as_ref()
-                .unwrap_or_else(|| &b.ty().name),
-            );
-            let mut ty = b.ty().clone();
+          self.merge_inner(vec![other]);
+        }
+        XsdImplType::Enum(b) => {
+          let field_name = to_field_name(
+            other
+              .fieldname_hint
+              .as_ref()
+              .unwrap_or_else(|| &b.ty().name),
+          );
+          let mut ty = b.ty().clone();
 
-            other.fieldname_hint = Some(field_name.clone());
+          other.fieldname_hint = Some(field_name.clone());
 
-            ty.name = format!("{}::{}", to_field_name(&a.ty().name), ty.name);
+          ty.name = format!("{}::{}", to_module_name(&a.ty().name), ty.name);
 
-            let variant = Variant::new(None, &to_struct_name(&field_name)).tuple(
-              None,
-              ty,
-              children_are_attributes,
-              flatten_children,
-            );
-            a.variants.push(variant);
+          let variant = Variant::new(element_name(&ty), &to_variant_name(&field_name))
+            .tuple(None, ty, children_are_attributes, flatten_children)
+            .provenance(Provenance::new(other.name.clone()));
+          a.variants.push(variant);
 
-            self.merge_inner(vec![other]);
-          }
-          XsdImplType::Type(b) | XsdImplType::TypeAlias(TypeAlias { alias: b, .. }) => {
-            let field_name = to_struct_name(other.fieldname_hint.as_ref().unwrap_or(&b.name));
+          self.merge_inner(vec![other]);
+        }
+        XsdImplType::Type(b) | XsdImplType::TypeAlias(TypeAlias { alias: b, .. }) => {
+          let field_name = to_variant_name(other.fieldname_hint.as_ref().unwrap_or(&b.name));
 
-            let mut b = b.clone();
-            for i in &mut other.inner {
-              if let XsdImplType::Type(_) | XsdImplType::TypeAlias(..) = i.element {
-                continue;
-              }
+          let mut b = b.clone();
+          let module = to_module_name(&a.ty().to_string());
+          for i in &mut other.inner {
+            if let XsdImplType::Type(_) | XsdImplType::TypeAlias(..) = i.element {
+              continue;
+            }
 
-              if i.element.get_type() == b {
-                b = b.path(&to_field_name(&a.ty().to_string()));
-              }
+            if i.element.get_type() == b {
+              b = b.path(&module);
+            }
 
-              let mut new_generics = vec![];
-              for generic in b.generics {
-                if i.element.get_type() == generic {
-                  new_generics.push(generic.path(&to_field_name(&a.ty().to_string())));
-                } else {
-                  new_generics.push(generic);
-                }
+            let mut new_generics = vec![];
+            for generic in b.generics {
+              if i.element.get_type() == generic {
+                new_generics.push(generic.path(&module));
+              } else {
+                new_generics.push(generic);
               }
-              b.generics = new_generics;
             }
+            b.generics = new_generics;
 
-            let variant = Variant::new(None, &field_name).tuple(
-              None,
-              b,
-              children_are_attributes,
-              flatten_children,
-            );
+            if let Some(parse_as) = b.parse_as.take() {
+              b.parse_as = Some(Box::new(qualify_type(
+                *parse_as,
+                &i.element.get_type(),
+                &module,
+              )));
+            }
+          }
 
-            a.variants.push(variant);
+          let variant = Variant::new(element_name(&b), &field_name)
+            .tuple(None, b, children_are_attributes, flatten_children)
+            .provenance(Provenance::new(other.name.clone()));
 
-            self.merge_inner(other.inner);
-          }
+          a.variants.push(variant);
+
+          self.merge_inner(other.inner);
         }
-      }
+      },
       XsdImplType::Type(_) => unimplemented!("Cannot merge into type."),
       XsdImplType::TypeAlias(..) => unimplemented!("Cannot merge into type alias."),
     }
@@ -513,7 +1057,7 @@ impl XsdImpl {
 }
 
 pub enum SearchResult<'a> {
-  MultipleMatches,
+  MultipleMatches(Vec<XsdName>),
   NoMatches,
   SingleMatch(&'a XsdImpl),
 }
@@ -521,104 +1065,279 @@ pub enum SearchResult<'a> {
 #[derive(Clone, Debug)]
 pub struct XsdContext {
   pub namespace: Namespace,
-  pub xml_schema_prefix: Option<String>,
+  /// The target namespace of the schema being filled, `None` when it has no `targetNamespace`.
+  pub target_namespace: Option<String>,
   pub structs: BTreeMap<XsdName, XsdImpl>,
+  pub load_options: LoadOptions,
+  /// The options the context is filled with, see `set_options`. The contexts of the imported
+  /// documents are filled with the same ones.
+  pub options: GeneratorOptions,
+  pub fixed_values: FixedValues,
+  pub verbose_codegen: bool,
+  pub field_metadata: bool,
+  pub flatten_wrappers: bool,
+  /// Whether the types derive serde's traits, see `GeneratorOptions::serde`.
+  pub serde: bool,
+  /// Whether the patterns are checked, see `GeneratorOptions::pattern_checks`.
+  pub pattern_checks: bool,
+  pub string_storage: StringStorage,
+  /// The Rust types of the overridden builtin types, see `GeneratorOptions::builtin_types`.
+  pub builtin_types: BTreeMap<String, String>,
+  /// Components generated as a stub, see `GeneratorOptions::stubs`.
+  pub stubs: BTreeMap<XsdName, StubKind>,
+  /// Whether the schema is embedded in another document, whose other schemas can provide the
+  /// namespaces it imports.
+  pub embedded: bool,
+  /// Constructs requiring a version of XSD newer than 1.0, in the schema and the ones it imports.
+  pub versions: Vec<VersionUse>,
+  /// References to builtin types with a lossy mapping, in the schema and the ones it imports.
+  pub lossy_mappings: Vec<LossyMapping>,
+  /// The largest `maxOccurs` read as a bound, see `GeneratorOptions::max_occurs_limit`.
+  pub max_occurs_limit: u64,
+  /// The `maxOccurs` above `max_occurs_limit`, in the schema and the ones it imports.
+  pub unbounded_max_occurs: Vec<Warning>,
+  /// The Rust names of the types declared at the top level of the schema, see `insert_impl`.
+  pub type_names: BTreeSet<String>,
+  /// Location of the schema document, recorded in the provenance of what it generates.
+  pub location: Option<String>,
+  /// Enumeration values that are not values of the base of their restriction, in the schema and
+  /// the ones it imports.
+  pub invalid_enumerations: Vec<Warning>,
+  /// Hash of the definition of the top-level components, in the schema and the ones it imports.
+  /// See `SchemaOptions::content_hash`.
+  pub content_hashes: BTreeMap<XsdName, String>,
+  /// The components of the imported documents, shared with other contexts filled with the same
+  /// options, see `Workspace`. Each context loads its imports when `None`.
+  pub import_cache: Option<ImportCache>,
+  /// Stops filling the context, see `GeneratorOptions::cancel_token`.
+  pub cancel_token: CancellationToken,
+  /// The substitution groups of the schema and the ones it imports.
+  pub substitution_groups: Substitutions,
+  /// The complex types derived from another one, in the schema and the ones it imports.
+  pub derived_types: Substitutions,
+  /// The documents being loaded, from the loaded schema to the one of this context, see
+  /// `LoadOptions::document_key`, with the namespace they are imported with. Importing one of
+  /// them again is a cycle.
+  pub import_chain: Vec<(String, Option<String>)>,
 }
 
 impl XsdContext {
-  pub fn new(content: &str) -> Result<Self, XsdError> {
-    let cursor = Cursor::new(content);
-    let parser = EventReader::new(cursor);
-
-    for xml_element in parser {
-      match xml_element {
-        Ok(XmlEvent::StartElement {
-          name, namespace, ..
-        }) => {
-          if name.namespace == Some("http://www.w3.org/2001/XMLSchema".to_string())
-            && name.local_name == "schema"
-          {
-            let namespace_uri = &name.namespace.unwrap();
-            let impl_basic_type = |name: &str, ty: &str| -> (XsdName, XsdImpl) {
-              let xsd_name = XsdName {
-                namespace: Some(namespace_uri.clone()),
-                local_name: name.to_string(),
-                ty: XsdType::SimpleType,
-              };
-
-              // let mut r#impl = Impl::new(ty).impl_trait("ParseXsd").to_owned();
-              // let func = r#impl.new_fn("parse");
-              // func.line("element.get_content()?");
-              // let func = r#impl.new_fn("parse_attribute");
-              // func.line("element.get_attribute()?");
-
-              let imp = XsdImpl {
-                name: xsd_name.clone(),
-                fieldname_hint: None,
-                element: XsdImplType::Type(Type::new(None, ty)),
-                inner: vec![],
-                implementation: vec![],
-                flatten: false,
-              };
-
-              (xsd_name, imp)
-            };
-
-            return Ok(XsdContext {
-              namespace,
-              xml_schema_prefix: None,
-              structs: BTreeMap::from_iter(
-                [
-                  ("bool", "bool"),
-                  ("boolean", "bool"),
-                  ("positiveInteger", "u64"),
-                  ("byte", "u8"),
-                  ("unsignedByte", "u8"),
-                  ("short", "i16"),
-                  ("unsignedShort", "u16"),
-                  ("int", "i32"),
-                  ("integer", "i32"),
-                  ("unsignedInt", "u32"),
-                  ("long", "i64"),
-                  ("unsignedLong", "u64"),
-                  ("nonNegativeInteger", "u64"),
-                  ("double", "f64"),
-                  ("decimal", "f64"),
-                  ("string", "String"),
-                  ("normalizedString", "String"),
-                  ("anyURI", "String"),
-                  ("NMTOKEN", "String"),
-                  ("token", "String"),
-                  ("language", "String"),
-                  ("hexBinary", "String"),
-                  ("dateTime", "String"),
-                  ("base64Binary", "String"),
-                  ("duration", "String"),
-                  ("gYear", "u16"),
-                  ("ID", "String"),
-                  ("IDREF", "String"),
-                  ("IDREFS", "String"),
-                  ("anyType", "String"),
-                  ("date", "Date"),
-                  ("NCName", "String"),
-                ]
-                .map(|(n, t)| impl_basic_type(n, t)),
-              ),
-            });
-          }
+  /// Create the context of a schema document from its parsed root, which must be a `schema`
+  /// element.
+  pub fn new(root: &xmltree::Element) -> Result<Self, XsdError> {
+    if root.namespace.as_deref() != Some("http://www.w3.org/2001/XMLSchema")
+      || root.name != "schema"
+    {
+      return Err(
+        SchemaError::Invalid(XsdParseError {
+          node_name: "schema".to_string(),
+          msg: "Bad XML Schema, unable to find schema element.".to_string(),
+        })
+        .into(),
+      );
+    }
+
+    Ok(Self::for_schema(root))
+  }
+
+  /// Create the context for a schema element of an already parsed document, using the
+  /// namespaces in scope at that element.
+  pub fn for_schema(element: &xmltree::Element) -> Self {
+    Self::with_namespace(
+      element
+        .namespaces
+        .clone()
+        .unwrap_or_else(|| NamespaceStack::default().squash()),
+    )
+  }
+
+  fn with_namespace(namespace: Namespace) -> Self {
+    let namespace_uri = "http://www.w3.org/2001/XMLSchema";
+    let impl_basic_type = |name: &str, ty: &str| -> (XsdName, XsdImpl) {
+      let xsd_name = XsdName {
+        namespace: Some(namespace_uri.to_string()),
+        local_name: name.to_string(),
+        ty: XsdType::SimpleType,
+      };
+
+      // let mut r#impl = Impl::new(ty).impl_trait("ParseXsd").to_owned();
+      // let func = r#impl.new_fn("parse");
+      // func.line("element.get_content()?");
+      // let func = r#impl.new_fn("parse_attribute");
+      // func.line("element.get_attribute()?");
+
+      let imp = XsdImpl {
+        name: xsd_name.clone(),
+        fieldname_hint: None,
+        element: XsdImplType::Type(Type::new(None, ty)),
+        inner: vec![],
+        implementation: vec![],
+        flatten: false,
+        // Any content goes in an `xs:anyType`.
+        content: if name == "anyType" {
+          ContentKind::Complex
+        } else {
+          ContentKind::Simple
+        },
+        attribute_groups: vec![],
+      };
+
+      (xsd_name, imp)
+    };
+
+    XsdContext {
+      namespace,
+      target_namespace: None,
+      load_options: LoadOptions::default(),
+      options: GeneratorOptions::default(),
+      fixed_values: FixedValues::default(),
+      verbose_codegen: false,
+      field_metadata: false,
+      flatten_wrappers: false,
+      serde: false,
+      pattern_checks: false,
+      string_storage: StringStorage::default(),
+      builtin_types: BTreeMap::new(),
+      stubs: BTreeMap::new(),
+      embedded: false,
+      versions: vec![],
+      lossy_mappings: vec![],
+      max_occurs_limit: DEFAULT_MAX_OCCURS_LIMIT,
+      unbounded_max_occurs: vec![],
+      type_names: BTreeSet::new(),
+      location: None,
+      invalid_enumerations: vec![],
+      content_hashes: BTreeMap::new(),
+      import_cache: None,
+      cancel_token: CancellationToken::default(),
+      substitution_groups: Substitutions::default(),
+      derived_types: Substitutions::default(),
+      import_chain: vec![],
+      structs: BTreeMap::from_iter(
+        [
+          ("bool", "bool"),
+          ("boolean", "bool"),
+          ("positiveInteger", "u64"),
+          ("byte", "u8"),
+          ("unsignedByte", "u8"),
+          ("short", "i16"),
+          ("unsignedShort", "u16"),
+          ("int", "i32"),
+          ("integer", "i32"),
+          ("unsignedInt", "u32"),
+          ("long", "i64"),
+          ("unsignedLong", "u64"),
+          ("nonNegativeInteger", "u64"),
+          ("double", "f64"),
+          ("decimal", "Decimal"),
+          ("string", "String"),
+          ("normalizedString", "String"),
+          ("anyURI", "String"),
+          ("NMTOKEN", "String"),
+          ("token", "String"),
+          ("language", "String"),
+          ("hexBinary", "xml_schema_parser::HexBinary"),
+          ("dateTime", "xml_schema_parser::DateTime"),
+          ("base64Binary", "xml_schema_parser::Base64Binary"),
+          ("duration", "xml_schema_parser::Duration"),
+          ("dateTimeStamp", "xml_schema_parser::DateTime"),
+          ("dayTimeDuration", "xml_schema_parser::Duration"),
+          ("yearMonthDuration", "xml_schema_parser::Duration"),
+          ("gYear", "xml_schema_parser::GYear"),
+          ("gYearMonth", "xml_schema_parser::GYearMonth"),
+          ("gMonthDay", "xml_schema_parser::GMonthDay"),
+          ("gDay", "xml_schema_parser::GDay"),
+          ("gMonth", "xml_schema_parser::GMonth"),
+          ("ID", "String"),
+          ("IDREF", "String"),
+          ("IDREFS", "String"),
+          ("anyType", "xml_schema_parser::AnyXml"),
+          ("date", "Date"),
+          ("time", "xml_schema_parser::Time"),
+          ("NCName", "String"),
+          ("QName", "xml_schema_parser::QName"),
+        ]
+        .map(|(n, t)| impl_basic_type(n, t)),
+      ),
+    }
+  }
+
+  /// Fill the context with `options`, returning the names of `options.builtin_types` that are
+  /// not builtin types. Has to be set before the schema is added to the context.
+  pub fn set_options(&mut self, options: &GeneratorOptions) -> Vec<String> {
+    self.options = options.clone();
+    self.fixed_values = options.fixed_values;
+    self.verbose_codegen = options.verbose_codegen;
+    self.field_metadata = options.field_metadata;
+    self.flatten_wrappers = options.flatten_wrappers;
+    self.serde = options.serde;
+    self.pattern_checks = options.pattern_checks;
+    self.stubs = options.stubs.clone();
+    self.cancel_token = options.cancel_token.clone();
+    self.max_occurs_limit = options.max_occurs_limit.unwrap_or(DEFAULT_MAX_OCCURS_LIMIT);
+    self.set_string_storage(options.string_storage.clone());
+    self.set_builtin_types(options.builtin_types.clone())
+  }
+
+  /// Use `storage` for the builtin types parsed into strings. Has to be set before the schema
+  /// is added to the context.
+  pub fn set_string_storage(&mut self, storage: StringStorage) {
+    let current = self.string_storage.to_type();
+    let replacement = storage.to_type();
+
+    for (name, imp) in self.structs.iter_mut() {
+      if name.namespace.as_deref() != Some("http://www.w3.org/2001/XMLSchema") {
+        continue;
+      }
+
+      if let XsdImplType::Type(ty) = &mut imp.element {
+        if ty.to_string() == current.to_string() {
+          ty.name = replacement.name.clone();
+          ty.generics = replacement.generics.clone();
         }
-        Err(_) => break,
-        _ => {}
       }
     }
 
-    Err(
-      XsdIoError::XsdParseError(XsdParseError {
-        node_name: "schema".to_string(),
-        msg: "Bad XML Schema, unable to found schema element.".to_string(),
-      })
-      .into(),
-    )
+    self.string_storage = storage;
+  }
+
+  /// Read the builtin types named in `types` into the given Rust types, returning the names that
+  /// are not builtin types. Has to be set before the schema is added to the context.
+  pub fn set_builtin_types(&mut self, types: BTreeMap<String, String>) -> Vec<String> {
+    let mut unknown = vec![];
+    for (builtin, ty) in &types {
+      let name = XsdName {
+        namespace: Some("http://www.w3.org/2001/XMLSchema".to_string()),
+        local_name: builtin.clone(),
+        ty: XsdType::SimpleType,
+      };
+      match self.structs.get_mut(&name).map(|imp| &mut imp.element) {
+        Some(XsdImplType::Type(current)) => *current = Type::new(None, ty),
+        _ => unknown.push(builtin.clone()),
+      }
+    }
+
+    self.builtin_types = types;
+    unknown
+  }
+
+  /// The stub replacing the component `name`, if any, see `matches`.
+  pub fn stub(&self, name: &XsdName) -> Option<StubKind> {
+    self
+      .stubs
+      .iter()
+      .find(|(stub, _)| self.matches(stub, name))
+      .map(|(_, kind)| *kind)
+  }
+
+  /// Whether `pattern`, a name given in the options, designates the component `name`. The
+  /// namespace of the pattern can be a prefix declared by the schema, a pattern without
+  /// namespace matches the component in any namespace.
+  pub fn matches(&self, pattern: &XsdName, name: &XsdName) -> bool {
+    pattern.local_name == name.local_name
+      && pattern.ty == name.ty
+      && (pattern.namespace.is_none()
+        || self.resolve_namespace(pattern.namespace.as_deref()) == name.namespace)
   }
 
   fn resolve_namespace(&self, namespace: Option<&str>) -> Option<String> {
@@ -633,6 +1352,81 @@ impl XsdContext {
     }
   }
 
+  /// The name of `name` with the prefix this schema declares for its namespace, e.g.
+  /// `mxl:note-type`. The namespace is written in full when the schema has no prefix for it.
+  fn prefixed_name(&self, name: &XsdName) -> String {
+    let Some(namespace) = &name.namespace else {
+      return name.local_name.clone();
+    };
+    if self.namespace.get(namespace).is_some() {
+      return name.to_string();
+    }
+
+    let prefix = self
+      .namespace
+      .0
+      .iter()
+      .filter(|(prefix, uri)| !prefix.is_empty() && *uri == namespace)
+      .map(|(prefix, _)| prefix)
+      .min();
+    match prefix {
+      Some(prefix) => format!("{prefix}:{}", name.local_name),
+      None if self.namespace.get("") == Some(namespace.as_str()) => name.local_name.clone(),
+      None => name.to_string(),
+    }
+  }
+
+  /// Check that `value` is a value of the simple type `base` as the generated code reads it: the
+  /// builtins read into numbers, booleans and dates, and the generated simple types through the
+  /// enum, wrapper or alias they are generated as. The types not in the context accept any value.
+  pub fn check_lexical(&self, base: &XsdName, value: &str) -> Result<(), String> {
+    let base = XsdName {
+      ty: XsdType::SimpleType,
+      ..base.clone()
+    };
+    match self.search(&base) {
+      Some(imp) => self.check_impl_lexical(imp, value),
+      None => Ok(()),
+    }
+  }
+
+  fn check_impl_lexical(&self, imp: &XsdImpl, value: &str) -> Result<(), String> {
+    match &imp.element {
+      XsdImplType::Type(ty) if is_lexical(&ty.name, value) => Ok(()),
+      XsdImplType::Type(_) => Err(format!("`{value}` is not a valid {}", imp.name.local_name)),
+      XsdImplType::TypeAlias(alias) => self.check_type_lexical(&alias.value, value),
+      XsdImplType::Struct(r#struct) => match r#struct.fields.fields() {
+        [field] if field.name.is_none() => self.check_type_lexical(&field.ty, value),
+        _ => Ok(()),
+      },
+      // Only the enums of enumerations know their values, not the ones of unions.
+      XsdImplType::Enum(r#enum) => match r#enum
+        .variants
+        .iter()
+        .map(|variant| variant.xml_value.as_deref())
+        .collect::<Option<Vec<_>>>()
+      {
+        Some(values) if !values.contains(&value) => {
+          Err(format!("`{value}` is not one of {}", values.join(", ")))
+        }
+        _ => Ok(()),
+      },
+    }
+  }
+
+  /// Check `value` against the type wrapped or aliased by a generated type, either another
+  /// generated type or the Rust type of a builtin.
+  fn check_type_lexical(&self, ty: &Type, value: &str) -> Result<(), String> {
+    let generated = self.structs.values().find(|imp| {
+      !matches!(imp.element, XsdImplType::Type(_)) && imp.element.get_type().name == ty.name
+    });
+    match generated {
+      Some(imp) => self.check_impl_lexical(imp, value),
+      None if is_lexical(&ty.name, value) => Ok(()),
+      None => Err(format!("`{value}` is not a valid {}", ty.name)),
+    }
+  }
+
   pub fn remove_impl(&mut self, name: &XsdName) -> Option<XsdImpl> {
     let namespace = self.resolve_namespace(name.namespace.as_deref());
 
@@ -643,27 +1437,38 @@ impl XsdContext {
     })
   }
 
+  /// Add the implementation of the top-level component `name`. A type named the same as an
+  /// implementation already in the context is suffixed with the kind of its component, e.g.
+  /// `DynamicsElement`. The types declared by the schema keep their name whatever the order the
+  /// components are generated in: the type of another kind of component named the same, e.g. the
+  /// anonymous type of an element, is always the one suffixed.
+  ///
+  /// The builtin types are never replaced: a declaration of `string` or `date` in the XML Schema
+  /// namespace itself, e.g. by a copy of `XMLSchema.xsd` the schema imports, is ignored.
   pub fn insert_impl(&mut self, name: XsdName, mut value: XsdImpl) {
     let namespace = self.resolve_namespace(name.namespace.as_deref());
+    if self.is_builtin(&XsdName {
+      namespace: namespace.clone(),
+      local_name: name.local_name.clone(),
+      ty: name.ty,
+    }) {
+      tracing::debug!("{name} is a builtin type, its declaration is ignored");
+      return;
+    }
 
     let ty = value.element.get_type();
-
-    for s in self.structs.values() {
-      if s.element.get_type().to_string() == ty.to_string() {
-        let old_type = value.element.get_type();
-        value.element.set_type(format!(
-          "{}{}",
-          ty.to_string(),
-          to_struct_name(&format!("{:?}", value.name.ty))
-        ));
-        for implementation in &mut value.implementation {
-          if implementation.target == old_type {
-            implementation.target = value.element.get_type();
-          }
-        }
-        break;
-      }
+    let is_type = matches!(name.ty, XsdType::ComplexType | XsdType::SimpleType);
+
+    if (!is_type && self.type_names.contains(&ty.to_string()))
+      || self
+        .structs
+        .values()
+        .any(|s| s.element.get_type().to_string() == ty.to_string())
+    {
+      let name = format!("{}{}", ty, to_struct_name(&format!("{:?}", value.name.ty)));
+      value.rename(name, self);
     }
+    value.locate_provenance(self);
 
     self.structs.insert(
       XsdName {
@@ -675,6 +1480,15 @@ impl XsdContext {
     );
   }
 
+  /// Whether `name`, with its namespace resolved, is one of the builtin types of XML Schema.
+  fn is_builtin(&self, name: &XsdName) -> bool {
+    name.namespace.as_deref() == Some("http://www.w3.org/2001/XMLSchema")
+      && matches!(
+        self.structs.get(name).map(|imp| &imp.element),
+        Some(XsdImplType::Type(_))
+      )
+  }
+
   pub fn search(&self, name: &XsdName) -> Option<&XsdImpl> {
     let namespace = self.resolve_namespace(name.namespace.as_deref());
 
@@ -690,28 +1504,30 @@ impl XsdContext {
     namespace: Option<String>,
     name: String,
     types: &[XsdType],
-  ) -> SearchResult {
-    let mut output = SearchResult::NoMatches;
+  ) -> SearchResult<'_> {
+    let mut matches = vec![];
     for ty in types {
-      if let Some(result) = self.search(&XsdName {
+      let candidate = XsdName {
         namespace: namespace.clone(),
         local_name: name.clone(),
         ty: *ty,
-      }) {
-        if let SearchResult::SingleMatch(_) = output {
-          return SearchResult::MultipleMatches;
-        }
-        output = SearchResult::SingleMatch(result);
+      };
+      if let Some(result) = self.search(&candidate) {
+        matches.push((candidate, result));
       }
     }
 
-    output
+    match matches.len() {
+      0 => SearchResult::NoMatches,
+      1 => SearchResult::SingleMatch(matches.remove(0).1),
+      _ => SearchResult::MultipleMatches(matches.into_iter().map(|(name, _)| name).collect()),
+    }
   }
 }
 
 #[test]
 fn bad_schema_definition() {
-  let context = XsdContext::new(
+  let context = crate::Xsd::new(
     r#"
     <xs:schema
       xmlns="http://www.w3.org/2001/XMLSchema"
@@ -721,4 +1537,450 @@ fn bad_schema_definition() {
   );
 
   assert!(context.is_err());
+
+  let root = xmltree::Element::parse(r#"<schema xmlns="urn:score"/>"#.as_bytes()).unwrap();
+  assert!(XsdContext::new(&root).is_err());
+}
+
+#[test]
+fn ambiguous_search_keeps_candidates() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:simpleType name="dup">
+        <xs:restriction base="xs:string" />
+      </xs:simpleType>
+      <xs:complexType name="dup">
+        <xs:sequence>
+          <xs:element name="a" type="xs:string" />
+        </xs:sequence>
+      </xs:complexType>
+      <xs:complexType name="thing">
+        <xs:complexContent>
+          <xs:extension base="dup" />
+        </xs:complexContent>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let error = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap_err();
+  assert_eq!(error.error_code(), "context-search");
+
+  match error {
+    XsdError::ContextSearchError {
+      name, candidates, ..
+    } => {
+      assert_eq!(name.local_name, "dup");
+      assert_eq!(
+        candidates.iter().map(|c| c.ty).collect::<Vec<_>>(),
+        vec![
+          xsd_types::XsdType::SimpleType,
+          xsd_types::XsdType::ComplexType
+        ]
+      );
+    }
+    error => panic!("unexpected error {error:?}"),
+  }
+}
+
+#[test]
+fn string_storage_replaces_builtin_strings() {
+  let schema = r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="note">
+        <xs:sequence>
+          <xs:element name="pitch" type="xs:string" />
+          <xs:element name="octave" type="xs:int" />
+        </xs:sequence>
+        <xs:attribute name="href" type="xs:anyURI" />
+      </xs:complexType>
+    </xs:schema>
+  "#;
+
+  let generate = |string_storage| {
+    crate::Xsd::new(schema)
+      .unwrap()
+      .generate_with(&crate::GeneratorOptions {
+        string_storage,
+        ..Default::default()
+      })
+      .unwrap()
+  };
+
+  let boxed = generate(StringStorage::BoxStr);
+  assert!(boxed.contains("pub pitch: Box<str>"));
+  assert!(boxed.contains("pub octave: i32"));
+  assert!(boxed.contains("pub href: Option<Box<str>>"));
+
+  let custom = generate(StringStorage::Custom("compact::Text".to_string()));
+  assert!(custom.contains("pub pitch: compact::Text"));
+  assert!(custom.contains("<Option<compact::Text> as XsdGen>::gen"));
+}
+
+#[test]
+fn merged_content_fields_precede_attributes() {
+  let named = |name: &str, ty: XsdType| XsdName::new(name, ty);
+  let field = |name: &str, ty: XsdType| XsdImpl {
+    name: named(name, ty),
+    fieldname_hint: Some(name.to_string()),
+    element: XsdImplType::Type(Type::new(Some(named(name, ty)), "String")),
+    inner: vec![],
+    implementation: vec![],
+    flatten: false,
+    content: ContentKind::Simple,
+    attribute_groups: vec![],
+  };
+
+  let mut pair = XsdImpl {
+    name: named("pair", XsdType::ComplexType),
+    fieldname_hint: None,
+    element: XsdImplType::Struct(Struct::new(None, "Pair").vis("pub")),
+    inner: vec![],
+    implementation: vec![],
+    flatten: false,
+    content: ContentKind::Empty,
+    attribute_groups: vec![],
+  };
+
+  // The attributes are merged first, the content still comes before them.
+  pair.merge(field("id", XsdType::Attribute), MergeSettings::ATTRIBUTE);
+
+  let mut sequence = XsdImpl {
+    name: named("pair", XsdType::Sequence),
+    fieldname_hint: None,
+    element: XsdImplType::Struct(Struct::new(None, "PairSequence").vis("pub")),
+    inner: vec![],
+    implementation: vec![],
+    flatten: false,
+    content: ContentKind::Empty,
+    attribute_groups: vec![],
+  };
+  sequence.merge(field("note", XsdType::Element), MergeSettings::default());
+  pair.merge(sequence, MergeSettings::default());
+  pair.merge(field("rest", XsdType::Element), MergeSettings::default());
+  pair.merge(field("lang", XsdType::Attribute), MergeSettings::ATTRIBUTE);
+
+  let names = match &pair.element {
+    XsdImplType::Struct(r#struct) => match &r#struct.fields {
+      Fields::Named(fields) => fields
+        .iter()
+        .map(|field| (field.name(), field.attribute))
+        .collect::<Vec<_>>(),
+      _ => panic!("expected named fields"),
+    },
+    _ => panic!("expected a struct"),
+  };
+  assert_eq!(
+    names,
+    vec![
+      ("note", false),
+      ("rest", false),
+      ("id", true),
+      ("lang", true)
+    ]
+  );
+}
+
+#[test]
+fn tuple_and_named_fields_are_merged_alike() {
+  let named = |name: &str, ty: XsdType| XsdName::new(name, ty);
+  let field = |name: &str, ty: XsdType| XsdImpl {
+    name: named(name, ty),
+    fieldname_hint: Some(name.to_string()),
+    element: XsdImplType::Type(Type::new(Some(named(name, ty)), "String")),
+    inner: vec![],
+    implementation: vec![],
+    flatten: false,
+    content: ContentKind::Simple,
+    attribute_groups: vec![],
+  };
+
+  for tuple in [false, true] {
+    let mut r#struct = Struct::new(None, "Pair").vis("pub");
+    if tuple {
+      r#struct.fields = Fields::Tuple(vec![]);
+    }
+    let mut pair = XsdImpl {
+      name: named("pair", XsdType::ComplexType),
+      fieldname_hint: None,
+      element: XsdImplType::Struct(r#struct),
+      inner: vec![],
+      implementation: vec![],
+      flatten: false,
+      content: ContentKind::Empty,
+      attribute_groups: vec![],
+    };
+
+    pair.merge(field("id", XsdType::Attribute), MergeSettings::ATTRIBUTE);
+    pair.merge(field("note", XsdType::Element), MergeSettings::default());
+    pair.merge(field("items", XsdType::Group), MergeSettings::default());
+    pair.merge(field("id", XsdType::Attribute), MergeSettings::ATTRIBUTE);
+
+    let fields = match &pair.element {
+      XsdImplType::Struct(r#struct) => match (&r#struct.fields, tuple) {
+        (Fields::Tuple(fields), true) | (Fields::Named(fields), false) => fields,
+        (fields, _) => panic!("unexpected fields {fields:?}"),
+      },
+      _ => panic!("expected a struct"),
+    };
+    let fields = fields
+      .iter()
+      .map(|field| {
+        (
+          field.name(),
+          field.xml_name.as_ref().unwrap().local_name.as_str(),
+          field.attribute,
+          field.flatten,
+        )
+      })
+      .collect::<Vec<_>>();
+
+    let expected = [
+      ("note", "note", false, false),
+      ("items", "items", false, true),
+      ("id", "id", true, false),
+      ("attr_id", "id", true, false),
+    ]
+    .map(|(name, xml_name, attribute, flatten)| {
+      (if tuple { "" } else { name }, xml_name, attribute, flatten)
+    });
+    assert_eq!(fields, expected, "tuple: {tuple}");
+  }
+}
+
+#[test]
+fn attribute_groups_are_merged_once() {
+  let named = |name: &str| XsdName::new(name, XsdType::AttributeGroup);
+  // A reference to the group `name` including `includes`, read into the field `hint`.
+  let group = |name: &str, hint: &str, includes: &[&str]| XsdImpl {
+    name: named(name),
+    fieldname_hint: Some(hint.to_string()),
+    element: XsdImplType::Type(Type::new(None, &to_struct_name(name))),
+    inner: vec![],
+    implementation: vec![],
+    flatten: true,
+    content: ContentKind::Empty,
+    attribute_groups: std::iter::once(name)
+      .chain(includes.iter().copied())
+      .map(named)
+      .collect(),
+  };
+
+  let mut direction = XsdImpl {
+    name: XsdName::new("direction", XsdType::ComplexType),
+    fieldname_hint: None,
+    element: XsdImplType::Struct(Struct::new(None, "Direction").vis("pub")),
+    inner: vec![],
+    implementation: vec![],
+    flatten: false,
+    content: ContentKind::Empty,
+    attribute_groups: vec![],
+  };
+
+  // `print-style` reads `position` in place of the field merged first.
+  direction.merge(group("position", "position", &[]), MergeSettings::ATTRIBUTE);
+  direction.merge(
+    group("print-style", "print_style", &["position"]),
+    MergeSettings::ATTRIBUTE,
+  );
+  direction.merge(group("position", "position", &[]), MergeSettings::ATTRIBUTE);
+  direction.merge(
+    group("print-style", "print_style", &["position"]),
+    MergeSettings::ATTRIBUTE,
+  );
+  // Another group with the same field name is still merged.
+  direction.merge(
+    group("placement", "print_style", &[]),
+    MergeSettings::ATTRIBUTE,
+  );
+
+  let names = match &direction.element {
+    XsdImplType::Struct(r#struct) => r#struct
+      .fields
+      .fields()
+      .iter()
+      .map(|field| field.name().to_string())
+      .collect::<Vec<_>>(),
+    _ => panic!("expected a struct"),
+  };
+  assert_eq!(names, ["print_style", "attr_print_style"]);
+  assert_eq!(
+    direction.attribute_groups,
+    ["position", "print-style", "placement"].map(named)
+  );
+}
+
+#[test]
+fn inner_modules_allow_unused_items() {
+  let xsd = crate::Xsd::new(
+    r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:complexType name="measure">
+        <xs:sequence>
+          <xs:element name="number" type="xs:int" />
+          <xs:sequence maxOccurs="unbounded">
+            <xs:choice minOccurs="0">
+              <xs:element name="note" type="xs:string" />
+              <xs:element name="rest" type="xs:string" />
+            </xs:choice>
+          </xs:sequence>
+        </xs:sequence>
+      </xs:complexType>
+    </xs:schema>
+  "#,
+  )
+  .unwrap();
+
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions::default())
+    .unwrap();
+  assert!(output
+    .contains("\n#[allow(dead_code, unused_imports)]\npub mod measure {\n    use super::*;\n"));
+  assert!(output.contains(
+    "\n    #[allow(dead_code, unused_imports)]\n    pub mod noterest_item {\n        use super::super::*;\n"
+  ));
+
+  let output = xsd
+    .generate_with(&crate::GeneratorOptions {
+      inner_module_lints: Some(vec![]),
+      ..Default::default()
+    })
+    .unwrap();
+  assert!(!output.contains("#[allow(dead_code"));
+  assert!(output.contains("\n    pub mod noterest_item {\n        use super::super::*;\n"));
+
+  // Inner types only using the prelude don't import anything.
+  let alias = |name: &str, value: &str| XsdImpl {
+    name: XsdName::new(name, XsdType::SimpleType),
+    fieldname_hint: None,
+    element: XsdImplType::TypeAlias(TypeAlias {
+      doc: None,
+      vis: Some("pub".to_string()),
+      alias: Type::new(None, name),
+      value: Type::new(None, value),
+    }),
+    inner: vec![],
+    implementation: vec![],
+    flatten: false,
+    content: ContentKind::Simple,
+    attribute_groups: vec![],
+  };
+  let mut imp = alias("Tenths", "f64");
+  imp.element = XsdImplType::Type(Type::new(None, "Tenths"));
+  imp.inner = vec![alias("Divisions", "f64"), alias("Layout", "Divisions")];
+  assert_eq!(
+    imp.to_string().unwrap(),
+    "#[allow(dead_code, unused_imports)]\npub mod tenths {\n    pub type Divisions = f64;\n\n    pub type Layout = Divisions;\n}\n"
+  );
+
+  imp.inner.push(alias("Scaling", "Measure"));
+  assert!(imp.to_string().unwrap().contains("    use super::*;\n"));
+}
+
+#[cfg(test)]
+fn nested_impls() -> XsdImpl {
+  let imp = |name: &str, element: XsdImplType, inner: Vec<XsdImpl>| XsdImpl {
+    name: XsdName::new(name, XsdType::Element),
+    fieldname_hint: None,
+    element,
+    inner,
+    implementation: vec![],
+    flatten: false,
+    content: ContentKind::Complex,
+    attribute_groups: vec![],
+  };
+  let ty = |name: &str| Type::new(None, name);
+
+  let mut note = Struct::new(None, "Note");
+  note.field(None, "pitch", ty("String"), false, false);
+  let note_variant = Variant::new(None, "Note").tuple(None, ty("measure::Note"), false, false);
+  let measure = Enum::new(None, "Measure").push_variant(note_variant);
+  let tempo = TypeAlias {
+    doc: None,
+    vis: Some("pub".to_string()),
+    alias: ty("Tempo"),
+    value: ty("f64"),
+  };
+  let mut part = Struct::new(None, "Part");
+  part.field(
+    None,
+    "measures",
+    ty("part::Measure").wrap("Vec").wrap("Option"),
+    false,
+    false,
+  );
+  part.field(None, "tempo", ty("part::Tempo"), true, false);
+  let mut score = Struct::new(None, "Score");
+  score.field(None, "parts", ty("score::Part").wrap("Vec"), false, false);
+
+  let mut score = imp(
+    "score",
+    XsdImplType::Struct(score),
+    vec![imp(
+      "part",
+      XsdImplType::Struct(part),
+      vec![
+        imp(
+          "measure",
+          XsdImplType::Enum(measure),
+          vec![imp("note", XsdImplType::Struct(note), vec![])],
+        ),
+        imp("tempo", XsdImplType::TypeAlias(tempo), vec![]),
+      ],
+    )],
+  );
+  score
+    .implementation
+    .push(Impl::new(ty("Score")).impl_trait(ty("XsdGen")));
+  score
+}
+
+#[test]
+fn inner_types_are_iterated_depth_first() {
+  let score = nested_impls();
+
+  let names = score
+    .iter_recursive()
+    .map(|imp| imp.name.local_name.as_str())
+    .collect::<Vec<_>>();
+  assert_eq!(names, ["score", "part", "measure", "note", "tempo"]);
+
+  let note = score.find_by_type_name("Note").map(|imp| &imp.name);
+  assert_eq!(note, Some(&XsdName::new("note", XsdType::Element)));
+  assert!(score.find_by_type_name("Tempo").is_some());
+  assert!(score.find_by_type_name("measure::Note").is_none());
+}
+
+#[test]
+fn map_types_visits_each_type_once() {
+  let mut score = nested_impls();
+  let mut part = score.inner.remove(0);
+
+  let mut visited = vec![];
+  part.map_types(&mut |ty| visited.push(ty.name.clone()));
+  assert_eq!(
+    visited,
+    ["Part", "Option", "Vec", "part::Measure", "part::Tempo"]
+  );
+
+  score.map_types(&mut |ty| {
+    if let Some(name) = ty.name.strip_prefix("score::") {
+      ty.name = format!("score_element::{name}");
+    }
+    if ty.name == "Score" {
+      ty.name = "ScoreElement".to_string();
+    }
+  });
+  let output = score.to_string().unwrap();
+  assert!(output.contains("pub struct ScoreElement {\n    parts: Vec<score_element::Part>,"));
+  assert!(output.contains("impl XsdGen for ScoreElement {"));
+
+  let mut tempo = part.inner.remove(1);
+  let mut visited = vec![];
+  tempo.map_types(&mut |ty| visited.push(ty.name.clone()));
+  assert_eq!(visited, ["Tempo", "f64"]);
 }
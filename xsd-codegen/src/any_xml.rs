@@ -0,0 +1,138 @@
+//! `xs:anyType`, read into a tree of elements whatever its content.
+
+use std::collections::BTreeMap;
+
+use xml::writer::{EmitterConfig, EventWriter};
+use xmltree::{Element, XMLNode};
+use xsd_types::{DocumentError, XsdGenError};
+
+use crate::{
+  xml_element::local_name, xml_writer::write_element, GenState, GenType, XMLElement, XsdGen,
+};
+
+/// An element of `xs:anyType`, kept whatever its content: its name, its attributes, its child
+/// elements and its text. The element and its attributes are named by their local names, as the
+/// generated types read them. The text is the one of the element joined, the whitespace between
+/// the children is only kept where `xml:space` preserves it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AnyXml {
+  pub name: String,
+  pub attributes: BTreeMap<String, String>,
+  pub children: Vec<AnyXml>,
+  pub text: String,
+}
+
+impl AnyXml {
+  /// The element, with its text ahead of its children.
+  pub fn to_element(&self) -> Element {
+    let mut element = Element::new(&self.name);
+    element.attributes.extend(self.attributes.clone());
+    if !self.text.is_empty() {
+      element.children.push(XMLNode::Text(self.text.clone()));
+    }
+    for child in &self.children {
+      element.children.push(XMLNode::Element(child.to_element()));
+    }
+    element
+  }
+}
+
+impl From<Element> for AnyXml {
+  fn from(element: Element) -> Self {
+    let mut any = Self {
+      name: element.name,
+      attributes: element
+        .attributes
+        .into_iter()
+        .map(|(name, value)| (local_name(&name).to_string(), value))
+        .collect(),
+      ..Default::default()
+    };
+    for child in element.children {
+      match child {
+        XMLNode::Element(child) => any.children.push(child.into()),
+        XMLNode::Text(text) | XMLNode::CData(text) => any.text.push_str(&text),
+        XMLNode::Comment(_) | XMLNode::ProcessingInstruction(..) => {}
+      }
+    }
+    any
+  }
+}
+
+/// Writes the element as XML without a declaration, its attributes sorted by name.
+impl std::fmt::Display for AnyXml {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let mut xml = vec![];
+    let config = EmitterConfig::new().write_document_declaration(false);
+    write_element(
+      &self.to_element(),
+      &mut EventWriter::new_with_config(&mut xml, config),
+    )
+    .map_err(|_| std::fmt::Error)?;
+    f.write_str(&String::from_utf8_lossy(&xml))
+  }
+}
+
+impl XsdGen for AnyXml {
+  fn gen(
+    element: &mut XMLElement,
+    gen_state: GenState,
+    name: Option<&str>,
+  ) -> Result<Self, DocumentError> {
+    match (gen_state.state, name) {
+      (GenType::Attribute, _) => Err(
+        XsdGenError {
+          node_name: element.node_name(),
+          ty: xsd_types::XsdType::Unknown,
+          msg: "anyType can only be read from an element.".to_string(),
+        }
+        .into(),
+      ),
+      (GenType::Content, Some(name)) => {
+        element.get_child_with(name, |child| Ok(Self::from(child.into_element())))
+      }
+      // The content of the element is taken, it's all read.
+      (GenType::Content, None) => {
+        let element = element.element_mut();
+        let mut content = Element::new(&element.name);
+        content.attributes = std::mem::take(&mut element.attributes);
+        content.children = std::mem::take(&mut element.children);
+        Ok(Self::from(content))
+      }
+    }
+  }
+}
+
+#[test]
+fn any_content_is_kept() {
+  let mut document = XMLElement::parse(
+    br#"<envelope xmlns:v="urn:vendor"><payload id="p1" xml:lang="fr" v:rev="2">Bonjour <b>tout</b> le monde<!-- - --><i/></payload></envelope>"#,
+  )
+  .unwrap();
+  let state = GenState {
+    is_root: true,
+    state: GenType::Content,
+    progress: None,
+  };
+
+  let payload = AnyXml::gen(&mut document, state.clone(), Some("payload")).unwrap();
+  assert_eq!(payload.name, "payload");
+  assert_eq!(payload.attributes["id"], "p1");
+  assert_eq!(payload.attributes["lang"], "fr");
+  assert_eq!(payload.attributes["rev"], "2");
+  assert_eq!(payload.text, "Bonjour  le monde");
+  assert_eq!(payload.children.len(), 2);
+  assert_eq!(payload.children[0].text, "tout");
+  assert_eq!(payload.children[1].name, "i");
+  assert_eq!(
+    payload.to_string(),
+    r#"<payload id="p1" lang="fr" rev="2">Bonjour  le monde<b>tout</b><i /></payload>"#
+  );
+  assert!(document.element().children.is_empty());
+
+  let mut payload = XMLElement::parse(payload.to_string().as_bytes()).unwrap();
+  let content = AnyXml::gen(&mut payload, state, None).unwrap();
+  assert_eq!(content.children.len(), 2);
+  assert!(payload.element().children.is_empty());
+  assert!(payload.element().attributes.is_empty());
+}
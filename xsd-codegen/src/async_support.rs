@@ -0,0 +1,56 @@
+//! Parsing of documents read from an async reader, enabled by the `tokio` feature.
+
+pub use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use xsd_types::DocumentError;
+
+use crate::{document::document_error, GenState, GenType, XMLElement, XsdGen};
+
+/// Read a whole document from `reader`, then parse its root element into `T` on tokio's
+/// blocking pool. Has to run within a tokio runtime.
+pub async fn parse_async<T, R>(mut reader: R) -> Result<T, DocumentError>
+where
+  T: XsdGen + Send + 'static,
+  R: AsyncRead + Unpin,
+{
+  let mut buffer = vec![];
+  reader
+    .read_to_end(&mut buffer)
+    .await
+    .map_err(|e| document_error(format!("Failed to read the document: {e}")))?;
+
+  let parse = move || {
+    let mut element = XMLElement::parse(&buffer)
+      .map_err(|e| document_error(format!("Failed to parse the document: {e}")))?;
+    let gen_state = GenState {
+      is_root: true,
+      state: GenType::Content,
+      progress: None,
+    };
+    T::gen(&mut element, gen_state, None)
+  };
+
+  match tokio::task::spawn_blocking(parse).await {
+    Ok(result) => result,
+    Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+    Err(e) => Err(document_error(format!("The parsing was cancelled: {e}"))),
+  }
+}
+
+#[test]
+fn documents_are_parsed_on_the_blocking_pool() {
+  let runtime = tokio::runtime::Builder::new_current_thread()
+    .build()
+    .unwrap();
+
+  let pitch = runtime.block_on(parse_async::<String, _>(&b"<pitch>C</pitch>"[..]));
+  assert_eq!(pitch.unwrap(), "C");
+
+  let error = runtime
+    .block_on(parse_async::<String, _>(&b"<pitch>C"[..]))
+    .unwrap_err();
+  assert!(
+    error.to_string().contains("Failed to parse the document"),
+    "{error}"
+  );
+}
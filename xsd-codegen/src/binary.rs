@@ -0,0 +1,80 @@
+//! The binary builtins, `xs:hexBinary` and `xs:base64Binary`, read into the octets they encode.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use crate::FromXmlString;
+
+/// An `xs:hexBinary`, e.g. `0FB7`. Its length is its number of octets, as counted by the length
+/// facets.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct HexBinary(pub Vec<u8>);
+
+/// An `xs:base64Binary`, e.g. `D7c=`. Its length is its number of octets, as counted by the
+/// length facets.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Base64Binary(pub Vec<u8>);
+
+impl FromXmlString for HexBinary {
+  fn from_xml(string: &str) -> Result<Self, String> {
+    let digits = string.trim().as_bytes();
+    if !digits.len().is_multiple_of(2) {
+      return Err(format!("{string} has an odd number of hex digits"));
+    }
+    digits
+      .chunks(2)
+      .map(|pair| {
+        std::str::from_utf8(pair)
+          .ok()
+          .filter(|pair| pair.chars().all(|c| c.is_ascii_hexdigit()))
+          .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+          .ok_or_else(|| format!("{string} is not hexBinary"))
+      })
+      .collect::<Result<_, _>>()
+      .map(HexBinary)
+  }
+}
+
+/// Writes the canonical lexical form, with upper case digits.
+impl Display for HexBinary {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    self.0.iter().try_for_each(|octet| write!(f, "{octet:02X}"))
+  }
+}
+
+impl FromXmlString for Base64Binary {
+  fn from_xml(string: &str) -> Result<Self, String> {
+    // The groups of characters may be separated by spaces.
+    let encoded: String = string.split_whitespace().collect();
+    if !encoded.len().is_multiple_of(4) {
+      return Err(format!("{string} is not base64Binary: it isn't padded"));
+    }
+    base64::decode(encoded)
+      .map(Base64Binary)
+      .map_err(|e| format!("{string} is not base64Binary: {e}"))
+  }
+}
+
+/// Writes the canonical lexical form, on a single line.
+impl Display for Base64Binary {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    f.write_str(&base64::encode(&self.0))
+  }
+}
+
+#[test]
+fn binaries_are_decoded() {
+  let hex = HexBinary::from_xml("0fB7").unwrap();
+  assert_eq!(hex.0, [0x0F, 0xB7]);
+  assert_eq!(hex.to_string(), "0FB7");
+  assert!(HexBinary::from_xml("").unwrap().0.is_empty());
+  for invalid in ["0", "0G", "+1", "é0"] {
+    assert!(HexBinary::from_xml(invalid).is_err(), "{invalid}");
+  }
+
+  let base64 = Base64Binary::from_xml("SGVs bG8=\n").unwrap();
+  assert_eq!(base64.0, b"Hello");
+  assert_eq!(base64.to_string(), "SGVsbG8=");
+  for invalid in ["SGVsbG8", "SGV*bG8=", "="] {
+    assert!(Base64Binary::from_xml(invalid).is_err(), "{invalid}");
+  }
+}
@@ -0,0 +1,432 @@
+//! Parsing of documents into the borrowed types generated with
+//! `GeneratorOptions::borrowed_types`, holding the text of the document instead of copies of it.
+
+use xmltree::{Element, XMLNode};
+use xsd_types::{DocumentError, XsdParseError};
+
+use crate::{
+  document::document_error,
+  xml_element::{attribute_key, local_name},
+  FromXmlString, GenState, GenType, RestrictedVec, XMLElement, XsdGen,
+};
+
+/// An element of a parsed document, read without being modified.
+///
+/// The counterpart of `XMLElement` for the borrowed types: the children read are only marked as
+/// such, and the text and attributes are borrowed from the document. The attributes and the text
+/// are never consumed.
+#[derive(Clone, Debug)]
+pub struct XMLElementRef<'a> {
+  element: &'a Element,
+  /// Whether each child of `element` was read, by index.
+  read: Vec<bool>,
+  /// Index of the first child not read yet.
+  first_unread: usize,
+}
+
+impl<'a> XMLElementRef<'a> {
+  pub fn new(element: &'a Element) -> Self {
+    Self {
+      element,
+      read: vec![false; element.children.len()],
+      first_unread: 0,
+    }
+  }
+
+  /// The element being read, including the children already read.
+  pub fn element(&self) -> &'a Element {
+    self.element
+  }
+
+  /// The local name of the element, without its prefix.
+  pub fn name(&self) -> &'a str {
+    &self.element.name
+  }
+
+  fn mark_read(&mut self, index: usize) {
+    self.read[index] = true;
+    while self.read.get(self.first_unread) == Some(&true) {
+      self.first_unread += 1;
+    }
+  }
+
+  /// The children not read yet, along with their index.
+  fn unread(&self) -> impl Iterator<Item = (usize, &'a Element)> + '_ {
+    let element = self.element;
+    (self.first_unread..element.children.len()).filter_map(move |index| {
+      match &element.children[index] {
+        XMLNode::Element(child) if !self.read[index] => Some((index, child)),
+        _ => None,
+      }
+    })
+  }
+
+  /// Read the first child element named `name` left, if any.
+  pub fn try_get_child(&mut self, name: &str) -> Option<XMLElementRef<'a>> {
+    let (index, child) = self
+      .unread()
+      .find(|(_, child)| child.name == local_name(name))?;
+    self.mark_read(index);
+    Some(XMLElementRef::new(child))
+  }
+
+  /// Read the first child element named `name` left, failing when there is none.
+  pub fn get_child(&mut self, name: &str) -> Result<XMLElementRef<'a>, DocumentError> {
+    self.try_get_child(name).ok_or_else(|| {
+      DocumentError::XsdParseError(XsdParseError {
+        node_name: self.name().to_string(),
+        msg: format!("Expected 1 child named {name} found 0"),
+      })
+    })
+  }
+
+  /// Read the next child element, i.e. the first one left whatever its name. Fails when there is
+  /// none or when it isn't named `name`, the child is read either way. See
+  /// `XMLElement::get_next_child_with`.
+  pub fn get_next_child(&mut self, name: &str) -> Result<XMLElementRef<'a>, DocumentError> {
+    let Some((index, child)) = self.unread().next() else {
+      return Err(DocumentError::XsdParseError(XsdParseError {
+        node_name: self.name().to_string(),
+        msg: format!("{} doesn't have anymore children.", self.name()),
+      }));
+    };
+    self.mark_read(index);
+
+    if child.name != local_name(name) {
+      return Err(DocumentError::XsdParseError(XsdParseError {
+        node_name: self.name().to_string(),
+        msg: format!(
+          "{} was the next child of {} not {name}.",
+          child.name,
+          self.name()
+        ),
+      }));
+    }
+    Ok(XMLElementRef::new(child))
+  }
+
+  /// Whether the next child element is named `name`. Doesn't read anything.
+  pub fn next_child_is(&self, name: &str) -> bool {
+    self
+      .unread()
+      .next()
+      .is_some_and(|(_, child)| child.name == local_name(name))
+  }
+
+  /// Whether the attribute `name` is present.
+  pub fn has_attr(&self, name: &str) -> bool {
+    attribute_key(self.element, name).is_some()
+  }
+
+  /// The value of the attribute `name`, if any.
+  pub fn try_get_attribute(&self, name: &str) -> Option<&'a str> {
+    attribute_key(self.element, name).map(|key| self.element.attributes[key].as_str())
+  }
+
+  /// The text content of the element, if any. Text split in several nodes, e.g. around a
+  /// comment, can't be borrowed and is an error.
+  pub fn try_get_content(&self) -> Result<Option<&'a str>, DocumentError> {
+    let mut texts = self
+      .element
+      .children
+      .iter()
+      .filter_map(|child| match child {
+        XMLNode::Text(text) | XMLNode::CData(text) => Some(text.as_str()),
+        _ => None,
+      });
+
+    match (texts.next(), texts.next()) {
+      (text, None) => Ok(text),
+      (Some(_), Some(_)) => Err(DocumentError::XsdParseError(XsdParseError {
+        node_name: self.name().to_string(),
+        msg: "the text is split in several nodes and can't be borrowed".to_string(),
+      })),
+      (None, Some(_)) => unreachable!(),
+    }
+  }
+
+  /// Read `T` with its owned parsing code, out of a copy of what is left of the element. The
+  /// children `T` reads are marked as read here too.
+  pub fn gen_owned<T: XsdGen>(
+    &mut self,
+    gen_state: GenState,
+    name: Option<&str>,
+  ) -> Result<T, DocumentError> {
+    let mut copy = self.copy_without_children();
+    let mut unread = vec![];
+    if let GenType::Content = gen_state.state {
+      for (index, child) in self.element.children.iter().enumerate() {
+        if !self.read[index] {
+          copy.children.push(child.clone());
+          unread.push(index);
+        }
+      }
+    }
+
+    let mut element = XMLElement::from_element(copy, None);
+    let output = T::gen(&mut element, gen_state, name)?;
+
+    // The children left are in the same order, the others were read.
+    let mut left = element.element().children.iter().peekable();
+    for index in unread {
+      if left.peek() == Some(&&self.element.children[index]) {
+        left.next();
+      } else {
+        self.mark_read(index);
+      }
+    }
+
+    Ok(output)
+  }
+
+  /// Read the attribute `name` with its owned parsing code, or its default value `default` when
+  /// the element doesn't have it, see `XMLElement::gen_attribute_or`.
+  pub fn gen_owned_attribute_or<T: XsdGen>(
+    &self,
+    gen_state: GenState,
+    name: Option<&str>,
+    default: &str,
+  ) -> Result<T, DocumentError> {
+    XMLElement::from_element(self.copy_without_children(), None)
+      .gen_attribute_or(gen_state, name, default)
+  }
+
+  /// Read the attribute `name` with its owned parsing code, failing when its value isn't the
+  /// fixed value `fixed`, see `XMLElement::gen_fixed_attribute`.
+  pub fn gen_owned_fixed_attribute<T: XsdGen>(
+    &self,
+    gen_state: GenState,
+    name: Option<&str>,
+    fixed: &str,
+  ) -> Result<T, DocumentError> {
+    XMLElement::from_element(self.copy_without_children(), None)
+      .gen_fixed_attribute(gen_state, name, fixed)
+  }
+
+  fn copy_without_children(&self) -> Element {
+    Element {
+      prefix: self.element.prefix.clone(),
+      namespace: self.element.namespace.clone(),
+      namespaces: self.element.namespaces.clone(),
+      name: self.element.name.clone(),
+      attributes: self.element.attributes.clone(),
+      children: vec![],
+    }
+  }
+}
+
+/// The counterpart of `XsdGen` for the borrowed types, reading an `XMLElementRef` borrowing from
+/// the document.
+pub trait XsdGenRef<'a>
+where
+  Self: Sized,
+{
+  fn gen_ref(
+    element: &mut XMLElementRef<'a>,
+    gen_state: GenState,
+    name: Option<&str>,
+  ) -> Result<Self, DocumentError>;
+}
+
+impl<'a> XsdGenRef<'a> for &'a str {
+  fn gen_ref(
+    element: &mut XMLElementRef<'a>,
+    gen_state: GenState,
+    name: Option<&str>,
+  ) -> Result<Self, DocumentError> {
+    match (gen_state.state, name) {
+      (GenType::Attribute, Some(name)) => element.try_get_attribute(name).ok_or_else(|| {
+        DocumentError::XsdParseError(XsdParseError {
+          node_name: element.name().to_string(),
+          msg: format!("{name} not found"),
+        })
+      }),
+      (GenType::Attribute, None) => Err(DocumentError::XsdParseError(XsdParseError {
+        node_name: element.name().to_string(),
+        msg: "Expected node name to parse &str attribute got None.".to_string(),
+      })),
+      (GenType::Content, Some(name)) => {
+        let child = element.get_child(name)?;
+        child.try_get_content()?.ok_or_else(|| {
+          DocumentError::XsdParseError(XsdParseError {
+            node_name: child.name().to_string(),
+            msg: "no text found".to_string(),
+          })
+        })
+      }
+      (GenType::Content, None) => Ok(element.try_get_content()?.unwrap_or_default()),
+    }
+  }
+}
+
+/// The values converted from text are parsed from the borrowed text directly.
+impl<'a, T: FromXmlString> XsdGenRef<'a> for T {
+  fn gen_ref(
+    element: &mut XMLElementRef<'a>,
+    gen_state: GenState,
+    name: Option<&str>,
+  ) -> Result<Self, DocumentError> {
+    let namespaces = element.element().namespaces.as_ref();
+    let text = <&str as XsdGenRef>::gen_ref(element, gen_state, name)?;
+    T::from_xml_in(text, namespaces).map_err(|e| {
+      DocumentError::XsdParseError(XsdParseError {
+        node_name: element.name().to_string(),
+        msg: format!(
+          "could not parse {} from text: {e}",
+          name.unwrap_or("content")
+        ),
+      })
+    })
+  }
+}
+
+impl<'a, T: XsdGenRef<'a>> XsdGenRef<'a> for Option<T> {
+  fn gen_ref(
+    element: &mut XMLElementRef<'a>,
+    gen_state: GenState,
+    name: Option<&str>,
+  ) -> Result<Self, DocumentError> {
+    let mut new_state = gen_state;
+    match (new_state.state, name) {
+      (GenType::Attribute, Some(name)) if !element.has_attr(name) => Ok(None),
+      (GenType::Attribute, Some(_)) => {
+        new_state.is_root = false;
+        T::gen_ref(element, new_state, name).map(Some)
+      }
+      (GenType::Content, Some(name)) => {
+        new_state.is_root = false;
+        match element.try_get_child(name) {
+          Some(mut child) => T::gen_ref(&mut child, new_state, None).map(Some),
+          None => Ok(None),
+        }
+      }
+      (_, None) => {
+        let mut attempt = element.clone();
+        match T::gen_ref(&mut attempt, new_state, None) {
+          Ok(value) => {
+            *element = attempt;
+            Ok(Some(value))
+          }
+          Err(_) => Ok(None),
+        }
+      }
+    }
+  }
+}
+
+impl<'a, T: XsdGenRef<'a>> XsdGenRef<'a> for Vec<T> {
+  fn gen_ref(
+    element: &mut XMLElementRef<'a>,
+    gen_state: GenState,
+    name: Option<&str>,
+  ) -> Result<Self, DocumentError> {
+    let mut new_state = gen_state;
+    match (new_state.state, name) {
+      (GenType::Attribute, _) => Ok(vec![T::gen_ref(element, new_state, name)?]),
+      (GenType::Content, Some(name)) => {
+        new_state.is_root = false;
+        let mut output = vec![];
+        while let Some(mut child) = element.try_get_child(name) {
+          output.push(T::gen_ref(&mut child, new_state.clone(), None)?);
+        }
+        Ok(output)
+      }
+      (GenType::Content, None) => {
+        let mut output = vec![];
+        loop {
+          let mut attempt = element.clone();
+          match T::gen_ref(&mut attempt, new_state.clone(), None) {
+            // Stop once nothing is read anymore.
+            Ok(value) if attempt.read != element.read => {
+              output.push(value);
+              *element = attempt;
+            }
+            _ => return Ok(output),
+          }
+        }
+      }
+    }
+  }
+}
+
+impl<'a, T: XsdGenRef<'a>, const MIN: usize, const MAX: usize> XsdGenRef<'a>
+  for RestrictedVec<T, MIN, MAX>
+{
+  fn gen_ref(
+    element: &mut XMLElementRef<'a>,
+    gen_state: GenState,
+    name: Option<&str>,
+  ) -> Result<Self, DocumentError> {
+    let values = <Vec<T> as XsdGenRef>::gen_ref(element, gen_state, name)?;
+    RestrictedVec::from_vec(values).map_err(|msg| {
+      DocumentError::XsdParseError(XsdParseError {
+        node_name: element.name().to_string(),
+        msg,
+      })
+    })
+  }
+}
+
+/// Parse the document whose root element is `root` into `T`, failing unless the root element is
+/// named `root_name`. The values of `T` borrow from `root`.
+pub fn parse_document_ref<'a, T: XsdGenRef<'a>>(
+  root: &'a Element,
+  root_name: &str,
+) -> Result<T, DocumentError> {
+  if root.name != local_name(root_name) {
+    return Err(document_error(format!(
+      "The root element is {} not {root_name}",
+      root.name
+    )));
+  }
+
+  let gen_state = GenState {
+    is_root: true,
+    state: GenType::Content,
+    progress: None,
+  };
+  T::gen_ref(&mut XMLElementRef::new(root), gen_state, None)
+}
+
+#[test]
+fn borrowed_values_match_the_owned_ones() {
+  let document =
+    r#"<note id="n1" dots="2"><pitch>C4</pitch><lyric>la</lyric><lyric>li</lyric><tie/></note>"#;
+  let root = Element::parse(document.as_bytes()).unwrap();
+  let attribute = GenState {
+    is_root: false,
+    state: GenType::Attribute,
+    progress: None,
+  };
+  let content = GenState {
+    state: GenType::Content,
+    ..attribute.clone()
+  };
+
+  let mut element = XMLElementRef::new(&root);
+  let id: &str = XsdGenRef::gen_ref(&mut element, attribute.clone(), Some("id")).unwrap();
+  let dots: Option<u8> = XsdGenRef::gen_ref(&mut element, attribute.clone(), Some("dots")).unwrap();
+  let color: Option<&str> = XsdGenRef::gen_ref(&mut element, attribute, Some("color")).unwrap();
+  let pitch: &str = XsdGenRef::gen_ref(&mut element, content.clone(), Some("pitch")).unwrap();
+  let lyrics: Vec<&str> = XsdGenRef::gen_ref(&mut element, content.clone(), Some("lyric")).unwrap();
+  assert_eq!((id, dots, color, pitch), ("n1", Some(2), None, "C4"));
+  assert_eq!(lyrics, ["la", "li"]);
+  assert!(element.next_child_is("tie"));
+
+  // The owned parsing code only sees, and reads, the children left.
+  let lyrics: Vec<String> = element.gen_owned(content.clone(), Some("lyric")).unwrap();
+  assert!(lyrics.is_empty());
+  let tie: Option<String> = element.gen_owned(content, Some("tie")).unwrap();
+  assert_eq!(tie.as_deref(), Some(""));
+  assert!(element.get_next_child("tie").is_err());
+
+  let pitch = root.get_child("pitch").unwrap();
+  assert_eq!(parse_document_ref::<&str>(pitch, "pitch").unwrap(), "C4");
+  let error = parse_document_ref::<&str>(&root, "pitch").unwrap_err();
+  assert!(
+    error
+      .to_string()
+      .contains("The root element is note not pitch"),
+    "{error}"
+  );
+}
@@ -5,7 +5,7 @@ pub fn xsdgen_impl(r#type: Type, block: Block, mut_gen: bool, name_used: bool) -
     .arg("element", Type::new(None, "&mut XMLElement"))
     .arg(if mut_gen { "mut gen_state" } else { "gen_state" }, Type::new(None, "GenState"))
     .arg(if name_used { "name" } else { "_name" }, Type::new(None, "Option<&str>"))
-    .ret(Type::new(None, "Result<Self, XsdIoError>"));
+    .ret(Type::new(None, "Result<Self, DocumentError>"));
   let mut skip_b = false;
   if let Some(b) = &block.before {
     skip_b = b.is_empty();
@@ -0,0 +1,198 @@
+//! `xs:decimal`, read exactly instead of rounded to an `f64`.
+
+use std::{
+  cmp::Ordering,
+  hash::{Hash, Hasher},
+};
+
+use crate::FromXmlString;
+
+/// The most digits a `Decimal` holds, the ones of an `i128`.
+const MAX_DIGITS: u32 = 38;
+
+/// A decimal number read exactly from its lexical form, e.g. `-1.50`: the integer `digits` scaled
+/// down by `10^scale`. It holds up to 38 digits. The values are compared by value, `1.5` equals
+/// `1.50`, and written back with the digits they were read with.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Decimal {
+  digits: i128,
+  scale: u32,
+}
+
+impl Decimal {
+  /// `digits` scaled down by `10^scale`, e.g. `Decimal::new(-150, 2)` is `-1.50`.
+  pub const fn new(digits: i128, scale: u32) -> Self {
+    Self { digits, scale }
+  }
+
+  pub fn digits(&self) -> i128 {
+    self.digits
+  }
+
+  pub fn scale(&self) -> u32 {
+    self.scale
+  }
+
+  /// The number of digits without the leading and trailing zeros, as counted by the
+  /// `totalDigits` facet.
+  pub fn total_digits(&self) -> usize {
+    let normalized = self.normalized();
+    match normalized.digits.unsigned_abs() {
+      0 => 0,
+      digits => digits.ilog10() as usize + 1,
+    }
+  }
+
+  /// The number of digits of the fraction without the trailing zeros, as counted by the
+  /// `fractionDigits` facet.
+  pub fn fraction_digits(&self) -> usize {
+    self.normalized().scale as usize
+  }
+
+  /// The nearest `f64`.
+  pub fn to_f64(&self) -> f64 {
+    self.to_string().parse().unwrap_or_default()
+  }
+
+  /// The same value without the trailing zeros of its fraction.
+  fn normalized(&self) -> Self {
+    let mut normalized = *self;
+    while normalized.scale > 0 && normalized.digits % 10 == 0 {
+      normalized.digits /= 10;
+      normalized.scale -= 1;
+    }
+    normalized
+  }
+}
+
+impl From<i64> for Decimal {
+  fn from(value: i64) -> Self {
+    Self::new(value.into(), 0)
+  }
+}
+
+impl FromXmlString for Decimal {
+  fn from_xml(string: &str) -> Result<Self, String> {
+    let invalid = || format!("{string} is not a decimal");
+    let lexical = string.trim();
+    let (negative, unsigned) = match lexical.strip_prefix('-') {
+      Some(unsigned) => (true, unsigned),
+      None => (false, lexical.strip_prefix('+').unwrap_or(lexical)),
+    };
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    if integer.is_empty() && fraction.is_empty() {
+      return Err(invalid());
+    }
+
+    let mut digits = 0i128;
+    for c in integer.chars().chain(fraction.chars()) {
+      let digit = c.to_digit(10).ok_or_else(invalid)?;
+      digits = digits
+        .checked_mul(10)
+        .and_then(|digits| digits.checked_add(digit.into()))
+        .ok_or_else(|| format!("{string} has more than {MAX_DIGITS} digits"))?;
+    }
+    let scale = fraction.len() as u32;
+    if scale > MAX_DIGITS {
+      return Err(format!("{string} has more than {MAX_DIGITS} digits"));
+    }
+
+    Ok(Self::new(if negative { -digits } else { digits }, scale))
+  }
+}
+
+/// Writes the lexical form read by `from_xml`, with as many fraction digits as `scale`.
+impl std::fmt::Display for Decimal {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let digits = self.digits.unsigned_abs().to_string();
+    let scale = self.scale as usize;
+    let digits = format!("{digits:0>width$}", width = scale + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+    if self.digits < 0 {
+      f.write_str("-")?;
+    }
+    f.write_str(integer)?;
+    if !fraction.is_empty() {
+      write!(f, ".{fraction}")?;
+    }
+    Ok(())
+  }
+}
+
+impl PartialEq for Decimal {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for Decimal {}
+
+impl Hash for Decimal {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    let normalized = self.normalized();
+    normalized.digits.hash(state);
+    normalized.scale.hash(state);
+  }
+}
+
+impl PartialOrd for Decimal {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for Decimal {
+  fn cmp(&self, other: &Self) -> Ordering {
+    // The digits of the one with the smaller scale are scaled up to the other.
+    let compare = |digits: i128, other: i128, shift: u32| {
+      match 10i128
+        .checked_pow(shift)
+        .and_then(|factor| other.checked_mul(factor))
+      {
+        Some(other) => digits.cmp(&other),
+        // Scaled up, `other` is out of the range of `digits`.
+        None => 0.cmp(&other),
+      }
+    };
+    let (left, right) = (self.normalized(), other.normalized());
+    if left.scale >= right.scale {
+      compare(left.digits, right.digits, left.scale - right.scale)
+    } else {
+      compare(right.digits, left.digits, right.scale - left.scale).reverse()
+    }
+  }
+}
+
+#[test]
+fn decimals_are_read_exactly() {
+  let decimal = |string| Decimal::from_xml(string).unwrap();
+
+  assert_eq!(decimal("-1.50"), Decimal::new(-150, 2));
+  assert_eq!(decimal("-1.50").to_string(), "-1.50");
+  assert_eq!(decimal(".5").to_string(), "0.5");
+  assert_eq!(decimal("+7.").to_string(), "7");
+  assert_eq!(decimal("0.1").to_f64(), 0.1);
+  assert_eq!(
+    decimal("12345678901234567890.123456789").to_string(),
+    "12345678901234567890.123456789"
+  );
+
+  assert_eq!(decimal("1.5"), decimal("1.50"));
+  assert!(decimal("-2") < decimal("-1.99"));
+  assert!(decimal("99999999999999999999999999999999999999") > decimal("0.5"));
+  assert!(decimal("-99999999999999999999999999999999999999") < decimal("0.5"));
+
+  assert_eq!(decimal("-012.3400").total_digits(), 4);
+  assert_eq!(decimal("-012.3400").fraction_digits(), 2);
+
+  for invalid in [
+    "",
+    ".",
+    "1e5",
+    "1.2.3",
+    "- 1",
+    "999999999999999999999999999999999999999",
+  ] {
+    assert!(Decimal::from_xml(invalid).is_err(), "{invalid}");
+  }
+}
@@ -0,0 +1,89 @@
+//! Parsing of whole documents into the types generated for the top-level elements.
+
+use xsd_types::{DocumentError, XsdParseError};
+
+use crate::{write_document, GenState, GenType, ParseOptions, XMLElement, XsdGen, XsdSer};
+
+/// Implemented by the types generated for the top-level elements, i.e. the types of whole
+/// documents, so that code handling any document type can be written once.
+pub trait XmlDocument: Sized {
+  /// The local name of the root element of the documents.
+  const ROOT_NAME: &'static str;
+
+  /// Parse a whole document, failing unless its root element is named `ROOT_NAME`.
+  fn parse_str(s: &str) -> Result<Self, DocumentError>;
+
+  /// Write the document back, see `write_document`.
+  fn to_xml_string(&self) -> Result<String, DocumentError>
+  where
+    Self: XsdSer,
+  {
+    write_document(self, Self::ROOT_NAME)
+  }
+}
+
+/// Parse the document `s` into `T`, failing unless its root element is named `root_name`.
+pub fn parse_document<T: XsdGen>(s: &str, root_name: &str) -> Result<T, DocumentError> {
+  parse_document_with(s, root_name, &ParseOptions::default())
+}
+
+/// Read the document `s` as a `T`, parsed with `options`, see `parse_document`.
+pub fn parse_document_with<T: XsdGen>(
+  s: &str,
+  root_name: &str,
+  options: &ParseOptions,
+) -> Result<T, DocumentError> {
+  let mut element = XMLElement::parse_hack_with(s.as_bytes(), options)
+    .map_err(|e| document_error(format!("Failed to parse the document: {e}")))?;
+  let gen_state = GenState {
+    is_root: true,
+    state: GenType::Content,
+    progress: None,
+  };
+  T::gen(&mut element, gen_state, Some(root_name))
+}
+
+pub(crate) fn document_error(msg: String) -> DocumentError {
+  XsdParseError {
+    node_name: "document".to_string(),
+    msg,
+  }
+  .into()
+}
+
+#[test]
+fn documents_are_read_from_their_root_element() {
+  let pitch: String = parse_document("<pitch>C</pitch>", "pitch").unwrap();
+  assert_eq!(pitch, "C");
+
+  let error = parse_document::<String>("<step>C</step>", "pitch").unwrap_err();
+  assert!(error.to_string().contains("pitch"), "{error}");
+  let error = parse_document::<String>("<pitch>C", "pitch").unwrap_err();
+  assert!(
+    error.to_string().contains("Failed to parse the document"),
+    "{error}"
+  );
+}
+
+#[test]
+fn trailing_siblings_of_the_root_are_rejected_when_strict() {
+  let strict = ParseOptions {
+    unexpected_elements: crate::UnexpectedElements::Reject,
+    ..Default::default()
+  };
+
+  let document = "<pitch>C</pitch>\n<!-- generated -->\n<?done?>\n";
+  let pitch: String = parse_document_with(document, "pitch", &strict).unwrap();
+  assert_eq!(pitch, "C");
+
+  let document = "<pitch>C</pitch><!-- generated --><step>D</step>";
+  let pitch: String = parse_document(document, "pitch").unwrap();
+  assert_eq!(pitch, "C");
+  let error = parse_document_with::<String>(document, "pitch", &strict).unwrap_err();
+  assert!(
+    error
+      .to_string()
+      .contains("unexpected element step after the root element"),
+    "{error}"
+  );
+}
@@ -0,0 +1,277 @@
+//! `xs:duration`, read into its months and its seconds.
+
+use std::cmp::Ordering;
+
+use crate::{Decimal, FromXmlString};
+
+/// An `xs:duration`, e.g. `-P1Y2M3DT4H5M6.5S`: a number of months and a number of seconds, both
+/// negative for a negative duration. A year is 12 months and a day 86400 seconds, but a month
+/// isn't a number of days: `P1M` is longer than `P27D`, shorter than `P32D` and not ordered with
+/// `P30D`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Duration {
+  months: i64,
+  seconds: Decimal,
+}
+
+impl Duration {
+  /// The duration of `months` and `seconds`, `None` when one is negative and the other positive.
+  pub fn new(months: i64, seconds: Decimal) -> Option<Self> {
+    let zero = Decimal::default();
+    if (months < 0 && seconds > zero) || (months > 0 && seconds < zero) {
+      return None;
+    }
+    Some(Self { months, seconds })
+  }
+
+  pub fn months(&self) -> i64 {
+    self.months
+  }
+
+  pub fn seconds(&self) -> Decimal {
+    self.seconds
+  }
+
+  pub fn is_negative(&self) -> bool {
+    self.months < 0 || self.seconds < Decimal::default()
+  }
+
+  /// The `std` duration of a duration without months which isn't negative, the digits of the
+  /// seconds past the nanoseconds dropped.
+  pub fn to_std(&self) -> Option<std::time::Duration> {
+    if self.months != 0 || self.is_negative() {
+      return None;
+    }
+    let (seconds, nanos) = split_seconds(self.seconds);
+    Some(std::time::Duration::new(seconds.try_into().ok()?, nanos))
+  }
+
+  /// The end of the duration starting at `start`, `None` out of the range of chrono.
+  fn end(&self, start: chrono::NaiveDateTime) -> Option<chrono::NaiveDateTime> {
+    let months = chrono::Months::new(self.months.unsigned_abs().try_into().ok()?);
+    let (seconds, nanos) = split_seconds(self.seconds);
+    let mut delta = chrono::TimeDelta::try_seconds(seconds.try_into().ok()?)?
+      .checked_add(&chrono::TimeDelta::nanoseconds(nanos.into()))?;
+    if self.is_negative() {
+      delta = -delta;
+    }
+
+    let start = if self.months < 0 {
+      start.checked_sub_months(months)?
+    } else {
+      start.checked_add_months(months)?
+    };
+    start.checked_add_signed(delta)
+  }
+}
+
+/// The whole seconds of `seconds` and its nanoseconds, without their sign. The digits past the
+/// nanoseconds are dropped.
+fn split_seconds(seconds: Decimal) -> (u128, u32) {
+  let digits = seconds.digits().unsigned_abs();
+  let scale = seconds.scale();
+  let Some(factor) = 10u128.checked_pow(scale) else {
+    // A scale of more than 38 digits leaves no room for whole seconds.
+    return (0, 0);
+  };
+  let fraction = digits % factor;
+  let nanos = if scale <= 9 {
+    fraction * 10u128.pow(9 - scale)
+  } else {
+    fraction / 10u128.pow(scale - 9)
+  };
+  (digits / factor, nanos as u32)
+}
+
+impl FromXmlString for Duration {
+  fn from_xml(string: &str) -> Result<Self, String> {
+    let bad_format = || format!("bad duration format: {string}");
+    let out_of_range = || format!("{string} is out of range");
+
+    let (negative, unsigned) = match string.strip_prefix('-') {
+      Some(unsigned) => (true, unsigned),
+      None => (false, string),
+    };
+    let designated = unsigned.strip_prefix('P').ok_or_else(bad_format)?;
+    let (date, time) = match designated.split_once('T') {
+      Some((_, "")) => return Err(bad_format()),
+      Some((date, time)) => (date, time),
+      None if designated.is_empty() => return Err(bad_format()),
+      None => (designated, ""),
+    };
+
+    let mut months = 0i64;
+    let mut seconds = 0i128;
+    let mut fraction = Decimal::default();
+    let components = [(date, "YMD"), (time, "HMS")];
+    for (part, designators) in components {
+      let mut rest = part;
+      let mut allowed = designators;
+      while !rest.is_empty() {
+        let end = rest
+          .find(|c: char| !c.is_ascii_digit() && c != '.')
+          .ok_or_else(bad_format)?;
+        let number = &rest[..end];
+        let designator = rest[end..].chars().next().unwrap_or_default();
+        let position = allowed.find(designator).ok_or_else(bad_format)?;
+        allowed = &allowed[position + 1..];
+        rest = &rest[end + designator.len_utf8()..];
+
+        if designator == 'S' && !number.starts_with('.') && !number.ends_with('.') {
+          fraction = Decimal::from_xml(number).map_err(|_| bad_format())?;
+          continue;
+        }
+        if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+          return Err(bad_format());
+        }
+        let value = number.parse::<i64>().map_err(|_| out_of_range())?;
+        match (designators, designator) {
+          (_, 'Y') => months = value.checked_mul(12).ok_or_else(out_of_range)?,
+          ("YMD", 'M') => months = months.checked_add(value).ok_or_else(out_of_range)?,
+          (_, 'D') => seconds = i128::from(value) * 86400,
+          (_, 'H') => seconds += i128::from(value) * 3600,
+          _ => seconds += i128::from(value) * 60,
+        }
+      }
+    }
+
+    // The whole seconds are added to the digits of the fraction, at its scale.
+    let digits = 10i128
+      .checked_pow(fraction.scale())
+      .and_then(|factor| seconds.checked_mul(factor))
+      .and_then(|seconds| seconds.checked_add(fraction.digits()))
+      .ok_or_else(out_of_range)?;
+    let seconds = Decimal::new(digits, fraction.scale());
+
+    Ok(if negative {
+      Self {
+        months: -months,
+        seconds: Decimal::new(-seconds.digits(), seconds.scale()),
+      }
+    } else {
+      Self { months, seconds }
+    })
+  }
+}
+
+/// Writes the canonical lexical form of the duration, the days, hours and minutes carried over,
+/// e.g. `PT36H` is written `P1DT12H`.
+impl std::fmt::Display for Duration {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if self.is_negative() {
+      f.write_str("-")?;
+    }
+    f.write_str("P")?;
+
+    let months = self.months.unsigned_abs();
+    let digits = self.seconds.digits().unsigned_abs();
+    let scale = self.seconds.scale() as usize;
+    let factor = 10u128.checked_pow(scale as u32).unwrap_or(u128::MAX);
+    let (whole, fraction) = (digits / factor, digits % factor);
+
+    let date = [
+      (u128::from(months / 12), 'Y'),
+      (u128::from(months % 12), 'M'),
+      (whole / 86400, 'D'),
+    ];
+    for (value, designator) in date {
+      if value != 0 {
+        write!(f, "{value}{designator}")?;
+      }
+    }
+
+    let time = [((whole % 86400) / 3600, 'H'), ((whole % 3600) / 60, 'M')];
+    if whole % 86400 == 0 && fraction == 0 {
+      if months == 0 && whole == 0 {
+        f.write_str("T0S")?;
+      }
+      return Ok(());
+    }
+    f.write_str("T")?;
+    for (value, designator) in time {
+      if value != 0 {
+        write!(f, "{value}{designator}")?;
+      }
+    }
+    if whole % 60 != 0 || fraction != 0 {
+      write!(f, "{}", whole % 60)?;
+      if fraction != 0 {
+        let fraction = format!("{fraction:0>scale$}");
+        write!(f, ".{}", fraction.trim_end_matches('0'))?;
+      }
+      f.write_str("S")?;
+    }
+    Ok(())
+  }
+}
+
+/// Durations are equal when their months and their seconds are, `P1D` equals `PT24H`.
+impl PartialEq for Duration {
+  fn eq(&self, other: &Self) -> bool {
+    self.months == other.months && self.seconds == other.seconds
+  }
+}
+
+/// Durations are ordered as XML Schema orders them: by their ends when they start at four points
+/// in time, picked to give the months their shortest and their longest spans. They're not
+/// ordered when the ends are not in the same order.
+impl PartialOrd for Duration {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    const STARTS: [(i32, u32); 4] = [(1696, 9), (1697, 2), (1903, 3), (1903, 7)];
+
+    let months = self.months.cmp(&other.months);
+    let seconds = self.seconds.cmp(&other.seconds);
+    if months == seconds || seconds == Ordering::Equal {
+      return Some(months);
+    }
+    if months == Ordering::Equal {
+      return Some(seconds);
+    }
+
+    let mut order = None;
+    for (year, month) in STARTS {
+      let start = chrono::NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+      let current = self.end(start)?.cmp(&other.end(start)?);
+      if order.is_some_and(|order| order != current) {
+        return None;
+      }
+      order = Some(current);
+    }
+    // Different durations are never equal.
+    order.filter(|order| *order != Ordering::Equal)
+  }
+}
+
+#[test]
+fn durations_are_read_and_ordered() {
+  let duration = |string| Duration::from_xml(string).unwrap();
+
+  let long = duration("-P1Y2M3DT4H5M6.50S");
+  assert_eq!(long.months(), -14);
+  assert_eq!(long.seconds(), Decimal::new(-27_390_650, 2));
+  assert!(long.is_negative());
+  assert_eq!(long.to_string(), "-P1Y2M3DT4H5M6.5S");
+  assert_eq!(duration("PT36H").to_string(), "P1DT12H");
+  assert_eq!(duration("P0Y").to_string(), "PT0S");
+  assert_eq!(duration("P2M").to_string(), "P2M");
+  assert_eq!(duration("PT0.000001S").to_string(), "PT0.000001S");
+
+  assert_eq!(duration("P1D"), duration("PT24H"));
+  assert!(duration("P1Y") > duration("P11M"));
+  assert!(duration("P1M") > duration("P27D"));
+  assert!(duration("P1M") < duration("P32D"));
+  assert_eq!(duration("P1M").partial_cmp(&duration("P30D")), None);
+  assert!(duration("-P1D") < duration("PT1S"));
+
+  assert_eq!(
+    duration("PT1M1.5S").to_std(),
+    Some(std::time::Duration::from_millis(61_500))
+  );
+  assert_eq!(duration("P1M").to_std(), None);
+
+  for invalid in [
+    "", "P", "PT", "P1YT", "1D", "P-1D", "P1S", "PT1D", "P1D1Y", "P1.5D", "PT.5S", "PT1.S", "P1Y1Y",
+  ] {
+    assert!(Duration::from_xml(invalid).is_err(), "{invalid}");
+  }
+}
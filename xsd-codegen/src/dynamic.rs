@@ -0,0 +1,662 @@
+//! A generic value tree the generated types convert to and from, e.g. to hand a document to a
+//! templating engine. The conversions are generated with `GeneratorOptions::dynamic_values`.
+
+use std::collections::BTreeMap;
+
+use xsd_types::{DocumentError, XsdParseError};
+
+use crate::{
+  AnyXml, AttributeName, Base64Binary, Date, DateTime, Decimal, Duration, FromXmlString, GDay,
+  GMonth, GMonthDay, GYear, GYearMonth, HexBinary, QName, RawXml, RestrictedVec, Time,
+  UnknownContent,
+};
+
+/// A generated value as a tree of maps, lists and scalars. Structs are maps of their fields by
+/// their XML names, choices maps of a single entry naming the alternative they hold, and the
+/// enumerations and the other simple types without a variant of their own hold their lexical
+/// form.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DynamicValue {
+  /// An absent optional value.
+  Null,
+  Bool(bool),
+  Integer(i128),
+  Float(f64),
+  Text(String),
+  /// The values of a repeated field or of a list.
+  List(Vec<DynamicValue>),
+  Map(BTreeMap<String, DynamicValue>),
+}
+
+/// Converted into a `DynamicValue`. The generated types implement it by calling their
+/// `From<&T> for DynamicValue` impl.
+pub trait ToDynamic {
+  fn to_dynamic(&self) -> DynamicValue;
+}
+
+/// Read out of a `DynamicValue`, the converse of `ToDynamic`. The generated types implement it by
+/// calling their `TryFrom<&DynamicValue>` impl.
+pub trait FromDynamic: Sized {
+  fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError>;
+}
+
+/// The entries of a map read into a struct or a choice, see `DynamicValue::fields`.
+#[derive(Clone, Copy, Debug)]
+pub struct DynamicFields<'a> {
+  ty: &'a str,
+  entries: &'a BTreeMap<String, DynamicValue>,
+}
+
+impl DynamicValue {
+  /// A map holding `entries`.
+  pub fn map<'a>(entries: impl IntoIterator<Item = (&'a str, DynamicValue)>) -> Self {
+    Self::Map(
+      entries
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect(),
+    )
+  }
+
+  /// The entries of a map read into the struct `ty`.
+  pub fn fields<'a>(&'a self, ty: &'a str) -> Result<DynamicFields<'a>, DocumentError> {
+    match self {
+      Self::Map(entries) => Ok(DynamicFields { ty, entries }),
+      value => Err(mismatch(ty, "a map", value)),
+    }
+  }
+
+  /// The single entry of a map read into the choice `ty`: the name of the alternative, along with
+  /// the entries to read it from.
+  pub fn variant<'a>(&'a self, ty: &'a str) -> Result<(&'a str, DynamicFields<'a>), DocumentError> {
+    let fields = self.fields(ty)?;
+    let mut keys = fields.entries.keys();
+    match (keys.next(), keys.next()) {
+      (Some(key), None) => Ok((key, fields)),
+      _ => Err(error(
+        "",
+        format!(
+          "{ty} expects a map of a single alternative, found {} entries",
+          fields.entries.len()
+        ),
+      )),
+    }
+  }
+
+  /// Read the lexical form of `ty` out of a text value.
+  pub fn parse_text<T: FromXmlString>(&self, ty: &str) -> Result<T, DocumentError> {
+    match self {
+      Self::Text(text) => T::from_xml(text).map_err(|msg| error("", format!("{ty}: {msg}"))),
+      value => Err(mismatch(ty, "text", value)),
+    }
+  }
+
+  /// What the value is, e.g. `a list`.
+  fn kind(&self) -> &'static str {
+    match self {
+      Self::Null => "null",
+      Self::Bool(_) => "a boolean",
+      Self::Integer(_) => "an integer",
+      Self::Float(_) => "a float",
+      Self::Text(_) => "text",
+      Self::List(_) => "a list",
+      Self::Map(_) => "a map",
+    }
+  }
+}
+
+impl DynamicFields<'_> {
+  /// Read the entry `key`, an entry which is missing is read from `Null`.
+  pub fn get<T: FromDynamic>(&self, key: &str) -> Result<T, DocumentError> {
+    let value = self.entries.get(key).unwrap_or(&DynamicValue::Null);
+    T::from_dynamic(value).map_err(|error| nest(error, key))
+  }
+
+  /// The entries of the map `key`, read into the struct or the choice variant `ty`. An error
+  /// reading them is not located at `key`.
+  pub fn fields(&self, key: &str) -> Result<DynamicFields<'_>, DocumentError> {
+    let value = self.entries.get(key).unwrap_or(&DynamicValue::Null);
+    value.fields(self.ty).map_err(|error| nest(error, key))
+  }
+
+  /// The error of an alternative of the choice which is not one of its variants.
+  pub fn unknown_variant(&self, key: &str) -> DocumentError {
+    error(key, format!("{} has no alternative named {key}", self.ty))
+  }
+}
+
+fn error(node_name: &str, msg: String) -> DocumentError {
+  XsdParseError {
+    node_name: node_name.to_string(),
+    msg,
+  }
+  .into()
+}
+
+fn mismatch(ty: &str, expected: &str, value: &DynamicValue) -> DocumentError {
+  error(
+    "",
+    format!("{ty} expects {expected}, found {}", value.kind()),
+  )
+}
+
+/// Locate `error`, raised reading the entry `key` or the item of index `key`, in the value
+/// holding it. The path of the entries is kept as the name of the node, e.g. `part/note[2]`.
+fn nest(error: DocumentError, key: &str) -> DocumentError {
+  let DocumentError::XsdParseError(mut error) = error else {
+    return error;
+  };
+  error.node_name = match error.node_name.as_str() {
+    "" => key.to_string(),
+    path if path.starts_with('[') => format!("{key}{path}"),
+    path => format!("{key}/{path}"),
+  };
+  error.into()
+}
+
+impl ToDynamic for String {
+  fn to_dynamic(&self) -> DynamicValue {
+    DynamicValue::Text(self.clone())
+  }
+}
+
+/// Also the fixed values stored as `&'static str`.
+impl ToDynamic for str {
+  fn to_dynamic(&self) -> DynamicValue {
+    DynamicValue::Text(self.to_string())
+  }
+}
+
+impl ToDynamic for bool {
+  fn to_dynamic(&self) -> DynamicValue {
+    DynamicValue::Bool(*self)
+  }
+}
+
+impl FromDynamic for String {
+  fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+    match value {
+      DynamicValue::Text(text) => Ok(text.clone()),
+      value => Err(mismatch("String", "text", value)),
+    }
+  }
+}
+
+impl FromDynamic for bool {
+  fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+    match value {
+      DynamicValue::Bool(value) => Ok(*value),
+      DynamicValue::Text(text) => match text.as_str() {
+        "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => Err(error("", format!("bool can't be read from {text:?}"))),
+      },
+      value => Err(mismatch("bool", "a boolean", value)),
+    }
+  }
+}
+
+macro_rules! dynamic_integer {
+  ($($ty: ty),*) => {
+    $(
+      impl ToDynamic for $ty {
+        fn to_dynamic(&self) -> DynamicValue {
+          DynamicValue::Integer(*self as i128)
+        }
+      }
+
+      impl FromDynamic for $ty {
+        fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+          let out_of_range = |value: &dyn std::fmt::Display| {
+            error("", format!("{} can't hold {value}", stringify!($ty)))
+          };
+          match value {
+            DynamicValue::Integer(value) => {
+              <$ty>::try_from(*value).map_err(|_| out_of_range(value))
+            }
+            DynamicValue::Text(text) => text.parse().map_err(|_| out_of_range(text)),
+            value => Err(mismatch(stringify!($ty), "an integer", value)),
+          }
+        }
+      }
+    )*
+  };
+}
+
+dynamic_integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+macro_rules! dynamic_float {
+  ($($ty: ty),*) => {
+    $(
+      impl ToDynamic for $ty {
+        fn to_dynamic(&self) -> DynamicValue {
+          DynamicValue::Float(*self as f64)
+        }
+      }
+
+      impl FromDynamic for $ty {
+        fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+          match value {
+            DynamicValue::Float(value) => Ok(*value as $ty),
+            DynamicValue::Integer(value) => Ok(*value as $ty),
+            DynamicValue::Text(text) => text
+              .parse()
+              .map_err(|_| error("", format!("{} can't be read from {text:?}", stringify!($ty)))),
+            value => Err(mismatch(stringify!($ty), "a float", value)),
+          }
+        }
+      }
+    )*
+  };
+}
+
+dynamic_float!(f32, f64);
+
+/// The lexical form of the date.
+impl ToDynamic for Date {
+  fn to_dynamic(&self) -> DynamicValue {
+    DynamicValue::Text(self.to_string())
+  }
+}
+
+impl FromDynamic for Date {
+  fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+    value.parse_text("Date")
+  }
+}
+
+/// The lexical form of the time.
+impl ToDynamic for Time {
+  fn to_dynamic(&self) -> DynamicValue {
+    DynamicValue::Text(self.to_string())
+  }
+}
+
+impl FromDynamic for Time {
+  fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+    value.parse_text("Time")
+  }
+}
+
+/// The lexical form of the date and time.
+impl ToDynamic for DateTime {
+  fn to_dynamic(&self) -> DynamicValue {
+    DynamicValue::Text(self.to_string())
+  }
+}
+
+impl FromDynamic for DateTime {
+  fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+    value.parse_text("DateTime")
+  }
+}
+
+/// The canonical lexical form of the duration.
+impl ToDynamic for Duration {
+  fn to_dynamic(&self) -> DynamicValue {
+    DynamicValue::Text(self.to_string())
+  }
+}
+
+impl FromDynamic for Duration {
+  fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+    value.parse_text("Duration")
+  }
+}
+
+/// The lexical form of the gregorian partial dates and of the binaries.
+macro_rules! dynamic_lexical {
+  ($($ty: ident),*) => {
+    $(
+      impl ToDynamic for $ty {
+        fn to_dynamic(&self) -> DynamicValue {
+          DynamicValue::Text(self.to_string())
+        }
+      }
+
+      impl FromDynamic for $ty {
+        fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+          value.parse_text(stringify!($ty))
+        }
+      }
+    )*
+  };
+}
+
+dynamic_lexical!(
+  GYear,
+  GYearMonth,
+  GMonthDay,
+  GDay,
+  GMonth,
+  HexBinary,
+  Base64Binary
+);
+
+/// The lexical form of the decimal, which keeps all its digits.
+impl ToDynamic for Decimal {
+  fn to_dynamic(&self) -> DynamicValue {
+    DynamicValue::Text(self.to_string())
+  }
+}
+
+/// Read from its lexical form or from an integer.
+impl FromDynamic for Decimal {
+  fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+    match value {
+      DynamicValue::Integer(integer) => Ok(Decimal::new(*integer, 0)),
+      value => value.parse_text("Decimal"),
+    }
+  }
+}
+
+/// The lexical form of the name, the namespace of its prefix is not kept.
+impl ToDynamic for QName {
+  fn to_dynamic(&self) -> DynamicValue {
+    DynamicValue::Text(self.to_string())
+  }
+}
+
+impl FromDynamic for QName {
+  fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+    value.parse_text("QName")
+  }
+}
+
+/// The element written as XML.
+impl ToDynamic for RawXml {
+  fn to_dynamic(&self) -> DynamicValue {
+    let mut xml = vec![];
+    let config = xmltree::EmitterConfig::new().write_document_declaration(false);
+    // Writing to a vector doesn't fail.
+    self.0.write_with_config(&mut xml, config).unwrap();
+    DynamicValue::Text(String::from_utf8_lossy(&xml).into_owned())
+  }
+}
+
+impl FromDynamic for RawXml {
+  fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+    match value {
+      DynamicValue::Text(text) => xmltree::Element::parse(text.as_bytes())
+        .map(RawXml)
+        .map_err(|e| error("", format!("RawXml can't be read: {e}"))),
+      value => Err(mismatch("RawXml", "text", value)),
+    }
+  }
+}
+
+/// A map of the `name`, the `attributes`, the `children` and the `text` of the element.
+impl ToDynamic for AnyXml {
+  fn to_dynamic(&self) -> DynamicValue {
+    let attributes = self
+      .attributes
+      .iter()
+      .map(|(name, value)| (name.as_str(), value.to_dynamic()));
+    DynamicValue::map([
+      ("name", self.name.to_dynamic()),
+      ("attributes", DynamicValue::map(attributes)),
+      ("children", self.children.to_dynamic()),
+      ("text", self.text.to_dynamic()),
+    ])
+  }
+}
+
+impl FromDynamic for AnyXml {
+  fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+    let fields = value.fields("AnyXml")?;
+    // Missing attributes or text are read as none.
+    Ok(Self {
+      name: fields.get("name")?,
+      attributes: attributes(&fields)?,
+      children: fields.get("children")?,
+      text: fields.get::<Option<String>>("text")?.unwrap_or_default(),
+    })
+  }
+}
+
+/// The map of the `attributes` of `fields`, none when it is missing.
+fn attributes(fields: &DynamicFields) -> Result<BTreeMap<String, String>, DocumentError> {
+  match fields.entries.get("attributes") {
+    None | Some(DynamicValue::Null) => Ok(BTreeMap::new()),
+    Some(_) => {
+      let attributes = fields.fields("attributes")?;
+      attributes
+        .entries
+        .keys()
+        .map(|name| Ok((name.clone(), attributes.get(name)?)))
+        .collect()
+    }
+  }
+}
+
+/// A map of the lists of the `attributes` and of the `children`, each attribute a map of its
+/// `namespace`, of its `name` written with its prefix and of its `value`, each child a map of its
+/// `index` and of its `element` written as XML.
+impl ToDynamic for UnknownContent {
+  fn to_dynamic(&self) -> DynamicValue {
+    let attributes = self
+      .attributes
+      .iter()
+      .map(|(name, value)| {
+        DynamicValue::map([
+          ("namespace", name.namespace.to_dynamic()),
+          ("name", name.qualified().to_dynamic()),
+          ("value", value.to_dynamic()),
+        ])
+      })
+      .collect();
+    let children = self
+      .children
+      .iter()
+      .map(|(index, child)| {
+        DynamicValue::map([
+          ("index", index.to_dynamic()),
+          ("element", child.to_dynamic()),
+        ])
+      })
+      .collect();
+    DynamicValue::map([
+      ("attributes", DynamicValue::List(attributes)),
+      ("children", DynamicValue::List(children)),
+    ])
+  }
+}
+
+/// A missing value holds nothing unknown.
+impl FromDynamic for UnknownContent {
+  fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+    if *value == DynamicValue::Null {
+      return Ok(Self::default());
+    }
+
+    let fields = value.fields("UnknownContent")?;
+    Ok(Self {
+      attributes: unknown_items(&fields, "attributes", unknown_attribute)?,
+      children: unknown_items(&fields, "children", unknown_child)?,
+    })
+  }
+}
+
+/// The items of the list `name` of the fields of `UnknownContent`, each read by `read`, none when
+/// it is missing.
+fn unknown_items<T, C: FromIterator<T>>(
+  fields: &DynamicFields,
+  name: &str,
+  read: fn(&DynamicValue) -> Result<T, DocumentError>,
+) -> Result<C, DocumentError> {
+  match fields.entries.get(name) {
+    None | Some(DynamicValue::Null) => Ok(std::iter::empty().collect()),
+    Some(DynamicValue::List(items)) => items
+      .iter()
+      .enumerate()
+      .map(|(index, item)| read(item).map_err(|e| nest(e, &format!("[{index}]"))))
+      .collect::<Result<_, DocumentError>>()
+      .map_err(|e| nest(e, name)),
+    Some(value) => Err(nest(mismatch("UnknownContent", "a list", value), name)),
+  }
+}
+
+/// An attribute of `UnknownContent` along with its value.
+fn unknown_attribute(value: &DynamicValue) -> Result<(AttributeName, String), DocumentError> {
+  let attribute = value.fields("UnknownContent")?;
+  let name: String = attribute.get("name")?;
+  let name = AttributeName::from_qualified(attribute.get("namespace")?, &name);
+  Ok((name, attribute.get("value")?))
+}
+
+/// A child element of `UnknownContent` along with its index.
+fn unknown_child(value: &DynamicValue) -> Result<(usize, RawXml), DocumentError> {
+  let child = value.fields("UnknownContent")?;
+  Ok((child.get("index")?, child.get("element")?))
+}
+
+impl<T: ToDynamic> ToDynamic for Option<T> {
+  fn to_dynamic(&self) -> DynamicValue {
+    self.as_ref().map_or(DynamicValue::Null, T::to_dynamic)
+  }
+}
+
+impl<T: FromDynamic> FromDynamic for Option<T> {
+  fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+    match value {
+      DynamicValue::Null => Ok(None),
+      value => T::from_dynamic(value).map(Some),
+    }
+  }
+}
+
+impl<T: ToDynamic> ToDynamic for Vec<T> {
+  fn to_dynamic(&self) -> DynamicValue {
+    DynamicValue::List(self.iter().map(T::to_dynamic).collect())
+  }
+}
+
+/// A missing list is read as an empty one.
+impl<T: FromDynamic> FromDynamic for Vec<T> {
+  fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+    match value {
+      DynamicValue::Null => Ok(vec![]),
+      DynamicValue::List(items) => items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| T::from_dynamic(item).map_err(|e| nest(e, &format!("[{index}]"))))
+        .collect(),
+      value => Err(mismatch("Vec", "a list", value)),
+    }
+  }
+}
+
+impl<T: ToDynamic, const MIN: usize, const MAX: usize> ToDynamic for RestrictedVec<T, MIN, MAX> {
+  fn to_dynamic(&self) -> DynamicValue {
+    self.0.to_dynamic()
+  }
+}
+
+/// Checks the number of items, like `XsdGen::gen`.
+impl<T: FromDynamic, const MIN: usize, const MAX: usize> FromDynamic
+  for RestrictedVec<T, MIN, MAX>
+{
+  fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+    Self::from_vec(Vec::from_dynamic(value)?).map_err(|msg| error("", msg))
+  }
+}
+
+impl<T: ToDynamic + ?Sized> ToDynamic for Box<T> {
+  fn to_dynamic(&self) -> DynamicValue {
+    (**self).to_dynamic()
+  }
+}
+
+impl<T: ToDynamic + ?Sized> ToDynamic for &T {
+  fn to_dynamic(&self) -> DynamicValue {
+    (**self).to_dynamic()
+  }
+}
+
+impl<T: FromDynamic> FromDynamic for Box<T> {
+  fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+    T::from_dynamic(value).map(Box::new)
+  }
+}
+
+impl FromDynamic for Box<str> {
+  fn from_dynamic(value: &DynamicValue) -> Result<Self, DocumentError> {
+    String::from_dynamic(value).map(String::into_boxed_str)
+  }
+}
+
+#[test]
+fn errors_locate_the_entry() {
+  let value = DynamicValue::map([
+    ("pitch", DynamicValue::Text("C".to_string())),
+    (
+      "dots",
+      DynamicValue::List(vec![
+        DynamicValue::Integer(1),
+        DynamicValue::Text("two".to_string()),
+      ]),
+    ),
+  ]);
+  let fields = value.fields("Note").unwrap();
+  assert_eq!(fields.get::<String>("pitch").unwrap(), "C");
+  assert_eq!(fields.get::<Option<u8>>("octave").unwrap(), None);
+
+  let error = fields.get::<Vec<u8>>("dots").unwrap_err();
+  assert_eq!(
+    error.to_string(),
+    "Error parsing xml node[dots[1]]: u8 can't hold two"
+  );
+  let error = fields
+    .get::<RestrictedVec<String, 1, 0>>("ties")
+    .unwrap_err();
+  assert!(error.to_string().contains("node[ties]"), "{error}");
+  let error = fields.get::<u8>("pitch").unwrap_err();
+  assert!(error.to_string().contains("u8 can't hold C"), "{error}");
+  let error = vec![1u8].to_dynamic().fields("Note").unwrap_err();
+  assert!(
+    error
+      .to_string()
+      .contains("Note expects a map, found a list"),
+    "{error}"
+  );
+}
+
+#[test]
+fn runtime_values_round_trip() {
+  let date = Date::from_xml("2024-01-31+05:30").unwrap();
+  assert_eq!(Date::from_dynamic(&date.to_dynamic()).unwrap(), date);
+  let decimal = Decimal::from_xml("-1.50").unwrap();
+  assert_eq!(
+    decimal.to_dynamic(),
+    DynamicValue::Text("-1.50".to_string())
+  );
+  assert_eq!(
+    Decimal::from_dynamic(&DynamicValue::Integer(3)).unwrap(),
+    Decimal::from(3)
+  );
+
+  let raw = RawXml(xmltree::Element::parse(r#"<a b="c"><d/></a>"#.as_bytes()).unwrap());
+  assert_eq!(
+    raw.to_dynamic(),
+    DynamicValue::Text(r#"<a b="c"><d /></a>"#.to_string())
+  );
+  assert_eq!(RawXml::from_dynamic(&raw.to_dynamic()).unwrap(), raw);
+
+  let any = AnyXml::from(xmltree::Element::parse(r#"<a b="c">e<d/></a>"#.as_bytes()).unwrap());
+  let dynamic = any.to_dynamic();
+  assert_eq!(
+    dynamic
+      .fields("AnyXml")
+      .unwrap()
+      .get::<String>("text")
+      .unwrap(),
+    "e"
+  );
+  assert_eq!(AnyXml::from_dynamic(&dynamic).unwrap(), any);
+
+  let values = Some(Box::new(vec![1.5f64, 2.0]));
+  assert_eq!(
+    Option::<Box<Vec<f64>>>::from_dynamic(&values.to_dynamic()).unwrap(),
+    values
+  );
+}
@@ -0,0 +1,131 @@
+//! The facets of the restrictions checked by the generated code when reading a value, see
+//! `Restriction::facet_checks` in the generator.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// The `pattern` facet of a restriction: an XML Schema regular expression, which matches the
+/// whole value. It's compiled on its first use.
+pub struct Pattern {
+  source: &'static str,
+  regex: OnceLock<Result<Regex, String>>,
+}
+
+impl Pattern {
+  pub const fn new(source: &'static str) -> Self {
+    Self {
+      source,
+      regex: OnceLock::new(),
+    }
+  }
+
+  /// Compile the XML Schema regular expression `source`, failing on the constructs `regex`
+  /// doesn't support, e.g. the block escapes like `\p{IsBasicLatin}`.
+  pub fn compile(source: &str) -> Result<Regex, String> {
+    Regex::new(&format!("^(?:{})$", translate(source)))
+      .map_err(|e| format!("The pattern {source} isn't supported: {e}"))
+  }
+
+  /// Check that `value` matches the pattern.
+  pub fn check(&self, value: &str) -> Result<(), String> {
+    let regex = self
+      .regex
+      .get_or_init(|| Self::compile(self.source))
+      .as_ref()?;
+    if regex.is_match(value) {
+      Ok(())
+    } else {
+      Err(format!(
+        "{value} doesn't match the pattern {}.",
+        self.source
+      ))
+    }
+  }
+}
+
+/// The syntax of `regex` for an XML Schema regular expression: the name character escapes are
+/// spelled out, the class subtractions `[a-z-[aeiou]]` become `[a-z--[aeiou]]`, and `^` and `$`
+/// are literals outside of the classes.
+fn translate(source: &str) -> String {
+  const NAME_START: &str = r"\p{L}_:";
+  const NAME: &str = r"\p{L}\p{M}\p{N}_:.\-";
+
+  let mut output = String::new();
+  let mut depth = 0usize;
+  let mut chars = source.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => match chars.next() {
+        Some('i') => output.push_str(&format!("[{NAME_START}]")),
+        Some('I') => output.push_str(&format!("[^{NAME_START}]")),
+        Some('c') => output.push_str(&format!("[{NAME}]")),
+        Some('C') => output.push_str(&format!("[^{NAME}]")),
+        Some(escaped) => {
+          output.push('\\');
+          output.push(escaped);
+        }
+        None => output.push('\\'),
+      },
+      '[' => {
+        depth += 1;
+        output.push('[');
+        if chars.peek() == Some(&'^') {
+          output.push('^');
+          chars.next();
+        }
+      }
+      ']' if depth > 0 => {
+        depth -= 1;
+        output.push(']');
+      }
+      '-' if depth > 0 && chars.peek() == Some(&'[') => output.push_str("--"),
+      '&' | '~' if depth > 0 => {
+        output.push('\\');
+        output.push(c);
+      }
+      '^' | '$' if depth == 0 => {
+        output.push('\\');
+        output.push(c);
+      }
+      c => output.push(c),
+    }
+  }
+  output
+}
+
+/// The number of digits of the decimal `lexical`, and of its fraction, without the leading and
+/// trailing zeros, as counted by the `totalDigits` and `fractionDigits` facets.
+pub fn decimal_digits(lexical: &str) -> (usize, usize) {
+  let digits = lexical.trim().trim_start_matches(['+', '-']);
+  let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+  let integer = integer.trim_start_matches('0');
+  let fraction = fraction.trim_end_matches('0');
+  (integer.len() + fraction.len(), fraction.len())
+}
+
+#[test]
+fn patterns_match_the_whole_value() {
+  let color = Pattern::new(r"#[\dA-F]{6}([\dA-F][\dA-F])?");
+  assert!(color.check("#40800A").is_ok());
+  assert!(color.check("#40800AFF").is_ok());
+  assert_eq!(
+    color.check("x#40800A").unwrap_err(),
+    r"x#40800A doesn't match the pattern #[\dA-F]{6}([\dA-F][\dA-F])?."
+  );
+
+  let name = Pattern::new(r"\i\c*");
+  assert!(name.check("smufl:noteheadBlack").is_ok());
+  assert!(name.check("1st").is_err());
+
+  let consonants = Pattern::new("[a-z-[aeiou]]+");
+  assert!(consonants.check("rhythm").is_ok());
+  assert!(consonants.check("beat").is_err());
+
+  let literal = Pattern::new("$[&~0-9]*^");
+  assert!(literal.check("$5&~^").is_ok());
+
+  assert!(Pattern::compile(r"\p{IsBasicLatin}+").is_err());
+  assert_eq!(decimal_digits("-012.3400"), (4, 2));
+  assert_eq!(decimal_digits("100"), (3, 0));
+}
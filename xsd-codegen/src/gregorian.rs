@@ -0,0 +1,217 @@
+//! The gregorian partial dates, `xs:gYear`, `xs:gYearMonth`, `xs:gMonthDay`, `xs:gDay` and
+//! `xs:gMonth`, each with its optional timezone.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use chrono::FixedOffset;
+
+use crate::{parse_timezone, write_timezone, FromXmlString};
+
+/// An `xs:gYear`, e.g. `2024` or `-0044Z`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct GYear {
+  pub year: i32,
+  pub timezone: Option<FixedOffset>,
+}
+
+/// An `xs:gYearMonth`, e.g. `2024-05`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct GYearMonth {
+  pub year: i32,
+  pub month: u32,
+  pub timezone: Option<FixedOffset>,
+}
+
+/// An `xs:gMonthDay`, e.g. `--02-29`, which is a day of the leap years.
+#[derive(PartialEq, Debug, Clone)]
+pub struct GMonthDay {
+  pub month: u32,
+  pub day: u32,
+  pub timezone: Option<FixedOffset>,
+}
+
+/// An `xs:gDay`, e.g. `---31`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct GDay {
+  pub day: u32,
+  pub timezone: Option<FixedOffset>,
+}
+
+/// An `xs:gMonth`, e.g. `--12`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct GMonth {
+  pub month: u32,
+  pub timezone: Option<FixedOffset>,
+}
+
+/// Split the timezone ending `string` from its value, e.g. `--05-01` and `+02:00`.
+fn split_timezone(string: &str) -> Result<(&str, Option<FixedOffset>), String> {
+  if let Some(value) = string.strip_suffix('Z') {
+    return Ok((value, Some(parse_timezone("Z")?)));
+  }
+  let bytes = string.as_bytes();
+  match bytes.len().checked_sub(6) {
+    Some(idx) if matches!(bytes[idx], b'+' | b'-') && bytes[idx + 3] == b':' => {
+      Ok((&string[..idx], Some(parse_timezone(&string[idx..])?)))
+    }
+    _ => Ok((string, None)),
+  }
+}
+
+/// The two digits of a month or a day, in `range`.
+fn parse_two_digits(s: &str, range: std::ops::RangeInclusive<u32>) -> Option<u32> {
+  if s.len() != 2 || !s.chars().all(|c| c.is_ascii_digit()) {
+    return None;
+  }
+  s.parse().ok().filter(|value| range.contains(value))
+}
+
+/// A year of at least four digits, without leading zeros past them, e.g. `0044` or `-12345`.
+fn parse_year(s: &str) -> Option<i32> {
+  let digits = s.strip_prefix('-').unwrap_or(s);
+  if digits.len() < 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+    return None;
+  }
+  if digits.len() > 4 && digits.starts_with('0') {
+    return None;
+  }
+  s.parse().ok()
+}
+
+/// Writes `year` with at least four digits, e.g. `-0044`.
+fn write_year(f: &mut Formatter<'_>, year: i32) -> FmtResult {
+  if year < 0 {
+    f.write_str("-")?;
+  }
+  write!(f, "{:04}", year.unsigned_abs())
+}
+
+impl FromXmlString for GYear {
+  fn from_xml(string: &str) -> Result<Self, String> {
+    let (value, timezone) = split_timezone(string)?;
+    let year = parse_year(value).ok_or_else(|| format!("bad gYear format: {string}"))?;
+    Ok(GYear { year, timezone })
+  }
+}
+
+impl Display for GYear {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    write_year(f, self.year)?;
+    write_timezone(f, self.timezone)
+  }
+}
+
+impl FromXmlString for GYearMonth {
+  fn from_xml(string: &str) -> Result<Self, String> {
+    let bad_format = || format!("bad gYearMonth format: {string}");
+    let (value, timezone) = split_timezone(string)?;
+    let (year, month) = value.rsplit_once('-').ok_or_else(bad_format)?;
+    Ok(GYearMonth {
+      year: parse_year(year).ok_or_else(bad_format)?,
+      month: parse_two_digits(month, 1..=12).ok_or_else(bad_format)?,
+      timezone,
+    })
+  }
+}
+
+impl Display for GYearMonth {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    write_year(f, self.year)?;
+    write!(f, "-{:02}", self.month)?;
+    write_timezone(f, self.timezone)
+  }
+}
+
+impl FromXmlString for GMonthDay {
+  fn from_xml(string: &str) -> Result<Self, String> {
+    let bad_format = || format!("bad gMonthDay format: {string}");
+    let (value, timezone) = split_timezone(string)?;
+    let (month, day) = value
+      .strip_prefix("--")
+      .and_then(|value| value.split_once('-'))
+      .ok_or_else(bad_format)?;
+    let month = parse_two_digits(month, 1..=12).ok_or_else(bad_format)?;
+    let day = parse_two_digits(day, 1..=31).ok_or_else(bad_format)?;
+    // 2000 is a leap year, all the days of the months are in it.
+    if chrono::NaiveDate::from_ymd_opt(2000, month, day).is_none() {
+      return Err(format!("{string} is not a day of the year"));
+    }
+    Ok(GMonthDay {
+      month,
+      day,
+      timezone,
+    })
+  }
+}
+
+impl Display for GMonthDay {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    write!(f, "--{:02}-{:02}", self.month, self.day)?;
+    write_timezone(f, self.timezone)
+  }
+}
+
+impl FromXmlString for GDay {
+  fn from_xml(string: &str) -> Result<Self, String> {
+    let (value, timezone) = split_timezone(string)?;
+    let day = value
+      .strip_prefix("---")
+      .and_then(|day| parse_two_digits(day, 1..=31))
+      .ok_or_else(|| format!("bad gDay format: {string}"))?;
+    Ok(GDay { day, timezone })
+  }
+}
+
+impl Display for GDay {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    write!(f, "---{:02}", self.day)?;
+    write_timezone(f, self.timezone)
+  }
+}
+
+impl FromXmlString for GMonth {
+  fn from_xml(string: &str) -> Result<Self, String> {
+    let (value, timezone) = split_timezone(string)?;
+    let month = value
+      .strip_prefix("--")
+      .and_then(|month| parse_two_digits(month, 1..=12))
+      .ok_or_else(|| format!("bad gMonth format: {string}"))?;
+    Ok(GMonth { month, timezone })
+  }
+}
+
+impl Display for GMonth {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    write!(f, "--{:02}", self.month)?;
+    write_timezone(f, self.timezone)
+  }
+}
+
+#[test]
+fn gregorian_dates_keep_their_timezone() {
+  let year = GYear::from_xml("-0044Z").unwrap();
+  assert_eq!(year.year, -44);
+  assert_eq!(year.timezone, FixedOffset::east_opt(0));
+  assert_eq!(year.to_string(), "-0044Z");
+  assert_eq!(GYear::from_xml("12345").unwrap().year, 12345);
+
+  let year_month = GYearMonth::from_xml("2024-05-05:00").unwrap();
+  assert_eq!((year_month.year, year_month.month), (2024, 5));
+  assert_eq!(year_month.timezone, FixedOffset::west_opt(5 * 3600));
+  assert_eq!(year_month.to_string(), "2024-05-05:00");
+
+  let month_day = GMonthDay::from_xml("--02-29+14:00").unwrap();
+  assert_eq!((month_day.month, month_day.day), (2, 29));
+  assert_eq!(month_day.to_string(), "--02-29+14:00");
+  assert_eq!(GDay::from_xml("---31").unwrap().to_string(), "---31");
+  assert_eq!(GMonth::from_xml("--12Z").unwrap().to_string(), "--12Z");
+
+  for value in ["", "24", "02024", "2024Y", "2024+1:00"] {
+    assert!(GYear::from_xml(value).is_err(), "{value}");
+  }
+  assert!(GYearMonth::from_xml("2024-13").is_err());
+  assert!(GMonthDay::from_xml("--04-31").is_err());
+  assert!(GMonthDay::from_xml("02-28").is_err());
+  assert!(GDay::from_xml("--31").is_err());
+  assert!(GMonth::from_xml("--1").is_err());
+}
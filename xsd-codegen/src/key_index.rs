@@ -0,0 +1,81 @@
+//! The index behind the key resolvers generated for the `xs:key` constraints of documents.
+
+use std::{
+  cell::{Cell, OnceCell},
+  collections::BTreeMap,
+  fmt,
+};
+
+/// The keys and the values an index is built from.
+type Entries<'a, K, T> = Box<dyn Iterator<Item = (K, &'a T)> + 'a>;
+
+/// The values of a document by their key, e.g. the staff details of a score by their number.
+/// The entries are only read, and indexed, on the first lookup. When several values have the
+/// same key, the first one is kept.
+pub struct KeyIndex<'a, K, T> {
+  entries: Cell<Option<Entries<'a, K, T>>>,
+  index: OnceCell<BTreeMap<K, &'a T>>,
+}
+
+impl<'a, K: Ord, T> KeyIndex<'a, K, T> {
+  pub fn new(entries: impl Iterator<Item = (K, &'a T)> + 'a) -> Self {
+    Self {
+      entries: Cell::new(Some(Box::new(entries))),
+      index: OnceCell::new(),
+    }
+  }
+
+  fn index(&self) -> &BTreeMap<K, &'a T> {
+    self.index.get_or_init(|| {
+      let mut index = BTreeMap::new();
+      for (key, value) in self.entries.take().into_iter().flatten() {
+        index.entry(key).or_insert(value);
+      }
+      index
+    })
+  }
+
+  /// The value with the key `key`.
+  pub fn get(&self, key: &K) -> Option<&'a T> {
+    self.index().get(key).copied()
+  }
+
+  /// The number of distinct keys.
+  pub fn len(&self) -> usize {
+    self.index().len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.index().is_empty()
+  }
+
+  /// The keys and their value, in key order.
+  pub fn iter(&self) -> impl Iterator<Item = (&K, &'a T)> {
+    self.index().iter().map(|(key, value)| (key, *value))
+  }
+}
+
+impl<K: fmt::Debug, T: fmt::Debug> fmt::Debug for KeyIndex<'_, K, T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.index.get() {
+      Some(index) => f.debug_map().entries(index).finish(),
+      None => f.write_str("KeyIndex(<not built>)"),
+    }
+  }
+}
+
+#[test]
+fn keys_are_indexed_on_the_first_lookup() {
+  let read = Cell::new(0);
+  let staves = [(1, "treble"), (2, "bass"), (1, "alto")];
+  let index = KeyIndex::new(staves.iter().map(|staff| {
+    read.set(read.get() + 1);
+    (staff.0, &staff.1)
+  }));
+  assert_eq!(read.get(), 0);
+
+  assert_eq!(index.get(&1), Some(&"treble"));
+  assert_eq!(index.get(&3), None);
+  assert_eq!(index.len(), 2);
+  assert_eq!(read.get(), 3);
+}
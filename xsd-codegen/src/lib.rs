@@ -1,26 +1,89 @@
+mod any_xml;
+#[cfg(feature = "tokio")]
+mod async_support;
+mod binary;
+mod borrowed;
 mod codegen_helper;
+mod decimal;
+mod document;
+mod duration;
+mod dynamic;
+mod facets;
+mod gregorian;
+mod key_index;
+mod qname;
 mod rust_codegen;
+#[cfg(feature = "serde")]
+mod serde_support;
+mod unknown_content;
 mod xml_element;
+mod xml_writer;
 
 use std::{
+  cell::Cell,
   collections::BTreeMap,
   ops::{Deref, DerefMut},
+  sync::Arc,
 };
 
+use chrono::Offset;
+
+// The code generation is only supported through `xml_schema_parser::Xsd`.
+pub use qname::{QName, XmlTypeIndex};
+#[doc(hidden)]
 pub use rust_codegen::{
-  Block, Enum, Field, Fields, Formatter, Function, Impl, Item, Module, Struct, TupleField, Type,
-  TypeAlias, TypeDef, Variant,
+  escape_str_literal, Block, Enum, Field, Fields, Formatter, Function, Impl, Item, MergeConflict,
+  Module, Occurrence, Provenance, Scope, Struct, Trait, Type, TypeAlias, TypeDef, ValueConstraint,
+  Variant,
 };
-pub use xml_element::XMLElement;
-use xsd_types::{XsdGenError, XsdIoError};
-
+pub use xml_element::{
+  DuplicateAttributes, ParseOptions, UnexpectedElements, XMLElement, XmlSpace,
+};
+use xsd_types::{DocumentError, XsdGenError};
+
+pub use any_xml::AnyXml;
+#[cfg(feature = "tokio")]
+pub use async_support::{parse_async, AsyncRead};
+pub use binary::{Base64Binary, HexBinary};
+pub use borrowed::{parse_document_ref, XMLElementRef, XsdGenRef};
+#[doc(hidden)]
 pub use codegen_helper::{fromxml_impl, xsdgen_impl};
+pub use decimal::Decimal;
+pub use document::{parse_document, parse_document_with, XmlDocument};
+pub use duration::Duration;
+pub use dynamic::{DynamicFields, DynamicValue, FromDynamic, ToDynamic};
+pub use facets::{decimal_digits, Pattern};
+pub use gregorian::{GDay, GMonth, GMonthDay, GYear, GYearMonth};
+pub use key_index::KeyIndex;
+pub use unknown_content::{AttributeName, UnknownContent};
+pub use xml_writer::{write_document, ToXmlString, XsdSer};
+
+/// The API used by the generated code and by the code reading documents with it.
+pub mod prelude {
+  pub use crate::{
+    decimal_digits, parse_document, parse_document_ref, parse_document_with, write_document,
+    xml_element, AnyXml, Base64Binary, ContentKind, Date, DateTime, Decimal, DuplicateAttributes,
+    Duration, DynamicFields, DynamicValue, FieldKind, FieldMeta, FixedValue, FromDynamic,
+    FromXmlString, GDay, GMonth, GMonthDay, GYear, GYearMonth, GenState, GenType, HexBinary,
+    KeyIndex, ParseOptions, Pattern, ProgressEvent, ProgressHook, QName, RawXml, RestrictedVec,
+    Time, ToDynamic, ToXmlString, UnexpectedElements, UnknownContent, VariantMeta, XMLElement,
+    XMLElementRef, XmlDocument, XmlSpace, XmlTypeIndex, XsdGen, XsdGenRef, XsdSer,
+    PROGRESS_INTERVAL,
+  };
+  #[cfg(feature = "tokio")]
+  pub use crate::{parse_async, AsyncRead};
+  #[allow(deprecated)]
+  pub use xsd_types::XsdIoError;
+  pub use xsd_types::{DocumentError, XsdGenError, XsdParseError, XsdType};
+}
 
+#[deprecated(since = "0.0.8", note = "not used by the generated code")]
 #[derive(Default)]
 pub struct TypeStore {
   names: BTreeMap<String, usize>,
 }
 
+#[allow(deprecated)]
 impl TypeStore {
   pub fn get(&mut self, name: &str) -> usize {
     let current_len = self.names.len();
@@ -34,10 +97,27 @@ pub enum GenType {
   Content,
 }
 
+/// Progress of the parsing of a repeated element, reported every `PROGRESS_INTERVAL` items.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProgressEvent {
+  /// The parent element and the name of the repeated element, e.g. `score-partwise/part`.
+  pub path: String,
+  /// Number of repeated elements parsed so far.
+  pub count: usize,
+}
+
+/// Called with the progress of the parsing, see `GenState::progress`.
+pub type ProgressHook = Arc<dyn Fn(ProgressEvent) + Send + Sync>;
+
+/// Number of repeated elements parsed between two progress events.
+pub const PROGRESS_INTERVAL: usize = 1000;
+
 #[derive(Clone)]
 pub struct GenState {
   pub is_root: bool,
   pub state: GenType,
+  /// Reports the progress of the repetitions, checked once per repetition when `None`.
+  pub progress: Option<ProgressHook>,
 }
 
 impl GenState {
@@ -45,6 +125,40 @@ impl GenState {
     Self {
       is_root: self.is_root,
       state: GenType::Attribute,
+      progress: self.progress.clone(),
+    }
+  }
+}
+
+/// Counts the items of a repetition, calling the hook every `PROGRESS_INTERVAL` items.
+struct ProgressCounter {
+  hook: ProgressHook,
+  path: String,
+  count: Cell<usize>,
+}
+
+impl ProgressCounter {
+  fn new(hook: ProgressHook, element: &XMLElement, name: Option<&str>) -> Self {
+    let path = match name {
+      Some(name) => format!("{}/{name}", element.name()),
+      None => element.name().to_string(),
+    };
+
+    Self {
+      hook,
+      path,
+      count: Cell::new(0),
+    }
+  }
+
+  fn tick(&self) {
+    let count = self.count.get() + 1;
+    self.count.set(count);
+    if count.is_multiple_of(PROGRESS_INTERVAL) {
+      (self.hook)(ProgressEvent {
+        path: self.path.clone(),
+        count,
+      });
     }
   }
 }
@@ -57,7 +171,40 @@ where
     element: &mut XMLElement,
     gen_state: GenState,
     name: Option<&str>,
-  ) -> Result<Self, XsdIoError>;
+  ) -> Result<Self, DocumentError>;
+}
+
+/// Parse the repeated content of `element`, calling `tick` after each item.
+fn gen_repeated<T: XsdGen>(
+  element: &mut XMLElement,
+  gen_state: GenState,
+  name: Option<&str>,
+  tick: impl Fn(),
+) -> Result<Vec<T>, DocumentError> {
+  if let Some(name) = name {
+    let mut new_state = gen_state;
+    new_state.is_root = false;
+    element.get_children_with(name, |mut value| {
+      let output = T::gen(&mut value, new_state.clone(), None);
+      tick();
+      output
+    })
+  } else {
+    let mut output = vec![];
+
+    let mut last_element = element.clone();
+    while let Ok(value) = T::gen(element, gen_state.clone(), None) {
+      if element == &mut last_element {
+        break;
+      }
+      output.push(value);
+      tick();
+      last_element = element.clone();
+    }
+    *element = last_element;
+
+    Ok(output)
+  }
 }
 
 impl<T: XsdGen> XsdGen for Vec<T> {
@@ -65,34 +212,18 @@ impl<T: XsdGen> XsdGen for Vec<T> {
     element: &mut XMLElement,
     gen_state: GenState,
     name: Option<&str>,
-  ) -> Result<Self, XsdIoError> {
+  ) -> Result<Self, DocumentError> {
     let output = match gen_state.state {
       GenType::Attribute => {
         vec![T::gen(element, gen_state, name)?]
       }
-      GenType::Content => {
-        if let Some(name) = name {
-          let mut new_state = gen_state;
-          new_state.is_root = false;
-          element.get_children_with(name, |mut value| {
-            T::gen(&mut value, new_state.clone(), None)
-          })?
-        } else {
-          let mut output = vec![];
-
-          let mut last_element = element.clone();
-          while let Ok(value) = T::gen(element, gen_state.clone(), None) {
-            if element == &mut last_element {
-              break;
-            }
-            output.push(value);
-            last_element = element.clone();
-          }
-          *element = last_element;
-
-          output
+      GenType::Content => match gen_state.progress.clone() {
+        None => gen_repeated(element, gen_state, name, || {})?,
+        Some(hook) => {
+          let counter = ProgressCounter::new(hook, element, name);
+          gen_repeated(element, gen_state, name, || counter.tick())?
         }
-      }
+      },
     };
 
     Ok(output)
@@ -104,13 +235,13 @@ impl<T: XsdGen> XsdGen for Option<T> {
     element: &mut XMLElement,
     gen_state: GenState,
     name: Option<&str>,
-  ) -> Result<Self, XsdIoError> {
+  ) -> Result<Self, DocumentError> {
     if let Some(name) = name {
       let output = match gen_state.state {
         GenType::Attribute => {
           let mut new_state = gen_state;
           new_state.is_root = false;
-          if element.element.attributes.contains_key(name) {
+          if element.has_attr(name) {
             Some(T::gen(element, new_state, Some(name))?)
           } else {
             None
@@ -146,13 +277,13 @@ impl<T: FromXmlString> XsdGen for T {
     element: &mut XMLElement,
     gen_state: GenState,
     name: Option<&str>,
-  ) -> Result<Self, XsdIoError> {
+  ) -> Result<Self, DocumentError> {
     match gen_state.state {
       GenType::Attribute => {
         if let Some(name) = name {
           element.get_attribute(name)
         } else {
-          return Err(
+          Err(
             XsdGenError {
               node_name: element.node_name(),
               ty: xsd_types::XsdType::Unknown,
@@ -162,18 +293,18 @@ impl<T: FromXmlString> XsdGen for T {
               ),
             }
             .into(),
-          );
+          )
         }
       }
       GenType::Content => {
         if let Some(name) = name {
           element.get_child_with(name, |mut element| element.get_content())
-        } else if let Ok(content) = element.get_content() {
+        } else if let Some(content) = element.try_get_content()? {
           Ok(content)
         } else if let Ok(content) = T::from_xml("") {
           Ok(content)
         } else {
-          Err(XsdIoError::XsdParseError(xsd_types::XsdParseError {
+          Err(DocumentError::XsdParseError(xsd_types::XsdParseError {
             node_name: element.node_name(),
             msg: "failed to convert text to T".to_string(),
           }))
@@ -183,11 +314,103 @@ impl<T: FromXmlString> XsdGen for T {
   }
 }
 
+/// How a generated field is represented in the document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldKind {
+  Attribute,
+  Element,
+  /// The content of a group, sequence or choice parsed in place.
+  Flattened,
+}
+
+/// What the element of a generated type holds, see the `CONTENT` constant of the generated
+/// types.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ContentKind {
+  /// Nothing at all.
+  #[default]
+  Empty,
+  /// Only attributes.
+  Attributes,
+  /// Only text, e.g. simple types, enumerations and lists.
+  Simple,
+  /// Child elements, or text along with attributes.
+  Complex,
+}
+
+impl ContentKind {
+  /// Whether values of the type can be represented as a single string.
+  pub fn is_simple(self) -> bool {
+    self == ContentKind::Simple
+  }
+
+  pub fn as_str(self) -> &'static str {
+    match self {
+      ContentKind::Empty => "empty",
+      ContentKind::Attributes => "attributes",
+      ContentKind::Simple => "simple",
+      ContentKind::Complex => "complex",
+    }
+  }
+
+  /// The kind of a type holding both the content of `self` and of `other`.
+  pub fn and(self, other: ContentKind) -> ContentKind {
+    match (self, other) {
+      (ContentKind::Empty, kind) | (kind, ContentKind::Empty) => kind,
+      (ContentKind::Attributes, ContentKind::Attributes) => ContentKind::Attributes,
+      (ContentKind::Simple, ContentKind::Simple) => ContentKind::Simple,
+      _ => ContentKind::Complex,
+    }
+  }
+}
+
+/// Structure of a generated field, listed in the `FIELDS` constant of the generated types.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldMeta {
+  /// Name of the Rust field, or its index for tuple structs.
+  pub name: &'static str,
+  pub xml_name: Option<&'static str>,
+  pub namespace: Option<&'static str>,
+  pub kind: FieldKind,
+  pub min: usize,
+  /// `None` when unbounded.
+  pub max: Option<usize>,
+  pub required: bool,
+}
+
+/// Structure of a generated enum variant, listed in the `VARIANTS` constant of the generated
+/// enums.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VariantMeta {
+  pub name: &'static str,
+  pub xml_name: Option<&'static str>,
+  pub namespace: Option<&'static str>,
+  pub fields: &'static [FieldMeta],
+}
+
+/// Implemented by the marker types generated for fixed values.
+pub trait FixedValue {
+  const VALUE: &'static str;
+}
+
+impl<T: FixedValue> FixedValue for Option<T> {
+  const VALUE: &'static str = T::VALUE;
+}
+
 pub trait FromXmlString
 where
   Self: Sized,
 {
   fn from_xml(string: &str) -> Result<Self, String>;
+
+  /// Convert `string` read on an element with the namespaces `namespaces` in scope. Only the
+  /// values naming other components, like `QName`, depend on them.
+  fn from_xml_in(
+    string: &str,
+    _namespaces: Option<&xml::namespace::Namespace>,
+  ) -> Result<Self, String> {
+    Self::from_xml(string)
+  }
 }
 
 impl FromXmlString for String {
@@ -196,6 +419,12 @@ impl FromXmlString for String {
   }
 }
 
+impl FromXmlString for Box<str> {
+  fn from_xml(string: &str) -> Result<Self, String> {
+    Ok(string.into())
+  }
+}
+
 macro_rules! gen_simple_parse_from_xml_string {
   ($ty: ty) => {
     impl FromXmlString for $ty {
@@ -209,6 +438,33 @@ macro_rules! gen_simple_parse_from_xml_string {
 #[derive(Clone, Debug, PartialEq)]
 pub struct RestrictedVec<T, const MIN: usize, const MAX: usize>(Vec<T>);
 
+impl<T, const MIN: usize, const MAX: usize> RestrictedVec<T, MIN, MAX> {
+  /// Check that `values` holds between `MIN` and `MAX` values, `MAX` being unbounded when 0.
+  pub(crate) fn from_vec(values: Vec<T>) -> Result<Self, String> {
+    if values.len() < MIN {
+      return Err(format!(
+        "Generated vector length is less than the minimum size ({} < {MIN})",
+        values.len()
+      ));
+    }
+
+    if MAX != 0 && values.len() > MAX {
+      return Err(format!(
+        "Generated vector length is greater than the maximuim size ({} > {MAX})",
+        values.len()
+      ));
+    }
+
+    Ok(Self(values))
+  }
+}
+
+impl<T, const MAX: usize> Default for RestrictedVec<T, 0, MAX> {
+  fn default() -> Self {
+    Self(vec![])
+  }
+}
+
 impl<T, const MIN: usize, const MAX: usize> Deref for RestrictedVec<T, MIN, MAX> {
   type Target = Vec<T>;
 
@@ -237,29 +493,42 @@ impl<T: XsdGen, const MIN: usize, const MAX: usize> XsdGen for RestrictedVec<T,
     element: &mut XMLElement,
     gen_state: GenState,
     name: Option<&str>,
-  ) -> Result<Self, XsdIoError> {
+  ) -> Result<Self, DocumentError> {
     let gen = <Vec<T> as XsdGen>::gen(element, gen_state, name)?;
-    if gen.len() < MIN {
-      return Err(XsdIoError::XsdParseError(xsd_types::XsdParseError {
+    Self::from_vec(gen).map_err(|msg| {
+      DocumentError::XsdParseError(xsd_types::XsdParseError {
         node_name: element.node_name(),
-        msg: format!(
-          "Generated vector length is less than the minimum size ({} < {MIN})",
-          gen.len()
-        ),
-      }));
-    }
+        msg,
+      })
+    })
+  }
+}
 
-    if MAX != 0 && gen.len() > MAX {
-      return Err(XsdIoError::XsdParseError(xsd_types::XsdParseError {
-        node_name: element.node_name(),
-        msg: format!(
-          "Generated vector length is greater than the maximuim size ({} > {MAX})",
-          gen.len()
-        ),
-      }));
-    }
+/// An element kept as is, without parsing its content. Used for the components stubbed out as
+/// opaque.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawXml(pub xmltree::Element);
 
-    Ok(Self(gen))
+impl XsdGen for RawXml {
+  fn gen(
+    element: &mut XMLElement,
+    gen_state: GenState,
+    name: Option<&str>,
+  ) -> Result<Self, DocumentError> {
+    match (gen_state.state, name) {
+      (GenType::Attribute, _) => Err(
+        XsdGenError {
+          node_name: element.node_name(),
+          ty: xsd_types::XsdType::Unknown,
+          msg: "Raw XML can only be read from an element.".to_string(),
+        }
+        .into(),
+      ),
+      (GenType::Content, Some(name)) => {
+        element.get_child_with(name, |child| Ok(Self(child.into_element())))
+      }
+      (GenType::Content, None) => Ok(Self(element.element().clone())),
+    }
   }
 }
 
@@ -280,34 +549,63 @@ pub struct Date {
   pub timezone: Option<chrono::FixedOffset>,
 }
 
+#[doc(hidden)]
 pub fn parse_timezone(s: &str) -> Result<chrono::FixedOffset, String> {
   if s == "Z" {
-    return Ok(chrono::FixedOffset::east(0));
+    return Ok(chrono::Utc.fix());
   }
 
-  let tokens: Vec<&str> = s[1..].split(':').collect();
+  let mut chars = s.chars();
+  let sign = chars.next();
+  let tokens: Vec<&str> = chars.as_str().split(':').collect();
   if tokens.len() != 2 || tokens[0].len() != 2 || tokens[1].len() != 2 {
     return Err("bad timezone format".to_string());
   }
-  if !tokens.iter().all(|t| t.chars().all(|c| c.is_digit(10))) {
+  if !tokens.iter().all(|t| t.chars().all(|c| c.is_ascii_digit())) {
     return Err("bad timezone format".to_string());
   }
 
-  let hours = tokens[0].parse::<i32>().unwrap();
-  let minutes = tokens[1].parse::<i32>().unwrap();
+  let hours = tokens[0].parse::<i32>().map_err(|e| e.to_string())?;
+  let minutes = tokens[1].parse::<i32>().map_err(|e| e.to_string())?;
 
   if hours > 14 || (hours == 14 && minutes != 0) || minutes >= 60 {
     return Err("bad timezone format: out of range".to_string());
   }
 
   let offset_secs = 60 * (60 * hours + minutes);
-  match s.chars().next().unwrap() {
-    '+' => Ok(chrono::FixedOffset::east(offset_secs)),
-    '-' => Ok(chrono::FixedOffset::west(offset_secs)),
+  match sign {
+    Some('+') => chrono::FixedOffset::east_opt(offset_secs)
+      .ok_or_else(|| "bad timezone format: out of range".to_string()),
+    Some('-') => chrono::FixedOffset::west_opt(offset_secs)
+      .ok_or_else(|| "bad timezone format: out of range".to_string()),
     _ => Err("bad timezone format: timezone should start with '+' or '-'".to_string()),
   }
 }
 
+/// Writes `timezone` as in the lexical forms, e.g. `Z` or `-05:00`.
+fn write_timezone(
+  f: &mut std::fmt::Formatter<'_>,
+  timezone: Option<chrono::FixedOffset>,
+) -> std::fmt::Result {
+  match timezone.map(|tz| tz.local_minus_utc()) {
+    None => Ok(()),
+    Some(0) => f.write_str("Z"),
+    Some(secs) => {
+      let sign = if secs < 0 { '-' } else { '+' };
+      let minutes = secs.abs() / 60;
+      write!(f, "{sign}{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+  }
+}
+
+/// Writes the lexical form read by `from_xml`, e.g. `2024-01-31Z`.
+impl std::fmt::Display for Date {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.value.format("%Y-%m-%d"))?;
+    write_timezone(f, self.timezone)
+  }
+}
+
 impl FromXmlString for Date {
   fn from_xml(string: &str) -> Result<Self, String> {
     fn parse_naive_date(s: &str) -> Result<chrono::NaiveDate, String> {
@@ -317,16 +615,15 @@ impl FromXmlString for Date {
     if let Some(s) = string.strip_suffix('Z') {
       return Ok(Date {
         value: parse_naive_date(s)?,
-        timezone: Some(chrono::FixedOffset::east(0)),
+        timezone: Some(chrono::Utc.fix()),
       });
     }
 
-    if string.contains('+') {
+    if let Some(idx) = string.find('+') {
       if string.matches('+').count() > 1 {
         return Err("bad date format".to_string());
       }
 
-      let idx: usize = string.match_indices('+').collect::<Vec<_>>()[0].0;
       let date_token = &string[..idx];
       let tz_token = &string[idx..];
       return Ok(Date {
@@ -335,8 +632,11 @@ impl FromXmlString for Date {
       });
     }
 
-    if string.matches('-').count() == 3 {
-      let idx: usize = string.match_indices('-').collect::<Vec<_>>()[2].0;
+    // The third dash starts the timezone, e.g. `2024-01-31-05:00`.
+    if let Some(idx) = string
+      .rfind('-')
+      .filter(|_| string.matches('-').count() == 3)
+    {
       let date_token = &string[..idx];
       let tz_token = &string[idx..];
       return Ok(Date {
@@ -351,3 +651,203 @@ impl FromXmlString for Date {
     })
   }
 }
+
+/// Parse the time of an `xs:time` or an `xs:dateTime`, e.g. `13:20:00.5-05:00`, along with
+/// whether it's the `24:00:00` ending the day, read as midnight. The digits of the seconds past
+/// the nanoseconds are dropped.
+fn parse_time(
+  string: &str,
+) -> Result<(chrono::NaiveTime, bool, Option<chrono::FixedOffset>), String> {
+  let bad_format = || format!("bad time format: {string}");
+
+  let (time, timezone) = match string.find(['Z', '+', '-']) {
+    Some(idx) => (&string[..idx], Some(parse_timezone(&string[idx..])?)),
+    None => (string, None),
+  };
+  let (hms, fraction) = match time.split_once('.') {
+    Some((_, "")) => return Err(bad_format()),
+    Some((hms, fraction)) => (hms, fraction),
+    None => (time, ""),
+  };
+  let fields: Vec<&str> = hms.split(':').collect();
+  let is_number = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+  if fields.len() != 3 || fields.iter().any(|f| f.len() != 2 || !is_number(f)) {
+    return Err(bad_format());
+  }
+  if !is_number(fraction) {
+    return Err(bad_format());
+  }
+
+  let [hour, minute, second] = [0, 1, 2].map(|i| fields[i].parse::<u32>().unwrap_or_default());
+  let nanos = format!("{:0<9}", &fraction[..fraction.len().min(9)])
+    .parse::<u32>()
+    .map_err(|e| e.to_string())?;
+  if hour == 24 && minute == 0 && second == 0 && nanos == 0 {
+    return Ok((chrono::NaiveTime::MIN, true, timezone));
+  }
+  // The leap seconds aren't values of the type, `from_hms_nano_opt` only reads them from
+  // nanoseconds past the second.
+  let time =
+    chrono::NaiveTime::from_hms_nano_opt(hour, minute, second, nanos).ok_or_else(bad_format)?;
+  Ok((time, false, timezone))
+}
+
+/// An `xs:time`, e.g. `13:20:00.5-05:00`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Time {
+  /// `24:00:00` is read as `00:00:00`.
+  pub value: chrono::NaiveTime,
+  pub timezone: Option<chrono::FixedOffset>,
+}
+
+/// Writes the lexical form read by `from_xml`, with the seconds as precise as they were read,
+/// e.g. `13:20:00.500-05:00`.
+impl std::fmt::Display for Time {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.value.format("%H:%M:%S%.f"))?;
+    write_timezone(f, self.timezone)
+  }
+}
+
+impl FromXmlString for Time {
+  fn from_xml(string: &str) -> Result<Self, String> {
+    let (value, _, timezone) = parse_time(string)?;
+    Ok(Time { value, timezone })
+  }
+}
+
+/// An `xs:dateTime`, e.g. `2024-01-31T13:20:00Z`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct DateTime {
+  /// `24:00:00` is read as the start of the next day.
+  pub value: chrono::NaiveDateTime,
+  pub timezone: Option<chrono::FixedOffset>,
+}
+
+/// Writes the lexical form read by `from_xml`, e.g. `2024-01-31T13:20:00.500Z`.
+impl std::fmt::Display for DateTime {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.value.format("%Y-%m-%dT%H:%M:%S%.f"))?;
+    write_timezone(f, self.timezone)
+  }
+}
+
+impl FromXmlString for DateTime {
+  fn from_xml(string: &str) -> Result<Self, String> {
+    let (date, time) = string
+      .split_once('T')
+      .ok_or_else(|| format!("bad dateTime format: {string}"))?;
+    let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| e.to_string())?;
+    let (time, end_of_day, timezone) = parse_time(time)?;
+    let date = if end_of_day {
+      date
+        .succ_opt()
+        .ok_or_else(|| format!("{string} is out of range"))?
+    } else {
+      date
+    };
+
+    Ok(DateTime {
+      value: date.and_time(time),
+      timezone,
+    })
+  }
+}
+
+#[test]
+fn progress_is_reported_every_interval() {
+  use std::sync::Mutex;
+
+  let count = 2 * PROGRESS_INTERVAL + 500;
+  let document = format!("<notes>{}</notes>", "<note>c</note>".repeat(count));
+
+  let events = Arc::new(Mutex::new(vec![]));
+  let hook: ProgressHook = {
+    let events = events.clone();
+    Arc::new(move |event| events.lock().unwrap().push(event))
+  };
+
+  let mut element = XMLElement::parse(document.as_bytes()).unwrap();
+  let state = GenState {
+    is_root: true,
+    state: GenType::Content,
+    progress: Some(hook),
+  };
+  let notes = <Vec<String> as XsdGen>::gen(&mut element, state, Some("note")).unwrap();
+  assert_eq!(notes.len(), count);
+
+  let events = events.lock().unwrap();
+  assert_eq!(
+    *events,
+    [
+      ProgressEvent {
+        path: "notes/note".to_string(),
+        count: PROGRESS_INTERVAL,
+      },
+      ProgressEvent {
+        path: "notes/note".to_string(),
+        count: 2 * PROGRESS_INTERVAL,
+      },
+    ]
+  );
+}
+
+#[test]
+fn malformed_dates_are_errors() {
+  let date = Date::from_xml("2024-01-31-05:00").unwrap();
+  assert_eq!(date.timezone, chrono::FixedOffset::west_opt(5 * 3600));
+  assert_eq!(
+    Date::from_xml("2024-01-31Z").unwrap().timezone,
+    Some(chrono::Utc.fix())
+  );
+
+  for value in [
+    "",
+    "+",
+    "2024-01-31+",
+    "2024-01-31+é0:00",
+    "2024-01-31-aa:bb",
+    "2024+01+31",
+  ] {
+    assert!(Date::from_xml(value).is_err(), "{value}");
+  }
+  for value in ["", "é", "+1:00", "*01:00"] {
+    assert!(parse_timezone(value).is_err(), "{value}");
+  }
+}
+
+#[test]
+fn times_keep_their_fraction_and_timezone() {
+  let time = Time::from_xml("13:20:00.5-05:00").unwrap();
+  assert_eq!(
+    time.value,
+    chrono::NaiveTime::from_hms_milli_opt(13, 20, 0, 500).unwrap()
+  );
+  assert_eq!(time.timezone, chrono::FixedOffset::west_opt(5 * 3600));
+  assert_eq!(time.to_string(), "13:20:00.500-05:00");
+  assert_eq!(Time::from_xml("24:00:00").unwrap().to_string(), "00:00:00");
+
+  let date_time = DateTime::from_xml("2024-02-29T09:30:00.123456789Z").unwrap();
+  assert_eq!(date_time.to_string(), "2024-02-29T09:30:00.123456789Z");
+  assert_eq!(
+    DateTime::from_xml("2024-12-31T24:00:00+01:00")
+      .unwrap()
+      .to_string(),
+    "2025-01-01T00:00:00+01:00"
+  );
+
+  for value in [
+    "",
+    "13:20",
+    "13:20:00.",
+    "1:20:00",
+    "13:20:60",
+    "24:00:01",
+    "13:20:00+5",
+  ] {
+    assert!(Time::from_xml(value).is_err(), "{value}");
+  }
+  for value in ["2024-01-31", "2024-01-31 09:30:00", "2024-02-30T09:30:00"] {
+    assert!(DateTime::from_xml(value).is_err(), "{value}");
+  }
+}
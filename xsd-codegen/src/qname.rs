@@ -0,0 +1,133 @@
+//! Values naming other components of the schema, e.g. attributes of type `xs:QName`.
+
+use xml::namespace::{Namespace, NS_NO_PREFIX};
+
+use crate::FromXmlString;
+
+/// A qualified name read from the document. The prefix is resolved through the namespaces in
+/// scope on the element the value is read from.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QName {
+  /// The namespace of the prefix, or the default namespace for unprefixed names. `None` when
+  /// there is no default namespace, or when the value was not read from a document.
+  pub namespace: Option<String>,
+  pub prefix: Option<String>,
+  pub local_name: String,
+}
+
+impl QName {
+  /// The path of the generated type named by this value, if any.
+  pub fn resolve(&self, index: &XmlTypeIndex) -> Option<&'static str> {
+    index.get(self.namespace.as_deref(), &self.local_name)
+  }
+}
+
+impl std::fmt::Display for QName {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match &self.prefix {
+      Some(prefix) => write!(f, "{prefix}:{}", self.local_name),
+      None => f.write_str(&self.local_name),
+    }
+  }
+}
+
+/// Without the namespaces of the document the name is left unresolved.
+impl FromXmlString for QName {
+  fn from_xml(string: &str) -> Result<Self, String> {
+    let string = string.trim();
+    let (prefix, local_name) = match string.split_once(':') {
+      Some((prefix, local_name)) => (Some(prefix), local_name),
+      None => (None, string),
+    };
+
+    let is_ncname = |name: &str| {
+      name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && !name.contains(|c: char| c == ':' || c.is_whitespace())
+    };
+    if !is_ncname(local_name) || prefix.is_some_and(|prefix| !is_ncname(prefix)) {
+      return Err(format!("{string:?} is not a qualified name"));
+    }
+
+    Ok(Self {
+      namespace: None,
+      prefix: prefix.map(str::to_string),
+      local_name: local_name.to_string(),
+    })
+  }
+
+  fn from_xml_in(string: &str, namespaces: Option<&Namespace>) -> Result<Self, String> {
+    let mut name = Self::from_xml(string)?;
+    let prefix = name.prefix.as_deref().unwrap_or(NS_NO_PREFIX);
+    name.namespace = namespaces
+      .and_then(|namespaces| namespaces.get(prefix))
+      .filter(|namespace| !namespace.is_empty())
+      .map(str::to_string);
+
+    if name.prefix.is_some() && name.namespace.is_none() {
+      return Err(format!("the prefix of {name} is not declared"));
+    }
+    Ok(name)
+  }
+}
+
+/// The generated types by the namespace and the name of the schema component they are
+/// generated from, see the `XML_TYPE_INDEX` static of the generated code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct XmlTypeIndex {
+  /// `(namespace, name, path of the generated type)`, sorted by namespace and name.
+  pub types: &'static [(Option<&'static str>, &'static str, &'static str)],
+}
+
+impl XmlTypeIndex {
+  pub const fn new(types: &'static [(Option<&'static str>, &'static str, &'static str)]) -> Self {
+    Self { types }
+  }
+
+  /// The path of the type generated for the component `local_name` of `namespace`.
+  pub fn get(&self, namespace: Option<&str>, local_name: &str) -> Option<&'static str> {
+    self
+      .types
+      .binary_search_by(|(ns, name, _)| (*ns, *name).cmp(&(namespace, local_name)))
+      .ok()
+      .map(|index| self.types[index].2)
+  }
+}
+
+#[test]
+fn names_are_resolved_through_the_namespaces_in_scope() {
+  let mut namespaces = Namespace::empty();
+  namespaces.put("s", "urn:shapes");
+  namespaces.put(NS_NO_PREFIX, "urn:default");
+
+  let name = QName::from_xml_in("s:circle", Some(&namespaces)).unwrap();
+  assert_eq!(name.namespace.as_deref(), Some("urn:shapes"));
+  assert_eq!(name.to_string(), "s:circle");
+  let name = QName::from_xml_in(" square ", Some(&namespaces)).unwrap();
+  assert_eq!(name.namespace.as_deref(), Some("urn:default"));
+  assert_eq!(name.local_name, "square");
+
+  assert!(QName::from_xml_in("t:circle", Some(&namespaces)).is_err());
+  assert!(QName::from_xml_in("circle", None)
+    .unwrap()
+    .namespace
+    .is_none());
+  for value in ["", "s:", ":circle", "a:b:c", "1circle"] {
+    assert!(QName::from_xml(value).is_err(), "{value}");
+  }
+
+  let index = XmlTypeIndex::new(&[
+    (None, "circle", "Circle"),
+    (Some("urn:shapes"), "circle", "shapes::Circle"),
+    (Some("urn:shapes"), "square", "shapes::Square"),
+  ]);
+  let name = QName::from_xml_in("s:circle", Some(&namespaces)).unwrap();
+  assert_eq!(name.resolve(&index), Some("shapes::Circle"));
+  assert_eq!(
+    QName::from_xml("circle").unwrap().resolve(&index),
+    Some("Circle")
+  );
+  assert_eq!(QName::from_xml("square").unwrap().resolve(&index), None);
+}
@@ -9,14 +9,14 @@
 //! For example:
 //!
 //! ```rust
-//! use codegen::Scope;
+//! use xsd_codegen::Scope;
 //!
 //! let mut scope = Scope::new();
 //!
-//! scope.new_struct("Foo")
+//! scope.new_struct(None, "Foo")
 //!     .derive("Debug")
-//!     .field("one", "usize")
-//!     .field("two", "String");
+//!     .field(None, "one", "usize", false, false)
+//!     .field(None, "two", "String", false, false);
 //!
 //! println!("{}", scope.to_string());
 //! ```
@@ -43,9 +43,22 @@ pub struct Scope {
   pub items: Vec<Item>,
 }
 
+/// Two items sharing a name but not a definition, found while merging scopes.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Conflicting definitions of `{path}`:\n{left}\nand\n{right}")]
+pub struct MergeConflict {
+  /// Path of the item in the merged scope, e.g. `note::Pitch` or `impl XsdGen for Note`.
+  pub path: String,
+  /// Rendering of the item already in the scope `merge` is called on.
+  pub left: String,
+  /// Rendering of the incoming item from `other`, the scope passed to `merge`.
+  pub right: String,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypeAlias {
   pub doc: Option<String>,
+  pub vis: Option<String>,
   pub alias: Type,
   pub value: Type,
 }
@@ -53,11 +66,12 @@ pub struct TypeAlias {
 impl TypeAlias {
   pub fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
     if let Some(doc) = &self.doc {
-      for line in doc.lines() {
-        writeln!(fmt, "/// {}", line)?;
-      }
+      fmt_doc_lines(doc, fmt)?;
     }
-    write!(fmt, "pub type ")?;
+    if let Some(ref vis) = self.vis {
+      write!(fmt, "{} ", vis)?;
+    }
+    write!(fmt, "type ")?;
     self.alias.fmt(fmt)?;
     write!(fmt, " = ")?;
     self.value.fmt(fmt)?;
@@ -90,6 +104,9 @@ pub struct Module {
   /// Module documentation
   pub docs: Option<Docs>,
 
+  /// Lints allowed in the module
+  pub allow: Vec<String>,
+
   /// Contents of the module
   pub scope: Scope,
 }
@@ -125,8 +142,41 @@ pub struct Trait {
 pub struct Type {
   pub name: String,
   pub generics: Vec<Type>,
+  /// Const generic arguments, following the type arguments.
+  pub const_generics: Vec<usize>,
   pub xml_name: Option<XsdName>,
   pub docs: Option<Docs>,
+  /// Type to parse the value with when it differs from the declared type.
+  pub parse_as: Option<Box<Type>>,
+  /// Bounds of the particle read into this type when it holds an optional or repeated particle,
+  /// see `Field::occurrence`.
+  pub occurrence: Option<Occurrence>,
+  /// The value of the attribute read into this type when the element doesn't have it.
+  pub value_constraint: Option<ValueConstraint>,
+}
+
+/// The `default` or `fixed` value of an attribute, as the expression of a `&'static str`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueConstraint {
+  /// Read when the attribute is absent.
+  Default(String),
+  /// Read when the attribute is absent, and the only value it can have.
+  Fixed(String),
+}
+
+/// The minimum and maximum (`None` when unbounded) number of times a particle occurs.
+pub type Occurrence = (usize, Option<usize>);
+
+/// The schema component a field, variant or impl was generated from, written after it as a
+/// `// from:` comment when the formatter emits provenance comments.
+#[derive(Debug, Clone)]
+pub struct Provenance {
+  pub name: XsdName,
+  /// The name of the component with the prefix its document declares for the namespace, e.g.
+  /// `mxl:note-type`. `None` until the component is located in its document.
+  pub prefixed_name: Option<String>,
+  /// Location of the schema document the component was generated from.
+  pub document: Option<String>,
 }
 
 /// Defines a type definition.
@@ -147,32 +197,31 @@ pub struct TypeDef {
 pub struct Variant {
   pub name: String,
   pub fields: Fields,
-  pub attributes: String,
+  /// Attributes written before the variant, e.g. `#[serde(rename = "note")]`.
+  pub attributes: Vec<String>,
   pub xml_name: Option<XsdName>,
+  /// The enumeration value the variant is read from
+  pub xml_value: Option<String>,
   pub doc: Option<String>,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub struct TupleField {
-  pub vis: Option<String>,
-  pub ty: Type,
-  pub attribute: bool,
-  pub flatten: bool,
+  /// The schema component the variant was generated from
+  pub provenance: Option<Provenance>,
 }
 
 /// Defines a set of fields.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Fields {
   Empty,
-  Tuple(Vec<TupleField>),
+  /// Fields without a name.
+  Tuple(Vec<Field>),
+  /// Fields with a name.
   Named(Vec<Field>),
 }
 
-/// Defines a struct field.
+/// Defines a struct field, named or part of a tuple.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Field {
-  /// Field name
-  pub name: String,
+  /// Field name, `None` for a tuple field
+  pub name: Option<String>,
 
   /// Field visibility
   pub vis: Option<String>,
@@ -194,6 +243,16 @@ pub struct Field {
 
   /// Should the current xml element be changed when parsing this field
   pub flatten: bool,
+
+  /// Bounds of the particle read into the field, `None` when it occurs exactly once
+  pub occurrence: Option<Occurrence>,
+
+  /// The schema component the field was generated from
+  pub provenance: Option<Provenance>,
+
+  /// Expression of the field value when it isn't read with `XsdGen`, e.g. from what the element
+  /// inherits. Only used by the parsing code of structs.
+  pub value: Option<String>,
 }
 
 /// Defines an associated type.
@@ -221,12 +280,18 @@ pub struct Impl {
   /// Associated types
   pub assoc_tys: Vec<Field>,
 
+  /// Associated constants (visibility, name, type, value)
+  pub assoc_consts: Vec<(Option<String>, String, Type, String)>,
+
   /// Bounds
   pub bounds: Vec<Bound>,
 
   pub fns: Vec<Function>,
 
   pub macros: Vec<String>,
+
+  /// The schema component the impl was generated from
+  pub provenance: Option<Provenance>,
 }
 
 /// Defines an import (`use` statement).
@@ -309,6 +374,9 @@ pub struct Formatter<'a> {
 
   /// Number of spaces per indentiation
   indent: usize,
+
+  /// Write the provenance of the fields, variants and impls as comments.
+  provenance_comments: bool,
 }
 
 const DEFAULT_INDENT: usize = 4;
@@ -336,7 +404,7 @@ impl Scope {
     self
       .imports
       .entry(path.to_string())
-      .or_insert_with(BTreeMap::new)
+      .or_default()
       .entry(ty.to_string())
       .or_insert_with(|| Import::new(path, ty))
   }
@@ -504,18 +572,76 @@ impl Scope {
     self
   }
 
-  /// Return a string representation of the scope.
-  pub fn to_string(&self) -> String {
-    let mut ret = String::new();
+  /// Combine the items and imports of `other` into this scope.
+  ///
+  /// Modules with the same name are merged recursively. Other items with the same name are
+  /// only kept once and must render identically, raw items are kept once per distinct text.
+  pub fn merge(mut self, other: Scope) -> Result<Scope, MergeConflict> {
+    self.merge_from("", other)?;
+    Ok(self)
+  }
 
-    self.fmt(&mut Formatter::new(&mut ret)).unwrap();
+  fn merge_from(&mut self, path: &str, other: Scope) -> Result<(), MergeConflict> {
+    if self.docs.is_none() {
+      self.docs = other.docs;
+    }
 
-    // Remove the trailing newline
-    if ret.as_bytes().last() == Some(&b'\n') {
-      ret.pop();
+    for (import_path, imports) in other.imports {
+      let entry = self.imports.entry(import_path).or_default();
+      for (ty, import) in imports {
+        entry.entry(ty).or_insert(import);
+      }
     }
 
-    ret
+    // Items of `other` sharing a key, like the inherent impls of a type, don't conflict.
+    let original = self.items.len();
+    for item in other.items {
+      if let Item::Module(module) = item {
+        let module_path = format!("{path}{}::", module.name);
+        match self.get_module_mut(&module.name) {
+          Some(existing) => {
+            for allow in module.allow {
+              if !existing.allow.contains(&allow) {
+                existing.allow.push(allow);
+              }
+            }
+            existing.scope.merge_from(&module_path, module.scope)?
+          }
+          None => self.items.push(Item::Module(module)),
+        }
+        continue;
+      }
+
+      let rendered = item.render();
+      let key = item.merge_key();
+      let same_key = self.items[..original]
+        .iter()
+        .filter(|existing| key.is_some() && existing.merge_key() == key)
+        .map(Item::render)
+        .collect::<Vec<_>>();
+
+      if same_key.contains(&rendered)
+        || self
+          .items
+          .iter()
+          .any(|existing| matches!(existing, Item::Raw(raw) if item.is_raw(raw)))
+      {
+        continue;
+      }
+
+      match (key, same_key.into_iter().next()) {
+        (Some(key), Some(left)) => {
+          return Err(MergeConflict {
+            path: format!("{path}{key}"),
+            left: left.trim_end().to_string(),
+            right: rendered.trim_end().to_string(),
+          })
+        }
+        _ => self.items.push(item),
+      }
+    }
+
+    Ok(())
   }
 
   /// Formats the scope using the given formatter.
@@ -527,22 +653,14 @@ impl Scope {
     }
 
     for (i, item) in self.items.iter().enumerate() {
-      if i != 0 {
+      // A raw comment is kept next to the item it precedes.
+      let after_comment =
+        i != 0 && matches!(&self.items[i - 1], Item::Raw(raw) if raw.starts_with("//"));
+      if i != 0 && !after_comment {
         writeln!(fmt)?;
       }
 
-      match *item {
-        Item::Module(ref v) => v.fmt(fmt)?,
-        Item::Struct(ref v) => v.fmt(fmt)?,
-        Item::Function(ref v) => v.fmt(false, fmt)?,
-        Item::Trait(ref v) => v.fmt(fmt)?,
-        Item::Enum(ref v) => v.fmt(fmt)?,
-        Item::Impl(ref v) => v.fmt(fmt)?,
-        Item::TypeAlias(ref v) => v.fmt(fmt)?,
-        Item::Raw(ref v) => {
-          writeln!(fmt, "{}", v)?;
-        }
-      }
+      item.fmt(fmt)?;
     }
 
     Ok(())
@@ -602,6 +720,73 @@ impl Scope {
   }
 }
 
+// ===== impl Item =====
+
+impl Item {
+  /// Formats the item using the given formatter.
+  pub fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+    match *self {
+      Item::Module(ref v) => v.fmt(fmt),
+      Item::Struct(ref v) => v.fmt(fmt),
+      Item::Function(ref v) => v.fmt(false, fmt),
+      Item::Trait(ref v) => v.fmt(fmt),
+      Item::Enum(ref v) => v.fmt(fmt),
+      Item::Impl(ref v) => v.fmt(fmt),
+      Item::TypeAlias(ref v) => v.fmt(fmt),
+      Item::Raw(ref v) => writeln!(fmt, "{}", v),
+    }
+  }
+
+  fn is_raw(&self, text: &str) -> bool {
+    matches!(self, Item::Raw(raw) if raw == text)
+  }
+
+  fn render(&self) -> String {
+    let mut dst = String::new();
+    self.fmt(&mut Formatter::new(&mut dst)).unwrap();
+    dst
+  }
+
+  /// The name identifying the item within its scope, raw items have none.
+  fn merge_key(&self) -> Option<String> {
+    match self {
+      Item::Module(v) => Some(v.name.clone()),
+      Item::Struct(v) => Some(v.type_def.ty.name.clone()),
+      Item::Enum(v) => Some(v.type_def.ty.name.clone()),
+      Item::Trait(v) => Some(v.type_def.ty.name.clone()),
+      Item::TypeAlias(v) => Some(v.alias.name.clone()),
+      Item::Function(v) => Some(format!("fn {}", v.name)),
+      Item::Impl(v) => Some(match &v.impl_trait {
+        Some(impl_trait) => format!("impl {impl_trait} for {}", v.target),
+        None => format!("impl {}", v.target),
+      }),
+      Item::Raw(_) => None,
+    }
+  }
+}
+
+impl Default for Scope {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl fmt::Display for Scope {
+  /// Formats the scope, removing the trailing newline.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut ret = String::new();
+
+    Scope::fmt(self, &mut Formatter::new(&mut ret))?;
+
+    // Remove the trailing newline
+    if ret.as_bytes().last() == Some(&b'\n') {
+      ret.pop();
+    }
+
+    f.write_str(&ret)
+  }
+}
+
 // ===== impl Module =====
 
 impl Module {
@@ -611,6 +796,7 @@ impl Module {
       name: name.to_string(),
       vis: None,
       docs: None,
+      allow: vec![],
       scope: Scope::new(),
     }
   }
@@ -626,6 +812,12 @@ impl Module {
     self
   }
 
+  /// Specify lint attribute to supress a warning or error in the module.
+  pub fn allow(&mut self, allow: &str) -> &mut Self {
+    self.allow.push(allow.to_string());
+    self
+  }
+
   /// Import a type into the module's scope.
   ///
   /// This results in a new `use` statement bein added to the beginning of the
@@ -781,6 +973,10 @@ impl Module {
 
   /// Formats the module using the given formatter.
   pub fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+    if !self.allow.is_empty() {
+      writeln!(fmt, "#[allow({})]", self.allow.join(", "))?;
+    }
+
     if let Some(ref vis) = self.vis {
       write!(fmt, "{} ", vis)?;
     }
@@ -958,6 +1154,12 @@ impl Trait {
     self
   }
 
+  /// Specify lint attribute to supress a warning or error.
+  pub fn allow(&mut self, allow: &str) -> &mut Self {
+    self.type_def.allow(allow);
+    self
+  }
+
   /// Add a macro to the trait def (e.g. `"#[async_trait]"`)
   pub fn r#macro(&mut self, r#macro: &str) -> &mut Self {
     self.type_def.r#macro(r#macro);
@@ -999,7 +1201,8 @@ impl Trait {
     self.fns.last_mut().unwrap()
   }
 
-  /// Push a function definition.
+  /// Push a function definition. A function with a body is a default method, an empty body is
+  /// written `{}`.
   pub fn push_fn(&mut self, item: Function) -> &mut Self {
     self.fns.push(item);
     self
@@ -1144,14 +1347,21 @@ impl Variant {
     Variant {
       name: name.to_string(),
       fields: Fields::Empty,
-      attributes: String::new(),
+      attributes: vec![],
       xml_name,
+      xml_value: None,
       doc: None,
+      provenance: None,
     }
   }
 
+  pub fn xml_value(mut self, value: &str) -> Self {
+    self.xml_value = Some(value.to_string());
+    self
+  }
+
   pub fn attribute(mut self, attribute: &str) -> Self {
-    self.attributes.push_str(attribute);
+    self.attributes.push(attribute.to_string());
     self
   }
 
@@ -1183,11 +1393,21 @@ impl Variant {
     self
   }
 
+  pub fn provenance(mut self, provenance: Provenance) -> Self {
+    self.provenance = Some(provenance);
+    self
+  }
+
   /// Formats the variant using the given formatter.
   pub fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+    for attribute in &self.attributes {
+      writeln!(fmt, "{}", attribute)?;
+    }
     write!(fmt, "{}", self.name)?;
     self.fields.fmt(fmt)?;
-    writeln!(fmt, ",")?;
+    write!(fmt, ",")?;
+    fmt_provenance(self.provenance.as_ref(), fmt)?;
+    writeln!(fmt)?;
 
     Ok(())
   }
@@ -1202,7 +1422,11 @@ impl Type {
       xml_name,
       name: name.to_string(),
       generics: vec![],
+      const_generics: vec![],
       docs: None,
+      parse_as: None,
+      occurrence: None,
+      value_constraint: None,
     }
   }
 
@@ -1221,9 +1445,32 @@ impl Type {
     self
   }
 
+  /// Parse the value as `ty` instead of this type.
+  pub fn parse_as(mut self, ty: Type) -> Self {
+    self.parse_as = Some(Box::new(ty));
+    self
+  }
+
+  /// Read the attribute with the value constraint `constraint`, see `XMLElement::gen_attribute_or`.
+  pub fn value_constraint(mut self, constraint: ValueConstraint) -> Self {
+    self.value_constraint = Some(constraint);
+    self
+  }
+
+  /// Record that the type holds a particle occurring between `min` and `max` (`None` when
+  /// unbounded) times.
+  pub fn occurs(mut self, min: usize, max: Option<usize>) -> Self {
+    self.occurrence = Some((min, max));
+    self
+  }
+
   pub fn wrap(mut self, ty: &str) -> Self {
     self.generics = vec![self.clone()];
+    self.const_generics = vec![];
     self.name = ty.to_string();
+    self.parse_as = None;
+    self.occurrence = None;
+    self.value_constraint = None;
 
     self
   }
@@ -1243,6 +1490,12 @@ impl Type {
     self
   }
 
+  /// Add a const generic argument to the type.
+  pub fn const_generic(mut self, value: usize) -> Self {
+    self.const_generics.push(value);
+    self
+  }
+
   /// Rewrite the `Type` with the provided path
   pub fn path(&self, path: &str) -> Type {
     assert!(!self.name.contains("::"));
@@ -1254,36 +1507,36 @@ impl Type {
     Type {
       name,
       generics: self.generics.clone(),
+      const_generics: self.const_generics.clone(),
       xml_name: self.xml_name.clone(),
       docs: self.docs.clone(),
+      parse_as: self.parse_as.clone(),
+      occurrence: self.occurrence,
+      value_constraint: self.value_constraint.clone(),
     }
   }
 
-  pub fn to_string(&self) -> String {
-    let mut dst = String::new();
-    let mut formatter = Formatter::new(&mut dst);
-    self.fmt(&mut formatter);
-
-    dst
-  }
-
   /// Formats the struct using the given formatter.
   pub fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
     write!(fmt, "{}", self.name)?;
-    Type::fmt_slice(&self.generics, fmt)
-  }
 
-  fn fmt_slice(generics: &[Type], fmt: &mut Formatter) -> fmt::Result {
-    if !generics.is_empty() {
+    if !self.generics.is_empty() || !self.const_generics.is_empty() {
       write!(fmt, "<")?;
 
-      for (i, ty) in generics.iter().enumerate() {
+      for (i, ty) in self.generics.iter().enumerate() {
         if i != 0 {
           write!(fmt, ", ")?
         }
         ty.fmt(fmt)?;
       }
 
+      for (i, value) in self.const_generics.iter().enumerate() {
+        if i != 0 || !self.generics.is_empty() {
+          write!(fmt, ", ")?
+        }
+        write!(fmt, "{}", value)?;
+      }
+
       write!(fmt, ">")?;
     }
 
@@ -1291,6 +1544,14 @@ impl Type {
   }
 }
 
+impl fmt::Display for Type {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut dst = String::new();
+    Type::fmt(self, &mut Formatter::new(&mut dst))?;
+    f.write_str(&dst)
+  }
+}
+
 impl<'a> From<&'a str> for Type {
   fn from(src: &'a str) -> Self {
     Type::new(None, src)
@@ -1302,8 +1563,12 @@ impl From<String> for Type {
     Type {
       name: src,
       generics: vec![],
+      const_generics: vec![],
       xml_name: None,
       docs: None,
+      parse_as: None,
+      occurrence: None,
+      value_constraint: None,
     }
   }
 }
@@ -1446,6 +1711,38 @@ impl TypeDef {
   }
 }
 
+/// Format `value` as a Rust string literal, quotes included.
+///
+/// Use this whenever text coming from a schema ends up in the generated source.
+pub fn escape_str_literal(value: &str) -> String {
+  format!("\"{}\"", value.escape_debug())
+}
+
+/// The documentation of the bounds of a field, e.g. `Occurs: 0..∞`.
+fn occurrence_doc((min, max): Occurrence) -> String {
+  match max {
+    Some(max) => format!("Occurs: {min}..{max}"),
+    None => format!("Occurs: {min}..∞"),
+  }
+}
+
+/// Write the provenance trailing a field or a variant, when the formatter emits it.
+fn fmt_provenance(provenance: Option<&Provenance>, fmt: &mut Formatter) -> fmt::Result {
+  match provenance {
+    Some(provenance) if fmt.provenance_comments => write!(fmt, " // from: {provenance}"),
+    _ => Ok(()),
+  }
+}
+
+/// Write `docs` as doc comments, bare carriage returns are not allowed in them.
+fn fmt_doc_lines(docs: &str, fmt: &mut Formatter) -> fmt::Result {
+  for line in docs.lines() {
+    writeln!(fmt, "/// {}", line.replace('\r', ""))?;
+  }
+
+  Ok(())
+}
+
 fn fmt_generics(generics: &[String], fmt: &mut Formatter) -> fmt::Result {
   if !generics.is_empty() {
     write!(fmt, "<")?;
@@ -1520,18 +1817,48 @@ impl Field {
   where
     T: Into<Type>,
   {
+    let ty = ty.into();
     Field {
-      name: name.into(),
-      ty: ty.into(),
+      name: Some(name.into()),
       vis: None,
       documentation: Vec::new(),
       annotation: Vec::new(),
       xml_name,
       attribute,
       flatten,
+      occurrence: ty.occurrence,
+      provenance: None,
+      value: None,
+      ty,
     }
   }
 
+  /// Return a tuple field of the provided type, read from the element named by the type.
+  pub fn tuple<T>(ty: T, attribute: bool, flatten: bool) -> Self
+  where
+    T: Into<Type>,
+  {
+    let ty = ty.into();
+    Field {
+      name: None,
+      vis: None,
+      documentation: Vec::new(),
+      annotation: Vec::new(),
+      xml_name: ty.xml_name.clone(),
+      occurrence: ty.occurrence,
+      provenance: None,
+      value: None,
+      ty,
+      attribute,
+      flatten,
+    }
+  }
+
+  /// The name of a named field, empty for a tuple field.
+  pub fn name(&self) -> &str {
+    self.name.as_deref().unwrap_or_default()
+  }
+
   /// Set field's documentation.
   pub fn doc(&mut self, documentation: Vec<&str>) -> &mut Self {
     self.documentation = documentation.iter().map(|doc| doc.to_string()).collect();
@@ -1548,6 +1875,51 @@ impl Field {
     self.vis = Some(vis.to_string());
     self
   }
+
+  pub fn provenance(mut self, provenance: Provenance) -> Self {
+    self.provenance = Some(provenance);
+    self
+  }
+
+  /// Set the expression of the field value, see `Field::value`.
+  pub fn value(mut self, value: &str) -> Self {
+    self.value = Some(value.to_string());
+    self
+  }
+}
+
+// ===== impl Provenance =====
+
+impl Provenance {
+  /// Return the provenance of the component `name`, before it is located in its document.
+  pub fn new(name: XsdName) -> Self {
+    Self {
+      name,
+      prefixed_name: None,
+      document: None,
+    }
+  }
+}
+
+// The provenance is not part of the generated code, the same field generated from two components
+// is the same field.
+impl PartialEq for Provenance {
+  fn eq(&self, _: &Self) -> bool {
+    true
+  }
+}
+
+impl fmt::Display for Provenance {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.prefixed_name {
+      Some(name) => write!(f, "{name} ({:?}", self.name.ty)?,
+      None => write!(f, "{} ({:?}", self.name, self.name.ty)?,
+    }
+    match &self.document {
+      Some(document) => write!(f, ", {document})"),
+      None => write!(f, ")"),
+    }
+  }
 }
 
 // ===== impl Fields =====
@@ -1578,38 +1950,16 @@ impl Fields {
   where
     T: Into<Type>,
   {
-    self.push_named(Field {
-      name: name.to_string(),
-      ty: ty.into(),
-      vis: None,
-      documentation: Vec::new(),
-      annotation: Vec::new(),
-      xml_name,
-      attribute,
-      flatten,
-    })
+    self.push_named(Field::new(xml_name, name, ty, attribute, flatten))
   }
 
-  pub fn tuple_vis<T>(&mut self, vis: &str, ty: T, attribute: bool, flatten: bool) -> &mut Self
-  where
-    T: Into<Type>,
-  {
+  pub fn push_tuple(&mut self, field: Field) -> &mut Self {
     match *self {
       Fields::Empty => {
-        *self = Fields::Tuple(vec![TupleField {
-          vis: Some(vis.to_string()),
-          ty: ty.into(),
-          attribute,
-          flatten,
-        }]);
+        *self = Fields::Tuple(vec![field]);
       }
       Fields::Tuple(ref mut fields) => {
-        fields.push(TupleField {
-          vis: Some(vis.to_string()),
-          ty: ty.into(),
-          attribute,
-          flatten,
-        });
+        fields.push(field);
       }
       _ => panic!("field list is tuple"),
     }
@@ -1617,31 +1967,35 @@ impl Fields {
     self
   }
 
+  pub fn tuple_vis<T>(&mut self, vis: &str, ty: T, attribute: bool, flatten: bool) -> &mut Self
+  where
+    T: Into<Type>,
+  {
+    self.push_tuple(Field::tuple(ty, attribute, flatten).vis(vis))
+  }
+
   pub fn tuple<T>(&mut self, vis: Option<&str>, ty: T, attribute: bool, flatten: bool) -> &mut Self
   where
     T: Into<Type>,
   {
-    match *self {
-      Fields::Empty => {
-        *self = Fields::Tuple(vec![TupleField {
-          vis: vis.map(|v| v.to_string()),
-          ty: ty.into(),
-          attribute,
-          flatten,
-        }]);
-      }
-      Fields::Tuple(ref mut fields) => {
-        fields.push(TupleField {
-          vis: vis.map(|v| v.to_string()),
-          ty: ty.into(),
-          attribute,
-          flatten,
-        });
-      }
-      _ => panic!("field list is tuple"),
+    let mut field = Field::tuple(ty, attribute, flatten);
+    field.vis = vis.map(|v| v.to_string());
+    self.push_tuple(field)
+  }
+
+  /// The fields, named or not.
+  pub fn fields(&self) -> &[Field] {
+    match self {
+      Fields::Empty => &[],
+      Fields::Tuple(fields) | Fields::Named(fields) => fields,
     }
+  }
 
-    self
+  pub fn fields_mut(&mut self) -> &mut [Field] {
+    match self {
+      Fields::Empty => &mut [],
+      Fields::Tuple(fields) | Fields::Named(fields) => fields,
+    }
   }
 
   fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
@@ -1653,9 +2007,12 @@ impl Fields {
           for f in fields {
             if !f.documentation.is_empty() {
               for doc in &f.documentation {
-                writeln!(fmt, "/// {}", doc)?;
+                fmt_doc_lines(doc, fmt)?;
               }
             }
+            if let Some(occurrence) = f.occurrence {
+              fmt_doc_lines(&occurrence_doc(occurrence), fmt)?;
+            }
             if !f.annotation.is_empty() {
               for ann in &f.annotation {
                 writeln!(fmt, "{}", ann)?;
@@ -1666,10 +2023,12 @@ impl Fields {
               "{}{}{}: ",
               f.vis.as_deref().unwrap_or(""),
               if f.vis.is_some() { " " } else { "" },
-              f.name
+              f.name()
             )?;
             f.ty.fmt(fmt)?;
-            writeln!(fmt, ",")?;
+            write!(fmt, ",")?;
+            fmt_provenance(f.provenance.as_ref(), fmt)?;
+            writeln!(fmt)?;
           }
 
           Ok(())
@@ -1680,7 +2039,7 @@ impl Fields {
 
         write!(fmt, "(")?;
 
-        for (i, TupleField { vis, ty, .. }) in tys.iter().enumerate() {
+        for (i, Field { vis, ty, .. }) in tys.iter().enumerate() {
           if i != 0 {
             write!(fmt, ", ")?;
           }
@@ -1713,9 +2072,11 @@ impl Impl {
       generics: vec![],
       impl_trait: None,
       assoc_tys: vec![],
+      assoc_consts: vec![],
       bounds: vec![],
       fns: vec![],
       macros: vec![],
+      provenance: None,
     }
   }
 
@@ -1764,7 +2125,7 @@ impl Impl {
     T: Into<Type>,
   {
     self.assoc_tys.push(Field {
-      name: name.to_string(),
+      name: Some(name.to_string()),
       ty: ty.into(),
       vis: None,
       documentation: Vec::new(),
@@ -1772,11 +2133,45 @@ impl Impl {
       xml_name,
       attribute,
       flatten,
+      occurrence: None,
+      provenance: None,
+      value: None,
     });
 
     self
   }
 
+  /// Set an associated constant.
+  pub fn associate_const<T>(&mut self, name: &str, ty: T, value: &str) -> &mut Self
+  where
+    T: Into<Type>,
+  {
+    self
+      .assoc_consts
+      .push((None, name.to_string(), ty.into(), value.to_string()));
+    self
+  }
+
+  /// Set an associated constant with the given visibility, only valid on inherent impls.
+  pub fn associate_const_with_vis<T>(
+    &mut self,
+    vis: &str,
+    name: &str,
+    ty: T,
+    value: &str,
+  ) -> &mut Self
+  where
+    T: Into<Type>,
+  {
+    self.assoc_consts.push((
+      Some(vis.to_string()),
+      name.to_string(),
+      ty.into(),
+      value.to_string(),
+    ));
+    self
+  }
+
   /// Add a `where` bound to the impl block.
   pub fn bound<T>(&mut self, name: &str, ty: T) -> &mut Self
   where
@@ -1797,6 +2192,9 @@ impl Impl {
 
   /// Formats the impl block using the given formatter.
   pub fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+    if let Some(provenance) = self.provenance.as_ref().filter(|_| fmt.provenance_comments) {
+      writeln!(fmt, "// from: {provenance}")?;
+    }
     for m in self.macros.iter() {
       writeln!(fmt, "{}", m)?;
     }
@@ -1818,14 +2216,23 @@ impl Impl {
       // format associated types
       if !self.assoc_tys.is_empty() {
         for ty in &self.assoc_tys {
-          write!(fmt, "type {} = ", ty.name)?;
+          write!(fmt, "type {} = ", ty.name())?;
           ty.ty.fmt(fmt)?;
           writeln!(fmt, ";")?;
         }
       }
 
+      for (vis, name, ty, value) in &self.assoc_consts {
+        if let Some(vis) = vis {
+          write!(fmt, "{} ", vis)?;
+        }
+        write!(fmt, "const {}: ", name)?;
+        ty.fmt(fmt)?;
+        writeln!(fmt, " = {};", value)?;
+      }
+
       for (i, func) in self.fns.iter().enumerate() {
-        if i != 0 || !self.assoc_tys.is_empty() {
+        if i != 0 || !self.assoc_tys.is_empty() || !self.assoc_consts.is_empty() {
           writeln!(fmt)?;
         }
 
@@ -1848,6 +2255,11 @@ impl Import {
     }
   }
 
+  /// The imported path, e.g. `std::fmt::Debug`.
+  pub fn line(&self) -> &str {
+    &self.line
+  }
+
   /// Set the import visibility.
   pub fn vis(&mut self, vis: &str) -> &mut Self {
     self.vis = Some(vis.to_string());
@@ -1931,7 +2343,7 @@ impl Function {
     T: Into<Type>,
   {
     self.args.push(Field {
-      name: name.to_string(),
+      name: Some(name.to_string()),
       ty: ty.into(),
       vis: None,
       // While a `Field` is used here, both `documentation`
@@ -1942,6 +2354,9 @@ impl Function {
       xml_name: None,
       attribute: false,
       flatten: false,
+      occurrence: None,
+      provenance: None,
+      value: None,
     });
 
     self
@@ -1981,10 +2396,20 @@ impl Function {
     self
   }
 
+  /// Push a `//` comment to the function implementation, one per line of `text`.
+  pub fn comment(mut self, text: &str) -> Self {
+    let body = self.body.get_or_insert(vec![]);
+    for line in text.lines() {
+      body.push(Body::String(format!("// {line}")));
+    }
+
+    self
+  }
+
   /// Add an attribute to the function.
   ///
   /// ```
-  /// use codegen::Function;
+  /// use xsd_codegen::Function;
   ///
   /// let mut func = Function::new("test");
   ///
@@ -1998,7 +2423,7 @@ impl Function {
 
   /// Specify an `extern` ABI for the function.
   /// ```
-  /// use codegen::Function;
+  /// use xsd_codegen::Function;
   ///
   /// let mut extern_func = Function::new("extern_func");
   ///
@@ -2064,7 +2489,7 @@ impl Function {
         write!(fmt, ", ")?;
       }
 
-      write!(fmt, "{}: ", arg.name)?;
+      write!(fmt, "{}: ", arg.name())?;
       arg.ty.fmt(fmt)?;
     }
 
@@ -2078,6 +2503,7 @@ impl Function {
     fmt_bounds(&self.bounds, fmt)?;
 
     match self.body {
+      Some(ref body) if body.is_empty() && is_trait => writeln!(fmt, " {{}}"),
       Some(ref body) => fmt.block(|fmt| {
         for b in body {
           b.fmt(fmt)?;
@@ -2123,6 +2549,20 @@ impl Block {
     self
   }
 
+  /// Push a `//` comment to the code block, one per line of `text`.
+  pub fn comment(mut self, text: &str) -> Self {
+    for line in text.lines() {
+      self.body.push(Body::String(format!("// {line}")));
+    }
+    self
+  }
+
+  /// Push an empty line to the code block.
+  pub fn blank_line(mut self) -> Self {
+    self.body.push(Body::String(String::new()));
+    self
+  }
+
   /// Add a snippet after the block.
   pub fn after(mut self, after: &str) -> Self {
     self.after = Some(after.to_string());
@@ -2183,11 +2623,7 @@ impl Docs {
   }
 
   fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
-    for line in self.docs.lines() {
-      writeln!(fmt, "/// {}", line)?;
-    }
-
-    Ok(())
+    fmt_doc_lines(&self.docs, fmt)
   }
 }
 
@@ -2200,9 +2636,16 @@ impl<'a> Formatter<'a> {
       dst,
       spaces: 0,
       indent: DEFAULT_INDENT,
+      provenance_comments: false,
     }
   }
 
+  /// Write the provenance of the fields, variants and impls as `// from:` comments.
+  pub fn provenance_comments(mut self, provenance_comments: bool) -> Self {
+    self.provenance_comments = provenance_comments;
+    self
+  }
+
   fn block<F>(&mut self, f: F) -> fmt::Result
   where
     F: FnOnce(&mut Self) -> fmt::Result,
@@ -2260,7 +2703,8 @@ impl<'a> fmt::Write for Formatter<'a> {
       // If this loops again, then we just wrote a new line
       should_indent = true;
 
-      self.dst.push_str(line);
+      // Lines always end with `\n`, whatever the line endings of the schema.
+      self.dst.push_str(line.trim_end_matches('\r'));
     }
 
     if s.as_bytes().last() == Some(&b'\n') {
@@ -2270,3 +2714,93 @@ impl<'a> fmt::Write for Formatter<'a> {
     Ok(())
   }
 }
+
+#[test]
+fn lines_end_with_line_feeds() {
+  let mut dst = String::new();
+  let mut fmt = Formatter::new(&mut dst);
+  write!(fmt, "first\r\nsecond\r\nthird\r").unwrap();
+  writeln!(fmt).unwrap();
+
+  assert_eq!(dst, "first\nsecond\nthird\n");
+}
+
+#[test]
+fn comments_are_indented_with_their_block() {
+  let function = Function::new("gen").comment("parse the fields").push_block(
+    Block::new("if ready")
+      .comment("field: pitch\n(element, minOccurs=1)")
+      .line("pitch();")
+      .blank_line()
+      .push_block(Block::new("else").comment("nested").line("rest();")),
+  );
+
+  let mut dst = String::new();
+  function.fmt(false, &mut Formatter::new(&mut dst)).unwrap();
+
+  assert_eq!(
+    dst,
+    "fn gen() {\n    // parse the fields\n    if ready {\n        // field: pitch\n        // (element, minOccurs=1)\n        pitch();\n\n        else {\n            // nested\n            rest();\n        }\n    }\n}\n"
+  );
+}
+
+#[test]
+fn merging_scopes_keeps_shared_items_once() {
+  let common = |scope: &mut Scope| {
+    scope.import("std::fmt", "Debug");
+    scope
+      .new_struct(None, "Common")
+      .field(None, "id", "String", false, false);
+  };
+
+  let mut left = Scope::new();
+  common(&mut left);
+  left.new_module("note").new_struct(None, "Pitch");
+  left.new_struct(None, "Note");
+
+  let mut right = Scope::new();
+  common(&mut right);
+  right.import("std::fmt", "Display");
+  right.new_module("note").new_struct(None, "Lyric");
+  right.new_struct(None, "Rest");
+
+  let merged = left.merge(right).unwrap();
+  assert_eq!(
+    merged.to_string(),
+    "use std::fmt::{Debug, Display};\n\npub struct Common {\n    id: String,\n}\n\nmod note {\n    pub struct Pitch;\n\n    pub struct Lyric;\n}\n\npub struct Note;\n\npub struct Rest;"
+  );
+
+  let mut conflicting = Scope::new();
+  conflicting
+    .new_struct(None, "Common")
+    .field(None, "id", "u32", false, false);
+  let mut nested = Scope::new();
+  nested
+    .new_module("note")
+    .new_struct(None, "Pitch")
+    .field(None, "step", "u8", false, false);
+
+  let error = merged.clone().merge(conflicting).unwrap_err();
+  assert_eq!(error.path, "Common");
+  assert!(error.left.contains("id: String"));
+  assert!(error.right.contains("id: u32"));
+  assert_eq!(merged.merge(nested).unwrap_err().path, "note::Pitch",);
+}
+
+#[test]
+fn trait_functions_with_a_body_are_default_methods() {
+  let mut visitor = Trait::new("NoteVisitor", None).vis("pub");
+  let mut visit = Function::new("visit_pitch");
+  visit.arg_mut_self();
+  visitor
+    .push_fn(visit.arg("_value", "&Pitch"))
+    .new_fn("finish");
+
+  let mut dst = String::new();
+  visitor.fmt(&mut Formatter::new(&mut dst)).unwrap();
+
+  assert_eq!(
+    dst,
+    "pub trait NoteVisitor {\n    fn visit_pitch(&mut self, _value: &Pitch) {}\n\n    fn finish();\n}\n"
+  );
+}
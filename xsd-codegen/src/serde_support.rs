@@ -0,0 +1,241 @@
+//! Serialization of the runtime types held by the generated code, enabled by the `serde` feature.
+
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+  AnyXml, AttributeName, Base64Binary, Date, DateTime, Decimal, Duration, FromXmlString, GDay,
+  GMonth, GMonthDay, GYear, GYearMonth, HexBinary, QName, RawXml, RestrictedVec, Time,
+  UnknownContent,
+};
+
+/// A sequence of its items.
+impl<T: Serialize, const MIN: usize, const MAX: usize> Serialize for RestrictedVec<T, MIN, MAX> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    self.0.serialize(serializer)
+  }
+}
+
+/// Checks the number of items, like `XsdGen::gen`.
+impl<'de, T: Deserialize<'de>, const MIN: usize, const MAX: usize> Deserialize<'de>
+  for RestrictedVec<T, MIN, MAX>
+{
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let items = Vec::<T>::deserialize(deserializer)?;
+    if items.len() < MIN || (MAX != 0 && items.len() > MAX) {
+      let expected = if MAX == 0 {
+        format!("at least {MIN} items")
+      } else {
+        format!("between {MIN} and {MAX} items")
+      };
+      return Err(de::Error::invalid_length(items.len(), &expected.as_str()));
+    }
+
+    Ok(Self(items))
+  }
+}
+
+/// The lexical form of the date, as in documents.
+impl Serialize for Date {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
+
+impl<'de> Deserialize<'de> for Date {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let string = String::deserialize(deserializer)?;
+    Date::from_xml(&string).map_err(de::Error::custom)
+  }
+}
+
+/// The lexical form of the time, as in documents.
+impl Serialize for Time {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
+
+impl<'de> Deserialize<'de> for Time {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let string = String::deserialize(deserializer)?;
+    Time::from_xml(&string).map_err(de::Error::custom)
+  }
+}
+
+/// The lexical form of the date and time, as in documents.
+impl Serialize for DateTime {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
+
+impl<'de> Deserialize<'de> for DateTime {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let string = String::deserialize(deserializer)?;
+    DateTime::from_xml(&string).map_err(de::Error::custom)
+  }
+}
+
+/// The canonical lexical form of the duration, as in documents.
+impl Serialize for Duration {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
+
+impl<'de> Deserialize<'de> for Duration {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let string = String::deserialize(deserializer)?;
+    Duration::from_xml(&string).map_err(de::Error::custom)
+  }
+}
+
+/// The lexical form of the gregorian partial dates and of the binaries, as in documents.
+macro_rules! serde_lexical {
+  ($($ty: ident),*) => {
+    $(
+      impl Serialize for $ty {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+          serializer.collect_str(self)
+        }
+      }
+
+      impl<'de> Deserialize<'de> for $ty {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+          let string = String::deserialize(deserializer)?;
+          $ty::from_xml(&string).map_err(de::Error::custom)
+        }
+      }
+    )*
+  };
+}
+
+serde_lexical!(
+  GYear,
+  GYearMonth,
+  GMonthDay,
+  GDay,
+  GMonth,
+  HexBinary,
+  Base64Binary
+);
+
+/// The lexical form of the decimal, a string which keeps all its digits.
+impl Serialize for Decimal {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let string = String::deserialize(deserializer)?;
+    Decimal::from_xml(&string).map_err(de::Error::custom)
+  }
+}
+
+/// The lexical form of the name, the namespace of its prefix is not kept.
+impl Serialize for QName {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
+
+impl<'de> Deserialize<'de> for QName {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let string = String::deserialize(deserializer)?;
+    QName::from_xml(&string).map_err(de::Error::custom)
+  }
+}
+
+/// The element written as XML.
+impl Serialize for AnyXml {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
+
+impl<'de> Deserialize<'de> for AnyXml {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let string = String::deserialize(deserializer)?;
+    xmltree::Element::parse(string.as_bytes())
+      .map(AnyXml::from)
+      .map_err(de::Error::custom)
+  }
+}
+
+/// A pair of the attributes and of the child elements, each attribute a triple of its namespace,
+/// of its name written with its prefix and of its value, each child a pair of its index and of
+/// the element written as XML.
+impl Serialize for UnknownContent {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let config = xmltree::EmitterConfig::new().write_document_declaration(false);
+    let mut children = vec![];
+    for (index, child) in &self.children {
+      let mut xml = vec![];
+      child
+        .0
+        .write_with_config(&mut xml, config.clone())
+        .map_err(ser::Error::custom)?;
+      children.push((*index, String::from_utf8_lossy(&xml).into_owned()));
+    }
+    let attributes: Vec<_> = self
+      .attributes
+      .iter()
+      .map(|(name, value)| (&name.namespace, name.qualified(), value))
+      .collect();
+    (attributes, children).serialize(serializer)
+  }
+}
+
+impl<'de> Deserialize<'de> for UnknownContent {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let (attributes, children) =
+      <(Vec<(Option<String>, String, String)>, Vec<(usize, String)>)>::deserialize(deserializer)?;
+    let attributes = attributes
+      .into_iter()
+      .map(|(namespace, name, value)| (AttributeName::from_qualified(namespace, &name), value))
+      .collect();
+    let children = children
+      .into_iter()
+      .map(|(index, xml)| {
+        xmltree::Element::parse(xml.as_bytes()).map(|child| (index, RawXml(child)))
+      })
+      .collect::<Result<_, _>>()
+      .map_err(de::Error::custom)?;
+
+    Ok(Self {
+      attributes,
+      children,
+    })
+  }
+}
+
+#[test]
+fn restricted_vec_checks_the_occurrences() {
+  let notes: RestrictedVec<u8, 1, 2> = serde_json::from_str("[1, 2]").unwrap();
+  assert_eq!(serde_json::to_string(&notes).unwrap(), "[1,2]");
+
+  for json in ["[]", "[1, 2, 3]"] {
+    let error = serde_json::from_str::<RestrictedVec<u8, 1, 2>>(json).unwrap_err();
+    assert!(
+      error.to_string().contains("between 1 and 2 items"),
+      "{error}"
+    );
+  }
+}
+
+#[test]
+fn dates_keep_their_timezone() {
+  for date in [
+    "2024-01-31",
+    "2024-01-31Z",
+    "2024-01-31+05:30",
+    "2024-01-31-08:00",
+  ] {
+    let json = format!("{date:?}");
+    let parsed: Date = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, Date::from_xml(date).unwrap());
+    assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+  }
+}
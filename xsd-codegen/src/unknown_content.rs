@@ -0,0 +1,146 @@
+//! The content of an element that its type doesn't read, kept to write it back. The generated
+//! types keep it with `GeneratorOptions::unknown_content`.
+
+use std::collections::BTreeMap;
+
+use crate::RawXml;
+
+/// The attributes and child elements left in an element once its type is read, e.g. the
+/// extensions of a vendor, see `XMLElement::take_unknown`. `XMLElement::write_unknown` writes them
+/// back where they were read: each child at its original index among the child elements, after
+/// the known ones when fewer are written, and the attributes along with the known ones, which are
+/// all written sorted by name.
+///
+/// The child elements are kept whole, along with their prefix and namespace, and so are the
+/// attributes: the prefix of an attribute is written back along with the declaration of its
+/// namespace, e.g. `v:id` with `xmlns:v`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UnknownContent {
+  /// The values of the attributes by name.
+  pub attributes: BTreeMap<AttributeName, String>,
+  /// The child elements in document order, each along with its index among the child elements of
+  /// the element read.
+  pub children: Vec<(usize, RawXml)>,
+}
+
+impl UnknownContent {
+  /// Whether the element only held content its type reads.
+  pub fn is_empty(&self) -> bool {
+    self.attributes.is_empty() && self.children.is_empty()
+  }
+
+  /// The value of the attribute `local_name` of `namespace`, whatever its prefix.
+  pub fn attribute(&self, namespace: Option<&str>, local_name: &str) -> Option<&str> {
+    self
+      .attributes
+      .iter()
+      .find(|(name, _)| name.namespace.as_deref() == namespace && name.local_name == local_name)
+      .map(|(_, value)| value.as_str())
+  }
+}
+
+/// The name of an attribute of `UnknownContent`: its local name, along with its prefix and the
+/// namespace bound to it for an attribute of a namespace, e.g. `xml:lang`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AttributeName {
+  pub namespace: Option<String>,
+  pub prefix: Option<String>,
+  pub local_name: String,
+}
+
+impl AttributeName {
+  /// The name as it is written, `{prefix}:{local name}` for an attribute of a namespace.
+  pub fn qualified(&self) -> String {
+    match &self.prefix {
+      Some(prefix) => format!("{prefix}:{}", self.local_name),
+      None => self.local_name.clone(),
+    }
+  }
+
+  /// The name of the attribute of `namespace` written `qualified`, as `qualified` returns it.
+  pub(crate) fn from_qualified(namespace: Option<String>, qualified: &str) -> Self {
+    let (prefix, local_name) = match qualified.split_once(':') {
+      Some((prefix, local_name)) => (Some(prefix.to_string()), local_name),
+      None => (None, qualified),
+    };
+    Self {
+      namespace,
+      prefix,
+      local_name: local_name.to_string(),
+    }
+  }
+}
+
+#[test]
+fn unknown_content_is_written_back_where_it_was() {
+  use crate::{write_document, GenState, GenType, XMLElement, XsdGen, XsdSer};
+
+  let mut note = XMLElement::parse(
+    br#"<note default-x="10" v:color="red" xml:lang="fr" xmlns:v="urn:vendor"><pitch>C</pitch><v:glow>2</v:glow><duration>4</duration><v:fx/><v:fx/></note>"#,
+  )
+  .unwrap();
+  let state = GenState {
+    is_root: true,
+    state: GenType::Content,
+    progress: None,
+  };
+
+  assert!(note.claim_unknown());
+  // The types the owner holds read the same element.
+  assert!(!note.claim_unknown());
+  let default_x = note.get_attribute::<u32>("default-x").unwrap();
+  let duration = <u32 as XsdGen>::gen(&mut note, state.clone(), Some("duration")).unwrap();
+  let pitch = <String as XsdGen>::gen(&mut note, state, Some("pitch")).unwrap();
+  let mut unknown = note.take_unknown();
+  let attributes: Vec<_> = unknown
+    .attributes
+    .iter()
+    .map(|(name, value)| (name.qualified(), name.namespace.as_deref(), value.as_str()))
+    .collect();
+  assert_eq!(
+    attributes,
+    [
+      (
+        "xml:lang".to_string(),
+        Some("http://www.w3.org/XML/1998/namespace"),
+        "fr"
+      ),
+      ("v:color".to_string(), Some("urn:vendor"), "red")
+    ]
+  );
+  assert_eq!(unknown.attribute(Some("urn:vendor"), "color"), Some("red"));
+  let children: Vec<_> = unknown
+    .children
+    .iter()
+    .map(|(index, child)| (*index, child.0.name.as_str(), child.0.prefix.as_deref()))
+    .collect();
+  assert_eq!(
+    children,
+    [
+      (1, "glow", Some("v")),
+      (3, "fx", Some("v")),
+      (4, "fx", Some("v"))
+    ]
+  );
+  assert!(note.element().children.is_empty());
+
+  let default_name = AttributeName {
+    namespace: None,
+    prefix: None,
+    local_name: "default-x".to_string(),
+  };
+  unknown.attributes.insert(default_name, "0".to_string());
+  let mut written = XMLElement::new("note");
+  default_x.ser(&mut written, GenType::Attribute, Some("default-x"));
+  pitch.ser(&mut written, GenType::Content, Some("pitch"));
+  duration.ser(&mut written, GenType::Content, Some("duration"));
+  written.write_unknown(&unknown);
+
+  // The known attributes are kept, the others written back with their prefix and the children
+  // interleaved as they were read.
+  let document = write_document(&RawXml(written.into_element()), "note").unwrap();
+  assert_eq!(
+    document,
+    r#"<?xml version="1.0" encoding="UTF-8"?><note xmlns:v="urn:vendor" default-x="10" v:color="red" xml:lang="fr"><pitch>C</pitch><v:glow>2</v:glow><duration>4</duration><v:fx /><v:fx /></note>"#
+  );
+}
@@ -1,49 +1,437 @@
-use xmltree::{Element, XMLNode};
-use xsd_types::{XsdIoError, XsdName, XsdParseError, XsdType};
+// The public fields of `XMLElement` are only deprecated for the users of the crate.
+#![allow(deprecated)]
 
-use crate::FromXmlString;
+use xml::{
+  attribute::OwnedAttribute,
+  name::OwnedName,
+  namespace::Namespace,
+  reader::{Error, EventReader, XmlEvent},
+  ParserConfig,
+};
+use xmltree::{Element, ParseError, XMLNode};
+use xsd_types::{DocumentError, XsdName, XsdParseError, XsdType};
 
-#[derive(Clone, PartialEq)]
+use crate::{
+  xml_writer::{XML_NAMESPACE, XSI_NAMESPACE},
+  AttributeName, FromXmlString, GenState, RawXml, UnknownContent, XsdGen,
+};
+
+/// Options of the reader used by `XMLElement::parse_with`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+  /// Accept documents with a DOCTYPE declaration. The DTD is never fetched and external entities
+  /// are never resolved, only the entities declared in the internal subset are expanded.
+  pub allow_dtd: bool,
+  /// What to do with the attributes of an element sharing a local name.
+  pub duplicate_attributes: DuplicateAttributes,
+  /// What to do with the elements the types don't read, see `UnexpectedElements`.
+  pub unexpected_elements: UnexpectedElements,
+}
+
+/// The handling of the attributes of an element that have the same local name, e.g. `a:id` and
+/// `b:id` with both prefixes bound to the same namespace, which isn't well-formed, or `lang` and
+/// `xml:lang`. The attributes are read by local name, so they can't be told apart.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateAttributes {
+  /// Keep the first of them in document order, the others are dropped.
+  #[default]
+  KeepFirst,
+  /// Fail to parse the document.
+  Reject,
+}
+
+/// The handling of the elements of a document that the types reading it don't expect: elements
+/// following the root element, and the children left in an element once the struct it holds is
+/// read with `XMLElement::read_next_child`, e.g. trailing children the schema doesn't declare.
+///
+/// Comments, processing instructions and whitespace-only text are always ignored, wherever they
+/// are, including between the expected children and after the root element.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnexpectedElements {
+  /// Drop them.
+  #[default]
+  Ignore,
+  /// Fail to parse the document.
+  Reject,
+}
+
+/// The handling of whitespace set by the `xml:space` attribute of an element or of its ancestors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum XmlSpace {
+  /// Whitespace-only text isn't significant and is dropped.
+  #[default]
+  Default,
+  /// All the text is significant, as set by `xml:space="preserve"`.
+  Preserve,
+}
+
+impl XmlSpace {
+  /// The handling set by `element`, the `inherited` one unless it has an `xml:space` attribute.
+  fn of(element: &Element, inherited: Self) -> Self {
+    match element.attributes.get("space").map(String::as_str) {
+      Some("preserve") => Self::Preserve,
+      Some("default") => Self::Default,
+      _ => inherited,
+    }
+  }
+}
+
+/// An element of the document being read, along with its attributes and content.
+///
+/// Reading is destructive: the children and attributes are removed from `element` as they're
+/// read, so that whatever is left once a type is read is what it didn't know about (see
+/// `finalize`). Keep a clone to read the same element twice.
+///
+/// The `xml:space` and `xml:lang` attributes are inherited by the children, see `effective_space`
+/// and `effective_lang`.
+///
+/// The attributes and children left once an element is read can be kept instead, along with the
+/// positions of the children, see `claim_unknown` and `take_unknown`.
+///
+/// To unit-test a generated type, build the element it reads with `xml_element!` or read its
+/// content out of a fragment with `from_fragment`, rather than writing whole documents.
+///
+/// A type generated for a complex type reads the content of the element it is given when it is
+/// called without a name, and the next child element of that name otherwise. To read a document
+/// whose root element was already parsed, e.g. by `xmltree` out of a container, build the element
+/// with `from_element` and read it without a name:
+///
+/// ```
+/// use xsd_codegen::{GenState, GenType, XMLElement, XsdGen};
+/// # use xsd_types::DocumentError;
+/// #
+/// # // The type generated for `<xs:complexType name="note">` with a `pitch` element.
+/// # struct Note {
+/// #   pitch: String,
+/// # }
+/// #
+/// # impl XsdGen for Note {
+/// #   fn gen(
+/// #     element: &mut XMLElement,
+/// #     gen_state: GenState,
+/// #     name: Option<&str>,
+/// #   ) -> Result<Self, DocumentError> {
+/// #     let gen_self = |element: &mut XMLElement, _name: Option<&str>| {
+/// #       Ok(Self {
+/// #         pitch: <String as XsdGen>::gen(element, gen_state.clone(), Some("pitch"))?,
+/// #       })
+/// #     };
+/// #     if let (Some(name), GenType::Content) = (name, gen_state.state) {
+/// #       element.get_next_child_with(name, |mut element| gen_self(&mut element, None))
+/// #     } else {
+/// #       gen_self(element, name)
+/// #     }
+/// #   }
+/// # }
+///
+/// let score = xmltree::Element::parse(&b"<score><pitch>C</pitch></score>"[..]).unwrap();
+///
+/// let mut element = XMLElement::from_element(score, None);
+/// let state = GenState {
+///   is_root: true,
+///   state: GenType::Content,
+///   progress: None,
+/// };
+/// let note = Note::gen(&mut element, state.clone(), None).unwrap();
+/// assert_eq!(note.pitch, "C");
+///
+/// // The pitch was consumed.
+/// assert!(!element.has_child("pitch"));
+///
+/// // Check the name of the root element too by reading it as a named child.
+/// let score = xmltree::Element::parse(&b"<score><pitch>D</pitch></score>"[..]).unwrap();
+/// let mut root = XMLElement::from_element(score, None).wrap_in_root();
+/// let note = Note::gen(&mut root, state, Some("score")).unwrap();
+/// assert_eq!(note.pitch, "D");
+/// ```
+#[derive(Clone)]
 pub struct XMLElement {
+  #[deprecated(
+    since = "0.0.8",
+    note = "use `XMLElement::element`, `element_mut` or `into_element` instead"
+  )]
   pub element: Element,
+  #[deprecated(
+    since = "0.0.8",
+    note = "use `XMLElement::default_namespace` or `set_default_namespace` instead"
+  )]
   pub default_namespace: Option<String>,
+  space: XmlSpace,
+  lang: Option<String>,
+  unexpected_elements: UnexpectedElements,
+  /// Whether the type keeping the unknown content of the element reads it, see `claim_unknown`.
+  claimed: bool,
+  /// The index among the child elements of each child node, recorded once the element is
+  /// claimed.
+  positions: Option<Vec<usize>>,
+}
+
+/// The bookkeeping of the unknown content isn't compared, claiming an element doesn't read it.
+impl PartialEq for XMLElement {
+  fn eq(&self, other: &Self) -> bool {
+    self.element == other.element
+      && self.default_namespace == other.default_namespace
+      && self.space == other.space
+      && self.lang == other.lang
+      && self.unexpected_elements == other.unexpected_elements
+  }
 }
 
 impl XMLElement {
-  pub fn parse(buffer: &[u8]) -> Result<Self, xmltree::ParseError> {
-    Ok(Self {
-      element: xmltree::Element::parse(buffer)?,
-      default_namespace: None,
-    })
+  /// Read an element already parsed by `xmltree`, without going through its text again. The
+  /// comments and processing instructions it holds are skipped when reading, as with `parse`.
+  pub fn from_element(element: Element, default_namespace: Option<String>) -> Self {
+    Self {
+      space: XmlSpace::of(&element, XmlSpace::Default),
+      lang: lang_of(&element, None),
+      element,
+      default_namespace,
+      unexpected_elements: UnexpectedElements::Ignore,
+      claimed: false,
+      positions: None,
+    }
   }
 
-  pub fn parse_hack(buffer: &[u8]) -> Result<Self, xmltree::ParseError> {
-    let mut element = Self::parse(buffer)?;
+  /// An element named `name` without attributes or content, see `xml_element!` to build a
+  /// document out of them.
+  pub fn new(name: &str) -> Self {
+    Self::from_element(Element::new(local_name(name)), None)
+  }
 
-    let mut root_element = Element::new("root");
-    root_element
-      .children
-      .push(XMLNode::Element(element.element));
-    element.element = root_element;
+  /// Add the attribute `name`, named like the attributes read by the generated types.
+  pub fn with_attr(mut self, name: &str, value: &str) -> Self {
+    self
+      .element
+      .attributes
+      .insert(local_name(name).to_string(), value.to_string());
+    self.space = XmlSpace::of(&self.element, self.space);
+    self.lang = lang_of(&self.element, self.lang.as_deref());
+    self
+  }
+
+  /// Add text after the content of the element.
+  pub fn with_text(mut self, text: &str) -> Self {
+    self.element.children.push(XMLNode::Text(text.to_string()));
+    self
+  }
+
+  /// Add a child element after the content of the element.
+  pub fn with_child(mut self, child: XMLElement) -> Self {
+    self.element.children.push(XMLNode::Element(child.element));
+    self
+  }
+
+  /// Parse `fragment` as the content of a `root` element, e.g. the children of the type being
+  /// tested: reading the element without a name reads them. The prefixes it uses have to be
+  /// declared in the fragment.
+  pub fn from_fragment(fragment: &str) -> Result<Self, ParseError> {
+    Self::parse(format!("<root>{fragment}</root>").as_bytes())
+  }
+
+  /// Read `element`, a child of this element, inheriting the `xml:space` and `xml:lang` in scope
+  /// and the handling of the unexpected elements.
+  fn child(&self, element: Element) -> XMLElement {
+    XMLElement {
+      space: XmlSpace::of(&element, self.space),
+      lang: lang_of(&element, self.lang.as_deref()),
+      element,
+      default_namespace: self.default_namespace.clone(),
+      unexpected_elements: self.unexpected_elements,
+      claimed: false,
+      positions: None,
+    }
+  }
+
+  /// The element being read, holding the children and attributes not read yet.
+  pub fn element(&self) -> &Element {
+    &self.element
+  }
+
+  /// The element being read. Whatever is removed from it is considered read, and the positions
+  /// of the children left are no longer known, see `take_unknown`.
+  pub fn element_mut(&mut self) -> &mut Element {
+    self.positions = None;
+    &mut self.element
+  }
+
+  /// The element being read, holding the children and attributes not read yet.
+  pub fn into_element(self) -> Element {
+    self.element
+  }
+
+  /// Namespace of the unprefixed names built by `new_name` and `new_qname`. Only the schema
+  /// parser resolves names, the generated types ignore it.
+  pub fn default_namespace(&self) -> Option<&str> {
+    self.default_namespace.as_deref()
+  }
+
+  pub fn set_default_namespace(&mut self, namespace: Option<String>) {
+    self.default_namespace = namespace;
+  }
+
+  /// The handling of the children `read_next_child` leaves unread, inherited by the children.
+  /// `parse_with` sets the one of its options, the other constructors `Ignore`.
+  pub fn unexpected_elements(&self) -> UnexpectedElements {
+    self.unexpected_elements
+  }
+
+  pub fn set_unexpected_elements(&mut self, handling: UnexpectedElements) {
+    self.unexpected_elements = handling;
+  }
+
+  /// The `xml:space` in scope, i.e. the one of the element or else the one of its closest
+  /// ancestor with the attribute. Whitespace-only text is only read where it is `Preserve`.
+  pub fn effective_space(&self) -> XmlSpace {
+    self.space
+  }
+
+  /// The `xml:lang` in scope, i.e. the one of the element or else the one of its closest ancestor
+  /// with the attribute. An empty `xml:lang` means that the language is unknown.
+  pub fn effective_lang(&self) -> Option<&str> {
+    self.lang.as_deref()
+  }
+
+  /// Parse the root element of a document, see `parse_with`. Documents with a DOCTYPE
+  /// declaration are rejected.
+  pub fn parse(buffer: &[u8]) -> Result<Self, ParseError> {
+    Self::parse_with(buffer, &ParseOptions::default())
+  }
 
+  /// Parse the root element of a document. Comments and processing instructions, including the
+  /// ones before and after the root element, are dropped, so is whitespace-only text unless
+  /// `xml:space` preserves it. Elements following the root element are dropped or rejected
+  /// according to `options.unexpected_elements`.
+  pub fn parse_with(buffer: &[u8], options: &ParseOptions) -> Result<Self, ParseError> {
+    let config = ParserConfig::new()
+      .ignore_comments(true)
+      .cdata_to_characters(true);
+    let mut reader = EventReader::new_with_config(buffer, config);
+
+    let element = loop {
+      match reader.next().map_err(ParseError::MalformedXml)? {
+        XmlEvent::StartElement {
+          name,
+          attributes,
+          namespace,
+        } => {
+          if reader.doctype().is_some() && !options.allow_dtd {
+            return Err(ParseError::MalformedXml(Error::from((
+              &reader,
+              "DOCTYPE declarations are not allowed",
+            ))));
+          }
+          let element = new_element(name, attributes, namespace, options)
+            .map_err(|msg| ParseError::MalformedXml(Error::from((&reader, msg))))?;
+          let space = XmlSpace::of(&element, XmlSpace::Default);
+          break build(&mut reader, element, space, options)?;
+        }
+        XmlEvent::EndDocument => return Err(ParseError::CannotParse),
+        _ => {}
+      }
+    };
+
+    // Read the rest of the document, it must still be well formed.
+    loop {
+      match reader.next().map_err(ParseError::MalformedXml)? {
+        XmlEvent::EndDocument => break,
+        XmlEvent::StartElement { name, .. }
+          if options.unexpected_elements == UnexpectedElements::Reject =>
+        {
+          let msg = format!(
+            "unexpected element {} after the root element",
+            name.local_name
+          );
+          return Err(ParseError::MalformedXml(Error::from((&reader, msg))));
+        }
+        _ => {}
+      }
+    }
+
+    let mut element = Self::from_element(element, None);
+    element.unexpected_elements = options.unexpected_elements;
     Ok(element)
   }
 
+  /// Make this element the only child of a `root` element, so that it can be read as a named
+  /// child. Reading the document root by name checks its name, which reading it directly doesn't.
+  pub fn wrap_in_root(self) -> Self {
+    let mut root_element = Element::new("root");
+    root_element.children.push(XMLNode::Element(self.element));
+
+    // The element inherits from `root` what it inherited so far.
+    Self {
+      element: root_element,
+      default_namespace: self.default_namespace,
+      space: self.space,
+      lang: self.lang,
+      unexpected_elements: self.unexpected_elements,
+      claimed: false,
+      positions: None,
+    }
+  }
+
+  /// Parse the root element of a document and wrap it in a `root` element, see `wrap_in_root`.
+  pub fn parse_hack(buffer: &[u8]) -> Result<Self, ParseError> {
+    Self::parse_hack_with(buffer, &ParseOptions::default())
+  }
+
+  /// `parse_with` followed by `wrap_in_root`.
+  pub fn parse_hack_with(buffer: &[u8], options: &ParseOptions) -> Result<Self, ParseError> {
+    Ok(Self::parse_with(buffer, options)?.wrap_in_root())
+  }
+
+  /// The local name of the element, without its prefix.
   pub fn name(&self) -> &str {
     &self.element.name
   }
 
+  /// The name of the component `name` declared by this schema element, in its default namespace.
   pub fn new_name(&self, name: &str, ty: XsdType) -> XsdName {
     XsdName::new_namespace(name, ty, self.default_namespace.as_deref())
   }
 
+  /// Resolve a QName referencing another component (a `ref`, `type`, `base`...) through the
+  /// namespaces in scope on this element. Unprefixed names use the default namespace, or the
+  /// target namespace of the schema when there is none. Unknown prefixes are kept as is.
+  pub fn new_qname(&self, qname: &str, ty: XsdType) -> XsdName {
+    let in_scope = |prefix: &str| {
+      self
+        .element
+        .namespaces
+        .as_ref()
+        .and_then(|namespaces| namespaces.get(prefix))
+        .filter(|uri| !uri.is_empty())
+    };
+
+    let (namespace, local_name) = match qname.split_once(':') {
+      Some((prefix, local_name)) => (
+        Some(in_scope(prefix).unwrap_or(prefix).to_string()),
+        local_name,
+      ),
+      None => (
+        in_scope("")
+          .map(|uri| uri.to_string())
+          .or_else(|| self.default_namespace.clone()),
+        qname,
+      ),
+    };
+
+    XsdName {
+      namespace,
+      local_name: local_name.to_string(),
+      ty,
+    }
+  }
+
+  /// The local name of the element, as reported in the errors.
   pub fn node_name(&self) -> String {
     self.element.name.to_string()
   }
 
-  pub fn check_name(&self, name: &str) -> Result<(), XsdIoError> {
-    if self.element.name != name {
-      Err(XsdIoError::XsdParseError(XsdParseError {
+  /// Fail unless the element is named `name`.
+  pub fn check_name(&self, name: &str) -> Result<(), DocumentError> {
+    if self.element.name != local_name(name) {
+      Err(DocumentError::XsdParseError(XsdParseError {
         node_name: self.node_name(),
         msg: format!(
           "Unexpected element name {} expected {}",
@@ -55,13 +443,26 @@ impl XMLElement {
     }
   }
 
+  /// Remove the child node at `index`, along with its position.
+  fn remove_child(&mut self, index: usize) -> XMLNode {
+    if let Some(positions) = &mut self.positions {
+      positions.remove(index);
+    }
+    self.element.children.remove(index)
+  }
+
   fn get_children(&mut self, name: &str, max_children: Option<usize>) -> Vec<XMLElement> {
+    let name = local_name(name);
     let mut output = Vec::new();
-    while let Some(child) = self.element.take_child(name) {
-      output.push(XMLElement {
-        element: child,
-        default_namespace: self.default_namespace.clone(),
-      });
+    while let Some(index) = self
+      .element
+      .children
+      .iter()
+      .position(|child| matches!(child, XMLNode::Element(element) if element.name == name))
+    {
+      if let XMLNode::Element(child) = self.remove_child(index) {
+        output.push(self.child(child));
+      }
 
       if let Some(max) = max_children {
         if output.len() >= max {
@@ -73,10 +474,12 @@ impl XMLElement {
     output
   }
 
-  pub fn get_child(&mut self, name: &str) -> Result<XMLElement, XsdIoError> {
+  /// Take the first child element named `name`, failing when there is none. The other children
+  /// of that name are left in place.
+  pub fn get_child(&mut self, name: &str) -> Result<XMLElement, DocumentError> {
     let mut output = self.get_children(name, Some(1));
     if output.len() != 1 {
-      return Err(XsdIoError::XsdParseError(XsdParseError {
+      return Err(DocumentError::XsdParseError(XsdParseError {
         node_name: self.node_name(),
         msg: format!("Expected 1 child named {} found 0", name),
       }));
@@ -85,10 +488,11 @@ impl XMLElement {
     Ok(output.remove(0))
   }
 
-  pub fn try_get_child(&mut self, name: &str) -> Result<Option<XMLElement>, XsdIoError> {
+  /// Take the first child element named `name`, if any.
+  pub fn try_get_child(&mut self, name: &str) -> Result<Option<XMLElement>, DocumentError> {
     let mut output = self.get_children(name, Some(1));
     if output.len() > 1 {
-      return Err(XsdIoError::XsdParseError(XsdParseError {
+      return Err(DocumentError::XsdParseError(XsdParseError {
         node_name: self.node_name(),
         msg: format!(
           "Expected 0 or 1 children named {} found {}",
@@ -105,11 +509,16 @@ impl XMLElement {
     }
   }
 
-  pub fn get_children_with_filter<T>(
+  /// Take all the child elements named `name` and read them with `func`, keeping the values it
+  /// returns. The children are consumed even when `func` returns `None` or fails.
+  ///
+  /// Like the other `*_with` methods, `func` can fail with any error a `DocumentError` converts
+  /// into, e.g. a `SchemaError` when reading a schema.
+  pub fn get_children_with_filter<T, E: From<DocumentError>>(
     &mut self,
     name: &str,
-    func: impl Fn(XMLElement) -> Result<Option<T>, XsdIoError>,
-  ) -> Result<Vec<T>, XsdIoError> {
+    func: impl Fn(XMLElement) -> Result<Option<T>, E>,
+  ) -> Result<Vec<T>, E> {
     let mut output = Vec::new();
     for child in self.get_children(name, None) {
       if let Some(child) = func(child)? {
@@ -120,35 +529,42 @@ impl XMLElement {
     Ok(output)
   }
 
-  fn has_child(&self, name: &str) -> bool {
-    self.element.get_child(name).is_some()
+  /// Whether a child element named `name` is left. Doesn't consume anything.
+  pub fn has_child(&self, name: &str) -> bool {
+    self.element.get_child(local_name(name)).is_some()
   }
 
-  fn has_attr(&self, name: &str) -> bool {
-    self.element.attributes.contains_key(name)
+  /// Whether the attribute `name` is left. Doesn't consume anything.
+  pub fn has_attr(&self, name: &str) -> bool {
+    attribute_key(&self.element, name).is_some()
   }
 
-  pub fn get_children_with<T>(
+  /// Take all the child elements named `name` and read them with `func`.
+  pub fn get_children_with<T, E: From<DocumentError>>(
     &mut self,
     name: &str,
-    func: impl Fn(XMLElement) -> Result<T, XsdIoError>,
-  ) -> Result<Vec<T>, XsdIoError> {
+    func: impl Fn(XMLElement) -> Result<T, E>,
+  ) -> Result<Vec<T>, E> {
     self.get_children_with_filter(name, |child| func(child).map(Some))
   }
 
-  pub fn get_child_with<T>(
+  /// Take the first child element named `name` and read it with `func`.
+  pub fn get_child_with<T, E: From<DocumentError>>(
     &mut self,
     name: &str,
-    func: impl FnOnce(XMLElement) -> Result<T, XsdIoError>,
-  ) -> Result<T, XsdIoError> {
+    func: impl FnOnce(XMLElement) -> Result<T, E>,
+  ) -> Result<T, E> {
     func(self.get_child(name)?)
   }
 
-  pub fn get_next_child_with<T>(
+  /// Take the next child element, i.e. the first one left whatever its name, and read it with
+  /// `func`. Fails when there is none or when it isn't named `name`, the child is consumed either
+  /// way.
+  pub fn get_next_child_with<T, E: From<DocumentError>>(
     &mut self,
     name: &str,
-    func: impl FnOnce(XMLElement) -> Result<T, XsdIoError>,
-  ) -> Result<T, XsdIoError> {
+    func: impl FnOnce(XMLElement) -> Result<T, E>,
+  ) -> Result<T, E> {
     if !self.element.children.is_empty() {
       let mut selected_index = None;
       for index in 0..self.element.children.len() {
@@ -159,44 +575,137 @@ impl XMLElement {
       }
 
       if let Some(index) = selected_index {
-        let child = XMLElement {
-          element: if let XMLNode::Element(element) = self.element.children.remove(index) {
-            element
-          } else {
-            unreachable!()
-          },
-          default_namespace: self.default_namespace.clone(),
+        let XMLNode::Element(element) = self.remove_child(index) else {
+          // `index` was picked as the index of an element.
+          unreachable!()
         };
-        if child.name() != name {
-          Err(XsdIoError::XsdParseError(XsdParseError {
-            node_name: self.name().to_string(),
-            msg: format!(
-              "{} was the next child of {} not {}.",
-              child.name(),
-              self.name(),
-              name
-            ),
-          }))
+        let child = self.child(element);
+        if child.name() != local_name(name) {
+          Err(
+            DocumentError::XsdParseError(XsdParseError {
+              node_name: self.name().to_string(),
+              msg: format!(
+                "{} was the next child of {} not {}.",
+                child.name(),
+                self.name(),
+                name
+              ),
+            })
+            .into(),
+          )
         } else {
           func(child)
         }
       } else {
-        Err(XsdIoError::XsdParseError(XsdParseError {
-          node_name: self.name().to_string(),
-          msg: format!(
-            "{} doesn't have anymore children that are elements.",
-            self.name()
-          ),
-        }))
+        Err(
+          DocumentError::XsdParseError(XsdParseError {
+            node_name: self.name().to_string(),
+            msg: format!(
+              "{} doesn't have anymore children that are elements.",
+              self.name()
+            ),
+          })
+          .into(),
+        )
       }
     } else {
-      Err(XsdIoError::XsdParseError(XsdParseError {
-        node_name: self.name().to_string(),
-        msg: format!("{} doesn't have anymore children.", self.name()),
-      }))
+      Err(
+        DocumentError::XsdParseError(XsdParseError {
+          node_name: self.name().to_string(),
+          msg: format!("{} doesn't have anymore children.", self.name()),
+        })
+        .into(),
+      )
     }
   }
 
+  /// Whether the next child element is named `name`. Doesn't consume anything.
+  pub fn next_child_is(&self, name: &str) -> bool {
+    self.element.children.iter().find_map(|child| match child {
+      XMLNode::Element(element) => Some(element.name == local_name(name)),
+      _ => None,
+    }) == Some(true)
+  }
+
+  /// The name of the next child element, if any. Doesn't consume anything.
+  pub fn next_child_name(&self) -> Option<&str> {
+    self.element.children.iter().find_map(|child| match child {
+      XMLNode::Element(element) => Some(element.name.as_str()),
+      _ => None,
+    })
+  }
+
+  /// Move the child elements named after one of `names` ahead of the others, in the order of
+  /// `names`. The children of an `all` compositor come in any order, once ordered they are read
+  /// like the ones of a sequence. The sort is stable, the text nodes are left in place and the
+  /// children keep their positions, see `claim_unknown`.
+  pub fn order_children(&mut self, names: &[&str]) {
+    let rank = |child: &XMLNode| match child {
+      XMLNode::Element(element) => names
+        .iter()
+        .position(|name| local_name(name) == element.name)
+        .unwrap_or(names.len()),
+      _ => names.len(),
+    };
+
+    let slots: Vec<usize> = self
+      .element
+      .children
+      .iter()
+      .enumerate()
+      .filter(|(_, child)| matches!(child, XMLNode::Element(_)))
+      .map(|(index, _)| index)
+      .collect();
+    let mut elements: Vec<_> = slots
+      .iter()
+      .map(|&index| {
+        let position = self
+          .positions
+          .as_ref()
+          .and_then(|positions| positions.get(index).copied());
+        let child = std::mem::replace(
+          &mut self.element.children[index],
+          XMLNode::Text(String::new()),
+        );
+        (child, position)
+      })
+      .collect();
+    elements.sort_by_key(|(child, _)| rank(child));
+
+    for (index, (child, position)) in slots.into_iter().zip(elements) {
+      self.element.children[index] = child;
+      if let (Some(positions), Some(position)) = (&mut self.positions, position) {
+        positions[index] = position;
+      }
+    }
+  }
+
+  /// The local name of the `xsi:type` of the next child element when it is named `name`, or of
+  /// this element without a name, e.g. `circle` for `xsi:type="s:circle"`. Doesn't consume
+  /// anything.
+  ///
+  /// The attributes are kept by local name, so this is the `type` attribute whatever its
+  /// namespace.
+  pub fn instance_type(&self, name: Option<&str>) -> Option<&str> {
+    let element = match name {
+      Some(name) => self
+        .element
+        .children
+        .iter()
+        .find_map(|child| match child {
+          XMLNode::Element(element) => Some(element),
+          _ => None,
+        })
+        .filter(|element| element.name == local_name(name))?,
+      None => &self.element,
+    };
+    element
+      .attributes
+      .get("type")
+      .map(|ty| local_name(ty.trim()))
+  }
+
+  /// Take all the child elements, in document order. The text content is left in place.
   pub fn get_all_children(&mut self) -> Vec<XMLElement> {
     let mut output = Vec::new();
 
@@ -209,11 +718,8 @@ impl XMLElement {
     to_remove.reverse();
 
     for index in to_remove {
-      if let XMLNode::Element(element) = self.element.children.remove(index) {
-        output.push(XMLElement {
-          element,
-          default_namespace: self.default_namespace.clone(),
-        });
+      if let XMLNode::Element(element) = self.remove_child(index) {
+        output.push(self.child(element));
       }
     }
 
@@ -222,11 +728,12 @@ impl XMLElement {
     output
   }
 
-  pub fn try_get_child_with<T>(
+  /// Take the first child element named `name`, if any, and read it with `func`.
+  pub fn try_get_child_with<T, E: From<DocumentError>>(
     &mut self,
     name: &str,
-    func: impl FnOnce(XMLElement) -> Result<T, XsdIoError>,
-  ) -> Result<Option<T>, XsdIoError> {
+    func: impl FnOnce(XMLElement) -> Result<T, E>,
+  ) -> Result<Option<T>, E> {
     if let Some(child) = self.try_get_child(name)? {
       Ok(Some(func(child)?))
     } else {
@@ -234,79 +741,242 @@ impl XMLElement {
     }
   }
 
+  /// Take the attribute `name`, if any, and convert its value. The attribute is consumed even when
+  /// the conversion fails.
   pub fn try_get_attribute<T: FromXmlString>(
     &mut self,
     name: &str,
-  ) -> Result<Option<T>, XsdIoError> {
-    let value = self.element.attributes.remove(name);
-    if let Some(value) = value {
-      Ok(Some(T::from_xml(&value).map_err(|e| XsdParseError {
-        node_name: self.node_name(),
-        msg: format!("error converting {} from text: {}", name, e),
+  ) -> Result<Option<T>, DocumentError> {
+    let key = attribute_key(&self.element, name).map(str::to_string);
+    if let Some(value) = key.and_then(|key| self.element.attributes.remove(&key)) {
+      let namespaces = self.element.namespaces.as_ref();
+      Ok(Some(T::from_xml_in(&value, namespaces).map_err(|e| {
+        XsdParseError {
+          node_name: self.node_name(),
+          msg: format!("error converting {} from text: {}", name, e),
+        }
       })?))
     } else {
       Ok(None)
     }
   }
 
-  pub fn get_attribute<T: FromXmlString>(&mut self, name: &str) -> Result<T, XsdIoError> {
+  /// Take the attribute `name` and convert its value, failing when there is none.
+  pub fn get_attribute<T: FromXmlString>(&mut self, name: &str) -> Result<T, DocumentError> {
     match self.try_get_attribute(name)? {
       Some(output) => Ok(output),
-      None => Err(XsdIoError::XsdParseError(XsdParseError {
+      None => Err(DocumentError::XsdParseError(XsdParseError {
         node_name: self.node_name(),
         msg: format!("{} not found", name),
       })),
     }
   }
 
+  /// Take the attribute `name` and convert its value, or return the default value when there is
+  /// none.
   pub fn get_attribute_default<T: Default + FromXmlString>(
     &mut self,
     name: &str,
-  ) -> Result<T, XsdIoError> {
+  ) -> Result<T, DocumentError> {
     match self.try_get_attribute(name)? {
       Some(output) => Ok(output),
       None => Ok(T::default()),
     }
   }
 
+  /// Take all the attributes left, as `(name, value)` pairs sorted by name.
   pub fn get_remaining_attributes(&mut self) -> Vec<(String, String)> {
-    self.element.attributes.drain().collect()
+    let mut attributes: Vec<_> = self.element.attributes.drain().collect();
+    attributes.sort();
+    attributes
   }
 
-  pub fn try_get_content<T: FromXmlString>(&mut self) -> Result<Option<T>, XsdIoError> {
-    let value = self.element.get_text();
+  /// Whether the caller is the first to claim the element, i.e. the type reading it as a whole
+  /// which keeps its unknown content: the types it holds read the same element once it claimed
+  /// it, and their own claims fail. The positions of the children are recorded from the first
+  /// claim on, for `take_unknown`.
+  pub fn claim_unknown(&mut self) -> bool {
+    if self.claimed {
+      return false;
+    }
+
+    self.claimed = true;
+    let mut count = 0;
+    let positions = self.element.children.iter().map(|child| {
+      let position = count;
+      if let XMLNode::Element(_) = child {
+        count += 1;
+      }
+      position
+    });
+    self.positions = Some(positions.collect());
+    true
+  }
+
+  /// Take the attributes and the child elements left, the content the type reading the element
+  /// doesn't know, each child along with its index among the child elements when the element was
+  /// claimed. Without a claim, or once the children were changed through `element_mut`, the
+  /// index is the one among the children left. The text content is left in place.
+  ///
+  /// The attributes keep their prefix along with the namespace bound to it, the inherited
+  /// `xml:space` and `xml:lang` ones included. The `xsi:` ones are dropped, the writers of the
+  /// derived types write `xsi:type` back.
+  pub fn take_unknown(&mut self) -> UnknownContent {
+    let namespaces = self.element.namespaces.as_ref();
+    let attributes = self
+      .element
+      .attributes
+      .drain()
+      .filter(|(name, _)| !XSI_ATTRIBUTES.contains(&name.as_str()))
+      .map(|(name, value)| {
+        if INHERITED_ATTRIBUTES.contains(&name.as_str()) {
+          let namespace = Some(XML_NAMESPACE.to_string());
+          return (
+            AttributeName::from_qualified(namespace, &format!("xml:{name}")),
+            value,
+          );
+        }
+        let namespace = name
+          .split_once(':')
+          .and_then(|(prefix, _)| namespaces?.get(prefix))
+          .map(str::to_string);
+        (AttributeName::from_qualified(namespace, &name), value)
+      })
+      .collect();
+
+    let positions = self
+      .positions
+      .take()
+      .filter(|positions| positions.len() == self.element.children.len());
+    let mut children = vec![];
+    for (index, child) in std::mem::take(&mut self.element.children)
+      .into_iter()
+      .enumerate()
+    {
+      match child {
+        XMLNode::Element(child) => {
+          let position = positions
+            .as_ref()
+            .map_or(children.len(), |positions| positions[index]);
+          children.push((position, RawXml(child)));
+        }
+        child => self.element.children.push(child),
+      }
+    }
+
+    UnknownContent {
+      attributes,
+      children,
+    }
+  }
+
+  /// Convert the text content of the element, if any. Whitespace-only text is no content unless
+  /// `effective_space` preserves it. The text isn't consumed, reading it again returns the same
+  /// value.
+  pub fn try_get_content<T: FromXmlString>(&mut self) -> Result<Option<T>, DocumentError> {
+    let value = self
+      .element
+      .get_text()
+      .filter(|text| self.effective_space() == XmlSpace::Preserve || !text.trim().is_empty());
     if let Some(value) = value {
-      Ok(Some(T::from_xml(&value).map_err(|e| XsdParseError {
-        node_name: self.node_name(),
-        msg: format!("could not parse node content from text: {}", e),
+      let namespaces = self.element.namespaces.as_ref();
+      Ok(Some(T::from_xml_in(&value, namespaces).map_err(|e| {
+        XsdParseError {
+          node_name: self.node_name(),
+          msg: format!("could not parse node content from text: {}", e),
+        }
       })?))
     } else {
       Ok(None)
     }
   }
 
-  pub fn get_content<T: FromXmlString>(&mut self) -> Result<T, XsdIoError> {
+  /// Convert the text content of the element, failing when there is none. See `try_get_content`.
+  pub fn get_content<T: FromXmlString>(&mut self) -> Result<T, DocumentError> {
     match self.try_get_content()? {
       Some(output) => Ok(output),
-      None => Err(XsdIoError::XsdParseError(XsdParseError {
+      None => Err(DocumentError::XsdParseError(XsdParseError {
         node_name: self.node_name(),
         msg: "no text found".to_string(),
       })),
     }
   }
 
-  fn get_content_default<T: Default + FromXmlString>(&mut self) -> Result<T, XsdIoError> {
+  /// Convert the text content of the element, or return the default value when there is none.
+  /// See `try_get_content`.
+  pub fn get_content_default<T: Default + FromXmlString>(&mut self) -> Result<T, DocumentError> {
     match self.try_get_content()? {
       Some(output) => Ok(output),
       None => Ok(T::default()),
     }
   }
 
+  /// Take the next child element like `get_next_child_with`, and read it with `func`. The child
+  /// elements `func` leaves unread are then rejected if the unexpected elements are, see
+  /// `UnexpectedElements`.
+  pub fn read_next_child<T, E: From<DocumentError>>(
+    &mut self,
+    name: &str,
+    func: impl FnOnce(&mut XMLElement) -> Result<T, E>,
+  ) -> Result<T, E> {
+    self.get_next_child_with(name, |mut child| {
+      let output = func(&mut child)?;
+      if child.unexpected_elements == UnexpectedElements::Reject {
+        child.finalize(true, false)?;
+      }
+      Ok(output)
+    })
+  }
+
+  /// Read the attribute `name` as `T`, reading its default value `default` when the element
+  /// doesn't have it, as if it had.
+  pub fn gen_attribute_or<T: XsdGen>(
+    &mut self,
+    mut gen_state: GenState,
+    name: Option<&str>,
+    default: &str,
+  ) -> Result<T, DocumentError> {
+    gen_state.is_root = false;
+    if let Some(name) = name.filter(|name| !self.has_attr(name)) {
+      self
+        .element
+        .attributes
+        .insert(local_name(name).to_string(), default.to_string());
+    }
+    T::gen(self, gen_state, name)
+  }
+
+  /// Read the attribute `name` as `T` like `gen_attribute_or`, failing when its value isn't the
+  /// fixed value `fixed`.
+  pub fn gen_fixed_attribute<T: XsdGen>(
+    &mut self,
+    gen_state: GenState,
+    name: Option<&str>,
+    fixed: &str,
+  ) -> Result<T, DocumentError> {
+    let value = name
+      .and_then(|name| attribute_key(&self.element, name))
+      .map(|key| &self.element.attributes[key]);
+    if let Some(value) = value.filter(|value| *value != fixed) {
+      return Err(DocumentError::XsdParseError(XsdParseError {
+        node_name: self.node_name(),
+        msg: format!(
+          "Expected the fixed value {fixed} of {}, found {value}.",
+          name.unwrap_or_default()
+        ),
+      }));
+    }
+    self.gen_attribute_or(gen_state, name, fixed)
+  }
+
+  /// Check that everything was read, i.e. that no child element or attribute is left, unless
+  /// they're allowed. The text content and the comments are never checked, nor are the inherited
+  /// `xml:space` and `xml:lang` attributes.
   pub fn finalize(
     self,
     allow_extra_attributes: bool,
     allow_extra_children: bool,
-  ) -> Result<(), XsdIoError> {
+  ) -> Result<(), DocumentError> {
     let child_errs = self
       .element
       .children
@@ -326,6 +996,7 @@ impl XMLElement {
       .attributes
       .iter()
       .map(|v| v.0.as_str())
+      .filter(|name| !INHERITED_ATTRIBUTES.contains(name))
       .collect::<Vec<_>>()
       .join(", ");
 
@@ -349,10 +1020,361 @@ impl XMLElement {
         }
         text.push_str(&format!("[extra attributes] {}", attr_errs));
       }
-      Err(XsdIoError::XsdParseError(XsdParseError {
+      Err(DocumentError::XsdParseError(XsdParseError {
         node_name: self.node_name(),
         msg: text,
       }))
     }
   }
 }
+
+/// Build an `XMLElement` out of its name followed by its attributes, text and children, each
+/// optional but in that order. Children are elements, e.g. built by the macro too.
+///
+/// ```
+/// use xsd_codegen::{xml_element, GenState, GenType, XMLElement, XsdGen};
+///
+/// let mut note = xml_element!(
+///   "note",
+///   attrs: { "default-x" => "10" },
+///   children: [
+///     xml_element!("pitch", text: "C"),
+///     xml_element!("lyric", attrs: { "xml:lang" => "fr" }, text: "la"),
+///   ],
+/// );
+/// let document = br#"<note default-x="10"><pitch>C</pitch><lyric xml:lang="fr">la</lyric></note>"#;
+/// assert_eq!(note.element(), XMLElement::parse(document).unwrap().element());
+///
+/// let state = GenState {
+///   is_root: true,
+///   state: GenType::Content,
+///   progress: None,
+/// };
+/// let pitch = <String as XsdGen>::gen(&mut note, state, Some("pitch")).unwrap();
+/// assert_eq!(pitch, "C");
+/// ```
+#[macro_export]
+macro_rules! xml_element {
+  (@build $element:expr, attrs: { $($name:expr => $value:expr),* $(,)? } $(, $($rest:tt)*)?) => {
+    $crate::xml_element!(@build $element$(.with_attr($name, $value))*, $($($rest)*)?)
+  };
+  (@build $element:expr, text: $text:expr $(, $($rest:tt)*)?) => {
+    $crate::xml_element!(@build $element.with_text($text), $($($rest)*)?)
+  };
+  (@build $element:expr, children: [ $($child:expr),* $(,)? ] $(,)?) => {
+    $element$(.with_child($child))*
+  };
+  (@build $element:expr, $(,)?) => {
+    $element
+  };
+  ($name:expr $(, $($rest:tt)*)?) => {
+    $crate::xml_element!(@build $crate::XMLElement::new($name), $($($rest)*)?)
+  };
+}
+
+/// The local names of the `xml:` attributes inherited by the children of an element.
+const INHERITED_ATTRIBUTES: &[&str] = &["space", "lang"];
+
+/// The local names of the `xsi:` attributes.
+const XSI_ATTRIBUTES: &[&str] = &["type", "nil", "schemaLocation", "noNamespaceSchemaLocation"];
+
+/// The `xml:lang` set by `element`, the `inherited` one unless it has the attribute.
+fn lang_of(element: &Element, inherited: Option<&str>) -> Option<String> {
+  match element.attributes.get("lang") {
+    Some(lang) if lang.is_empty() => None,
+    Some(lang) => Some(lang.clone()),
+    None => inherited.map(str::to_string),
+  }
+}
+
+/// The local part of the name of an element or attribute. The generated code names the ones of
+/// a namespace `{namespace}:{local name}`, while documents are read by local name.
+pub(crate) fn local_name(name: &str) -> &str {
+  name
+    .rsplit_once(':')
+    .map_or(name, |(_, local_name)| local_name)
+}
+
+/// The key of the attribute `name` of `element`, found by local name: the attributes of a
+/// namespace other than the XML and XSI ones are kept along with their prefix, see `new_element`.
+pub(crate) fn attribute_key<'a>(element: &'a Element, name: &str) -> Option<&'a str> {
+  let name = local_name(name);
+  match element.attributes.get_key_value(name) {
+    Some((key, _)) => Some(key),
+    None => element
+      .attributes
+      .keys()
+      .map(String::as_str)
+      .find(|key| local_name(key) == name),
+  }
+}
+
+/// Create the element, keeping its attributes by local name, the ones of a namespace other than
+/// the XML and XSI ones along with their prefix to write them back. Fails with the reason when
+/// two of them share a local name and `options` reject it.
+fn new_element(
+  name: OwnedName,
+  attributes: Vec<OwnedAttribute>,
+  namespace: Namespace,
+  options: &ParseOptions,
+) -> Result<Element, String> {
+  let mut element = Element::new(&name.local_name);
+  for (index, attribute) in attributes.iter().enumerate() {
+    let local_name = &attribute.name.local_name;
+    if attribute_key(&element, local_name).is_none() {
+      let key = match (&attribute.name.prefix, attribute.name.namespace.as_deref()) {
+        (Some(prefix), Some(namespace))
+          if namespace != XML_NAMESPACE && namespace != XSI_NAMESPACE =>
+        {
+          format!("{prefix}:{local_name}")
+        }
+        _ => local_name.clone(),
+      };
+      element.attributes.insert(key, attribute.value.clone());
+      continue;
+    }
+
+    if options.duplicate_attributes == DuplicateAttributes::Reject {
+      let first = attributes[..index]
+        .iter()
+        .find(|first| first.name.local_name == *local_name)
+        .unwrap();
+      return Err(format!(
+        "Attributes '{}' and '{}' of '{}' are both read as '{local_name}'",
+        first.name.borrow().to_repr(),
+        attribute.name.borrow().to_repr(),
+        name.borrow().to_repr(),
+      ));
+    }
+  }
+  element.prefix = name.prefix;
+  element.namespace = name.namespace;
+  if !namespace.is_essentially_empty() {
+    element.namespaces = Some(namespace);
+  }
+
+  Ok(element)
+}
+
+/// Read the content of `element` up to its end tag. Whitespace-only text is kept where `space`,
+/// the `xml:space` in scope, preserves it.
+fn build(
+  reader: &mut EventReader<&[u8]>,
+  mut element: Element,
+  space: XmlSpace,
+  options: &ParseOptions,
+) -> Result<Element, ParseError> {
+  loop {
+    match reader.next().map_err(ParseError::MalformedXml)? {
+      XmlEvent::StartElement {
+        name,
+        attributes,
+        namespace,
+      } => {
+        let child = new_element(name, attributes, namespace, options)
+          .map_err(|msg| ParseError::MalformedXml(Error::from((&*reader, msg))))?;
+        let child_space = XmlSpace::of(&child, space);
+        let child = build(reader, child, child_space, options)?;
+        element.children.push(XMLNode::Element(child));
+      }
+      XmlEvent::EndElement { .. } => return Ok(element),
+      XmlEvent::Characters(text) => element.children.push(XMLNode::Text(text)),
+      XmlEvent::Whitespace(text) if space == XmlSpace::Preserve => {
+        element.children.push(XMLNode::Text(text))
+      }
+      XmlEvent::EndDocument => return Err(ParseError::CannotParse),
+      _ => {}
+    }
+  }
+}
+
+#[test]
+fn doctype_is_only_read_when_allowed() {
+  let document = br#"<?xml version="1.0"?>
+<!DOCTYPE score [
+  <!ENTITY composer "Bach">
+  <!ENTITY secret SYSTEM "file:///etc/passwd">
+]>
+<?editor version="2"?>
+<score><?editor cursor?><composer>&composer;</composer><notes>&secret;</notes></score>"#;
+
+  let Err(error) = XMLElement::parse(document) else {
+    panic!("the DOCTYPE is not rejected");
+  };
+  assert!(
+    error
+      .to_string()
+      .contains("DOCTYPE declarations are not allowed"),
+    "{error}"
+  );
+
+  let options = ParseOptions {
+    allow_dtd: true,
+    ..Default::default()
+  };
+  let mut score = XMLElement::parse_with(document, &options).unwrap();
+  let composer: String = score.get_child("composer").unwrap().get_content().unwrap();
+  assert_eq!(composer, "Bach");
+  // External entities are never resolved.
+  let notes: Option<String> = score.get_child("notes").unwrap().try_get_content().unwrap();
+  assert_eq!(notes, None);
+  assert!(score.element.children.is_empty());
+}
+
+#[test]
+fn duplicate_attributes_are_read_once() {
+  // Both prefixes are bound to the same namespace, and `lang` is read like `xml:lang`.
+  let document = br#"<note xmlns:a="urn:a" xmlns:b="urn:a">
+  <pitch a:id="first" b:id="second" lang="en" xml:lang="fr"/>
+</note>"#;
+
+  let mut note = XMLElement::parse(document).unwrap();
+  let mut pitch = note.get_child("pitch").unwrap();
+  assert_eq!(pitch.effective_lang(), Some("en"));
+  let id: String = pitch.get_attribute("urn:a:id").unwrap();
+  assert_eq!(id, "first");
+  let lang: String = pitch.get_attribute("lang").unwrap();
+  assert_eq!(lang, "en");
+  // Nothing is left to read.
+  assert!(pitch.element().attributes.is_empty());
+  pitch.finalize(false, false).unwrap();
+
+  let options = ParseOptions {
+    duplicate_attributes: DuplicateAttributes::Reject,
+    ..Default::default()
+  };
+  let Err(error) = XMLElement::parse_with(document, &options) else {
+    panic!("the duplicate attributes are not rejected");
+  };
+  assert!(
+    error
+      .to_string()
+      .contains("Attributes 'a:id' and 'b:id' of 'pitch' are both read as 'id'"),
+    "{error}"
+  );
+  assert!(XMLElement::parse_with(br#"<pitch lang="en" xml:lang="fr"/>"#, &options).is_err());
+}
+
+#[test]
+fn unread_children_are_rejected_when_strict() {
+  let document = br#"<score>
+  <!-- the first part -->
+  <part><?page 1?><note/><!-- tied --><note/></part>
+  <!-- the end -->
+</score>
+<!-- generated -->"#;
+  let read_notes = |part: &mut XMLElement| {
+    part.get_next_child_with("note", |_| Ok::<_, DocumentError>(()))?;
+    part.get_next_child_with("note", |_| Ok::<_, DocumentError>(()))
+  };
+
+  let strict = ParseOptions {
+    unexpected_elements: UnexpectedElements::Reject,
+    ..Default::default()
+  };
+  let mut score = XMLElement::parse_with(document, &strict).unwrap();
+  assert_eq!(score.unexpected_elements(), UnexpectedElements::Reject);
+  score.read_next_child("part", read_notes).unwrap();
+  score.finalize(false, false).unwrap();
+
+  let document = br#"<score><part><note/><note/><note/></part></score>"#;
+  let mut score = XMLElement::parse(document).unwrap();
+  score.read_next_child("part", read_notes).unwrap();
+
+  let mut score = XMLElement::parse_with(document, &strict).unwrap();
+  let error = score.read_next_child("part", read_notes).unwrap_err();
+  assert!(
+    error.to_string().contains("[extra children] note"),
+    "{error}"
+  );
+}
+
+#[test]
+fn namespaced_names_are_read_by_local_name() {
+  let document = br#"<note xmlns="urn:notes" xmlns:xlink="http://www.w3.org/1999/xlink" xlink:href="a.xml" xml:lang="en"><pitch>C</pitch></note>"#;
+  let mut note = XMLElement::parse(document).unwrap();
+
+  assert!(note.next_child_is("urn:notes:pitch"));
+  assert!(note.has_attr("http://www.w3.org/1999/xlink:href"));
+  let lang: String = note
+    .get_attribute("http://www.w3.org/XML/1998/namespace:lang")
+    .unwrap();
+  assert_eq!(lang, "en");
+  let pitch: String = note
+    .get_child("urn:notes:pitch")
+    .unwrap()
+    .get_content()
+    .unwrap();
+  assert_eq!(pitch, "C");
+}
+
+#[test]
+fn xml_space_and_lang_are_inherited() {
+  let document = br#"<credit xml:lang="fr" xml:space="preserve"><words>  </words><line xml:space="default" xml:lang="de"><words>  </words><words xml:space="preserve" xml:lang="">  </words></line></credit>"#;
+  let mut credit = XMLElement::parse(document).unwrap();
+  assert_eq!(credit.effective_space(), XmlSpace::Preserve);
+  assert_eq!(credit.effective_lang(), Some("fr"));
+
+  let mut words = credit.get_child("words").unwrap();
+  assert_eq!(words.effective_space(), XmlSpace::Preserve);
+  assert_eq!(words.effective_lang(), Some("fr"));
+  let text: Option<String> = words.try_get_content().unwrap();
+  assert_eq!(text.as_deref(), Some("  "));
+
+  let mut line = credit
+    .get_next_child_with("line", Ok::<_, DocumentError>)
+    .unwrap();
+  assert_eq!(line.effective_space(), XmlSpace::Default);
+  assert_eq!(line.effective_lang(), Some("de"));
+  let mut all = line.get_all_children();
+  let text: Option<String> = all[0].try_get_content().unwrap();
+  assert_eq!(text, None);
+  assert_eq!(all[1].effective_space(), XmlSpace::Preserve);
+  assert_eq!(all[1].effective_lang(), None);
+  let text: Option<String> = all[1].try_get_content().unwrap();
+  assert_eq!(text.as_deref(), Some("  "));
+
+  // The inherited attributes are left alone by the parsing code.
+  line.finalize(false, false).unwrap();
+  assert!(XMLElement::parse(br#"<line lyric="la"/>"#)
+    .unwrap()
+    .finalize(false, false)
+    .is_err());
+}
+
+#[test]
+fn fragments_are_read_as_content() {
+  let mut root = XMLElement::from_fragment("<pitch>C</pitch>text<pitch>D</pitch>").unwrap();
+  assert_eq!(root.name(), "root");
+  let pitches: Vec<String> = root
+    .get_children_with("pitch", |mut pitch| pitch.get_content())
+    .unwrap();
+  assert_eq!(pitches, ["C", "D"]);
+  assert_eq!(root.get_content::<String>().unwrap(), "text");
+
+  let words = xml_element!("words", attrs: { "xml:space" => "preserve" }, text: " ");
+  assert_eq!(words.effective_space(), XmlSpace::Preserve);
+  assert_eq!(words.element().attributes["space"], "preserve");
+}
+
+#[test]
+fn ordered_children_keep_their_positions() {
+  let mut note =
+    XMLElement::parse(br#"<note><type>quarter</type><tie/><pitch>C</pitch></note>"#).unwrap();
+  assert!(note.claim_unknown());
+  note.order_children(&["pitch", "type"]);
+
+  assert_eq!(note.next_child_name(), Some("pitch"));
+  let pitch: String = note
+    .read_next_child("pitch", |pitch| pitch.get_content::<String>())
+    .unwrap();
+  assert_eq!(pitch, "C");
+  let ty: String = note
+    .read_next_child("type", |ty| ty.get_content::<String>())
+    .unwrap();
+  assert_eq!(ty, "quarter");
+
+  let unknown = note.take_unknown();
+  assert_eq!(unknown.children.len(), 1);
+  assert_eq!(unknown.children[0].0, 1);
+}
@@ -0,0 +1,399 @@
+//! Writing the generated types back to XML, the converse of `XsdGen`. The writers are generated
+//! with `GeneratorOptions::xml_writers`.
+
+use std::borrow::Cow;
+
+use xml::{
+  attribute::Attribute,
+  name::Name,
+  namespace::Namespace,
+  writer::{EmitterConfig, EventWriter, XmlEvent},
+};
+use xmltree::{Element, XMLNode};
+use xsd_types::DocumentError;
+
+use crate::{
+  document::document_error, xml_element::local_name, AnyXml, Base64Binary, Date, DateTime, Decimal,
+  Duration, GDay, GMonth, GMonthDay, GYear, GYearMonth, GenType, HexBinary, QName, RawXml,
+  RestrictedVec, Time, UnknownContent, XMLElement,
+};
+
+pub(crate) const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+pub(crate) const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+/// Converted to the text `FromXmlString` reads it from, e.g. the value of an attribute.
+pub trait ToXmlString {
+  fn to_xml(&self) -> String;
+}
+
+/// Written as the XML `XsdGen` reads it from.
+///
+/// The arguments are the ones `XsdGen::gen` is called with: with the `Attribute` state the value
+/// is the attribute `name` of `element`, with the `Content` state the child element `name`, or
+/// the content of `element` itself without a name. The generated types are written by the local
+/// names of their elements and attributes, as they are read, so the documents of a target
+/// namespace are written without it.
+pub trait XsdSer {
+  fn ser(&self, element: &mut XMLElement, state: GenType, name: Option<&str>);
+}
+
+impl XMLElement {
+  /// Set the attribute `name`. The attributes of the XML namespace keep their `xml` prefix, e.g.
+  /// `xml:lang`, the others are written by local name.
+  pub fn write_attribute(&mut self, name: &str, value: String) {
+    let name = match name.rsplit_once(':') {
+      Some((XML_NAMESPACE, local_name)) => format!("xml:{local_name}"),
+      _ => local_name(name).to_string(),
+    };
+    self.element_mut().attributes.insert(name, value);
+  }
+
+  /// Add text after the content of the element.
+  pub fn write_text(&mut self, text: String) {
+    if !text.is_empty() {
+      self.element_mut().children.push(XMLNode::Text(text));
+    }
+  }
+
+  /// Add the child element `name` after the content of the element, its own content written by
+  /// `write`.
+  pub fn write_child(&mut self, name: &str, write: impl FnOnce(&mut XMLElement)) {
+    let mut child = XMLElement::new(name);
+    write(&mut child);
+    self
+      .element_mut()
+      .children
+      .push(XMLNode::Element(child.into_element()));
+  }
+
+  /// Write the content taken by `take_unknown` back, once the known content is written: each
+  /// child element at its index among the child elements, after them when there are fewer, and
+  /// the attributes the element doesn't have, along with the namespaces bound to their prefixes.
+  pub fn write_unknown(&mut self, unknown: &UnknownContent) {
+    let element = self.element_mut();
+    for (name, value) in &unknown.attributes {
+      let qualified = name.qualified();
+      if element.attributes.contains_key(&qualified) {
+        continue;
+      }
+      if let (Some(prefix), Some(namespace)) = (&name.prefix, &name.namespace) {
+        if namespace != XML_NAMESPACE {
+          element
+            .namespaces
+            .get_or_insert_with(Namespace::empty)
+            .put(prefix.as_str(), namespace.as_str());
+        }
+      }
+      element.attributes.insert(qualified, value.clone());
+    }
+
+    for (index, child) in &unknown.children {
+      let position = element
+        .children
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| matches!(node, XMLNode::Element(_)))
+        .nth(*index)
+        .map_or(element.children.len(), |(position, _)| position);
+      element
+        .children
+        .insert(position, XMLNode::Element(child.0.clone()));
+    }
+  }
+
+  /// Set the `xsi:type` of the last child element `name`, or of the element itself without a
+  /// name, to `ty`, declaring the `xsi` prefix on it. The converse of `instance_type`.
+  pub fn write_instance_type(&mut self, name: Option<&str>, ty: &str) {
+    let element = match name {
+      Some(name) => self
+        .element_mut()
+        .children
+        .iter_mut()
+        .rev()
+        .find_map(|child| match child {
+          XMLNode::Element(child) if child.name == local_name(name) => Some(child),
+          _ => None,
+        }),
+      None => Some(self.element_mut()),
+    };
+    if let Some(element) = element {
+      element
+        .namespaces
+        .get_or_insert_with(Namespace::empty)
+        .put("xsi", XSI_NAMESPACE);
+      element
+        .attributes
+        .insert("xsi:type".to_string(), ty.to_string());
+    }
+  }
+}
+
+impl<T: ToXmlString> XsdSer for T {
+  fn ser(&self, element: &mut XMLElement, state: GenType, name: Option<&str>) {
+    match (state, name) {
+      (GenType::Attribute, Some(name)) => element.write_attribute(name, self.to_xml()),
+      // Nothing is read from an attribute without a name.
+      (GenType::Attribute, None) => {}
+      (GenType::Content, Some(name)) => {
+        element.write_child(name, |child| child.write_text(self.to_xml()))
+      }
+      (GenType::Content, None) => element.write_text(self.to_xml()),
+    }
+  }
+}
+
+impl<T: XsdSer> XsdSer for Vec<T> {
+  fn ser(&self, element: &mut XMLElement, state: GenType, name: Option<&str>) {
+    for value in self {
+      value.ser(element, state, name);
+    }
+  }
+}
+
+impl<T: XsdSer> XsdSer for Option<T> {
+  fn ser(&self, element: &mut XMLElement, state: GenType, name: Option<&str>) {
+    if let Some(value) = self {
+      value.ser(element, state, name);
+    }
+  }
+}
+
+impl<T: XsdSer, const MIN: usize, const MAX: usize> XsdSer for RestrictedVec<T, MIN, MAX> {
+  fn ser(&self, element: &mut XMLElement, state: GenType, name: Option<&str>) {
+    self.0.ser(element, state, name);
+  }
+}
+
+/// The element is written as it was read.
+impl XsdSer for RawXml {
+  fn ser(&self, element: &mut XMLElement, state: GenType, name: Option<&str>) {
+    match (state, name) {
+      (GenType::Attribute, _) => {}
+      (GenType::Content, Some(_)) => element
+        .element_mut()
+        .children
+        .push(XMLNode::Element(self.0.clone())),
+      (GenType::Content, None) => {
+        let raw = self.0.clone();
+        let element = element.element_mut();
+        // The prefixes of the attributes stay bound.
+        if let Some(namespaces) = &raw.namespaces {
+          let declared = element.namespaces.get_or_insert_with(Namespace::empty);
+          for (prefix, namespace) in namespaces {
+            declared.put(prefix, namespace);
+          }
+        }
+        element.attributes.extend(raw.attributes);
+        element.children.extend(raw.children);
+      }
+    }
+  }
+}
+
+/// The element is written back with its text ahead of its children.
+impl XsdSer for AnyXml {
+  fn ser(&self, element: &mut XMLElement, state: GenType, name: Option<&str>) {
+    match (state, name) {
+      (GenType::Attribute, _) => {}
+      (GenType::Content, Some(name)) => {
+        let mut any = self.to_element();
+        any.name = local_name(name).to_string();
+        element.element_mut().children.push(XMLNode::Element(any));
+      }
+      (GenType::Content, None) => {
+        let any = self.to_element();
+        let element = element.element_mut();
+        element.attributes.extend(any.attributes);
+        element.children.extend(any.children);
+      }
+    }
+  }
+}
+
+impl ToXmlString for String {
+  fn to_xml(&self) -> String {
+    self.clone()
+  }
+}
+
+impl ToXmlString for Box<str> {
+  fn to_xml(&self) -> String {
+    self.to_string()
+  }
+}
+
+impl ToXmlString for Date {
+  fn to_xml(&self) -> String {
+    self.to_string()
+  }
+}
+
+impl ToXmlString for Decimal {
+  fn to_xml(&self) -> String {
+    self.to_string()
+  }
+}
+
+impl ToXmlString for Time {
+  fn to_xml(&self) -> String {
+    self.to_string()
+  }
+}
+
+impl ToXmlString for DateTime {
+  fn to_xml(&self) -> String {
+    self.to_string()
+  }
+}
+
+impl ToXmlString for Duration {
+  fn to_xml(&self) -> String {
+    self.to_string()
+  }
+}
+
+/// Written with the prefix it was read with, which isn't declared by the writer.
+impl ToXmlString for QName {
+  fn to_xml(&self) -> String {
+    self.to_string()
+  }
+}
+
+macro_rules! gen_simple_to_xml_string {
+  ($ty: ty) => {
+    impl ToXmlString for $ty {
+      fn to_xml(&self) -> String {
+        self.to_string()
+      }
+    }
+  };
+}
+
+/// The infinities are spelled as in XML Schema, `INF` and `-INF`.
+macro_rules! gen_float_to_xml_string {
+  ($ty: ty) => {
+    impl ToXmlString for $ty {
+      fn to_xml(&self) -> String {
+        match *self {
+          <$ty>::INFINITY => "INF".to_string(),
+          <$ty>::NEG_INFINITY => "-INF".to_string(),
+          value => value.to_string(),
+        }
+      }
+    }
+  };
+}
+
+gen_simple_to_xml_string!(isize);
+gen_simple_to_xml_string!(usize);
+gen_simple_to_xml_string!(i64);
+gen_simple_to_xml_string!(u64);
+gen_simple_to_xml_string!(i32);
+gen_simple_to_xml_string!(u32);
+gen_simple_to_xml_string!(i8);
+gen_simple_to_xml_string!(u8);
+gen_simple_to_xml_string!(GYear);
+gen_simple_to_xml_string!(GYearMonth);
+gen_simple_to_xml_string!(GMonthDay);
+gen_simple_to_xml_string!(GDay);
+gen_simple_to_xml_string!(GMonth);
+gen_simple_to_xml_string!(HexBinary);
+gen_simple_to_xml_string!(Base64Binary);
+gen_float_to_xml_string!(f32);
+gen_float_to_xml_string!(f64);
+
+/// Write `value` as a document whose root element is `root_name`, the converse of
+/// `parse_document`. The attributes of each element are written sorted by name.
+pub fn write_document<T: XsdSer>(value: &T, root_name: &str) -> Result<String, DocumentError> {
+  let mut root = XMLElement::new(root_name);
+  value.ser(&mut root, GenType::Content, None);
+
+  let mut output = vec![];
+  let mut writer = EventWriter::new_with_config(&mut output, EmitterConfig::new());
+  writer
+    .write(XmlEvent::StartDocument {
+      version: xml::common::XmlVersion::Version10,
+      encoding: Some("UTF-8"),
+      standalone: None,
+    })
+    .and_then(|()| write_element(root.element(), &mut writer))
+    .map_err(|e| document_error(format!("Failed to write the document: {e}")))?;
+
+  String::from_utf8(output)
+    .map_err(|e| document_error(format!("Failed to write the document: {e}")))
+}
+
+/// Write `element` and its content, its attributes sorted by name.
+pub(crate) fn write_element<W: std::io::Write>(
+  element: &Element,
+  writer: &mut EventWriter<W>,
+) -> xml::writer::Result<()> {
+  let mut attributes: Vec<_> = element.attributes.iter().collect();
+  attributes.sort();
+  let attributes = attributes
+    .into_iter()
+    .map(|(name, value)| Attribute::new(Name::from(name.as_str()), value))
+    .collect::<Vec<_>>();
+
+  let empty = Namespace::empty();
+  writer.write(XmlEvent::StartElement {
+    name: Name {
+      local_name: &element.name,
+      namespace: element.namespace.as_deref(),
+      prefix: element.prefix.as_deref(),
+    },
+    attributes: Cow::Owned(attributes),
+    namespace: Cow::Borrowed(element.namespaces.as_ref().unwrap_or(&empty)),
+  })?;
+
+  for child in &element.children {
+    match child {
+      XMLNode::Element(child) => write_element(child, writer)?,
+      XMLNode::Text(text) => writer.write(XmlEvent::Characters(text))?,
+      XMLNode::CData(text) => writer.write(XmlEvent::CData(text))?,
+      XMLNode::Comment(comment) => writer.write(XmlEvent::Comment(comment))?,
+      XMLNode::ProcessingInstruction(name, data) => {
+        writer.write(XmlEvent::ProcessingInstruction {
+          name,
+          data: data.as_deref(),
+        })?
+      }
+    }
+  }
+
+  writer.write(XmlEvent::EndElement { name: None })
+}
+
+#[test]
+fn documents_are_written_as_they_are_read() {
+  let document = write_document(&"D <flat>".to_string(), "pitch").unwrap();
+  assert_eq!(
+    document,
+    r#"<?xml version="1.0" encoding="UTF-8"?><pitch>D &lt;flat&gt;</pitch>"#
+  );
+  let pitch: String = crate::parse_document(&document, "pitch").unwrap();
+  assert_eq!(pitch, "D <flat>");
+
+  let mut element = XMLElement::new("note");
+  Some(1.5f64).ser(&mut element, GenType::Attribute, Some("default-x"));
+  f64::INFINITY.ser(&mut element, GenType::Attribute, Some("width"));
+  "en".to_string().ser(
+    &mut element,
+    GenType::Attribute,
+    Some("http://www.w3.org/XML/1998/namespace:lang"),
+  );
+  vec![3u8, 4].ser(&mut element, GenType::Content, Some("dot"));
+  None::<String>.ser(&mut element, GenType::Content, Some("tie"));
+
+  let document = write_document(&RawXml(element.into_element()), "note").unwrap();
+  assert_eq!(
+    document,
+    r#"<?xml version="1.0" encoding="UTF-8"?><note default-x="1.5" width="INF" xml:lang="en"><dot>3</dot><dot>4</dot></note>"#
+  );
+
+  // The infinities are read back.
+  assert_eq!(
+    <f64 as crate::FromXmlString>::from_xml("INF").unwrap(),
+    f64::INFINITY
+  );
+}
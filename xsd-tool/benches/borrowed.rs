@@ -0,0 +1,14 @@
+//! Compares the time taken to read parsed documents into the owned types and into the borrowed
+//! types of `--borrowed-types`, which take their text from the tree instead of copying it.
+//!
+//! Run with `cargo bench -p xsd-tool --bench borrowed`.
+
+mod support;
+
+fn main() {
+  support::compare_reads(
+    "borrowed",
+    "text.xsd",
+    "<note id=\"n1\" color=\"#a0a0a0\"><pitch>C4</pitch><lyric>la</lyric></note>",
+  );
+}
@@ -0,0 +1,15 @@
+//! Compares the memory retained by parsed documents when fixed values are stored as owned
+//! strings and when they are represented by the marker types (`--fixed-values marker`).
+//!
+//! Run with `cargo bench -p xsd-tool --bench fixed_values`.
+
+mod support;
+
+fn main() {
+  support::compare(
+    "fixed_values",
+    "fixed.xsd",
+    r#"<note version="3.1" kind="full"><pitch>C</pitch></note>"#,
+    &[("owned", &[]), ("marker", &["--fixed-values", "marker"])],
+  );
+}
@@ -0,0 +1,15 @@
+//! Compares the memory retained by parsed documents when text is stored as `String` and as
+//! `Box<str>` (`--string-type box-str`).
+//!
+//! Run with `cargo bench -p xsd-tool --bench string_storage`.
+
+mod support;
+
+fn main() {
+  support::compare(
+    "string_storage",
+    "text.xsd",
+    "<note id=\"n1\" color=\"#a0a0a0\"><pitch>C4</pitch><lyric>la</lyric></note>",
+    &[("String", &[]), ("Box<str>", &["--string-type", "box-str"])],
+  );
+}
@@ -0,0 +1,94 @@
+// Measurement code of the memory benches, compiled in the scratch crate next to the generated
+// modules.
+
+use std::{
+  alloc::{GlobalAlloc, Layout, System},
+  sync::atomic::{AtomicUsize, Ordering},
+  time::{Duration, Instant},
+};
+
+use xml_schema_parser::{GenState, GenType, XMLElement, XsdGen};
+
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+/// Bytes allocated since the start, frees are not subtracted.
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+    ALLOCATIONS.fetch_add(layout.size(), Ordering::Relaxed);
+    System.alloc(layout)
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    System.dealloc(ptr, layout)
+  }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const NOTES: usize = 20_000;
+const READS: u32 = 10;
+
+/// A score holding `NOTES` copies of `note`.
+pub fn document(note: &str) -> String {
+  let mut document = String::from("<score>");
+  for _ in 0..NOTES {
+    document.push_str(note);
+  }
+  document.push_str("</score>");
+  document
+}
+
+/// Parse the document and report the bytes still allocated by the parsed notes.
+pub fn measure<T: XsdGen>(label: &str, document: &str) {
+  let before = ALLOCATED.load(Ordering::Relaxed);
+  let start = Instant::now();
+
+  let mut element =
+    XMLElement::from_element(xmltree::Element::parse(document.as_bytes()).unwrap(), None);
+  let state = GenState {
+    is_root: true,
+    state: GenType::Content,
+    progress: None,
+  };
+  let notes = <Vec<T> as XsdGen>::gen(&mut element, state, Some("note")).unwrap();
+  drop(element);
+
+  let elapsed = start.elapsed();
+  let retained = ALLOCATED.load(Ordering::Relaxed) - before;
+
+  println!(
+    "{label:>8}: {} notes, {retained} bytes retained ({:.1} bytes/note, {} bytes inline), parsed in {elapsed:?}",
+    notes.len(),
+    retained as f64 / notes.len() as f64,
+    std::mem::size_of::<T>(),
+  );
+}
+
+/// Read the notes out of freshly parsed trees of the document with `read`, which returns the
+/// number of notes, and report the time and the bytes allocated by `read` alone.
+pub fn time_reads(label: &str, document: &str, mut read: impl FnMut(xmltree::Element) -> usize) {
+  let mut elapsed = Duration::ZERO;
+  let mut allocated = 0;
+  let mut notes = 0;
+  for _ in 0..READS {
+    let root = xmltree::Element::parse(document.as_bytes()).unwrap();
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    notes = read(root);
+    elapsed += start.elapsed();
+    allocated += ALLOCATIONS.load(Ordering::Relaxed) - before;
+  }
+
+  println!(
+    "{label:>8}: {notes} notes read in {:?}, allocating {:.1} bytes/note",
+    elapsed / READS,
+    allocated as f64 / READS as f64 / notes as f64,
+  );
+}
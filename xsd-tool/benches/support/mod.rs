@@ -0,0 +1,86 @@
+//! Builds the memory and read benches out of generated code.
+
+// Each bench runs one of the comparisons.
+#![allow(dead_code)]
+
+#[path = "../../tests/support/mod.rs"]
+mod scratch;
+
+use std::fs;
+
+use scratch::{cargo, write_crate, xsd_tool};
+
+/// Generate a module from the fixture `schema` for each `(label, options)` variant, then parse a
+/// score of `note` elements into the `Note` type of each module and print the memory they retain.
+///
+/// The measurements run in a scratch crate built in release mode.
+pub fn compare(name: &str, schema: &str, note: &str, variants: &[(&str, &[&str])]) {
+  let dir = tempfile::tempdir().unwrap();
+
+  let mut code = include_str!("memory.rs").to_string();
+  let mut main = format!("fn main() {{\n  let document = {name}::document({note:?});\n");
+  for (label, options) in variants {
+    let mut args = vec!["generate", schema];
+    args.extend_from_slice(options);
+    let generated = xsd_tool(&args);
+
+    let module = label
+      .to_lowercase()
+      .replace(|c: char| !c.is_alphanumeric(), "_")
+      .trim_matches('_')
+      .to_string();
+    code.push_str(&format!("\npub mod {module} {{\n{generated}\n}}\n"));
+    main.push_str(&format!(
+      "  {name}::measure::<{name}::{module}::Note>({label:?}, &document);\n"
+    ));
+  }
+  main.push_str("}\n");
+
+  write_crate(dir.path(), name, &[], &code);
+  fs::write(dir.path().join(name).join("src/main.rs"), main).unwrap();
+  print!(
+    "{}",
+    cargo(dir.path(), &[name], &["run", "--release", "--quiet"])
+  );
+}
+
+/// Generate the fixture `schema` with `--borrowed-types`, then read a score of `note` elements
+/// into both `Vec<Note>` and `Vec<NoteRef>` and print the time each read takes.
+///
+/// The measurements run in a scratch crate built in release mode.
+pub fn compare_reads(name: &str, schema: &str, note: &str) {
+  let dir = tempfile::tempdir().unwrap();
+
+  let generated = xsd_tool(&["generate", schema, "--borrowed-types"]);
+  let code = format!(
+    "{}\npub mod generated {{\n{generated}\n}}\n",
+    include_str!("memory.rs")
+  );
+  let main = format!(
+    r#"use xml_schema_parser::{{GenState, GenType, XMLElement, XMLElementRef, XsdGen, XsdGenRef}};
+
+use {name}::generated::{{Note, NoteRef}};
+
+fn main() {{
+  let document = {name}::document({note:?});
+  let state = GenState {{ is_root: true, state: GenType::Content, progress: None }};
+
+  {name}::time_reads("owned", &document, |root| {{
+    let mut element = XMLElement::from_element(root, None);
+    <Vec<Note> as XsdGen>::gen(&mut element, state.clone(), Some("note")).unwrap().len()
+  }});
+  {name}::time_reads("borrowed", &document, |root| {{
+    let mut element = XMLElementRef::new(&root);
+    <Vec<NoteRef> as XsdGenRef>::gen_ref(&mut element, state.clone(), Some("note")).unwrap().len()
+  }});
+}}
+"#
+  );
+
+  write_crate(dir.path(), name, &[], &code);
+  fs::write(dir.path().join(name).join("src/main.rs"), main).unwrap();
+  print!(
+    "{}",
+    cargo(dir.path(), &[name], &["run", "--release", "--quiet"])
+  );
+}
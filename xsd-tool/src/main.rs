@@ -0,0 +1,791 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  process::ExitCode,
+  sync::OnceLock,
+};
+
+use clap::{Args, Parser, Subcommand};
+use xml_schema_parser::{
+  CancellationToken, DriftCause, DriftReport, DuplicateAttributes, FixedValues, GenerationPlan,
+  GenerationReport, GeneratorOptions, LoadOptions, ParseOptions, StringStorage, StubKind,
+  Visibility, Workspace, XMLElement, Xsd, XsdError, XsdErrorKind, XsdName, XsdType, XsdVersion,
+};
+
+#[derive(Parser, Debug)]
+#[command(
+  name = "xsd-tool",
+  version,
+  about = "Generate Rust structures from XSD schemas"
+)]
+struct Cli {
+  #[command(flatten)]
+  global: GlobalArgs,
+
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Args, Debug)]
+struct GlobalArgs {
+  /// Never fetch schemas over the network.
+  #[arg(long, global = true)]
+  offline: bool,
+
+  /// Catalog file mapping schema locations to local files.
+  #[arg(long, global = true, value_name = "FILE")]
+  catalog: Option<PathBuf>,
+
+  /// Directory the relative schema paths given as arguments are read from, instead of the
+  /// current directory. The imports are always read relative to the importing schema.
+  #[arg(long, global = true, value_name = "DIR")]
+  base_dir: Option<PathBuf>,
+
+  /// Only print errors.
+  #[arg(short, long, global = true, conflicts_with = "verbose")]
+  quiet: bool,
+
+  /// Print more diagnostics, can be repeated.
+  #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+  verbose: u8,
+}
+
+/// The options of the generated code, shared by `generate` and `check`.
+#[derive(Args, Debug)]
+struct GenerateArgs {
+  /// Only generate these top-level components and the types they depend on.
+  #[arg(long, value_delimiter = ',', num_args = 1..)]
+  roots: Vec<String>,
+
+  /// Extra derives added to every generated type.
+  #[arg(long = "derive", value_delimiter = ',')]
+  derives: Vec<String>,
+
+  /// Visibility of the generated items: pub, pub(crate) or pub(super).
+  #[arg(long, default_value = "pub")]
+  visibility: Visibility,
+
+  /// Put the types of each namespace in their own module.
+  #[arg(long)]
+  namespace_module: bool,
+
+  /// Import the types of a namespace from another crate instead of generating them.
+  #[arg(long = "external", value_name = "NAMESPACE=PATH", value_parser = parse_external)]
+  externals: Vec<(String, String)>,
+
+  /// Representation of fixed values: owned, marker or static-str.
+  #[arg(long, default_value = "owned")]
+  fixed_values: FixedValues,
+
+  /// Type of the fields holding text: string, box-str or the path of a type implementing
+  /// `FromXmlString`.
+  #[arg(long, default_value = "string", value_name = "TYPE")]
+  string_type: StringStorage,
+
+  /// Emit a test module asserting the generated types are `Send + Sync`.
+  #[arg(long)]
+  assert_send_sync: bool,
+
+  /// Add the field and reference counts to the doc comment of each type.
+  #[arg(long)]
+  type_stats: bool,
+
+  /// Comment the generated parsing code with the schema construct behind each field.
+  #[arg(long)]
+  verbose_codegen: bool,
+
+  /// Comment each generated field, variant and impl with the schema component behind it.
+  #[arg(long)]
+  provenance_comments: bool,
+
+  /// Precede each generated type with a hash of its schema definition.
+  #[arg(long)]
+  content_hashes: bool,
+
+  /// Emit `FIELDS` and `VARIANTS` constants describing the structure of the generated types.
+  #[arg(long)]
+  field_metadata: bool,
+
+  /// Emit simple types restricting another type without any facet as `pub type` aliases.
+  #[arg(long)]
+  flatten_wrappers: bool,
+
+  /// Emit a `gen_with_progress` constructor on the types of the top-level elements.
+  #[arg(long)]
+  progress_hooks: bool,
+
+  /// Emit an `async fn parse_async` constructor on the types of the top-level elements, gated
+  /// on the `tokio` feature of the generated crate.
+  #[arg(long)]
+  async_entry_points: bool,
+
+  /// Experimental: also emit `{Type}Ref<'a>` types borrowing their text from the document.
+  #[arg(long)]
+  borrowed_types: bool,
+
+  /// Add an `xml_lang` field holding the inherited `xml:lang` to the types reading `xml:lang`.
+  #[arg(long)]
+  inherited_lang: bool,
+
+  /// Emit a `{Enum}Visitor` trait and an `accept` method for the enum of each choice.
+  #[arg(long)]
+  choice_visitors: bool,
+
+  /// Link the generated types from the docs, for a navigable rustdoc output.
+  #[arg(long)]
+  doc_links: bool,
+
+  /// Emit typed resolvers for the keys a keyref refers to on the types of the documents.
+  #[arg(long)]
+  key_resolvers: bool,
+
+  /// Generate a stub instead of a component: `opaque` keeps its content as raw XML, `skip`
+  /// removes it along with the fields referencing it. KIND is element, complexType, simpleType,
+  /// attribute, group or attributeGroup.
+  #[arg(long = "stub", value_name = "KIND:NAME=STUB", value_parser = parse_stub)]
+  stubs: Vec<(XsdName, StubKind)>,
+
+  /// Warn about constructs newer than this version of XSD: 1.0 or 1.1.
+  #[arg(long, default_value = "1.1", value_name = "VERSION")]
+  conformance: XsdVersion,
+
+  /// Fail instead of warning about constructs newer than `--conformance`.
+  #[arg(long)]
+  strict_conformance: bool,
+
+  /// Warn instead of failing about enumeration values that are not values of their base.
+  #[arg(long)]
+  lenient_enumerations: bool,
+
+  /// Read a builtin type into another Rust type, e.g. integer=i64.
+  #[arg(long = "builtin-type", value_name = "BUILTIN=TYPE", value_parser = parse_builtin_type)]
+  builtin_types: Vec<(String, String)>,
+
+  /// Fail instead of reporting the builtin types read into a type that can't hold all their
+  /// values, e.g. xs:integer read into an i32.
+  #[arg(long)]
+  strict_mappings: bool,
+
+  /// Read the maxOccurs larger than N as unbounded, with a warning. 4294967295 by default and at
+  /// most.
+  #[arg(long, value_name = "N")]
+  max_occurs_limit: Option<u64>,
+
+  /// Derive serde's traits on the generated types, renaming fields and variants after the XML
+  /// names.
+  #[arg(long)]
+  serde: bool,
+
+  /// Convert the generated types to and from `DynamicValue`, a generic tree of maps, lists and
+  /// scalars keyed by the XML names.
+  #[arg(long)]
+  dynamic_values: bool,
+
+  /// Write the generated types back to XML with `XsdSer`, e.g. to round-trip documents.
+  #[arg(long)]
+  xml_writers: bool,
+
+  /// Keep the attributes and child elements the types don't read, written back by the XML
+  /// writers.
+  #[arg(long)]
+  unknown_content: bool,
+
+  /// Lints allowed in the modules holding the inner types, none when given without a value.
+  /// Defaults to dead_code,unused_imports.
+  #[arg(long, value_name = "LINTS", value_delimiter = ',', num_args = 0..)]
+  inner_module_allow: Option<Vec<String>>,
+
+  /// Emit an `XML_TYPE_INDEX` static mapping the names of the schema types to the generated
+  /// types, to resolve the values of `xs:QName` attributes.
+  #[arg(long)]
+  type_index: bool,
+
+  /// Check the pattern facets when reading the values, with the XSD regular expressions
+  /// translated for the regex crate.
+  #[arg(long)]
+  pattern_checks: bool,
+}
+
+impl GenerateArgs {
+  fn options(self) -> GeneratorOptions {
+    let GenerateArgs {
+      roots,
+      derives,
+      visibility,
+      namespace_module,
+      externals,
+      fixed_values,
+      string_type,
+      assert_send_sync,
+      type_stats,
+      verbose_codegen,
+      provenance_comments,
+      content_hashes,
+      field_metadata,
+      flatten_wrappers,
+      progress_hooks,
+      async_entry_points,
+      borrowed_types,
+      inherited_lang,
+      choice_visitors,
+      doc_links,
+      key_resolvers,
+      stubs,
+      conformance,
+      strict_conformance,
+      lenient_enumerations,
+      builtin_types,
+      strict_mappings,
+      max_occurs_limit,
+      serde,
+      dynamic_values,
+      xml_writers,
+      unknown_content,
+      inner_module_allow,
+      type_index,
+      pattern_checks,
+    } = self;
+
+    GeneratorOptions {
+      roots,
+      extra_derives: derives,
+      visibility,
+      namespace_modules: namespace_module,
+      external_namespaces: externals.into_iter().collect(),
+      fixed_values,
+      string_storage: string_type,
+      send_sync_assertions: assert_send_sync,
+      stats_in_docs: type_stats,
+      verbose_codegen,
+      provenance_comments,
+      content_hashes,
+      field_metadata,
+      flatten_wrappers,
+      progress_hooks,
+      async_entry_points,
+      borrowed_types,
+      inherited_lang,
+      choice_visitors,
+      doc_links,
+      key_resolvers,
+      stubs: stubs.into_iter().collect(),
+      conformance,
+      strict_conformance,
+      lenient_enumerations,
+      builtin_types: builtin_types.into_iter().collect(),
+      strict_mappings,
+      max_occurs_limit,
+      serde,
+      dynamic_values,
+      xml_writers,
+      unknown_content,
+      inner_module_lints: inner_module_allow,
+      type_index,
+      pattern_checks,
+      cancel_token: interrupt_token(),
+      // The naming convention is only set through the library.
+      ..Default::default()
+    }
+  }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+  /// Generate Rust code for a schema.
+  Generate {
+    schema: String,
+
+    /// Output file, or directory to write `<schema name>.rs` into. Defaults to stdout.
+    #[arg(short, long, value_name = "FILE|DIR")]
+    output: Option<PathBuf>,
+
+    #[command(flatten)]
+    args: GenerateArgs,
+  },
+  /// Generate the schemas listed in a manifest into the modules of a directory, emitting the
+  /// namespaces they import once.
+  ///
+  /// The manifest maps the module of each schema to its location:
+  ///
+  /// [schemas]
+  /// score = "score.xsd"
+  GenerateWorkspace {
+    /// Directory to write a `<module>.rs` file per module, and their `mod.rs`, into.
+    dir: PathBuf,
+
+    /// The manifest listing the schemas.
+    #[arg(long, value_name = "FILE", default_value = "xsd-workspace.toml")]
+    manifest: PathBuf,
+
+    #[command(flatten)]
+    args: GenerateArgs,
+  },
+  /// Check that a file of generated code is up to date with the schema and the options.
+  Check {
+    schema: String,
+
+    /// The generated code to check.
+    file: PathBuf,
+
+    #[command(flatten)]
+    args: GenerateArgs,
+  },
+  /// Check the elements, attributes and simple values of an instance document against a schema.
+  Validate {
+    schema: String,
+    instance: PathBuf,
+
+    /// Accept an instance document with a DOCTYPE, external DTDs and entities are never fetched.
+    #[arg(long)]
+    allow_dtd: bool,
+
+    /// Reject an element with two attributes of the same local name instead of keeping the first.
+    #[arg(long)]
+    reject_duplicate_attributes: bool,
+  },
+  /// Print the top-level components of a schema.
+  Inspect {
+    schema: String,
+
+    /// Print statistics about the generated types instead.
+    #[arg(long)]
+    types: bool,
+
+    /// Print the items a generation would emit and its warnings instead, without generating the
+    /// code.
+    #[arg(long, conflicts_with = "types")]
+    plan: bool,
+  },
+}
+
+enum Failure {
+  Xsd(XsdError),
+  Io(PathBuf, std::io::Error),
+  Invalid(String),
+  Drift(DriftReport),
+  Manifest(PathBuf, String),
+}
+
+impl From<XsdError> for Failure {
+  fn from(error: XsdError) -> Self {
+    Failure::Xsd(error)
+  }
+}
+
+impl Failure {
+  /// 1: generation or other errors, 2: io, 3: schema errors, 4: validation failures, 5: generated
+  /// code out of date.
+  fn exit_code(&self) -> ExitCode {
+    match self {
+      Failure::Io(..) => ExitCode::from(2),
+      Failure::Invalid(_) => ExitCode::from(4),
+      Failure::Drift(_) => ExitCode::from(5),
+      Failure::Manifest(..) => ExitCode::from(1),
+      Failure::Xsd(error) => match error.kind() {
+        XsdErrorKind::Io => ExitCode::from(2),
+        XsdErrorKind::Schema => ExitCode::from(3),
+        XsdErrorKind::Generation => ExitCode::from(1),
+        XsdErrorKind::Cancelled => ExitCode::from(130),
+      },
+    }
+  }
+
+  fn report(&self) {
+    match self {
+      Failure::Io(path, error) => eprintln!("error: {}: {error}", path.display()),
+      Failure::Invalid(msg) => eprintln!("invalid: {msg}"),
+      Failure::Drift(report) => eprintln!("drift: {report}"),
+      Failure::Manifest(path, msg) => eprintln!("error: {}: {msg}", path.display()),
+      Failure::Xsd(error) => {
+        eprintln!("error[{}]: {error}", error.error_code());
+        let mut source = std::error::Error::source(error);
+        while let Some(cause) = source {
+          eprintln!("  caused by: {cause}");
+          source = cause.source();
+        }
+      }
+    }
+  }
+}
+
+fn parse_external(value: &str) -> Result<(String, String), String> {
+  match value.rsplit_once('=') {
+    Some((namespace, path)) if !namespace.is_empty() && !path.is_empty() => {
+      Ok((namespace.to_string(), path.to_string()))
+    }
+    _ => Err(format!("expected NAMESPACE=PATH, found {value}")),
+  }
+}
+
+fn parse_builtin_type(value: &str) -> Result<(String, String), String> {
+  match value.split_once('=') {
+    Some((builtin, ty)) if !builtin.is_empty() && !ty.is_empty() => {
+      Ok((builtin.to_string(), ty.to_string()))
+    }
+    _ => Err(format!("expected BUILTIN=TYPE, found {value}")),
+  }
+}
+
+fn parse_stub(value: &str) -> Result<(XsdName, StubKind), String> {
+  let Some(((kind, name), stub)) = value
+    .rsplit_once('=')
+    .and_then(|(component, stub)| Some((component.split_once(':')?, stub)))
+  else {
+    return Err(format!("expected KIND:NAME=STUB, found {value}"));
+  };
+
+  let ty = match kind {
+    "element" => XsdType::Element,
+    "complexType" => XsdType::ComplexType,
+    "simpleType" => XsdType::SimpleType,
+    "attribute" => XsdType::Attribute,
+    "group" => XsdType::Group,
+    "attributeGroup" => XsdType::AttributeGroup,
+    kind => {
+      return Err(format!(
+        "{kind} is not a component; expected (element|complexType|simpleType|attribute|group|attributeGroup)."
+      ))
+    }
+  };
+
+  Ok((XsdName::new(name, ty), stub.parse()?))
+}
+
+fn init_tracing(global: &GlobalArgs) {
+  let level = if global.quiet {
+    tracing::Level::ERROR
+  } else {
+    match global.verbose {
+      0 => tracing::Level::WARN,
+      1 => tracing::Level::INFO,
+      2 => tracing::Level::DEBUG,
+      _ => tracing::Level::TRACE,
+    }
+  };
+
+  tracing_subscriber::fmt()
+    .with_max_level(level)
+    .with_writer(std::io::stderr)
+    .init();
+}
+
+fn load_options(global: &GlobalArgs) -> Result<LoadOptions, Failure> {
+  let options = LoadOptions {
+    offline: global.offline,
+    base_dir: global.base_dir.clone(),
+    ..Default::default()
+  };
+
+  Ok(match &global.catalog {
+    Some(catalog) => options.with_catalog_file(catalog)?,
+    None => options,
+  })
+}
+
+fn generate(
+  xsd: &Xsd,
+  schema: &str,
+  output: Option<PathBuf>,
+  options: &GeneratorOptions,
+) -> Result<(), Failure> {
+  let (code, report) = xsd.generate_with_report(options)?;
+  if options.flatten_wrappers {
+    tracing::info!("Collapsed {} wrappers", report.collapsed_wrappers.len());
+  }
+  for path in &report.dispatch_tables {
+    tracing::info!("{path} is a large choice, dispatched on the name of the next child");
+  }
+  for (name, path) in &report.reserved_renames {
+    tracing::info!("{name} is a reserved name, emitted {path} instead");
+  }
+  for used in &report.lossy_mappings {
+    tracing::info!(
+      "{}: xs:{} is read into {}, {}",
+      used.path,
+      used.builtin,
+      used.ty,
+      used.loss
+    );
+  }
+
+  let Some(output) = output else {
+    print!("{code}");
+    return Ok(());
+  };
+
+  let output = if output.is_dir() {
+    let stem = Path::new(schema)
+      .file_stem()
+      .and_then(|stem| stem.to_str())
+      .unwrap_or("schema");
+    output.join(format!("{stem}.rs"))
+  } else {
+    output
+  };
+
+  fs::write(&output, code).map_err(|error| Failure::Io(output.clone(), error))?;
+  tracing::info!("Wrote {}", output.display());
+
+  Ok(())
+}
+
+/// Read the schemas of a workspace from the `schemas` table of its manifest.
+fn read_manifest(path: &Path, load_options: &LoadOptions) -> Result<Workspace, Failure> {
+  let content = fs::read_to_string(path).map_err(|error| Failure::Io(path.to_path_buf(), error))?;
+  let manifest = content
+    .parse::<toml::Table>()
+    .map_err(|error| Failure::Manifest(path.to_path_buf(), error.message().to_string()))?;
+  let invalid = |msg: String| Failure::Manifest(path.to_path_buf(), msg);
+
+  let Some(schemas) = manifest.get("schemas") else {
+    return Err(invalid("no `schemas` table".to_string()));
+  };
+  let Some(schemas) = schemas.as_table() else {
+    return Err(invalid("`schemas` is not a table".to_string()));
+  };
+
+  let mut workspace = Workspace::new(load_options);
+  for (module, location) in schemas {
+    let Some(location) = location.as_str() else {
+      return Err(invalid(format!(
+        "the location of `{module}` is not a string"
+      )));
+    };
+    workspace.add_schema(module, location);
+  }
+  Ok(workspace)
+}
+
+fn generate_workspace(
+  workspace: &Workspace,
+  dir: &Path,
+  options: &GeneratorOptions,
+) -> Result<(), Failure> {
+  for path in workspace.write_with(dir, options)? {
+    tracing::info!("Wrote {}", path.display());
+  }
+  Ok(())
+}
+
+fn check(xsd: &Xsd, file: &Path, options: &GeneratorOptions) -> Result<(), Failure> {
+  match xsd.check_generated_with(file, options) {
+    Ok(()) => {
+      println!("{}: up to date", file.display());
+      Ok(())
+    }
+    Err(DriftReport {
+      path,
+      cause: DriftCause::Unreadable(error),
+      ..
+    }) => Err(Failure::Io(path, error)),
+    Err(DriftReport {
+      cause: DriftCause::Generation(error),
+      ..
+    }) => Err(Failure::Xsd(*error)),
+    Err(report) => Err(Failure::Drift(report)),
+  }
+}
+
+fn validate(xsd: &Xsd, instance: &Path, options: &ParseOptions) -> Result<(), Failure> {
+  let content = fs::read(instance).map_err(|error| Failure::Io(instance.to_path_buf(), error))?;
+  let root = XMLElement::parse_with(&content, options)
+    .map_err(|error| Failure::Invalid(format!("{}: {error}", instance.display())))?;
+
+  let problems = xsd.validate(root.element())?;
+  if problems.is_empty() {
+    println!("{}: ok", instance.display());
+    return Ok(());
+  }
+
+  for problem in &problems {
+    eprintln!("{}:{}: {}", instance.display(), problem.path, problem.msg);
+  }
+  Err(Failure::Invalid(format!(
+    "{}: {} problem(s) found",
+    instance.display(),
+    problems.len()
+  )))
+}
+
+fn inspect(xsd: &Xsd) {
+  println!(
+    "target namespace: {}",
+    xsd.target_namespace().unwrap_or("(none)")
+  );
+
+  let components = xsd.top_level_components();
+  let mut counts = std::collections::BTreeMap::new();
+  for name in &components {
+    println!("{:?} {}", name.ty, name.local_name);
+    *counts.entry(format!("{:?}", name.ty)).or_insert(0) += 1;
+  }
+
+  println!();
+  for (ty, count) in counts {
+    println!("{ty}: {count}");
+  }
+  println!("total: {}", components.len());
+}
+
+fn inspect_types(report: &GenerationReport) {
+  let width = report
+    .types
+    .iter()
+    .map(|stats| stats.path.len())
+    .max()
+    .unwrap_or(0)
+    .max("type".len());
+
+  println!(
+    "{:width$}  fields  transitive  referenced by  content",
+    "type"
+  );
+  for stats in &report.types {
+    println!(
+      "{:width$}  {:>6}  {:>10}  {:>13}  {}",
+      stats.path,
+      stats.fields,
+      stats.transitive_size,
+      stats.referenced_by,
+      stats.content.as_str()
+    );
+  }
+
+  println!();
+  println!("unreferenced: {}", report.unreferenced().count());
+}
+
+fn inspect_plan(plan: &GenerationPlan) {
+  let width = plan
+    .items
+    .iter()
+    .map(|item| item.path.len())
+    .max()
+    .unwrap_or(0)
+    .max("item".len());
+
+  println!("{:width$}  kind    fields  origin", "item");
+  for item in &plan.items {
+    println!(
+      "{:width$}  {:6}  {:>6}  {:?} {}",
+      item.path,
+      item.kind.as_str(),
+      item.fields,
+      item.origin.ty,
+      item.origin.local_name
+    );
+  }
+
+  println!();
+  for warning in &plan.warnings {
+    println!("warning: {}: {}", warning.path, warning.msg);
+  }
+  for path in &plan.dispatch_tables {
+    println!("dispatch table: {path}");
+  }
+  println!("items: {}", plan.items.len());
+}
+
+fn run(cli: Cli) -> Result<(), Failure> {
+  let load_options = load_options(&cli.global)?;
+
+  match cli.command {
+    Command::Generate {
+      schema,
+      output,
+      args,
+    } => {
+      let xsd = Xsd::new_from_file_with(&schema, &load_options)?;
+      generate(&xsd, &schema, output, &args.options())
+    }
+    Command::GenerateWorkspace {
+      dir,
+      manifest,
+      args,
+    } => {
+      let workspace = read_manifest(&manifest, &load_options)?;
+      generate_workspace(&workspace, &dir, &args.options())
+    }
+    Command::Check { schema, file, args } => {
+      let xsd = Xsd::new_from_file_with(&schema, &load_options)?;
+      check(&xsd, &file, &args.options())
+    }
+    Command::Validate {
+      schema,
+      instance,
+      allow_dtd,
+      reject_duplicate_attributes,
+    } => {
+      let xsd = Xsd::new_from_file_with(&schema, &load_options)?;
+      let options = ParseOptions {
+        allow_dtd,
+        duplicate_attributes: if reject_duplicate_attributes {
+          DuplicateAttributes::Reject
+        } else {
+          DuplicateAttributes::KeepFirst
+        },
+        ..Default::default()
+      };
+      validate(&xsd, &instance, &options)
+    }
+    Command::Inspect {
+      schema,
+      types,
+      plan,
+    } => {
+      let xsd = Xsd::new_from_file_with(&schema, &load_options)?;
+      if plan {
+        inspect_plan(&xsd.plan(&GeneratorOptions::default())?);
+      } else if types {
+        let (_, report) = xsd.generate_with_report(&GeneratorOptions::default())?;
+        inspect_types(&report);
+      } else {
+        inspect(&xsd);
+      }
+      Ok(())
+    }
+  }
+}
+
+/// The token cancelled by Ctrl-C. The generation then stops with an error instead of leaving a
+/// partial output, and a second Ctrl-C terminates the process.
+fn interrupt_token() -> CancellationToken {
+  static TOKEN: OnceLock<CancellationToken> = OnceLock::new();
+
+  #[cfg(unix)]
+  extern "C" fn on_interrupt(_: libc::c_int) {
+    if let Some(token) = TOKEN.get() {
+      token.cancel();
+    }
+    // SAFETY: `signal` is async-signal-safe.
+    unsafe {
+      libc::signal(libc::SIGINT, libc::SIG_DFL);
+    }
+  }
+
+  TOKEN
+    .get_or_init(|| {
+      #[cfg(unix)]
+      // SAFETY: the handler only reads the initialized token and resets the handler.
+      unsafe {
+        let handler: extern "C" fn(libc::c_int) = on_interrupt;
+        libc::signal(libc::SIGINT, handler as libc::sighandler_t);
+      }
+      CancellationToken::new()
+    })
+    .clone()
+}
+
+fn main() -> ExitCode {
+  let cli = Cli::parse();
+  init_tracing(&cli.global);
+  interrupt_token();
+
+  match run(cli) {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(failure) => {
+      failure.report();
+      failure.exit_code()
+    }
+  }
+}
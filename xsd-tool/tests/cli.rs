@@ -0,0 +1,794 @@
+use std::io::Write;
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+
+const NOTE: &str = "tests/fixtures/note.xsd";
+
+fn xsd_tool() -> Command {
+  Command::cargo_bin("xsd-tool").unwrap()
+}
+
+#[test]
+fn generate_to_stdout() {
+  xsd_tool()
+    .args(["generate", NOTE])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("pub struct Note"))
+    .stdout(predicate::str::contains("pub enum Color"));
+}
+
+#[test]
+fn generate_into_directory() {
+  let dir = tempfile::tempdir().unwrap();
+
+  xsd_tool()
+    .args(["generate", NOTE, "-o"])
+    .arg(dir.path())
+    .assert()
+    .success();
+
+  let output = std::fs::read_to_string(dir.path().join("note.rs")).unwrap();
+  assert!(output.contains("pub struct Note"));
+}
+
+#[test]
+fn generate_with_roots_and_derives() {
+  xsd_tool()
+    .args(["generate", NOTE, "--roots", "color", "--derive", "Eq,Hash"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("pub enum Color"))
+    .stdout(predicate::str::contains("Hash"))
+    .stdout(predicate::str::contains("pub struct Note").not());
+}
+
+#[test]
+fn generate_with_visibility() {
+  xsd_tool()
+    .args(["generate", NOTE, "--visibility", "pub(crate)"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("pub(crate) struct Note"))
+    .stdout(predicate::str::contains("pub(crate) enum Color"))
+    .stdout(predicate::str::contains("pub struct").not());
+
+  xsd_tool()
+    .args(["generate", NOTE, "--visibility", "pub(crat)"])
+    .assert()
+    .code(2)
+    .stderr(predicate::str::contains(
+      "pub(crat) is not a recognized visibility",
+    ));
+}
+
+#[test]
+fn unknown_root_is_a_schema_error() {
+  xsd_tool()
+    .args(["generate", NOTE, "--roots", "missing"])
+    .assert()
+    .code(3);
+}
+
+#[test]
+fn missing_schema_is_an_io_error() {
+  xsd_tool()
+    .args(["inspect", "tests/fixtures/does_not_exist.xsd"])
+    .assert()
+    .code(2)
+    .stderr(predicate::str::contains("error[io]"));
+}
+
+#[test]
+fn broken_schema_is_a_schema_error() {
+  xsd_tool()
+    .args(["inspect", "tests/fixtures/broken.xsd"])
+    .assert()
+    .code(3);
+}
+
+#[test]
+fn groups_holding_all_are_only_the_content_of_a_type() {
+  xsd_tool()
+    .args(["generate", "tests/fixtures/misplaced_all.xsd"])
+    .assert()
+    .code(3)
+    .stderr(predicate::str::contains(
+      "`pitch` holds an all, it can only be referenced once as the content of a complex type",
+    ));
+}
+
+#[test]
+fn documents_other_than_schemas_are_schema_errors() {
+  xsd_tool()
+    .args(["inspect", "tests/fixtures/error_page.html"])
+    .assert()
+    .code(3)
+    .stderr(predicate::str::contains(
+      "error[not-a-schema]: tests/fixtures/error_page.html is not an XML Schema: the document is an HTML page",
+    ))
+    .stderr(predicate::str::contains("<title>404 Not Found</title>"));
+
+  xsd_tool()
+    .args(["inspect", "tests/fixtures/score.xml"])
+    .assert()
+    .code(3)
+    .stderr(predicate::str::contains(
+      "tests/fixtures/score.xml is not an XML Schema: the root element is score in no namespace",
+    ));
+
+  let empty = tempfile::NamedTempFile::new().unwrap();
+  xsd_tool()
+    .arg("inspect")
+    .arg(empty.path())
+    .assert()
+    .code(3)
+    .stderr(predicate::str::contains(
+      "is not an XML Schema: the document is empty",
+    ));
+}
+
+#[test]
+fn offline_refuses_remote_schemas() {
+  xsd_tool()
+    .args(["--offline", "inspect", "http://example.com/schema.xsd"])
+    .assert()
+    .code(2)
+    .stderr(predicate::str::contains("error[offline]"));
+}
+
+#[test]
+fn catalog_maps_remote_schemas() {
+  let mut catalog = tempfile::NamedTempFile::new().unwrap();
+  let note = std::fs::canonicalize(NOTE).unwrap();
+  writeln!(catalog, "http://example.com/note.xsd {}", note.display()).unwrap();
+
+  xsd_tool()
+    .args(["--offline", "--catalog"])
+    .arg(catalog.path())
+    .args(["inspect", "http://example.com/note.xsd"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("Element score"));
+}
+
+#[test]
+fn imports_are_read_relative_to_the_importing_schema() {
+  let fixtures = std::fs::canonicalize("tests/fixtures").unwrap();
+  let elsewhere = tempfile::tempdir().unwrap();
+
+  xsd_tool()
+    .arg("generate")
+    .arg(fixtures.join("harbor.xsd"))
+    .current_dir(elsewhere.path())
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("pub struct Position"));
+
+  xsd_tool()
+    .arg("--base-dir")
+    .arg(&fixtures)
+    .args(["generate", "harbor.xsd"])
+    .current_dir(elsewhere.path())
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("pub struct Position"));
+
+  xsd_tool()
+    .arg("--base-dir")
+    .arg(elsewhere.path())
+    .args(["generate", "harbor.xsd"])
+    .assert()
+    .code(2)
+    .stderr(predicate::str::contains(
+      elsewhere.path().join("harbor.xsd").display().to_string(),
+    ));
+}
+
+/// Generate `schema` `runs` times, each in a new process, and check the outputs are identical.
+fn assert_regenerates_identically(args: &[&str], schema: &str, runs: usize) {
+  let generate = || {
+    let output = xsd_tool()
+      .args(args)
+      .args(["generate", schema, "--namespace-module"])
+      .current_dir("tests/fixtures")
+      .output()
+      .unwrap();
+    assert!(output.status.success(), "{schema}: {output:?}");
+    output.stdout
+  };
+
+  let first = generate();
+  for _ in 1..runs {
+    assert!(generate() == first, "{schema} generated differently");
+  }
+}
+
+#[test]
+fn regeneration_is_deterministic() {
+  let mut catalog = tempfile::NamedTempFile::new().unwrap();
+  for schema in ["xml.xsd", "xlink.xsd"] {
+    let copy = std::fs::canonicalize(format!("tests/fixtures/w3c/{schema}")).unwrap();
+    writeln!(
+      catalog,
+      "http://www.musicxml.org/xsd/{schema} {}",
+      copy.display()
+    )
+    .unwrap();
+  }
+  let catalog = catalog.path().to_str().unwrap();
+  assert_regenerates_identically(
+    &["--offline", "--catalog", catalog],
+    "../../../musicxml.xsd",
+    5,
+  );
+
+  for schema in [
+    "note.xsd",
+    "chord.xsd",
+    "dialect.xsd",
+    "diamond.xsd",
+    "drawing.xsd",
+    "harbor.xsd",
+    "shared_names.xsd",
+    "ship.xsd",
+  ] {
+    assert_regenerates_identically(&[], schema, 5);
+  }
+}
+
+#[test]
+fn inspect_lists_components() {
+  xsd_tool()
+    .args(["inspect", NOTE])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("SimpleType color"))
+    .stdout(predicate::str::contains("ComplexType note"))
+    .stdout(predicate::str::contains("total: 3"));
+}
+
+#[test]
+fn validate_checks_the_root_element() {
+  xsd_tool()
+    .args(["validate", NOTE, "tests/fixtures/score.xml"])
+    .assert()
+    .success();
+
+  xsd_tool()
+    .args(["validate", NOTE, "tests/fixtures/unknown_root.xml"])
+    .assert()
+    .code(4);
+}
+
+#[test]
+fn validate_rejects_undeclared_content() {
+  xsd_tool()
+    .args(["validate", NOTE, "tests/fixtures/invalid_score.xml"])
+    .assert()
+    .code(4)
+    .stderr(predicate::str::contains(
+      "invalid_score.xml:/score: unexpected attribute `tempo`",
+    ))
+    .stderr(predicate::str::contains(
+      "invalid_score.xml:/score/tie: `blue` is not one of red, green",
+    ))
+    .stderr(predicate::str::contains(
+      "invalid_score.xml:/score: unexpected element `volume`",
+    ))
+    .stderr(predicate::str::contains("3 problem(s) found"));
+}
+
+#[test]
+fn generate_fixed_values_as_markers() {
+  xsd_tool()
+    .args([
+      "generate",
+      "tests/fixtures/fixed.xsd",
+      "--fixed-values",
+      "marker",
+    ])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("pub struct Yes;"))
+    .stdout(predicate::str::contains("pub kind: note::Kind"));
+}
+
+#[test]
+fn identical_sibling_enumerations_share_a_type() {
+  let output = xsd_tool()
+    .args(["generate", "tests/fixtures/yes_no.xsd"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains(
+      "    pub print_object: note::PrintObject,\n    pub print_dot: note::PrintObject,\n    pub print_lyric: note::PrintObject,\n",
+    ))
+    .get_output()
+    .stdout
+    .clone();
+
+  let output = String::from_utf8(output).unwrap();
+  assert_eq!(output.matches("pub enum").count(), 1, "{output}");
+  assert!(!output.contains("PrintDot"));
+}
+
+#[test]
+fn declarations_of_builtin_names_keep_the_builtins() {
+  // The dialect declares its own `date` and `string`, and imports a copy of the builtins.
+  xsd_tool()
+    .current_dir("tests/fixtures")
+    .args(["generate", "dialect.xsd"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains(
+      "    pub issued: Date,\n    pub filed: DateType,\n    pub title: String,\n    pub code: StringType,\n",
+    ))
+    .stdout(predicate::str::contains("pub struct DateType(pub String);"))
+    .stdout(predicate::str::contains("pub struct StringType(pub String);"))
+    .stdout(predicate::str::contains("SimpleType").not());
+}
+
+#[test]
+fn inspect_type_statistics() {
+  xsd_tool()
+    .args(["inspect", NOTE, "--types"])
+    .assert()
+    .success()
+    .stdout(predicate::str::is_match(r"Color\s+2\s+2\s+1").unwrap())
+    .stdout(predicate::str::contains("unreferenced: 1"));
+}
+
+#[test]
+fn inspect_generation_plan() {
+  xsd_tool()
+    .args(["inspect", NOTE, "--plan"])
+    .assert()
+    .success()
+    .stdout(predicate::str::is_match(r"Color\s+enum\s+2\s+SimpleType color").unwrap())
+    .stdout(predicate::str::contains("items: "));
+}
+
+#[test]
+fn large_choices_are_reported_in_the_plan() {
+  xsd_tool()
+    .args(["inspect", "tests/fixtures/large.xsd", "--plan"])
+    .assert()
+    .success()
+    .stdout(predicate::str::is_match(r"data::DataItem\s+enum\s+300").unwrap())
+    .stdout(predicate::str::contains("dispatch table: data::DataItem\n"));
+}
+
+#[test]
+fn unused_external_namespace_is_an_error() {
+  xsd_tool()
+    .args(["generate", NOTE, "--external", "urn:missing=missing_types"])
+    .assert()
+    .code(1)
+    .stderr(predicate::str::contains("error[external-namespace]"));
+}
+
+#[test]
+fn failing_forward_base_is_reported_against_the_base() {
+  xsd_tool()
+    .args(["generate", "tests/fixtures/forward_base.xsd"])
+    .assert()
+    .code(3)
+    .stderr(predicate::str::contains(
+      "error[context-search]: Failed to generate [ComplexType] base (needed by [DerivedTypes] base, [ComplexType] derived)",
+    ))
+    .stderr(predicate::str::contains("caused by: When searching for pitch"));
+}
+
+#[test]
+fn references_resolve_through_the_prefixes_in_scope() {
+  xsd_tool()
+    .current_dir("tests/fixtures")
+    .args(["generate", "drawing.xsd"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("pub outline: Outline,"))
+    .stdout(predicate::str::contains("pub label: Label,"))
+    .stdout(predicate::str::contains("pub fill: Color,"))
+    .stdout(predicate::str::contains("pub position: Position,"))
+    .stdout(predicate::str::contains("pub stroke: Color,"));
+}
+
+#[test]
+fn validate_accepts_a_doctype_when_allowed() {
+  xsd_tool()
+    .args(["validate", NOTE, "tests/fixtures/doctype_score.xml"])
+    .assert()
+    .code(4)
+    .stderr(predicate::str::contains(
+      "DOCTYPE declarations are not allowed",
+    ));
+
+  xsd_tool()
+    .args([
+      "validate",
+      NOTE,
+      "tests/fixtures/doctype_score.xml",
+      "--allow-dtd",
+    ])
+    .assert()
+    .success();
+}
+
+#[test]
+fn validate_rejects_duplicate_attributes_when_asked() {
+  // The first `id` is kept, the one in another namespace is dropped.
+  xsd_tool()
+    .args(["validate", NOTE, "tests/fixtures/duplicate_id_score.xml"])
+    .assert()
+    .success();
+
+  xsd_tool()
+    .args([
+      "validate",
+      NOTE,
+      "tests/fixtures/duplicate_id_score.xml",
+      "--reject-duplicate-attributes",
+    ])
+    .assert()
+    .code(4)
+    .stderr(predicate::str::contains(
+      "Attributes 'id' and 'a:id' of 'score' are both read as 'id'",
+    ));
+}
+
+#[test]
+fn generate_flattened_wrappers() {
+  xsd_tool()
+    .args([
+      "generate",
+      "tests/fixtures/tenths.xsd",
+      "--flatten-wrappers",
+    ])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("pub type Tenths = Decimal;"))
+    .stdout(predicate::str::contains("pub type Divisions = Tenths;"))
+    .stdout(predicate::str::contains("pub type Duration = Divisions;"))
+    .stdout(predicate::str::contains("pub duration: Duration,"))
+    // The documented and the constrained types keep their struct.
+    .stdout(predicate::str::contains(
+      "/// A duration that is never zero.\n#[derive(Clone, Debug, PartialEq)]\npub struct PositiveDuration(pub Duration);",
+    ))
+    .stdout(predicate::str::contains("pub struct Octave(pub i32);"))
+    .stdout(predicate::str::contains("impl XsdGen for Tenths").not());
+}
+
+#[test]
+fn generate_with_provenance_comments() {
+  xsd_tool()
+    .current_dir("tests/fixtures")
+    .args(["generate", "w3c/links.xsd", "--provenance-comments"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains(
+      "pub link_attributes: LinkAttributes, // from: link-attributes (AttributeGroup, w3c/links.xsd)",
+    ))
+    // The components of the imported schemas are located in their own document.
+    .stdout(predicate::str::contains(
+      "pub base: Option<Base>, // from: xml:base (Attribute, xml.xsd)",
+    ))
+    .stdout(predicate::str::contains(
+      "OnRequest, // from: xlink:actuate (SimpleType, xlink.xsd)",
+    ))
+    .stdout(predicate::str::contains(
+      "// from: link (Element, w3c/links.xsd)\nimpl XsdGen for Link {",
+    ));
+
+  xsd_tool()
+    .current_dir("tests/fixtures")
+    .args(["generate", "w3c/links.xsd"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("// from:").not());
+}
+
+#[test]
+fn generate_with_content_hashes() {
+  let hashed = xsd_tool()
+    .current_dir("tests/fixtures")
+    .args(["generate", "w3c/links.xsd", "--content-hashes"])
+    .assert()
+    .success()
+    .stdout(
+      predicate::str::is_match(
+        r"// xsd-hash: [0-9a-f]{8}\n#\[derive\(Clone, Debug, PartialEq\)\]\npub struct Link \{",
+      )
+      .unwrap(),
+    )
+    // The components of the imported schemas are hashed as well.
+    .stdout(predicate::str::is_match(r"// xsd-hash: [0-9a-f]{8}\npub type Href = ").unwrap())
+    .get_output()
+    .stdout
+    .clone();
+
+  xsd_tool()
+    .current_dir("tests/fixtures")
+    .args(["generate", "w3c/links.xsd", "--content-hashes"])
+    .assert()
+    .success()
+    .stdout(hashed);
+}
+
+#[test]
+fn check_generated_code() {
+  let dir = tempfile::tempdir().unwrap();
+  let generated = dir.path().join("note.rs");
+
+  xsd_tool()
+    .args(["generate", NOTE, "-o"])
+    .arg(&generated)
+    .assert()
+    .success();
+
+  xsd_tool()
+    .args(["check", NOTE])
+    .arg(&generated)
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("note.rs: up to date"));
+
+  xsd_tool()
+    .args(["check", NOTE, "--derive", "Eq"])
+    .arg(&generated)
+    .assert()
+    .code(5)
+    .stderr(predicate::str::contains(
+      "was generated with other options (fingerprint ",
+    ))
+    .stderr(predicate::str::contains("  changed: pub struct Note {"));
+
+  let code = std::fs::read_to_string(&generated).unwrap();
+  std::fs::write(
+    &generated,
+    code.replace("pub struct Note {", "pub struct Note {\n    pub extra: u8,"),
+  )
+  .unwrap();
+  xsd_tool()
+    .args(["check", NOTE])
+    .arg(&generated)
+    .assert()
+    .code(5)
+    .stderr(predicate::str::contains(
+      "note.rs is out of date with the schema\n  changed: pub struct Note {\n",
+    ));
+
+  xsd_tool()
+    .args(["check", NOTE])
+    .arg(dir.path().join("missing.rs"))
+    .assert()
+    .code(2);
+}
+
+#[test]
+fn enumerations_outside_their_base_are_schema_errors() {
+  xsd_tool()
+    .args(["generate", "tests/fixtures/enumerations.xsd"])
+    .assert()
+    .code(3)
+    .stderr(predicate::str::contains(
+      "/schema/simpleType[@name=\"staves\"]/restriction: enumeration value `two` is not a value of the base xs:positiveInteger",
+    ))
+    .stderr(predicate::str::contains("`2024-02-30` is not a valid date"))
+    .stderr(predicate::str::contains("printed").not());
+
+  xsd_tool()
+    .args([
+      "generate",
+      "tests/fixtures/enumerations.xsd",
+      "--lenient-enumerations",
+    ])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("\"two\" => Ok(Self::Two)"))
+    .stderr(predicate::str::contains("enumeration value `two`"));
+}
+
+#[test]
+fn lossy_mappings_are_reported_and_can_be_overridden() {
+  xsd_tool()
+    .args(["-v", "generate", "tests/fixtures/tenths.xsd"])
+    .assert()
+    .success()
+    .stderr(predicate::str::contains(
+      "/schema/simpleType[@name=\"tenths\"]/restriction: xs:decimal is read into Decimal, values with more than 38 digits are rejected",
+    ))
+    .stderr(predicate::str::contains(
+      "/schema/simpleType[@name=\"octave\"]/restriction: xs:integer is read into i32, values outside of the i32 range are rejected",
+    ));
+
+  xsd_tool()
+    .args(["generate", "tests/fixtures/tenths.xsd", "--strict-mappings"])
+    .assert()
+    .code(3)
+    .stderr(predicate::str::contains("error[lossy-mapping]"))
+    .stderr(predicate::str::contains(
+      "/schema/simpleType[@name=\"octave\"]/restriction: xs:integer as i32",
+    ));
+
+  xsd_tool()
+    .args([
+      "generate",
+      "tests/fixtures/tenths.xsd",
+      "--strict-mappings",
+      "--builtin-type",
+      "integer=i64",
+      "--builtin-type",
+      "decimal=String",
+    ])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("pub struct Octave(pub i64);"))
+    .stdout(predicate::str::contains("pub struct Tenths(pub String);"));
+}
+
+#[cfg(unix)]
+#[test]
+fn interrupted_generations_write_nothing() {
+  let dir = tempfile::tempdir().unwrap();
+  let mut schema = String::from(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">"#);
+  for index in 0..3000 {
+    schema.push_str(&format!(
+      r#"<xs:complexType name="part{index}">
+        <xs:sequence>
+          <xs:element name="measure" type="xs:string" maxOccurs="unbounded"/>
+        </xs:sequence>
+        <xs:attribute name="id" type="xs:int"/>
+      </xs:complexType>"#
+    ));
+  }
+  schema.push_str("</xs:schema>");
+  let path = dir.path().join("large.xsd");
+  std::fs::write(&path, schema).unwrap();
+  let output = dir.path().join("large.rs");
+
+  let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("xsd-tool"))
+    .arg("generate")
+    .arg(&path)
+    .arg("-o")
+    .arg(&output)
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .unwrap();
+  std::thread::sleep(std::time::Duration::from_millis(500));
+  let start = std::time::Instant::now();
+  std::process::Command::new("kill")
+    .args(["-INT", &child.id().to_string()])
+    .status()
+    .unwrap();
+  let status = child.wait().unwrap();
+
+  assert_eq!(status.code(), Some(130));
+  assert!(start.elapsed() < std::time::Duration::from_secs(2));
+  assert!(!output.exists());
+  let mut stderr = String::new();
+  std::io::Read::read_to_string(&mut child.stderr.take().unwrap(), &mut stderr).unwrap();
+  assert!(stderr.contains("error[cancelled]"), "{stderr}");
+}
+
+#[test]
+fn generate_with_stubs() {
+  xsd_tool()
+    .current_dir("tests/fixtures")
+    .args([
+      "generate",
+      "stubs.xsd",
+      "--stub",
+      "complexType:identification=skip",
+      "--stub",
+      "complexType:figured-bass=opaque",
+    ])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("pub type FiguredBass = RawXml;"))
+    .stdout(predicate::str::contains("identification").not())
+    .stderr(predicate::str::contains(
+      r#"/schema/complexType[@name="identification"]: skipped, removing Score::identification"#,
+    ));
+
+  xsd_tool()
+    .current_dir("tests/fixtures")
+    .args(["generate", "stubs.xsd", "--stub", "identification=skip"])
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("expected KIND:NAME=STUB"));
+}
+
+#[test]
+fn generate_without_target_namespace() {
+  xsd_tool()
+    .current_dir("tests/fixtures")
+    .args(["generate", "local.xsd"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("pub type LevelAttribute = Level;"))
+    .stdout(predicate::str::contains(
+      r#"level: <Option<LevelAttribute> as XsdGen>::gen(element, gen_state.to_attr(), Some("level"))?,"#,
+    ))
+    .stdout(predicate::str::contains(
+      r#"title: <String as XsdGen>::gen(element, gen_state.clone(), Some("title"))?,"#,
+    ))
+    .stdout(predicate::str::contains("pub heading: Heading,"))
+    .stdout(predicate::str::contains(
+      r#"href: <Option<Href> as XsdGen>::gen(element, gen_state.to_attr(), Some("http://www.w3.org/1999/xlink:href"))?,"#,
+    ));
+}
+
+#[test]
+fn generated_code_does_not_panic() {
+  let mut generated = 0;
+  for entry in std::fs::read_dir("tests/fixtures").unwrap() {
+    let path = entry.unwrap().path();
+    if path.extension().is_none_or(|extension| extension != "xsd") {
+      continue;
+    }
+
+    for options in [
+      &[][..],
+      &[
+        "--fixed-values",
+        "marker",
+        "--field-metadata",
+        "--flatten-wrappers",
+        "--progress-hooks",
+      ],
+    ] {
+      let output = xsd_tool()
+        .current_dir("tests/fixtures")
+        .arg("generate")
+        .arg(path.file_name().unwrap())
+        .args(options)
+        .output()
+        .unwrap();
+      // Some fixtures are broken on purpose.
+      if !output.status.success() {
+        continue;
+      }
+
+      let code = String::from_utf8(output.stdout).unwrap();
+      for pattern in [
+        "assert!(",
+        ".unwrap()",
+        ".expect(",
+        "panic!(",
+        "unreachable!(",
+        "unimplemented!(",
+        "todo!(",
+        "[0]",
+      ] {
+        assert!(
+          !code.contains(pattern),
+          "{pattern} in the code generated for {}",
+          path.display()
+        );
+      }
+      generated += 1;
+    }
+  }
+
+  assert!(generated >= 40, "only {generated} fixtures generated");
+}
+
+#[test]
+fn workspace_manifest_lists_the_schemas() {
+  let dir = tempfile::tempdir().unwrap();
+  let manifest = dir.path().join("fleet.toml");
+  std::fs::write(&manifest, "[schema]\nship = \"ship.xsd\"\n").unwrap();
+
+  xsd_tool()
+    .current_dir("tests/fixtures")
+    .args([
+      "generate-workspace",
+      dir.path().to_str().unwrap(),
+      "--manifest",
+    ])
+    .arg(&manifest)
+    .assert()
+    .code(1)
+    .stderr(predicate::str::contains("fleet.toml: no `schemas` table"));
+}
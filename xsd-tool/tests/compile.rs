@@ -0,0 +1,1809 @@
+mod support;
+
+use std::{io::Write, path::Path};
+
+use support::{cargo, write_crate, xsd_tool};
+
+/// `cargo check` the crates of a scratch workspace.
+fn check_workspace(root: &Path, members: &[&str]) {
+  cargo(root, members, &["check", "--workspace"]);
+}
+
+/// `cargo clippy` the crates of a scratch workspace, failing on warnings.
+fn clippy_workspace(root: &Path, members: &[&str]) {
+  cargo(
+    root,
+    members,
+    &["clippy", "--workspace", "--", "-D", "warnings"],
+  );
+}
+
+/// `cargo doc` the crates of a scratch workspace.
+fn doc_workspace(root: &Path, members: &[&str]) {
+  cargo(root, members, &["doc", "--workspace", "--no-deps"]);
+}
+
+/// `cargo test` the crates of a scratch workspace and return the test output.
+fn test_workspace(root: &Path, members: &[&str]) -> String {
+  cargo(root, members, &["test", "--workspace"])
+}
+
+#[test]
+fn external_namespaces_are_imported_from_another_crate() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let xlink = xsd_tool(&["generate", "xlink.xsd"]);
+  let linked = xsd_tool(&[
+    "generate",
+    "linked.xsd",
+    "--external",
+    "http://www.w3.org/1999/xlink=xlink_types",
+  ]);
+
+  assert!(linked.contains("use xlink_types::{Href, Link};"));
+  assert!(!linked.contains("pub struct Link"));
+
+  write_crate(dir.path(), "xlink_types", &[], &xlink);
+  write_crate(dir.path(), "linked", &["xlink_types"], &linked);
+  check_workspace(dir.path(), &["xlink_types", "linked"]);
+}
+
+#[test]
+fn schema_text_is_escaped_in_literals() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let owned = xsd_tool(&["generate", "literals.xsd"]);
+  let marker = xsd_tool(&["generate", "literals.xsd", "--fixed-values", "marker"]);
+
+  assert!(owned.contains(r#""say \"hi\"" => Ok(Self::SayHi),"#));
+  assert!(owned.contains(r#""C:\\notes" => Ok(Self::CNotes),"#));
+  assert!(marker.contains(r#"const VALUE: &'static str = "C:\\\"{x}\"";"#));
+
+  write_crate(dir.path(), "owned", &[], &owned);
+  write_crate(dir.path(), "marker", &[], &marker);
+  check_workspace(dir.path(), &["owned", "marker"]);
+}
+
+#[test]
+fn field_metadata_compiles() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let note = xsd_tool(&["generate", "note.xsd", "--field-metadata"]);
+  assert!(note.contains("pub const FIELDS: &'static [FieldMeta]"));
+
+  write_crate(dir.path(), "note", &[], &note);
+  check_workspace(dir.path(), &["note"]);
+}
+
+#[test]
+fn send_sync_assertions_pass() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let note = xsd_tool(&["generate", "note.xsd", "--assert-send-sync"]);
+
+  write_crate(dir.path(), "note", &[], &note);
+  let output = test_workspace(dir.path(), &["note"]);
+  assert!(output.contains("generated_types_are_send_sync ... ok"));
+}
+
+#[test]
+fn send_sync_assertions_pass_on_musicxml() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let mut catalog = tempfile::NamedTempFile::new().unwrap();
+  for schema in ["xml.xsd", "xlink.xsd"] {
+    let copy = std::fs::canonicalize(format!("tests/fixtures/w3c/{schema}")).unwrap();
+    writeln!(
+      catalog,
+      "http://www.musicxml.org/xsd/{schema} {}",
+      copy.display()
+    )
+    .unwrap();
+  }
+  let musicxml = xsd_tool(&[
+    "--offline",
+    "--catalog",
+    catalog.path().to_str().unwrap(),
+    "generate",
+    "../../../musicxml.xsd",
+    "--assert-send-sync",
+  ]);
+  // The type of `sync` doesn't shadow the trait.
+  assert!(!musicxml.contains("pub struct Sync {"));
+
+  write_crate(dir.path(), "musicxml", &[], &musicxml);
+  let output = test_workspace(dir.path(), &["musicxml"]);
+  assert!(output.contains("generated_types_are_send_sync ... ok"));
+}
+
+#[test]
+fn restricted_choice_checks_the_occurrences() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let measure = xsd_tool(&["generate", "measure.xsd"]);
+  assert!(measure.contains("pub measure: RestrictedVec<measure::MeasureItem, 1, 3>,"));
+
+  let tests = r#"
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(content: &str) -> Result<Measure, DocumentError> {
+    let mut element = XMLElement::from_fragment(content).unwrap();
+    let state = GenState { is_root: true, state: GenType::Content, progress: None };
+    Measure::gen(&mut element, state, None)
+  }
+
+  #[test]
+  fn occurrences() {
+    let parsed = parse("<note>C</note><rest>q</rest>").unwrap();
+    assert_eq!(
+      *parsed.measure,
+      [measure::MeasureItem::Note("C".to_string()), measure::MeasureItem::Rest("q".to_string())]
+    );
+
+    let too_few = parse("").unwrap_err();
+    assert!(too_few.to_string().contains("less than the minimum size (0 < 1)"), "{too_few}");
+
+    let too_many = parse("<note>C</note><rest>q</rest><note>D</note><rest>h</rest>").unwrap_err();
+    assert!(too_many.to_string().contains("greater than the maximuim size (4 > 3)"), "{too_many}");
+  }
+}
+"#;
+
+  write_crate(dir.path(), "measure", &[], &(measure + tests));
+  let output = test_workspace(dir.path(), &["measure"]);
+  assert!(output.contains("tests::occurrences ... ok"));
+}
+
+#[test]
+fn empty_type_only_consumes_its_element() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let chord = xsd_tool(&["generate", "chord.xsd"]);
+  assert!(chord.contains("pub struct Chord;"));
+
+  let tests = r#"
+#[cfg(test)]
+mod tests {
+  use xml_schema_parser::xml_element;
+
+  use super::*;
+
+  fn parse(content: &str, state: GenType) -> Result<Note, DocumentError> {
+    let mut element = XMLElement::from_fragment(content).unwrap();
+    Note::gen(&mut element, GenState { is_root: true, state, progress: None }, None)
+  }
+
+  #[test]
+  fn empty_type() {
+    let note = parse("<chord/><pitch>C</pitch>", GenType::Content).unwrap();
+    assert_eq!(note.chord, Some(note::Chord));
+    assert_eq!(note.pitch, "C");
+
+    let note = parse("<pitch>D</pitch>", GenType::Content).unwrap();
+    assert_eq!(note.chord, None);
+    assert_eq!(note.pitch, "D");
+
+    // No attribute is required in the attribute mode.
+    let mut element = xml_element!("chord");
+    let state = GenState { is_root: false, state: GenType::Attribute, progress: None };
+    assert_eq!(note::Chord::gen(&mut element, state, Some("chord")).unwrap(), note::Chord);
+  }
+}
+"#;
+
+  write_crate(dir.path(), "chord", &[], &(chord + tests));
+  let output = test_workspace(dir.path(), &["chord"]);
+  assert!(output.contains("tests::empty_type ... ok"));
+}
+
+#[test]
+fn repeated_alternatives_keep_the_document_order() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let notes = xsd_tool(&["generate", "notes.xsd"]);
+  assert!(notes.contains("pub noterest: Vec<measure::NoterestItem>,"));
+
+  let tests = r#"
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(document: &str) -> Result<Measure, DocumentError> {
+    let mut element = XMLElement::from_element(
+      xmltree::Element::parse(document.as_bytes()).unwrap(),
+      None,
+    );
+    let state = GenState { is_root: true, state: GenType::Content, progress: None };
+    Measure::gen(&mut element, state, None)
+  }
+
+  #[test]
+  fn document_order() {
+    use measure::noterest_item::Noterest::{Note, Rest};
+
+    let measure =
+      parse("<measure><number>1</number><note>C</note><rest>q</rest><note>D</note></measure>")
+        .unwrap();
+    let values: Vec<_> = measure.noterest.into_iter().map(|v| v.noterest).collect();
+    assert_eq!(values, [Note("C".to_string()), Rest("q".to_string()), Note("D".to_string())]);
+
+    let measure = parse("<measure><number>2</number></measure>").unwrap();
+    assert!(measure.noterest.is_empty());
+  }
+}
+"#;
+
+  write_crate(dir.path(), "notes", &[], &(notes + tests));
+  let output = test_workspace(dir.path(), &["notes"]);
+  assert!(output.contains("tests::document_order ... ok"));
+}
+
+#[test]
+fn complex_restriction_keeps_its_restated_content() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let restricted = xsd_tool(&["generate", "restricted.xsd"]);
+  assert!(restricted.contains("pub struct GraceNote {"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use xml_schema_parser::xml_element;
+
+  use super::*;
+
+  fn parse(mut element: XMLElement) -> Result<GraceNote, DocumentError> {
+    GraceNote::gen(&mut element, GenState { is_root: true, state: GenType::Content, progress: None }, None)
+  }
+
+  #[test]
+  fn restricted_content() {
+    let pitch = xml_element!("pitch", text: "C");
+    let grace = parse(xml_element!(
+      "grace",
+      attrs: { "id" => "g1", "color" => "red" },
+      children: [pitch.clone()],
+    ))
+    .unwrap();
+    assert_eq!(
+      grace,
+      GraceNote {
+        pitch: "C".to_string(),
+        id: Some("g1".to_string()),
+        color: "red".to_string(),
+      }
+    );
+
+    // The attribute made required by the restriction.
+    assert!(parse(xml_element!("grace", children: [pitch])).is_err());
+  }
+}
+"##;
+
+  write_crate(dir.path(), "restricted", &[], &(restricted + tests));
+  let output = test_workspace(dir.path(), &["restricted"]);
+  assert!(output.contains("tests::restricted_content ... ok"));
+}
+
+#[test]
+fn alternatives_are_picked_by_element_name() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let path = xsd_tool(&["generate", "path.xsd"]);
+  assert!(path.contains("if element.next_child_is(\"down\")"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(document: &str) -> Result<Path, DocumentError> {
+    let mut element = XMLElement::from_element(
+      xmltree::Element::parse(document.as_bytes()).unwrap(),
+      None,
+    );
+    Path::gen(&mut element, GenState { is_root: true, state: GenType::Content, progress: None }, None)
+  }
+
+  #[test]
+  fn dispatch() {
+    let path = parse(r#"<path><down count="1"/><up/><down count="3"/></path>"#).unwrap();
+    assert_eq!(
+      path.path.to_vec(),
+      vec![
+        path::PathItem::Down(Step { count: Some(1) }),
+        path::PathItem::Up(Step { count: None }),
+        path::PathItem::Down(Step { count: Some(3) }),
+      ]
+    );
+
+    // The error of the alternative named by the child is reported.
+    let error = parse(r#"<path><up count="high"/></path>"#).unwrap_err();
+    assert!(!error.to_string().contains("No valid values"), "{error}");
+  }
+}
+"##;
+
+  write_crate(dir.path(), "path", &[], &(path + tests));
+  let output = test_workspace(dir.path(), &["path"]);
+  assert!(output.contains("tests::dispatch ... ok"));
+}
+
+#[test]
+fn elements_with_the_same_fields_convert() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let scores = xsd_tool(&["generate", "scores.xsd"]);
+  assert!(scores.contains("impl From<Opus> for Score {"));
+  // Both scores are read into `Score` and the fields of a movement differ.
+  assert!(!scores.contains("impl From<Score> for Score"));
+  assert!(!scores.contains("> for Movement"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use xml_schema_parser::xml_element;
+
+  use super::*;
+
+  #[test]
+  fn conversions() {
+    let mut element = xml_element!(
+      "opus",
+      attrs: { "version" => "4" },
+      children: [xml_element!("title", text: "Suite")],
+    );
+    let opus = Opus::gen(&mut element, GenState { is_root: true, state: GenType::Content, progress: None }, None)
+      .unwrap();
+
+    let score = Score::from(opus.clone());
+    assert_eq!(score.title, "Suite");
+    assert_eq!(score.version.as_deref(), Some("4"));
+    assert_eq!(Opus::from(score), opus);
+  }
+}
+"##;
+
+  write_crate(dir.path(), "scores", &[], &(scores + tests));
+  let output = test_workspace(dir.path(), &["scores"]);
+  assert!(output.contains("tests::conversions ... ok"));
+}
+
+#[test]
+fn flattened_wrappers_hold_their_base() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let tenths = xsd_tool(&["generate", "tenths.xsd", "--flatten-wrappers"]);
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn values() {
+    let mut element = XMLElement::from_element(
+      xmltree::Element::parse(
+        r#"<note default-x="2.5"><duration>4</duration><tied-duration>2</tied-duration><octave>3</octave></note>"#
+          .as_bytes(),
+      )
+      .unwrap(),
+      None,
+    );
+    let note = Note::gen(&mut element, GenState { is_root: true, state: GenType::Content, progress: None }, None)
+      .unwrap();
+
+    let duration: Decimal = note.duration;
+    assert_eq!(duration.to_f64() + note.tied_duration.0.to_f64(), 6.0);
+    assert_eq!(note.default_x, Some(Decimal::new(25, 1)));
+    assert_eq!(note.octave.0, 3);
+  }
+}
+"##;
+
+  write_crate(dir.path(), "tenths", &[], &(tenths + tests));
+  let output = test_workspace(dir.path(), &["tenths"]);
+  assert!(output.contains("tests::values ... ok"));
+}
+
+#[test]
+fn schemas_without_target_namespace_read_unqualified_names() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let local = xsd_tool(&["generate", "local.xsd"]);
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn section() {
+    let mut element = XMLElement::from_element(
+      xmltree::Element::parse(
+        r#"<section level="2" tag="intro" kind="final">
+          <title>Scope</title><author>Ann</author><para>One</para><para>Two</para>
+        </section>"#
+          .as_bytes(),
+      )
+      .unwrap(),
+      None,
+    );
+    let section =
+      Section::gen(&mut element, GenState { is_root: true, state: GenType::Content, progress: None }, None)
+        .unwrap();
+
+    assert_eq!(section.heading.title, "Scope");
+    assert_eq!(section.heading.author.as_deref(), Some("Ann"));
+    assert_eq!(section.para.len(), 2);
+    assert_eq!(section.tagged.level, Some(Level(2)));
+    assert_eq!(section.tagged.tag.as_deref(), Some("intro"));
+    assert_eq!(section.kind, Some(Kind::Final));
+  }
+}
+"##;
+
+  write_crate(dir.path(), "local", &[], &(local + tests));
+  let output = test_workspace(dir.path(), &["local"]);
+  assert!(output.contains("tests::section ... ok"));
+}
+
+#[test]
+fn progress_hooks_count_the_repetitions() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let note = xsd_tool(&["generate", "note.xsd", "--progress-hooks"]);
+  assert!(
+    note.contains("pub fn gen_with_progress(element: &mut XMLElement, progress: ProgressHook)")
+  );
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use std::sync::{Arc, Mutex};
+
+  use super::*;
+
+  #[test]
+  fn progress() {
+    let ties = "<tie>red</tie>".repeat(2500);
+    let document = format!("<score><pitch>C</pitch>{ties}</score>");
+    let mut element = XMLElement::parse(document.as_bytes()).unwrap();
+
+    let events = Arc::new(Mutex::new(vec![]));
+    let hook: ProgressHook = {
+      let events = events.clone();
+      Arc::new(move |event: xml_schema_parser::ProgressEvent| {
+        events.lock().unwrap().push((event.path, event.count))
+      })
+    };
+
+    let note = Note::gen_with_progress(&mut element, hook).unwrap();
+    assert_eq!(note.tie.len(), 2500);
+    assert_eq!(
+      *events.lock().unwrap(),
+      [("score/tie".to_string(), 1000), ("score/tie".to_string(), 2000)]
+    );
+  }
+}
+"##;
+
+  write_crate(dir.path(), "note", &[], &(note + tests));
+  let output = test_workspace(dir.path(), &["note"]);
+  assert!(output.contains("tests::progress ... ok"));
+}
+
+#[test]
+fn reserved_names_do_not_shadow_the_prelude() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let reserved = xsd_tool(&["generate", "reserved.xsd"]);
+  // The times are read through their path, a type of the schema can be named the same.
+  assert!(reserved.contains("pub struct Time {"));
+  assert!(reserved.contains("pub start: xml_schema_parser::Time,"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn concert() {
+    let mut element = XMLElement::from_element(
+      xmltree::Element::parse(
+        r#"<concert>
+          <date><day>2024-05-01</day><online>on</online></date>
+          <string tuning="off">E</string><string>A</string>
+          <title>Strings</title>
+          <time start="20:00:00+02:00" length="PT90M"><doors>2024-05-01T19:30:00.5Z</doors></time>
+        </concert>"#
+          .as_bytes(),
+      )
+      .unwrap(),
+      None,
+    );
+    let state = GenState { is_root: true, state: GenType::Content, progress: None };
+    let concert = Concert::gen(&mut element, state, None).unwrap();
+
+    let day: &Date = &concert.date.day;
+    assert_eq!(day.value.to_string(), "2024-05-01");
+    assert_eq!(concert.date.placeonline, date_type::Placeonline::Online(OptionType::On));
+    assert_eq!(concert.string[0].tuning, Some(OptionType::Off));
+    assert_eq!(concert.string[1].string, "A");
+    assert_eq!(concert.encore, None);
+
+    let time: &Time = concert.time.as_ref().unwrap();
+    assert_eq!(time.start.to_string(), "20:00:00+02:00");
+    assert_eq!(time.doors.value.to_string(), "2024-05-01 19:30:00.500");
+    assert_eq!(time.doors.to_string(), "2024-05-01T19:30:00.500Z");
+    let length = time.length.unwrap();
+    assert_eq!(length.to_string(), "PT1H30M");
+    assert!(length > xml_schema_parser::Duration::from_xml("PT1H").unwrap());
+    assert_eq!(length.to_std(), Some(std::time::Duration::from_secs(5400)));
+  }
+}
+"##;
+
+  write_crate(dir.path(), "reserved", &[], &(reserved + tests));
+  let output = test_workspace(dir.path(), &["reserved"]);
+  assert!(output.contains("tests::concert ... ok"));
+}
+
+#[test]
+fn extended_content_is_read_in_particle_order() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let ordered = xsd_tool(&["generate", "ordered.xsd"]);
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn pair() {
+    let mut element = XMLElement::from_element(
+      xmltree::Element::parse(
+        r#"<pair id="p"><note>a</note><rest>r</rest><note>b</note></pair>"#.as_bytes(),
+      )
+      .unwrap(),
+      None,
+    );
+    let state = GenState { is_root: true, state: GenType::Content, progress: None };
+    let pair = Pair::gen(&mut element, state, None).unwrap();
+
+    assert_eq!(pair.pair.note, "a");
+    assert_eq!(pair.pair.id.as_deref(), Some("p"));
+    assert_eq!(pair.rest, "r");
+    assert_eq!(pair.note, "b");
+  }
+}
+"##;
+
+  write_crate(dir.path(), "ordered", &[], &(ordered + tests));
+  let output = test_workspace(dir.path(), &["ordered"]);
+  assert!(output.contains("tests::pair ... ok"));
+}
+
+#[test]
+fn all_children_are_read_in_any_order() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let unordered = xsd_tool(&["generate", "unordered.xsd"]);
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use xml_schema_parser::XmlDocument;
+
+  #[test]
+  fn pitch() {
+    let pitch = Pitch::parse_str(
+      r#"<pitch><octave cautionary="yes">4</octave><step>C</step><accidental>sharp</accidental></pitch>"#,
+    )
+    .unwrap();
+
+    assert_eq!(pitch.pitch.step, "C");
+    assert_eq!(pitch.pitch.alter, None);
+    assert_eq!(pitch.pitch.accidental.unwrap().accidental, "sharp");
+    assert_eq!(pitch.pitch.octave.accidental, "4");
+    assert_eq!(pitch.pitch.octave.cautionary.as_deref(), Some("yes"));
+
+    assert!(Pitch::parse_str("<pitch><octave>4</octave></pitch>").is_err());
+  }
+}
+"##;
+
+  write_crate(dir.path(), "unordered", &[], &(unordered + tests));
+  let output = test_workspace(dir.path(), &["unordered"]);
+  assert!(output.contains("tests::pitch ... ok"));
+}
+
+#[test]
+fn stubbed_components_are_left_unparsed() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let stubs = xsd_tool(&[
+    "generate",
+    "stubs.xsd",
+    "--roots",
+    "score",
+    "--stub",
+    "complexType:identification=skip",
+    "--stub",
+    "complexType:figured-bass=opaque",
+  ]);
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn score() {
+    let mut element = XMLElement::from_element(
+      xmltree::Element::parse(
+        r#"<score version="4.0">
+          <title>Sonata</title>
+          <identification><creator type="composer">Anon</creator></identification>
+          <figured-bass><figure>6</figure><figure>4</figure></figured-bass>
+        </score>"#
+          .as_bytes(),
+      )
+      .unwrap(),
+      None,
+    );
+    let state = GenState { is_root: true, state: GenType::Content, progress: None };
+    let score = Score::gen(&mut element, state, None).unwrap();
+
+    assert_eq!(score.title, "Sonata");
+    assert_eq!(score.version.as_deref(), Some("4.0"));
+    let figured_bass: &RawXml = &score.figured_bass[0];
+    assert_eq!(figured_bass.0.name, "figured-bass");
+    assert_eq!(figured_bass.0.children.len(), 2);
+  }
+}
+"##;
+
+  write_crate(dir.path(), "stubs", &[], &(stubs + tests));
+  let output = test_workspace(dir.path(), &["stubs"]);
+  assert!(output.contains("tests::score ... ok"));
+}
+
+#[test]
+fn types_keep_their_name_over_other_components() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let shared = xsd_tool(&["generate", "shared_names.xsd"]);
+  for field in [
+    "pub dynamics: Dynamics,",
+    "pub wedge: WedgeElement,",
+    "pub hairpin: Option<Wedge>,",
+    "pub stems: Option<Slash>,",
+    "pub slash: SlashGroup,",
+  ] {
+    assert!(shared.contains(field), "{field} in {shared}");
+  }
+  assert!(shared.contains("pub mod slash_group {"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn direction() {
+    let mut element = XMLElement::from_element(
+      xmltree::Element::parse(
+        r#"<direction><dynamics><p>p</p></dynamics><wedge type="crescendo"/><hairpin><wedge/><spread>1</spread></hairpin><slash-dot>d</slash-dot></direction>"#.as_bytes(),
+      )
+      .unwrap(),
+      None,
+    );
+    let state = GenState { is_root: true, state: GenType::Content, progress: None };
+    let direction = Direction::gen(&mut element, state, None).unwrap();
+
+    assert_eq!(direction.wedge.r_type.as_deref(), Some("crescendo"));
+    assert_eq!(direction.hairpin.unwrap().spread, "1");
+    assert!(direction.stems.is_none());
+    assert_eq!(
+      direction.slash.slash_typeslash_dot,
+      Some(slash_group::SlashTypeslashDotValue::SlashDot("d".to_string()))
+    );
+  }
+}
+"##;
+
+  write_crate(dir.path(), "shared", &[], &(shared + tests));
+  let output = test_workspace(dir.path(), &["shared"]);
+  assert!(output.contains("tests::direction ... ok"));
+}
+
+#[test]
+fn generated_code_passes_clippy() {
+  let dir = tempfile::tempdir().unwrap();
+
+  // In a private module, clippy also checks the names of the types as they are not exported.
+  let private = |code: String| format!("#[allow(dead_code)]\nmod generated {{\n{code}}}\n");
+
+  let lints = xsd_tool(&["generate", "lints.xsd"]);
+  assert!(lints.contains("#[allow(clippy::enum_variant_names, clippy::large_enum_variant)]"));
+  assert!(lints.contains("#[allow(clippy::module_inception, dead_code, unused_imports)]"));
+
+  let options = xsd_tool(&[
+    "generate",
+    "lints.xsd",
+    "--fixed-values",
+    "marker",
+    "--field-metadata",
+    "--progress-hooks",
+    "--verbose-codegen",
+  ]);
+  let stubs = xsd_tool(&[
+    "generate",
+    "stubs.xsd",
+    "--roots",
+    "score",
+    "--stub",
+    "complexType:identification=skip",
+    "--stub",
+    "complexType:figured-bass=opaque",
+  ]);
+
+  write_crate(dir.path(), "lints", &[], &private(lints));
+  write_crate(dir.path(), "options", &[], &private(options));
+  write_crate(dir.path(), "stubs", &[], &private(stubs));
+  clippy_workspace(dir.path(), &["lints", "options", "stubs"]);
+}
+
+#[test]
+fn serde_uses_the_xml_names() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let parts = xsd_tool(&["generate", "parts.xsd", "--serde"]);
+  assert!(parts.contains("#[serde(rename = \"part-name\")]\n    pub part_name: String,"));
+  assert!(!parts.contains("#[serde(rename = \"group\")]"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn round_trip() {
+    let mut element = XMLElement::from_element(
+      xmltree::Element::parse(
+        r#"<score-part id="P1" print-object="no" created="2024-01-31Z"><part-name>Piano</part-name><virtual-instrument>Grand</virtual-instrument></score-part>"#.as_bytes(),
+      )
+      .unwrap(),
+      None,
+    );
+    let part = ScorePart::gen(&mut element, GenState { is_root: true, state: GenType::Content, progress: None }, None)
+      .unwrap();
+
+    let json = serde_json::to_string(&part).unwrap();
+    assert_eq!(
+      json,
+      r#"{"part-name":"Piano","group":[],"score_instrumentvirtual_instrument":{"virtual-instrument":"Grand"},"id":"P1","print-object":"no","created":"2024-01-31Z"}"#
+    );
+    assert_eq!(serde_json::from_str::<ScorePart>(&json).unwrap(), part);
+
+    // The repeated elements may be missing.
+    let json = json.replace(r#""group":[],"#, "");
+    assert_eq!(serde_json::from_str::<ScorePart>(&json).unwrap(), part);
+  }
+}
+"##;
+
+  write_crate(
+    dir.path(),
+    "parts",
+    &[
+      "xml-schema-parser/serde",
+      r#"serde = { version = "1", features = ["derive"] }"#,
+      r#"serde_json = "1""#,
+    ],
+    &(parts + tests),
+  );
+  let output = test_workspace(dir.path(), &["parts"]);
+  assert!(output.contains("tests::round_trip ... ok"));
+}
+
+#[test]
+fn substitution_groups_are_picked_by_element_name() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let instruments = xsd_tool(&["generate", "instruments.xsd", "--xml-writers"]);
+  assert!(instruments.contains("pub enum InstrumentGroup {"));
+  assert!(instruments.contains("pub instrument: RestrictedVec<InstrumentGroup, 1, 0>,"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use xml_schema_parser::XmlDocument;
+
+  #[test]
+  fn substitution() {
+    let document = r#"<part><part-name>Church</part-name><organ stops="12"/><piano>Grand</piano><keyboard>Upright</keyboard></part>"#;
+    let part = Part::parse_str(document).unwrap();
+    assert_eq!(
+      part.instrument.to_vec(),
+      vec![
+        InstrumentGroup::Organ(Organ { stops: Some(12) }),
+        InstrumentGroup::Piano("Grand".to_string()),
+        InstrumentGroup::Keyboard("Upright".to_string()),
+      ]
+    );
+    assert_eq!(part.keyboard, None);
+
+    let written = part.to_xml_string().unwrap();
+    assert_eq!(Part::parse_str(&written).unwrap(), part);
+
+    // The abstract head isn't read in place of its members.
+    let error = Part::parse_str("<part><part-name>Empty</part-name><instrument>Flute</instrument></part>");
+    assert!(error.is_err());
+  }
+}
+"##;
+
+  write_crate(dir.path(), "instruments", &[], &(instruments + tests));
+  clippy_workspace(dir.path(), &["instruments"]);
+  let output = test_workspace(dir.path(), &["instruments"]);
+  assert!(output.contains("tests::substitution ... ok"));
+}
+
+#[test]
+fn absent_attributes_have_their_default_or_fixed_value() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let defaults = xsd_tool(&["generate", "defaults.xsd", "--xml-writers"]);
+  assert!(defaults.contains("pub dynamics: Decimal,"));
+  assert!(defaults.contains("pub units: Units,"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use xml_schema_parser::XmlDocument;
+
+  #[test]
+  fn value_constraints() {
+    let note = Note::parse_str("<note/>").unwrap();
+    assert_eq!(
+      note,
+      Note {
+        dynamics: Decimal::from(90),
+        print_object: YesNo::Yes,
+        kind: "pitched".to_string(),
+        staff: None,
+        units: Units("mm".to_string()),
+        revision: Revision("4.0".to_string()),
+      }
+    );
+
+    let note = Note::parse_str(r#"<note dynamics="45" print-object="no" kind="pitched" units="cm"/>"#).unwrap();
+    assert_eq!(note.dynamics, Decimal::from(45));
+    assert_eq!(note.print_object, YesNo::No);
+    assert_eq!(note.units, Units("cm".to_string()));
+
+    let written = note.to_xml_string().unwrap();
+    assert_eq!(Note::parse_str(&written).unwrap(), note);
+
+    // A fixed attribute can only have its fixed value.
+    let error = Note::parse_str(r#"<note kind="unpitched"/>"#).unwrap_err();
+    assert!(error.to_string().contains("Expected the fixed value pitched of kind, found unpitched."), "{error}");
+    assert!(Note::parse_str(r#"<note revision="3.1"/>"#).is_err());
+  }
+}
+"##;
+
+  write_crate(dir.path(), "defaults", &[], &(defaults + tests));
+  clippy_workspace(dir.path(), &["defaults"]);
+  let output = test_workspace(dir.path(), &["defaults"]);
+  assert!(output.contains("tests::value_constraints ... ok"));
+}
+
+#[test]
+fn gregorian_dates_keep_their_timezone() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let calendar = xsd_tool(&["generate", "calendar.xsd", "--xml-writers"]);
+  assert!(calendar.contains("pub year: xml_schema_parser::GYear,"));
+  assert!(calendar.contains("pub premiere: RestrictedVec<xml_schema_parser::GMonthDay, 1, 0>,"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use xml_schema_parser::XmlDocument;
+
+  #[test]
+  fn season() {
+    let season = Season::parse_str(
+      r#"<season year="2025-05:00" opening="2024-09Z" closing="--06" rehearsal="---15"><premiere>--02-29</premiere></season>"#,
+    )
+    .unwrap();
+    assert_eq!(season.year.year, 2025);
+    assert_eq!(season.year.to_string(), "2025-05:00");
+    assert_eq!(season.opening.as_ref().unwrap().to_string(), "2024-09Z");
+    assert_eq!(season.closing.as_ref().unwrap().month, 6);
+    assert_eq!(season.rehearsal.as_ref().unwrap().day, 15);
+    assert_eq!(season.premiere[0].to_string(), "--02-29");
+
+    let written = season.to_xml_string().unwrap();
+    assert_eq!(Season::parse_str(&written).unwrap(), season);
+
+    let error = Season::parse_str(r#"<season year="2025"><premiere>--02-30</premiere></season>"#).unwrap_err();
+    assert!(error.to_string().contains("--02-30 is not a day of the year"), "{error}");
+  }
+}
+"##;
+
+  write_crate(dir.path(), "calendar", &[], &(calendar + tests));
+  clippy_workspace(dir.path(), &["calendar"]);
+  let output = test_workspace(dir.path(), &["calendar"]);
+  assert!(output.contains("tests::season ... ok"));
+}
+
+#[test]
+fn any_type_content_is_kept() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let envelope = xsd_tool(&[
+    "generate",
+    "envelope.xsd",
+    "--xml-writers",
+    "--dynamic-values",
+  ]);
+  assert!(envelope.contains("pub payload: xml_schema_parser::AnyXml,"));
+  assert!(envelope.contains("pub extension: Vec<xml_schema_parser::AnyXml>,"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use xml_schema_parser::{DynamicValue, FromDynamic, ToDynamic, XmlDocument};
+
+  #[test]
+  fn envelope() {
+    let envelope = Envelope::parse_str(
+      r#"<envelope><sender>ci</sender><payload kind="build"><step id="1">fetch</step><step id="2">test</step></payload><extension/></envelope>"#,
+    )
+    .unwrap();
+    assert_eq!(envelope.payload.attributes["kind"], "build");
+    assert_eq!(envelope.payload.children.len(), 2);
+    assert_eq!(envelope.payload.children[1].attributes["id"], "2");
+    assert_eq!(envelope.payload.children[1].text, "test");
+    assert_eq!(envelope.extension.len(), 1);
+
+    let written = envelope.to_xml_string().unwrap();
+    assert!(written.contains(r#"<payload kind="build"><step id="1">fetch</step>"#), "{written}");
+    assert_eq!(Envelope::parse_str(&written).unwrap(), envelope);
+
+    let DynamicValue::Map(fields) = envelope.to_dynamic() else {
+      panic!("the envelope is not a map");
+    };
+    assert!(matches!(&fields["payload"], DynamicValue::Map(payload) if payload.contains_key("children")));
+    assert_eq!(Envelope::from_dynamic(&envelope.to_dynamic()).unwrap(), envelope);
+  }
+}
+"##;
+
+  write_crate(dir.path(), "envelope", &[], &(envelope + tests));
+  clippy_workspace(dir.path(), &["envelope"]);
+  let output = test_workspace(dir.path(), &["envelope"]);
+  assert!(output.contains("tests::envelope ... ok"));
+}
+
+#[test]
+fn derived_types_are_picked_by_xsi_type() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let figures = xsd_tool(&["generate", "figures.xsd", "--xml-writers"]);
+  assert!(figures.contains("pub enum AnyShape {"));
+  assert!(figures.contains("pub shape: RestrictedVec<AnyShape, 1, 0>,"));
+  assert!(figures.contains("pub frame: Option<AnySquare>,"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use xml_schema_parser::XmlDocument;
+
+  #[test]
+  fn instance_types() {
+    let document = r#"<figure xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"><shape xsi:type="circle" color="red" radius="2"/><shape xsi:type="rounded-square" corner="0.5"><side>3</side></shape><frame><side>10</side></frame></figure>"#;
+    let figure = Figure::parse_str(document).unwrap();
+    assert_eq!(
+      figure.shape.to_vec(),
+      vec![
+        AnyShape::Circle(Circle {
+          circle: Shape { color: Some("red".to_string()) },
+          radius: Some(2.0),
+        }),
+        AnyShape::RoundedSquare(RoundedSquare {
+          rounded_square: Square { square: Shape { color: None }, side: 3.0 },
+          corner: Some(0.5),
+        }),
+      ]
+    );
+    // Without an `xsi:type` the element is of its declared type.
+    assert!(matches!(figure.frame, Some(AnySquare::Square(Square { side: 10.0, .. }))));
+    assert_eq!(AnySquare::INSTANCE_TYPES, &["square", "rounded-square"]);
+
+    let written = figure.to_xml_string().unwrap();
+    assert!(written.contains(r#"xsi:type="rounded-square""#), "{written}");
+    assert_eq!(Figure::parse_str(&written).unwrap(), figure);
+
+    // The abstract type needs an `xsi:type`, which must derive from it.
+    let error = Figure::parse_str("<figure><shape color=\"red\"/></figure>").unwrap_err();
+    assert!(error.to_string().contains("abstract"), "{error}");
+    let error = Figure::parse_str(
+      r#"<figure xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"><shape xsi:type="triangle"/></figure>"#,
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("circle, square, rounded-square"), "{error}");
+  }
+}
+"##;
+
+  write_crate(dir.path(), "figures", &[], &(figures + tests));
+  clippy_workspace(dir.path(), &["figures"]);
+  let output = test_workspace(dir.path(), &["figures"]);
+  assert!(output.contains("tests::instance_types ... ok"));
+}
+
+#[test]
+fn restriction_facets_are_checked_when_reading() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let facets = xsd_tool(&[
+    "generate",
+    "facets.xsd",
+    "--xml-writers",
+    "--pattern-checks",
+  ]);
+  assert!(facets.contains("impl FromXmlString for Octave {"));
+  assert!(facets.contains("impl XsdGen for PositiveDivisions {"));
+
+  let tests = r###"
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use xml_schema_parser::XmlDocument;
+
+  #[test]
+  fn facets() {
+    let note = Note::parse_str(
+      r##"<note color="#40800A" octave="4" tempo="120.5" glyph="codaSquare" checksum="0fb7"><duration>2</duration><syllable>la</syllable></note>"##,
+    )
+    .unwrap();
+    assert_eq!(note.octave, Octave(4));
+    assert_eq!(note.duration, PositiveDivisions(Divisions(Decimal::from(2))));
+    assert_eq!(note.syllable, Some(Syllable("la".to_string())));
+    assert_eq!(note.checksum.as_ref().unwrap().0 .0, [0x0F, 0xB7]);
+
+    let written = note.to_xml_string().unwrap();
+    assert_eq!(Note::parse_str(&written).unwrap(), note);
+
+    let error = Note::parse_str(r#"<note octave="10"><duration>1</duration></note>"#).unwrap_err();
+    assert!(error.to_string().contains("10 is greater than the maxInclusive 9 of octave."), "{error}");
+    let error = Note::parse_str(r#"<note octave="4"><duration>0</duration></note>"#).unwrap_err();
+    assert!(error.to_string().contains("0 is not greater than the minExclusive 0 of positive-divisions."), "{error}");
+    let error = Note::parse_str(r#"<note octave="4" color="red"><duration>1</duration></note>"#).unwrap_err();
+    assert!(error.to_string().contains("red doesn't match the pattern"), "{error}");
+    let error = Note::parse_str(r#"<note octave="4" glyph="segno"><duration>1</duration></note>"#).unwrap_err();
+    assert!(error.to_string().contains("segno doesn't match the pattern coda\\c*."), "{error}");
+    let error = Note::parse_str(r#"<note octave="4" tempo="60.125"><duration>1</duration></note>"#).unwrap_err();
+    assert!(error.to_string().contains("60.125 has more fraction digits than the fractionDigits 2 of tempo."), "{error}");
+    let error = Note::parse_str(r#"<note octave="4"><duration>1</duration><syllable>supercalifragilistic</syllable></note>"#).unwrap_err();
+    assert!(error.to_string().contains("The length 20 is greater than the maxLength 8 of syllable."), "{error}");
+    let error = Note::parse_str(r#"<note octave="4" checksum="0FB7C0"><duration>1</duration></note>"#).unwrap_err();
+    assert!(error.to_string().contains("The length 3 is not the length 2 of checksum."), "{error}");
+  }
+}
+"###;
+
+  write_crate(dir.path(), "facets", &[], &(facets + tests));
+  clippy_workspace(dir.path(), &["facets"]);
+  let output = test_workspace(dir.path(), &["facets"]);
+  assert!(output.contains("tests::facets ... ok"));
+}
+
+#[test]
+fn dynamic_values_round_trip() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let parts = xsd_tool(&["generate", "parts.xsd", "--dynamic-values"]);
+  assert!(parts.contains("(\"part-name\", value.part_name.to_dynamic()),"));
+  assert!(parts.contains("part_name: fields.get(\"part-name\")?,"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn round_trip() {
+    let mut element = XMLElement::from_element(
+      xmltree::Element::parse(
+        r#"<score-part id="P1" print-object="no" created="2024-01-31Z"><part-name>Piano</part-name><virtual-instrument>Grand</virtual-instrument></score-part>"#.as_bytes(),
+      )
+      .unwrap(),
+      None,
+    );
+    let part = ScorePart::gen(&mut element, GenState { is_root: true, state: GenType::Content, progress: None }, None)
+      .unwrap();
+
+    let value = DynamicValue::from(&part);
+    let fields = value.fields("ScorePart").unwrap();
+    assert_eq!(fields.get::<String>("part-name").unwrap(), "Piano");
+    assert_eq!(fields.get::<String>("id").unwrap(), "P1");
+    assert_eq!(ScorePart::try_from(&value).unwrap(), part);
+
+    let DynamicValue::Map(mut entries) = value else { panic!("{value:?}") };
+    entries.insert("part-name".to_string(), DynamicValue::List(vec![]));
+    let error = ScorePart::try_from(&DynamicValue::Map(entries)).unwrap_err();
+    assert!(error.to_string().contains("part-name"), "{error}");
+  }
+}
+"##;
+
+  write_crate(dir.path(), "parts", &[], &(parts + tests));
+  clippy_workspace(dir.path(), &["parts"]);
+  let output = test_workspace(dir.path(), &["parts"]);
+  assert!(output.contains("tests::round_trip ... ok"));
+}
+
+#[test]
+fn xml_writers_round_trip() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let parts = xsd_tool(&["generate", "parts.xsd", "--xml-writers"]);
+  assert!(parts.contains("impl XsdSer for ScorePart {"));
+  assert!(parts.contains("XsdSer::ser(&self.id, element, GenType::Attribute, Some(\"id\"));"));
+  assert!(parts.contains("impl ToXmlString for YesNo {"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use xml_schema_parser::XmlDocument;
+
+  #[test]
+  fn round_trip() {
+    let document = r#"<score-part id="P1" print-object="no" created="2024-01-31Z"><part-name>Piano &amp; voice</part-name><group>a</group><group>b</group><virtual-instrument>Grand</virtual-instrument></score-part>"#;
+    let part = ScorePart::parse_str(document).unwrap();
+
+    let written = part.to_xml_string().unwrap();
+    assert_eq!(
+      written,
+      r#"<?xml version="1.0" encoding="UTF-8"?><score-part created="2024-01-31Z" id="P1" print-object="no"><part-name>Piano &amp; voice</part-name><group>a</group><group>b</group><virtual-instrument>Grand</virtual-instrument></score-part>"#
+    );
+    assert_eq!(ScorePart::parse_str(&written).unwrap(), part);
+  }
+}
+"##;
+
+  write_crate(dir.path(), "parts", &[], &(parts + tests));
+  clippy_workspace(dir.path(), &["parts"]);
+  let output = test_workspace(dir.path(), &["parts"]);
+  assert!(output.contains("tests::round_trip ... ok"));
+}
+
+#[test]
+fn unknown_content_is_written_back() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let vendor = xsd_tool(&[
+    "generate",
+    "vendor.xsd",
+    "--xml-writers",
+    "--unknown-content",
+    "--dynamic-values",
+    "--serde",
+  ]);
+  assert!(vendor.contains("let keep_unknown = element.claim_unknown();"));
+  assert!(vendor.contains("element.write_unknown(&self.unknown_content);"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use xml_schema_parser::XmlDocument;
+
+  #[test]
+  fn vendor_extensions() {
+    let document = r#"<note default-x="10" v:color="red" xmlns:v="urn:vendor"><pitch>C</pitch><v:glow>2</v:glow><duration>4</duration><lyric number="1" v:font="serif"><text>la</text><v:hint>soft</v:hint></lyric><notations id="n1" v:style="bold"><v:before/><ornament placement="above" v:size="2"><trill>tr</trill><v:wave>3</v:wave></ornament></notations><v:fx/></note>"#;
+    let mut note = Note::parse_str(document).unwrap();
+    assert_eq!(note.unknown_content.attribute(Some("urn:vendor"), "color"), Some("red"));
+    assert_eq!(note.unknown_content.children.len(), 2);
+    assert_eq!(note.lyric[0].unknown_content.attribute(Some("urn:vendor"), "font"), Some("serif"));
+    // The anonymous types of the nested elements keep theirs too.
+    let notations = note.notations.as_ref().unwrap();
+    assert_eq!(notations.unknown_content.attribute(Some("urn:vendor"), "style"), Some("bold"));
+    assert_eq!(notations.unknown_content.children.len(), 1);
+    assert_eq!(notations.ornament[0].unknown_content.attribute(Some("urn:vendor"), "size"), Some("2"));
+    assert_eq!(notations.ornament[0].unknown_content.children.len(), 1);
+
+    note.pitch = "D".to_string();
+    let written = note.to_xml_string().unwrap();
+    // The content of the vendor keeps its prefix, the elements are kept where they were.
+    assert_eq!(
+      written,
+      r#"<?xml version="1.0" encoding="UTF-8"?><note xmlns:v="urn:vendor" default-x="10" v:color="red"><pitch>D</pitch><v:glow>2</v:glow><duration>4</duration><lyric number="1" v:font="serif"><text>la</text><v:hint>soft</v:hint></lyric><notations id="n1" v:style="bold"><v:before /><ornament placement="above" v:size="2"><trill>tr</trill><v:wave>3</v:wave></ornament></notations><v:fx /></note>"#
+    );
+    assert_eq!(Note::parse_str(&written).unwrap(), note);
+
+    let value = DynamicValue::from(&note);
+    assert_eq!(Note::try_from(&value).unwrap(), note);
+    let json = serde_json::to_string(&note).unwrap();
+    assert_eq!(serde_json::from_str::<Note>(&json).unwrap(), note);
+  }
+}
+"##;
+
+  write_crate(
+    dir.path(),
+    "vendor",
+    &[
+      "xml-schema-parser/serde",
+      r#"serde = { version = "1", features = ["derive"] }"#,
+      r#"serde_json = "1""#,
+    ],
+    &(vendor + tests),
+  );
+  clippy_workspace(dir.path(), &["vendor"]);
+  let output = test_workspace(dir.path(), &["vendor"]);
+  assert!(output.contains("tests::vendor_extensions ... ok"));
+}
+
+#[test]
+fn async_entry_points_parse_on_the_blocking_pool() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let note = xsd_tool(&["generate", "note.xsd", "--async-entry-points"]);
+  assert!(note.contains("#[cfg(feature = \"tokio\")]\nimpl Note {"));
+
+  let tests = r##"
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_async() {
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let document = r#"<score id="n1"><pitch>C</pitch><tie>red</tie></score>"#;
+
+    let note = runtime.block_on(Note::parse_async(document.as_bytes())).unwrap();
+    assert_eq!(note.pitch, "C");
+    assert_eq!(note.id.as_deref(), Some("n1"));
+
+    let error = runtime.block_on(Note::parse_async(&b"<score>"[..])).unwrap_err();
+    assert!(error.to_string().contains("Failed to parse the document"), "{error}");
+  }
+}
+"##;
+
+  // The optional dependency provides the `tokio` feature gating the entry points.
+  write_crate(
+    dir.path(),
+    "note",
+    &[
+      "xml-schema-parser/tokio",
+      r#"tokio = { version = "1", features = ["rt"], optional = true }"#,
+    ],
+    &(note + tests),
+  );
+  check_workspace(dir.path(), &["note"]);
+  let output = cargo(
+    dir.path(),
+    &["note"],
+    &["test", "--workspace", "--features", "note/tokio"],
+  );
+  assert!(output.contains("tests::parse_async ... ok"));
+}
+
+#[test]
+fn qualified_names_resolve_to_generated_types() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let typed = xsd_tool(&["generate", "typed.xsd", "--type-index"]);
+  assert!(typed.contains("pub kind: xml_schema_parser::QName,"));
+  assert!(typed.contains("    (None, \"circle\", \"Circle\"),\n"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn shape_kind() {
+    let document = r#"<shape xmlns:o="urn:other" kind="circle"><label>o:circle</label></shape>"#;
+    let mut element = XMLElement::parse(document.as_bytes()).unwrap();
+    let state = GenState { is_root: true, state: GenType::Content, progress: None };
+    let shape = Shape::gen(&mut element, state, None).unwrap();
+
+    assert_eq!(shape.kind.namespace, None);
+    assert_eq!(shape.kind.resolve(&XML_TYPE_INDEX), Some("Circle"));
+    let label = shape.label.unwrap();
+    assert_eq!(label.namespace.as_deref(), Some("urn:other"));
+    assert_eq!(label.resolve(&XML_TYPE_INDEX), None);
+
+    let document = r#"<shape kind="o:circle" />"#;
+    let mut element = XMLElement::parse(document.as_bytes()).unwrap();
+    let state = GenState { is_root: true, state: GenType::Content, progress: None };
+    let error = Shape::gen(&mut element, state, None).unwrap_err();
+    assert!(error.to_string().contains("not declared"), "{error}");
+  }
+}
+"##;
+
+  write_crate(dir.path(), "typed", &[], &(typed + tests));
+  let output = test_workspace(dir.path(), &["typed"]);
+  assert!(output.contains("tests::shape_kind ... ok"));
+}
+
+#[test]
+fn large_choices_are_dispatched_on_the_child_name() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let large = xsd_tool(&["generate", "large.xsd"]);
+  assert!(large.contains("match next_child.as_deref() {"));
+  assert!(!large.contains("if element.next_child_is("));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn marks() {
+    let document = r#"<data><mark-300 value="3"/><mark-1 value="1"/><mark-150/></data>"#;
+    let mut element = XMLElement::parse(document.as_bytes()).unwrap();
+    let state = GenState { is_root: true, state: GenType::Content, progress: None };
+    let data = Data::gen(&mut element, state, None).unwrap();
+
+    assert_eq!(data.data.len(), 3);
+    assert!(matches!(&data.data[0], data::DataItem::Mark300(Mark { value: Some(3) })));
+    assert!(matches!(&data.data[1], data::DataItem::Mark1(Mark { value: Some(1) })));
+    assert!(matches!(&data.data[2], data::DataItem::Mark150(Mark { value: None })));
+  }
+}
+"##;
+
+  write_crate(dir.path(), "large", &[], &(large + tests));
+  let start = std::time::Instant::now();
+  let output = test_workspace(dir.path(), &["large"]);
+  assert!(output.contains("tests::marks ... ok"));
+  assert!(
+    start.elapsed() < std::time::Duration::from_secs(300),
+    "{:?}",
+    start.elapsed()
+  );
+}
+
+#[test]
+fn w3c_attribute_schemas_compile_and_read_their_attributes() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let xml = xsd_tool(&["generate", "w3c/xml.xsd"]);
+  let xlink = xsd_tool(&["generate", "w3c/xlink.xsd"]);
+  let links = xsd_tool(&["generate", "w3c/links.xsd"]);
+  assert!(xml.contains("pub struct SpecialAttrs {"));
+  assert!(links.contains(
+    r#"href: <Href as XsdGen>::gen(element, gen_state.to_attr(), Some("http://www.w3.org/1999/xlink:href"))?,"#
+  ));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn link_attributes() {
+    let document = r#"<link xmlns:xlink="http://www.w3.org/1999/xlink" xlink:href="score.xml" xlink:show="new" xml:lang="fr" name="first"><text xml:space="preserve">Partition</text></link>"#;
+    let mut element = XMLElement::parse(document.as_bytes()).unwrap();
+    let state = GenState { is_root: true, state: GenType::Content, progress: None };
+    let link = Link::gen(&mut element, state, None).unwrap();
+
+    assert_eq!(link.link_attributes.href, "score.xml");
+    assert_eq!(link.link_attributes.show, Show(show::Show::New));
+    // An absent attribute has its default value.
+    assert_eq!(link.link_attributes.actuate, Actuate(actuate::Actuate::OnRequest));
+    assert_eq!(Actuate::DEFAULT, "onRequest");
+    assert_eq!(Actuate::default(), Actuate(actuate::Actuate::OnRequest));
+    assert_eq!(link.special_attrs.lang, Some(lang::Lang::String("fr".to_string())));
+    assert_eq!(link.name.as_deref(), Some("first"));
+    let text = link.text.unwrap();
+    assert_eq!(text.text_element_data, "Partition");
+    assert_eq!(text.space, Some(space::Space::Preserve));
+
+    let document = r#"<link name="first" />"#;
+    let mut element = XMLElement::parse(document.as_bytes()).unwrap();
+    let state = GenState { is_root: true, state: GenType::Content, progress: None };
+    assert!(Link::gen(&mut element, state, None).is_err());
+  }
+}
+"##;
+
+  write_crate(dir.path(), "xml", &[], &xml);
+  write_crate(dir.path(), "xlink", &[], &xlink);
+  write_crate(dir.path(), "links", &[], &(links + tests));
+  clippy_workspace(dir.path(), &["xml", "xlink", "links"]);
+  let output = test_workspace(dir.path(), &["xml", "xlink", "links"]);
+  assert!(output.contains("tests::link_attributes ... ok"));
+}
+
+#[test]
+fn documents_are_loaded_through_a_common_trait() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let scores = xsd_tool(&["generate", "scores.xsd"]);
+  assert!(scores.contains("impl xml_schema_parser::XmlDocument for Opus {"));
+  // Both scores are read into `Score`, it has no single root name.
+  assert!(!scores.contains("XmlDocument for Score"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use xml_schema_parser::XmlDocument;
+
+  fn load<T: XmlDocument>(document: &str) -> Result<(&'static str, T), DocumentError> {
+    Ok((T::ROOT_NAME, T::parse_str(document)?))
+  }
+
+  #[test]
+  fn generic_loader() {
+    let opus = r#"<opus version="4"><title>Suite</title></opus>"#;
+    let (root, value) = load::<Opus>(opus).unwrap();
+    assert_eq!(root, "opus");
+    assert_eq!(value.title, "Suite");
+
+    let (root, value) = load::<Movement>("<movement><title>Prelude</title></movement>").unwrap();
+    assert_eq!(root, "movement");
+    assert_eq!(value.title, "Prelude");
+
+    assert!(load::<Movement>(opus).is_err());
+  }
+}
+"##;
+
+  write_crate(dir.path(), "scores", &[], &(scores + tests));
+  let output = test_workspace(dir.path(), &["scores"]);
+  assert!(output.contains("tests::generic_loader ... ok"));
+}
+
+#[test]
+fn documents_are_read_through_the_prelude() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let scores = xsd_tool(&["generate", "scores.xsd"]);
+  let tests = r##"
+#[cfg(test)]
+#[deny(deprecated)]
+mod prelude_tests {
+  use super::Opus;
+  use xml_schema_parser::prelude::*;
+
+  #[test]
+  fn read_with_the_prelude() {
+    let opus = Opus::parse_str(r#"<opus version="4"><title>Suite</title></opus>"#).unwrap();
+    assert_eq!(opus.title, "Suite");
+
+    let mut element = XMLElement::parse(r#"<opus version="4"><title>Suite</title><extra/></opus>"#.as_bytes()).unwrap();
+    let state = GenState { is_root: true, state: GenType::Content, progress: None };
+    let opus = Opus::gen(&mut element, state, None).unwrap();
+    assert_eq!(opus.title, "Suite");
+    assert_eq!(element.element().children.len(), 1);
+    assert_eq!(element.into_element().name, "opus");
+  }
+}
+"##;
+
+  write_crate(dir.path(), "scores", &[], &(scores + tests));
+  let output = test_workspace(dir.path(), &["scores"]);
+  assert!(output.contains("prelude_tests::read_with_the_prelude ... ok"));
+}
+
+#[test]
+fn borrowed_types_read_the_same_fields() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let note = xsd_tool(&["generate", "note.xsd", "--borrowed-types"]);
+  assert!(note.contains("pub struct NoteRef<'a> {\n    pub pitch: &'a str,"));
+  assert!(note.contains("impl<'a> XsdGenRef<'a> for NoteRef<'a> {"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use xml_schema_parser::XmlDocument;
+
+  use super::*;
+
+  #[test]
+  fn same_fields() {
+    let document = r#"<score id="n1"><pitch>C</pitch><duration>4</duration><tie>red</tie><tie>green</tie></score>"#;
+    let root = xmltree::Element::parse(document.as_bytes()).unwrap();
+
+    let owned = Note::parse_str(document).unwrap();
+    let borrowed: NoteRef = xml_schema_parser::parse_document_ref(&root, "score").unwrap();
+    assert_eq!(borrowed.pitch, owned.pitch);
+    assert_eq!(borrowed.duration, owned.duration);
+    assert_eq!(borrowed.tie, owned.tie);
+    assert_eq!(borrowed.id, owned.id.as_deref());
+
+    let error = xml_schema_parser::parse_document_ref::<NoteRef>(&root, "note").unwrap_err();
+    assert!(error.to_string().contains("The root element is score not note"), "{error}");
+  }
+}
+"##;
+
+  write_crate(dir.path(), "note", &[], &(note + tests));
+  clippy_workspace(dir.path(), &["note"]);
+  let output = test_workspace(dir.path(), &["note"]);
+  assert!(output.contains("tests::same_fields ... ok"));
+}
+
+#[test]
+fn inherited_lang_is_read_from_the_ancestors() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let xml = xsd_tool(&["generate", "w3c/xml.xsd"]);
+  let xlink = xsd_tool(&["generate", "w3c/xlink.xsd"]);
+  let links = xsd_tool(&["generate", "w3c/links.xsd", "--inherited-lang"]);
+  assert!(links.contains("xml_lang: element.effective_lang().map(str::to_string),"));
+  // The attribute group itself reads the attribute of its element.
+  assert!(!xml.contains("xml_lang"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use xml_schema_parser::XmlDocument;
+
+  use super::*;
+
+  #[test]
+  fn inherited_lang() {
+    let document = r#"<link xmlns:xlink="http://www.w3.org/1999/xlink" xlink:href="score.xml" xml:lang="fr"><text xml:space="preserve">  </text></link>"#;
+    let link = Link::parse_str(document).unwrap();
+
+    assert_eq!(link.xml_lang.as_deref(), Some("fr"));
+    let text = link.text.unwrap();
+    assert_eq!(text.text_element_data, "  ");
+    assert_eq!(text.lang, None);
+    assert_eq!(text.xml_lang.as_deref(), Some("fr"));
+
+    let document = r#"<link xmlns:xlink="http://www.w3.org/1999/xlink" xlink:href="score.xml" xml:lang="fr"><text xml:lang="de">Partition</text></link>"#;
+    let text = Link::parse_str(document).unwrap().text.unwrap();
+    assert_eq!(text.xml_lang.as_deref(), Some("de"));
+  }
+}
+"##;
+
+  write_crate(dir.path(), "xml", &[], &xml);
+  write_crate(dir.path(), "xlink", &[], &xlink);
+  write_crate(dir.path(), "links", &[], &(links + tests));
+  clippy_workspace(dir.path(), &["xml", "xlink", "links"]);
+  let output = test_workspace(dir.path(), &["xml", "xlink", "links"]);
+  assert!(output.contains("tests::inherited_lang ... ok"));
+}
+
+#[test]
+fn choice_visitors_count_the_variants() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let measure = xsd_tool(&["generate", "measure.xsd", "--choice-visitors"]);
+  assert!(measure.contains("pub trait MeasureItemVisitor {"));
+  assert!(measure.contains("Self::Rest(value) => visitor.visit_rest(value),"));
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use xml_schema_parser::XmlDocument;
+
+  use super::*;
+  use measure::MeasureItemVisitor;
+
+  #[derive(Default)]
+  struct Counter {
+    notes: usize,
+    rests: usize,
+  }
+
+  impl MeasureItemVisitor for Counter {
+    fn visit_note(&mut self, _value: &String) {
+      self.notes += 1;
+    }
+
+    fn visit_rest(&mut self, _value: &String) {
+      self.rests += 1;
+    }
+  }
+
+  #[test]
+  fn count_variants() {
+    let measure = Measure::parse_str("<measure><note>C</note><rest>q</rest><note>D</note></measure>").unwrap();
+
+    let mut counter = Counter::default();
+    for item in measure.measure.iter() {
+      item.accept(&mut counter);
+    }
+    assert_eq!((counter.notes, counter.rests), (2, 1));
+  }
+}
+"##;
+
+  write_crate(dir.path(), "measure", &[], &(measure + tests));
+  clippy_workspace(dir.path(), &["measure"]);
+  let output = test_workspace(dir.path(), &["measure"]);
+  assert!(output.contains("tests::count_variants ... ok"));
+}
+
+#[test]
+fn doc_links_resolve_across_namespace_modules() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let documented = xsd_tool(&[
+    "generate",
+    "documented.xsd",
+    "--namespace-module",
+    "--doc-links",
+  ]);
+  assert!(documented.contains("/// Type: [`xlink::Href`]\n    pub href: Option<Href>,"));
+  assert!(
+    documented.contains("/// The credit of an [Image] \\[optional\\], linked with `xlink:href`.")
+  );
+
+  // rustdoc warns about the links it can't resolve, or which name their target twice.
+  let lints = "#![deny(rustdoc::broken_intra_doc_links, rustdoc::redundant_explicit_links)]\n";
+  write_crate(
+    dir.path(),
+    "documented",
+    &[],
+    &(lints.to_string() + &documented),
+  );
+  doc_workspace(dir.path(), &["documented"]);
+}
+
+#[test]
+fn shared_attribute_groups_are_read_once() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let diamond = xsd_tool(&["generate", "diamond.xsd"]);
+  // `print-style` already reads the attributes of `position`, whichever comes first.
+  for name in ["Direction", "Fermata"] {
+    let start = diamond.find(&format!("pub struct {name} {{")).unwrap();
+    let fields = &diamond[start..start + diamond[start..].find('}').unwrap()];
+    assert!(fields.contains("pub print_style: PrintStyle,"), "{fields}");
+    assert!(!fields.contains("pub position: Position,"), "{fields}");
+  }
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use xml_schema_parser::XmlDocument;
+
+  use super::*;
+
+  #[test]
+  fn diamond() {
+    let direction = Direction::parse_str(r#"<direction default-x="1" color="red" placement="above"/>"#).unwrap();
+    assert_eq!(direction.placement.as_deref(), Some("above"));
+    assert_eq!(direction.print_style.color.as_deref(), Some("red"));
+    assert_eq!(direction.print_style.position.default_x, Some(Decimal::from(1)));
+
+    let fermata = Fermata::parse_str(r#"<fermata default-y="2">angled</fermata>"#).unwrap();
+    assert_eq!(fermata.fermata, "angled");
+    assert_eq!(fermata.print_style.position.default_y, Some(Decimal::from(2)));
+  }
+}
+"##;
+
+  write_crate(dir.path(), "diamond", &[], &(diamond + tests));
+  clippy_workspace(dir.path(), &["diamond"]);
+  let output = test_workspace(dir.path(), &["diamond"]);
+  assert!(output.contains("tests::diamond ... ok"));
+}
+
+#[test]
+fn keyrefs_resolve_to_the_values_with_their_key() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let keys = xsd_tool(&["generate", "--key-resolvers", "keys.xsd"]);
+  assert!(keys.contains("pub struct StaffKey(pub u64);"), "{keys}");
+
+  let tests = r##"
+#[cfg(test)]
+mod tests {
+  use xml_schema_parser::XmlDocument;
+
+  use super::*;
+
+  #[test]
+  fn keys() {
+    let score = Score::parse_str(
+      r#"<score>
+        <staff-details number="1" lines="5"/>
+        <staff-details number="2" lines="4"/>
+        <part><note staff="2" pitch="C"/><note pitch="D"/><note staff="3" pitch="E"/></part>
+      </score>"#,
+    )
+    .unwrap();
+
+    let notes = &score.part[0].note;
+    assert_eq!(score.resolve_note_staff(&notes[0]).unwrap().lines, Some(4));
+    assert!(score.resolve_note_staff(&notes[1]).is_none());
+    assert!(score.resolve_note_staff(&notes[2]).is_none());
+
+    let index = score.staff_index();
+    assert_eq!(index.get(&StaffKey(1)).unwrap().lines, Some(5));
+    assert_eq!(index.len(), 2);
+    assert_eq!(StaffKey::from_note_staff(&notes[2]), Some(StaffKey(3)));
+  }
+}
+"##;
+
+  write_crate(dir.path(), "keys", &[], &(keys + tests));
+  clippy_workspace(dir.path(), &["keys"]);
+  let output = test_workspace(dir.path(), &["keys"]);
+  assert!(output.contains("tests::keys ... ok"));
+}
+
+#[test]
+fn workspace_schemas_share_the_imported_types() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let tests = r##"
+pub mod fleet;
+
+#[cfg(test)]
+mod tests {
+  use xml_schema_parser::XmlDocument;
+
+  use super::fleet::{harbor::Harbor, route::Route, ship::Ship, u::Position};
+
+  #[test]
+  fn fleet() {
+    let ship = Ship::parse_str(
+      r#"<ship name="Ariel"><position lat="1.5" lon="2"/><length>12.5</length></ship>"#,
+    )
+    .unwrap();
+    let route = Route::parse_str(r#"<route><waypoint lat="1.5" lon="2"/></route>"#).unwrap();
+    let harbor =
+      Harbor::parse_str(r#"<harbor><position lat="0" lon="0"/><berth>20</berth></harbor>"#)
+        .unwrap();
+
+    let position: &Position = &ship.position;
+    assert_eq!(route.waypoint[0], *position);
+    assert_eq!(harbor.berth.len(), 1);
+  }
+}
+"##;
+  write_crate(dir.path(), "fleet", &[], tests);
+
+  let fleet = dir.path().join("fleet/src/fleet");
+  xsd_tool(&[
+    "generate-workspace",
+    fleet.to_str().unwrap(),
+    "--manifest",
+    "fleet.toml",
+  ]);
+
+  let modules = ["harbor", "route", "ship", "u"]
+    .map(|module| std::fs::read_to_string(fleet.join(format!("{module}.rs"))).unwrap());
+  for (module, code) in ["harbor", "route", "ship"].iter().zip(&modules) {
+    assert!(code.contains("use super::u::"), "{module}: {code}");
+    assert!(!code.contains("pub struct Position"), "{module}: {code}");
+  }
+  assert_eq!(modules[3].matches("pub struct Position").count(), 1);
+
+  clippy_workspace(dir.path(), &["fleet"]);
+  let output = test_workspace(dir.path(), &["fleet"]);
+  assert!(output.contains("tests::fleet ... ok"));
+}
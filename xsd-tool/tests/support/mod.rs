@@ -0,0 +1,80 @@
+//! Helpers building scratch crates out of generated code, shared by the compile tests and the
+//! benches.
+
+use std::{fs, path::Path, process};
+
+use assert_cmd::Command;
+
+/// Run `xsd-tool` from the fixtures directory, imports are resolved from the current directory.
+pub fn xsd_tool(args: &[&str]) -> String {
+  let output = Command::cargo_bin("xsd-tool")
+    .unwrap()
+    .current_dir("tests/fixtures")
+    .args(args)
+    .output()
+    .unwrap();
+  assert!(
+    output.status.success(),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  String::from_utf8(output.stdout).unwrap()
+}
+
+/// Write a library crate depending on `xml-schema-parser`, `xmltree` and `dependencies`.
+///
+/// A dependency is the name of another scratch crate, a manifest line (e.g. `serde_json = "1"`)
+/// or a feature of `xml-schema-parser` (e.g. `xml-schema-parser/serde`).
+pub fn write_crate(root: &Path, name: &str, dependencies: &[&str], code: &str) {
+  let parser = fs::canonicalize("../xml_schema_parser").unwrap();
+  let features: Vec<_> = dependencies
+    .iter()
+    .filter_map(|dependency| dependency.strip_prefix("xml-schema-parser/"))
+    .collect();
+  let mut manifest = format!(
+    "[package]\nname = \"{name}\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n[dependencies]\nxml-schema-parser = {{ path = {:?}, features = {features:?} }}\nxmltree = \"0.10.3\"\n",
+    parser.display()
+  );
+  for dependency in dependencies {
+    if dependency.starts_with("xml-schema-parser/") {
+      continue;
+    }
+
+    if dependency.contains('=') {
+      manifest.push_str(&format!("{dependency}\n"));
+    } else {
+      manifest.push_str(&format!(
+        "{dependency} = {{ path = \"../{dependency}\" }}\n"
+      ));
+    }
+  }
+
+  fs::create_dir_all(root.join(name).join("src")).unwrap();
+  fs::write(root.join(name).join("Cargo.toml"), manifest).unwrap();
+  fs::write(root.join(name).join("src/lib.rs"), code).unwrap();
+}
+
+/// Run cargo in a scratch workspace made of `members` and return its standard output.
+pub fn cargo(root: &Path, members: &[&str], args: &[&str]) -> String {
+  fs::write(
+    root.join("Cargo.toml"),
+    format!("[workspace]\nresolver = \"2\"\nmembers = {members:?}\n"),
+  )
+  .unwrap();
+
+  let target = fs::canonicalize("..").unwrap().join("target/compile-tests");
+  let output = process::Command::new(std::env::var("CARGO").unwrap_or("cargo".to_string()))
+    .arg("--offline")
+    .args(args)
+    .current_dir(root)
+    .env("CARGO_TARGET_DIR", target)
+    .output()
+    .unwrap();
+  assert!(
+    output.status.success(),
+    "{}{}",
+    String::from_utf8_lossy(&output.stdout),
+    String::from_utf8_lossy(&output.stderr)
+  );
+  String::from_utf8(output.stdout).unwrap()
+}
@@ -1,52 +1,99 @@
+use std::{cell::RefCell, collections::HashMap, sync::Arc, thread::LocalKey};
+
 use heck::{CamelCase, SnakeCase};
 use thiserror::Error;
 
+/// The types shared by the generator and the generated code.
+pub mod prelude {
+  #[allow(deprecated)]
+  pub use crate::XsdIoError;
+  pub use crate::{DocumentError, SchemaError, XsdGenError, XsdName, XsdParseError, XsdType};
+}
+
+/// A node that can't be read: an attribute, an element or its text is missing or invalid.
 #[derive(Error, Debug)]
 #[error("Error parsing xml node[{node_name}]: {msg}")]
 pub struct XsdParseError {
+  /// Name of the element read.
   pub node_name: String,
   pub msg: String,
 }
 
+/// A node that doesn't match what is expected of it, e.g. none of the alternatives of a union.
 #[derive(Error, Debug)]
 #[error("Error generating xsd node [{node_name}; {ty:?}]: {msg}")]
 pub struct XsdGenError {
+  /// Name of the element read.
   pub node_name: String,
   pub ty: XsdType,
   pub msg: String,
 }
 
+/// A problem in an instance document, read by `XMLElement` and the generated code.
 #[derive(Error, Debug)]
-pub enum XsdIoError {
+pub enum DocumentError {
   #[error(transparent)]
   XsdParseError(#[from] XsdParseError),
   #[error(transparent)]
   XsdGenError(#[from] XsdGenError),
 }
 
+/// The errors of the documents and of the schemas used to be the same type.
+#[deprecated(note = "use `DocumentError`, or `SchemaError` for the problems of a schema")]
+pub type XsdIoError = DocumentError;
+
+/// A problem in the schema itself.
+#[derive(Error, Debug)]
+pub enum SchemaError {
+  /// The schema doesn't follow XML Schema, e.g. a required attribute is missing.
+  #[error(transparent)]
+  Invalid(#[from] XsdParseError),
+  /// The schema uses a construct that can't be generated.
+  #[error(transparent)]
+  Unsupported(#[from] XsdGenError),
+}
+
+/// The schema documents are read as any other document, what can't be read is a problem of the
+/// schema.
+impl From<DocumentError> for SchemaError {
+  fn from(error: DocumentError) -> Self {
+    match error {
+      DocumentError::XsdParseError(error) => SchemaError::Invalid(error),
+      DocumentError::XsdGenError(error) => SchemaError::Unsupported(error),
+    }
+  }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum XsdType {
+  All,
   Annotation,
   AttributeGroup,
   Attribute,
   Choice,
   ComplexContent,
   ComplexType,
+  DerivedTypes,
   Element,
   Extension,
   Group,
+  IdentityConstraint,
   Import,
   List,
   Restriction,
   Sequence,
   SimpleContent,
   SimpleType,
+  SubstitutionGroup,
   Union,
   Unknown,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct XsdName {
+  /// The namespace URI of the component once resolved through the context. `None` is the absence
+  /// of a namespace: the components of a schema without `targetNamespace`, and the unqualified
+  /// names referring to them. It is never a prefix or the default namespace of another schema.
   pub namespace: Option<String>,
   pub local_name: String,
   pub ty: XsdType,
@@ -95,8 +142,15 @@ impl XsdName {
     }
   }
 
+  /// Name of the Rust type generated for the component. A reserved name, see `is_reserved`, is
+  /// suffixed with `RESERVED_SUFFIX`, e.g. `DateType`.
   pub fn to_struct_name(&self) -> String {
-    to_struct_name(&self.local_name)
+    let name = to_struct_name(&self.local_name);
+    if is_reserved(&name) {
+      format!("{name}{RESERVED_SUFFIX}")
+    } else {
+      name
+    }
   }
 
   pub fn to_field_name(&self) -> String {
@@ -104,23 +158,191 @@ impl XsdName {
   }
 }
 
-pub fn to_struct_name(name: &str) -> String {
-  let output = name.replace(".", "_").to_camel_case();
-  if let Some(char) = output.chars().next() {
-    if char.is_numeric() {
-      return format!("_{output}");
+/// Names the generated code uses from the Rust prelude. A generated type named the same would
+/// shadow them, as would one named after the names of the `generated_prelude`.
+pub const RESERVED_NAMES: &[&str] = &[
+  "Box", "Err", "From", "None", "Ok", "Option", "Result", "Self", "Send", "Some", "String", "Sync",
+  "TryFrom", "Vec",
+];
+
+/// The names the generated code imports from `xml_schema_parser`, each group imported along with
+/// the option of the generator using it.
+pub mod generated_prelude {
+  /// Imported by all the generated code.
+  pub const BASE: &[&str] = &[
+    "DocumentError",
+    "XsdGenError",
+    "XMLElement",
+    "XsdType",
+    "XsdGen",
+    "GenState",
+    "GenType",
+    "Date",
+    "Decimal",
+    "FixedValue",
+    "FromXmlString",
+    "RestrictedVec",
+  ];
+  pub const FIELD_METADATA: &[&str] = &["ContentKind", "FieldKind", "FieldMeta", "VariantMeta"];
+  pub const PROGRESS_HOOKS: &[&str] = &["ProgressHook"];
+  /// Imported along with the opaque stubs.
+  pub const OPAQUE_STUBS: &[&str] = &["RawXml"];
+  pub const TYPE_INDEX: &[&str] = &["XmlTypeIndex"];
+  pub const BORROWED_TYPES: &[&str] = &["XMLElementRef", "XsdGenRef"];
+  pub const KEY_RESOLVERS: &[&str] = &["KeyIndex"];
+  pub const DYNAMIC_VALUES: &[&str] = &["DynamicValue", "FromDynamic", "ToDynamic"];
+  pub const XML_WRITERS: &[&str] = &["ToXmlString", "XsdSer"];
+  pub const UNKNOWN_CONTENT: &[&str] = &["UnknownContent"];
+
+  /// All the groups, whichever options import them.
+  pub const ALL: &[&[&str]] = &[
+    BASE,
+    FIELD_METADATA,
+    PROGRESS_HOOKS,
+    OPAQUE_STUBS,
+    TYPE_INDEX,
+    BORROWED_TYPES,
+    KEY_RESOLVERS,
+    DYNAMIC_VALUES,
+    XML_WRITERS,
+    UNKNOWN_CONTENT,
+  ];
+}
+
+/// Appended to the generated types named after a reserved name, see `is_reserved`. It does not
+/// depend on the kind of the component, the struct of a sequence is named the same as the complex
+/// type it is merged into.
+pub const RESERVED_SUFFIX: &str = "Type";
+
+/// Whether a generated type named `name` would shadow a name the generated code uses, one of the
+/// `RESERVED_NAMES` or of the `generated_prelude`.
+pub fn is_reserved(name: &str) -> bool {
+  RESERVED_NAMES.contains(&name)
+    || generated_prelude::ALL
+      .iter()
+      .any(|names| names.contains(&name))
+}
+
+/// How the names of the schema are cased into Rust identifiers.
+///
+/// The names are given with their dots replaced by underscores. A leading digit and the `type`
+/// keyword are escaped once the convention ran, and the names colliding with one another or with
+/// the reserved names, see `is_reserved`, are renamed as usual. Some names are converted again
+/// once converted, which has to give back the same name.
+pub trait NamingConvention: Send + Sync {
+  /// The name of a struct, an enum or a type alias.
+  fn struct_name(&self, name: &str) -> String;
+
+  /// The name of a field.
+  fn field_name(&self, name: &str) -> String;
+
+  /// The name of an enum variant, the name of a struct by default.
+  fn variant_name(&self, name: &str) -> String {
+    self.struct_name(name)
+  }
+
+  /// The name of a module, e.g. the one holding the inner types of a struct, the name of a field
+  /// by default.
+  fn module_name(&self, name: &str) -> String {
+    self.field_name(name)
+  }
+}
+
+/// The default `NamingConvention`: `CamelCase` types and variants, `snake_case` fields and
+/// modules.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultNaming;
+
+impl NamingConvention for DefaultNaming {
+  fn struct_name(&self, name: &str) -> String {
+    name.to_camel_case()
+  }
+
+  fn field_name(&self, name: &str) -> String {
+    name.to_snake_case()
+  }
+}
+
+thread_local! {
+  static NAMING: RefCell<Arc<dyn NamingConvention>> = RefCell::new(Arc::new(DefaultNaming));
+  // Generating a schema converts the same few names thousands of times.
+  static STRUCT_NAMES: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+  static FIELD_NAMES: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+  static VARIANT_NAMES: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+  static MODULE_NAMES: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+}
+
+/// Make `convention` the one of this thread, returning the previous one.
+fn set_naming(convention: Arc<dyn NamingConvention>) -> Arc<dyn NamingConvention> {
+  for cache in [&STRUCT_NAMES, &FIELD_NAMES, &VARIANT_NAMES, &MODULE_NAMES] {
+    cache.with(|cache| cache.borrow_mut().clear());
+  }
+  NAMING.with(|naming| naming.replace(convention))
+}
+
+/// Run `f` with the names converted by `convention` on this thread, `DefaultNaming` otherwise.
+pub fn with_naming_convention<T>(
+  convention: Arc<dyn NamingConvention>,
+  f: impl FnOnce() -> T,
+) -> T {
+  // Restored on unwinding too.
+  struct Restore(Option<Arc<dyn NamingConvention>>);
+  impl Drop for Restore {
+    fn drop(&mut self) {
+      if let Some(previous) = self.0.take() {
+        set_naming(previous);
+      }
     }
   }
 
-  output
+  let _restore = Restore(Some(set_naming(convention)));
+  f()
 }
 
-pub fn to_field_name(name: &str) -> String {
-  let name = name.to_snake_case();
+fn cached(
+  cache: &'static LocalKey<RefCell<HashMap<String, String>>>,
+  name: &str,
+  convert: fn(&dyn NamingConvention, &str) -> String,
+) -> String {
+  cache.with(|cache| {
+    if let Some(converted) = cache.borrow().get(name) {
+      return converted.clone();
+    }
+
+    let naming = NAMING.with(|naming| naming.borrow().clone());
+    let converted = escape_identifier(convert(naming.as_ref(), &name.replace('.', "_")));
+    cache
+      .borrow_mut()
+      .insert(name.to_string(), converted.clone());
+    converted
+  })
+}
 
-  if name == "type" {
+/// Escape a converted name that is not an identifier.
+fn escape_identifier(name: String) -> String {
+  if name.starts_with(|c: char| c.is_numeric()) {
+    format!("_{name}")
+  } else if name == "type" {
     "r#type".to_string()
   } else {
     name
   }
 }
+
+pub fn to_struct_name(name: &str) -> String {
+  cached(&STRUCT_NAMES, name, |naming, name| naming.struct_name(name))
+}
+
+pub fn to_field_name(name: &str) -> String {
+  cached(&FIELD_NAMES, name, |naming, name| naming.field_name(name))
+}
+
+pub fn to_variant_name(name: &str) -> String {
+  cached(&VARIANT_NAMES, name, |naming, name| {
+    naming.variant_name(name)
+  })
+}
+
+pub fn to_module_name(name: &str) -> String {
+  cached(&MODULE_NAMES, name, |naming, name| naming.module_name(name))
+}